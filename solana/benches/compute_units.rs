@@ -0,0 +1,34 @@
+// ========================================
+// 链上开销基准 - 合约文件大小和 Increment 指令消耗的CU
+// 运行前需要先执行 cargo build-sbf；对比日志格式化的开销时，
+// 分别用默认特性和 --features debug-logs 编译合约后各运行一次 cargo bench --bench compute_units
+// ========================================
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use solana_sdk::signature::Signer;
+
+fn main() {
+    let program_size = std::fs::metadata(common::PROGRAM_PATH)
+        .expect("未找到合约文件，请先运行 cargo build-sbf")
+        .len();
+
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    ctx.create_gongde_account(&user);
+
+    // 第一次增加功德会创建全局PDA，之后的增加才是日常的开销
+    let first = ctx.increment(&user).expect("增加功德失败");
+    let steady = ctx.increment(&user).expect("增加功德失败");
+    let increment_by = {
+        let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+        let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 10);
+        ctx.send(&[instruction], &user, &[]).expect("增加功德失败")
+    };
+
+    println!("合约大小: {} 字节", program_size);
+    println!("Increment（首次）: {} CU", first.compute_units_consumed);
+    println!("Increment: {} CU", steady.compute_units_consumed);
+    println!("IncrementBy(10): {} CU", increment_by.compute_units_consumed);
+}
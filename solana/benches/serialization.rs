@@ -0,0 +1,59 @@
+// ========================================
+// 序列化工具基准测试
+// 运行: cargo bench --features bench
+// ========================================
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_program::pubkey::Pubkey;
+
+use gong_de_increase::utils::{
+    derive_global_gongde_pda_address,
+    derive_gongde_account_address,
+    is_public_account,
+    read_gongde_value,
+    write_gongde_value,
+    GongDeInstruction,
+    GONGDE_ACCOUNT_SIZE,
+};
+
+/// 账户数据读写：原地读写小端序功德值和标志位
+fn bench_account_data(c: &mut Criterion) {
+    let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+    write_gongde_value(&mut data, 12345).unwrap();
+
+    c.bench_function("read_gongde_value", |b| {
+        b.iter(|| read_gongde_value(black_box(&data)).unwrap())
+    });
+    c.bench_function("write_gongde_value", |b| {
+        b.iter(|| write_gongde_value(black_box(&mut data), black_box(54321)).unwrap())
+    });
+    c.bench_function("is_public_account", |b| {
+        b.iter(|| is_public_account(black_box(&data)))
+    });
+}
+
+/// 指令编解码
+fn bench_instruction(c: &mut Criterion) {
+    c.bench_function("instruction_encode", |b| {
+        b.iter(|| [black_box(GongDeInstruction::Initialize) as u8, black_box(1u8)])
+    });
+    c.bench_function("instruction_decode", |b| {
+        b.iter(|| GongDeInstruction::from_instruction_data(black_box(&[2, 1])).unwrap())
+    });
+}
+
+/// 地址派生：种子地址（一次sha256）与PDA（可能多次尝试bump）
+fn bench_address_derivation(c: &mut Criterion) {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+
+    c.bench_function("derive_gongde_account_address", |b| {
+        b.iter(|| derive_gongde_account_address(black_box(&user), black_box(&program_id)).unwrap())
+    });
+    c.bench_function("derive_global_gongde_pda_address", |b| {
+        b.iter(|| derive_global_gongde_pda_address(black_box(&program_id)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_account_data, bench_instruction, bench_address_derivation);
+criterion_main!(benches);
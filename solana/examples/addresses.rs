@@ -0,0 +1,83 @@
+// ========================================
+// 用户地址清单工具
+// 🗂️ 列出合约可能为某个用户创建的全部账户地址，安全审计时用来预先授权或监控；只做本地派生，不连接网络
+// ========================================
+
+use solana_sdk::pubkey::Pubkey;
+use tracing::info;
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_query_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+use gong_de_increase::utils::{derive_gongde_account_address, enumerate_user_pdas, EnumerateOptions};
+
+/// 取出所有 `flag <值>` 形式的参数值，同一个选项可以重复出现
+fn flag_values<'a>(args: &'a [String], flag: &str) -> Result<Vec<&'a String>, String> {
+    args.iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == flag)
+        .map(|(index, _)| args.get(index + 1).ok_or_else(|| format!("{} 需要一个值", flag)))
+        .collect()
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+
+    // [用户公钥] [--campaign <编号>]... [--lottery <轮次>]... [--endorse <用户>]... [--shared]，不指定用户时列出自己的
+    let args: Vec<String> = std::env::args().collect();
+    let value_flags = ["--campaign", "--lottery", "--endorse"];
+    let user = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(index, arg)| !arg.starts_with('-') && !value_flags.contains(&args[index - 1].as_str()))
+        .map(|(_, arg)| resolve_user(arg).map_err(|e| format!("用户无效: {}", e)))
+        .transpose()?;
+
+    let config = initialize_query_config()?;
+    let user = match user {
+        Some(user) => user,
+        None => config.default_user()?,
+    };
+
+    let parse_ids = |flag| -> Result<Vec<u64>, String> {
+        flag_values(&args, flag)?
+            .into_iter()
+            .map(|value| value.parse::<u64>().map_err(|e| format!("{} 参数无效: {}", flag, e)))
+            .collect()
+    };
+    let endorsees = flag_values(&args, "--endorse")?
+        .into_iter()
+        .map(|endorsee| {
+            let endorsee = resolve_user(endorsee).map_err(|e| format!("--endorse 用户无效: {}", e))?;
+            derive_gongde_account_address(&endorsee, &config.program_id)
+                .map_err(|e| format!("生成账户地址失败: {:?}", e))
+        })
+        .collect::<Result<Vec<Pubkey>, String>>()?;
+    let options = EnumerateOptions {
+        campaign_ids: parse_ids("--campaign")?,
+        lottery_rounds: parse_ids("--lottery")?,
+        endorsees,
+        include_shared: args.iter().any(|arg| arg == "--shared"),
+    };
+
+    // 安静模式下每行输出一个地址，方便脚本直接读取
+    info!("🗂️ 用户 {} 在程序 {} 中可能创建的账户:", user, config.program_id);
+    for pda in enumerate_user_pdas(&user, &config.program_id, &options) {
+        match pda.bump {
+            Some(bump) => info!("  {:<16} {}  bump {}", pda.name, pda.address, bump),
+            None => info!("  {:<16} {}  (种子地址)", pda.name, pda.address),
+        }
+        logging::emit_result(pda.address);
+    }
+    Ok(())
+}
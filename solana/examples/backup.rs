@@ -0,0 +1,79 @@
+// ========================================
+// 备份和恢复本地状态 - 重装系统前备份，装好后恢复
+// 💾 备份Solana CLI配置、通讯录和跟随进度；私钥默认不备份，带 --include-keys 才包含
+// 用法: cargo run --features client --example backup -- backup <文件> [--include-keys] | restore <文件> [--yes] [-q]
+// ========================================
+
+use std::io::IsTerminal;
+use tracing::{info, warn};
+
+use gong_de_increase::client::backup::{apply_restore, plan_restore, BackupBundle, BackupSources, RestoreChange};
+use gong_de_increase::client::config::home_dir;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+/// 恢复前确认：带了 --yes 直接恢复；交互终端中输入 y 恢复；非交互环境不会等待输入，视为取消
+fn confirm_restore(yes: bool) -> bool {
+    if yes {
+        return true;
+    }
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return false;
+    }
+    eprint!("是否恢复以上文件？[y/N]: ");
+    let mut line = String::new();
+    matches!(stdin.read_line(&mut line), Ok(read) if read > 0 && line.trim().eq_ignore_ascii_case("y"))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+    let args: Vec<String> = std::env::args().collect();
+    let positional: Vec<&String> = args.iter().filter(|arg| !arg.starts_with('-')).collect();
+    let home = home_dir();
+    match (positional.get(1).map(|arg| arg.as_str()), positional.get(2)) {
+        (Some("backup"), Some(file)) => {
+            let include_keys = args.iter().any(|arg| arg == "--include-keys");
+            let bundle = BackupBundle::create(&BackupSources::discover(), home.as_deref(), include_keys)?;
+            bundle.save(file)?;
+            for item in &bundle.items {
+                info!("💾 {}: {}", item.kind, item.path);
+            }
+            for key in &bundle.excluded_keys {
+                warn!("🔑 私钥没有备份: {}，恢复后请自行放回，或者带 --include-keys 重新备份", key);
+            }
+            if bundle.includes_keys() {
+                warn!("⚠️  备份中包含私钥，请像保管私钥一样保管 {}", file);
+            }
+            info!("✅ 已备份 {} 个文件到 {}", bundle.items.len(), file);
+            logging::emit_result(file);
+        }
+        (Some("restore"), Some(file)) => {
+            let bundle = BackupBundle::load(file)?;
+            let plan = plan_restore(&bundle, home.as_deref())?;
+            for action in &plan {
+                info!("  [{}] {}: {}", action.change, action.kind, action.path);
+            }
+            // 安静模式下输出需要恢复的文件数，0 表示本地文件与备份一致
+            let changed = plan.iter().filter(|action| action.change != RestoreChange::Unchanged).count();
+            if changed == 0 {
+                info!("✅ 本地文件与备份一致，不需要恢复");
+                logging::emit_result(changed);
+                return Ok(());
+            }
+            let yes = args.iter().any(|arg| arg == "--yes");
+            if !confirm_restore(yes) {
+                return Err("已取消恢复，非交互环境请带 --yes".into());
+            }
+            apply_restore(&bundle, home.as_deref())?;
+            for key in &bundle.excluded_keys {
+                warn!("🔑 备份中没有私钥，请把私钥放回: {}", key);
+            }
+            info!("✅ 恢复完成");
+            logging::emit_result(changed);
+        }
+        _ => return Err("用法: backup backup <文件> [--include-keys] | restore <文件> [--yes]".into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,225 @@
+// ========================================
+// 配捐活动管理工具
+// 🎉 管理员（发起人）从自己的功德中划出奖池创建配捐活动，
+// 活动期间别人增加功德时带上活动账户，每点功德额外从奖池配捐 multiplier 点
+// ========================================
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::Transaction,
+};
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_program_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{
+    arg_value,
+    cluster_unix_timestamp,
+    format_duration,
+    parse_arg,
+    send_transaction_and_watch,
+    verify_post_state,
+    ProgramGuard,
+};
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{
+    derive_campaign_address,
+    derive_global_gongde_pda_address,
+    derive_gongde_account_address,
+    predict_increment,
+    read_gongde_value,
+    validate_increment_amount,
+    Campaign,
+    CreateCampaignArgs,
+    SECONDS_PER_DAY,
+};
+
+/// 描述活动当前的状态，判断逻辑与链上配捐一致
+///
+/// # 参数
+/// * `campaign` - 配捐活动
+/// * `now` - 集群时间（Unix时间戳）
+pub fn describe_campaign(campaign: &Campaign, now: i64) -> String {
+    if now < campaign.start {
+        format!("⏳ 未开始（{}后开始）", format_duration(campaign.start - now))
+    } else if !campaign.is_active(now) {
+        "🔚 已结束".to_string()
+    } else if campaign.remaining == 0 {
+        "🈳 奖池已耗尽".to_string()
+    } else {
+        format!("🎉 进行中（{}后结束）", format_duration(campaign.end - now))
+    }
+}
+
+/// 读取功德账户中的功德值，账户不存在时为0（全局PDA在第一次增加功德时创建）
+fn fetch_merit(client: &RpcClient, pubkey: &Pubkey) -> Result<u32, Box<dyn std::error::Error>> {
+    match client.get_account_with_commitment(pubkey, client.commitment())?.value {
+        Some(account) => Ok(read_gongde_value(&account.data).map_err(|e| format!("读取功德值失败: {:?}", e))?),
+        None => Ok(0),
+    }
+}
+
+/// 预测带配捐活动增加功德之后的个人功德和全局功德，与链上 Increment 使用同样的规则
+/// 配捐只转移奖池中的功德，不计入全局功德；个人功德已圆满时指令直接结束，没有配捐
+///
+/// # 参数
+/// * `value` - 执行前的个人功德
+/// * `global` - 执行前的全局功德
+/// * `campaign` - 执行前的配捐活动
+/// * `now` - 集群时间（Unix时间戳）
+/// * `amount` - 增加的功德
+pub fn predict_campaign_increment(value: u32, global: u32, mut campaign: Campaign, now: i64, amount: u32) -> (u32, u32) {
+    let (new_value, new_global) = predict_increment(value, global, amount);
+    if value == u32::MAX {
+        return (new_value, new_global);
+    }
+    (new_value + campaign.take_bonus(now, amount, u32::MAX - new_value), new_global)
+}
+
+/// 查询配捐活动
+///
+/// # 返回
+/// * `Result<Option<Campaign>, Box<dyn std::error::Error>>` - 活动不存在时返回None
+fn fetch_campaign(client: &RpcClient, campaign_pubkey: &Pubkey) -> Result<Option<Campaign>, Box<dyn std::error::Error>> {
+    let account = client.get_account_with_commitment(campaign_pubkey, client.commitment())?.value;
+    match account {
+        Some(account) => Ok(Some(
+            Campaign::unpack(&account.data).map_err(|e| format!("解析配捐活动失败: {:?}", e))?,
+        )),
+        None => Ok(None),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 配捐活动管理工具启动 ===");
+
+    // create --budget <功德> [--multiplier <倍数>] [--days <天数>]  从现在开始的配捐活动，默认每点配捐1点、持续7天
+    // show [--organizer <发起人公钥>]  查看活动状态，默认查看自己发起的活动
+    // join --organizer <发起人公钥> [--amount <功德>]  带上活动账户给自己增加功德
+    // --id <编号> 活动编号，同一发起人可以有多个活动，默认0
+    // --force 跳过目标程序检查
+    let args: Vec<String> = std::env::args().collect();
+    let command = args.get(1).map(String::as_str).unwrap_or("show");
+    let campaign_id: u64 = parse_arg(&args, "--id", 0)?;
+    let force = args.iter().any(|arg| arg == "--force");
+
+    let config = initialize_program_config()?;
+    let payer = config.keypair.pubkey();
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    let organizer = match arg_value(&args, "--organizer") {
+        Some(organizer) => resolve_user(organizer).map_err(|e| format!("发起人无效: {}", e))?,
+        None if command == "join" => return Err("请用 --organizer 指定活动发起人".into()),
+        None => payer,
+    };
+    let (campaign_pubkey, _bump) = derive_campaign_address(&organizer, campaign_id, &config.program_id);
+    info!("📝 配捐活动地址: {}", campaign_pubkey);
+
+    match command {
+        "create" => {
+            ProgramGuard::new(force).ensure(&*client, &config.program_id, &payer)?;
+            let budget: u32 = parse_arg(&args, "--budget", 0)?;
+            let days: i64 = parse_arg(&args, "--days", 7)?;
+            let start = cluster_unix_timestamp(&client)?;
+            let campaign_args = CreateCampaignArgs {
+                campaign_id,
+                start,
+                end: start.saturating_add(days.saturating_mul(SECONDS_PER_DAY)),
+                multiplier: parse_arg(&args, "--multiplier", 1)?,
+                budget,
+            };
+            let organizer_gongde = derive_gongde_account_address(&payer, &config.program_id)
+                .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+            let create = instruction::create_campaign(&config.program_id, &organizer_gongde, &payer, &campaign_args);
+            // 发送前在本地按链上规则检查参数和发起人的功德，不合法时不白白支付手续费
+            instruction::validate_spend(&create, fetch_merit(&client, &organizer_gongde)?)
+                .map_err(|e| format!("{}（奖池和倍数都不能为0且奖池不能超过当前功德，持续时间必须大于0）", e))?;
+
+            info!("💰 奖池 {} 功德从您的功德中扣除，持续 {} 天，每点功德配捐 {} 点", budget, days, campaign_args.multiplier);
+            let mut transaction = Transaction::new_with_payer(&[create], Some(&payer));
+            transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+            send_transaction_and_watch(
+                &client,
+                &transaction,
+                &payer,
+                "创建配捐活动",
+                &[(organizer_gongde, "发起人功德账户"), (campaign_pubkey, "配捐活动")],
+            )?;
+            info!("📢 其他用户运行 `cargo run --features client --example campaign -- join --organizer {} --id {}` 即可参加", payer, campaign_id);
+        }
+        "show" => {
+            let Some(campaign) = fetch_campaign(&client, &campaign_pubkey)? else {
+                warn!("❌ 配捐活动不存在");
+                return Ok(());
+            };
+            let now = cluster_unix_timestamp(&client)?;
+            info!("👤 发起人: {}", campaign.organizer);
+            info!("🕐 时间: {} ~ {}（Unix时间戳）", campaign.start, campaign.end);
+            info!("✖️  配捐倍数: {}", campaign.multiplier);
+            info!("💰 奖池剩余: {} 功德", campaign.remaining);
+            info!("📊 状态: {}", describe_campaign(&campaign, now));
+        }
+        "join" => {
+            ProgramGuard::new(force).ensure(&*client, &config.program_id, &payer)?;
+            let campaign = fetch_campaign(&client, &campaign_pubkey)?.ok_or("配捐活动不存在")?;
+            // 活动不在进行中时照常增加功德，只是没有配捐
+            let now = cluster_unix_timestamp(&client)?;
+            info!("📊 活动状态: {}", describe_campaign(&campaign, now));
+
+            let amount: u64 = parse_arg(&args, "--amount", 1)?;
+            let amount32 = validate_increment_amount(amount).map_err(|e| format!("功德数量无效: {:?}", e))?;
+            let gongde_pubkey = derive_gongde_account_address(&payer, &config.program_id)
+                .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+            let (global_pubkey, _bump) = derive_global_gongde_pda_address(&config.program_id)
+                .map_err(|e| format!("生成全局PDA账户地址失败: {:?}", e))?;
+            // 按执行前的状态预测结果；活动恰好在发送期间开始或结束时预测会有偏差
+            let (expected_value, expected_global) = predict_campaign_increment(
+                fetch_merit(&client, &gongde_pubkey)?,
+                fetch_merit(&client, &global_pubkey)?,
+                campaign,
+                now,
+                amount32,
+            );
+            let increment = instruction::with_campaign(
+                instruction::increment_by(&config.program_id, &gongde_pubkey, &payer, &global_pubkey, amount),
+                &campaign_pubkey,
+            );
+            let mut transaction = Transaction::new_with_payer(&[increment], Some(&payer));
+            transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+            let (signature, _) = send_transaction_and_watch(
+                &client,
+                &transaction,
+                &payer,
+                "增加功德（配捐活动）",
+                &[(gongde_pubkey, "功德账户"), (campaign_pubkey, "配捐活动")],
+            )?;
+            let expected = [(gongde_pubkey, "功德账户", expected_value), (global_pubkey, "全局功德PDA账户", expected_global)];
+            if let Err(e) = verify_post_state(&*client, &signature, &expected) {
+                warn!("⚠️  校验执行后状态失败: {}", e);
+            }
+        }
+        other => return Err(format!("未知命令: {}，可用命令: create / show / join", other).into()),
+    }
+    Ok(())
+}
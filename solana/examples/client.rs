@@ -3,222 +3,347 @@
 // 🎯 这个文件演示如何调用智能合约，包含全局PDA账户的自动创建
 // ========================================
 
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
     transaction::Transaction,
-    signature::Signer,
-    system_instruction,
+    signature::{Signature, Signer},
 };
+use std::io::IsTerminal;
+use tracing::{info, warn};
 
 // 引用本地配置模块
-mod config;
-use config::initialize_program_config_with_keypair;
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+use config::{initialize_program_config_with_keypair, ProgramConfig};
+
+// 引用演示计划模块
+use gong_de_increase::client::plan;
+use plan::{check_gongde_address, lifecycle_cost, plan_demo, AccountState, DemoContext, DemoPlan, PlannedOperation, DEMO_INCREMENTS};
+
+// 引用工作量证明模块，开启后自动计算 nonce
+use gong_de_increase::client::pow;
 
 // 引用工具函数模块
-mod utils;
-use utils::{check_and_print_balance, send_transaction_and_check_balance, print_total_consumption};
+use gong_de_increase::client::utils;
+use utils::{
+    check_and_print_balance,
+    format_sol_balance,
+    send_transaction_and_watch,
+    verify_post_state,
+    fetch_session_fees,
+    print_cost_breakdown,
+    ask_migration_consent,
+    with_auto_migration,
+    AccountPoller,
+    ComputeUnitTuner,
+    ProgramGuard,
+    DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT,
+};
 use gong_de_increase::utils::{
     read_gongde_value, 
+    is_usable_account,
     derive_gongde_account_address, 
     derive_global_gongde_pda_address,
-    GongDeInstruction, 
-    GONGDE_VALUE_SIZE, 
+    GONGDE_ACCOUNT_SIZE, 
+    GONGDE_VALUE_SIZE,
     GONGDE_ACCOUNT_SEED, 
     GLOBAL_GONGDE_ACCOUNT_SEED,
 };
 
+/// 只读查询账户现状，账户不存在或余额为0时视为不存在；已创建但没有初始化标志的账户单独区分，
+/// 重新运行时只补发初始化，不会再创建一次
+fn fetch_account_state(client: &MultiRpcClient, pubkey: &Pubkey) -> Result<AccountState, Box<dyn std::error::Error>> {
+    match client.get_account(pubkey) {
+        Ok(account) if account.lamports > 0 && !is_usable_account(&account.data) => {
+            Ok(AccountState::Uninitialized { lamports: account.lamports })
+        }
+        Ok(account) if account.lamports > 0 => {
+            let value = read_gongde_value(&account.data)
+                .map_err(|e| format!("读取功德值失败: {:?}", e))?;
+            Ok(AccountState::Present { value, lamports: account.lamports })
+        }
+        _ => Ok(AccountState::Absent),
+    }
+}
+
+/// 按计划依次发送交易，每一步一笔交易；每笔交易前按模拟结果设置计算单元上限
+///
+/// # 返回
+/// * `Vec<Signature>` - 已发送交易的签名，用于统计网络手续费
+fn execute_plan(
+    client: &MultiRpcClient,
+    config: &ProgramConfig,
+    plan: DemoPlan,
+    tuner: &mut ComputeUnitTuner,
+) -> Result<Vec<Signature>, Box<dyn std::error::Error>> {
+    let mut signatures = Vec::with_capacity(plan.operations.len());
+    for operation in &plan.operations {
+        let description = match operation {
+            PlannedOperation::CreateAccount { is_public, .. } => {
+                info!("\n=== 创建个人功德账户（{}） ===", if *is_public { "公开功德箱" } else { "私有功德箱" });
+                "个人功德账户创建".to_string()
+            }
+            PlannedOperation::InitializeAccount { is_public } => {
+                info!("\n=== 补发初始化个人功德账户（{}） ===", if *is_public { "公开功德箱" } else { "私有功德箱" });
+                "个人功德账户初始化".to_string()
+            }
+            PlannedOperation::TopUpRent { lamports } => {
+                info!("\n=== 补足个人功德账户租金: {} lamports ===", lamports);
+                "补足租金".to_string()
+            }
+            PlannedOperation::Increment { index, .. } => {
+                info!("\n🔄 第 {} 次增加操作:", index);
+                format!("功德第{}次增加", index)
+            }
+        };
+
+        // ⛏️ 全局配置开启工作量证明时，增加功德前先算出 nonce，发送 IncrementWithProof
+        let planned = match operation {
+            PlannedOperation::Increment { .. } => vec![pow::increment_instruction(
+                &**client,
+                &plan.program_id,
+                &plan.gongde_pubkey,
+                &plan.payer,
+                &plan.global_pubkey,
+            )?],
+            _ => plan.instructions(operation),
+        };
+
+        // 📤 发送交易到网络执行
+        let recent_blockhash = client.get_latest_blockhash()?;
+        let instructions = tuner.with_compute_unit_limit(&**client, &planned, &config.keypair.pubkey());
+        let mut transaction = Transaction::new_with_payer(
+            &instructions,
+            Some(&config.keypair.pubkey())
+        );
+        transaction.sign(&[&config.keypair], recent_blockhash);
+        let watched = [(plan.gongde_pubkey, "个人功德账户"), (plan.global_pubkey, "全局功德PDA账户")];
+        let sent = send_transaction_and_watch(
+            client,
+            &transaction,
+            &config.keypair.pubkey(),
+            &description,
+            &watched
+        );
+        let signature = match sent {
+            Ok((signature, _)) => signature,
+            // 🔁 创建或初始化失败时再查一次：上次运行或同时运行的另一个客户端可能已经完成了，
+            // 账户已初始化就当作这一步完成；只创建了没初始化的账户，重新运行会只补发初始化
+            Err(e) if matches!(operation, PlannedOperation::CreateAccount { .. } | PlannedOperation::InitializeAccount { .. }) => {
+                match fetch_account_state(client, &plan.gongde_pubkey)? {
+                    AccountState::Present { value, .. } => {
+                        warn!("⚠️  {}失败，但功德账户已经初始化（当前值 {}），跳过这一步: {}", description, value, e);
+                        continue;
+                    }
+                    AccountState::Uninitialized { .. } => {
+                        return Err(format!("{}失败，账户已创建但没有初始化，重新运行会补发初始化: {}", description, e).into());
+                    }
+                    AccountState::Absent => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        signatures.push(signature);
+
+        match operation {
+            PlannedOperation::CreateAccount { .. } => {
+                // ⏳ 等待新账户在节点上可见，避免紧接着的查询读不到
+                AccountPoller::default().wait_for_account(&**client, &plan.gongde_pubkey)?;
+                info!("✅ 个人功德账户创建成功，初始值: 0");
+            }
+            PlannedOperation::InitializeAccount { .. } => info!("✅ 个人功德账户初始化完成"),
+            PlannedOperation::TopUpRent { .. } => info!("✅ 租金已补足"),
+            PlannedOperation::Increment { expected_value, expected_global, .. } => {
+                info!("📋 预计个人功德 {}，全局功德 {}", expected_value, expected_global);
+            }
+        }
+
+        // 🔎 重新读取功德，与计划中按链上规则预测的值比较，不一致时醒目报告但不中断
+        let expected = match operation {
+            PlannedOperation::CreateAccount { .. } | PlannedOperation::InitializeAccount { .. } => {
+                vec![(plan.gongde_pubkey, "个人功德账户", 0)]
+            }
+            PlannedOperation::TopUpRent { .. } => Vec::new(),
+            PlannedOperation::Increment { expected_value, expected_global, .. } => vec![
+                (plan.gongde_pubkey, "个人功德账户", *expected_value),
+                (plan.global_pubkey, "全局功德PDA账户", *expected_global),
+            ],
+        };
+        if let Err(e) = verify_post_state(&**client, &signature, &expected) {
+            warn!("⚠️  校验执行后状态失败: {}", e);
+        }
+    }
+    Ok(signatures)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("=== Solana 功德 智能合约客户端（支持全局PDA）启动 ===");
+    logging::init_logging_for_result();
+    info!("=== Solana 功德 智能合约客户端（支持全局PDA）启动 ===");
     
     // 检查命令行参数
+    // --private 表示创建私有功德箱（只有自己能增加功德），默认是公开功德箱
     let args: Vec<String> = std::env::args().collect();
+    let is_public = !args.iter().any(|arg| arg == "--private");
+    // --force 跳过目标程序检查
+    let force = args.iter().any(|arg| arg == "--force");
+    // --plan 只打印执行计划，不发送任何交易
+    let plan_only = args.iter().any(|arg| arg == "--plan");
+    // --migrate 账户是旧布局时不询问，直接迁移
+    let auto_migrate = args.iter().any(|arg| arg == "--migrate");
+    // --cu-margin <百分比> 计算单元上限在模拟消耗之上的余量，默认20%
+    let cu_margin = match args.iter().position(|arg| arg == "--cu-margin") {
+        Some(index) => args
+            .get(index + 1)
+            .ok_or("--cu-margin 需要一个百分比")?
+            .parse::<u32>()
+            .map_err(|e| format!("--cu-margin 参数无效: {}", e))?,
+        None => DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT,
+    };
+    let positional: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, arg)| !arg.starts_with('-') && args[index - 1] != "--cu-margin")
+        .map(|(_, arg)| arg)
+        .collect();
     
     // 初始化配置 - 如果有第一个参数就使用指定的私钥文件，否则使用默认配置
-    let config = if args.len() > 1 {
-        let keypair_path = &args[1];
-        println!("🔑 使用指定的私钥文件: {}", keypair_path);
-        initialize_program_config_with_keypair(Some(keypair_path))?
+    let config = if let Some(keypair_path) = positional.first() {
+        info!("🔑 使用指定的私钥文件: {}", keypair_path);
+        initialize_program_config_with_keypair(Some(keypair_path.as_str()))?
     } else {
-        println!("🔑 使用默认配置");
+        info!("🔑 使用默认配置");
         initialize_program_config_with_keypair(None)?
     };
-    println!("✅ 配置初始化成功!");
-    println!("  - 程序ID: {}", config.program_id);
-    println!("  - RPC URL: {}", config.rpc_url);
-    println!("  - 用户地址: {}", config.keypair.pubkey());
+    info!("✅ 配置初始化成功!");
+    info!("  - 程序ID: {}", config.program_id);
+    info!("  - RPC URL: {}", config.rpc_url);
+    info!("  - 用户地址: {}", config.keypair.pubkey());
 
     // 📱 连接到 Solana 网络
-    println!("\n🌐 连接到Solana网络: {}", config.rpc_url);
-    let client = RpcClient::new_with_commitment(config.rpc_url, CommitmentConfig::confirmed());
+    info!("\n🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    // 🔍 发送交易前确认程序ID指向的是本合约（只做模拟，不会写入）
+    ProgramGuard::new(force).ensure(&*client, &config.program_id, &config.keypair.pubkey())?;
 
     // 💰 检查账户余额
-    println!("\n💰 检查账户余额...");
+    info!("\n💰 检查账户余额...");
     let balance = check_and_print_balance(&client, &config.keypair.pubkey(), "当前账户余额")?;
 
     // 🏗️ 创建专属的数据账户地址
     let gongde_pubkey = derive_gongde_account_address(&config.keypair.pubkey(), &config.program_id)
         .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
-    println!("\n📝 用户专属功德账户地址: {}", gongde_pubkey);
-    println!("   (基于用户公钥 + 种子: '{}' + 程序ID生成)", GONGDE_ACCOUNT_SEED);
+    info!("\n📝 用户专属功德账户地址: {}", gongde_pubkey);
+    info!("   (基于用户公钥 + 种子: '{}' + 程序ID生成)", GONGDE_ACCOUNT_SEED);
 
     // 🌍 创建全局功德PDA账户地址
     let (global_gongde_pubkey, _bump) = derive_global_gongde_pda_address(&config.program_id)
         .map_err(|e| format!("生成全局PDA账户地址失败: {:?}", e))?;
-    println!("\n🌍 全局功德PDA账户地址: {}", global_gongde_pubkey);
-    println!("   (基于程序ID + 种子: '{}' 的PDA)", GLOBAL_GONGDE_ACCOUNT_SEED);
-
-    // 🔍 检查功德账户是否已存在
-    let mut gongde_exists = match client.get_account(&gongde_pubkey) {
-        Ok(account) => {
-            if account.lamports > 0 {
-                let gongde_value = read_gongde_value(&account.data)
-                    .map_err(|e| format!("读取功德值失败: {:?}", e))?;
-                println!("✅ 功德账户已存在，当前值: {}", gongde_value);
-                true
-            } else {
-                false
-            }
-        },
-        Err(_) => {
-            println!("ℹ️  功德账户不存在，需要创建");
-            false
-        }
-    };
+    info!("\n🌍 全局功德PDA账户地址: {}", global_gongde_pubkey);
+    info!("   (基于程序ID + 种子: '{}' 的PDA)", GLOBAL_GONGDE_ACCOUNT_SEED);
 
-    // 🔍 检查全局功德PDA账户是否已存在
-    let global_gongde_exists = match client.get_account(&global_gongde_pubkey) {
-        Ok(account) => {
-            if account.lamports > 0 {
-                let global_gongde_value = read_gongde_value(&account.data)
-                    .map_err(|e| format!("读取全局功德值失败: {:?}", e))?;
-                println!("✅ 全局功德PDA账户已存在，当前值: {}", global_gongde_value);
-                true
-            } else {
-                false
-            }
-        },
-        Err(_) => {
-            println!("ℹ️  全局功德PDA账户不存在，将在第一次调用时自动创建");
-            false
+    // 🛡️ 地址上已有不属于本合约的余额时创建账户会失败，提前说明原因
+    let existing = client.get_account_with_commitment(&gongde_pubkey, client.commitment())?.value;
+    check_gongde_address(&config.keypair.pubkey(), &gongde_pubkey, &config.program_id, existing.as_ref())?;
+
+    // 🔍 查询账户现状，据此生成计划；账户是旧布局时经同意先迁移（--plan 模式不发送交易）
+    let stdin = std::io::stdin();
+    let gongde = with_auto_migration(
+        &*client,
+        &config.keypair,
+        &config.program_id,
+        &config.keypair.pubkey(),
+        &gongde_pubkey,
+        |gap| !plan_only && ask_migration_consent(gap, auto_migrate, stdin.is_terminal(), &mut stdin.lock()),
+        |_| fetch_account_state(&client, &gongde_pubkey),
+    )?;
+    match gongde {
+        AccountState::Present { value, .. } => info!("✅ 功德账户已存在，当前值: {}", value),
+        AccountState::Absent => info!("ℹ️  功德账户不存在，需要创建"),
+        AccountState::Uninitialized { .. } => info!("ℹ️  功德账户已创建但没有初始化，将只补发初始化"),
+    }
+    let global = fetch_account_state(&client, &global_gongde_pubkey)?;
+    match global {
+        AccountState::Present { value, .. } => info!("✅ 全局功德PDA账户已存在，当前值: {}", value),
+        AccountState::Absent | AccountState::Uninitialized { .. } => {
+            info!("ℹ️  全局功德PDA账户不存在，将在第一次调用时自动创建")
         }
-    };
+    }
 
-    // 🏗️ 如果个人账户不存在，创建新的数据账户
-    if !gongde_exists {
-        println!("\n=== 步骤 1: 创建个人功德账户 ===");
-        
-        // 💰 计算账户所需租金
-        let rent = client.get_minimum_balance_for_rent_exemption(GONGDE_VALUE_SIZE)?;
-        
-        // 🏗️ 使用系统程序创建账户
-        let create_instruction = system_instruction::create_account_with_seed(
-            &config.keypair.pubkey(),
-            &gongde_pubkey,
-            &config.keypair.pubkey(),
-            GONGDE_ACCOUNT_SEED,
-            rent,
-            GONGDE_VALUE_SIZE as u64,
-            &config.program_id,
-        );
+    let gongde_rent = client.get_minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE)?;
+    let global_rent = client.get_minimum_balance_for_rent_exemption(GONGDE_VALUE_SIZE)?;
+    let plan = plan_demo(&DemoContext {
+        program_id: config.program_id,
+        payer: config.keypair.pubkey(),
+        gongde_pubkey,
+        global_pubkey: global_gongde_pubkey,
+        is_public,
+        gongde,
+        global,
+        gongde_rent,
+        global_rent,
+    });
 
-        // 📦 构建并发送创建账户的交易
-        let recent_blockhash = client.get_latest_blockhash()?;
-        let mut transaction = Transaction::new_with_payer(
-            &[create_instruction], 
-            Some(&config.keypair.pubkey())
+    // 💡 新用户关心创建账户要花多少钱：租金在关闭时退还，算上关闭这笔交易之后只花掉手续费
+    if gongde == AccountState::Absent {
+        let global_rent = if matches!(global, AccountState::Present { .. }) { 0 } else { global_rent };
+        let cost = lifecycle_cost(gongde_rent, global_rent, DEMO_INCREMENTS);
+        info!(
+            "💡 创建账户 → 增加 {} 次功德 → 关闭账户，需要先准备 {}，关闭后净花费约 {}（租金 {} 在关闭时全额退还）",
+            DEMO_INCREMENTS,
+            format_sol_balance(cost.upfront()),
+            format_sol_balance(cost.net()),
+            format_sol_balance(cost.refundable_rent)
         );
-        transaction.sign(&[&config.keypair], recent_blockhash);
-
-        let _signature = send_transaction_and_check_balance(
-            &client,
-            &transaction,
-            &config.keypair.pubkey(),
-            "个人功德账户创建"
-        )?;
+        if cost.global_rent > 0 {
+            info!("   其中 {} 是全局功德PDA的租金，由第一个用户支付且不退还", format_sol_balance(cost.global_rent));
+        }
+    }
 
-        println!("✅ 个人功德账户创建成功，初始值: 0");
-        gongde_exists = true;
+    // 📋 只打印计划
+    if plan_only {
+        plan.print();
+        return Ok(());
     }
 
-    // 只有在个人账户存在时才继续执行
-    if gongde_exists {
-        // 🎯 调用智能合约函数 - 增加功德三次
-        println!("\n=== 步骤 2: 增加功德（执行3次） ===");
-        if !global_gongde_exists {
-            println!("注意：全局PDA账户将在第一次调用时自动创建");
-        }
-        
-        for i in 1..=3 {
-            println!("\n🔄 第 {} 次增加操作:", i);
-            
-            // 📝 创建调用指令 - 手续费将直接转到全局PDA账户
-            let increment_instruction = Instruction::new_with_bytes(
-                config.program_id,
-                &[GongDeInstruction::Increment as u8],
-                vec![
-                    AccountMeta::new(gongde_pubkey, false),           // 个人功德账户（可写）
-                    AccountMeta::new(config.keypair.pubkey(), true),  // 用户账户（签名者，支付手续费）
-                    AccountMeta::new(global_gongde_pubkey, false),    // 全局PDA账户（可写）
-                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false), // 系统程序
-                ],
-            );
-
-            // 📤 发送交易到网络执行
-            let recent_blockhash = client.get_latest_blockhash()?;
-            let mut transaction = Transaction::new_with_payer(
-                &[increment_instruction],
-                Some(&config.keypair.pubkey())
-            );
-            transaction.sign(&[&config.keypair], recent_blockhash);
-
-            let _signature = send_transaction_and_check_balance(
-                &client,
-                &transaction,
-                &config.keypair.pubkey(),
-                &format!("功德第{}次增加", i)
-            )?;
-
-            // 📊 读取执行结果
-            let gongde_account = client.get_account(&gongde_pubkey)?;
-            let gongde_value = read_gongde_value(&gongde_account.data)
-                .map_err(|e| format!("读取功德值失败: {:?}", e))?;
-            println!("📊 当前个人功德值: {}", gongde_value);
+    // 🎯 按计划执行
+    let signatures = execute_plan(&client, &config, plan, &mut ComputeUnitTuner::new(cu_margin))?;
 
-            // 📊 读取全局功德值
-            let global_gongde_account = client.get_account(&global_gongde_pubkey)?;
-            let global_gongde_value = read_gongde_value(&global_gongde_account.data)
-                .map_err(|e| format!("读取全局功德值失败: {:?}", e))?;
-            println!("🌍 当前全局功德值: {}", global_gongde_value);
-        }
+    // 📊 显示最终结果和统计信息
+    let gongde_account = client.get_account(&gongde_pubkey)?;
+    let final_gongde_value = read_gongde_value(&gongde_account.data)
+        .map_err(|e| format!("读取最终功德值失败: {:?}", e))?;
+    info!("\n📊 最终个人功德值: {}", final_gongde_value);
+    logging::emit_result(final_gongde_value);
 
-        // 📊 显示最终结果和统计信息
-        let gongde_account = client.get_account(&gongde_pubkey)?;
-        let final_gongde_value = read_gongde_value(&gongde_account.data)
-            .map_err(|e| format!("读取最终功德值失败: {:?}", e))?;
-        println!("\n📊 最终个人功德值: {}", final_gongde_value);
-
-        let global_gongde_account = client.get_account(&global_gongde_pubkey)?;
-        let final_global_gongde_value = read_gongde_value(&global_gongde_account.data)
-            .map_err(|e| format!("读取最终全局功德值失败: {:?}", e))?;
-        println!("🌍 最终全局功德值: {}", final_global_gongde_value);
-
-        // 显示最终余额和总消耗
-        let final_balance = check_and_print_balance(&client, &config.keypair.pubkey(), "最终余额")?;
-        print_total_consumption(balance, final_balance);
-        
-        println!("\n🎉 === 功德智能合约演示完成 ===");
-        println!("📝 本次演示执行的操作:");
-        println!("   1. ✅ 创建了用户的个人功德账户");
-        println!("   2. ✅ 自动创建了全局功德PDA账户");
-        println!("   3. ✅ 执行了 3 次增加操作（同时增加个人和全局功德）");
-        println!("🎊 所有操作均成功完成！");
-        println!("ℹ️  个人功德账户地址: {}", gongde_pubkey);
-        println!("ℹ️  全局功德PDA账户地址: {}", global_gongde_pubkey);
-        println!("ℹ️  使用 close.rs 可以关闭个人账户并回收租金");
-    }
+    let global_gongde_account = client.get_account(&global_gongde_pubkey)?;
+    let final_global_gongde_value = read_gongde_value(&global_gongde_account.data)
+        .map_err(|e| format!("读取最终全局功德值失败: {:?}", e))?;
+    info!("🌍 最终全局功德值: {}", final_global_gongde_value);
+
+    // 显示最终余额和总消耗
+    let final_balance = check_and_print_balance(&client, &config.keypair.pubkey(), "最终余额")?;
+    let network_fees = fetch_session_fees(&client, &signatures)?;
+    print_cost_breakdown(balance, final_balance, network_fees);
+    
+    info!("\n🎉 === 功德智能合约演示完成 ===");
+    info!("📝 本次演示执行的操作:");
+    info!("   1. ✅ 确保用户的个人功德账户存在且租金充足");
+    info!("   2. ✅ 自动创建了全局功德PDA账户（如不存在）");
+    info!("   3. ✅ 执行了 3 次增加操作（同时增加个人和全局功德）");
+    info!("🎊 所有操作均成功完成！");
+    info!("ℹ️  个人功德账户地址: {}", gongde_pubkey);
+    info!("ℹ️  全局功德PDA账户地址: {}", global_gongde_pubkey);
+    info!("ℹ️  使用 close.rs 可以关闭个人账户并回收租金");
     
     Ok(())
 }
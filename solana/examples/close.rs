@@ -3,40 +3,80 @@
 // 用于关闭 功德 账户并回收租金
 // ========================================
 
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
     transaction::Transaction,
     signature::Signer,
 };
+use tracing::{info, warn};
 
 // 引用本地配置模块
-mod config;
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
 use config::initialize_program_config;
 
-// 引用工具函数模块 - 直接使用src中的工具函数和examples中的客户端工具
-mod utils;
-use utils::{check_and_print_balance, send_transaction_and_check_balance, format_sol_balance};
-use gong_de_increase::utils::{read_gongde_value, derive_gongde_account_address, GongDeInstruction};
+// 引用工具函数模块 - 合约的工具函数和 client 中的客户端工具
+use gong_de_increase::client::utils;
+use utils::{
+    check_and_print_balance,
+    format_sol_balance,
+    guard_destructive_operation,
+    query_gongde_account_at,
+    send_transaction_and_watch,
+    ProgramGuard,
+};
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{read_gongde_value, derive_gongde_account_address};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("=== 关闭 功德 账户并回收租金（精简版）===");
+    logging::init_logging_for_result();
+    info!("=== 关闭 功德 账户并回收租金（精简版）===");
     
     // 初始化配置
     let config = initialize_program_config()?;
-    println!("✅ 配置初始化成功!");
-    println!("  - 程序ID: {}", config.program_id);
-    println!("  - 用户地址: {}", config.keypair.pubkey());
+    info!("✅ 配置初始化成功!");
+    info!("  - 程序ID: {}", config.program_id);
+    info!("  - 用户地址: {}", config.keypair.pubkey());
 
     // 连接到 Solana 网络
-    let client = RpcClient::new_with_commitment(config.rpc_url, CommitmentConfig::confirmed());
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    // 发送交易前确认程序ID指向的是本合约，--force 跳过检查
+    let force = std::env::args().any(|arg| arg == "--force");
+    ProgramGuard::new(force).ensure(&*client, &config.program_id, &config.keypair.pubkey())?;
 
-    // 使用与 client.rs 相同的方法计算账户地址
-    let gongde_pubkey = derive_gongde_account_address(&config.keypair.pubkey(), &config.program_id)
-        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
-    println!("\n📝 用户专属 功德 账户地址: {}", gongde_pubkey);
+    // 🚨 关闭账户不可撤销，主网上需要 --allow-mainnet 或输入集群名称确认
+    let allow_mainnet = std::env::args().any(|arg| arg == "--allow-mainnet");
+    guard_destructive_operation(&client, "关闭功德账户", allow_mainnet)?;
+
+    // --address 直接指定账户地址（旧的 create_with_seed 账户或随机密钥对账户），否则使用与 client.rs 相同的方法计算
+    let args: Vec<String> = std::env::args().collect();
+    let address = args.iter().position(|arg| arg == "--address").map(|index| {
+        let address = args.get(index + 1).ok_or("--address 需要指定功德账户地址")?;
+        address.parse::<Pubkey>().map_err(|e| format!("功德账户地址无效: {}", e))
+    });
+    let gongde_pubkey = match address.transpose()? {
+        Some(address) => {
+            // 手动指定的地址可能根本不是功德账户，先确认归本程序所有且能解析
+            query_gongde_account_at(&*client, &address, &config.program_id)?;
+            info!("\n📝 直接指定的 功德 账户地址: {}（不从用户公钥派生）", address);
+            address
+        }
+        None => {
+            let gongde_pubkey = derive_gongde_account_address(&config.keypair.pubkey(), &config.program_id)
+                .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+            info!("\n📝 用户专属 功德 账户地址: {}", gongde_pubkey);
+            gongde_pubkey
+        }
+    };
 
     // 检查 功德 账户是否存在
     let _gongde_account = match client.get_account(&gongde_pubkey) {
@@ -44,51 +84,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             if account.lamports > 0 {
                 let gongde_value = read_gongde_value(&account.data)
                     .map_err(|e| format!("读取功德值失败: {:?}", e))?;
-                println!("✅ 功德 账户存在，当前值: {}", gongde_value);
-                println!("📊 账户余额: {} lamports ({})", 
+                info!("✅ 功德 账户存在，当前值: {}", gongde_value);
+                info!("📊 账户余额: {} lamports ({})", 
                          account.lamports, 
                          format_sol_balance(account.lamports));
                 account
             } else {
-                println!("❌ 功德 账户已经被关闭");
+                warn!("❌ 功德 账户已经被关闭");
+                logging::emit_result(0);
                 return Ok(());
             }
         },
         Err(_) => {
-            println!("❌ 功德 账户不存在，无需关闭");
+            warn!("❌ 功德 账户不存在，无需关闭");
+            logging::emit_result(0);
             return Ok(());
         }
     };
 
     // 检查用户余额
-    println!("\n💰 检查用户余额...");
+    info!("\n💰 检查用户余额...");
     let balance_before = check_and_print_balance(&client, &config.keypair.pubkey(), "关闭前余额")?;
 
     // 关闭账户并回收租金
-    println!("\n🔄 执行关闭操作...");
+    info!("\n🔄 执行关闭操作...");
     
     // 创建关闭指令
-    let close_instruction = Instruction::new_with_bytes(
-        config.program_id,
-        &[GongDeInstruction::Close as u8],
-        vec![
-            // 功德 账户（可写，将被关闭）
-            AccountMeta::new(gongde_pubkey, false),
-            // 用户账户（可写，接收租金，签名者）
-            AccountMeta::new(config.keypair.pubkey(), true),
-        ],
-    );
+    let close_instruction = instruction::close(&config.program_id, &gongde_pubkey, &config.keypair.pubkey());
 
     // 发送关闭交易
     let recent_blockhash = client.get_latest_blockhash()?;
     let mut transaction = Transaction::new_with_payer(&[close_instruction], Some(&config.keypair.pubkey()));
     transaction.sign(&[&config.keypair], recent_blockhash);
 
-    let _signature = send_transaction_and_check_balance(
+    let _ = send_transaction_and_watch(
         &client,
         &transaction,
         &config.keypair.pubkey(),
-        "关闭 功德 账户"
+        "关闭 功德 账户",
+        &[(gongde_pubkey, "功德账户")]
     )?;
 
     // 检查关闭后的余额
@@ -96,22 +130,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 计算回收的租金
     let recovered_rent = balance_after.saturating_sub(balance_before);
-    println!("🎉 成功回收租金: {} lamports ({})", 
+    info!("🎉 成功回收租金: {} lamports ({})", 
              recovered_rent, format_sol_balance(recovered_rent));
+    logging::emit_result(recovered_rent);
 
     // 验证账户已被关闭
     match client.get_account(&gongde_pubkey) {
         Ok(account) => {
             if account.lamports == 0 {
-                println!("✅ 确认：账户已成功关闭");
+                info!("✅ 确认：账户已成功关闭");
             } else {
-                println!("⚠️  警告：账户仍有余额");
+                warn!("⚠️  警告：账户仍有余额");
             }
         },
-        Err(_) => println!("✅ 确认：账户已完全删除"),
+        Err(_) => info!("✅ 确认：账户已完全删除"),
     }
 
-    println!("\n🎊 账户关闭完成！租金已成功回收！");
+    info!("\n🎊 账户关闭完成！租金已成功回收！");
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+// ========================================
+// 通讯录 - 给常查的用户起个短名字
+// 📇 名字和公钥保存在本地文件中，查询、背书等命令里需要用户公钥的地方都可以写 @名字
+// 用法: cargo run --features client --example contacts -- add <名字> <公钥> | remove <名字> | list [-q]
+// ========================================
+
+use tracing::info;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts::{contacts_path, AddressBook, ContactsError};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+    let args: Vec<String> = std::env::args().filter(|arg| !arg.starts_with('-')).collect();
+    let path = contacts_path();
+    let mut book = AddressBook::load(&path).map_err(|e| e.to_string())?;
+    match args.get(1).map(String::as_str) {
+        Some("add") => {
+            let (Some(name), Some(user)) = (args.get(2), args.get(3)) else {
+                return Err("用法: contacts add <名字> <公钥 | @已有名字>".into());
+            };
+            let pubkey = book.resolve(user).map_err(|e| e.to_string())?;
+            match book.add(name, pubkey).map_err(|e| e.to_string())? {
+                Some(previous) if previous != pubkey => info!("✏️  @{}: {} -> {}", name, previous, pubkey),
+                _ => info!("✅ @{}: {}", name, pubkey),
+            }
+            book.save(&path).map_err(|e| e.to_string())?;
+            logging::emit_result(pubkey);
+        }
+        Some("remove") => {
+            let name = args.get(2).ok_or("用法: contacts remove <名字>")?;
+            let name = name.strip_prefix('@').unwrap_or(name);
+            let removed = book.remove(name).ok_or_else(|| {
+                ContactsError::UnknownName { name: name.to_string(), suggestions: book.suggestions(name) }.to_string()
+            })?;
+            book.save(&path).map_err(|e| e.to_string())?;
+            info!("🗑️  已删除 @{}: {}", name, removed);
+            logging::emit_result(removed);
+        }
+        Some("list") | None => {
+            if book.is_empty() {
+                info!("📇 通讯录是空的，用 `cargo run --features client --example contacts -- add <名字> <公钥>` 添加");
+            }
+            for (name, pubkey) in book.iter() {
+                let line = format!("@{:<16} {}", name, pubkey);
+                info!("{}", line);
+                logging::emit_result(line);
+            }
+        }
+        Some(other) => return Err(format!("未知命令: {}，可用命令: add / remove / list", other).into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,320 @@
+// ========================================
+// 账户开通费用估算工具
+// 💰 批量开通功德账户前估算需要准备多少SOL：每个账户的租金、签名费，以及按指定单价计算的优先费
+// 只读查询，不发送任何交易
+// ========================================
+
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use solana_system_interface::MAX_PERMITTED_DATA_LENGTH;
+use std::fs;
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+use config::initialize_program_config;
+
+// 引用演示计划模块（签名费和优先费估算）
+use gong_de_increase::client::plan;
+use plan::{priority_fee, CREATE_ACCOUNT_COMPUTE_UNITS, LAMPORTS_PER_SIGNATURE};
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{arg_value, fetch_gongde_accounts, format_sol_balance, partition_users, Throttle};
+use gong_de_increase::utils::{
+    derive_gongde_account_address,
+    gongde_account_size,
+    shared_ledger_size,
+    GONGDE_LAYOUT_VERSION,
+    SHARED_LEDGER_ENTRY_SIZE,
+    SHARED_LEDGER_HEADER_SIZE,
+};
+
+/// 未指定 --priority-fee 时使用的优先费单价（每个计算单元的 micro-lamports）
+pub const DEFAULT_PRIORITY_FEE_PRICE: u64 = 10_000;
+
+/// 批量开通的费用估算，每个账户由用户自己签名一笔交易创建并初始化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostEstimate {
+    /// 需要开通的账户数量
+    pub accounts: u64,
+    /// 账户布局版本
+    pub layout_version: u8,
+    /// 单个账户的数据大小
+    pub account_size: usize,
+    /// 单个账户的免租金最低余额
+    pub rent_per_account: u64,
+    /// 全部账户的租金
+    pub total_rent: u64,
+    /// 全部交易的签名费
+    pub signature_fees: u64,
+    /// 优先费单价（每个计算单元的 micro-lamports）
+    pub priority_fee_price: u64,
+    /// 全部交易的优先费
+    pub priority_fees: u64,
+    /// 改用共享账本时全部用户的租金，超出单个账户的大小上限时为None
+    pub shared_ledger_rent: Option<u64>,
+}
+
+impl CostEstimate {
+    /// 不加优先费的总费用
+    pub fn total_without_priority(&self) -> u64 {
+        self.total_rent.saturating_add(self.signature_fees)
+    }
+
+    /// 加上优先费的总费用
+    pub fn total_with_priority(&self) -> u64 {
+        self.total_without_priority().saturating_add(self.priority_fees)
+    }
+
+    /// 打印费用明细
+    pub fn print(&self) {
+        info!("\n💰 === 开通费用估算 ===");
+        info!("👥 账户数量: {}", self.accounts);
+        info!("📐 账户布局: 版本 {}，{} 字节", self.layout_version, self.account_size);
+        info!("🏠 单个账户租金: {}", format_sol_balance(self.rent_per_account));
+        info!("🏠 租金合计: {}", format_sol_balance(self.total_rent));
+        info!("✍️  签名费合计: {}", format_sol_balance(self.signature_fees));
+        info!("🚀 优先费合计: {}（单价 {} micro-lamports/CU）", format_sol_balance(self.priority_fees), self.priority_fee_price);
+        info!("💸 总计（不含优先费）: {}", format_sol_balance(self.total_without_priority()));
+        info!("💸 总计（含优先费）: {}", format_sol_balance(self.total_with_priority()));
+        info!("ℹ️  租金在关闭账户时全额退还，签名费和优先费不退还");
+        match self.shared_ledger_rent {
+            Some(rent) => info!(
+                "📒 改用共享账本: 租金合计 {}（每人 {} 字节，没有独立账户的开销）",
+                format_sol_balance(rent),
+                SHARED_LEDGER_ENTRY_SIZE
+            ),
+            None => info!("📒 用户数量超出单个共享账本的容量，不能改用共享账本"),
+        }
+    }
+}
+
+/// 共享账本容纳指定数量用户所需的大小
+///
+/// # 返回
+/// * `Option<usize>` - 超出单个账户的大小上限（10MB）时返回None
+pub fn shared_ledger_size_for(accounts: u64) -> Option<usize> {
+    let max_entries = (MAX_PERMITTED_DATA_LENGTH as usize - SHARED_LEDGER_HEADER_SIZE) / SHARED_LEDGER_ENTRY_SIZE;
+    usize::try_from(accounts).ok().filter(|entries| *entries <= max_entries).map(shared_ledger_size)
+}
+
+/// 估算批量开通功德账户的费用
+///
+/// # 参数
+/// * `accounts` - 需要开通的账户数量
+/// * `layout_version` - 账户布局版本，决定账户大小
+/// * `minimum_balance` - 按数据大小计算免租金最低余额，通常来自RPC或 `Rent::minimum_balance`
+/// * `priority_fee_price` - 优先费单价（每个计算单元的 micro-lamports）
+///
+/// # 返回
+/// * `Result<CostEstimate, String>` - 费用估算，未知布局版本时返回错误
+pub fn estimate_onboarding_cost(
+    accounts: u64,
+    layout_version: u8,
+    minimum_balance: impl Fn(usize) -> u64,
+    priority_fee_price: u64,
+) -> Result<CostEstimate, String> {
+    let account_size = gongde_account_size(layout_version)
+        .ok_or_else(|| format!("未知的账户布局版本: {}", layout_version))?;
+    let rent_per_account = minimum_balance(account_size);
+    Ok(CostEstimate {
+        accounts,
+        layout_version,
+        account_size,
+        rent_per_account,
+        total_rent: rent_per_account.saturating_mul(accounts),
+        signature_fees: LAMPORTS_PER_SIGNATURE.saturating_mul(accounts),
+        priority_fee_price,
+        priority_fees: priority_fee(CREATE_ACCOUNT_COMPUTE_UNITS, priority_fee_price).saturating_mul(accounts),
+        shared_ledger_rent: shared_ledger_size_for(accounts).map(&minimum_balance),
+    })
+}
+
+/// 解析用户列表文件：每行一个公钥，忽略空行和 # 开头的注释
+///
+/// # 返回
+/// * `Result<Vec<Pubkey>, String>` - 按文件顺序排列的用户，去掉重复项
+pub fn parse_users_file(content: &str) -> Result<Vec<Pubkey>, String> {
+    let mut users = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let user: Pubkey = line.parse().map_err(|_| format!("第 {} 行不是有效的公钥: {}", index + 1, line))?;
+        if !users.contains(&user) {
+            users.push(user);
+        }
+    }
+    Ok(users)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 功德账户开通费用估算 ===");
+
+    let args: Vec<String> = std::env::args().collect();
+    let layout_version = match arg_value(&args, "--layout") {
+        Some(value) => value.parse()?,
+        None => GONGDE_LAYOUT_VERSION,
+    };
+    let priority_fee_price = match arg_value(&args, "--priority-fee") {
+        Some(value) => value.parse()?,
+        None => DEFAULT_PRIORITY_FEE_PRICE,
+    };
+    let users_file = arg_value(&args, "--users-file");
+    let account_count = arg_value(&args, "--accounts").map(str::parse::<u64>).transpose()?;
+    // --offline 使用默认租金参数，不连接网络
+    let offline = args.iter().any(|arg| arg == "--offline");
+
+    let estimate = if offline {
+        if users_file.is_some() {
+            return Err("--users-file 需要查询链上账户，不能和 --offline 一起使用".into());
+        }
+        let accounts = account_count.ok_or("请用 --accounts 指定要开通的账户数量")?;
+        estimate_onboarding_cost(accounts, layout_version, |size| Rent::default().minimum_balance(size), priority_fee_price)?
+    } else {
+        let config = initialize_program_config()?;
+        info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+        let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+        let accounts = match users_file {
+            Some(path) => {
+                let users = parse_users_file(&fs::read_to_string(path)?)?;
+                let gongde_pubkeys = users
+                    .iter()
+                    .map(|user| derive_gongde_account_address(user, &config.program_id))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+                let results = fetch_gongde_accounts(&client, &gongde_pubkeys, &config.program_id, &mut Throttle::from_env())?;
+                let partition = partition_users(&users, results);
+                info!("📋 用户列表: {} 人，已有功德账户 {} 人，需要开通 {} 人", users.len(), partition.existing.len(), partition.new_users.len());
+                for (user, e) in &partition.blocked {
+                    warn!("⚠️  {} 无法开通: {}", user, e);
+                }
+                partition.new_users.len() as u64
+            }
+            None => account_count.ok_or("请用 --accounts 指定要开通的账户数量，或用 --users-file 指定用户列表")?,
+        };
+
+        let size = gongde_account_size(layout_version).ok_or_else(|| format!("未知的账户布局版本: {}", layout_version))?;
+        let rent_per_account = client.get_minimum_balance_for_rent_exemption(size)?;
+        // 共享账本的大小与独立账户不同，单独查询一次
+        let shared_size = shared_ledger_size_for(accounts);
+        let shared_rent = match shared_size {
+            Some(size) => Some(client.get_minimum_balance_for_rent_exemption(size)?),
+            None => None,
+        };
+        let minimum_balance = |query: usize| if Some(query) == shared_size { shared_rent.unwrap_or(0) } else { rent_per_account };
+        estimate_onboarding_cost(accounts, layout_version, minimum_balance, priority_fee_price)?
+    };
+
+    estimate.print();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gong_de_increase::utils::{
+        GONGDE_ACCOUNT_SIZE,
+        GONGDE_EMOJI_OFFSET,
+        GONGDE_LAST_ACTIVE_OFFSET,
+        GONGDE_LAST_INCREMENT_SLOT_OFFSET,
+        GONGDE_LIFETIME_OFFSET,
+        GONGDE_MILESTONES_OFFSET,
+        GONGDE_STAKED_OFFSET,
+        GONGDE_VALUE_SIZE,
+    };
+
+    fn default_rent(size: usize) -> u64 {
+        Rent::default().minimum_balance(size)
+    }
+
+    #[test]
+    fn test_estimate_for_layout_versions() {
+        let layouts = [
+            (1, GONGDE_VALUE_SIZE),
+            (2, GONGDE_LIFETIME_OFFSET),
+            (3, GONGDE_MILESTONES_OFFSET),
+            (4, GONGDE_LAST_ACTIVE_OFFSET),
+            (5, GONGDE_STAKED_OFFSET),
+            (6, GONGDE_LAST_INCREMENT_SLOT_OFFSET),
+            (7, GONGDE_EMOJI_OFFSET),
+            (8, GONGDE_ACCOUNT_SIZE),
+        ];
+        for (layout_version, account_size) in layouts {
+            let estimate = estimate_onboarding_cost(500, layout_version, default_rent, 0).unwrap();
+            assert_eq!(estimate.account_size, account_size);
+            assert_eq!(estimate.rent_per_account, default_rent(account_size));
+            assert_eq!(estimate.total_rent, 500 * default_rent(account_size));
+            assert_eq!(estimate.signature_fees, 500 * LAMPORTS_PER_SIGNATURE);
+            assert_eq!(estimate.priority_fees, 0);
+            assert_eq!(estimate.total_with_priority(), estimate.total_without_priority());
+        }
+
+        // 新布局更大，租金更高
+        let legacy = estimate_onboarding_cost(1, 1, default_rent, 0).unwrap();
+        let current = estimate_onboarding_cost(1, GONGDE_LAYOUT_VERSION, default_rent, 0).unwrap();
+        assert!(current.rent_per_account > legacy.rent_per_account);
+
+        assert!(estimate_onboarding_cost(1, 0, default_rent, 0).is_err());
+        assert!(estimate_onboarding_cost(1, GONGDE_LAYOUT_VERSION + 1, default_rent, 0).is_err());
+    }
+
+    #[test]
+    fn test_estimate_priority_fee_levels() {
+        for price in [0, 1, DEFAULT_PRIORITY_FEE_PRICE, 1_000_000] {
+            let estimate = estimate_onboarding_cost(500, GONGDE_LAYOUT_VERSION, default_rent, price).unwrap();
+            let per_transaction = priority_fee(CREATE_ACCOUNT_COMPUTE_UNITS, price);
+            assert_eq!(estimate.priority_fees, 500 * per_transaction, "单价 {}", price);
+            assert_eq!(estimate.total_with_priority(), estimate.total_without_priority() + 500 * per_transaction);
+        }
+
+        // 5000 CU × 10000 micro-lamports = 50 lamports
+        let estimate = estimate_onboarding_cost(2, GONGDE_LAYOUT_VERSION, |_| 1_000, DEFAULT_PRIORITY_FEE_PRICE).unwrap();
+        assert_eq!(estimate.priority_fees, 100);
+        assert_eq!(estimate.total_with_priority(), 2 * 1_000 + 2 * LAMPORTS_PER_SIGNATURE + 100);
+    }
+
+    #[test]
+    fn test_estimate_zero_accounts() {
+        let estimate = estimate_onboarding_cost(0, GONGDE_LAYOUT_VERSION, default_rent, DEFAULT_PRIORITY_FEE_PRICE).unwrap();
+        assert_eq!(estimate.total_with_priority(), 0);
+    }
+
+    #[test]
+    fn test_shared_ledger_is_cheaper() {
+        let estimate = estimate_onboarding_cost(1_000, GONGDE_LAYOUT_VERSION, default_rent, 0).unwrap();
+        assert_eq!(estimate.shared_ledger_rent, Some(default_rent(shared_ledger_size(1_000))));
+        assert!(estimate.shared_ledger_rent.unwrap() < estimate.total_rent);
+
+        // 单个账户最多10MB
+        let max_entries = (MAX_PERMITTED_DATA_LENGTH as usize - SHARED_LEDGER_HEADER_SIZE) / SHARED_LEDGER_ENTRY_SIZE;
+        assert!(shared_ledger_size_for(max_entries as u64).is_some());
+        assert_eq!(shared_ledger_size_for(max_entries as u64 + 1), None);
+        assert_eq!(estimate_onboarding_cost(u64::MAX, GONGDE_LAYOUT_VERSION, default_rent, 0).unwrap().shared_ledger_rent, None);
+    }
+
+    #[test]
+    fn test_parse_users_file() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let content = format!("# 社区成员\n{}\n\n  {}  \n{}\n", a, b, a);
+        assert_eq!(parse_users_file(&content), Ok(vec![a, b]));
+
+        let err = parse_users_file(&format!("{}\nnot-a-pubkey\n", a)).unwrap_err();
+        assert!(err.contains("第 2 行"), "{}", err);
+    }
+}
@@ -0,0 +1,157 @@
+// ========================================
+// 定时自动增加功德
+// ⏰ 测试累计收益、连续打卡等功能时使用：按固定间隔给指定的功德账户增加功德，
+// 每次的结果都输出日志；失败时指数退避重试，RPC限流时按节点提示等待，连续失败太多次后退出
+// ========================================
+
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use std::time::Instant;
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::{initialize_program_config, ProgramConfig};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用定时调度模块
+use gong_de_increase::client::daemon;
+use daemon::{DaemonOptions, DaemonSchedule, RetryDecision};
+
+// 引用工作量证明模块，开启后自动计算 nonce
+use gong_de_increase::client::pow;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{
+    is_rate_limited,
+    query_gongde_account_at,
+    retry_after_hint,
+    send_and_confirm_with_subscription,
+    ProgramGuard,
+};
+use gong_de_increase::utils::{
+    derive_gongde_account_address,
+    derive_global_gongde_pda_address,
+    increment_merit,
+    read_gongde_value,
+};
+
+/// 一次增加的结果
+enum Attempt {
+    /// 交易已确认，功德从 before 增加到 after
+    Incremented { signature: Signature, before: u32, after: u32 },
+    /// 功德已经圆满，不需要再增加
+    Full,
+}
+
+/// 读取当前功德，没有圆满时发送一笔增加功德的交易
+// 和 client 模块一样直接返回 ClientError，不装箱
+#[allow(clippy::result_large_err)]
+fn increment_once(
+    client: &MultiRpcClient,
+    config: &ProgramConfig,
+    gongde_pubkey: &Pubkey,
+    global_pubkey: &Pubkey,
+) -> ClientResult<Attempt> {
+    let account = client.get_account(gongde_pubkey)?;
+    let before = read_gongde_value(&account.data)
+        .map_err(|e| ClientErrorKind::Custom(format!("读取功德值失败: {:?}", e)))?;
+    let Some(after) = increment_merit(before, 1) else {
+        return Ok(Attempt::Full);
+    };
+
+    // 每次都重新查询全局配置：运行期间创作者可能开启或调整工作量证明
+    let increment = pow::increment_instruction(&**client, &config.program_id, gongde_pubkey, &config.keypair.pubkey(), global_pubkey)?;
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let mut transaction = Transaction::new_with_payer(&[increment], Some(&config.keypair.pubkey()));
+    transaction.sign(&[&config.keypair], recent_blockhash);
+    let (signature, _method) = send_and_confirm_with_subscription(client, &transaction)?;
+    Ok(Attempt::Incremented { signature, before, after })
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 定时自动增加功德 ===");
+
+    // [--interval <秒>] 两次增加的间隔，默认60秒；[--max-iterations <次数>] 成功这么多次后退出，默认一直运行
+    let args: Vec<String> = std::env::args().collect();
+    let options = DaemonOptions::from_args(&args)?;
+
+    let config = initialize_program_config()?;
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    // 发送交易前确认程序ID指向的是本合约，--force 跳过检查
+    let force = args.iter().any(|arg| arg == "--force");
+    ProgramGuard::new(force).ensure(&*client, &config.program_id, &config.keypair.pubkey())?;
+
+    // --address 指定别人的公开功德箱，否则使用自己的功德账户
+    let gongde_pubkey = match args.iter().position(|arg| arg == "--address") {
+        Some(index) => {
+            let address = args.get(index + 1).ok_or("--address 需要指定功德账户地址")?;
+            address.parse::<Pubkey>().map_err(|e| format!("功德账户地址无效: {}", e))?
+        }
+        None => derive_gongde_account_address(&config.keypair.pubkey(), &config.program_id)
+            .map_err(|e| format!("生成账户地址失败: {:?}", e))?,
+    };
+    // 启动时确认账户存在且归本程序所有，运行中的错误再交给退避重试
+    query_gongde_account_at(&*client, &gongde_pubkey, &config.program_id)?;
+    let (global_pubkey, _bump) = derive_global_gongde_pda_address(&config.program_id)
+        .map_err(|e| format!("生成全局PDA地址失败: {:?}", e))?;
+
+    match options.max_iterations {
+        Some(max) => info!("⏰ 每 {} 秒给 {} 增加一次功德，共 {} 次", options.interval.as_secs(), gongde_pubkey, max),
+        None => info!("⏰ 每 {} 秒给 {} 增加一次功德，按 Ctrl+C 停止", options.interval.as_secs(), gongde_pubkey),
+    }
+
+    let mut schedule = DaemonSchedule::new(options, Instant::now());
+    while !schedule.is_finished() {
+        std::thread::sleep(schedule.wait(Instant::now()));
+
+        match increment_once(&client, &config, &gongde_pubkey, &global_pubkey) {
+            Ok(Attempt::Incremented { signature, before, after }) => {
+                schedule.record_success(Instant::now());
+                info!(
+                    signature = %signature,
+                    before,
+                    after,
+                    "✅ 第 {} 次增加功德: {} → {}",
+                    schedule.completed(),
+                    before,
+                    after
+                );
+            }
+            Ok(Attempt::Full) => {
+                info!("🎉 功德圆满，不再增加");
+                break;
+            }
+            Err(err) => {
+                let hint = if is_rate_limited(&err) { retry_after_hint(&err) } else { None };
+                match schedule.record_failure(Instant::now(), hint) {
+                    RetryDecision::RetryAfter(delay) => {
+                        warn!("⚠️  增加功德失败，{:.1}秒后重试: {}", delay.as_secs_f64(), err);
+                    }
+                    RetryDecision::GiveUp => {
+                        return Err(format!("连续 {} 次增加功德失败，退出: {}", daemon::MAX_CONSECUTIVE_FAILURES, err).into());
+                    }
+                }
+            }
+        }
+    }
+
+    info!("🏁 共增加 {} 次功德", schedule.completed());
+    Ok(())
+}
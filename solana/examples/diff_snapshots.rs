@@ -0,0 +1,67 @@
+// ========================================
+// 功德快照对比工具
+// 📊 比较 leaderboard --snapshot 生成的两个快照文件，输出新建和关闭的账户、每个账户的功德变化、
+// 功德总变化和涨幅榜，适合写每周的社区报告；只读本地文件，不连接网络
+// ========================================
+
+use tracing::{info, warn};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用快照模块
+use gong_de_increase::client::snapshot;
+use snapshot::{diff_snapshots, Snapshot};
+
+// 引用工具函数模块
+use gong_de_increase::client::utils::arg_value;
+
+/// 默认显示的涨幅榜名次数
+const DEFAULT_TOP: usize = 10;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+
+    // <旧快照> <新快照> [--top <名次数>] [--format table|json|csv]
+    let args: Vec<String> = std::env::args().collect();
+    let top = match arg_value(&args, "--top") {
+        Some(value) => value.parse::<usize>().map_err(|e| format!("名次数无效: {}", e))?,
+        None => DEFAULT_TOP,
+    };
+    let format = arg_value(&args, "--format").unwrap_or("table");
+    let files: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, arg)| !arg.starts_with("--") && !matches!(args[index - 1].as_str(), "--top" | "--format"))
+        .map(|(_, arg)| arg)
+        .collect();
+    let [before_path, after_path] = files[..] else {
+        return Err("用法: diff-snapshots <旧快照> <新快照> [--top <名次数>] [--format table|json|csv]".into());
+    };
+
+    let before = Snapshot::read(before_path)?;
+    let after = Snapshot::read(after_path)?;
+    if before.program_id != after.program_id {
+        warn!("⚠️  两个快照来自不同的程序: {} / {}", before.program_id, after.program_id);
+    }
+    if after.taken_at < before.taken_at {
+        warn!("⚠️  新快照 {} 比旧快照 {} 拍得还早，确认参数顺序", after_path, before_path);
+    }
+    info!("📸 {}（{} 个账户）→ {}（{} 个账户）", before_path, before.accounts.len(), after_path, after.accounts.len());
+
+    let diff = diff_snapshots(&before.accounts, &after.accounts);
+    let output = match format {
+        "table" => diff.render(top),
+        "json" => serde_json::to_string_pretty(&diff.to_json(top))?,
+        "csv" => diff.to_csv(),
+        other => return Err(format!("未知的输出格式: {}，可选值: table, json, csv", other).into()),
+    };
+    // 终端中逐行写进日志；输出被管道或重定向时只把报告本身写到stdout，方便直接保存
+    for line in output.lines() {
+        info!("{}", line);
+    }
+    logging::emit_result(output.trim_end());
+    Ok(())
+}
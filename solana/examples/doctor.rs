@@ -0,0 +1,515 @@
+// ========================================
+// 环境自检工具
+// 🩺 新手第一次运行示例时常常同时遇到好几个不相关的错误（没有CLI配置、没有部署密钥、钱包没钱、连错集群）
+// doctor 逐项检查并给出修复建议，有关键项失败时以非0退出码结束
+// ========================================
+
+// 和 client 模块一样，节点查询直接返回 ClientError，不装箱
+#![allow(clippy::result_large_err)]
+
+use solana_client::{client_error::Result as ClientResult, client_error::ClientError, rpc_client::RpcClient};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Signer,
+};
+use std::fmt;
+use tracing::{error, info};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::{
+    find_program_id,
+    find_solana_cli_config_path,
+    load_keypair_from_file,
+    read_solana_cli_config,
+    ConfigError,
+    PROGRAM_KEYPAIR_PATHS,
+};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::is_failover_error;
+
+use gong_de_increase::utils::{derive_global_gongde_pda_address, derive_gongde_account_address};
+
+/// 余额低于该值时提示领空投，够支付几十笔交易和一个账户的租金
+pub const MIN_DOCTOR_BALANCE: u64 = LAMPORTS_PER_SOL / 100;
+
+/// doctor 需要的节点能力，RpcClient实现它，测试中用模拟节点代替
+pub trait ClusterProbe {
+    /// 节点是否健康
+    fn health(&self) -> ClientResult<()>;
+    /// 节点的solana-core版本
+    fn version(&self) -> ClientResult<String>;
+    /// 查询余额
+    fn balance(&self, pubkey: &Pubkey) -> ClientResult<u64>;
+    /// 查询账户，不存在时返回None
+    fn account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>>;
+}
+
+impl ClusterProbe for RpcClient {
+    fn health(&self) -> ClientResult<()> {
+        self.get_health()
+    }
+
+    fn version(&self) -> ClientResult<String> {
+        Ok(self.get_version()?.solana_core)
+    }
+
+    fn balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        self.get_balance(pubkey)
+    }
+
+    fn account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        Ok(self.get_account_with_commitment(pubkey, self.commitment())?.value)
+    }
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// 通过
+    Pass,
+    /// 有问题但不影响运行示例
+    Warn,
+    /// 失败
+    Fail,
+    /// 依赖的前置检查失败，未执行
+    Skip,
+}
+
+/// 单项检查
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// 检查项名称
+    pub name: &'static str,
+    /// 关键项失败时 doctor 以非0退出码结束
+    pub critical: bool,
+    pub status: CheckStatus,
+    /// 检查到的内容或错误信息
+    pub detail: String,
+    /// 修复建议
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, critical: bool, detail: impl Into<String>) -> Self {
+        Self { name, critical, status: CheckStatus::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn fail(name: &'static str, critical: bool, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, critical, status: CheckStatus::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, critical: false, status: CheckStatus::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn skip(name: &'static str, critical: bool, reason: &str) -> Self {
+        Self { name, critical, status: CheckStatus::Skip, detail: format!("跳过：{}", reason), fix: None }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let icon = match self.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️ ",
+            CheckStatus::Fail => "❌",
+            CheckStatus::Skip => "⏭️ ",
+        };
+        write!(f, "{} {}: {}", icon, self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            write!(f, "\n   💡 {}", fix)?;
+        }
+        Ok(())
+    }
+}
+
+/// 全部检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// 没有关键项失败
+    pub fn is_healthy(&self) -> bool {
+        !self.checks.iter().any(|check| check.critical && check.status == CheckStatus::Fail)
+    }
+
+    /// 进程退出码，有关键项失败时为1
+    pub fn exit_code(&self) -> i32 {
+        if self.is_healthy() { 0 } else { 1 }
+    }
+
+    /// 按名称查找检查项
+    pub fn check(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// 打印检查清单
+    pub fn print(&self) {
+        info!("\n🩺 === 环境自检 ===");
+        for check in &self.checks {
+            info!("{}", check);
+        }
+        if self.is_healthy() {
+            info!("\n🎉 环境正常，可以运行示例");
+        } else {
+            error!("\n❌ 存在关键问题，请按提示修复后重试");
+        }
+    }
+}
+
+/// 需要检查的文件位置
+#[derive(Debug, Clone)]
+pub struct DoctorInputs {
+    /// CLI配置文件路径，None表示在默认位置都没有找到
+    pub cli_config_path: Option<String>,
+    /// 程序密钥对文件的候选路径
+    pub program_keypair_paths: Vec<String>,
+}
+
+impl DoctorInputs {
+    /// 使用示例程序默认的查找位置
+    pub fn discover() -> Self {
+        Self {
+            cli_config_path: find_solana_cli_config_path(),
+            program_keypair_paths: PROGRAM_KEYPAIR_PATHS.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+}
+
+/// 配置错误对应的修复建议
+pub fn config_error_fix(err: &ConfigError) -> &'static str {
+    match err {
+        ConfigError::CliConfigNotFound => "运行 solana config set --url localhost 生成CLI配置",
+        ConfigError::ProgramKeypairNotFound => "先运行 cargo build-sbf 生成程序密钥对，再用 solana program deploy 部署",
+        ConfigError::FileNotFound(_) => "运行 solana-keygen new 生成钱包，或在CLI配置中修改 keypair_path",
+        ConfigError::Io { .. } => "检查文件权限",
+        ConfigError::Parse { .. } | ConfigError::InvalidKeypair { .. } => "文件已损坏，请重新生成或从备份恢复",
+        ConfigError::HomeNotSet(_) => "设置HOME环境变量，或在配置中改用绝对路径",
+    }
+}
+
+/// RPC错误对应的修复建议
+pub fn client_error_fix(err: &ClientError) -> &'static str {
+    if is_failover_error(err) {
+        "确认节点地址正确且已启动（本地运行 solana-test-validator），或用 GONGDE_RPC_URLS 配置备用节点"
+    } else {
+        "检查CLI配置中的 json_rpc_url 是否指向可用的Solana节点"
+    }
+}
+
+/// 逐项检查环境，前置检查失败时后续依赖项标记为跳过
+///
+/// # 参数
+/// * `inputs` - 配置文件位置
+/// * `connect` - 根据RPC地址创建节点连接
+///
+/// # 返回
+/// * `DoctorReport` - 每一项的检查结果
+pub fn run_doctor<P: ClusterProbe>(inputs: &DoctorInputs, connect: impl FnOnce(&str) -> P) -> DoctorReport {
+    let mut checks = Vec::new();
+
+    // 1. CLI配置
+    let cli_config = match inputs.cli_config_path.as_deref().ok_or(ConfigError::CliConfigNotFound) {
+        Ok(path) => read_solana_cli_config(path).map(|config| (path, config)),
+        Err(e) => Err(e),
+    };
+    let cli_config = match cli_config {
+        Ok((path, config)) => {
+            checks.push(Check::pass("CLI配置", true, format!("{}（{}）", path, config.json_rpc_url)));
+            Some(config)
+        }
+        Err(e) => {
+            checks.push(Check::fail("CLI配置", true, e.to_string(), config_error_fix(&e)));
+            None
+        }
+    };
+
+    // 2. 钱包私钥
+    let wallet = match &cli_config {
+        Some(config) => match load_keypair_from_file(&config.keypair_path) {
+            Ok(keypair) => {
+                checks.push(Check::pass("钱包私钥", true, keypair.pubkey().to_string()));
+                Some(keypair.pubkey())
+            }
+            Err(e) => {
+                checks.push(Check::fail("钱包私钥", true, e.to_string(), config_error_fix(&e)));
+                None
+            }
+        },
+        None => {
+            checks.push(Check::skip("钱包私钥", true, "没有CLI配置"));
+            None
+        }
+    };
+
+    // 3. RPC节点
+    let probe = match &cli_config {
+        Some(config) => {
+            let probe = connect(&config.json_rpc_url);
+            match probe.health().and_then(|_| probe.version()) {
+                Ok(version) => {
+                    checks.push(Check::pass("RPC节点", true, format!("{} 健康，版本 {}", config.json_rpc_url, version)));
+                    Some(probe)
+                }
+                Err(e) => {
+                    checks.push(Check::fail("RPC节点", true, format!("{}: {}", config.json_rpc_url, e), client_error_fix(&e)));
+                    None
+                }
+            }
+        }
+        None => {
+            checks.push(Check::skip("RPC节点", true, "没有CLI配置"));
+            None
+        }
+    };
+
+    // 4. 钱包余额
+    match (&probe, wallet) {
+        (Some(probe), Some(wallet)) => match probe.balance(&wallet) {
+            Ok(balance) if balance >= MIN_DOCTOR_BALANCE => {
+                checks.push(Check::pass("钱包余额", true, format!("{} lamports", balance)));
+            }
+            Ok(balance) => checks.push(Check::fail(
+                "钱包余额",
+                true,
+                format!("{} lamports，低于 {} lamports", balance, MIN_DOCTOR_BALANCE),
+                "运行 solana airdrop 1 领取测试币",
+            )),
+            Err(e) => checks.push(Check::fail("钱包余额", true, e.to_string(), client_error_fix(&e))),
+        },
+        _ => checks.push(Check::skip("钱包余额", true, "钱包或RPC节点不可用")),
+    }
+
+    // 5. 程序ID
+    let program_id = match find_program_id(&inputs.program_keypair_paths) {
+        Ok((program_id, path)) => {
+            checks.push(Check::pass("程序ID", true, format!("{}（{}）", program_id, path)));
+            Some(program_id)
+        }
+        Err(e) => {
+            checks.push(Check::fail("程序ID", true, e.to_string(), config_error_fix(&e)));
+            None
+        }
+    };
+
+    // 6. 程序是否已部署到当前集群
+    match (&probe, program_id) {
+        (Some(probe), Some(program_id)) => match probe.account(&program_id) {
+            Ok(Some(account)) if account.executable => {
+                checks.push(Check::pass("程序部署", true, "程序账户存在且可执行"));
+            }
+            Ok(Some(_)) => checks.push(Check::fail(
+                "程序部署",
+                true,
+                "程序ID对应的账户不可执行",
+                "程序密钥对可能与集群上的账户不一致，请重新部署",
+            )),
+            Ok(None) => checks.push(Check::fail(
+                "程序部署",
+                true,
+                "当前集群上没有该程序",
+                "运行 solana program deploy target/deploy/gong_de_increase.so，或确认CLI配置连接的集群",
+            )),
+            Err(e) => checks.push(Check::fail("程序部署", true, e.to_string(), client_error_fix(&e))),
+        },
+        _ => checks.push(Check::skip("程序部署", true, "程序ID或RPC节点不可用")),
+    }
+
+    // 7. 账户地址派生
+    match program_id {
+        Some(program_id) => {
+            let global = derive_global_gongde_pda_address(&program_id);
+            let gongde = wallet.map(|wallet| derive_gongde_account_address(&wallet, &program_id));
+            match (global, gongde) {
+                (Ok((global, _bump)), Some(Ok(gongde))) => {
+                    checks.push(Check::pass("账户地址", false, format!("全局PDA {}，个人功德账户 {}", global, gongde)));
+                }
+                (Ok((global, _bump)), None) => checks.push(Check::pass("账户地址", false, format!("全局PDA {}", global))),
+                (Err(e), _) | (_, Some(Err(e))) => {
+                    checks.push(Check::warn("账户地址", format!("{:?}", e), "程序ID异常，请重新生成程序密钥对"))
+                }
+            }
+        }
+        None => checks.push(Check::skip("账户地址", false, "没有程序ID")),
+    }
+
+    DoctorReport { checks }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+
+    let report = run_doctor(&DoctorInputs::discover(), |url| {
+        RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed())
+    });
+    report.print();
+
+    if !report.is_healthy() {
+        std::process::exit(report.exit_code());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::client_error::ClientErrorKind;
+    use solana_sdk::signature::Keypair;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    /// 模拟节点
+    struct MockProbe {
+        healthy: bool,
+        balance: u64,
+        program: Option<Account>,
+    }
+
+    impl ClusterProbe for MockProbe {
+        fn health(&self) -> ClientResult<()> {
+            if self.healthy {
+                Ok(())
+            } else {
+                Err(ClientErrorKind::Io(io::Error::from(io::ErrorKind::ConnectionRefused)).into())
+            }
+        }
+
+        fn version(&self) -> ClientResult<String> {
+            Ok("2.2.0".to_string())
+        }
+
+        fn balance(&self, _pubkey: &Pubkey) -> ClientResult<u64> {
+            Ok(self.balance)
+        }
+
+        fn account(&self, _pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(self.program.clone())
+        }
+    }
+
+    fn healthy_probe() -> MockProbe {
+        let program = Account { executable: true, ..Account::default() };
+        MockProbe { healthy: true, balance: LAMPORTS_PER_SOL, program: Some(program) }
+    }
+
+    /// 在临时目录中生成CLI配置、钱包和程序密钥对
+    fn write_environment(name: &str) -> (PathBuf, DoctorInputs) {
+        let dir = std::env::temp_dir().join(format!("gongde-doctor-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let wallet_path = dir.join("id.json");
+        fs::write(&wallet_path, serde_json::to_string(&Keypair::new().to_bytes().to_vec()).unwrap()).unwrap();
+        let program_path = dir.join("program-keypair.json");
+        fs::write(&program_path, serde_json::to_string(&Keypair::new().to_bytes().to_vec()).unwrap()).unwrap();
+        let config_path = dir.join("config.yml");
+        fs::write(
+            &config_path,
+            format!(
+                "json_rpc_url: http://localhost:8899\nkeypair_path: {}\ncommitment: confirmed\n",
+                wallet_path.display()
+            ),
+        )
+        .unwrap();
+        let inputs = DoctorInputs {
+            cli_config_path: Some(config_path.to_string_lossy().to_string()),
+            program_keypair_paths: vec![program_path.to_string_lossy().to_string()],
+        };
+        (dir, inputs)
+    }
+
+    fn status(report: &DoctorReport, name: &str) -> CheckStatus {
+        report.check(name).unwrap_or_else(|| panic!("缺少检查项 {}", name)).status.clone()
+    }
+
+    #[test]
+    fn test_healthy_environment_passes() {
+        let (dir, inputs) = write_environment("healthy");
+        let report = run_doctor(&inputs, |url| {
+            assert_eq!(url, "http://localhost:8899");
+            healthy_probe()
+        });
+
+        assert!(report.checks.iter().all(|check| check.status == CheckStatus::Pass), "{:?}", report);
+        assert_eq!(report.exit_code(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_cli_config_skips_dependent_checks() {
+        let (dir, mut inputs) = write_environment("no-config");
+        inputs.cli_config_path = None;
+        let report = run_doctor(&inputs, |_| -> MockProbe { panic!("没有CLI配置时不应连接节点") });
+
+        assert_eq!(status(&report, "CLI配置"), CheckStatus::Fail);
+        assert_eq!(status(&report, "钱包私钥"), CheckStatus::Skip);
+        assert_eq!(status(&report, "RPC节点"), CheckStatus::Skip);
+        assert_eq!(status(&report, "程序ID"), CheckStatus::Pass);
+        assert_eq!(report.exit_code(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_wallet_file() {
+        let (dir, inputs) = write_environment("no-wallet");
+        fs::remove_file(dir.join("id.json")).unwrap();
+        let report = run_doctor(&inputs, |_| healthy_probe());
+
+        let wallet = report.check("钱包私钥").unwrap();
+        assert_eq!(wallet.status, CheckStatus::Fail);
+        assert!(wallet.fix.as_deref().unwrap().contains("solana-keygen"));
+        assert_eq!(status(&report, "钱包余额"), CheckStatus::Skip);
+        assert!(!report.is_healthy());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unreachable_rpc_and_low_balance() {
+        let (dir, inputs) = write_environment("rpc");
+        let report = run_doctor(&inputs, |_| MockProbe { healthy: false, ..healthy_probe() });
+        assert_eq!(status(&report, "RPC节点"), CheckStatus::Fail);
+        assert_eq!(status(&report, "程序部署"), CheckStatus::Skip);
+
+        let report = run_doctor(&inputs, |_| MockProbe { balance: MIN_DOCTOR_BALANCE - 1, ..healthy_probe() });
+        assert_eq!(status(&report, "钱包余额"), CheckStatus::Fail);
+        assert!(report.check("钱包余额").unwrap().fix.as_deref().unwrap().contains("airdrop"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_program_not_deployed_on_cluster() {
+        let (dir, inputs) = write_environment("not-deployed");
+        let report = run_doctor(&inputs, |_| MockProbe { program: None, ..healthy_probe() });
+        assert_eq!(status(&report, "程序部署"), CheckStatus::Fail);
+        assert_eq!(status(&report, "账户地址"), CheckStatus::Pass);
+
+        let report = run_doctor(&inputs, |_| MockProbe { program: Some(Account::default()), ..healthy_probe() });
+        assert_eq!(status(&report, "程序部署"), CheckStatus::Fail);
+        assert_eq!(report.exit_code(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_program_keypair() {
+        let (dir, mut inputs) = write_environment("no-program");
+        inputs.program_keypair_paths = vec![Path::new(&dir).join("missing.json").to_string_lossy().to_string()];
+        let report = run_doctor(&inputs, |_| healthy_probe());
+
+        let program = report.check("程序ID").unwrap();
+        assert_eq!(program.status, CheckStatus::Fail);
+        assert!(program.fix.as_deref().unwrap().contains("cargo build-sbf"));
+        assert_eq!(status(&report, "账户地址"), CheckStatus::Skip);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+// ========================================
+// 账户转储工具
+// 🩺 打印功德账户的全部字段、原始数据、所有者、余额和租金状态，并标出发现的不一致，
+// 输出可以直接附在问题反馈里
+// ========================================
+
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+};
+use tracing::info;
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_query_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{dump_account, get_gongde_account_address};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+
+    // [用户公钥 | --address <账户地址>]，都不指定时转储自己的功德账户
+    let args: Vec<String> = std::env::args().collect();
+    let address_index = args.iter().position(|arg| arg == "--address").map(|index| index + 1);
+    let user = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(index, arg)| !arg.starts_with('-') && Some(*index) != address_index)
+        .map(|(_, arg)| resolve_user(arg).map_err(|e| format!("用户无效: {}", e)))
+        .transpose()?;
+
+    let config = initialize_query_config()?;
+    let pubkey = match address_index {
+        Some(index) => {
+            let address = args.get(index).ok_or("--address 需要指定账户地址")?;
+            address.parse::<Pubkey>().map_err(|e| format!("账户地址无效: {}", e))?
+        }
+        None => {
+            let user = match user {
+                Some(user) => user,
+                None => config.default_user()?,
+            };
+            get_gongde_account_address(&user, &config.program_id)?
+        }
+    };
+
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    let dump = dump_account(&*client, &pubkey, &config.program_id)?.to_string();
+    // 终端中逐行写进日志；输出被管道或重定向时只把转储内容写到stdout，可以直接附在问题反馈里
+    for line in dump.lines() {
+        info!("{}", line);
+    }
+    logging::emit_result(dump.trim_end());
+    Ok(())
+}
@@ -0,0 +1,197 @@
+// ========================================
+// 背书工具
+// 🤝 给别人的功德账户背书，被背书人获得 1 + floor(log10(您的功德)) 点功德，
+// 每对用户只能背书一次，发送前先显示按您当前功德计算出的权重
+// --file 模式给名单中的所有人背书，适合活动组织者；已经背过书的自动跳过，可以重复运行
+// ========================================
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::fs;
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_program_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{
+    arg_value,
+    endorse_users,
+    fetch_gongde_accounts,
+    pending_endorsements,
+    send_transaction_and_watch,
+    verify_post_state,
+    ProgramGuard,
+    Throttle,
+};
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{derive_endorsement_address, derive_gongde_account_address, endorsement_weight, read_gongde_value};
+
+/// 读取功德账户中的功德值，账户不存在时返回None
+fn fetch_merit(client: &RpcClient, pubkey: &Pubkey) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+    match client.get_account_with_commitment(pubkey, client.commitment())?.value {
+        Some(account) => Ok(Some(read_gongde_value(&account.data).map_err(|e| format!("读取功德值失败: {:?}", e))?)),
+        None => Ok(None),
+    }
+}
+
+/// 要背书的对象
+enum Target<'a> {
+    /// 单个用户
+    User(Pubkey),
+    /// 背书名单文件
+    File(&'a str),
+}
+
+/// 解析背书名单：每行一个用户公钥或 @名字，空行和 `#` 开头的注释行被忽略
+///
+/// # 参数
+/// * `content` - 文件内容
+///
+/// # 返回
+/// * `Result<Vec<Pubkey>, String>` - 去重后的用户公钥，顺序与文件一致；任何一行无效都返回错误
+fn parse_endorsee_list(content: &str) -> Result<Vec<Pubkey>, String> {
+    let mut users = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let user = resolve_user(line).map_err(|e| format!("第 {} 行的用户无效: {}", index + 1, e))?;
+        if !users.contains(&user) {
+            users.push(user);
+        }
+    }
+    Ok(users)
+}
+
+/// 给名单中的所有人背书：没有功德账户的和已经背过书的跳过，其余按交易大小分组发送 EndorseMany 指令
+fn endorse_from_file(
+    client: &RpcClient,
+    endorser: &Keypair,
+    program_id: &Pubkey,
+    endorser_gongde: &Pubkey,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let users = parse_endorsee_list(&fs::read_to_string(path)?)?;
+    let mut throttle = Throttle::from_env();
+
+    // 🔍 不能给自己背书，没有功德账户的人也无法背书
+    let users: Vec<Pubkey> = users.into_iter().filter(|user| *user != endorser.pubkey()).collect();
+    let gongde_pubkeys = users
+        .iter()
+        .map(|user| derive_gongde_account_address(user, program_id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+    let results = fetch_gongde_accounts(client, &gongde_pubkeys, program_id, &mut throttle)?;
+    let mut endorsee_gongdes = Vec::new();
+    for ((user, gongde_pubkey), result) in users.iter().zip(&gongde_pubkeys).zip(results) {
+        match result {
+            Ok(_) => endorsee_gongdes.push(*gongde_pubkey),
+            Err(e) => warn!("⚠️  {} 无法背书: {}", user, e),
+        }
+    }
+    let pending = pending_endorsements(client, program_id, &endorser.pubkey(), &endorsee_gongdes, &mut throttle)?;
+    info!(
+        "📋 背书名单: {} 人，可以背书 {} 人，已经背过书 {} 人",
+        users.len(),
+        endorsee_gongdes.len(),
+        endorsee_gongdes.len() - pending.len()
+    );
+    if pending.is_empty() {
+        info!("✅ 名单中的所有人都已背书");
+        return Ok(());
+    }
+
+    let endorser_value = fetch_merit(client, endorser_gongde)?.ok_or("您还没有功德账户，请先运行 client 创建")?;
+    info!("⚖️  您当前的功德 {}，每人获得背书权重 {}", endorser_value, endorsement_weight(endorser_value));
+
+    let report = endorse_users(client, endorser, program_id, endorser_gongde, &pending)?;
+    info!("\n{}", report.render());
+    if !report.is_complete() {
+        warn!("❌ 部分背书未完成，重新运行即可重试");
+        std::process::exit(report.exit_code());
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 背书工具启动 ===");
+
+    // <被背书用户公钥> | --file <名单文件> [--force 跳过目标程序检查]
+    let args: Vec<String> = std::env::args().collect();
+    let force = args.iter().any(|arg| arg == "--force");
+    let target = match arg_value(&args, "--file") {
+        Some(path) => Target::File(path),
+        None => {
+            let endorsee = args.get(1).filter(|arg| !arg.starts_with("--")).ok_or("请指定被背书用户的公钥，或用 --file 指定背书名单")?;
+            Target::User(resolve_user(endorsee).map_err(|e| format!("被背书用户无效: {}", e))?)
+        }
+    };
+
+    let config = initialize_program_config()?;
+    let payer = config.keypair.pubkey();
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+    ProgramGuard::new(force).ensure(&*client, &config.program_id, &payer)?;
+
+    let endorser_gongde = derive_gongde_account_address(&payer, &config.program_id)
+        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+    let endorsee = match target {
+        Target::User(endorsee) => endorsee,
+        Target::File(path) => return endorse_from_file(&client, &config.keypair, &config.program_id, &endorser_gongde, path),
+    };
+    let endorsee_gongde = derive_gongde_account_address(&endorsee, &config.program_id)
+        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+    let (endorsement_pubkey, _bump) = derive_endorsement_address(&payer, &endorsee_gongde, &config.program_id);
+
+    // 发送前在本地检查，背书不成立时不白白支付手续费
+    if client.get_account_with_commitment(&endorsement_pubkey, client.commitment())?.value.is_some() {
+        warn!("❌ 您已经给这个用户背过书了");
+        return Ok(());
+    }
+    let endorser_value = fetch_merit(&client, &endorser_gongde)?.ok_or("您还没有功德账户，请先运行 client 创建")?;
+    let endorsee_value = fetch_merit(&client, &endorsee_gongde)?.ok_or("被背书用户还没有功德账户")?;
+
+    // ⚖️ 权重与链上使用同一个函数计算
+    let weight = endorsement_weight(endorser_value);
+    info!("⚖️  您当前的功德 {}，背书权重 {}（1 + floor(log10(功德))）", endorser_value, weight);
+    info!("📈 被背书用户的功德预计从 {} 增加到 {}", endorsee_value, endorsee_value.saturating_add(weight));
+
+    let endorse = instruction::endorse(&config.program_id, &endorsee_gongde, &payer, &endorser_gongde);
+    let mut transaction = Transaction::new_with_payer(&[endorse], Some(&payer));
+    transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+    let (signature, _) = send_transaction_and_watch(
+        &client,
+        &transaction,
+        &payer,
+        "背书",
+        &[(endorsee_gongde, "被背书的功德账户"), (endorsement_pubkey, "背书记录")],
+    )?;
+    // 您的功德在发送期间发生变化时权重会不同，以背书记录为准
+    let expected = [(endorsee_gongde, "被背书的功德账户", endorsee_value.saturating_add(weight))];
+    if let Err(e) = verify_post_state(&*client, &signature, &expected) {
+        warn!("⚠️  校验执行后状态失败: {}", e);
+    }
+    Ok(())
+}
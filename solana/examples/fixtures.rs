@@ -0,0 +1,203 @@
+// ========================================
+// 本地链夹具生成器
+// 🎯 用固定种子生成用户并执行脚本化操作，得到可复现的链上状态，供演示和工具调试使用
+// 背书记录无法删除，脚本执行过背书之后，重新生成需要先重置本地链
+// ========================================
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use solana_system_interface::instruction as system_instruction;
+use tracing::info;
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+use config::initialize_program_config;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::guard_destructive_operation;
+
+// 与集成测试共用同一份夹具脚本
+#[path = "../tests/common/fixtures.rs"]
+#[allow(dead_code)]
+mod fixtures;
+use fixtures::{fixture_users, replay_expected_state, FixtureOp, FIXTURE_SCRIPT};
+
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{
+    read_gongde_value,
+    derive_endorsement_address,
+    derive_gongde_account_address,
+    derive_global_gongde_pda_address,
+    GONGDE_ACCOUNT_SIZE,
+    GONGDE_ACCOUNT_SEED,
+};
+
+/// 每个夹具用户至少需要的余额
+const MIN_USER_BALANCE: u64 = LAMPORTS_PER_SOL / 10;
+
+/// 发送单条指令的交易，payer同时是唯一签名者
+fn send_instruction(
+    client: &RpcClient,
+    instruction: Instruction,
+    payer: &Keypair,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.send_and_confirm_transaction(&transaction)?;
+    Ok(())
+}
+
+/// 读取账户中的功德值，账户不存在或已关闭时返回None
+fn read_value(client: &RpcClient, pubkey: &Pubkey) -> Option<u32> {
+    client
+        .get_account(pubkey)
+        .ok()
+        .filter(|account| account.lamports > 0)
+        .and_then(|account| read_gongde_value(&account.data).ok())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 本地链夹具生成器启动 ===");
+
+    let config = initialize_program_config()?;
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    // 🚨 会关闭并重建夹具账户，主网上需要 --allow-mainnet 或输入集群名称确认
+    let allow_mainnet = std::env::args().any(|arg| arg == "--allow-mainnet");
+    guard_destructive_operation(&client, "关闭并重建夹具账户", allow_mainnet)?;
+    let program_id = config.program_id;
+    let (global_pubkey, _bump) = derive_global_gongde_pda_address(&program_id)
+        .map_err(|e| format!("生成全局PDA账户地址失败: {:?}", e))?;
+
+    let users = fixture_users();
+    let gongde_pubkeys = users
+        .iter()
+        .map(|user| derive_gongde_account_address(&user.pubkey(), &program_id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+
+    // 🤝 背书记录在关闭功德账户后仍然存在，再次背书会失败，提前说明而不是执行到一半才报错
+    for &(index, op) in FIXTURE_SCRIPT {
+        if let FixtureOp::Endorse(to) = op {
+            let (endorsement, _bump) = derive_endorsement_address(&users[index].pubkey(), &gongde_pubkeys[to], &program_id);
+            if client.get_account(&endorsement).is_ok() {
+                return Err(format!(
+                    "用户{} 给用户{} 的背书记录 {} 已存在，请先重置本地链（solana-test-validator --reset）再生成夹具",
+                    index, to, endorsement
+                )
+                .into());
+            }
+        }
+    }
+
+    // 🧹 准备阶段：给用户充值，并把上次运行留下的账户关闭后重新创建
+    info!("\n=== 步骤 1: 准备 {} 个夹具用户 ===", users.len());
+    let rent = client.get_minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE)?;
+    for (index, (user, gongde_pubkey)) in users.iter().zip(&gongde_pubkeys).enumerate() {
+        let balance = client.get_balance(&user.pubkey())?;
+        if balance < MIN_USER_BALANCE {
+            let transfer = system_instruction::transfer(
+                &config.keypair.pubkey(),
+                &user.pubkey(),
+                MIN_USER_BALANCE - balance,
+            );
+            send_instruction(&client, transfer, &config.keypair)?;
+        }
+
+        if read_value(&client, gongde_pubkey).is_some() {
+            send_instruction(&client, instruction::close(&program_id, gongde_pubkey, &user.pubkey()), user)?;
+        }
+
+        let create_instruction = system_instruction::create_account_with_seed(
+            &user.pubkey(),
+            gongde_pubkey,
+            &user.pubkey(),
+            GONGDE_ACCOUNT_SEED,
+            rent,
+            GONGDE_ACCOUNT_SIZE as u64,
+            &program_id,
+        );
+        let recent_blockhash = client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[create_instruction, instruction::initialize(&program_id, gongde_pubkey, &user.pubkey(), true)],
+            Some(&user.pubkey()),
+            &[user],
+            recent_blockhash,
+        );
+        client.send_and_confirm_transaction(&transaction)?;
+        info!("👤 用户{}: {} -> 功德账户 {}", index, user.pubkey(), gongde_pubkey);
+    }
+
+    // 🎬 执行脚本
+    info!("\n=== 步骤 2: 执行 {} 步脚本 ===", FIXTURE_SCRIPT.len());
+    let global_before = read_value(&client, &global_pubkey).unwrap_or(0);
+    for &(index, op) in FIXTURE_SCRIPT {
+        let user = &users[index];
+        let gongde_pubkey = &gongde_pubkeys[index];
+        let instruction = match op {
+            FixtureOp::Increment => instruction::increment(&program_id, gongde_pubkey, &user.pubkey(), &global_pubkey),
+            FixtureOp::IncrementBy(amount) => {
+                instruction::increment_by(&program_id, gongde_pubkey, &user.pubkey(), &global_pubkey, amount)
+            }
+            FixtureOp::Transfer { to, amount } => {
+                instruction::transfer_merit(&program_id, gongde_pubkey, &user.pubkey(), &gongde_pubkeys[to], amount)
+            }
+            FixtureOp::Endorse(to) => instruction::endorse(&program_id, &gongde_pubkeys[to], &user.pubkey(), gongde_pubkey),
+            // 检查点用0（1970年），总是早于当前时间
+            FixtureOp::RestoreTo(value) => instruction::restore_to(&program_id, gongde_pubkey, &user.pubkey(), value, 0),
+            FixtureOp::SetEmoji(emoji) => instruction::set_emoji(&program_id, gongde_pubkey, &user.pubkey(), emoji as u32),
+            FixtureOp::Archive => instruction::archive(&program_id, gongde_pubkey, &user.pubkey()),
+            FixtureOp::Close => instruction::close(&program_id, gongde_pubkey, &user.pubkey()),
+        };
+        send_instruction(&client, instruction, user)?;
+        info!("✅ 用户{} {:?}", index, op);
+    }
+
+    // 🔍 校验结果
+    info!("\n=== 步骤 3: 校验预期状态 ===");
+    let state = replay_expected_state();
+    let expected_global_total = state.global_total;
+    for (index, (gongde_pubkey, expected)) in gongde_pubkeys.iter().zip(&state.counts).enumerate() {
+        let actual = read_value(&client, gongde_pubkey);
+        if actual != *expected {
+            return Err(format!("用户{} 功德值不符: 预期 {:?}, 实际 {:?}", index, expected, actual).into());
+        }
+        info!("📊 用户{}: {:?}", index, actual);
+    }
+
+    // 全局账户在本地链上可能已有历史数据，因此只校验增量
+    let global_after = read_value(&client, &global_pubkey).unwrap_or(0);
+    if global_after.saturating_sub(global_before) != expected_global_total {
+        return Err(format!(
+            "全局功德增量不符: 预期 {}, 实际 {}",
+            expected_global_total,
+            global_after.saturating_sub(global_before)
+        ).into());
+    }
+    info!("🌍 全局功德: {} (+{})", global_after, expected_global_total);
+
+    info!("\n🎉 === 夹具生成完成，状态与预期一致 ===");
+    Ok(())
+}
@@ -0,0 +1,72 @@
+// ========================================
+// 功德变化跟随
+// 📡 持续输出本程序每笔交易造成的功德变化，给索引器、通知机器人等下游使用；
+// 每处理完一笔交易就保存进度，重启后先补齐中断期间的交易再继续实时订阅，同一笔交易可能输出两次，不会遗漏
+// ========================================
+
+use solana_sdk::commitment_config::CommitmentConfig;
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_query_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用功德变化跟随模块
+use gong_de_increase::client::follow;
+use follow::{cursor_path, follow, FollowCursor};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 功德变化跟随 ===");
+
+    // [--cursor <文件>] 进度文件，默认 ./.config/gongde/follow-cursor.json，也可以用 GONGDE_FOLLOW_CURSOR 指定
+    let args: Vec<String> = std::env::args().collect();
+    let path = match args.iter().position(|arg| arg == "--cursor") {
+        Some(index) => args.get(index + 1).ok_or("--cursor 需要指定进度文件路径")?.clone(),
+        None => cursor_path(),
+    };
+
+    // 跟随是只读操作，不需要私钥
+    let config = initialize_query_config()?;
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    let cursor = FollowCursor::read(&path)?;
+    match &cursor {
+        Some(cursor) => info!("⏮️  从进度 {}（slot {}）之后补齐", cursor.signature, cursor.slot),
+        None => info!("🆕 没有进度文件 {}，从现在开始跟随", path),
+    }
+    let (mut subscription, follower) = follow(&client, &config.program_id, cursor.as_ref())?;
+    info!("📡 已订阅程序 {} 的交易日志", config.program_id);
+
+    for record in follower {
+        for change in &record.changes {
+            info!(
+                signature = %record.signature,
+                slot = record.slot,
+                account = %change.account,
+                before = change.before,
+                after = change.after,
+                "🙏 {} 功德 {} → {}（slot {}）",
+                change.account,
+                change.before,
+                change.after,
+                record.slot
+            );
+        }
+        // 处理完再保存进度，中途退出时这笔交易下次还会输出
+        record.cursor().write(&path)?;
+    }
+
+    let _ = subscription.shutdown();
+    warn!("🔌 订阅已断开，重新运行会从保存的进度继续");
+    Err("交易日志订阅已断开".into())
+}
@@ -0,0 +1,284 @@
+// ========================================
+// IDL导出工具
+// 📜 把合约的指令ABI导出为Anchor兼容的IDL JSON，供TypeScript等其他语言的客户端生成代码
+// 指令、账户和参数全部来自 InstructionSpec，账户结构来自布局常量，修改合约后重新导出即可，不需要手工维护
+// ========================================
+
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::{fs, path::Path};
+use tracing::info;
+
+// 引用本地配置模块（只用到程序ID的查找）
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils::arg_value;
+
+use gong_de_increase::instruction::{AccountSpec, ArgSpec, InstructionSpec};
+use gong_de_increase::utils::{
+    GongDeInstruction,
+    MAX_MILESTONES,
+};
+
+/// IDL中的程序名称，与部署文件名一致
+pub const IDL_PROGRAM_NAME: &str = "gong_de_increase";
+
+/// 未指定 --out 时的输出路径（target目录不进版本库）
+pub const DEFAULT_IDL_PATH: &str = "./target/idl/gong_de_increase.json";
+
+/// 功德账户在IDL中的类型名称
+pub const GONGDE_ACCOUNT_TYPE: &str = "GongDeAccount";
+
+/// 里程碑条目在IDL中的类型名称
+pub const MILESTONE_TYPE: &str = "Milestone";
+
+/// 把 kebab-case 的指令名称转换为IDL习惯的 snake_case
+fn idl_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// 单个账户的IDL描述
+fn account_json(spec: &AccountSpec, optional: bool) -> Value {
+    let mut account = json!({
+        "name": idl_name(spec.name),
+        "docs": [spec.role],
+        "writable": spec.is_writable,
+        "signer": spec.is_signer,
+    });
+    if optional {
+        account["optional"] = json!(true);
+    }
+    if let Some(address) = spec.address {
+        account["address"] = json!(address.to_string());
+    }
+    account
+}
+
+/// 单个参数的IDL描述
+fn arg_json(arg: &ArgSpec) -> Value {
+    json!({ "name": arg.name, "type": arg.ty.idl_name() })
+}
+
+/// 单条指令的IDL描述，指令码就是1字节的判别符
+fn instruction_json(instruction: GongDeInstruction) -> Value {
+    let spec = InstructionSpec::of(instruction);
+    let accounts = spec
+        .accounts
+        .iter()
+        .map(|account| account_json(account, false))
+        .chain(spec.optional_accounts.iter().map(|account| account_json(account, true)))
+        .collect::<Vec<_>>();
+    json!({
+        "name": idl_name(instruction.name()),
+        "docs": [spec.summary()],
+        "discriminator": [instruction as u8],
+        "accounts": accounts,
+        "args": spec.args.iter().map(arg_json).collect::<Vec<_>>(),
+    })
+}
+
+/// 功德账户和里程碑条目的类型定义，字段顺序与链上字节布局一致
+fn account_types_json() -> Value {
+    json!([
+        {
+            "name": GONGDE_ACCOUNT_TYPE,
+            "type": {
+                "kind": "struct",
+                "fields": [
+                    { "name": "value", "type": "u32" },
+                    { "name": "flags", "type": "u8" },
+                    { "name": "lifetime_total", "type": "u64" },
+                    { "name": "milestones", "type": { "array": [{ "defined": { "name": MILESTONE_TYPE } }, MAX_MILESTONES] } },
+                    { "name": "last_active", "type": "i64" },
+                    { "name": "beneficiary", "type": "pubkey" },
+                    { "name": "timeout_days", "type": "u16" },
+                    { "name": "authority", "type": "pubkey" },
+                    { "name": "staked", "type": "u64" },
+                    { "name": "last_claim_slot", "type": "u64" },
+                    { "name": "stake_remainder", "type": "u64" },
+                    { "name": "last_increment_slot", "type": "u64" },
+                    { "name": "emoji", "type": "u32" },
+                ]
+            }
+        },
+        {
+            "name": MILESTONE_TYPE,
+            "type": {
+                "kind": "struct",
+                "fields": [
+                    { "name": "milestone", "type": "u64" },
+                    { "name": "reached_at", "type": "i64" },
+                ]
+            }
+        }
+    ])
+}
+
+/// 生成Anchor兼容（0.30格式）的IDL
+///
+/// # 参数
+/// * `program_id` - 程序地址，写入IDL的 address 字段
+///
+/// # 返回
+/// * `Value` - IDL JSON
+///
+/// 合约不使用Anchor的8字节判别符：指令的判别符是1字节指令码，功德账户没有判别符
+pub fn build_idl(program_id: &Pubkey) -> Value {
+    json!({
+        "address": program_id.to_string(),
+        "metadata": {
+            "name": IDL_PROGRAM_NAME,
+            "version": env!("CARGO_PKG_VERSION"),
+            "spec": "0.1.0",
+            "description": "功德+1",
+        },
+        "instructions": GongDeInstruction::ALL.iter().map(|instruction| instruction_json(*instruction)).collect::<Vec<_>>(),
+        "accounts": [{ "name": GONGDE_ACCOUNT_TYPE, "discriminator": [] }],
+        "types": account_types_json(),
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 导出程序IDL ===");
+
+    let args: Vec<String> = std::env::args().collect();
+    // 没有部署文件时可以用 --program-id 直接指定
+    let program_id = match arg_value(&args, "--program-id") {
+        Some(value) => value.parse()?,
+        None => config::find_program_id(&config::PROGRAM_KEYPAIR_PATHS)?.0,
+    };
+    let out = Path::new(arg_value(&args, "--out").unwrap_or(DEFAULT_IDL_PATH));
+
+    let idl = build_idl(&program_id);
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(out, serde_json::to_string_pretty(&idl)?)?;
+    info!("📜 程序ID: {}", program_id);
+    info!("📜 已导出 {} 条指令到 {}", GongDeInstruction::ALL.len(), out.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gong_de_increase::utils::{
+        GONGDE_ACCOUNT_SIZE,
+        GONGDE_AUTHORITY_OFFSET,
+        GONGDE_BENEFICIARY_OFFSET,
+        GONGDE_EMOJI_OFFSET,
+        GONGDE_FLAGS_OFFSET,
+        GONGDE_LAST_ACTIVE_OFFSET,
+        GONGDE_LAST_CLAIM_SLOT_OFFSET,
+        GONGDE_LAST_INCREMENT_SLOT_OFFSET,
+        GONGDE_LIFETIME_OFFSET,
+        GONGDE_MILESTONES_OFFSET,
+        GONGDE_STAKED_OFFSET,
+        GONGDE_STAKE_REMAINDER_OFFSET,
+        GONGDE_TIMEOUT_DAYS_OFFSET,
+        MILESTONE_ENTRY_SIZE,
+    };
+
+    fn parsed_idl() -> Value {
+        // 经过一次序列化再解析，检查的是实际写入文件的内容
+        let text = serde_json::to_string_pretty(&build_idl(&Pubkey::new_unique())).unwrap();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    fn type_size(ty: &Value, types: &[Value]) -> usize {
+        match ty {
+            Value::String(name) => match name.as_str() {
+                "u8" | "bool" => 1,
+                "u16" => 2,
+                "u32" => 4,
+                "u64" | "i64" => 8,
+                "pubkey" => 32,
+                other => panic!("未知类型 {}", other),
+            },
+            Value::Object(map) if map.contains_key("array") => {
+                let array = map["array"].as_array().unwrap();
+                type_size(&array[0], types) * array[1].as_u64().unwrap() as usize
+            }
+            Value::Object(map) if map.contains_key("defined") => {
+                let name = map["defined"]["name"].as_str().unwrap();
+                struct_size(types.iter().find(|ty| ty["name"] == name).unwrap(), types)
+            }
+            other => panic!("未知类型 {}", other),
+        }
+    }
+
+    fn struct_size(ty: &Value, types: &[Value]) -> usize {
+        ty["type"]["fields"].as_array().unwrap().iter().map(|field| type_size(&field["type"], types)).sum()
+    }
+
+    #[test]
+    fn test_idl_lists_every_instruction() {
+        let idl = parsed_idl();
+        let instructions = idl["instructions"].as_array().unwrap();
+        assert_eq!(instructions.len(), GongDeInstruction::ALL.len());
+
+        for instruction in GongDeInstruction::ALL {
+            let name = idl_name(instruction.name());
+            let entry = instructions.iter().find(|entry| entry["name"] == name.as_str()).unwrap_or_else(|| panic!("缺少指令 {}", name));
+            assert_eq!(entry["discriminator"], json!([instruction as u8]), "{}", name);
+
+            let spec = InstructionSpec::of(instruction);
+            let accounts = entry["accounts"].as_array().unwrap();
+            assert_eq!(accounts.len(), spec.accounts.len() + spec.optional_accounts.len(), "{}", name);
+            let args = entry["args"].as_array().unwrap();
+            let args_size: usize = args.iter().map(|arg| type_size(&arg["type"], &[])).sum();
+            assert_eq!(1 + args_size, spec.data_len(), "{}", name);
+        }
+
+        let increment = instructions.iter().find(|entry| entry["name"] == "increment").unwrap();
+        let system_program = increment["accounts"].as_array().unwrap().iter().find(|account| account["name"] == "system_program").unwrap();
+        assert_eq!(system_program["address"], json!(solana_system_interface::program::id().to_string()));
+        assert!(increment["accounts"].as_array().unwrap().iter().any(|account| account["optional"] == json!(true)));
+    }
+
+    #[test]
+    fn test_idl_account_matches_layout() {
+        let idl = parsed_idl();
+        let types = idl["types"].as_array().unwrap();
+        let account = types.iter().find(|ty| ty["name"] == GONGDE_ACCOUNT_TYPE).unwrap();
+        assert_eq!(struct_size(account, types), GONGDE_ACCOUNT_SIZE);
+
+        // 每个字段的偏移量与合约中的布局常量一致
+        let fields = account["type"]["fields"].as_array().unwrap();
+        let offsets = fields
+            .iter()
+            .scan(0, |offset, field| {
+                let start = *offset;
+                *offset += type_size(&field["type"], types);
+                Some(start)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            vec![
+                0,
+                GONGDE_FLAGS_OFFSET,
+                GONGDE_LIFETIME_OFFSET,
+                GONGDE_MILESTONES_OFFSET,
+                GONGDE_LAST_ACTIVE_OFFSET,
+                GONGDE_BENEFICIARY_OFFSET,
+                GONGDE_TIMEOUT_DAYS_OFFSET,
+                GONGDE_AUTHORITY_OFFSET,
+                GONGDE_STAKED_OFFSET,
+                GONGDE_LAST_CLAIM_SLOT_OFFSET,
+                GONGDE_STAKE_REMAINDER_OFFSET,
+                GONGDE_LAST_INCREMENT_SLOT_OFFSET,
+                GONGDE_EMOJI_OFFSET,
+            ]
+        );
+
+        let milestone = types.iter().find(|ty| ty["name"] == MILESTONE_TYPE).unwrap();
+        assert_eq!(struct_size(milestone, types), MILESTONE_ENTRY_SIZE);
+    }
+}
@@ -0,0 +1,101 @@
+// ========================================
+// 功德排行榜
+// 🏆 扫描本程序下的全部功德账户，按当前功德排名；已归档的账户默认不参与排名
+// ========================================
+
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_query_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用快照模块
+use gong_de_increase::client::snapshot;
+use snapshot::Snapshot;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{emoji_suffix, fetch_leaderboard, MeritDisplay, Throttle};
+use gong_de_increase::utils::{derive_global_gongde_pda_address, MeritLevel};
+
+/// 默认显示的名次数
+const DEFAULT_TOP: usize = 10;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 功德排行榜 ===");
+
+    // [--top <名次数>] 默认显示前10名
+    // [--include-archived] 已归档的账户也参与排名
+    // [--snapshot <文件>] 把全部账户（含已归档）的当前功德写成快照，用 diff-snapshots 比较两个快照
+    // [--rough] 只显示功德所在的等级区间，也可以设置 GONGDE_ROUGH=1，快照不受影响
+    let args: Vec<String> = std::env::args().collect();
+    let top = match args.iter().position(|arg| arg == "--top") {
+        Some(index) => args
+            .get(index + 1)
+            .ok_or("--top 需要指定名次数")?
+            .parse::<usize>()
+            .map_err(|e| format!("名次数无效: {}", e))?,
+        None => DEFAULT_TOP,
+    };
+    let include_archived = args.iter().any(|arg| arg == "--include-archived");
+    let snapshot_path = match args.iter().position(|arg| arg == "--snapshot") {
+        Some(index) => Some(args.get(index + 1).ok_or("--snapshot 需要指定快照文件路径")?),
+        None => None,
+    };
+    let merit_display = MeritDisplay::current();
+
+    // 排行榜是只读操作，不需要私钥
+    let config = initialize_query_config()?;
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    let (global_pubkey, _bump) = derive_global_gongde_pda_address(&config.program_id)
+        .map_err(|e| format!("生成全局PDA账户地址失败: {:?}", e))?;
+    // getProgramAccounts 是公共节点上最容易触发限流的请求，每种布局大小查询一次，经过节流器
+    // 快照记录全部账户，归档与否只影响排名显示
+    let entries = fetch_leaderboard(
+        &*client,
+        &config.program_id,
+        &global_pubkey,
+        include_archived || snapshot_path.is_some(),
+        &mut Throttle::from_env(),
+    )?;
+    if let Some(path) = snapshot_path {
+        let taken_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let snapshot = Snapshot::from_leaderboard(config.program_id, taken_at, &entries);
+        snapshot.write(path)?;
+        info!("📸 已写入快照: {}（{} 个账户）", path, snapshot.accounts.len());
+    }
+    let entries: Vec<_> = entries.into_iter().filter(|entry| include_archived || !entry.archived).collect();
+    if entries.is_empty() {
+        info!("🈳 还没有人增加功德");
+        return Ok(());
+    }
+
+    info!("\n🏆 === 前 {} 名（共 {} 个账户{}）===", top.min(entries.len()), entries.len(), if include_archived { "，含已归档" } else { "" });
+    for (rank, entry) in entries.iter().take(top).enumerate() {
+        let badge = if entry.archived { " 🗄️ 已归档" } else { "" };
+        info!(
+            "{:>3}. {}{}  功德 {}（累计 {}） {}{}",
+            rank + 1,
+            entry.pubkey,
+            emoji_suffix(entry.emoji),
+            merit_display.format(entry.value),
+            merit_display.format_total(entry.lifetime_total),
+            MeritLevel::from_value(entry.value),
+            badge
+        );
+    }
+    Ok(())
+}
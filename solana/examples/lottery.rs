@@ -0,0 +1,247 @@
+// ========================================
+// 功德彩票工具
+// 🎰 管理员创建一轮彩票，投注期间用功德买票，票款全部进入奖池；
+// 结束slot之后任何人都可以开奖，由结束slot的哈希决定中奖票号，奖池加给中奖者的功德
+// ========================================
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signer,
+    sysvar,
+    transaction::Transaction,
+};
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::initialize_program_config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{arg_value, parse_arg, send_transaction_and_watch, ProgramGuard};
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{
+    derive_gongde_account_address,
+    derive_lottery_address,
+    lottery_pending_refunds,
+    lottery_ticket_owner,
+    read_gongde_value,
+    slot_hash_at,
+    winning_ticket,
+    CreateLotteryArgs,
+    LotteryRound,
+    MAX_LOTTERY_REFUND_ACCOUNTS,
+    SLOT_HASHES_MAX_ENTRIES,
+};
+
+/// 描述轮次当前的状态，判断逻辑与链上一致
+///
+/// # 参数
+/// * `round` - 彩票轮次
+/// * `slot` - 集群当前slot
+pub fn describe_round(round: &LotteryRound, slot: u64) -> String {
+    if round.voided {
+        "🚫 已作废（结束slot的哈希过期），票款退还给投注者".to_string()
+    } else if round.settled {
+        if round.total_tickets == 0 {
+            "🔚 已结束（没有人投注）".to_string()
+        } else {
+            format!("🏆 已开奖，中奖票号 {}", round.winning_ticket)
+        }
+    } else if slot < round.start_slot {
+        format!("⏳ 未开始（还有 {} 个slot）", round.start_slot - slot)
+    } else if round.is_open(slot) {
+        format!("🎟️  投注中（还有 {} 个slot结束）", round.end_slot - slot)
+    } else if slot == round.end_slot {
+        "⏳ 已截止，等待结束slot的哈希".to_string()
+    } else {
+        "🎰 等待开奖".to_string()
+    }
+}
+
+/// 彩票轮次和原始账户数据
+type RoundAccount = (LotteryRound, Vec<u8>);
+
+/// 查询彩票轮次和账户数据（开奖时需要用数据查找中奖者）
+///
+/// # 返回
+/// * `Result<Option<RoundAccount>, Box<dyn std::error::Error>>` - 轮次不存在时返回None
+fn fetch_round(client: &RpcClient, lottery_pubkey: &Pubkey) -> Result<Option<RoundAccount>, Box<dyn std::error::Error>> {
+    match client.get_account_with_commitment(lottery_pubkey, client.commitment())?.value {
+        Some(account) => {
+            let round = LotteryRound::unpack(&account.data).map_err(|e| format!("解析彩票轮次失败: {:?}", e))?;
+            Ok(Some((round, account.data)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 功德彩票工具启动 ===");
+
+    // create --price <功德> [--start <slot数>] [--duration <slot数>]  从现在起若干slot后开始的一轮彩票，默认立即开始、持续9000个slot（约1小时）
+    // show [--admin <管理员公钥>]  查看轮次状态，默认查看自己创建的轮次
+    // enter --admin <管理员公钥> [--tickets <票数>]  用功德买彩票，默认1张
+    // settle --admin <管理员公钥>  开奖，在本地算出中奖者后发送；结束slot的哈希过期时作废本轮并给所有投注者退款
+    // --id <编号> 轮次编号，同一管理员可以有多轮，默认0
+    // --force 跳过目标程序检查
+    let args: Vec<String> = std::env::args().collect();
+    let command = args.get(1).map(String::as_str).unwrap_or("show");
+    let round_id: u64 = parse_arg(&args, "--id", 0)?;
+    let force = args.iter().any(|arg| arg == "--force");
+
+    let config = initialize_program_config()?;
+    let payer = config.keypair.pubkey();
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+
+    let admin = match arg_value(&args, "--admin") {
+        Some(admin) => resolve_user(admin).map_err(|e| format!("管理员无效: {}", e))?,
+        None if command == "enter" || command == "settle" => return Err("请用 --admin 指定轮次管理员".into()),
+        None => payer,
+    };
+    let (lottery_pubkey, _bump) = derive_lottery_address(&admin, round_id, &config.program_id);
+    info!("📝 彩票轮次地址: {}", lottery_pubkey);
+
+    match command {
+        "create" => {
+            ProgramGuard::new(force).ensure(&*client, &config.program_id, &payer)?;
+            let start_slot = client.get_slot()?.saturating_add(parse_arg(&args, "--start", 0)?);
+            let lottery_args = CreateLotteryArgs {
+                round_id,
+                start_slot,
+                end_slot: start_slot.saturating_add(parse_arg(&args, "--duration", 9_000)?),
+                ticket_price: parse_arg(&args, "--price", 0)?,
+            };
+            let create = instruction::create_lottery(&config.program_id, &payer, &lottery_args);
+            // 发送前在本地按链上规则检查参数，不合法时不白白支付手续费
+            instruction::validate_args(&create).map_err(|e| format!("{}（票价不能为0，持续时间必须大于0）", e))?;
+
+            info!(
+                "🎟️  每张票 {} 功德，投注slot [{}, {})",
+                lottery_args.ticket_price, lottery_args.start_slot, lottery_args.end_slot
+            );
+            let mut transaction = Transaction::new_with_payer(&[create], Some(&payer));
+            transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+            send_transaction_and_watch(&client, &transaction, &payer, "创建彩票", &[(lottery_pubkey, "彩票轮次")])?;
+            info!("📢 其他用户运行 `cargo run --features client --example lottery -- enter --admin {} --id {}` 即可投注", payer, round_id);
+            info!("⚠️  结束slot的哈希只保留约512个slot（几分钟），请在结束后及时运行 settle 开奖");
+        }
+        "show" => {
+            let Some((round, _data)) = fetch_round(&client, &lottery_pubkey)? else {
+                warn!("❌ 彩票轮次不存在");
+                return Ok(());
+            };
+            info!("👤 管理员: {}", round.admin);
+            info!("🕐 投注slot: [{}, {})", round.start_slot, round.end_slot);
+            info!("🎟️  票价: {} 功德，已售 {} 张（{} 笔投注）", round.ticket_price, round.total_tickets, round.entry_count);
+            info!("💰 奖池: {} 功德", round.jackpot());
+            info!("📊 状态: {}", describe_round(&round, client.get_slot()?));
+        }
+        "enter" => {
+            ProgramGuard::new(force).ensure(&*client, &config.program_id, &payer)?;
+            let (round, _data) = fetch_round(&client, &lottery_pubkey)?.ok_or("彩票轮次不存在")?;
+            let tickets: u16 = parse_arg(&args, "--tickets", 1)?;
+            if tickets == 0 {
+                return Err("票数必须大于0".into());
+            }
+            let slot = client.get_slot()?;
+            if !round.is_open(slot) {
+                warn!("❌ 当前不能投注: {}", describe_round(&round, slot));
+                return Ok(());
+            }
+
+            let gongde_pubkey = derive_gongde_account_address(&payer, &config.program_id)
+                .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+            let gongde_account = client
+                .get_account_with_commitment(&gongde_pubkey, client.commitment())?
+                .value
+                .ok_or("您还没有功德账户，请先运行 client 创建")?;
+            let value = read_gongde_value(&gongde_account.data).map_err(|e| format!("读取功德值失败: {:?}", e))?;
+            let cost = round.cost(tickets);
+            if u64::from(value) < cost {
+                warn!("❌ 功德不足，当前功德 {}，{} 张票需要 {}", value, tickets, cost);
+                return Ok(());
+            }
+
+            info!("💸 {} 张票花费 {} 功德，您的票号为 [{}, {})", tickets, cost, round.total_tickets, round.total_tickets + u32::from(tickets));
+            let enter = instruction::enter_lottery(&config.program_id, &gongde_pubkey, &payer, &lottery_pubkey, tickets);
+            let mut transaction = Transaction::new_with_payer(&[enter], Some(&payer));
+            transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+            send_transaction_and_watch(
+                &client,
+                &transaction,
+                &payer,
+                "买彩票",
+                &[(gongde_pubkey, "功德账户"), (lottery_pubkey, "彩票轮次")],
+            )?;
+        }
+        "settle" => {
+            let (round, data) = fetch_round(&client, &lottery_pubkey)?.ok_or("彩票轮次不存在")?;
+            let slot = client.get_slot()?;
+            if (round.settled && !round.voided) || slot <= round.end_slot {
+                warn!("❌ 当前不能开奖: {}", describe_round(&round, slot));
+                return Ok(());
+            }
+
+            // 🔍 与链上使用同样的函数，从 SlotHashes 中算出中奖者，开奖交易必须带上中奖的功德账户
+            let slot_hash = if round.voided || round.total_tickets == 0 {
+                None
+            } else {
+                let slot_hashes = client.get_account(&sysvar::slot_hashes::ID)?;
+                slot_hash_at(&slot_hashes.data, round.end_slot)
+            };
+            let settles = if round.total_tickets == 0 {
+                info!("🈳 没有人投注，开奖只会结束本轮");
+                vec![(instruction::settle_lottery(&config.program_id, &lottery_pubkey, None), None)]
+            } else if let Some(hash) = slot_hash {
+                let ticket = winning_ticket(&hash, round.total_tickets);
+                let winner = lottery_ticket_owner(&data, ticket)
+                    .map_err(|e| format!("查找中奖者失败: {:?}", e))?
+                    .ok_or("彩票轮次数据不完整")?;
+                info!("🏆 中奖票号 {}，中奖账户 {}，奖池 {} 功德", ticket, winner, round.jackpot());
+                vec![(instruction::settle_lottery(&config.program_id, &lottery_pubkey, Some(&winner)), Some(winner))]
+            } else if round.voided || slot - round.end_slot > SLOT_HASHES_MAX_ENTRIES {
+                // 💸 哈希已过期：第一条指令把本轮标记为作废，每条指令给一批投注者退款
+                let pending = lottery_pending_refunds(&data).map_err(|e| format!("读取投注条目失败: {:?}", e))?;
+                info!("🚫 结束slot的哈希已过期，本轮作废，给 {} 个投注者退还票款", pending.len());
+                let mut chunks = pending.chunks(MAX_LOTTERY_REFUND_ACCOUNTS).map(|chunk| chunk.to_vec()).collect::<Vec<_>>();
+                if chunks.is_empty() {
+                    chunks.push(Vec::new());
+                }
+                chunks
+                    .into_iter()
+                    .map(|chunk| (instruction::refund_lottery(&config.program_id, &lottery_pubkey, &chunk), None))
+                    .collect()
+            } else {
+                warn!("❌ 结束slot的哈希暂时查不到，稍后重试");
+                return Ok(());
+            };
+
+            for (settle, winner) in settles {
+                let mut transaction = Transaction::new_with_payer(&[settle], Some(&payer));
+                transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+                let mut watched = vec![(lottery_pubkey, "彩票轮次")];
+                watched.extend(winner.map(|winner| (winner, "中奖的功德账户")));
+                send_transaction_and_watch(&client, &transaction, &payer, "开奖", &watched)?;
+            }
+        }
+        other => return Err(format!("未知命令: {}，可用命令: create / show / enter / settle", other).into()),
+    }
+    Ok(())
+}
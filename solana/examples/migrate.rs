@@ -0,0 +1,139 @@
+// ========================================
+// 账户迁移工具
+// 🔄 布局升级后一条命令把旧布局的账户迁移到当前布局，租金差额由当前用户支付
+// 默认只检查一个账户：说明布局版本、大小和租金差额，迁移后确认功德值不变；--all 批量迁移自己和通讯录中用户的旧账户
+// ========================================
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signer,
+};
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+use config::{initialize_program_config, ProgramConfig};
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::{contacts_path, resolve_user, AddressBook};
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{
+    find_legacy_accounts,
+    format_sol_balance,
+    migrate_accounts,
+    migrate_single,
+    preview_migration,
+    write_report,
+    AccountFetcher,
+    OperationReport,
+    ProgramGuard,
+    Throttle,
+};
+use gong_de_increase::utils::{derive_gongde_account_address, GONGDE_ACCOUNT_SIZE};
+
+/// 查找并迁移当前用户和通讯录中用户的旧账户
+/// 只按用户派生功德账户地址，不扫描整个程序，全局功德PDA等其他账户不会被迁移
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `config` - 程序配置，当前用户支付交易费和租金差额
+///
+/// # 返回
+/// * `Result<OperationReport, Box<dyn std::error::Error>>` - 逐笔交易的迁移结果
+pub fn migrate_all(client: &RpcClient, config: &ProgramConfig) -> Result<OperationReport, Box<dyn std::error::Error>> {
+    let mut owners = vec![config.keypair.pubkey()];
+    let book = AddressBook::load(&contacts_path()).map_err(|e| e.to_string())?;
+    owners.extend(book.iter().map(|(_, pubkey)| pubkey).filter(|pubkey| *pubkey != config.keypair.pubkey()));
+    let candidates = find_legacy_accounts(client, &config.program_id, &owners, &mut Throttle::from_env())?;
+    info!("🔍 {} 个用户中共有 {} 个旧布局账户", owners.len(), candidates.len());
+    if candidates.is_empty() {
+        info!("✅ 没有需要迁移的账户");
+        return Ok(OperationReport::new("迁移"));
+    }
+
+    for candidate in &candidates {
+        info!("  - {} 功德 {}", candidate.pubkey, candidate.value);
+    }
+    Ok(migrate_accounts(client, &config.keypair, &config.program_id, &candidates)?)
+}
+
+/// 迁移一个用户的功德账户：先说明要改什么，迁移后确认功德值不变；已是当前布局时拒绝执行
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `config` - 程序配置，当前用户支付交易费和租金差额
+/// * `user` - 功德账户的主人；迁移不改变账户行为，可以替别人迁移
+///
+/// # 返回
+/// * `Result<OperationReport, Box<dyn std::error::Error>>` - 迁移结果
+pub fn migrate_user(
+    client: &RpcClient,
+    config: &ProgramConfig,
+    user: &Pubkey,
+) -> Result<OperationReport, Box<dyn std::error::Error>> {
+    let gongde_pubkey = derive_gongde_account_address(user, &config.program_id)
+        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+    let rent = client.get_minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE)?;
+    let preview = preview_migration(user, &gongde_pubkey, client.fetch_account(&gongde_pubkey)?, &config.program_id, rent)?;
+    preview.print();
+
+    let signature = migrate_single(client, &config.keypair, &config.program_id, &preview)?;
+    let mut report = OperationReport::new("迁移");
+    report.record(Some(signature), vec![gongde_pubkey], preview.rent_deficit, Ok(()));
+    Ok(report)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 功德账户迁移工具启动 ===");
+
+    // --all 迁移自己和通讯录中用户的旧账户（迁移不改变账户行为，任何人都可以代为迁移）
+    // --user <公钥> 替指定用户迁移，默认迁移自己的账户
+    // --force 跳过目标程序检查
+    // --report <path> 把逐笔交易的结果写成JSON文件
+    let args: Vec<String> = std::env::args().collect();
+    let include_all = args.iter().any(|arg| arg == "--all");
+    let force = args.iter().any(|arg| arg == "--force");
+    let report_path = args.iter().position(|arg| arg == "--report").and_then(|index| args.get(index + 1));
+    let user = args
+        .iter()
+        .position(|arg| arg == "--user")
+        .map(|index| resolve_user(args.get(index + 1).ok_or("--user 需要一个公钥")?).map_err(|e| format!("用户无效: {}", e)))
+        .transpose()?;
+
+    let config = initialize_program_config()?;
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+    ProgramGuard::new(force).ensure(&*client, &config.program_id, &config.keypair.pubkey())?;
+
+    let report = if include_all {
+        migrate_all(&client, &config)?
+    } else {
+        migrate_user(&client, &config, &user.unwrap_or_else(|| config.keypair.pubkey()))?
+    };
+
+    info!("\n{}", report.render());
+    let rent_funded = report.lamports_spent();
+    info!("💰 补足租金: {} lamports ({})", rent_funded, format_sol_balance(rent_funded));
+    if let Some(path) = report_path {
+        write_report(&report, path)?;
+    }
+    if !report.is_complete() {
+        warn!("❌ 部分账户迁移未完成，重新运行会跳过已迁移的账户");
+        std::process::exit(report.exit_code());
+    }
+    Ok(())
+}
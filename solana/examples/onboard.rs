@@ -0,0 +1,213 @@
+// ========================================
+// 批量开通工具
+// 🎫 为一批用户创建并初始化功德账户，当前用户（管理员）支付租金和交易费
+// 可重复运行：每次都先查询链上状态，已开通的用户自动跳过，中断后重新运行即可从断点继续
+// ========================================
+
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::{initialize_program_config, load_keypair_from_file};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{
+    arg_value,
+    fetch_gongde_accounts,
+    format_sol_balance,
+    onboard_users,
+    pack_onboardings,
+    partition_users,
+    write_report,
+    ProgramGuard,
+    Throttle,
+};
+use gong_de_increase::utils::{derive_gongde_account_address, GONGDE_ACCOUNT_SIZE};
+
+/// 解析用户列表文件：每行一个用户的密钥文件路径，空行和 `#` 开头的注释行被忽略
+///
+/// 功德账户是以用户为base的种子地址，创建时必须有用户签名，所以这里需要密钥文件而不只是公钥
+///
+/// # 参数
+/// * `content` - 文件内容
+/// * `base_dir` - 相对路径的基准目录（用户列表文件所在目录）
+///
+/// # 返回
+/// * `Vec<PathBuf>` - 去重后的密钥文件路径，顺序与文件一致
+pub fn parse_keypair_list(content: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = if line.starts_with("~/") {
+            PathBuf::from(line)
+        } else {
+            base_dir.join(line)
+        };
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// 读取所有用户的密钥，同一个用户出现多次时只保留一份
+///
+/// # 参数
+/// * `paths` - 密钥文件路径
+///
+/// # 返回
+/// * `Result<Vec<Keypair>, String>` - 用户密钥；任何一个文件读取失败都返回错误，避免只开通一部分还以为全部完成
+pub fn load_users(paths: &[PathBuf]) -> Result<Vec<Keypair>, String> {
+    let mut users: Vec<Keypair> = Vec::new();
+    for path in paths {
+        let user = load_keypair_from_file(&path.to_string_lossy())
+            .map_err(|e| format!("读取密钥文件 {} 失败: {}", path.display(), e))?;
+        if !users.iter().any(|existing| existing.pubkey() == user.pubkey()) {
+            users.push(user);
+        }
+    }
+    Ok(users)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging();
+    info!("=== 功德账户批量开通工具启动 ===");
+
+    // --users-file 用户密钥文件列表
+    // --dry-run 只显示开通计划，不发送交易
+    // --private 开通为私有功德箱（默认公开）
+    // --force 跳过目标程序检查
+    // --report <path> 把逐笔交易的结果写成JSON文件
+    let args: Vec<String> = std::env::args().collect();
+    let users_file = arg_value(&args, "--users-file").ok_or("请用 --users-file 指定用户密钥文件列表")?;
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let is_public = !args.iter().any(|arg| arg == "--private");
+    let force = args.iter().any(|arg| arg == "--force");
+    let report_path = arg_value(&args, "--report");
+
+    let base_dir = Path::new(users_file).parent().unwrap_or(Path::new("."));
+    let users = load_users(&parse_keypair_list(&fs::read_to_string(users_file)?, base_dir))?;
+
+    let config = initialize_program_config()?;
+    info!("🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+    ProgramGuard::new(force).ensure(&*client, &config.program_id, &config.keypair.pubkey())?;
+
+    // 🔍 查询链上现状，已开通的用户跳过
+    let pubkeys: Vec<Pubkey> = users.iter().map(Signer::pubkey).collect();
+    let gongde_pubkeys = pubkeys
+        .iter()
+        .map(|user| derive_gongde_account_address(user, &config.program_id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("生成账户地址失败: {:?}", e))?;
+    let results = fetch_gongde_accounts(&client, &gongde_pubkeys, &config.program_id, &mut Throttle::from_env())?;
+    let partition = partition_users(&pubkeys, results);
+    info!(
+        "📋 用户列表: {} 人，已开通 {} 人，需要开通 {} 人",
+        pubkeys.len(),
+        partition.existing.len(),
+        partition.new_users.len()
+    );
+    for (user, e) in &partition.blocked {
+        warn!("⚠️  {} 无法开通: {}", user, e);
+    }
+    if partition.new_users.is_empty() {
+        info!("✅ 所有用户都已开通");
+        return Ok(());
+    }
+
+    let rent = client.get_minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE)?;
+    if dry_run {
+        let batches = pack_onboardings(&config.program_id, &config.keypair.pubkey(), &partition.new_users, rent)?;
+        info!("\n📝 === 开通计划（--dry-run，不发送交易）===");
+        for (index, batch) in batches.iter().enumerate() {
+            info!("交易 {}: {} 个用户", index + 1, batch.len());
+            for user in batch {
+                info!("  - {}", user);
+            }
+        }
+        let total_rent = rent * partition.new_users.len() as u64;
+        info!("💰 需要支付租金: {} lamports ({})", total_rent, format_sol_balance(total_rent));
+        return Ok(());
+    }
+
+    let new_users: Vec<Keypair> = users
+        .into_iter()
+        .filter(|user| partition.new_users.contains(&user.pubkey()))
+        .collect();
+    let report = onboard_users(&client, &config.keypair, &config.program_id, &new_users, is_public)?;
+
+    info!("\n{}", report.render());
+    info!("⏭️  已开通跳过: {} 个", partition.existing.len());
+    let rent_paid = report.lamports_spent();
+    info!("💰 支付租金: {} lamports ({})", rent_paid, format_sol_balance(rent_paid));
+    if let Some(path) = report_path {
+        write_report(&report, path)?;
+    }
+    if !report.is_complete() {
+        warn!("❌ 部分用户开通未完成，重新运行即可重试");
+        std::process::exit(report.exit_code());
+    }
+    if !partition.blocked.is_empty() {
+        return Err(format!("{} 个用户的地址被占用，无法开通", partition.blocked.len()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::write_keypair_file;
+
+    #[test]
+    fn test_parse_keypair_list() {
+        let base_dir = Path::new("/data/users");
+        let content = "# 社区成员\nalice.json\n\n  bob.json  \n/keys/carol.json\nalice.json\n";
+        assert_eq!(
+            parse_keypair_list(content, base_dir),
+            vec![
+                PathBuf::from("/data/users/alice.json"),
+                PathBuf::from("/data/users/bob.json"),
+                PathBuf::from("/keys/carol.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_users_dedupes_and_reports_missing() {
+        let dir = std::env::temp_dir().join(format!("gongde-onboard-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let alice = Keypair::new();
+        let alice_path = dir.join("alice.json");
+        write_keypair_file(&alice, &alice_path).unwrap();
+        // 同一个密钥的副本
+        let copy_path = dir.join("alice-copy.json");
+        fs::copy(&alice_path, &copy_path).unwrap();
+
+        let users = load_users(&[alice_path.clone(), copy_path]).unwrap();
+        assert_eq!(users.iter().map(Signer::pubkey).collect::<Vec<_>>(), vec![alice.pubkey()]);
+
+        let err = load_users(&[alice_path, dir.join("missing.json")]).unwrap_err();
+        assert!(err.contains("missing.json"), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,129 +3,209 @@
 // 🎯 根据用户公钥查询功德账户信息
 // ========================================
 
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
-    signature::Signer,
 };
+use tracing::{error, info, warn};
 use std::env;
 
 // 引用本地配置模块
-mod config;
-use config::initialize_program_config;
+use gong_de_increase::client::config;
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+use config::initialize_query_config;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+// 引用工具函数模块 - 合约的工具函数和 client 中的客户端工具
+use gong_de_increase::client::utils;
+use utils::{query_gongde_account, query_gongde_account_at, print_gongde_info, progress_bar, fetch_transfer_quota, format_count, MeritDisplay, format_duration, format_utc_datetime};
+use gong_de_increase::utils::{merit_to_next_level, read_milestones, MeritLevel, DAILY_TRANSFER_CAP, GONGDE_VALUE_SIZE};
+
+/// 输出用法和功德等级表
+fn print_usage(program: &str) {
+    info!("📖 用法: {} [用户公钥 | --address <功德账户地址>] [--rough] [-v|-vv] [--json] [-q]", program);
+    info!("💡 不提供公钥参数时查询您自己的功德");
+    info!("💡 --rough 只显示功德等级和所在区间（如 101+），不显示准确值；设置 {}=1 默认粗略显示，--exact 临时恢复", utils::ROUGH_MERIT_ENV);
+    info!("💡 --address 直接查询指定的账户，不从用户公钥派生，用于调试旧的 create_with_seed 账户或随机密钥对账户");
+    info!("🏅 功德等级（--json 日志中的 merit_level 字段使用左侧标识）:");
+    for level in MeritLevel::ALL {
+        info!("   {:<10} {}  功德 >= {}", level.id(), level, level.threshold());
+    }
+}
 
-// 引用工具函数模块 - 直接使用src中的工具函数和examples中的客户端工具
-mod utils;
-use utils::{query_gongde_account, print_gongde_info};
-use gong_de_increase::utils::GONGDE_VALUE_SIZE;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
+    // 用法说明没有单一结果，输出被管道时（如 `query --help | less`）也要完整显示
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        logging::init_logging();
+        print_usage(&args[0]);
+        return Ok(());
+    }
+    logging::init_logging_for_result();
+    // --address 后面的值不是用户公钥
+    let address_index = args.iter().position(|arg| arg == "--address").map(|index| index + 1);
+    let address = match address_index {
+        Some(index) => {
+            let address = args.get(index).ok_or("--address 需要指定功德账户地址")?;
+            Some(address.parse::<Pubkey>().map_err(|e| format!("功德账户地址无效: {}", e))?)
+        }
+        None => None,
+    };
+    let positional: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, arg)| !arg.starts_with('-') && Some(*index) != address_index)
+        .map(|(_, arg)| arg)
+        .collect();
+    let merit_display = MeritDisplay::current();
     
-    println!("=== Solana 功德查询程序启动 ===");
+    info!("=== Solana 功德查询程序启动 ===");
     
-    // 初始化配置（获取程序ID和RPC连接）
-    let config = initialize_program_config()?;
+    // 初始化只读配置（只需要程序ID和RPC地址，查询不需要私钥）
+    let config = initialize_query_config()?;
     
-    // 确定要查询的用户公钥
-    let user_pubkey = if args.len() >= 2 {
+    // 确定要查询的用户公钥，直接指定账户地址时不需要
+    let user_pubkey = if address.is_some() {
+        if !positional.is_empty() {
+            return Err("--address 和用户公钥不能同时指定".into());
+        }
+        None
+    } else if let Some(user_pubkey_str) = positional.first() {
         // 如果提供了公钥参数，解析并使用它
-        let user_pubkey_str = &args[1];
-        match user_pubkey_str.parse::<Pubkey>() {
+        match resolve_user(user_pubkey_str) {
             Ok(pubkey) => {
-                println!("🔍 查询指定用户的功德: {}", pubkey);
-                pubkey
+                info!("🔍 查询指定用户的功德: {}", pubkey);
+                Some(pubkey)
             },
             Err(e) => {
-                println!("❌ 错误: 无效的公钥格式: {}", e);
-                println!("💡 公钥应该是58个字符的Base58编码字符串");
-                println!("📖 用法: {} [用户公钥]", args[0]);
-                println!("📝 示例: {} BvpjTs88TmXJrFfghPJmo1kEJXdtqXX8SdvW6jv8ng9R", args[0]);
-                println!("💡 提示: 不提供公钥参数时将查询您自己的功德");
-                return Err(e.into());
+                error!("❌ 错误: {}", e);
+                info!("💡 公钥应该是58个字符的Base58编码字符串，或通讯录中的 @名字");
+                info!("📖 用法: {} [用户公钥]", args[0]);
+                info!("📝 示例: {} BvpjTs88TmXJrFfghPJmo1kEJXdtqXX8SdvW6jv8ng9R", args[0]);
+                info!("💡 提示: 不提供公钥参数时将查询您自己的功德");
+                return Err(e.to_string().into());
             }
         }
     } else {
         // 如果没有提供公钥参数，使用当前用户的公钥
-        let user_pubkey = config.keypair.pubkey();
-        println!("🔍 查询您自己的功德: {}", user_pubkey);
-        user_pubkey
+        let user_pubkey = config.default_user().map_err(|e| {
+            error!("❌ 读取本地钱包失败: {}", e);
+            info!("💡 没有本地钱包时请指定要查询的用户公钥: {} <用户公钥>", args[0]);
+            e
+        })?;
+        info!("🔍 查询您自己的功德: {}", user_pubkey);
+        Some(user_pubkey)
     };
-    println!("✅ 配置初始化成功!");
-    println!("  - 程序ID: {}", config.program_id);
-    println!("  - RPC URL: {}", config.rpc_url);
+    info!("✅ 配置初始化成功!");
+    info!("  - 程序ID: {}", config.program_id);
+    info!("  - RPC URL: {}", config.rpc_url);
     
     // 连接到 Solana 网络
-    println!("\n🌐 连接到Solana网络: {}", config.rpc_url);
-    let client = RpcClient::new_with_commitment(config.rpc_url, CommitmentConfig::confirmed());
+    info!("\n🌐 连接到Solana网络: {}", config.rpc_urls.join(", "));
+    let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
     
     // 查询用户的功德账户
-    println!("\n🔍 查询功德账户信息...");
-    match query_gongde_account(&client, &user_pubkey, &config.program_id) {
-        Ok(gongde_info) => {
-            println!("\n📊 === 查询结果 ===");
-            print_gongde_info(&user_pubkey, gongde_info);
+    info!("\n🔍 查询功德账户信息...");
+    let lookup = match (address, user_pubkey) {
+        (Some(address), _) => {
+            info!("🔍 直接查询功德账户: {}（不从用户公钥派生）", address);
+            query_gongde_account_at(&*client, &address, &config.program_id)
+        }
+        (None, Some(user_pubkey)) => query_gongde_account(&*client, &user_pubkey, &config.program_id),
+        (None, None) => unreachable!("没有 --address 时一定有用户公钥"),
+    };
+    match lookup {
+        Ok(lookup) => {
+            info!("\n📊 === 查询结果 ===");
+            print_gongde_info(user_pubkey.as_ref(), &lookup, merit_display);
+            // 没有功德账户或账户已关闭时功德为0
+            logging::emit_result(merit_display.format(lookup.value()));
             
-            // 如果找到功德账户，显示详细统计
-            if let Some((gongde_pubkey, gongde_value, account_balance)) = gongde_info {
-                println!("\n📈 === 详细统计 ===");
+            // 如果找到可用的功德账户，显示详细统计
+            if let Some(info) = lookup.live() {
+                let (gongde_pubkey, gongde_value, account_balance) = (info.pubkey, info.value, info.lamports);
+                info!("\n📈 === 详细统计 ===");
                 
                 // 计算进度条
-                let progress_chars = match gongde_value {
-                    0 => "▱▱▱▱▱▱▱▱▱▱",
-                    1..=10 => "▰▱▱▱▱▱▱▱▱▱",
-                    11..=100 => "▰▰▱▱▱▱▱▱▱▱",
-                    101..=1000 => "▰▰▰▱▱▱▱▱▱▱",
-                    1001..=10000 => "▰▰▰▰▱▱▱▱▱▱",
-                    _ => "▰▰▰▰▰▰▰▰▰▰"
-                };
-                
-                println!("📊 功德进度: {} ({})", progress_chars, gongde_value);
-                
-                // 下一个等级所需功德
-                let next_milestone = match gongde_value {
-                    0..=10 => 11,
-                    11..=100 => 101,
-                    101..=1000 => 1001,
-                    1001..=10000 => 10001,
-                    _ => gongde_value
-                };
+                info!("📊 功德进度: {} ({})", progress_bar(gongde_value), merit_display.format(gongde_value));
                 
-                if gongde_value < u32::MAX && next_milestone > gongde_value {
-                    let needed = next_milestone - gongde_value;
-                    println!("🎯 距离下一等级还需: {} 功德", needed);
+                // 下一个等级所需功德，粗略显示时差值会暴露准确值，只显示下一等级
+                match (merit_to_next_level(gongde_value), merit_display) {
+                    (Some(needed), MeritDisplay::Exact) => info!("🎯 距离下一等级还需: {} 功德", format_count(needed.into())),
+                    (Some(_), MeritDisplay::Rough) => {
+                        let next = MeritLevel::from_value(gongde_value).next().expect("还有下一等级");
+                        info!("🎯 下一等级: {}（功德 {}+）", next, format_count(next.threshold().into()));
+                    }
+                    (None, _) => {}
+                }
+
+                // 今日转赠额度按集群时间计算，与链上判断一致；额度按用户记录，直接指定账户地址时不知道用户
+                if let Some(user_pubkey) = &user_pubkey {
+                    match fetch_transfer_quota(&client, &config.program_id, user_pubkey) {
+                        Ok(quota) => info!(
+                            "🎁 今日剩余转赠额度: {}/{}（{}后恢复）",
+                            quota.remaining,
+                            DAILY_TRANSFER_CAP,
+                            format_duration(quota.resets_in)
+                        ),
+                        Err(e) => warn!("⚠️  查询转赠额度失败: {}", e),
+                    }
+                }
+
+                // 已达成的里程碑及第一次达成的时间
+                match client.get_account(&gongde_pubkey) {
+                    Ok(account) => {
+                        let milestones = read_milestones(&account.data);
+                        if milestones.is_empty() {
+                            info!("🏁 还没有达成里程碑");
+                        }
+                        for milestone in milestones {
+                            info!("🏁 功德 {} 达成于 {}", format_count(milestone.milestone), format_utc_datetime(milestone.reached_at));
+                        }
+                    }
+                    Err(e) => warn!("⚠️  查询里程碑失败: {}", e),
                 }
                 
                 // 账户使用情况
                 let rent_exemption = client.get_minimum_balance_for_rent_exemption(GONGDE_VALUE_SIZE)?;
-                println!("💾 账户数据大小: {} 字节 (u32)", GONGDE_VALUE_SIZE);
-                println!("💰 最低租金要求: {:.6} SOL", rent_exemption as f64 / 1_000_000_000.0);
+                info!("💾 账户数据大小: {} 字节 (u32)", GONGDE_VALUE_SIZE);
+                info!("💰 最低租金要求: {:.6} SOL", rent_exemption as f64 / 1_000_000_000.0);
                 
                 if account_balance >= rent_exemption {
-                    println!("✅ 账户租金充足，数据安全");
+                    info!("✅ 账户租金充足，数据安全");
                 } else {
-                    println!("⚠️  账户租金不足，数据可能被清理");
+                    warn!("⚠️  账户租金不足，数据可能被清理");
                 }
                 
-                println!("\n🔗 区块链浏览器链接:");
-                println!("   Solana Explorer: https://explorer.solana.com/address/{}?cluster=devnet", gongde_pubkey);
-                println!("   Solscan: https://solscan.io/account/{}?cluster=devnet", gongde_pubkey);
+                info!("\n🔗 区块链浏览器链接:");
+                info!("   Solana Explorer: https://explorer.solana.com/address/{}?cluster=devnet", gongde_pubkey);
+                info!("   Solscan: https://solscan.io/account/{}?cluster=devnet", gongde_pubkey);
             }
         },
         Err(e) => {
-            println!("❌ 查询失败: {}", e);
-            println!("💡 请检查网络连接或用户公钥是否正确");
+            error!("❌ 查询失败: {}", e);
+            info!("💡 请检查网络连接或用户公钥是否正确");
             return Err(e);
         }
     }
     
-    println!("\n🎉 === 查询完成 ===");
-    println!("💡 提示:");
-    println!("   - 使用 client.rs 可以创建和增加功德");
-    println!("   - 使用 close.rs 可以关闭账户并回收租金");
-    println!("   - 功德账户地址基于用户公钥确定性生成");
+    info!("\n🎉 === 查询完成 ===");
+    info!("💡 提示:");
+    info!("   - 使用 client.rs 可以创建和增加功德");
+    info!("   - 使用 close.rs 可以关闭账户并回收租金");
+    info!("   - 功德账户地址基于用户公钥确定性生成");
     
     Ok(())
 }
@@ -138,16 +218,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //   - 根据用户公钥查询功德账户
 //   - 显示功德值和账户状态
 //   - 提供功德等级和进度信息
+//   - 列出功德里程碑的达成日期
 //   - 显示区块链浏览器链接
 // 
 // 📋 使用方法：
-//   cargo run --example query [用户公钥]
+//   cargo run --features client --example query [用户公钥]
 //   不提供公钥参数时查询自己的功德
+//   cargo run --features client --example query -- --address <功德账户地址>
+//   直接查询指定的账户，跳过地址派生，账户必须归本程序所有
+//   cargo run --features client --example query -- --rough
+//   只显示功德等级区间，不显示准确值（GONGDE_ROUGH=1 默认开启）
 // 
 // 🔍 查询逻辑：
 //   1. 解析用户公钥
 //   2. 生成确定性的功德账户地址
-//   3. 查询账户是否存在
+//   3. 查询账户是否存在（余额为0但数据还在的账户视为已关闭）
 //   4. 读取并解析功德数据
 //   5. 格式化显示结果
 // 
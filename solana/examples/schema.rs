@@ -0,0 +1,41 @@
+// ========================================
+// 机器可读输出的 JSON Schema - 给编写脚本的人查看输出格式
+// 🧾 --schema <名称> 打印一种输出的 Schema，--write 重新生成 schemas/ 中的全部文件（改了输出结构后运行）
+// 用法: cargo run --features schema --example schema -- --schema <report | snapshot | diff> | --write
+// 输出被管道或重定向时只输出 Schema 本身，可以直接保存成文件
+// ========================================
+
+use std::fs;
+use tracing::info;
+
+use gong_de_increase::client::output::{schema_text, OUTPUT_NAMES, SCHEMA_DIR};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用命令行参数工具
+use gong_de_increase::client::utils::arg_value;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--schema") {
+        let name = arg_value(&args, "--schema").ok_or("用法: schema --schema <名称>")?;
+        let text = schema_text(name)
+            .ok_or_else(|| format!("没有名为 {} 的输出，可用的输出: {}", name, OUTPUT_NAMES.join(" / ")))?;
+        info!("🧾 {} 的 Schema:\n{}", name, text.trim_end());
+        logging::emit_result(text.trim_end());
+    } else if args.iter().any(|arg| arg == "--write") {
+        let dir = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), SCHEMA_DIR);
+        fs::create_dir_all(&dir)?;
+        for name in OUTPUT_NAMES {
+            let path = format!("{}/{}.json", dir, name);
+            fs::write(&path, schema_text(name).expect("OUTPUT_NAMES 中的名称都有 Schema"))?;
+            info!("📝 {}", path);
+        }
+    } else {
+        info!("可用的输出: {}", OUTPUT_NAMES.join(" / "));
+        info!("用法: schema --schema <名称> | --write");
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+// ========================================
+// 共享账本模式
+// 📒 所有用户的功德存放在同一个PDA中，不需要每人开一个功德账户，新用户只需支付36字节的租金
+// 与个人功德账户互相独立：共享账本中的功德不会出现在 query 的结果里
+// ========================================
+
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::Signer,
+    transaction::Transaction,
+};
+use tracing::{info, warn};
+
+// 引用本地配置模块
+use gong_de_increase::client::config;
+use config::{initialize_program_config, initialize_query_config};
+
+// 引用日志初始化模块
+use gong_de_increase::client::logging;
+
+// 引用多节点故障转移模块
+use gong_de_increase::client::rpc;
+use rpc::MultiRpcClient;
+
+// 引用通讯录模块
+use gong_de_increase::client::contacts;
+use contacts::resolve_user;
+
+// 引用工具函数模块
+use gong_de_increase::client::utils;
+use utils::{send_transaction_and_watch, ProgramGuard};
+use gong_de_increase::error::StateError;
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{
+    derive_global_gongde_pda_address,
+    derive_shared_ledger_address,
+    shared_ledger_get,
+    shared_ledger_len,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init_logging_for_result();
+    info!("=== 共享账本模式 ===");
+
+    // get [用户公钥]  查询用户在共享账本中的功德，默认查询自己
+    // increment [数量]  在共享账本中给自己增加功德，默认1点
+    // --force 跳过目标程序检查
+    let args: Vec<String> = std::env::args().collect();
+    let force = args.iter().any(|arg| arg == "--force");
+    let positional: Vec<&String> = args.iter().skip(1).filter(|arg| !arg.starts_with('-')).collect();
+    let command = positional.first().map(|arg| arg.as_str()).unwrap_or("get");
+
+    match command {
+        "get" => {
+            let config = initialize_query_config()?;
+            let user = match positional.get(1) {
+                Some(user) => resolve_user(user).map_err(|e| format!("用户无效: {}", e))?,
+                None => config.default_user()?,
+            };
+            let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+            let (ledger_pubkey, _bump) = derive_shared_ledger_address(&config.program_id);
+            let Some(ledger) = client.get_account_with_commitment(&ledger_pubkey, client.commitment())?.value else {
+                warn!("❌ 共享账本还没有创建，第一次 increment 时自动创建");
+                logging::emit_result(0);
+                return Ok(());
+            };
+            let decode_error = |e: StateError| format!("解析共享账本失败: {:?}", e);
+            let value = shared_ledger_get(&ledger.data, &user).map_err(decode_error)?;
+            info!("📒 共享账本: {}（{} 位用户）", ledger_pubkey, shared_ledger_len(&ledger.data).map_err(decode_error)?);
+            match value {
+                Some(value) => info!("🙏 {} 的功德: {}", user, value),
+                None => info!("ℹ️  {} 还不在共享账本中", user),
+            }
+            logging::emit_result(value.unwrap_or(0));
+        }
+        "increment" => {
+            let amount = positional.get(1).map(|arg| arg.parse::<u64>()).transpose()?.unwrap_or(1);
+            let config = initialize_program_config()?;
+            let user = config.keypair.pubkey();
+            let client = MultiRpcClient::connect(&config.rpc_urls, CommitmentConfig::confirmed())?;
+            ProgramGuard::new(force).ensure(&*client, &config.program_id, &user)?;
+
+            let (ledger_pubkey, _bump) = derive_shared_ledger_address(&config.program_id);
+            let (global_pubkey, _bump) = derive_global_gongde_pda_address(&config.program_id)
+                .map_err(|e| format!("生成全局PDA账户地址失败: {:?}", e))?;
+            let increment = instruction::shared_increment(&config.program_id, &user, &global_pubkey, amount);
+            // 发送前在本地按链上规则检查数量，不合法时不白白支付手续费
+            instruction::validate_args(&increment)?;
+            let mut transaction = Transaction::new_with_payer(&[increment], Some(&user));
+            transaction.sign(&[&config.keypair], client.get_latest_blockhash()?);
+            send_transaction_and_watch(
+                &client,
+                &transaction,
+                &user,
+                "共享账本增加功德",
+                &[(ledger_pubkey, "共享账本"), (global_pubkey, "全局功德")],
+            )?;
+
+            let ledger = client.get_account(&ledger_pubkey)?;
+            let value = shared_ledger_get(&ledger.data, &user)
+                .map_err(|e| format!("解析共享账本失败: {:?}", e))?
+                .unwrap_or(0);
+            info!("🙏 共享账本中的功德: {}", value);
+            logging::emit_result(value);
+        }
+        other => return Err(format!("未知命令: {}，可用命令: get / increment", other).into()),
+    }
+    Ok(())
+}
@@ -1 +1 @@
-cargo run --example client $1
+cargo run --features client --example client $1
@@ -1 +1 @@
-cargo test -- --no-capture
+cargo build-sbf && cargo test -- --no-capture
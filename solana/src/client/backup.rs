@@ -0,0 +1,410 @@
+// ========================================
+// 本地状态备份与恢复 - 重装系统前把客户端用到的本地文件打包成一个文件，装好后再还原
+// 💾 包括Solana CLI配置、通讯录和跟随进度；配置缓存可以重新生成，不备份
+// 私钥默认不打包，只记录路径，带 --include-keys 才包含；恢复时私钥文件权限设为 0600
+// 主目录下的文件按 ~/ 记录，恢复到新机器的主目录；命令行在 examples/backup.rs
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::client::config::{expand_home, find_solana_cli_config_path, read_solana_cli_config};
+use crate::client::contacts::contacts_path;
+use crate::client::follow::cursor_path;
+
+/// 备份文件格式版本，格式不兼容地变化时加一
+pub const BACKUP_VERSION: u32 = 1;
+
+/// 备份中的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupItemKind {
+    /// Solana CLI配置（config.yml）
+    CliConfig,
+    /// CLI配置中 keypair_path 指向的私钥
+    Keypair,
+    /// 通讯录
+    Contacts,
+    /// follow 的跟随进度
+    FollowCursor,
+}
+
+impl fmt::Display for BackupItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::CliConfig => "CLI配置",
+            Self::Keypair => "私钥",
+            Self::Contacts => "通讯录",
+            Self::FollowCursor => "跟随进度",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 备份中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupItem {
+    pub kind: BackupItemKind,
+    /// 文件路径，主目录下的文件以 ~/ 开头
+    pub path: String,
+    /// 文件内容，都是文本文件
+    pub content: String,
+}
+
+/// 备份文件：一个带版本号的JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupBundle {
+    pub version: u32,
+    pub items: Vec<BackupItem>,
+    /// 没有打包的私钥路径，恢复后需要自己把私钥放回这里
+    #[serde(default)]
+    pub excluded_keys: Vec<String>,
+}
+
+/// 只读出版本号，新版本的格式可能无法按当前结构解析
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
+}
+
+/// 备份和恢复失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupError {
+    /// 读写文件失败
+    Io { path: String, message: String },
+    /// 备份文件或CLI配置的内容格式错误
+    Parse { path: String, message: String },
+    /// 备份文件的版本不是当前支持的版本
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// 路径以 ~/ 开头，但没有设置HOME环境变量
+    HomeNotSet(String),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, message } => write!(f, "读写文件 {} 失败: {}", path, message),
+            Self::Parse { path, message } => write!(f, "文件格式错误 {}: {}", path, message),
+            Self::UnsupportedVersion { found, supported } => {
+                write!(f, "备份文件版本为 {}，当前只支持版本 {}，请使用对应版本的客户端恢复", found, supported)
+            }
+            Self::HomeNotSet(path) => write!(f, "未设置HOME环境变量，无法展开路径 {}", path),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+fn io_error(path: &str, error: impl fmt::Display) -> BackupError {
+    BackupError::Io { path: path.to_string(), message: error.to_string() }
+}
+
+/// 读取文件，文件不存在时返回None
+fn read_optional(path: &str) -> Result<Option<String>, BackupError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io_error(path, e)),
+    }
+}
+
+/// 展开 ~/ 开头的路径
+fn expand(path: &str, home: Option<&str>) -> Result<String, BackupError> {
+    expand_home(path, home).map_err(|_| BackupError::HomeNotSet(path.to_string()))
+}
+
+/// 把主目录下的路径写成 ~/ 开头，恢复到别的机器时展开到那台机器的主目录
+fn collapse_home(path: &str, home: Option<&str>) -> String {
+    home.map(|home| home.trim_end_matches('/'))
+        .filter(|home| !home.is_empty())
+        .and_then(|home| path.strip_prefix(home)?.strip_prefix('/').map(|rest| format!("~/{}", rest)))
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// 私钥文件只允许自己读写
+#[cfg(unix)]
+fn restrict_permissions(path: &str) -> Result<(), BackupError> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| io_error(path, e))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &str) -> Result<(), BackupError> {
+    Ok(())
+}
+
+/// 需要备份的文件的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupSources {
+    /// Solana CLI配置，None表示没有找到
+    pub cli_config: Option<String>,
+    pub contacts: String,
+    pub follow_cursor: String,
+}
+
+impl BackupSources {
+    /// 按客户端实际使用的位置查找：CLI配置的候选路径、GONGDE_CONTACTS 和 GONGDE_FOLLOW_CURSOR 环境变量
+    pub fn discover() -> Self {
+        Self { cli_config: find_solana_cli_config_path(), contacts: contacts_path(), follow_cursor: cursor_path() }
+    }
+}
+
+impl BackupBundle {
+    /// 读取本地文件生成备份，不存在的文件跳过
+    ///
+    /// # 参数
+    /// * `sources` - 需要备份的文件的位置
+    /// * `home` - 用户主目录，用来展开和记录 ~/ 路径
+    /// * `include_keys` - 是否包含CLI配置指向的私钥，不包含时只记录路径
+    ///
+    /// # 错误
+    /// * 文件存在但读取失败，或者CLI配置格式错误
+    pub fn create(sources: &BackupSources, home: Option<&str>, include_keys: bool) -> Result<Self, BackupError> {
+        let mut items = Vec::new();
+        let mut excluded_keys = Vec::new();
+        let mut add = |kind, path: &str, content| {
+            items.push(BackupItem { kind, path: collapse_home(path, home), content });
+        };
+
+        if let Some(cli_config) = &sources.cli_config {
+            if let Some(content) = read_optional(cli_config)? {
+                add(BackupItemKind::CliConfig, cli_config, content);
+                let cli = read_solana_cli_config(cli_config)
+                    .map_err(|e| BackupError::Parse { path: cli_config.clone(), message: e.to_string() })?;
+                let keypair_path = expand(&cli.keypair_path, home)?;
+                if include_keys {
+                    let content = read_optional(&keypair_path)?.ok_or_else(|| io_error(&keypair_path, "私钥文件不存在"))?;
+                    add(BackupItemKind::Keypair, &keypair_path, content);
+                } else {
+                    excluded_keys.push(collapse_home(&keypair_path, home));
+                }
+            }
+        }
+        if let Some(content) = read_optional(&sources.contacts)? {
+            add(BackupItemKind::Contacts, &sources.contacts, content);
+        }
+        if let Some(content) = read_optional(&sources.follow_cursor)? {
+            add(BackupItemKind::FollowCursor, &sources.follow_cursor, content);
+        }
+        Ok(Self { version: BACKUP_VERSION, items, excluded_keys })
+    }
+
+    /// 是否包含私钥
+    pub fn includes_keys(&self) -> bool {
+        self.items.iter().any(|item| item.kind == BackupItemKind::Keypair)
+    }
+
+    /// 写入备份文件，文件权限设为 0600（即使不含私钥，CLI配置中也可能有带密钥的RPC地址）
+    pub fn save(&self, path: &str) -> Result<(), BackupError> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| io_error(path, e))?;
+        fs::write(path, content).map_err(|e| io_error(path, e))?;
+        restrict_permissions(path)
+    }
+
+    /// 读取备份文件，先检查版本再解析内容
+    ///
+    /// # 错误
+    /// * `BackupError::UnsupportedVersion` - 版本不是 BACKUP_VERSION
+    /// * `BackupError::Parse` - 不是备份文件
+    pub fn load(path: &str) -> Result<Self, BackupError> {
+        let content = fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+        let parse_error = |e: serde_json::Error| BackupError::Parse { path: path.to_string(), message: e.to_string() };
+        let probe: VersionProbe = serde_json::from_str(&content).map_err(parse_error)?;
+        if probe.version != BACKUP_VERSION {
+            return Err(BackupError::UnsupportedVersion { found: probe.version, supported: BACKUP_VERSION });
+        }
+        serde_json::from_str(&content).map_err(parse_error)
+    }
+}
+
+/// 恢复一个文件时的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreChange {
+    /// 文件不存在，新建
+    Create,
+    /// 文件已存在且内容不同，覆盖
+    Overwrite,
+    /// 文件已存在且内容相同
+    Unchanged,
+}
+
+impl fmt::Display for RestoreChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Create => "新建",
+            Self::Overwrite => "覆盖",
+            Self::Unchanged => "不变",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 恢复计划中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreAction {
+    pub kind: BackupItemKind,
+    /// 展开 ~/ 后的实际路径
+    pub path: String,
+    pub change: RestoreChange,
+}
+
+/// 列出恢复时每个文件的变化，不修改任何文件，用于恢复前确认
+///
+/// # 参数
+/// * `bundle` - 备份
+/// * `home` - 恢复到的用户主目录
+///
+/// # 错误
+/// * 备份中有 ~/ 路径但没有主目录，或者已有文件读取失败
+pub fn plan_restore(bundle: &BackupBundle, home: Option<&str>) -> Result<Vec<RestoreAction>, BackupError> {
+    bundle
+        .items
+        .iter()
+        .map(|item| {
+            let path = expand(&item.path, home)?;
+            let change = match read_optional(&path)? {
+                None => RestoreChange::Create,
+                Some(existing) if existing == item.content => RestoreChange::Unchanged,
+                Some(_) => RestoreChange::Overwrite,
+            };
+            Ok(RestoreAction { kind: item.kind, path, change })
+        })
+        .collect()
+}
+
+/// 恢复备份中的文件，目录不存在时自动创建，私钥文件权限设为 0600
+///
+/// # 返回
+/// * `Result<Vec<RestoreAction>, BackupError>` - 每个文件恢复前的变化，和 `plan_restore` 相同
+pub fn apply_restore(bundle: &BackupBundle, home: Option<&str>) -> Result<Vec<RestoreAction>, BackupError> {
+    let actions = plan_restore(bundle, home)?;
+    for (item, action) in bundle.items.iter().zip(&actions) {
+        if action.change != RestoreChange::Unchanged {
+            if let Some(parent) = Path::new(&action.path).parent() {
+                fs::create_dir_all(parent).map_err(|e| io_error(&action.path, e))?;
+            }
+            fs::write(&action.path, &item.content).map_err(|e| io_error(&action.path, e))?;
+        }
+        // 内容不变的私钥也修正权限
+        if item.kind == BackupItemKind::Keypair {
+            restrict_permissions(&action.path)?;
+        }
+    }
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYPAIR: &str = "[1,2,3]";
+    const CONTACTS: &str = "{\"contacts\":{}}";
+
+    /// 用临时目录当作主目录，写入CLI配置、私钥和通讯录
+    fn fake_home(name: &str) -> (String, BackupSources) {
+        let home = std::env::temp_dir().join(format!("gongde-backup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let home = home.to_string_lossy().to_string();
+        let cli_config = format!("{}/.config/solana/cli/config.yml", home);
+        fs::create_dir_all(Path::new(&cli_config).parent().unwrap()).unwrap();
+        fs::write(
+            &cli_config,
+            "json_rpc_url: http://localhost:8899\nkeypair_path: ~/.config/solana/id.json\ncommitment: confirmed\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/.config/solana/id.json", home), KEYPAIR).unwrap();
+        fs::write(format!("{}/contacts.json", home), CONTACTS).unwrap();
+        let sources = BackupSources {
+            cli_config: Some(cli_config),
+            contacts: format!("{}/contacts.json", home),
+            follow_cursor: format!("{}/follow-cursor.json", home),
+        };
+        (home, sources)
+    }
+
+    fn empty_home(name: &str) -> String {
+        let home = std::env::temp_dir().join(format!("gongde-backup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        home.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_round_trip_excludes_keys_by_default() {
+        let (home, sources) = fake_home("source");
+        let bundle = BackupBundle::create(&sources, Some(&home), false).unwrap();
+        assert!(!bundle.includes_keys());
+        assert_eq!(bundle.excluded_keys, ["~/.config/solana/id.json"]);
+        // 主目录下的路径记录为 ~/，跟随进度不存在时跳过
+        let paths: Vec<_> = bundle.items.iter().map(|item| (item.kind, item.path.as_str())).collect();
+        assert_eq!(
+            paths,
+            [(BackupItemKind::CliConfig, "~/.config/solana/cli/config.yml"), (BackupItemKind::Contacts, "~/contacts.json")]
+        );
+
+        let file = format!("{}/backup.json", home);
+        bundle.save(&file).unwrap();
+        let loaded = BackupBundle::load(&file).unwrap();
+        assert_eq!(loaded, bundle);
+
+        // 恢复到新的主目录：先列出计划，恢复后再恢复一次不会有变化
+        let target = empty_home("target");
+        let plan = plan_restore(&loaded, Some(&target)).unwrap();
+        assert!(plan.iter().all(|action| action.change == RestoreChange::Create));
+        assert_eq!(apply_restore(&loaded, Some(&target)).unwrap(), plan);
+        assert_eq!(fs::read_to_string(format!("{}/contacts.json", target)).unwrap(), CONTACTS);
+        assert!(!Path::new(&format!("{}/.config/solana/id.json", target)).exists());
+        let again = apply_restore(&loaded, Some(&target)).unwrap();
+        assert!(again.iter().all(|action| action.change == RestoreChange::Unchanged));
+    }
+
+    #[test]
+    fn test_round_trip_with_keys_restricts_permissions() {
+        let (home, sources) = fake_home("keys");
+        let bundle = BackupBundle::create(&sources, Some(&home), true).unwrap();
+        assert!(bundle.includes_keys());
+        assert!(bundle.excluded_keys.is_empty());
+        let file = format!("{}/backup.json", home);
+        bundle.save(&file).unwrap();
+
+        // 目标主目录中已有不同的通讯录，恢复计划显示覆盖
+        let target = empty_home("keys-target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(format!("{}/contacts.json", target), "{}").unwrap();
+        let loaded = BackupBundle::load(&file).unwrap();
+        let plan = plan_restore(&loaded, Some(&target)).unwrap();
+        let contacts = plan.iter().find(|action| action.kind == BackupItemKind::Contacts).unwrap();
+        assert_eq!(contacts.change, RestoreChange::Overwrite);
+
+        apply_restore(&loaded, Some(&target)).unwrap();
+        let keypair = format!("{}/.config/solana/id.json", target);
+        assert_eq!(fs::read_to_string(&keypair).unwrap(), KEYPAIR);
+        assert_eq!(fs::read_to_string(format!("{}/contacts.json", target)).unwrap(), CONTACTS);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&keypair).unwrap().permissions().mode() & 0o777, 0o600);
+            assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_other_versions() {
+        let home = empty_home("version");
+        fs::create_dir_all(&home).unwrap();
+        let file = format!("{}/backup.json", home);
+        fs::write(&file, "{\"version\": 2, \"files\": []}").unwrap();
+        assert_eq!(BackupBundle::load(&file), Err(BackupError::UnsupportedVersion { found: 2, supported: BACKUP_VERSION }));
+        fs::write(&file, "not json").unwrap();
+        assert!(matches!(BackupBundle::load(&file), Err(BackupError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_collapse_home() {
+        assert_eq!(collapse_home("/home/me/.config/a", Some("/home/me/")), "~/.config/a");
+        assert_eq!(collapse_home("/home/meow/a", Some("/home/me")), "/home/meow/a");
+        assert_eq!(collapse_home("./.config/gongde/contacts.json", Some("/home/me")), "./.config/gongde/contacts.json");
+        assert_eq!(collapse_home("/home/me/a", None), "/home/me/a");
+    }
+}
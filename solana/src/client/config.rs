@@ -0,0 +1,482 @@
+use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signer}};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+/// Solana CLI 配置文件结构
+#[derive(Debug, Deserialize)]
+pub struct SolanaCliConfig {
+    pub json_rpc_url: String,
+    pub keypair_path: String,
+    pub commitment: String,
+}
+
+/// 读取配置、私钥和程序密钥对时的错误
+#[derive(Debug)]
+pub enum ConfigError {
+    /// 所有候选路径下都没有CLI配置文件
+    CliConfigNotFound,
+    /// 所有候选路径下都没有可用的程序密钥对文件
+    ProgramKeypairNotFound,
+    /// 指定的文件不存在
+    FileNotFound(String),
+    /// 读取文件失败
+    Io { path: String, source: std::io::Error },
+    /// 文件内容不是期望的格式
+    Parse { path: String, message: String },
+    /// 密钥对文件不是64个字节
+    InvalidKeypair { path: String, len: usize },
+    /// 路径以 ~/ 开头，但没有设置HOME环境变量
+    HomeNotSet(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CliConfigNotFound => write!(
+                f,
+                "未找到Solana CLI配置文件，请检查以下路径:\n  - ./.config/solana/cli/config.yml\n  - ~/.config/solana/cli/config.yml"
+            ),
+            Self::ProgramKeypairNotFound => write!(
+                f,
+                "未找到程序密钥对文件，请检查以下路径:\n  - {}",
+                PROGRAM_KEYPAIR_PATHS.join("\n  - ")
+            ),
+            Self::FileNotFound(path) => write!(f, "文件不存在: {}", path),
+            Self::Io { path, source } => write!(f, "读取文件失败 {}: {}", path, source),
+            Self::Parse { path, message } => write!(f, "文件格式错误 {}: {}", path, message),
+            Self::InvalidKeypair { path, len } => {
+                write!(f, "密钥对文件格式错误 {}，应包含64个字节，实际包含{}个字节", path, len)
+            }
+            Self::HomeNotSet(path) => write!(f, "未设置HOME环境变量，无法展开路径 {}，请设置HOME或改用绝对路径", path),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// 读取文件内容，区分文件不存在和其他IO错误
+fn read_file(path: &str) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|source| match source.kind() {
+        std::io::ErrorKind::NotFound => ConfigError::FileNotFound(path.to_string()),
+        _ => ConfigError::Io { path: path.to_string(), source },
+    })
+}
+
+/// 解析64字节数组格式的密钥对文件
+fn read_keypair_file(path: &str) -> Result<Keypair, ConfigError> {
+    let key_bytes: Vec<u8> = serde_json::from_str(&read_file(path)?)
+        .map_err(|e| ConfigError::Parse { path: path.to_string(), message: e.to_string() })?;
+    if key_bytes.len() != 64 {
+        return Err(ConfigError::InvalidKeypair { path: path.to_string(), len: key_bytes.len() });
+    }
+    Keypair::try_from(key_bytes.as_slice()).map_err(|e| ConfigError::Parse { path: path.to_string(), message: e.to_string() })
+}
+
+/// 备用RPC节点环境变量，多个地址用逗号分隔
+const BACKUP_RPC_URLS_ENV: &str = "GONGDE_RPC_URLS";
+
+/// 程序配置结构
+#[derive(Debug)]
+pub struct ProgramConfig {
+    pub program_id: Pubkey,
+    pub rpc_url: String,
+    /// 按优先级排列的全部RPC节点，第一个就是 rpc_url
+    pub rpc_urls: Vec<String>,
+    pub keypair: Keypair,
+}
+
+/// 组合主节点和环境变量中配置的备用节点，去掉空白和重复地址
+fn collect_rpc_urls(primary: &str, backups: Option<&str>) -> Vec<String> {
+    let mut urls = vec![primary.to_string()];
+    for url in backups.unwrap_or_default().split(',').map(str::trim) {
+        if !url.is_empty() && !urls.iter().any(|existing| existing == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+/// 读取用户主目录，HOME未设置或为空时返回None
+pub fn home_dir() -> Option<String> {
+    std::env::var("HOME").ok().filter(|home| !home.is_empty())
+}
+
+/// 把 ~/ 开头的路径展开到用户主目录
+///
+/// # 参数
+/// * `path` - 原始路径
+/// * `home` - 用户主目录，None表示未设置
+///
+/// # 错误
+/// * `ConfigError::HomeNotSet` - 路径需要展开但没有主目录，不会拼出 /.config/... 这样的错误路径
+pub fn expand_home(path: &str, home: Option<&str>) -> Result<String, ConfigError> {
+    match path.strip_prefix("~/") {
+        Some(rest) => home
+            .map(|home| format!("{}/{}", home.trim_end_matches('/'), rest))
+            .ok_or_else(|| ConfigError::HomeNotSet(path.to_string())),
+        None => Ok(path.to_string()),
+    }
+}
+
+/// CLI配置文件的候选路径，依次为项目内配置和用户主目录配置
+fn cli_config_candidates(home: Option<&str>) -> Vec<String> {
+    ["./.config/solana/cli/config.yml", "~/.config/solana/cli/config.yml"]
+        .iter()
+        // 没有主目录时跳过主目录下的候选路径
+        .filter_map(|path| expand_home(path, home).ok())
+        .collect()
+}
+
+/// 查找Solana CLI配置文件路径
+/// 依次尝试项目内配置和用户主目录配置
+pub fn find_solana_cli_config_path() -> Option<String> {
+    let home = home_dir();
+    if home.is_none() {
+        warn!("未设置HOME环境变量，只查找项目内的CLI配置文件");
+    }
+    cli_config_candidates(home.as_deref())
+        .into_iter()
+        .find(|path| Path::new(path).exists())
+}
+
+/// 读取指定路径的Solana CLI配置文件
+pub fn read_solana_cli_config(path: &str) -> Result<SolanaCliConfig, ConfigError> {
+    debug!("读取Solana CLI配置文件: {}", path);
+    serde_yaml::from_str(&read_file(path)?)
+        .map_err(|e| ConfigError::Parse { path: path.to_string(), message: e.to_string() })
+}
+
+/// 从Solana CLI配置文件读取配置信息
+/// 支持相对路径和绝对路径的私钥文件
+fn load_solana_cli_config() -> Result<SolanaCliConfig, ConfigError> {
+    // 找不到配置文件直接报错
+    let path = find_solana_cli_config_path().ok_or(ConfigError::CliConfigNotFound)?;
+    read_solana_cli_config(&path)
+}
+
+/// 从私钥JSON文件加载密钥对
+/// JSON文件格式为字节数组，例如: [1, 2, 3, ...]
+pub fn load_keypair_from_file(file_path: &str) -> Result<Keypair, ConfigError> {
+    // 展开 ~/ 开头的路径，相对路径和绝对路径原样使用
+    let full_path = expand_home(file_path, home_dir().as_deref())?;
+    
+    debug!("从文件加载私钥: {}", full_path);
+    
+    let keypair = read_keypair_file(&full_path)?;
+    debug!("成功加载私钥，公钥地址: {}", keypair.pubkey());
+    
+    Ok(keypair)
+}
+
+/// 可能的程序密钥对文件路径
+pub const PROGRAM_KEYPAIR_PATHS: [&str; 3] = [
+    "./target/deploy/gong_de_increase-keypair.json",
+    "./solana/target/deploy/gong_de_increase-keypair.json",
+    "../target/deploy/gong_de_increase-keypair.json",
+];
+
+/// 从程序密钥对文件读取程序ID
+fn read_program_id(path: &str) -> Result<Pubkey, ConfigError> {
+    Ok(read_keypair_file(path)?.pubkey())
+}
+
+/// 从部署文件中读取程序ID
+/// 尝试多个可能的程序密钥对文件路径
+/// 
+/// # 返回
+/// * `Result<(Pubkey, String), ConfigError>` - (程序ID, 密钥对文件路径)
+fn load_program_id() -> Result<(Pubkey, String), ConfigError> {
+    find_program_id(&PROGRAM_KEYPAIR_PATHS)
+}
+
+/// 在给定的候选路径中查找第一个可用的程序密钥对文件
+pub fn find_program_id<P: AsRef<str>>(paths: &[P]) -> Result<(Pubkey, String), ConfigError> {
+    for path in paths.iter().map(AsRef::as_ref) {
+        if Path::new(path).exists() {
+            debug!("从部署文件读取程序ID: {}", path);
+            let program_id = match read_program_id(path) {
+                Ok(program_id) => program_id,
+                Err(_) => continue, // 尝试下一个文件
+            };
+            debug!("成功读取程序ID: {}", program_id);
+            return Ok((program_id, path.to_string()));
+        }
+    }
+    
+    // 找不到程序密钥对文件直接报错
+    Err(ConfigError::ProgramKeypairNotFound)
+}
+
+// ========================================
+// 配置缓存 - 避免每次运行都重新解析CLI配置和部署密钥
+// ========================================
+
+/// 配置缓存文件路径（target目录不进版本库）
+const CONFIG_CACHE_PATH: &str = "./target/gongde-config-cache.json";
+
+/// 可缓存的配置信息，不包含私钥，私钥每次仍然从文件加载
+/// 通过记录来源文件的修改时间判断缓存是否过期，重新部署（程序ID变化）或修改CLI配置都会使缓存失效
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedConfig {
+    pub program_id: String,
+    pub rpc_url: String,
+    pub keypair_path: String,
+    pub cli_config_path: String,
+    pub cli_config_modified: u128,
+    pub program_keypair_path: String,
+    pub program_keypair_modified: u128,
+}
+
+/// 读取文件修改时间（纳秒），文件不存在时返回None
+fn file_modified(path: &str) -> Option<u128> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+impl CachedConfig {
+    /// 重新发现配置并生成缓存
+    fn discover() -> Result<Self, Box<dyn std::error::Error>> {
+        let cli_config_path = find_solana_cli_config_path()
+            .ok_or("未找到Solana CLI配置文件")?;
+        let cli_config = load_solana_cli_config()?;
+        let (program_id, program_keypair_path) = load_program_id()?;
+
+        Ok(Self {
+            program_id: program_id.to_string(),
+            rpc_url: cli_config.json_rpc_url,
+            keypair_path: cli_config.keypair_path,
+            cli_config_modified: file_modified(&cli_config_path).unwrap_or_default(),
+            cli_config_path,
+            program_keypair_modified: file_modified(&program_keypair_path).unwrap_or_default(),
+            program_keypair_path,
+        })
+    }
+
+    /// 来源文件都没有变化时缓存有效
+    pub fn is_fresh(&self) -> bool {
+        file_modified(&self.cli_config_path) == Some(self.cli_config_modified)
+            && file_modified(&self.program_keypair_path) == Some(self.program_keypair_modified)
+    }
+
+    /// 解析缓存中的程序ID
+    pub fn program_id(&self) -> Result<Pubkey, Box<dyn std::error::Error>> {
+        Ok(self.program_id.parse()?)
+    }
+
+    /// 从缓存文件读取，文件不存在或格式错误时返回None
+    pub fn load(path: &str) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// 写入缓存文件
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// 优先使用新鲜的缓存，否则重新发现配置并更新缓存
+fn load_cached_config() -> Result<CachedConfig, Box<dyn std::error::Error>> {
+    if let Some(cached) = CachedConfig::load(CONFIG_CACHE_PATH) {
+        if cached.is_fresh() {
+            debug!("使用配置缓存: {}", CONFIG_CACHE_PATH);
+            return Ok(cached);
+        }
+        debug!("配置缓存已过期，重新读取配置");
+    }
+
+    let cached = CachedConfig::discover()?;
+    if let Err(e) = cached.save(CONFIG_CACHE_PATH) {
+        // 缓存写入失败不影响本次运行
+        warn!("⚠️  写入配置缓存失败: {}", e);
+    }
+    Ok(cached)
+}
+
+/// 只读配置：查询不需要签名，只解析程序ID和RPC地址，不加载用户私钥
+/// 没有本地钱包也可以查询别人的功德
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryConfig {
+    pub program_id: Pubkey,
+    pub rpc_url: String,
+    /// 按优先级排列的全部RPC节点，第一个就是 rpc_url
+    pub rpc_urls: Vec<String>,
+    /// CLI配置中的私钥文件路径，只在需要默认用户时才读取
+    pub keypair_path: String,
+}
+
+impl QueryConfig {
+    /// 由缓存的配置生成只读配置
+    ///
+    /// # 参数
+    /// * `cached` - CLI配置和程序ID
+    /// * `backup_urls` - 备用RPC节点（逗号分隔）
+    pub fn from_cached(cached: CachedConfig, backup_urls: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            program_id: cached.program_id()?,
+            rpc_urls: collect_rpc_urls(&cached.rpc_url, backup_urls),
+            rpc_url: cached.rpc_url,
+            keypair_path: cached.keypair_path,
+        })
+    }
+
+    /// 默认查询的用户：CLI配置中钱包的公钥，这时才读取私钥文件
+    ///
+    /// # 返回
+    /// * `Result<Pubkey, ConfigError>` - 钱包公钥；没有本地钱包时返回错误
+    pub fn default_user(&self) -> Result<Pubkey, ConfigError> {
+        Ok(load_keypair_from_file(&self.keypair_path)?.pubkey())
+    }
+}
+
+/// 初始化只读配置，不加载用户私钥
+pub fn initialize_query_config() -> Result<QueryConfig, Box<dyn std::error::Error>> {
+    let backup_urls = std::env::var(BACKUP_RPC_URLS_ENV).ok();
+    QueryConfig::from_cached(load_cached_config()?, backup_urls.as_deref())
+}
+
+/// 初始化程序配置
+/// 从配置文件和密钥文件中读取所有必要的配置信息
+pub fn initialize_program_config() -> Result<ProgramConfig, Box<dyn std::error::Error>> {
+    initialize_program_config_with_keypair(None)
+}
+
+/// 初始化程序配置（支持自定义私钥文件）
+/// 如果提供了 keypair_file_path，则使用指定的私钥文件；否则从CLI配置读取
+pub fn initialize_program_config_with_keypair(keypair_file_path: Option<&str>) -> Result<ProgramConfig, Box<dyn std::error::Error>> {
+    // 1. 读取CLI配置和程序ID（优先使用缓存）
+    let query = initialize_query_config()?;
+    
+    // 2. 加载用户私钥 - 使用提供的路径或默认路径
+    let keypair = match keypair_file_path {
+        Some(custom_path) => {
+            debug!("使用指定的私钥文件: {}", custom_path);
+            load_keypair_from_file(custom_path)?
+        },
+        None => {
+            debug!("使用CLI配置中的私钥文件: {}", query.keypair_path);
+            load_keypair_from_file(&query.keypair_path)?
+        }
+    };
+    
+    Ok(ProgramConfig {
+        program_id: query.program_id,
+        rpc_url: query.rpc_url,
+        rpc_urls: query.rpc_urls,
+        keypair,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_rpc_urls() {
+        assert_eq!(collect_rpc_urls("http://a", None), vec!["http://a"]);
+        assert_eq!(
+            collect_rpc_urls("http://a", Some(" http://b , ,http://a,http://c")),
+            vec!["http://a", "http://b", "http://c"]
+        );
+    }
+
+    /// 在临时目录中生成CLI配置和程序密钥对文件
+    fn write_fixture_files(dir: &Path, program_keypair: &Keypair) -> (String, String) {
+        fs::create_dir_all(dir).unwrap();
+        let cli_config_path = dir.join("config.yml").to_string_lossy().to_string();
+        fs::write(&cli_config_path, "json_rpc_url: http://localhost:8899\nkeypair_path: ./id.json\ncommitment: confirmed\n").unwrap();
+        let program_keypair_path = dir.join("program-keypair.json").to_string_lossy().to_string();
+        fs::write(&program_keypair_path, serde_json::to_string(&program_keypair.to_bytes().to_vec()).unwrap()).unwrap();
+        (cli_config_path, program_keypair_path)
+    }
+
+    fn cached_config_for(cli_config_path: String, program_keypair_path: String) -> CachedConfig {
+        CachedConfig {
+            program_id: read_program_id(&program_keypair_path).unwrap().to_string(),
+            rpc_url: "http://localhost:8899".to_string(),
+            keypair_path: "./id.json".to_string(),
+            cli_config_modified: file_modified(&cli_config_path).unwrap(),
+            cli_config_path,
+            program_keypair_modified: file_modified(&program_keypair_path).unwrap(),
+            program_keypair_path,
+        }
+    }
+
+    #[test]
+    fn test_cached_config_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("gongde-cache-roundtrip-{}", std::process::id()));
+        let program_keypair = Keypair::new();
+        let (cli_config_path, program_keypair_path) = write_fixture_files(&dir, &program_keypair);
+        let cached = cached_config_for(cli_config_path, program_keypair_path);
+
+        let cache_path = dir.join("cache.json").to_string_lossy().to_string();
+        cached.save(&cache_path).unwrap();
+        let loaded = CachedConfig::load(&cache_path).unwrap();
+
+        assert_eq!(loaded, cached);
+        assert_eq!(loaded.program_id().unwrap(), program_keypair.pubkey());
+        assert!(loaded.is_fresh());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cached_config_invalidated_when_program_id_changes() {
+        let dir = std::env::temp_dir().join(format!("gongde-cache-invalidate-{}", std::process::id()));
+        let (cli_config_path, program_keypair_path) = write_fixture_files(&dir, &Keypair::new());
+        let cached = cached_config_for(cli_config_path, program_keypair_path.clone());
+        assert!(cached.is_fresh());
+
+        // 重新部署生成新的程序密钥对，修改时间随之变化
+        let (_, program_keypair_path) = write_fixture_files(&dir, &Keypair::new());
+        let file = fs::File::options().write(true).open(&program_keypair_path).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1)).unwrap();
+        assert!(!cached.is_fresh());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_query_config_without_keypair_file() {
+        let dir = std::env::temp_dir().join(format!("gongde-query-config-{}", std::process::id()));
+        let program_keypair = Keypair::new();
+        let (cli_config_path, program_keypair_path) = write_fixture_files(&dir, &program_keypair);
+        // CLI配置中的私钥文件不存在（没有本地钱包）
+        let mut cached = cached_config_for(cli_config_path, program_keypair_path);
+        cached.keypair_path = dir.join("missing-id.json").to_string_lossy().to_string();
+
+        let config = QueryConfig::from_cached(cached, Some("http://backup")).unwrap();
+        assert_eq!(config.program_id, program_keypair.pubkey());
+        assert_eq!(config.rpc_urls, vec!["http://localhost:8899", "http://backup"]);
+        // 只有需要默认用户时才读取私钥文件
+        assert!(matches!(config.default_user(), Err(ConfigError::FileNotFound(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expand_home_without_home() {
+        assert_eq!(expand_home("~/id.json", Some("/home/user/")).unwrap(), "/home/user/id.json");
+        assert_eq!(expand_home("./id.json", None).unwrap(), "./id.json");
+        // 没有主目录时报错，而不是拼出 /id.json
+        assert!(matches!(expand_home("~/id.json", None), Err(ConfigError::HomeNotSet(path)) if path == "~/id.json"));
+        assert_eq!(cli_config_candidates(None), vec!["./.config/solana/cli/config.yml"]);
+        assert_eq!(
+            cli_config_candidates(Some("/home/user")),
+            vec!["./.config/solana/cli/config.yml", "/home/user/.config/solana/cli/config.yml"]
+        );
+    }
+
+    #[test]
+    fn test_missing_cache_file() {
+        assert_eq!(CachedConfig::load("./target/does-not-exist.json"), None);
+    }
+}
@@ -0,0 +1,298 @@
+// ========================================
+// 通讯录 - 给常查的用户起个短名字
+// 📇 名字和公钥保存在本地文件中，查询、背书等命令里需要用户公钥的地方都可以写 @名字
+// 增删联系人的命令行在 examples/contacts.rs
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::{ParsePubkeyError, Pubkey};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// 通讯录文件默认路径，与项目内部的CLI配置放在一起（不进版本库）
+pub const DEFAULT_CONTACTS_PATH: &str = "./.config/gongde/contacts.json";
+
+/// 指定通讯录文件路径的环境变量
+pub const CONTACTS_PATH_ENV: &str = "GONGDE_CONTACTS";
+
+/// 名字解析失败时最多列出的相近名字
+const MAX_SUGGESTIONS: usize = 5;
+
+/// 通讯录操作失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContactsError {
+    /// 名字为空，或包含空白字符和 @
+    InvalidName(String),
+    /// 公钥不是有效的Base58编码
+    InvalidPubkey { input: String, message: String },
+    /// 通讯录中没有这个名字，附带相近的名字
+    UnknownName { name: String, suggestions: Vec<String> },
+    /// 名字前缀匹配到多个联系人
+    AmbiguousName { name: String, candidates: Vec<String> },
+    /// 读写通讯录文件失败
+    Io { path: String, message: String },
+}
+
+impl fmt::Display for ContactsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "联系人名字 \"{}\" 无效，不能为空，也不能包含空白字符和 @", name),
+            Self::InvalidPubkey { input, message } => write!(f, "公钥 \"{}\" 无效: {}", input, message),
+            Self::UnknownName { name, suggestions } if suggestions.is_empty() => {
+                write!(f, "通讯录中没有 @{}，可以用 `cargo run --features client --example contacts -- list` 查看", name)
+            }
+            Self::UnknownName { name, suggestions } => {
+                write!(f, "通讯录中没有 @{}，您是不是要找: @{}", name, suggestions.join(", @"))
+            }
+            Self::AmbiguousName { name, candidates } => {
+                write!(f, "@{} 对应多个联系人: @{}，请写出完整的名字", name, candidates.join(", @"))
+            }
+            Self::Io { path, message } => write!(f, "读写通讯录 {} 失败: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for ContactsError {}
+
+/// 通讯录：名字到用户公钥，按名字排序保存
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressBook {
+    /// 名字 -> Base58编码的公钥
+    contacts: BTreeMap<String, String>,
+}
+
+impl AddressBook {
+    /// 从文件读取通讯录，文件不存在时返回空通讯录
+    ///
+    /// # 错误
+    /// * `ContactsError::Io` - 文件存在但读取失败或格式错误
+    pub fn load(path: &str) -> Result<Self, ContactsError> {
+        let io_error = |message: String| ContactsError::Io { path: path.to_string(), message };
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| io_error(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(io_error(e.to_string())),
+        }
+    }
+
+    /// 写入通讯录文件，目录不存在时自动创建
+    pub fn save(&self, path: &str) -> Result<(), ContactsError> {
+        let io_error = |message: String| ContactsError::Io { path: path.to_string(), message };
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error(e.to_string()))?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| io_error(e.to_string()))?;
+        fs::write(path, content).map_err(|e| io_error(e.to_string()))
+    }
+
+    /// 添加或更新联系人
+    ///
+    /// # 返回
+    /// * `Result<Option<Pubkey>, ContactsError>` - 名字已存在时返回原来的公钥
+    ///
+    /// # 错误
+    /// * `ContactsError::InvalidName` - 名字为空或包含空白字符和 @
+    pub fn add(&mut self, name: &str, pubkey: Pubkey) -> Result<Option<Pubkey>, ContactsError> {
+        if name.is_empty() || name.contains('@') || name.chars().any(char::is_whitespace) {
+            return Err(ContactsError::InvalidName(name.to_string()));
+        }
+        let previous = self.contacts.insert(name.to_string(), pubkey.to_string());
+        Ok(previous.and_then(|pubkey| pubkey.parse().ok()))
+    }
+
+    /// 删除联系人，返回被删除的公钥，名字不存在时返回None
+    pub fn remove(&mut self, name: &str) -> Option<Pubkey> {
+        self.contacts.remove(name).and_then(|pubkey| pubkey.parse().ok())
+    }
+
+    /// 通讯录是否为空
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+
+    /// 按名字排序的全部联系人，文件中被手动改坏的公钥跳过
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Pubkey)> {
+        self.contacts
+            .iter()
+            .filter_map(|(name, pubkey)| pubkey.parse().ok().map(|pubkey| (name.as_str(), pubkey)))
+    }
+
+    /// 按名字查找联系人：完全相同的名字优先，否则接受唯一的前缀
+    ///
+    /// # 错误
+    /// * `ContactsError::AmbiguousName` - 前缀匹配到多个联系人
+    /// * `ContactsError::UnknownName` - 没有匹配的联系人，附带相近的名字
+    /// * `ContactsError::InvalidPubkey` - 文件中保存的公钥被改坏
+    pub fn lookup(&self, name: &str) -> Result<Pubkey, ContactsError> {
+        let stored = match self.contacts.get(name) {
+            Some(pubkey) => pubkey,
+            None => {
+                let candidates: Vec<&String> = self.contacts.keys().filter(|candidate| candidate.starts_with(name)).collect();
+                match candidates.as_slice() {
+                    [only] => &self.contacts[*only],
+                    [] => {
+                        return Err(ContactsError::UnknownName { name: name.to_string(), suggestions: self.suggestions(name) })
+                    }
+                    _ => {
+                        return Err(ContactsError::AmbiguousName {
+                            name: name.to_string(),
+                            candidates: candidates.into_iter().cloned().collect(),
+                        })
+                    }
+                }
+            }
+        };
+        parse_pubkey(stored)
+    }
+
+    /// 把命令行中的用户参数解析为公钥：@名字 查通讯录，否则按Base58公钥解析
+    pub fn resolve(&self, input: &str) -> Result<Pubkey, ContactsError> {
+        match input.strip_prefix('@') {
+            Some(name) => self.lookup(name),
+            None => parse_pubkey(input),
+        }
+    }
+
+    /// 与输入相近的名字：包含输入或编辑距离不超过2，按距离排序
+    pub fn suggestions(&self, name: &str) -> Vec<String> {
+        let mut scored: Vec<(usize, &String)> = self
+            .contacts
+            .keys()
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|(distance, candidate)| *distance <= 2 || candidate.contains(name))
+            .collect();
+        scored.sort();
+        scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate.clone()).collect()
+    }
+}
+
+/// 解析Base58公钥
+fn parse_pubkey(input: &str) -> Result<Pubkey, ContactsError> {
+    input.parse().map_err(|e: ParsePubkeyError| ContactsError::InvalidPubkey { input: input.to_string(), message: e.to_string() })
+}
+
+/// 两个名字之间的编辑距离（按字符计算）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 通讯录文件路径：优先使用 GONGDE_CONTACTS 环境变量
+pub fn contacts_path() -> String {
+    std::env::var(CONTACTS_PATH_ENV).unwrap_or_else(|_| DEFAULT_CONTACTS_PATH.to_string())
+}
+
+/// 解析命令行中的用户参数，只有 @名字 才读取通讯录文件
+///
+/// # 参数
+/// * `input` - Base58公钥或 @名字
+///
+/// # 错误
+/// * `ContactsError` - 公钥无效、名字不存在或匹配到多个联系人
+pub fn resolve_user(input: &str) -> Result<Pubkey, ContactsError> {
+    if input.starts_with('@') {
+        AddressBook::load(&contacts_path())?.resolve(input)
+    } else {
+        parse_pubkey(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("gongde-contacts-{}-{}", name, std::process::id()))
+            .join("contacts.json")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_add_remove_persist() {
+        let path = temp_path("persist");
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+
+        // 文件不存在时是空通讯录
+        let mut book = AddressBook::load(&path).unwrap();
+        assert_eq!(book.iter().count(), 0);
+        assert_eq!(book.add("alice", alice), Ok(None));
+        assert_eq!(book.add("bob", bob), Ok(None));
+        book.save(&path).unwrap();
+
+        let mut reloaded = AddressBook::load(&path).unwrap();
+        assert_eq!(reloaded, book);
+        assert_eq!(reloaded.iter().collect::<Vec<_>>(), vec![("alice", alice), ("bob", bob)]);
+
+        // 同名覆盖返回原来的公钥
+        let alice2 = Pubkey::new_unique();
+        assert_eq!(reloaded.add("alice", alice2), Ok(Some(alice)));
+        assert_eq!(reloaded.remove("bob"), Some(bob));
+        assert_eq!(reloaded.remove("bob"), None);
+        reloaded.save(&path).unwrap();
+        assert_eq!(AddressBook::load(&path).unwrap().iter().collect::<Vec<_>>(), vec![("alice", alice2)]);
+
+        fs::remove_dir_all(Path::new(&path).parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        let mut book = AddressBook::default();
+        for name in ["", "@alice", "al ice"] {
+            assert_eq!(book.add(name, Pubkey::new_unique()), Err(ContactsError::InvalidName(name.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_resolve_prefix_and_ambiguity() {
+        let mut book = AddressBook::default();
+        let (alice, alina, bob) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        book.add("alice", alice).unwrap();
+        book.add("alina", alina).unwrap();
+        book.add("bob", bob).unwrap();
+        book.add("bo", alice).unwrap();
+
+        assert_eq!(book.resolve(&bob.to_string()), Ok(bob));
+        assert_eq!(book.resolve("@alice"), Ok(alice));
+        // 唯一前缀可以省略后面的字符
+        assert_eq!(book.resolve("@alic"), Ok(alice));
+        // 完全相同的名字优先于前缀
+        assert_eq!(book.resolve("@bo"), Ok(alice));
+        assert_eq!(
+            book.resolve("@al"),
+            Err(ContactsError::AmbiguousName { name: "al".to_string(), candidates: vec!["alice".to_string(), "alina".to_string()] })
+        );
+        assert!(matches!(book.resolve("not-a-pubkey"), Err(ContactsError::InvalidPubkey { .. })));
+    }
+
+    #[test]
+    fn test_unknown_name_lists_close_matches() {
+        let mut book = AddressBook::default();
+        for name in ["alice", "alina", "bob"] {
+            book.add(name, Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            book.resolve("@alcie"),
+            Err(ContactsError::UnknownName { name: "alcie".to_string(), suggestions: vec!["alice".to_string()] })
+        );
+        let err = book.resolve("@bbo").unwrap_err();
+        assert_eq!(err.to_string(), "通讯录中没有 @bbo，您是不是要找: @bob");
+        assert_eq!(
+            book.resolve("@zed"),
+            Err(ContactsError::UnknownName { name: "zed".to_string(), suggestions: vec![] })
+        );
+    }
+}
@@ -0,0 +1,200 @@
+// ========================================
+// 定时增加功德的调度模块 - 测试累计收益、连续打卡等功能时按固定间隔自动增加功德
+// ⏰ 成功后按固定节拍等到下一次，错过的节拍直接跳过，不会集中补发；
+// 失败后指数退避重试，节点提示了等待时间（RPC限流）时按提示等待，连续失败太多次就放弃
+// 调度只依赖传入的时间，方便不连网络做单元测试
+// ========================================
+
+use std::time::{Duration, Instant};
+
+/// 未指定 --interval 时两次增加之间的秒数
+pub const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// 第一次失败后的等待时间，之后每次翻倍
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 退避等待的上限
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 连续失败这么多次后放弃，避免节点或配置有问题时无限重试
+pub const MAX_CONSECUTIVE_FAILURES: u32 = 10;
+
+/// 从命令行参数解析的定时配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaemonOptions {
+    /// 两次增加之间的间隔
+    pub interval: Duration,
+    /// 成功增加这么多次后退出，None 表示一直运行
+    pub max_iterations: Option<u64>,
+}
+
+impl DaemonOptions {
+    /// 解析 `--interval <秒>` 和 `--max-iterations <次数>`
+    ///
+    /// # 错误
+    /// * 缺少参数值、不是正整数时返回错误
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let value = |flag: &str| -> Result<Option<u64>, String> {
+            let Some(index) = args.iter().position(|arg| arg == flag) else {
+                return Ok(None);
+            };
+            let value = args.get(index + 1).ok_or(format!("{} 需要指定一个正整数", flag))?;
+            match value.parse::<u64>() {
+                Ok(value) if value > 0 => Ok(Some(value)),
+                _ => Err(format!("{} 需要指定一个正整数，实际是 {}", flag, value)),
+            }
+        };
+        Ok(Self {
+            interval: Duration::from_secs(value("--interval")?.unwrap_or(DEFAULT_INTERVAL_SECS)),
+            max_iterations: value("--max-iterations")?,
+        })
+    }
+}
+
+/// 失败后的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// 等待这么久后重试
+    RetryAfter(Duration),
+    /// 连续失败次数达到上限，放弃
+    GiveUp,
+}
+
+/// 定时增加功德的调度：记录固定节拍、下一次执行的时间、成功次数和连续失败次数
+#[derive(Debug, Clone)]
+pub struct DaemonSchedule {
+    options: DaemonOptions,
+    /// 最近一次应当执行的节拍，失败重试不会改变节拍
+    tick: Instant,
+    next_run: Instant,
+    completed: u64,
+    failures: u32,
+}
+
+impl DaemonSchedule {
+    /// 创建调度，第一次在 `start` 立即执行
+    pub fn new(options: DaemonOptions, start: Instant) -> Self {
+        Self { options, tick: start, next_run: start, completed: 0, failures: 0 }
+    }
+
+    /// 已经成功增加的次数
+    pub fn completed(&self) -> u64 {
+        self.completed
+    }
+
+    /// 达到 --max-iterations 后结束
+    pub fn is_finished(&self) -> bool {
+        self.options.max_iterations.is_some_and(|max| self.completed >= max)
+    }
+
+    /// 距离下一次执行还要等待的时间，已经到时间时为0
+    pub fn wait(&self, now: Instant) -> Duration {
+        self.next_run.saturating_duration_since(now)
+    }
+
+    /// 记录一次成功：清零连续失败次数，下一次对齐到固定节拍，执行太慢错过的节拍直接跳过
+    pub fn record_success(&mut self, now: Instant) {
+        self.completed += 1;
+        self.failures = 0;
+        self.tick += self.options.interval;
+        if self.tick <= now {
+            let behind = now.duration_since(self.tick).as_nanos() / self.options.interval.as_nanos();
+            self.tick += self.options.interval * (behind as u32 + 1);
+        }
+        self.next_run = self.tick;
+    }
+
+    /// 记录一次失败，决定多久后重试
+    ///
+    /// # 参数
+    /// * `now` - 失败的时间
+    /// * `hint` - 节点提示的等待时间（如RPC限流的 retry after），优先使用
+    ///
+    /// # 返回
+    /// * `RetryDecision` - 重试前的等待时间，或者连续失败太多次后放弃
+    pub fn record_failure(&mut self, now: Instant, hint: Option<Duration>) -> RetryDecision {
+        self.failures += 1;
+        if self.failures >= MAX_CONSECUTIVE_FAILURES {
+            return RetryDecision::GiveUp;
+        }
+        let delay = hint
+            .unwrap_or_else(|| BASE_BACKOFF.saturating_mul(1 << (self.failures - 1).min(16)))
+            .min(MAX_BACKOFF);
+        self.next_run = now + delay;
+        RetryDecision::RetryAfter(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(interval_secs: u64, max_iterations: Option<u64>) -> DaemonOptions {
+        DaemonOptions { interval: Duration::from_secs(interval_secs), max_iterations }
+    }
+
+    #[test]
+    fn test_success_follows_fixed_schedule_and_skips_missed_ticks() {
+        let start = Instant::now();
+        let mut schedule = DaemonSchedule::new(options(10, None), start);
+        assert_eq!(schedule.wait(start), Duration::ZERO);
+
+        // 发送花了2秒，下一次仍然在第10秒
+        schedule.record_success(start + Duration::from_secs(2));
+        assert_eq!(schedule.wait(start + Duration::from_secs(2)), Duration::from_secs(8));
+
+        // 第10秒开始的这次花了25秒，第20、30秒的节拍跳过，下一次在第40秒
+        schedule.record_success(start + Duration::from_secs(35));
+        assert_eq!(schedule.wait(start + Duration::from_secs(35)), Duration::from_secs(5));
+        assert_eq!(schedule.completed(), 2);
+    }
+
+    #[test]
+    fn test_failure_backs_off_and_resets_after_success() {
+        let start = Instant::now();
+        let mut schedule = DaemonSchedule::new(options(60, None), start);
+
+        let delays: Vec<_> = (0..4).map(|_| schedule.record_failure(start, None)).collect();
+        let expected = [1, 2, 4, 8].map(|secs| RetryDecision::RetryAfter(Duration::from_secs(secs)));
+        assert_eq!(delays, expected);
+        assert_eq!(schedule.wait(start), Duration::from_secs(8));
+
+        // 节点提示的等待时间优先，但不超过上限
+        assert_eq!(schedule.record_failure(start, Some(Duration::from_secs(3))), RetryDecision::RetryAfter(Duration::from_secs(3)));
+        assert_eq!(schedule.record_failure(start, Some(Duration::from_secs(3600))), RetryDecision::RetryAfter(MAX_BACKOFF));
+
+        // 重试成功后回到原来的节拍，退避从头开始，失败的尝试不计入次数
+        schedule.record_success(start + Duration::from_secs(5));
+        assert_eq!(schedule.completed(), 1);
+        assert_eq!(schedule.wait(start), Duration::from_secs(60));
+        assert_eq!(schedule.record_failure(start, None), RetryDecision::RetryAfter(BASE_BACKOFF));
+    }
+
+    #[test]
+    fn test_gives_up_after_consecutive_failures() {
+        let start = Instant::now();
+        let mut schedule = DaemonSchedule::new(options(60, None), start);
+        for _ in 1..MAX_CONSECUTIVE_FAILURES {
+            assert!(matches!(schedule.record_failure(start, None), RetryDecision::RetryAfter(_)));
+        }
+        assert_eq!(schedule.record_failure(start, None), RetryDecision::GiveUp);
+    }
+
+    #[test]
+    fn test_max_iterations_and_args() {
+        let args: Vec<String> = ["daemon", "--interval", "5", "--max-iterations", "2"].map(String::from).to_vec();
+        let parsed = DaemonOptions::from_args(&args).unwrap();
+        assert_eq!(parsed, options(5, Some(2)));
+
+        let start = Instant::now();
+        let mut schedule = DaemonSchedule::new(parsed, start);
+        schedule.record_success(start);
+        assert!(!schedule.is_finished());
+        schedule.record_success(start);
+        assert!(schedule.is_finished());
+
+        assert_eq!(DaemonOptions::from_args(&[]).unwrap(), options(DEFAULT_INTERVAL_SECS, None));
+        assert!(DaemonOptions::from_args(&["--interval".to_string(), "0".to_string()]).is_err());
+        assert!(DaemonOptions::from_args(&["--max-iterations".to_string()]).is_err());
+    }
+}
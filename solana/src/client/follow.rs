@@ -0,0 +1,446 @@
+// ========================================
+// 功德变化跟随模块 - 给索引器用，每笔交易至少送达一次，重启后从上次的进度继续
+// 📡 先订阅本程序的交易日志，再用 getSignaturesForAddress 补齐进度之后错过的交易，
+// 然后转入实时订阅；补齐和订阅重叠的交易按签名去重。进度保存在本地JSON文件中
+// 合并逻辑只依赖交易历史和订阅通知，方便用预设的历史做单元测试
+// ========================================
+
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    pubsub_client::{PubsubClient, PubsubLogsClientSubscription},
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::RpcLogsResponse,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::{HashSet, VecDeque};
+
+use super::utils::{decode_merit_changes, fetch_merit_changes, websocket_url};
+use crate::utils::MeritChangeEvent;
+
+/// 进度文件默认路径，与通讯录放在一起（不进版本库）
+pub const DEFAULT_CURSOR_PATH: &str = "./.config/gongde/follow-cursor.json";
+
+/// 指定进度文件路径的环境变量
+pub const CURSOR_PATH_ENV: &str = "GONGDE_FOLLOW_CURSOR";
+
+/// getSignaturesForAddress 每页最多返回的签名数
+const SIGNATURE_PAGE_LIMIT: usize = 1000;
+
+/// 去重时记住的最近签名数，补齐和实时订阅的重叠远小于这个范围
+const SEEN_SIGNATURES: usize = 4096;
+
+/// 进度文件路径：优先使用 GONGDE_FOLLOW_CURSOR 环境变量
+pub fn cursor_path() -> String {
+    std::env::var(CURSOR_PATH_ENV).unwrap_or_else(|_| DEFAULT_CURSOR_PATH.to_string())
+}
+
+/// 跟随进度：最后处理完的交易
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FollowCursor {
+    /// 交易所在的slot
+    pub slot: u64,
+    /// 交易签名，补齐时只查询比它新的交易
+    pub signature: Signature,
+}
+
+impl FollowCursor {
+    /// 生成进度文件的JSON
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!({ "slot": self.slot, "signature": self.signature.to_string() })
+    }
+
+    /// 解析进度文件的JSON
+    ///
+    /// # 错误
+    /// * 缺少字段或签名无效时返回错误
+    pub fn from_json(json: &serde_json::Value) -> Result<Self, String> {
+        let slot = json["slot"].as_u64().ok_or("进度缺少 slot 字段")?;
+        let signature = json["signature"]
+            .as_str()
+            .ok_or("进度缺少 signature 字段")?
+            .parse()
+            .map_err(|e| format!("进度中的交易签名无效: {}", e))?;
+        Ok(Self { slot, signature })
+    }
+
+    /// 写入进度文件，目录不存在时自动创建
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.to_json())?)?;
+        Ok(())
+    }
+
+    /// 读取进度文件，文件不存在时返回None（第一次运行）
+    pub fn read(path: &str) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let json: serde_json::Value = serde_json::from_str(&content)?;
+        Ok(Some(Self::from_json(&json).map_err(|e| format!("{}: {}", path, e))?))
+    }
+}
+
+/// 一笔交易造成的功德变化，跟随输出的单位
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeritChangeRecord {
+    pub signature: Signature,
+    pub slot: u64,
+    /// 按第一次修改的顺序排列，没有改动功德的交易为空
+    pub changes: Vec<MeritChangeEvent>,
+}
+
+impl MeritChangeRecord {
+    /// 处理完这笔交易之后的进度
+    pub fn cursor(&self) -> FollowCursor {
+        FollowCursor { slot: self.slot, signature: self.signature }
+    }
+}
+
+/// 交易历史中的一条签名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub signature: Signature,
+    pub slot: u64,
+    /// 交易执行失败，没有生效
+    pub failed: bool,
+}
+
+/// 本程序的交易历史，测试中用预设的历史代替RPC节点
+pub trait ProgramHistory {
+    /// 比 before 旧、比 until 新的签名，新的在前，与 getSignaturesForAddress 一致
+    fn signatures_between(
+        &self,
+        program_id: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<HistoryEntry>>;
+
+    /// 一笔交易改动了哪些功德账户
+    fn merit_changes(&self, signature: &Signature, program_id: &Pubkey) -> ClientResult<Vec<MeritChangeEvent>>;
+}
+
+impl ProgramHistory for RpcClient {
+    fn signatures_between(
+        &self,
+        program_id: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<HistoryEntry>> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(limit),
+            commitment: Some(self.commitment()),
+        };
+        self.get_signatures_for_address_with_config(program_id, config)?
+            .into_iter()
+            .map(|status| {
+                let signature = status
+                    .signature
+                    .parse()
+                    .map_err(|e| ClientErrorKind::Custom(format!("节点返回的交易签名无效 {}: {}", status.signature, e)))?;
+                Ok(HistoryEntry { signature, slot: status.slot, failed: status.err.is_some() })
+            })
+            .collect()
+    }
+
+    fn merit_changes(&self, signature: &Signature, program_id: &Pubkey) -> ClientResult<Vec<MeritChangeEvent>> {
+        fetch_merit_changes(self, signature, program_id)
+    }
+}
+
+/// 补齐进度之后的全部交易，旧的在前；失败的交易没有生效，直接跳过
+///
+/// # 参数
+/// * `history` - 交易历史，通常是RPC客户端
+/// * `program_id` - 本合约的程序ID
+/// * `cursor` - 上次处理到的交易
+///
+/// # 返回
+/// * `ClientResult<Vec<MeritChangeRecord>>` - 进度之后的交易，包括没有改动功德的交易，方便推进进度
+pub fn backfill<H: ProgramHistory + ?Sized>(
+    history: &H,
+    program_id: &Pubkey,
+    cursor: &FollowCursor,
+) -> ClientResult<Vec<MeritChangeRecord>> {
+    // 节点每页按从新到旧返回，翻页直到碰到进度
+    let mut entries = Vec::new();
+    let mut before = None;
+    loop {
+        let page = history.signatures_between(program_id, before, Some(cursor.signature), SIGNATURE_PAGE_LIMIT)?;
+        let full = page.len() == SIGNATURE_PAGE_LIMIT;
+        before = page.last().map(|entry| entry.signature);
+        entries.extend(page);
+        if !full {
+            break;
+        }
+    }
+    entries
+        .into_iter()
+        .rev()
+        .filter(|entry| !entry.failed)
+        .map(|entry| {
+            Ok(MeritChangeRecord {
+                signature: entry.signature,
+                slot: entry.slot,
+                changes: history.merit_changes(&entry.signature, program_id)?,
+            })
+        })
+        .collect()
+}
+
+/// 把一条交易日志订阅通知转成功德变化
+///
+/// # 参数
+/// * `slot` - 通知所在的slot
+/// * `logs` - 通知内容
+/// * `program_id` - 本合约的程序ID
+///
+/// # 返回
+/// * `Option<MeritChangeRecord>` - 失败的交易或签名无效时返回None
+pub fn record_from_logs(slot: u64, logs: &RpcLogsResponse, program_id: &Pubkey) -> Option<MeritChangeRecord> {
+    if logs.err.is_some() {
+        return None;
+    }
+    let signature = logs.signature.parse().ok()?;
+    Some(MeritChangeRecord { signature, slot, changes: decode_merit_changes(&logs.logs, program_id) })
+}
+
+/// 最近输出过的签名，补齐和实时订阅重叠的交易只输出一次
+#[derive(Debug, Default)]
+pub struct SeenSignatures {
+    order: VecDeque<Signature>,
+    seen: HashSet<Signature>,
+}
+
+impl SeenSignatures {
+    /// 记住一个签名，只保留最近 SEEN_SIGNATURES 个
+    ///
+    /// # 返回
+    /// * `bool` - 第一次见到时返回true
+    pub fn insert(&mut self, signature: Signature) -> bool {
+        if !self.seen.insert(signature) {
+            return false;
+        }
+        self.order.push_back(signature);
+        if self.order.len() > SEEN_SIGNATURES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// 合并后的功德变化流：先输出补齐的交易，再输出实时订阅收到的交易，按签名去重
+/// 调用方每处理完一条就保存 `record.cursor()`，中途退出时下次从这里重新补齐，保证至少送达一次
+pub struct Follower<L> {
+    program_id: Pubkey,
+    backfilled: VecDeque<MeritChangeRecord>,
+    live: L,
+    seen: SeenSignatures,
+}
+
+impl<L: Iterator<Item = (u64, RpcLogsResponse)>> Follower<L> {
+    /// # 参数
+    /// * `program_id` - 本合约的程序ID
+    /// * `backfilled` - `backfill` 补齐的交易，旧的在前
+    /// * `live` - 订阅通知 (slot, 日志)，必须在补齐之前开始订阅，否则两者之间的交易会丢失
+    pub fn new(program_id: Pubkey, backfilled: Vec<MeritChangeRecord>, live: L) -> Self {
+        let mut seen = SeenSignatures::default();
+        for record in &backfilled {
+            seen.insert(record.signature);
+        }
+        Self { program_id, backfilled: backfilled.into(), live, seen }
+    }
+}
+
+impl<L: Iterator<Item = (u64, RpcLogsResponse)>> Iterator for Follower<L> {
+    type Item = MeritChangeRecord;
+
+    /// 订阅断开时返回None，重新运行会从保存的进度补齐
+    fn next(&mut self) -> Option<MeritChangeRecord> {
+        if let Some(record) = self.backfilled.pop_front() {
+            return Some(record);
+        }
+        for (slot, logs) in self.live.by_ref() {
+            let Some(record) = record_from_logs(slot, &logs, &self.program_id) else {
+                continue;
+            };
+            if self.seen.insert(record.signature) {
+                return Some(record);
+            }
+        }
+        None
+    }
+}
+
+/// 实时订阅的通知
+pub type LiveLogs = Box<dyn Iterator<Item = (u64, RpcLogsResponse)>>;
+
+/// 开始跟随本程序的功德变化：先订阅交易日志，有进度时再补齐进度之后的交易
+///
+/// # 参数
+/// * `client` - RPC客户端，WebSocket地址由RPC地址推导
+/// * `program_id` - 本合约的程序ID
+/// * `cursor` - 上次保存的进度，None 表示从现在开始
+///
+/// # 返回
+/// * `ClientResult<(PubsubLogsClientSubscription, Follower<LiveLogs>)>` - 订阅（结束时调用 shutdown）和合并后的变化流
+pub fn follow(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    cursor: Option<&FollowCursor>,
+) -> ClientResult<(PubsubLogsClientSubscription, Follower<LiveLogs>)> {
+    let ws_url = websocket_url(&client.url());
+    let filter = RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]);
+    let config = RpcTransactionLogsConfig { commitment: Some(client.commitment()) };
+    let (subscription, receiver) = PubsubClient::logs_subscribe(&ws_url, filter, config)
+        .map_err(|e| ClientErrorKind::Custom(format!("订阅交易日志失败 {}: {}", ws_url, e)))?;
+    let backfilled = match cursor {
+        Some(cursor) => backfill(client, program_id, cursor)?,
+        None => Vec::new(),
+    };
+    let live: LiveLogs = Box::new(receiver.into_iter().map(|response| (response.context.slot, response.value)));
+    Ok((subscription, Follower::new(*program_id, backfilled, live)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use crate::utils::MERIT_CHANGE_EVENT_TAG;
+    use std::collections::HashMap;
+
+    /// 预设的交易历史：按时间顺序排列，每笔交易改动一个账户
+    struct FixtureHistory {
+        entries: Vec<HistoryEntry>,
+        changes: HashMap<Signature, Vec<MeritChangeEvent>>,
+    }
+
+    impl FixtureHistory {
+        fn new(count: usize, account: Pubkey) -> Self {
+            let mut entries = Vec::new();
+            let mut changes = HashMap::new();
+            for index in 0..count {
+                let signature = Signature::new_unique();
+                entries.push(HistoryEntry { signature, slot: 100 + index as u64, failed: false });
+                let value = index as u32;
+                changes.insert(signature, vec![MeritChangeEvent { account, before: value, after: value + 1 }]);
+            }
+            Self { entries, changes }
+        }
+    }
+
+    impl ProgramHistory for FixtureHistory {
+        fn signatures_between(
+            &self,
+            _program_id: &Pubkey,
+            before: Option<Signature>,
+            until: Option<Signature>,
+            limit: usize,
+        ) -> ClientResult<Vec<HistoryEntry>> {
+            let position = |signature: Option<Signature>| {
+                signature.and_then(|signature| self.entries.iter().position(|entry| entry.signature == signature))
+            };
+            let end = position(before).unwrap_or(self.entries.len());
+            let start = position(until).map_or(0, |index| index + 1);
+            Ok(self.entries[start..end.max(start)].iter().rev().take(limit).copied().collect())
+        }
+
+        fn merit_changes(&self, signature: &Signature, _program_id: &Pubkey) -> ClientResult<Vec<MeritChangeEvent>> {
+            Ok(self.changes.get(signature).cloned().unwrap_or_default())
+        }
+    }
+
+    /// 本程序发出一条功德变化事件的交易日志通知
+    fn live_notification(program_id: &Pubkey, entry: &HistoryEntry, event: &MeritChangeEvent) -> (u64, RpcLogsResponse) {
+        let data = format!("Program data: {} {}", BASE64.encode(MERIT_CHANGE_EVENT_TAG), BASE64.encode(event.pack()));
+        let logs = vec![format!("Program {} invoke [1]", program_id), data, format!("Program {} success", program_id)];
+        (entry.slot, RpcLogsResponse { signature: entry.signature.to_string(), err: None, logs })
+    }
+
+    #[test]
+    fn test_backfill_returns_gap_oldest_first() {
+        let program_id = Pubkey::new_unique();
+        let history = FixtureHistory::new(5, Pubkey::new_unique());
+        let cursor = FollowCursor { slot: 101, signature: history.entries[1].signature };
+
+        let records = backfill(&history, &program_id, &cursor).unwrap();
+        let slots: Vec<u64> = records.iter().map(|record| record.slot).collect();
+        assert_eq!(slots, vec![102, 103, 104]);
+        assert_eq!(records[0].changes[0].after, 3);
+        assert_eq!(records.last().unwrap().cursor(), FollowCursor { slot: 104, signature: history.entries[4].signature });
+    }
+
+    #[test]
+    fn test_backfill_pages_and_skips_failed() {
+        let program_id = Pubkey::new_unique();
+        let mut history = FixtureHistory::new(SIGNATURE_PAGE_LIMIT * 2 + 3, Pubkey::new_unique());
+        history.entries[10].failed = true;
+        let cursor = FollowCursor { slot: 100, signature: history.entries[0].signature };
+
+        let records = backfill(&history, &program_id, &cursor).unwrap();
+        assert_eq!(records.len(), SIGNATURE_PAGE_LIMIT * 2 + 1);
+        assert!(records.windows(2).all(|pair| pair[0].slot < pair[1].slot));
+        assert!(records.iter().all(|record| record.signature != history.entries[10].signature));
+    }
+
+    #[test]
+    fn test_follower_deduplicates_overlap_with_live() {
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let history = FixtureHistory::new(6, account);
+        // 进度停在第2笔，订阅在补齐之前开始，收到了第4笔到第6笔，其中第4、5笔也在补齐结果中
+        let cursor = FollowCursor { slot: 101, signature: history.entries[1].signature };
+        let backfilled = backfill(&history, &program_id, &cursor).unwrap()[..3].to_vec();
+        let mut live: Vec<_> = history.entries[3..]
+            .iter()
+            .map(|entry| live_notification(&program_id, entry, &history.changes[&entry.signature][0]))
+            .collect();
+        // 失败的交易没有生效
+        let failed = HistoryEntry { signature: Signature::new_unique(), slot: 106, failed: true };
+        let (slot, mut logs) = live_notification(&program_id, &failed, &history.changes[&history.entries[5].signature][0]);
+        logs.err = serde_json::from_value(serde_json::json!("AccountNotFound")).unwrap();
+        live.push((slot, logs));
+
+        let records: Vec<MeritChangeRecord> = Follower::new(program_id, backfilled, live.into_iter()).collect();
+        let slots: Vec<u64> = records.iter().map(|record| record.slot).collect();
+        assert_eq!(slots, vec![102, 103, 104, 105]);
+        assert_eq!(records[3].changes, vec![MeritChangeEvent { account, before: 5, after: 6 }]);
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = FollowCursor { slot: 42, signature: Signature::new_unique() };
+        assert_eq!(FollowCursor::from_json(&cursor.to_json()), Ok(cursor));
+        assert!(FollowCursor::from_json(&serde_json::json!({ "slot": 1 })).is_err());
+
+        let path = std::env::temp_dir().join(format!("gongde-follow-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        assert!(FollowCursor::read(path).unwrap().is_none());
+        cursor.write(path).unwrap();
+        assert_eq!(FollowCursor::read(path).unwrap(), Some(cursor));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_seen_signatures_forgets_oldest() {
+        let mut seen = SeenSignatures::default();
+        let first = Signature::new_unique();
+        assert!(seen.insert(first));
+        assert!(!seen.insert(first));
+        for _ in 0..SEEN_SIGNATURES {
+            seen.insert(Signature::new_unique());
+        }
+        assert!(seen.insert(first));
+    }
+}
@@ -0,0 +1,212 @@
+// ========================================
+// 日志初始化模块
+// 📝 所有示例统一用 tracing 输出：默认保持原来的emoji文本格式，-v/-vv 提高详细程度，--json 输出机器可读日志
+// 有单一结果的命令（查询、增加、关闭）输出被管道或重定向时，或加 -q/--quiet 时进入安静模式：
+// 日志只把警告写到stderr，stdout只有结果本身
+// ========================================
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+
+/// 输出模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// 给人看：带emoji的完整过程
+    Human,
+    /// 给脚本用：stdout只输出结果
+    Quiet,
+}
+
+impl OutputMode {
+    /// 选择输出模式
+    ///
+    /// # 参数
+    /// * `options` - 日志选项
+    /// * `stdout_is_terminal` - stdout是否连接终端
+    ///
+    /// # 返回
+    /// * `OutputMode` - 指定了 --quiet，或者 stdout 不是终端时为安静模式；
+    ///   --json 的日志本身就是机器可读的，不会因为管道切换模式
+    pub fn select(options: &LogOptions, stdout_is_terminal: bool) -> Self {
+        if options.quiet || (!stdout_is_terminal && !options.json) {
+            OutputMode::Quiet
+        } else {
+            OutputMode::Human
+        }
+    }
+}
+
+/// 初始化日志时选定的输出模式
+static OUTPUT_MODE: OnceLock<OutputMode> = OnceLock::new();
+
+/// 当前输出模式，没有初始化日志时为 Human
+pub fn output_mode() -> OutputMode {
+    OUTPUT_MODE.get().copied().unwrap_or(OutputMode::Human)
+}
+
+/// 输出命令的结果：安静模式下单独一行写到stdout，供脚本读取；
+/// 其他模式下结果已经包含在日志里，不重复输出
+pub fn emit_result(result: impl Display) {
+    if output_mode() == OutputMode::Quiet {
+        println!("{}", result);
+    }
+}
+
+/// 日志选项，从命令行参数解析
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogOptions {
+    /// 本程序日志的最低级别
+    pub level: Level,
+    /// 是否输出JSON格式（作为服务运行时使用）
+    pub json: bool,
+    /// 是否指定了 -q/--quiet
+    pub quiet: bool,
+}
+
+impl LogOptions {
+    /// 解析日志相关参数：`-v`/`--verbose` 为 debug，`-vv` 为 trace，`--json` 切换为JSON格式，`-q`/`--quiet` 只输出结果
+    ///
+    /// # 参数
+    /// * `args` - 命令行参数
+    ///
+    /// # 返回
+    /// * `LogOptions` - 解析结果，未指定时为 info 级别的文本格式
+    pub fn from_args<S: AsRef<str>>(args: &[S]) -> Self {
+        let mut verbosity = 0;
+        let mut json = false;
+        let mut quiet = false;
+        for arg in args {
+            match arg.as_ref() {
+                "-v" | "--verbose" => verbosity += 1,
+                "-vv" => verbosity += 2,
+                "--json" => json = true,
+                "-q" | "--quiet" => quiet = true,
+                _ => {}
+            }
+        }
+        let level = match verbosity {
+            0 => Level::INFO,
+            1 => Level::DEBUG,
+            _ => Level::TRACE,
+        };
+        Self { level, json, quiet }
+    }
+
+    /// 构建日志过滤规则：依赖库只输出警告，本程序和客户端工具按指定级别输出
+    /// 设置了 RUST_LOG 环境变量时以环境变量为准
+    ///
+    /// # 参数
+    /// * `program` - 示例程序的日志target，见 `program_target`
+    fn filter(&self, program: &str) -> EnvFilter {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(self.directives(program)))
+    }
+
+    fn directives(&self, program: &str) -> String {
+        format!("warn,{}={},{}={}", env!("CARGO_CRATE_NAME"), self.level, program, self.level)
+    }
+}
+
+/// 示例程序的日志target：可执行文件名，短横线换成下划线，与编译时的crate名一致
+fn program_target(arg0: &str) -> String {
+    let name = Path::new(arg0).file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    name.replace('-', "_")
+}
+
+/// 根据命令行参数初始化全局日志，输出被管道时保持完整日志，适合没有单一结果的命令
+///
+/// # 返回
+/// * `LogOptions` - 实际使用的日志选项
+pub fn init_logging() -> LogOptions {
+    init(false)
+}
+
+/// 初始化全局日志，输出被管道或重定向时自动进入安静模式，适合用 `emit_result` 输出结果的命令
+///
+/// # 返回
+/// * `LogOptions` - 实际使用的日志选项，输出模式通过 `output_mode()` 获取
+pub fn init_logging_for_result() -> LogOptions {
+    init(true)
+}
+
+fn init(detect_pipe: bool) -> LogOptions {
+    let args: Vec<String> = std::env::args().collect();
+    let options = LogOptions::from_args(&args);
+    let stdout_is_terminal = !detect_pipe || std::io::stdout().is_terminal();
+    let output = OutputMode::select(&options, stdout_is_terminal);
+    let _ = OUTPUT_MODE.set(output);
+    if output == OutputMode::Quiet {
+        // 安静模式：过程日志不进stdout，只保留警告和错误
+        tracing_subscriber::fmt()
+            .with_env_filter(EnvFilter::new("warn"))
+            .with_writer(std::io::stderr)
+            .without_time()
+            .with_target(false)
+            .init();
+        return options;
+    }
+    let program = program_target(args.first().map_or("", String::as_str));
+    let builder = tracing_subscriber::fmt().with_env_filter(options.filter(&program));
+    if options.json {
+        builder.json().init();
+    } else {
+        // 默认格式：只输出消息本身，和原来的emoji输出一致
+        builder
+            .without_time()
+            .with_target(false)
+            .with_level(false)
+            .init();
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_info_text() {
+        let options = LogOptions::from_args(&["client"]);
+        assert_eq!(options, LogOptions { level: Level::INFO, json: false, quiet: false });
+    }
+
+    #[test]
+    fn test_verbosity_flags() {
+        assert_eq!(LogOptions::from_args(&["client", "-v"]).level, Level::DEBUG);
+        assert_eq!(LogOptions::from_args(&["client", "--verbose"]).level, Level::DEBUG);
+        assert_eq!(LogOptions::from_args(&["client", "-vv"]).level, Level::TRACE);
+        assert_eq!(LogOptions::from_args(&["client", "-v", "-v"]).level, Level::TRACE);
+    }
+
+    #[test]
+    fn test_json_flag() {
+        let options = LogOptions::from_args(&["monitor", "--json", "-v"]);
+        assert!(options.json);
+        assert_eq!(options.level, Level::DEBUG);
+    }
+
+    #[test]
+    fn test_output_mode_selection() {
+        let select = |args: &[&str], terminal: bool| OutputMode::select(&LogOptions::from_args(args), terminal);
+        // 终端中默认给人看，管道或重定向时自动安静
+        assert_eq!(select(&["query"], true), OutputMode::Human);
+        assert_eq!(select(&["query"], false), OutputMode::Quiet);
+        // 显式安静
+        assert_eq!(select(&["query", "-q"], true), OutputMode::Quiet);
+        assert_eq!(select(&["query", "--quiet"], true), OutputMode::Quiet);
+        // JSON日志本身可以被管道消费，保持原样；同时指定 --quiet 时以 --quiet 为准
+        assert_eq!(select(&["query", "--json"], false), OutputMode::Human);
+        assert_eq!(select(&["query", "--json", "--quiet"], false), OutputMode::Quiet);
+    }
+
+    #[test]
+    fn test_filter_covers_program_and_client() {
+        assert_eq!(program_target("target/debug/examples/diff-snapshots"), "diff_snapshots");
+        assert_eq!(program_target("query"), "query");
+        let options = LogOptions::from_args(&["query", "-v"]);
+        assert_eq!(options.directives("query"), "warn,gong_de_increase=DEBUG,query=DEBUG");
+    }
+}
@@ -0,0 +1,21 @@
+// ========================================
+// 链下客户端 - 示例程序共用的配置、多节点RPC、日志、交易发送和查询工具
+// 开启 `client` feature 后可用，依赖 solana-client，不能用 cargo build-sbf 编译；
+// 示例程序直接从这里导入，单元测试用 `cargo test --lib --features client` 运行
+// ========================================
+
+// solana-client 的 ClientError 超过200字节，RPC调用和错误枚举都直接带着它，装箱只会让调用方多一层解引用
+#![allow(clippy::result_large_err, clippy::large_enum_variant)]
+
+pub mod backup;
+pub mod config;
+pub mod contacts;
+pub mod daemon;
+pub mod follow;
+pub mod logging;
+pub mod output;
+pub mod plan;
+pub mod pow;
+pub mod rpc;
+pub mod snapshot;
+pub mod utils;
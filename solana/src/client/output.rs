@@ -0,0 +1,231 @@
+// ========================================
+// 机器可读输出 - 所有JSON输出的结构都定义在这里
+// 🧾 脚本会解析这些输出，字段不能悄悄变化：每种输出在 schemas/<名称>.json 中有对应的 JSON Schema，
+// 开启 `schema` feature 后由这里的结构生成，测试会和文件比较，增删或改名字段时必须同时更新文件
+// 重新生成: cargo run --features schema --example schema -- --write
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Schema 文件所在的目录，相对于 Cargo.toml
+pub const SCHEMA_DIR: &str = "schemas";
+
+/// 有 JSON Schema 的输出名称，也是 Schema 文件名
+pub const OUTPUT_NAMES: [&str; 3] = ["report", "snapshot", "diff"];
+
+/// 多交易操作的结果（onboard 和 migrate 的 --report 文件），见 `OperationReport`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ReportOutput {
+    /// 操作名称
+    pub operation: String,
+    /// 是否所有交易都已确认
+    pub complete: bool,
+    /// 进程退出码：全部确认为0，全部未确认为1，部分确认为2
+    pub exit_code: i32,
+    /// 已确认交易中额外支付的lamports，不含交易费
+    pub lamports_spent: u64,
+    pub transactions: Vec<TransactionOutput>,
+}
+
+/// 单笔交易的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct TransactionOutput {
+    /// 交易签名，没有签名成功时为null
+    pub signature: Option<String>,
+    /// confirmed：已确认；failed：确定没有上链；unknown：可能已经上链，需要查询后再决定是否重试
+    #[cfg_attr(feature = "schema", schemars(extend("enum" = ["confirmed", "failed", "unknown"])))]
+    pub status: String,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 这笔交易处理的账户
+    pub accounts: Vec<String>,
+    /// 付款人在交易中额外支付的lamports
+    pub lamports: u64,
+}
+
+/// 功德快照文件（leaderboard --snapshot），见 `Snapshot`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotOutput {
+    /// 快照格式版本
+    pub version: u64,
+    pub program_id: String,
+    /// 拍摄时间（Unix时间戳）
+    pub taken_at: i64,
+    /// 功德账户地址 → 当前功德，按地址排列
+    pub accounts: BTreeMap<String, u32>,
+}
+
+/// 两个快照的比较结果（diff-snapshots --format json），见 `SnapshotDiff`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotDiffOutput {
+    /// 新建的账户数
+    pub created: usize,
+    /// 关闭的账户数
+    pub closed: usize,
+    /// 功德没有变化的账户数
+    pub unchanged: usize,
+    pub total_before: u64,
+    pub total_after: u64,
+    pub total_change: i64,
+    /// 涨幅最大的账户，数量由 --top 指定
+    pub top_gainers: Vec<AccountDeltaOutput>,
+    /// 所有有变化的账户，按地址排列
+    pub changes: Vec<AccountDeltaOutput>,
+}
+
+/// 单个账户在两个快照之间的变化
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct AccountDeltaOutput {
+    pub pubkey: String,
+    /// 之前的功德，新建的账户为null
+    pub before: Option<u32>,
+    /// 之后的功德，关闭的账户为null
+    pub after: Option<u32>,
+    pub delta: i64,
+    #[cfg_attr(feature = "schema", schemars(extend("enum" = ["created", "closed", "decreased", "increased"])))]
+    pub status: String,
+}
+
+/// 生成指定输出的 JSON Schema
+///
+/// # 参数
+/// * `name` - OUTPUT_NAMES 中的名称
+///
+/// # 返回
+/// * `Option<schemars::Schema>` - 名称不认识时返回None
+#[cfg(feature = "schema")]
+pub fn schema(name: &str) -> Option<schemars::Schema> {
+    match name {
+        "report" => Some(schemars::schema_for!(ReportOutput)),
+        "snapshot" => Some(schemars::schema_for!(SnapshotOutput)),
+        "diff" => Some(schemars::schema_for!(SnapshotDiffOutput)),
+        _ => None,
+    }
+}
+
+/// 生成 Schema 文件的内容，末尾带换行
+#[cfg(feature = "schema")]
+pub fn schema_text(name: &str) -> Option<String> {
+    schema(name).map(|schema| serde_json::to_string_pretty(&schema).expect("Schema 总是可以序列化") + "\n")
+}
+
+#[cfg(all(test, feature = "schema"))]
+mod tests {
+    use super::*;
+
+    fn schema_file(name: &str) -> serde_json::Value {
+        let path = format!("{}/{}/{}.json", env!("CARGO_MANIFEST_DIR"), SCHEMA_DIR, name);
+        let content = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("读取 {} 失败: {}", path, e));
+        serde_json::from_str(&content).unwrap()
+    }
+
+    /// 用已提交的 Schema 文件校验输出
+    fn assert_valid(name: &str, output: &impl Serialize) {
+        let value = serde_json::to_value(output).unwrap();
+        let validator = jsonschema::validator_for(&schema_file(name)).unwrap();
+        let errors: Vec<String> = validator.iter_errors(&value).map(|e| e.to_string()).collect();
+        assert!(errors.is_empty(), "{} 不符合 schemas/{}.json: {:?}", value, name, errors);
+    }
+
+    fn delta(before: Option<u32>, after: Option<u32>, status: &str) -> AccountDeltaOutput {
+        let delta = i64::from(after.unwrap_or(0)) - i64::from(before.unwrap_or(0));
+        AccountDeltaOutput { pubkey: "11111111111111111111111111111111".to_string(), before, after, delta, status: status.to_string() }
+    }
+
+    #[test]
+    fn test_schema_files_are_up_to_date() {
+        for name in OUTPUT_NAMES {
+            let generated: serde_json::Value = serde_json::from_str(&schema_text(name).unwrap()).unwrap();
+            assert_eq!(
+                generated,
+                schema_file(name),
+                "schemas/{}.json 与代码不一致，确认改动后运行 cargo run --features schema --example schema -- --write",
+                name
+            );
+        }
+        assert!(schema("unknown").is_none());
+    }
+
+    #[test]
+    fn test_outputs_match_checked_in_schemas() {
+        let transaction = |signature: Option<&str>, status: &str, error: Option<&str>| TransactionOutput {
+            signature: signature.map(String::from),
+            status: status.to_string(),
+            error: error.map(String::from),
+            accounts: vec!["11111111111111111111111111111111".to_string()],
+            lamports: 1_000,
+        };
+        assert_valid(
+            "report",
+            &ReportOutput {
+                operation: "开通".to_string(),
+                complete: false,
+                exit_code: 2,
+                lamports_spent: 1_000,
+                transactions: vec![
+                    transaction(Some("5VER"), "confirmed", None),
+                    transaction(Some("3xyz"), "unknown", Some("超时")),
+                    transaction(None, "failed", Some("余额不足")),
+                ],
+            },
+        );
+        assert_valid(
+            "snapshot",
+            &SnapshotOutput {
+                version: 1,
+                program_id: "11111111111111111111111111111111".to_string(),
+                taken_at: 1_700_000_000,
+                accounts: BTreeMap::from([("11111111111111111111111111111111".to_string(), 7)]),
+            },
+        );
+        let changes =
+            vec![delta(None, Some(3), "created"), delta(Some(2), None, "closed"), delta(Some(5), Some(1), "decreased")];
+        assert_valid(
+            "diff",
+            &SnapshotDiffOutput {
+                created: 1,
+                closed: 1,
+                unchanged: 4,
+                total_before: 7,
+                total_after: 4,
+                total_change: -3,
+                top_gainers: changes[..1].to_vec(),
+                changes,
+            },
+        );
+    }
+
+    #[test]
+    fn test_schema_rejects_drift() {
+        // 多出的字段、缺少的字段和不认识的状态都不符合 Schema
+        let validator = jsonschema::validator_for(&schema_file("diff")).unwrap();
+        let mut value = serde_json::to_value(delta(Some(1), Some(2), "increased")).unwrap();
+        let check = |value: &serde_json::Value| {
+            let diff = serde_json::json!({
+                "created": 0, "closed": 0, "unchanged": 0, "total_before": 1, "total_after": 2, "total_change": 1,
+                "top_gainers": [], "changes": [value],
+            });
+            validator.is_valid(&diff)
+        };
+        assert!(check(&value));
+        value["renamed"] = serde_json::json!(1);
+        assert!(!check(&value));
+        value.as_object_mut().unwrap().remove("renamed");
+        value.as_object_mut().unwrap().remove("delta");
+        assert!(!check(&value));
+        value["delta"] = serde_json::json!(1);
+        value["status"] = serde_json::json!("grew");
+        assert!(!check(&value));
+    }
+}
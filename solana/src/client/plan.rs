@@ -0,0 +1,499 @@
+// ========================================
+// 演示流程计划模块
+// 📋 client.rs 先根据链上现状生成计划，再按计划执行；--plan 只打印计划不发送任何交易
+// 计划只依赖只读查询的结果，方便针对各种已有状态做单元测试
+// ========================================
+
+use solana_sdk::{account::Account, instruction::Instruction, pubkey::Pubkey};
+use solana_system_interface::{instruction as system_instruction, program as system_program};
+use tracing::info;
+
+use crate::instruction;
+use crate::utils::{predict_increment, GONGDE_ACCOUNT_SEED, GONGDE_ACCOUNT_SIZE};
+
+/// 每笔交易的签名费（单签名），与网络默认费率一致
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// 合约每点功德收取的手续费，转入全局PDA账户
+pub const CONTRACT_FEE_PER_INCREMENT: u64 = 5000;
+
+/// 演示中增加功德的次数
+pub const DEMO_INCREMENTS: u32 = 3;
+
+/// 优先费单价的单位：每个计算单元的 micro-lamports
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// 创建并初始化一个功德账户的计算单元上限，设置 compute unit limit 和估算优先费时使用
+pub const CREATE_ACCOUNT_COMPUTE_UNITS: u32 = 5_000;
+
+/// 计算一笔交易的优先费，不足1 lamport的部分向上取整
+///
+/// # 参数
+/// * `compute_units` - 交易的计算单元上限
+/// * `micro_lamports_per_cu` - 优先费单价（每个计算单元的 micro-lamports）
+///
+/// # 返回
+/// * `u64` - 优先费（lamports），溢出时为 u64::MAX
+pub fn priority_fee(compute_units: u32, micro_lamports_per_cu: u64) -> u64 {
+    let micro_lamports = u128::from(compute_units) * u128::from(micro_lamports_per_cu);
+    u64::try_from(micro_lamports.div_ceil(u128::from(MICRO_LAMPORTS_PER_LAMPORT))).unwrap_or(u64::MAX)
+}
+
+/// 功德账户完整生命周期（创建 → 增加功德 → 关闭）的费用
+/// 个人账户的租金在关闭时全额退还，真正花掉的只有签名费、合约手续费和全局账户的租金
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifecycleCost {
+    /// 创建时预付、关闭时全额退还的个人账户租金
+    pub refundable_rent: u64,
+    /// 全局功德PDA的租金，由第一次增加功德的人支付；全局账户不会关闭，不退还
+    pub global_rent: u64,
+    /// 创建、每次增加功德和关闭各一笔交易的签名费
+    pub signature_fees: u64,
+    /// 合约每点功德收取的手续费
+    pub contract_fees: u64,
+}
+
+impl LifecycleCost {
+    /// 开始前钱包里至少要有的余额：租金押金加上全部费用
+    pub fn upfront(&self) -> u64 {
+        self.refundable_rent.saturating_add(self.net())
+    }
+
+    /// 关闭账户、收回租金之后的净花费
+    pub fn net(&self) -> u64 {
+        self.global_rent.saturating_add(self.signature_fees).saturating_add(self.contract_fees)
+    }
+}
+
+/// 计算功德账户完整生命周期的费用：一笔交易创建并初始化，每次增加功德一笔交易，最后一笔交易关闭
+///
+/// # 参数
+/// * `gongde_rent` - 个人功德账户的免租金最低余额
+/// * `global_rent` - 全局功德PDA的免租金最低余额，全局账户已存在时传0
+/// * `increments` - 关闭前增加功德的次数
+///
+/// # 返回
+/// * `LifecycleCost` - 费用明细，溢出时按 u64::MAX 计
+pub fn lifecycle_cost(gongde_rent: u64, global_rent: u64, increments: u32) -> LifecycleCost {
+    let transactions = u64::from(increments) + 2;
+    LifecycleCost {
+        refundable_rent: gongde_rent,
+        global_rent: if increments > 0 { global_rent } else { 0 },
+        signature_fees: LAMPORTS_PER_SIGNATURE.saturating_mul(transactions),
+        contract_fees: CONTRACT_FEE_PER_INCREMENT.saturating_mul(u64::from(increments)),
+    }
+}
+
+/// 账户在链上的现状（只读查询得到）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountState {
+    /// 账户不存在或余额为0
+    Absent,
+    /// 账户已存在
+    Present { value: u32, lamports: u64 },
+    /// 账户已创建并归本合约所有，但还没有初始化（创建后初始化没有完成）
+    Uninitialized { lamports: u64 },
+}
+
+/// 生成计划所需的全部输入
+#[derive(Debug, Clone)]
+pub struct DemoContext {
+    pub program_id: Pubkey,
+    pub payer: Pubkey,
+    pub gongde_pubkey: Pubkey,
+    pub global_pubkey: Pubkey,
+    /// 新建账户是否为公开功德箱
+    pub is_public: bool,
+    /// 个人功德账户现状
+    pub gongde: AccountState,
+    /// 全局功德PDA账户现状
+    pub global: AccountState,
+    /// 个人功德账户的免租金最低余额
+    pub gongde_rent: u64,
+    /// 全局功德PDA账户的免租金最低余额
+    pub global_rent: u64,
+}
+
+/// 计划中的单步操作，每一步对应一笔交易
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlannedOperation {
+    /// 创建并初始化个人功德账户
+    CreateAccount { rent: u64, is_public: bool },
+    /// 账户已创建但没有初始化，只补发初始化
+    InitializeAccount { is_public: bool },
+    /// 账户余额低于免租金最低余额，补足差额
+    TopUpRent { lamports: u64 },
+    /// 增加一次功德，附带执行后的预期值
+    Increment { index: u32, expected_value: u32, expected_global: u32 },
+}
+
+/// 完整的演示计划
+#[derive(Debug, Clone)]
+pub struct DemoPlan {
+    pub program_id: Pubkey,
+    pub payer: Pubkey,
+    pub gongde_pubkey: Pubkey,
+    pub global_pubkey: Pubkey,
+    /// 按顺序执行的操作
+    pub operations: Vec<PlannedOperation>,
+    /// 预计支出的租金（个人账户、补足差额和全局账户）
+    pub estimated_rent: u64,
+    /// 预计的签名费和合约手续费
+    pub estimated_fees: u64,
+    /// 预计的最终个人功德值
+    pub expected_final_value: u32,
+}
+
+/// 根据链上现状生成演示计划
+///
+/// # 参数
+/// * `context` - 地址、参数和只读查询得到的账户现状
+///
+/// # 返回
+/// * `DemoPlan` - 按执行顺序排列的操作和费用估算
+pub fn plan_demo(context: &DemoContext) -> DemoPlan {
+    let mut operations = Vec::new();
+    let mut estimated_rent = 0;
+
+    let mut value = match context.gongde {
+        AccountState::Absent => {
+            operations.push(PlannedOperation::CreateAccount {
+                rent: context.gongde_rent,
+                is_public: context.is_public,
+            });
+            estimated_rent += context.gongde_rent;
+            0
+        }
+        AccountState::Present { value, lamports } => {
+            if lamports < context.gongde_rent {
+                let deficit = context.gongde_rent - lamports;
+                operations.push(PlannedOperation::TopUpRent { lamports: deficit });
+                estimated_rent += deficit;
+            }
+            value
+        }
+        AccountState::Uninitialized { lamports } => {
+            // 账户已经创建，再创建会因为 "already in use" 失败，补足租金后只发初始化
+            if lamports < context.gongde_rent {
+                let deficit = context.gongde_rent - lamports;
+                operations.push(PlannedOperation::TopUpRent { lamports: deficit });
+                estimated_rent += deficit;
+            }
+            operations.push(PlannedOperation::InitializeAccount { is_public: context.is_public });
+            0
+        }
+    };
+
+    let mut global = match context.global {
+        AccountState::Absent => {
+            // 全局PDA账户在第一次增加功德时由合约创建，租金由付款人支付
+            estimated_rent += context.global_rent;
+            0
+        }
+        AccountState::Present { value, .. } => value,
+        AccountState::Uninitialized { .. } => 0,
+    };
+
+    // 预期值与链上使用同一套规则计算，执行后据此校验
+    for index in 1..=DEMO_INCREMENTS {
+        (value, global) = predict_increment(value, global, 1);
+        operations.push(PlannedOperation::Increment { index, expected_value: value, expected_global: global });
+    }
+
+    let increments = operations
+        .iter()
+        .filter(|operation| matches!(operation, PlannedOperation::Increment { .. }))
+        .count() as u64;
+    let estimated_fees = operations.len() as u64 * LAMPORTS_PER_SIGNATURE + increments * CONTRACT_FEE_PER_INCREMENT;
+
+    DemoPlan {
+        program_id: context.program_id,
+        payer: context.payer,
+        gongde_pubkey: context.gongde_pubkey,
+        global_pubkey: context.global_pubkey,
+        operations,
+        estimated_rent,
+        estimated_fees,
+        expected_final_value: value,
+    }
+}
+
+/// 检查功德账户地址可以用来创建账户，地址上已有不属于本合约的余额时 create_account_with_seed 会失败，
+/// 提前给出原因，不用对着链上的 "already in use" 猜
+///
+/// # 参数
+/// * `payer` - 付款钱包
+/// * `gongde_pubkey` - 功德账户地址
+/// * `program_id` - 合约程序ID
+/// * `existing` - 只读查询到的功德账户地址上的账户，不存在时为None
+///
+/// # 返回
+/// * `Result<(), String>` - 地址可用（不存在、余额为0或已是本合约的账户）时返回Ok
+///
+/// # 错误
+/// * `String` - 地址与付款钱包相同，或地址上已有不属于本合约的余额
+pub fn check_gongde_address(
+    payer: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    program_id: &Pubkey,
+    existing: Option<&Account>,
+) -> Result<(), String> {
+    if gongde_pubkey == payer {
+        return Err(format!("功德账户地址 {} 与付款钱包相同，不能把钱包本身当作功德账户", gongde_pubkey));
+    }
+    match existing {
+        Some(account) if account.lamports > 0 && account.owner == system_program::ID => Err(format!(
+            "功德账户地址 {} 上已有 {} lamports，但还是普通的系统账户，创建账户会失败；\
+             可以用 transfer_with_seed（种子 {}）把余额转回钱包后重试",
+            gongde_pubkey, account.lamports, GONGDE_ACCOUNT_SEED
+        )),
+        Some(account) if account.lamports > 0 && account.owner != *program_id => {
+            Err(format!("功德账户地址 {} 已被程序 {} 占用，请确认程序ID是否正确", gongde_pubkey, account.owner))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl DemoPlan {
+    /// 生成某一步操作的指令，同一步的指令放在同一笔交易中
+    ///
+    /// # 参数
+    /// * `operation` - 计划中的操作
+    ///
+    /// # 返回
+    /// * `Vec<Instruction>` - 按顺序排列的指令
+    pub fn instructions(&self, operation: &PlannedOperation) -> Vec<Instruction> {
+        match *operation {
+            PlannedOperation::CreateAccount { rent, is_public } => vec![
+                system_instruction::create_account_with_seed(
+                    &self.payer,
+                    &self.gongde_pubkey,
+                    &self.payer,
+                    GONGDE_ACCOUNT_SEED,
+                    rent,
+                    GONGDE_ACCOUNT_SIZE as u64,
+                    &self.program_id,
+                ),
+                instruction::initialize(&self.program_id, &self.gongde_pubkey, &self.payer, is_public),
+            ],
+            PlannedOperation::InitializeAccount { is_public } => {
+                vec![instruction::initialize(&self.program_id, &self.gongde_pubkey, &self.payer, is_public)]
+            }
+            PlannedOperation::TopUpRent { lamports } => {
+                vec![system_instruction::transfer(&self.payer, &self.gongde_pubkey, lamports)]
+            }
+            PlannedOperation::Increment { .. } => {
+                vec![instruction::increment(&self.program_id, &self.gongde_pubkey, &self.payer, &self.global_pubkey)]
+            }
+        }
+    }
+
+    /// 打印计划内容，不发送任何交易
+    pub fn print(&self) {
+        info!("\n📋 === 执行计划（未发送任何交易） ===");
+        info!("📝 个人功德账户: {}", self.gongde_pubkey);
+        info!("🌍 全局功德PDA账户: {}", self.global_pubkey);
+        for (step, operation) in self.operations.iter().enumerate() {
+            let description = match operation {
+                PlannedOperation::CreateAccount { rent, is_public } => format!(
+                    "创建{}，租金 {} lamports",
+                    if *is_public { "公开功德箱" } else { "私有功德箱" },
+                    rent
+                ),
+                PlannedOperation::InitializeAccount { is_public } => format!(
+                    "账户已创建但没有初始化，补发初始化（{}）",
+                    if *is_public { "公开功德箱" } else { "私有功德箱" }
+                ),
+                PlannedOperation::TopUpRent { lamports } => format!("补足租金 {} lamports", lamports),
+                PlannedOperation::Increment { index, expected_value, expected_global } => format!(
+                    "第 {} 次增加功德，预计个人功德 {}，全局功德 {}",
+                    index, expected_value, expected_global
+                ),
+            };
+            info!("  {}. {}", step + 1, description);
+            for instruction in self.instructions(operation) {
+                // 功德合约的指令显示名称和参数，其他程序（例如系统程序）的指令显示原始数据
+                match (instruction.program_id == self.program_id)
+                    .then(|| instruction::describe_instruction(&instruction.data))
+                    .flatten()
+                {
+                    Some(description) => info!("     - 指令: {}（{}）", description, description.summary()),
+                    None => info!("     - 指令: 程序 {}，数据 {:?}", instruction.program_id, instruction.data),
+                }
+            }
+        }
+        info!("💰 预计租金: {} lamports", self.estimated_rent);
+        info!("💸 预计手续费: {} lamports", self.estimated_fees);
+        info!("📊 预计最终个人功德值: {}", self.expected_final_value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{split_client_version, GongDeInstruction};
+
+    const GONGDE_RENT: u64 = 946_560;
+    const GLOBAL_RENT: u64 = 918_720;
+
+    fn context(gongde: AccountState, global: AccountState) -> DemoContext {
+        DemoContext {
+            program_id: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            gongde_pubkey: Pubkey::new_unique(),
+            global_pubkey: Pubkey::new_unique(),
+            is_public: true,
+            gongde,
+            global,
+            gongde_rent: GONGDE_RENT,
+            global_rent: GLOBAL_RENT,
+        }
+    }
+
+    #[test]
+    fn test_plan_account_absent() {
+        let plan = plan_demo(&context(AccountState::Absent, AccountState::Absent));
+        assert_eq!(
+            plan.operations,
+            vec![
+                PlannedOperation::CreateAccount { rent: GONGDE_RENT, is_public: true },
+                PlannedOperation::Increment { index: 1, expected_value: 1, expected_global: 1 },
+                PlannedOperation::Increment { index: 2, expected_value: 2, expected_global: 2 },
+                PlannedOperation::Increment { index: 3, expected_value: 3, expected_global: 3 },
+            ]
+        );
+        assert_eq!(plan.estimated_rent, GONGDE_RENT + GLOBAL_RENT);
+        assert_eq!(plan.estimated_fees, 4 * LAMPORTS_PER_SIGNATURE + 3 * CONTRACT_FEE_PER_INCREMENT);
+        assert_eq!(plan.expected_final_value, 3);
+
+        // 创建账户和初始化在同一笔交易中
+        let instructions = plan.instructions(&plan.operations[0]);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(split_client_version(&instructions[1].data).1, [GongDeInstruction::Initialize as u8, 1]);
+    }
+
+    #[test]
+    fn test_plan_account_present() {
+        let plan = plan_demo(&context(
+            AccountState::Present { value: 7, lamports: GONGDE_RENT },
+            AccountState::Present { value: 40, lamports: GLOBAL_RENT },
+        ));
+        assert_eq!(plan.operations.len(), DEMO_INCREMENTS as usize);
+        assert_eq!(
+            plan.operations[0],
+            PlannedOperation::Increment { index: 1, expected_value: 8, expected_global: 41 }
+        );
+        assert_eq!(plan.estimated_rent, 0);
+        assert_eq!(plan.estimated_fees, 3 * (LAMPORTS_PER_SIGNATURE + CONTRACT_FEE_PER_INCREMENT));
+        assert_eq!(plan.expected_final_value, 10);
+
+        let instructions = plan.instructions(&plan.operations[0]);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].accounts[2].pubkey, plan.global_pubkey);
+    }
+
+    #[test]
+    fn test_plan_account_uninitialized() {
+        // 上次创建了账户但初始化没有完成：不再创建，只补发初始化
+        let plan = plan_demo(&context(
+            AccountState::Uninitialized { lamports: GONGDE_RENT },
+            AccountState::Present { value: 9, lamports: GLOBAL_RENT },
+        ));
+        assert_eq!(plan.operations[0], PlannedOperation::InitializeAccount { is_public: true });
+        assert_eq!(plan.operations[1], PlannedOperation::Increment { index: 1, expected_value: 1, expected_global: 10 });
+        assert_eq!(plan.operations.len(), 1 + DEMO_INCREMENTS as usize);
+        assert_eq!(plan.estimated_rent, 0);
+        assert_eq!(plan.expected_final_value, 3);
+
+        let instructions = plan.instructions(&plan.operations[0]);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(split_client_version(&instructions[0].data).1, [GongDeInstruction::Initialize as u8, 1]);
+
+        // 租金不足时先补足再初始化
+        let plan = plan_demo(&context(
+            AccountState::Uninitialized { lamports: GONGDE_RENT - 10 },
+            AccountState::Absent,
+        ));
+        assert_eq!(
+            plan.operations[..2],
+            [PlannedOperation::TopUpRent { lamports: 10 }, PlannedOperation::InitializeAccount { is_public: true }]
+        );
+        assert!(!plan.operations.iter().any(|operation| matches!(operation, PlannedOperation::CreateAccount { .. })));
+    }
+
+    #[test]
+    fn test_lifecycle_cost_refunds_rent() {
+        // 只创建和关闭：两笔交易的签名费，租金全额退还
+        let cost = lifecycle_cost(GONGDE_RENT, GLOBAL_RENT, 0);
+        assert_eq!(cost.net(), 2 * LAMPORTS_PER_SIGNATURE);
+        assert_eq!(cost.net(), 10_000);
+        assert_eq!(cost.upfront(), GONGDE_RENT + 10_000);
+
+        // 全局账户已存在：3次增加功德再关闭，5笔签名费加3次合约手续费
+        let cost = lifecycle_cost(GONGDE_RENT, 0, DEMO_INCREMENTS);
+        assert_eq!(
+            cost,
+            LifecycleCost {
+                refundable_rent: GONGDE_RENT,
+                global_rent: 0,
+                signature_fees: 25_000,
+                contract_fees: 15_000,
+            }
+        );
+        assert_eq!(cost.net(), 40_000);
+
+        // 第一个用户还要支付全局账户的租金，这部分不退还
+        let cost = lifecycle_cost(GONGDE_RENT, GLOBAL_RENT, DEMO_INCREMENTS);
+        assert_eq!(cost.net(), GLOBAL_RENT + 40_000);
+        assert_eq!(cost.upfront(), GONGDE_RENT + GLOBAL_RENT + 40_000);
+
+        assert_eq!(lifecycle_cost(u64::MAX, u64::MAX, u32::MAX).upfront(), u64::MAX);
+    }
+
+    #[test]
+    fn test_priority_fee_rounds_up() {
+        assert_eq!(priority_fee(CREATE_ACCOUNT_COMPUTE_UNITS, 0), 0);
+        assert_eq!(priority_fee(1, 1), 1);
+        assert_eq!(priority_fee(200_000, 1_000), 200);
+        assert_eq!(priority_fee(200_000, 1_001), 201);
+        assert_eq!(priority_fee(u32::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_plan_rent_deficient() {
+        let plan = plan_demo(&context(
+            AccountState::Present { value: 2, lamports: GONGDE_RENT - 1000 },
+            AccountState::Present { value: 2, lamports: GLOBAL_RENT },
+        ));
+        assert_eq!(plan.operations[0], PlannedOperation::TopUpRent { lamports: 1000 });
+        assert_eq!(plan.operations.len(), 1 + DEMO_INCREMENTS as usize);
+        assert_eq!(plan.estimated_rent, 1000);
+        assert_eq!(plan.expected_final_value, 5);
+
+        let instructions = plan.instructions(&plan.operations[0]);
+        assert_eq!(instructions, vec![system_instruction::transfer(&plan.payer, &plan.gongde_pubkey, 1000)]);
+    }
+
+    #[test]
+    fn test_check_gongde_address() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let gongde_pubkey = Pubkey::new_unique();
+        let account = |lamports, owner| Account { lamports, data: vec![], owner, executable: false, rent_epoch: 0 };
+
+        assert_eq!(check_gongde_address(&payer, &gongde_pubkey, &program_id, None), Ok(()));
+        assert_eq!(check_gongde_address(&payer, &gongde_pubkey, &program_id, Some(&account(0, system_program::ID))), Ok(()));
+        assert_eq!(check_gongde_address(&payer, &gongde_pubkey, &program_id, Some(&account(GONGDE_RENT, program_id))), Ok(()));
+
+        // 把钱包本身当作功德账户
+        let err = check_gongde_address(&payer, &payer, &program_id, Some(&account(GONGDE_RENT, system_program::ID))).unwrap_err();
+        assert!(err.contains("与付款钱包相同"), "{}", err);
+        // 有人提前往地址上转了账
+        let err = check_gongde_address(&payer, &gongde_pubkey, &program_id, Some(&account(1, system_program::ID))).unwrap_err();
+        assert!(err.contains("普通的系统账户"), "{}", err);
+        // 程序ID配置错了，地址属于别的程序
+        let other = Pubkey::new_unique();
+        let err = check_gongde_address(&payer, &gongde_pubkey, &program_id, Some(&account(GONGDE_RENT, other))).unwrap_err();
+        assert!(err.contains(&other.to_string()), "{}", err);
+    }
+}
@@ -0,0 +1,156 @@
+// ========================================
+// 工作量证明的 nonce 计算 - 全局配置开启工作量证明后，增加功德前先在本地算出 nonce
+// ⛏️ 多个线程交错尝试（第 i 个线程尝试 i, i+线程数, ...），每个线程都按从小到大的顺序尝试，
+// 别的线程找到更小的结果后才停止，所以无论几个线程，返回的都是最小的有效 nonce
+// ========================================
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use tracing::info;
+
+use crate::client::utils::AccountFetcher;
+use crate::instruction;
+use crate::utils::{derive_config_address, meets_pow_difficulty, pow_hash, read_last_increment_slot, GlobalConfig};
+
+/// 每个线程尝试这么多次后汇报一次进度
+pub const PROGRESS_INTERVAL: u64 = 1 << 16;
+
+/// 默认的线程数：本机可用的CPU核数
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1)
+}
+
+/// 找出满足难度的最小 nonce，合约用同样的 `pow_hash` 校验
+///
+/// # 参数
+/// * `gongde_pubkey` - 增加功德的账户地址，必须和交易中的功德账户一致
+/// * `last_increment_slot` - 功德账户记录的上次证明的slot，见 `read_last_increment_slot`
+/// * `difficulty` - 全局配置中的难度，0时直接返回0
+/// * `threads` - 线程数，0按1处理
+/// * `progress` - 进度回调，参数是所有线程累计尝试的次数，可能在多个线程中同时调用
+///
+/// # 返回
+/// * `Option<u64>` - 最小的有效 nonce；整个 nonce 空间都不满足时返回 None，难度不超过 MAX_POW_DIFFICULTY 时不会出现
+pub fn mine_nonce(
+    gongde_pubkey: &Pubkey,
+    last_increment_slot: u64,
+    difficulty: u8,
+    threads: usize,
+    progress: impl Fn(u64) + Sync,
+) -> Option<u64> {
+    if difficulty == 0 {
+        return Some(0);
+    }
+    let threads = threads.max(1) as u64;
+    let found = AtomicU64::new(u64::MAX);
+    let attempts = AtomicU64::new(0);
+
+    std::thread::scope(|scope| {
+        for start in 0..threads {
+            let (found, attempts, progress) = (&found, &attempts, &progress);
+            scope.spawn(move || {
+                let mut nonce = start;
+                let mut pending = 0;
+                // 比已找到的结果大的 nonce 不用再试
+                while nonce < found.load(Ordering::Relaxed) {
+                    if meets_pow_difficulty(&pow_hash(gongde_pubkey, last_increment_slot, nonce), difficulty) {
+                        found.fetch_min(nonce, Ordering::Relaxed);
+                        break;
+                    }
+                    pending += 1;
+                    if pending == PROGRESS_INTERVAL {
+                        progress(attempts.fetch_add(pending, Ordering::Relaxed) + pending);
+                        pending = 0;
+                    }
+                    let Some(next) = nonce.checked_add(threads) else { break };
+                    nonce = next;
+                }
+            });
+        }
+    });
+
+    // u64::MAX 本身也可能是有效的 nonce，重新校验一次
+    let nonce = found.into_inner();
+    meets_pow_difficulty(&pow_hash(gongde_pubkey, last_increment_slot, nonce), difficulty).then_some(nonce)
+}
+
+/// 查询全局配置，还没有设置过时账户不存在，返回默认配置（不限制版本、不要求工作量证明）
+///
+/// # 错误
+/// * RPC请求失败，或者全局配置的数据无法解析
+pub fn fetch_global_config<C: AccountFetcher + ?Sized>(client: &C, program_id: &Pubkey) -> ClientResult<GlobalConfig> {
+    let (config_pubkey, _bump) = derive_config_address(program_id);
+    match client.fetch_account(&config_pubkey)? {
+        Some(account) if account.owner == *program_id => GlobalConfig::unpack(&account.data)
+            .map_err(|e| ClientErrorKind::Custom(format!("解析全局配置失败: {:?}", e)).into()),
+        _ => Ok(GlobalConfig::default()),
+    }
+}
+
+/// 构建功德+1的指令：全局配置开启工作量证明时先用本机所有CPU核算出 nonce，构建 IncrementWithProof；
+/// 否则构建普通的 Increment，和开启前完全一样
+///
+/// nonce 和功德账户当前记录的slot绑定，期间有别的交易给同一个账户增加过功德时合约返回 InvalidProofOfWork，重新构建即可
+///
+/// # 参数
+/// * `client` - 查询全局配置和功德账户
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `payer` - 支付手续费的用户
+/// * `global_pubkey` - 全局功德PDA账户地址
+pub fn increment_instruction<C: AccountFetcher + ?Sized>(
+    client: &C,
+    program_id: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    payer: &Pubkey,
+    global_pubkey: &Pubkey,
+) -> ClientResult<Instruction> {
+    let config = fetch_global_config(client, program_id)?;
+    if !config.pow_enabled() {
+        return Ok(instruction::increment(program_id, gongde_pubkey, payer, global_pubkey));
+    }
+
+    // 账户还不存在（第一次增加时创建）或是旧布局时按0计算，旧布局的账户会被合约提示先迁移
+    let last_slot = client
+        .fetch_account(gongde_pubkey)?
+        .and_then(|account| read_last_increment_slot(&account.data))
+        .unwrap_or(0);
+    info!("⛏️  已开启工作量证明（难度 {} 比特），正在计算 nonce...", config.pow_difficulty);
+    let nonce = mine_nonce(gongde_pubkey, last_slot, config.pow_difficulty, default_threads(), |total| {
+        if total % (PROGRESS_INTERVAL * 64) == 0 {
+            info!("⛏️  已尝试 {} 个 nonce", total);
+        }
+    })
+    .ok_or_else(|| ClientErrorKind::Custom(format!("难度 {} 比特下找不到有效的 nonce", config.pow_difficulty)))?;
+    info!("⛏️  找到 nonce: {}", nonce);
+    Ok(instruction::increment_with_proof(program_id, gongde_pubkey, payer, global_pubkey, nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mine_nonce_finds_smallest_valid_nonce() {
+        // 与 utils 中固定的样例相同：难度8的第一个有效 nonce 是156
+        let gongde_pubkey = Pubkey::new_from_array([1; 32]);
+        assert_eq!(mine_nonce(&gongde_pubkey, 42, 8, 1, |_| {}), Some(156));
+        assert_eq!(mine_nonce(&gongde_pubkey, 42, 8, 4, |_| {}), Some(156));
+        assert_eq!(mine_nonce(&gongde_pubkey, 42, 0, 4, |_| {}), Some(0));
+    }
+
+    #[test]
+    fn test_mine_nonce_reports_progress() {
+        // 单线程时每尝试 PROGRESS_INTERVAL 个 nonce 汇报一次，这组输入难度18的结果是137874
+        let gongde_pubkey = Pubkey::new_from_array([1; 32]);
+        let reported = std::sync::Mutex::new(Vec::new());
+        let nonce = mine_nonce(&gongde_pubkey, 2, 18, 1, |total| reported.lock().unwrap().push(total));
+        assert_eq!(nonce, Some(137874));
+        assert_eq!(reported.into_inner().unwrap(), [PROGRESS_INTERVAL, 2 * PROGRESS_INTERVAL]);
+
+        // 多线程找到的是同一个 nonce
+        assert_eq!(mine_nonce(&gongde_pubkey, 2, 18, 3, |_| {}), Some(137874));
+    }
+}
@@ -0,0 +1,293 @@
+// ========================================
+// 多RPC节点故障转移模块
+// 公共devnet节点限流严重，配置多个节点后按顺序尝试，出现网络错误、429限流或节点落后时切换到下一个
+// 找到健康节点后本次会话固定使用它
+// ========================================
+
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_client::RpcClient,
+    rpc_request::RpcError,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::cell::Cell;
+use std::ops::Deref;
+use tracing::{debug, warn};
+
+/// 节点不健康（落后太多）时RPC返回的错误码
+const NODE_UNHEALTHY_CODE: i64 = -32005;
+
+/// HTTP 429 Too Many Requests
+const HTTP_TOO_MANY_REQUESTS: u16 = 429;
+
+/// 故障转移需要的节点能力，RpcClient实现它，测试中用模拟节点代替
+pub trait RpcEndpoint {
+    /// 节点地址
+    fn endpoint_url(&self) -> String;
+    /// 节点是否健康（get_health 返回 ok）
+    fn is_healthy(&self) -> bool;
+}
+
+impl RpcEndpoint for RpcClient {
+    fn endpoint_url(&self) -> String {
+        self.url()
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.get_health().is_ok()
+    }
+}
+
+/// 判断错误是否应该切换节点
+/// 网络错误、限流和节点落后切换；交易执行失败等合约错误换节点也没用，不切换
+///
+/// # 参数
+/// * `err` - RPC调用返回的错误
+///
+/// # 返回
+/// * `bool` - 是否应该尝试下一个节点
+pub fn is_failover_error(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(e) => {
+            e.is_connect()
+                || e.is_timeout()
+                || e.status().map(|status| status.as_u16() == HTTP_TOO_MANY_REQUESTS || status.is_server_error()).unwrap_or(false)
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => *code == NODE_UNHEALTHY_CODE,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        _ => false,
+    }
+}
+
+/// 故障转移策略：记录当前使用的节点下标，按顺序尝试后续节点
+#[derive(Debug, Default)]
+pub struct FailoverPolicy {
+    current: Cell<usize>,
+}
+
+impl FailoverPolicy {
+    /// 创建策略，从第一个节点开始
+    pub fn new() -> Self {
+        Self {
+            current: Cell::new(0),
+        }
+    }
+
+    /// 当前使用的节点下标
+    pub fn current(&self) -> usize {
+        self.current.get()
+    }
+
+    /// 从当前节点开始找到第一个健康节点并固定使用
+    ///
+    /// # 参数
+    /// * `endpoints` - 按优先级排列的节点
+    ///
+    /// # 返回
+    /// * `Option<usize>` - 健康节点下标，全部不健康时返回None
+    pub fn select_healthy<E: RpcEndpoint>(&self, endpoints: &[E]) -> Option<usize> {
+        for (index, endpoint) in endpoints.iter().enumerate().skip(self.current()) {
+            if endpoint.is_healthy() {
+                self.current.set(index);
+                debug!("🌐 使用RPC节点: {}", endpoint.endpoint_url());
+                return Some(index);
+            }
+            warn!("⚠️  RPC节点不可用，尝试下一个: {}", endpoint.endpoint_url());
+        }
+        None
+    }
+
+    /// 在当前节点执行请求，遇到需要切换的错误时依次尝试后续节点
+    /// 成功的节点会被记住，后续请求直接使用
+    ///
+    /// # 参数
+    /// * `endpoints` - 按优先级排列的节点
+    /// * `op` - 要执行的请求
+    ///
+    /// # 返回
+    /// * `ClientResult<T>` - 第一个成功的结果，或最后一个节点的错误
+    pub fn execute<E, T>(
+        &self,
+        endpoints: &[E],
+        mut op: impl FnMut(&E) -> ClientResult<T>,
+    ) -> ClientResult<T>
+    where
+        E: RpcEndpoint,
+    {
+        let mut index = self.current();
+        loop {
+            let endpoint = &endpoints[index];
+            match op(endpoint) {
+                Ok(value) => {
+                    debug!("🌐 请求由节点处理: {}", endpoint.endpoint_url());
+                    self.current.set(index);
+                    return Ok(value);
+                }
+                Err(err) if is_failover_error(&err) && index + 1 < endpoints.len() => {
+                    warn!("⚠️  RPC节点 {} 请求失败，切换到下一个: {}", endpoint.endpoint_url(), err);
+                    index += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// 支持故障转移的RPC客户端
+/// 通过Deref直接当作当前节点的RpcClient使用，需要自动重试的请求走 `call`
+pub struct MultiRpcClient {
+    clients: Vec<RpcClient>,
+    policy: FailoverPolicy,
+}
+
+impl MultiRpcClient {
+    /// 连接多个节点并选出第一个健康节点
+    ///
+    /// # 参数
+    /// * `urls` - 按优先级排列的RPC地址
+    /// * `commitment` - 确认级别
+    ///
+    /// # 返回
+    /// * `Result<Self, Box<dyn std::error::Error>>` - 客户端；全部节点不可用时报错
+    pub fn connect(
+        urls: &[String],
+        commitment: CommitmentConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if urls.is_empty() {
+            return Err("未配置RPC节点".into());
+        }
+        let clients: Vec<RpcClient> = urls
+            .iter()
+            .map(|url| RpcClient::new_with_commitment(url.clone(), commitment))
+            .collect();
+        let policy = FailoverPolicy::new();
+        if policy.select_healthy(&clients).is_none() {
+            return Err(format!("所有RPC节点都不可用: {}", urls.join(", ")).into());
+        }
+        Ok(Self { clients, policy })
+    }
+
+    /// 执行请求，失败时自动切换节点重试
+    pub fn call<T>(&self, op: impl FnMut(&RpcClient) -> ClientResult<T>) -> ClientResult<T> {
+        self.policy.execute(&self.clients, op)
+    }
+}
+
+impl Deref for MultiRpcClient {
+    type Target = RpcClient;
+
+    fn deref(&self) -> &RpcClient {
+        &self.clients[self.policy.current()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::cell::RefCell;
+    use std::io;
+
+    /// 模拟节点：按顺序返回预设的响应
+    struct MockEndpoint {
+        url: &'static str,
+        healthy: bool,
+        responses: RefCell<VecDeque<ClientResult<u64>>>,
+    }
+
+    impl MockEndpoint {
+        fn new(url: &'static str, healthy: bool, responses: Vec<ClientResult<u64>>) -> Self {
+            Self { url, healthy, responses: RefCell::new(responses.into()) }
+        }
+
+        fn get_slot(&self) -> ClientResult<u64> {
+            self.responses.borrow_mut().pop_front().expect("模拟节点没有更多响应")
+        }
+    }
+
+    impl RpcEndpoint for MockEndpoint {
+        fn endpoint_url(&self) -> String {
+            self.url.to_string()
+        }
+
+        fn is_healthy(&self) -> bool {
+            self.healthy
+        }
+    }
+
+    fn connection_refused() -> ClientError {
+        ClientErrorKind::Io(io::Error::from(io::ErrorKind::ConnectionRefused)).into()
+    }
+
+    fn node_behind() -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: NODE_UNHEALTHY_CODE,
+            message: "Node is behind by 150 slots".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        })
+        .into()
+    }
+
+    #[test]
+    fn test_dead_first_endpoint_fails_over_and_sticks() {
+        let endpoints = [
+            MockEndpoint::new("http://dead", true, vec![Err(connection_refused())]),
+            MockEndpoint::new("http://alive", true, vec![Ok(100), Ok(101)]),
+        ];
+        let policy = FailoverPolicy::new();
+
+        assert_eq!(policy.execute(&endpoints, |e| e.get_slot()).unwrap(), 100);
+        assert_eq!(policy.current(), 1);
+
+        // 后续请求直接使用健康节点，不再访问失败的节点
+        assert_eq!(policy.execute(&endpoints, |e| e.get_slot()).unwrap(), 101);
+        assert!(endpoints[0].responses.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_node_behind_fails_over() {
+        let endpoints = [
+            MockEndpoint::new("http://behind", true, vec![Err(node_behind())]),
+            MockEndpoint::new("http://alive", true, vec![Ok(7)]),
+        ];
+        let policy = FailoverPolicy::new();
+        assert_eq!(policy.execute(&endpoints, |e| e.get_slot()).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_program_error_does_not_fail_over() {
+        let program_error: ClientError = ClientErrorKind::Custom("交易执行失败".to_string()).into();
+        let endpoints = [
+            MockEndpoint::new("http://first", true, vec![Err(program_error)]),
+            MockEndpoint::new("http://second", true, vec![Ok(1)]),
+        ];
+        let policy = FailoverPolicy::new();
+
+        assert!(policy.execute(&endpoints, |e| e.get_slot()).is_err());
+        assert_eq!(policy.current(), 0);
+    }
+
+    #[test]
+    fn test_all_endpoints_dead_returns_last_error() {
+        let endpoints = [
+            MockEndpoint::new("http://dead1", true, vec![Err(connection_refused())]),
+            MockEndpoint::new("http://dead2", true, vec![Err(connection_refused())]),
+        ];
+        let policy = FailoverPolicy::new();
+        assert!(policy.execute(&endpoints, |e| e.get_slot()).is_err());
+    }
+
+    #[test]
+    fn test_select_healthy_skips_unhealthy() {
+        let endpoints = [
+            MockEndpoint::new("http://unhealthy", false, vec![]),
+            MockEndpoint::new("http://healthy", true, vec![]),
+        ];
+        let policy = FailoverPolicy::new();
+        assert_eq!(policy.select_healthy(&endpoints), Some(1));
+
+        let endpoints = [MockEndpoint::new("http://unhealthy", false, vec![])];
+        assert_eq!(FailoverPolicy::new().select_healthy(&endpoints), None);
+    }
+}
@@ -0,0 +1,380 @@
+// ========================================
+// 功德快照模块
+// 📸 leaderboard --snapshot 把全部功德账户的当前功德写成快照文件，diff-snapshots 比较两个快照，
+// 列出新建、关闭的账户、每个账户的变化、功德总变化和涨幅最大的账户，用于每周的社区报告
+// 比较逻辑只依赖两个快照的内容，方便针对各种变化做单元测试
+// ========================================
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+use crate::client::output::{AccountDeltaOutput, SnapshotDiffOutput, SnapshotOutput};
+use crate::client::utils::LeaderboardEntry;
+
+/// 快照文件的格式版本
+pub const SNAPSHOT_FORMAT_VERSION: u64 = 1;
+
+/// 某一时刻本程序下全部功德账户的功德
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// 程序ID
+    pub program_id: Pubkey,
+    /// 拍摄时间（Unix时间戳）
+    pub taken_at: i64,
+    /// 功德账户地址 → 当前功德
+    pub accounts: BTreeMap<Pubkey, u32>,
+}
+
+impl Snapshot {
+    /// 用排行榜扫描到的账户生成快照，只记录每个账户的当前功德
+    ///
+    /// # 参数
+    /// * `program_id` - 程序ID
+    /// * `taken_at` - 拍摄时间（Unix时间戳）
+    /// * `entries` - 排行榜中的账户
+    pub fn from_leaderboard(program_id: Pubkey, taken_at: i64, entries: &[LeaderboardEntry]) -> Self {
+        Self { program_id, taken_at, accounts: entries.iter().map(|entry| (entry.pubkey, entry.value)).collect() }
+    }
+
+    /// 快照文件的结构，Schema 见 schemas/snapshot.json
+    pub fn to_output(&self) -> SnapshotOutput {
+        SnapshotOutput {
+            version: SNAPSHOT_FORMAT_VERSION,
+            program_id: self.program_id.to_string(),
+            taken_at: self.taken_at,
+            accounts: self.accounts.iter().map(|(pubkey, value)| (pubkey.to_string(), *value)).collect(),
+        }
+    }
+
+    /// 生成快照文件的JSON，账户按地址排列，同样的状态总是得到同样的文件
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self.to_output()).expect("快照总是可以序列化")
+    }
+
+    /// 解析快照文件的JSON
+    ///
+    /// # 错误
+    /// * 缺少字段、版本不认识、地址或功德值无效时返回错误
+    pub fn from_json(json: &serde_json::Value) -> Result<Self, String> {
+        let version = json["version"].as_u64().ok_or("快照缺少 version 字段")?;
+        if version != SNAPSHOT_FORMAT_VERSION {
+            return Err(format!("不支持的快照版本: {}", version));
+        }
+        let program_id = json["program_id"]
+            .as_str()
+            .ok_or("快照缺少 program_id 字段")?
+            .parse()
+            .map_err(|e| format!("快照中的程序ID无效: {}", e))?;
+        let taken_at = json["taken_at"].as_i64().ok_or("快照缺少 taken_at 字段")?;
+        let mut accounts = BTreeMap::new();
+        for (pubkey, value) in json["accounts"].as_object().ok_or("快照缺少 accounts 字段")? {
+            let pubkey = pubkey.parse().map_err(|e| format!("快照中的账户地址 {} 无效: {}", pubkey, e))?;
+            let value = value
+                .as_u64()
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or_else(|| format!("账户 {} 的功德值无效: {}", pubkey, value))?;
+            accounts.insert(pubkey, value);
+        }
+        Ok(Self { program_id, taken_at, accounts })
+    }
+
+    /// 写入快照文件
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(&self.to_json())?)?;
+        Ok(())
+    }
+
+    /// 读取快照文件
+    pub fn read(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Self::from_json(&json).map_err(|e| format!("{}: {}", path, e))?)
+    }
+}
+
+/// 单个账户在两个快照之间的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountDelta {
+    /// 功德账户地址
+    pub pubkey: Pubkey,
+    /// 旧快照中的功德，新建的账户为None
+    pub before: Option<u32>,
+    /// 新快照中的功德，关闭的账户为None
+    pub after: Option<u32>,
+}
+
+impl AccountDelta {
+    /// 功德变化，不存在的一侧按0计算
+    pub fn delta(&self) -> i64 {
+        i64::from(self.after.unwrap_or(0)) - i64::from(self.before.unwrap_or(0))
+    }
+
+    /// 变化类型，用于表格和CSV
+    pub fn status(&self) -> &'static str {
+        match (self.before, self.after) {
+            (None, _) => "created",
+            (_, None) => "closed",
+            _ if self.delta() < 0 => "decreased",
+            _ => "increased",
+        }
+    }
+}
+
+/// 两个快照的比较结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// 有变化的账户（新建、关闭或功德变化），按地址排列
+    pub changes: Vec<AccountDelta>,
+    /// 两边都有并且功德没有变化的账户数
+    pub unchanged: usize,
+    /// 旧快照的功德总和
+    pub total_before: u64,
+    /// 新快照的功德总和
+    pub total_after: u64,
+}
+
+/// 比较两个快照中的账户
+///
+/// # 参数
+/// * `before` - 旧快照的账户
+/// * `after` - 新快照的账户
+///
+/// # 返回
+/// * `SnapshotDiff` - 比较结果
+pub fn diff_snapshots(before: &BTreeMap<Pubkey, u32>, after: &BTreeMap<Pubkey, u32>) -> SnapshotDiff {
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    let pubkeys: std::collections::BTreeSet<&Pubkey> = before.keys().chain(after.keys()).collect();
+    for pubkey in pubkeys {
+        let delta = AccountDelta { pubkey: *pubkey, before: before.get(pubkey).copied(), after: after.get(pubkey).copied() };
+        if delta.before == delta.after {
+            unchanged += 1;
+        } else {
+            changes.push(delta);
+        }
+    }
+    SnapshotDiff {
+        changes,
+        unchanged,
+        total_before: before.values().map(|value| u64::from(*value)).sum(),
+        total_after: after.values().map(|value| u64::from(*value)).sum(),
+    }
+}
+
+impl SnapshotDiff {
+    /// 新建的账户
+    pub fn created(&self) -> impl Iterator<Item = &AccountDelta> {
+        self.changes.iter().filter(|change| change.before.is_none())
+    }
+
+    /// 关闭的账户
+    pub fn closed(&self) -> impl Iterator<Item = &AccountDelta> {
+        self.changes.iter().filter(|change| change.after.is_none())
+    }
+
+    /// 功德总变化
+    pub fn total_change(&self) -> i64 {
+        self.total_after as i64 - self.total_before as i64
+    }
+
+    /// 涨幅最大的账户，新建的账户按从0开始计算；涨幅相同时按地址排列
+    ///
+    /// # 参数
+    /// * `count` - 最多返回的账户数
+    pub fn top_gainers(&self, count: usize) -> Vec<AccountDelta> {
+        let mut gainers: Vec<AccountDelta> = self.changes.iter().copied().filter(|change| change.delta() > 0).collect();
+        gainers.sort_by(|a, b| b.delta().cmp(&a.delta()).then(a.pubkey.cmp(&b.pubkey)));
+        gainers.truncate(count);
+        gainers
+    }
+
+    /// 生成给人看的报告
+    ///
+    /// # 参数
+    /// * `top` - 涨幅榜显示的账户数
+    pub fn render(&self, top: usize) -> String {
+        let format_value = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_else(|| "-".to_string());
+        let mut lines = vec![
+            "📊 === 功德快照对比 ===".to_string(),
+            format!(
+                "账户: 新建 {}，关闭 {}，功德变化 {}，未变化 {}",
+                self.created().count(),
+                self.closed().count(),
+                self.changes.len() - self.created().count() - self.closed().count(),
+                self.unchanged
+            ),
+            format!("功德总计: {} → {}（{:+}）", self.total_before, self.total_after, self.total_change()),
+        ];
+        let gainers = self.top_gainers(top);
+        if !gainers.is_empty() {
+            lines.push(format!("\n🏆 涨幅前 {} 名", gainers.len()));
+            for (rank, gainer) in gainers.iter().enumerate() {
+                lines.push(format!("{:>3}. {:<44} {:+}", rank + 1, gainer.pubkey, gainer.delta()));
+            }
+        }
+        if !self.changes.is_empty() {
+            lines.push(format!("\n{:<44} {:>10} {:>10} {:>10}  {}", "账户", "之前", "之后", "变化", "类型"));
+            for change in &self.changes {
+                lines.push(format!(
+                    "{:<44} {:>10} {:>10} {:>+10}  {}",
+                    change.pubkey,
+                    format_value(change.before),
+                    format_value(change.after),
+                    change.delta(),
+                    change.status()
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// 机器可读输出的结构，Schema 见 schemas/diff.json
+    ///
+    /// # 参数
+    /// * `top` - 涨幅榜包含的账户数
+    pub fn to_output(&self, top: usize) -> SnapshotDiffOutput {
+        let delta_output = |change: &AccountDelta| AccountDeltaOutput {
+            pubkey: change.pubkey.to_string(),
+            before: change.before,
+            after: change.after,
+            delta: change.delta(),
+            status: change.status().to_string(),
+        };
+        SnapshotDiffOutput {
+            created: self.created().count(),
+            closed: self.closed().count(),
+            unchanged: self.unchanged,
+            total_before: self.total_before,
+            total_after: self.total_after,
+            total_change: self.total_change(),
+            top_gainers: self.top_gainers(top).iter().map(delta_output).collect(),
+            changes: self.changes.iter().map(delta_output).collect(),
+        }
+    }
+
+    /// 生成机器可读的JSON
+    ///
+    /// # 参数
+    /// * `top` - 涨幅榜包含的账户数
+    pub fn to_json(&self, top: usize) -> serde_json::Value {
+        serde_json::to_value(self.to_output(top)).expect("快照对比结果总是可以序列化")
+    }
+
+    /// 生成CSV，每个有变化的账户一行，不存在的一侧留空
+    pub fn to_csv(&self) -> String {
+        let format_value = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+        let mut lines = vec!["pubkey,before,after,delta,status".to_string()];
+        for change in &self.changes {
+            lines.push(format!(
+                "{},{},{},{},{}",
+                change.pubkey,
+                format_value(change.before),
+                format_value(change.after),
+                change.delta(),
+                change.status()
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按地址顺序生成n个账户地址，测试中的输出顺序和这里一致
+    fn pubkeys<const N: usize>() -> [Pubkey; N] {
+        let mut pubkeys = [(); N].map(|_| Pubkey::new_unique());
+        pubkeys.sort();
+        pubkeys
+    }
+
+    #[test]
+    fn test_diff_created_closed_and_unchanged() {
+        let [kept, closed, created] = pubkeys();
+        let before = BTreeMap::from([(kept, 5), (closed, 3)]);
+        let after = BTreeMap::from([(kept, 5), (created, 2)]);
+
+        let diff = diff_snapshots(&before, &after);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(
+            diff.changes,
+            vec![
+                AccountDelta { pubkey: closed, before: Some(3), after: None },
+                AccountDelta { pubkey: created, before: None, after: Some(2) },
+            ]
+        );
+        assert_eq!(diff.created().map(|change| change.pubkey).collect::<Vec<_>>(), vec![created]);
+        assert_eq!(diff.closed().map(|change| change.pubkey).collect::<Vec<_>>(), vec![closed]);
+        // 关闭的账户带走了全部功德
+        assert_eq!(diff.changes[0].delta(), -3);
+        assert_eq!((diff.total_before, diff.total_after, diff.total_change()), (8, 7, -1));
+    }
+
+    #[test]
+    fn test_diff_value_decrease_is_reported() {
+        // 回滚或转出后功德变少
+        let [reset, grown] = pubkeys();
+        let before = BTreeMap::from([(reset, 100), (grown, 1)]);
+        let after = BTreeMap::from([(reset, 0), (grown, 4)]);
+
+        let diff = diff_snapshots(&before, &after);
+        assert_eq!(diff.unchanged, 0);
+        assert_eq!(diff.changes[0].delta(), -100);
+        assert_eq!(diff.changes[0].status(), "decreased");
+        assert_eq!(diff.changes[1].status(), "increased");
+        assert_eq!(diff.total_change(), -97);
+        // 功德减少的账户不进涨幅榜
+        assert_eq!(diff.top_gainers(10), vec![diff.changes[1]]);
+    }
+
+    #[test]
+    fn test_diff_identical_snapshots() {
+        let [a, b] = pubkeys();
+        let accounts = BTreeMap::from([(a, 1), (b, 2)]);
+        let diff = diff_snapshots(&accounts, &accounts);
+        assert_eq!(diff.changes, vec![]);
+        assert_eq!((diff.unchanged, diff.total_change()), (2, 0));
+        assert_eq!(diff.to_csv(), "pubkey,before,after,delta,status\n");
+    }
+
+    #[test]
+    fn test_top_gainers_include_created_accounts() {
+        let [a, b, c, d] = pubkeys();
+        let before = BTreeMap::from([(a, 1), (b, 1), (c, 1)]);
+        let after = BTreeMap::from([(a, 3), (b, 11), (c, 3), (d, 5)]);
+
+        let diff = diff_snapshots(&before, &after);
+        let gainers: Vec<(Pubkey, i64)> = diff.top_gainers(3).iter().map(|gainer| (gainer.pubkey, gainer.delta())).collect();
+        // 涨幅相同时按地址排列
+        assert_eq!(gainers, vec![(b, 10), (d, 5), (a, 2)]);
+        assert_eq!(diff.top_gainers(0), vec![]);
+    }
+
+    #[test]
+    fn test_diff_outputs() {
+        let [closed, created] = pubkeys();
+        let diff = diff_snapshots(&BTreeMap::from([(closed, 3)]), &BTreeMap::from([(created, 2)]));
+
+        assert_eq!(diff.to_csv(), format!("pubkey,before,after,delta,status\n{},3,,-3,closed\n{},,2,2,created\n", closed, created));
+        let json = diff.to_json(5);
+        assert_eq!(json["total_change"], -1);
+        assert_eq!(json["changes"][0]["after"], serde_json::Value::Null);
+        assert_eq!(json["top_gainers"][0]["pubkey"], created.to_string());
+        let report = diff.render(5);
+        assert!(report.contains("新建 1，关闭 1，功德变化 0，未变化 0"), "{}", report);
+        assert!(report.contains("功德总计: 3 → 2（-1）"), "{}", report);
+    }
+
+    #[test]
+    fn test_snapshot_json_round_trip() {
+        let [a, b] = pubkeys();
+        let snapshot = Snapshot { program_id: Pubkey::new_unique(), taken_at: 1_700_000_000, accounts: BTreeMap::from([(a, 1), (b, 2)]) };
+        assert_eq!(Snapshot::from_json(&snapshot.to_json()), Ok(snapshot.clone()));
+
+        let mut json = snapshot.to_json();
+        json["accounts"][a.to_string()] = serde_json::json!(-1);
+        assert!(Snapshot::from_json(&json).unwrap_err().contains("功德值无效"));
+        json["version"] = serde_json::json!(SNAPSHOT_FORMAT_VERSION + 1);
+        assert!(Snapshot::from_json(&json).unwrap_err().contains("不支持的快照版本"));
+    }
+}
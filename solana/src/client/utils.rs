@@ -0,0 +1,5089 @@
+// ========================================
+// 工具函数模块
+// 包含客户端常用的工具函数，如余额检查、交易发送等
+// ========================================
+
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::{RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcSimulateTransactionConfig, RpcTransactionConfig},
+    rpc_filter::RpcFilterType,
+    rpc_request::RpcError,
+    rpc_response::{ProcessedSignatureResult, RpcSignatureResult},
+};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer,
+    TransactionConfirmationStatus,
+    TransactionStatus as SignatureStatus,
+    UiTransactionEncoding,
+    UiTransactionStatusMeta,
+};
+use solana_sdk::{
+    account::Account,
+    bpf_loader,
+    bpf_loader_deprecated,
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    rent::Rent,
+    transaction::{Transaction, TransactionError},
+    signature::{Keypair, Signature, Signer},
+};
+use solana_sdk_ids::bpf_loader_upgradeable;
+use solana_system_interface::instruction as system_instruction;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// 引入src中的工具函数，避免重复实现
+// 注意：这里需要使用相对路径引用同一crate中的模块
+use crate::client::output::{ReportOutput, TransactionOutput};
+use crate::error::{GongDeError, StateError};
+use crate::instruction;
+use crate::utils::{
+    day_index,
+    derive_endorsement_address,
+    derive_transfer_state_address,
+    gongde_account_size,
+    is_usable_account,
+    layout_version,
+    read_authority,
+    read_beneficiary,
+    read_last_active,
+    read_last_increment_slot,
+    read_emoji,
+    read_milestones,
+    parse_restore_return_data,
+    MeritBand,
+    MeritChangeEvent,
+    seconds_until_next_day,
+    split_client_version,
+    GongDeAccount,
+    MeritLevel,
+    ProtocolInfo,
+    StakeState,
+    TransferState,
+    DAILY_TRANSFER_CAP,
+    ENDORSEMENT_SIZE,
+    GONGDE_ACCOUNT_SEED,
+    GONGDE_ACCOUNT_SIZE,
+    GONGDE_LAYOUT_VERSION,
+    MAX_ENDORSE_MANY_TARGETS,
+    SECONDS_PER_DAY,
+};
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// 单次 get_multiple_accounts 最多查询的账户数量（RPC限制）
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// 等待交易确认的最长时间
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 轮询交易状态的间隔
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 检查并打印账户余额
+/// 
+/// # 参数
+/// * `client` - RPC客户端
+/// * `pubkey` - 要检查的账户公钥
+/// * `label` - 余额标签（用于日志输出）
+/// 
+/// # 返回
+/// * `Result<u64, Box<dyn std::error::Error>>` - 账户余额（lamports）
+pub fn check_and_print_balance(
+    client: &RpcClient,
+    pubkey: &Pubkey,
+    label: &str,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let balance = client.get_balance(pubkey)?;
+    let sol_balance = balance as f64 / 1_000_000_000.0;
+    info!("💰 {}: {:.6} SOL ({} lamports)", label, sol_balance, balance);
+    Ok(balance)
+}
+
+/// 发送交易并检查余额变化
+/// 
+/// # 参数
+/// * `client` - RPC客户端
+/// * `transaction` - 要发送的交易
+/// * `payer_pubkey` - 交易费用付费者的公钥
+/// * `operation_name` - 操作名称（用于日志输出）
+/// 
+/// # 返回
+/// * `Result<Signature, Box<dyn std::error::Error>>` - 交易签名
+pub fn send_transaction_and_check_balance(
+    client: &RpcClient,
+    transaction: &Transaction,
+    payer_pubkey: &Pubkey,
+    operation_name: &str,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    send_transaction_and_watch(client, transaction, payer_pubkey, operation_name, &[])
+        .map(|(signature, _)| signature)
+}
+
+/// 发送交易并检查余额变化，同时对比关注账户在交易前后的变化
+/// 
+/// # 参数
+/// * `client` - RPC客户端
+/// * `transaction` - 要发送的交易
+/// * `payer_pubkey` - 交易费用付费者的公钥
+/// * `operation_name` - 操作名称（用于日志输出）
+/// * `watched` - 关注的账户和显示名称，为空时不额外查询
+/// 
+/// # 返回
+/// * `Result<(Signature, Vec<AccountDiff>), Box<dyn std::error::Error>>` - 交易签名和与 `watched` 一一对应的变化
+pub fn send_transaction_and_watch(
+    client: &RpcClient,
+    transaction: &Transaction,
+    payer_pubkey: &Pubkey,
+    operation_name: &str,
+    watched: &[(Pubkey, &str)],
+) -> Result<(Signature, Vec<AccountDiff>), Box<dyn std::error::Error>> {
+    let pubkeys: Vec<Pubkey> = watched.iter().map(|(pubkey, _)| *pubkey).collect();
+    let before = snapshot_accounts(client, &pubkeys)?;
+
+    // 发送交易并等待确认，优先使用WebSocket订阅
+    let (signature, method) = send_and_confirm_with_subscription(client, transaction)
+        .inspect_err(|err| report_transaction_failure(operation_name, err))?;
+    info!("✅ {} 成功!", operation_name);
+    debug!("📝 {} 交易签名: {} (确认方式: {:?})", operation_name, signature, method);
+
+    let after = snapshot_accounts(client, &pubkeys)?;
+    let diffs: Vec<AccountDiff> = watched
+        .iter()
+        .zip(before.iter().zip(&after))
+        .map(|((pubkey, label), (before, after))| AccountDiff {
+            pubkey: *pubkey,
+            label: label.to_string(),
+            change: diff_account(before.as_ref(), after.as_ref()),
+        })
+        .collect();
+    for diff in &diffs {
+        info!("📊 {}: {}", diff.label, diff.change);
+    }
+    
+    // 检查操作后的余额
+    check_and_print_balance(client, payer_pubkey, &format!("{}后余额", operation_name))?;
+    
+    Ok((signature, diffs))
+}
+
+// ========================================
+// 交易状态轮询 - 所有等待确认的地方共用，不再各自写轮询循环
+// ========================================
+
+/// 查询交易状态的接口，便于在测试中替换RPC
+pub trait SignatureStatusSource {
+    /// 查询单个签名的状态，节点还没见过这笔交易时返回None
+    fn fetch_signature_status(&self, signature: &Signature) -> ClientResult<Option<SignatureStatus>>;
+}
+
+impl SignatureStatusSource for RpcClient {
+    fn fetch_signature_status(&self, signature: &Signature) -> ClientResult<Option<SignatureStatus>> {
+        Ok(self.get_signature_statuses(&[*signature])?.value.into_iter().next().flatten())
+    }
+}
+
+/// 等待交易确认失败的原因
+#[derive(Debug)]
+pub enum SignatureWaitError {
+    /// 截止时间前没有达到目标确认级别，`reached` 为最后一次看到的级别
+    Timeout {
+        signature: Signature,
+        reached: Option<TransactionConfirmationStatus>,
+    },
+    /// 交易执行失败
+    Failed(TransactionError),
+    /// RPC请求失败
+    Rpc(ClientError),
+}
+
+impl std::fmt::Display for SignatureWaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureWaitError::Timeout { signature, reached: Some(reached) } => {
+                write!(f, "等待交易确认超时: {}（最后状态 {:?}）", signature, reached)
+            }
+            SignatureWaitError::Timeout { signature, reached: None } => {
+                write!(f, "等待交易确认超时: {}（节点还没有看到这笔交易）", signature)
+            }
+            SignatureWaitError::Failed(err) => write!(f, "交易执行失败: {}", err),
+            SignatureWaitError::Rpc(err) => write!(f, "查询交易状态失败: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SignatureWaitError {}
+
+impl From<SignatureWaitError> for ClientError {
+    fn from(err: SignatureWaitError) -> Self {
+        match err {
+            SignatureWaitError::Failed(err) => err.into(),
+            SignatureWaitError::Rpc(err) => err,
+            timeout => ClientErrorKind::Custom(timeout.to_string()).into(),
+        }
+    }
+}
+
+/// 轮询交易状态，直到达到目标确认级别、执行失败或超过截止时间
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `signature` - 交易签名
+/// * `target` - 目标确认级别
+/// * `deadline` - 截止时间
+///
+/// # 错误
+/// * `SignatureWaitError::Timeout` - 截止时间前没有达到目标确认级别
+/// * `SignatureWaitError::Failed` - 交易执行失败
+/// * `SignatureWaitError::Rpc` - RPC请求失败
+pub fn poll_signature_status<C: SignatureStatusSource + ?Sized>(
+    client: &C,
+    signature: &Signature,
+    target: CommitmentConfig,
+    deadline: Instant,
+) -> Result<(), SignatureWaitError> {
+    poll_signature_status_with_sleep(client, signature, target, deadline, std::thread::sleep)
+}
+
+/// 同 `poll_signature_status`，等待方式可以替换，便于测试
+fn poll_signature_status_with_sleep<C: SignatureStatusSource + ?Sized>(
+    client: &C,
+    signature: &Signature,
+    target: CommitmentConfig,
+    deadline: Instant,
+    mut sleep: impl FnMut(Duration),
+) -> Result<(), SignatureWaitError> {
+    let mut reached = None;
+    loop {
+        if let Some(status) = client.fetch_signature_status(signature).map_err(SignatureWaitError::Rpc)? {
+            // 执行失败的交易不会再变成功，不必等到目标级别
+            if let Some(err) = status.err.clone() {
+                return Err(SignatureWaitError::Failed(err));
+            }
+            if status.satisfies_commitment(target) {
+                return Ok(());
+            }
+            reached = Some(status.confirmation_status());
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(SignatureWaitError::Timeout { signature: *signature, reached });
+        }
+        sleep(CONFIRM_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+// ========================================
+// 账户变化对比 - 交易前后各取一次快照，解码功德值并计算余额变化
+// ========================================
+
+/// 账户快照，账户不存在或已关闭时用None表示
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSnapshot {
+    /// 余额（lamports）
+    pub lamports: u64,
+    /// 解码后的功德数据，不是功德账户（例如付款人）时为None
+    pub gongde: Option<GongDeAccount>,
+}
+
+impl AccountSnapshot {
+    /// 从RPC返回的账户生成快照
+    /// 
+    /// # 参数
+    /// * `account` - RPC返回的账户，None表示不存在
+    /// 
+    /// # 返回
+    /// * `Option<AccountSnapshot>` - 账户不存在或余额为0时返回None
+    pub fn from_account(account: Option<&Account>) -> Option<Self> {
+        let account = account.filter(|account| account.lamports > 0)?;
+        Some(Self {
+            lamports: account.lamports,
+            gongde: GongDeAccount::unpack(&account.data).ok(),
+        })
+    }
+}
+
+/// 单个账户在交易前后的变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountChange {
+    /// 交易中新建（例如创建功德账户、自动创建全局PDA）
+    Created { lamports: u64, value: Option<u32> },
+    /// 交易中关闭，`lamports` 为关闭前的余额
+    Closed { lamports: u64, value: Option<u32> },
+    /// 交易前后都存在
+    Updated { value: Option<(u32, u32)>, lamport_delta: i128 },
+}
+
+impl std::fmt::Display for AccountChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountChange::Created { lamports, value } => {
+                write!(f, "新建账户")?;
+                if let Some(value) = value {
+                    write!(f, "，功德 {}", value)?;
+                }
+                write!(f, "，余额 {} lamports", lamports)
+            }
+            AccountChange::Closed { lamports, value } => {
+                write!(f, "账户已关闭")?;
+                if let Some(value) = value {
+                    write!(f, "，关闭前功德 {}", value)?;
+                }
+                write!(f, "，转出 {} lamports", lamports)
+            }
+            AccountChange::Updated { value, lamport_delta } => {
+                match value {
+                    Some((before, after)) => write!(f, "功德 {} → {}", before, after)?,
+                    None => write!(f, "功德无")?,
+                }
+                if *lamport_delta != 0 {
+                    write!(f, "，余额 {:+} lamports", lamport_delta)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 关注账户的变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub pubkey: Pubkey,
+    /// 显示名称
+    pub label: String,
+    pub change: AccountChange,
+}
+
+/// 批量查询账户快照，与输入地址一一对应
+fn snapshot_accounts(client: &RpcClient, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<AccountSnapshot>>> {
+    if pubkeys.is_empty() {
+        return Ok(Vec::new());
+    }
+    let accounts = client.get_multiple_accounts(pubkeys)?;
+    Ok(accounts.iter().map(|account| AccountSnapshot::from_account(account.as_ref())).collect())
+}
+
+/// 对比交易前后的账户快照
+/// 
+/// # 参数
+/// * `before` - 交易前的快照，None表示交易前不存在
+/// * `after` - 交易后的快照，None表示交易后不存在
+/// 
+/// # 返回
+/// * `AccountChange` - 账户变化；前后都不存在时视为余额不变
+pub fn diff_account(before: Option<&AccountSnapshot>, after: Option<&AccountSnapshot>) -> AccountChange {
+    let value = |snapshot: &AccountSnapshot| snapshot.gongde.as_ref().map(|gongde| gongde.value);
+    match (before, after) {
+        (None, Some(after)) => AccountChange::Created { lamports: after.lamports, value: value(after) },
+        (Some(before), None) => AccountChange::Closed { lamports: before.lamports, value: value(before) },
+        (Some(before), Some(after)) => AccountChange::Updated {
+            value: value(before).zip(value(after)),
+            lamport_delta: i128::from(after.lamports) - i128::from(before.lamports),
+        },
+        (None, None) => AccountChange::Updated { value: None, lamport_delta: 0 },
+    }
+}
+
+/// 交易确认方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmMethod {
+    /// 通过WebSocket签名订阅得到通知
+    Subscription,
+    /// WebSocket不可用或超时，回退到轮询
+    Polling,
+}
+
+/// 根据HTTP RPC地址推导WebSocket地址，规则与Solana CLI一致：
+/// http 换成 ws、https 换成 wss，写了端口时端口号加1（本地节点 8899 -> 8900）
+///
+/// # 参数
+/// * `rpc_url` - HTTP RPC地址
+///
+/// # 返回
+/// * `String` - WebSocket地址
+pub fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+    let (host, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) => match port.parse::<u16>() {
+            Ok(port) => format!("{}:{}", name, port.saturating_add(1)),
+            Err(_) => host.to_string(),
+        },
+        None => host.to_string(),
+    };
+    format!("{}://{}{}", scheme, host, path)
+}
+
+/// 发送交易并通过WebSocket签名订阅等待确认，比轮询更快拿到结果
+/// 先订阅再发送，避免确认通知在订阅之前到达；订阅失败或超时时回退到轮询
+///
+/// # 参数
+/// * `client` - RPC客户端，确认级别使用客户端的 commitment
+/// * `transaction` - 已签名的交易
+///
+/// # 返回
+/// * `ClientResult<(Signature, ConfirmMethod)>` - 交易签名和实际使用的确认方式
+pub fn send_and_confirm_with_subscription(
+    client: &RpcClient,
+    transaction: &Transaction,
+) -> ClientResult<(Signature, ConfirmMethod)> {
+    let signature = transaction.signatures[0];
+    let ws_url = websocket_url(&client.url());
+    let config = RpcSignatureSubscribeConfig {
+        commitment: Some(client.commitment()),
+        enable_received_notification: Some(false),
+    };
+
+    let (mut subscription, receiver) = match PubsubClient::signature_subscribe(&ws_url, &signature, Some(config)) {
+        Ok(subscribed) => subscribed,
+        Err(e) => {
+            debug!("🔌 WebSocket订阅失败，回退到轮询: {} ({})", ws_url, e);
+            let signature = client.send_and_confirm_transaction(transaction)?;
+            return Ok((signature, ConfirmMethod::Polling));
+        }
+    };
+
+    let sent = client.send_transaction(transaction);
+    let notification = sent.as_ref().ok().map(|_| receiver.recv_timeout(CONFIRM_TIMEOUT));
+    // 关闭订阅失败不影响交易结果
+    let _ = subscription.shutdown();
+    sent?;
+
+    match notification {
+        Some(Ok(response)) => match response.value {
+            RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: None }) => {
+                Ok((signature, ConfirmMethod::Subscription))
+            }
+            RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: Some(err) }) => Err(err.into()),
+            RpcSignatureResult::ReceivedSignature(_) => {
+                poll_signature_status(client, &signature, client.commitment(), Instant::now() + CONFIRM_TIMEOUT)?;
+                Ok((signature, ConfirmMethod::Polling))
+            }
+        },
+        _ => {
+            warn!("⏳ 未收到WebSocket确认通知，回退到轮询: {}", signature);
+            poll_signature_status(client, &signature, client.commitment(), Instant::now() + CONFIRM_TIMEOUT)?;
+            Ok((signature, ConfirmMethod::Polling))
+        }
+    }
+}
+
+/// 计算并打印总消耗
+/// 
+/// # 参数
+/// * `initial_balance` - 初始余额（lamports）
+/// * `final_balance` - 最终余额（lamports）
+pub fn print_total_consumption(initial_balance: u64, final_balance: u64) {
+    let total_consumed = initial_balance.saturating_sub(final_balance);
+    let total_consumed_sol = total_consumed as f64 / 1_000_000_000.0;
+    info!("📉 总消耗: {:.6} SOL ({} lamports)", total_consumed_sol, total_consumed);
+}
+
+/// 汇总交易元数据中的实际网络手续费
+/// 
+/// # 参数
+/// * `metas` - 交易元数据
+/// 
+/// # 返回
+/// * `u64` - 手续费总和（lamports）
+pub fn sum_transaction_fees<'a>(metas: impl IntoIterator<Item = &'a UiTransactionStatusMeta>) -> u64 {
+    metas.into_iter().map(|meta| meta.fee).sum()
+}
+
+/// 查询本次会话发送的交易，统计实际支付的网络手续费
+/// 余额差值里还包含租金押金和合约收取的功德手续费，这里只统计 meta.fee
+/// 
+/// # 参数
+/// * `client` - RPC客户端
+/// * `signatures` - 本次会话发送的交易签名
+/// 
+/// # 返回
+/// * `ClientResult<u64>` - 网络手续费总和（lamports）
+pub fn fetch_session_fees(client: &RpcClient, signatures: &[Signature]) -> ClientResult<u64> {
+    let mut metas = Vec::with_capacity(signatures.len());
+    for signature in signatures {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = client.get_transaction_with_config(signature, config)?;
+        match transaction.transaction.meta {
+            Some(meta) => metas.push(meta),
+            None => warn!("⚠️  交易 {} 没有元数据，手续费未计入", signature),
+        }
+    }
+    Ok(sum_transaction_fees(&metas))
+}
+
+/// 打印费用明细：网络手续费和其他支出（租金押金、合约手续费）分开显示
+/// 
+/// # 参数
+/// * `initial_balance` - 初始余额（lamports）
+/// * `final_balance` - 最终余额（lamports）
+/// * `network_fees` - 实际网络手续费（lamports）
+pub fn print_cost_breakdown(initial_balance: u64, final_balance: u64, network_fees: u64) {
+    print_total_consumption(initial_balance, final_balance);
+    let other = initial_balance.saturating_sub(final_balance).saturating_sub(network_fees);
+    info!("   ⛽ 网络手续费: {} ({} lamports)", format_sol_balance(network_fees), network_fees);
+    info!("   🏦 租金押金及合约手续费: {} ({} lamports)", format_sol_balance(other), other);
+}
+
+/// 将lamports转换为SOL并格式化显示
+/// 
+/// # 参数
+/// * `lamports` - lamports数量
+/// 
+/// # 返回
+/// * `String` - 格式化的SOL字符串
+pub fn format_sol_balance(lamports: u64) -> String {
+    let sol_balance = lamports as f64 / 1_000_000_000.0;
+    format!("{:.6} SOL", sol_balance)
+}
+
+/// 指定千位分隔符样式的环境变量，可选值 comma、space、dot、none；未设置时按系统语言选择
+pub const NUMBER_STYLE_ENV: &str = "GONGDE_NUMBER_STYLE";
+
+/// 千位分隔符样式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeparatorStyle {
+    /// 1,234,567（中文、英文、日文等）
+    Comma,
+    /// 1 234 567（法文、俄文、波兰文等）
+    Space,
+    /// 1.234.567（德文、西班牙文、意大利文等）
+    Dot,
+    /// 1234567，不分隔
+    None,
+}
+
+impl SeparatorStyle {
+    /// 解析 GONGDE_NUMBER_STYLE 的取值，不区分大小写
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "comma" => Some(Self::Comma),
+            "space" => Some(Self::Space),
+            "dot" => Some(Self::Dot),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// 按语言环境选择样式，例如 "de_DE.UTF-8" 用点，"fr_FR" 用空格，其他（包括 C/POSIX）用逗号
+    pub fn from_locale(locale: &str) -> Self {
+        let language = locale.split(['_', '-', '.', '@']).next().unwrap_or_default().to_ascii_lowercase();
+        match language.as_str() {
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "da" | "tr" | "el" => Self::Dot,
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "uk" | "hu" => Self::Space,
+            _ => Self::Comma,
+        }
+    }
+
+    /// 当前使用的样式：GONGDE_NUMBER_STYLE 优先，其次是 LC_ALL、LC_NUMERIC、LANG；第一次调用后缓存
+    pub fn current() -> Self {
+        static CURRENT: std::sync::OnceLock<SeparatorStyle> = std::sync::OnceLock::new();
+        *CURRENT.get_or_init(|| {
+            if let Ok(name) = std::env::var(NUMBER_STYLE_ENV) {
+                match Self::from_name(&name) {
+                    Some(style) => return style,
+                    None => warn!("⚠️ {} 的取值 {} 无效，可选值: comma, space, dot, none", NUMBER_STYLE_ENV, name),
+                }
+            }
+            ["LC_ALL", "LC_NUMERIC", "LANG"]
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|locale| !locale.is_empty()))
+                .map_or(Self::Comma, |locale| Self::from_locale(&locale))
+        })
+    }
+
+    fn separator(self) -> Option<char> {
+        match self {
+            Self::Comma => Some(','),
+            Self::Space => Some(' '),
+            Self::Dot => Some('.'),
+            Self::None => None,
+        }
+    }
+}
+
+/// 按当前语言环境加上千位分隔符显示数量，例如 1234567 显示为 "1,234,567"
+///
+/// # 参数
+/// * `value` - 功德值等数量
+///
+/// # 返回
+/// * `String` - 格式化后的字符串，样式见 SeparatorStyle::current
+pub fn format_count(value: u64) -> String {
+    format_count_with(value, SeparatorStyle::current())
+}
+
+/// 用指定样式加上千位分隔符，只分配一次结果字符串
+///
+/// # 参数
+/// * `value` - 数量
+/// * `style` - 分隔符样式
+///
+/// # 返回
+/// * `String` - 格式化后的字符串
+pub fn format_count_with(value: u64, style: SeparatorStyle) -> String {
+    // 从低位到高位取出每一位数字，u64 最多20位
+    let mut digits = [0u8; 20];
+    let mut len = 0;
+    let mut rest = value;
+    loop {
+        digits[len] = b'0' + (rest % 10) as u8;
+        len += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+
+    let separator = style.separator();
+    let mut formatted = String::with_capacity(len + (len - 1) / 3);
+    for index in (0..len).rev() {
+        formatted.push(char::from(digits[index]));
+        if let Some(separator) = separator.filter(|_| index > 0 && index % 3 == 0) {
+            formatted.push(separator);
+        }
+    }
+    formatted
+}
+
+/// 默认粗略显示功德的环境变量，设为 1 或 true 时相当于每次都加了 --rough
+pub const ROUGH_MERIT_ENV: &str = "GONGDE_ROUGH";
+
+/// 功德的显示方式
+///
+/// 链上数据是公开的，粗略显示只是不在自己的屏幕和日志里写出准确值，方便不想被比较的用户
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeritDisplay {
+    /// 显示准确值，加千位分隔符
+    Exact,
+    /// 只显示等级区间的下限，例如 "101+"，见 MeritBand
+    Rough,
+}
+
+impl MeritDisplay {
+    /// 根据命令行参数和 GONGDE_ROUGH 的取值选择显示方式：--rough 或环境变量开启粗略显示，--exact 优先
+    ///
+    /// # 参数
+    /// * `args` - 命令行参数
+    /// * `env_value` - GONGDE_ROUGH 的取值，未设置时为None
+    pub fn select<S: AsRef<str>>(args: &[S], env_value: Option<&str>) -> Self {
+        let has = |flag: &str| args.iter().any(|arg| arg.as_ref() == flag);
+        let env_rough = env_value.is_some_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true"));
+        if !has("--exact") && (has("--rough") || env_rough) {
+            Self::Rough
+        } else {
+            Self::Exact
+        }
+    }
+
+    /// 按当前进程的命令行参数和环境变量选择显示方式
+    pub fn current() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        Self::select(&args, std::env::var(ROUGH_MERIT_ENV).ok().as_deref())
+    }
+
+    /// 格式化功德值
+    pub fn format(self, value: u32) -> String {
+        match self {
+            Self::Exact => format_count(value.into()),
+            Self::Rough => MeritBand::from_value(value).to_string(),
+        }
+    }
+
+    /// 格式化累计功德，超出 u32 的部分粗略显示时归入最高等级
+    pub fn format_total(self, value: u64) -> String {
+        match self {
+            Self::Exact => format_count(value),
+            Self::Rough => MeritBand::from_value(u32::try_from(value).unwrap_or(u32::MAX)).to_string(),
+        }
+    }
+}
+
+/// 从账户数据中读取功德值（客户端版本）
+/// 这是对src版本的包装，提供客户端友好的错误处理
+/// 
+/// # 参数
+/// * `account_data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `u32` - 功德值，如果数据不足则返回0
+pub fn read_gongde_value(account_data: &[u8]) -> u32 {
+    // 使用src中的函数，但提供客户端友好的错误处理
+    // 客户端版本：数据不足时返回0而不是错误
+    crate::utils::read_gongde_value(account_data).unwrap_or_default()
+}
+
+/// 生成用户的功德账户地址（客户端版本）
+/// 这是对src版本的包装，提供客户端友好的错误处理
+/// 
+/// # 参数
+/// * `user_pubkey` - 用户公钥
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `Result<Pubkey, Box<dyn std::error::Error>>` - 功德账户地址
+pub fn get_gongde_account_address(
+    user_pubkey: &Pubkey, 
+    program_id: &Pubkey
+) -> Result<Pubkey, Box<dyn std::error::Error>> {
+    // 使用src中的函数，转换错误类型
+    crate::utils::derive_gongde_account_address(user_pubkey, program_id)
+        .map_err(|e| format!("生成账户地址失败: {:?}", e).into())
+}
+
+// ========================================
+// PDA bump 缓存 - find_program_address 最多要尝试255次哈希，知道 bump 后 create_program_address 只算一次
+// 合约的账户里不保存 bump，bump 来自第一次派生，或者 enumerate_user_pdas 清单中记录的值
+// ========================================
+
+/// 按程序ID和种子缓存PDA的 bump，之后的派生走 create_program_address
+#[derive(Debug, Default)]
+pub struct BumpCache {
+    bumps: HashMap<(Pubkey, Vec<Vec<u8>>), u8>,
+}
+
+impl BumpCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 派生PDA地址：缓存中有 bump 时直接用 create_program_address，否则用 find_program_address 并记下 bump
+    ///
+    /// # 参数
+    /// * `seeds` - 不含 bump 的种子
+    /// * `program_id` - 程序ID
+    ///
+    /// # 返回
+    /// * `(Pubkey, u8)` - (PDA地址, bump种子)，与 find_program_address 的结果相同
+    pub fn derive(&mut self, seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+        let key = Self::key(seeds, program_id);
+        if let Some(&bump) = self.bumps.get(&key) {
+            if let Some(address) = create_with_bump(seeds, bump, program_id) {
+                return (address, bump);
+            }
+        }
+        let (address, bump) = Pubkey::find_program_address(seeds, program_id);
+        self.bumps.insert(key, bump);
+        (address, bump)
+    }
+
+    /// 记录从别处得到的 bump（例如地址清单），先用它算出地址并确认就是已有账户的地址
+    ///
+    /// # 参数
+    /// * `seeds` - 不含 bump 的种子
+    /// * `bump` - 要记录的 bump
+    /// * `program_id` - 程序ID
+    /// * `expected` - 已有账户的地址
+    ///
+    /// # 错误
+    /// * `String` - bump 算不出合法的PDA，或算出的地址与已有账户不一致
+    pub fn insert_verified(&mut self, seeds: &[&[u8]], bump: u8, program_id: &Pubkey, expected: &Pubkey) -> Result<(), String> {
+        match create_with_bump(seeds, bump, program_id) {
+            Some(address) if address == *expected => {
+                self.bumps.insert(Self::key(seeds, program_id), bump);
+                Ok(())
+            }
+            Some(address) => Err(format!("bump {} 派生出的地址 {} 与账户地址 {} 不一致", bump, address, expected)),
+            None => Err(format!("bump {} 派生出的地址在曲线上，不是合法的PDA", bump)),
+        }
+    }
+
+    fn key(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, Vec<Vec<u8>>) {
+        (*program_id, seeds.iter().map(|seed| seed.to_vec()).collect())
+    }
+}
+
+/// 把 bump 附在种子最后，用 create_program_address 算出地址；结果在曲线上时返回None
+fn create_with_bump(seeds: &[&[u8]], bump: u8, program_id: &Pubkey) -> Option<Pubkey> {
+    let bump = [bump];
+    let mut seeds = seeds.to_vec();
+    seeds.push(&bump);
+    Pubkey::create_program_address(&seeds, program_id).ok()
+}
+
+/// 可用功德账户的详细信息，字段名即含义；以后账户新增的字段也加在这里，调用方按名称取用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GongDeAccountInfo {
+    /// 功德账户地址
+    pub pubkey: Pubkey,
+    /// 当前功德
+    pub value: u32,
+    /// 累计功德，只增不减
+    pub lifetime_total: u64,
+    /// 账户余额（lamports）
+    pub lamports: u64,
+    /// 账户主人最后一次操作的时间（Unix时间戳），布局v4之前的账户没有记录
+    pub last_active: Option<i64>,
+    /// 按当前功德计算的等级
+    pub level: MeritLevel,
+    /// 是否允许任何人增加功德
+    pub public: bool,
+    /// 是否已归档
+    pub archived: bool,
+    /// 账户主人设置的头像emoji，布局v8之前的账户和没有设置时为None
+    pub emoji: Option<char>,
+}
+
+impl GongDeAccountInfo {
+    /// 从账户数据解析
+    ///
+    /// # 参数
+    /// * `pubkey` - 功德账户地址
+    /// * `account` - 查到的账户
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据不足4字节
+    pub fn from_account(pubkey: Pubkey, account: &Account) -> Result<Self, StateError> {
+        let gongde = GongDeAccount::unpack(&account.data)?;
+        Ok(Self {
+            pubkey,
+            value: gongde.value,
+            lifetime_total: gongde.lifetime_total,
+            lamports: account.lamports,
+            last_active: read_last_active(&account.data),
+            level: MeritLevel::from_value(gongde.value),
+            public: gongde.is_public(),
+            archived: gongde.is_archived(),
+            emoji: read_emoji(&account.data),
+        })
+    }
+}
+
+/// 显示在用户旁边的头像，没有设置时为空字符串，设置了时前面带一个空格
+pub fn emoji_suffix(emoji: Option<char>) -> String {
+    emoji.map(|emoji| format!(" {}", emoji)).unwrap_or_default()
+}
+
+/// 查询功德账户的结果
+///
+/// 账户关闭时余额被转走，数据要等运行时回收账户后才会消失；
+/// 在此之前节点可能还会返回一个余额为0但仍带着数据的账户，
+/// 这和从未创建过的账户不同，需要分开告诉用户
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GongDeAccountLookup {
+    /// 账户存在且有余额
+    Live(GongDeAccountInfo),
+    /// 账户已关闭（余额为0），但数据还没有被回收
+    ClosedButPresent {
+        /// 功德账户地址
+        pubkey: Pubkey,
+        /// 残留的数据长度
+        data_len: usize,
+    },
+    /// 链上没有这个账户：从未创建，或关闭后已被回收
+    NotFound {
+        /// 功德账户地址
+        pubkey: Pubkey,
+    },
+}
+
+impl GongDeAccountLookup {
+    /// 功德账户地址
+    pub fn pubkey(&self) -> Pubkey {
+        match *self {
+            Self::Live(GongDeAccountInfo { pubkey, .. })
+            | Self::ClosedButPresent { pubkey, .. }
+            | Self::NotFound { pubkey } => pubkey,
+        }
+    }
+
+    /// 只有账户可用时才返回账户信息
+    pub fn live(&self) -> Option<GongDeAccountInfo> {
+        match *self {
+            Self::Live(info) => Some(info),
+            _ => None,
+        }
+    }
+
+    /// 当前可用的功德值，已关闭或不存在时为0
+    pub fn value(&self) -> u32 {
+        self.live().map(|info| info.value).unwrap_or(0)
+    }
+}
+
+/// 根据查到的账户判断功德账户的状态
+///
+/// # 参数
+/// * `gongde_pubkey` - 功德账户地址
+/// * `account` - 查到的账户，不存在时为None
+///
+/// # 返回
+/// * `Result<GongDeAccountLookup, Box<dyn std::error::Error>>` - 账户状态
+///
+/// # 错误
+/// * 账户有余额但数据无法解析为功德账户
+pub fn classify_gongde_account(
+    gongde_pubkey: Pubkey,
+    account: Option<Account>,
+) -> Result<GongDeAccountLookup, Box<dyn std::error::Error>> {
+    let Some(account) = account else {
+        return Ok(GongDeAccountLookup::NotFound { pubkey: gongde_pubkey });
+    };
+    // 余额为0说明账户已经关闭，残留的数据不代表当前功德
+    if account.lamports == 0 {
+        return Ok(GongDeAccountLookup::ClosedButPresent {
+            pubkey: gongde_pubkey,
+            data_len: account.data.len(),
+        });
+    }
+    let info = GongDeAccountInfo::from_account(gongde_pubkey, &account)
+        .map_err(|e| format!("功德账户 {} 数据无法解析: {:?}", gongde_pubkey, e))?;
+    Ok(GongDeAccountLookup::Live(info))
+}
+
+/// 查询用户的功德账户信息
+/// 
+/// # 参数
+/// * `client` - 账户查询接口，通常是RPC客户端
+/// * `user_pubkey` - 用户公钥
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `Result<GongDeAccountLookup, Box<dyn std::error::Error>>` - 
+///   账户可用、已关闭但数据还在、或不存在
+///
+/// # 错误
+/// * RPC请求失败，或账户数据无法解析
+pub fn query_gongde_account<C: AccountFetcher + ?Sized>(
+    client: &C,
+    user_pubkey: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<GongDeAccountLookup, Box<dyn std::error::Error>> {
+    // 生成功德账户地址
+    let gongde_pubkey = get_gongde_account_address(user_pubkey, program_id)?;
+    
+    // 查询失败直接报错，不能当成账户不存在
+    let account = client.fetch_account(&gongde_pubkey)?;
+    classify_gongde_account(gongde_pubkey, account)
+}
+
+/// 直接按地址查询功德账户，不从用户公钥派生
+/// 用于调试不在派生地址上的账户，例如旧的 create_with_seed 账户或客户端随机生成的密钥对账户
+///
+/// # 参数
+/// * `client` - 账户查询接口，通常是RPC客户端
+/// * `gongde_pubkey` - 功德账户地址
+/// * `program_id` - 程序ID，账户必须归它所有
+///
+/// # 返回
+/// * `Result<GongDeAccountLookup, Box<dyn std::error::Error>>` -
+///   账户可用、已关闭但数据还在、或不存在
+///
+/// # 错误
+/// * RPC请求失败，账户不归本程序所有，或账户数据无法解析
+pub fn query_gongde_account_at<C: AccountFetcher + ?Sized>(
+    client: &C,
+    gongde_pubkey: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<GongDeAccountLookup, Box<dyn std::error::Error>> {
+    let account = client.fetch_account(gongde_pubkey)?;
+    // 地址是手动指定的，可能根本不是功德账户，先确认归本程序所有再解析
+    if let Some(account) = &account {
+        if account.owner != *program_id {
+            return Err(format!("账户 {} 不归本程序所有（所有者 {}）", gongde_pubkey, account.owner).into());
+        }
+    }
+    classify_gongde_account(*gongde_pubkey, account)
+}
+
+/// 格式化并打印功德账户信息
+/// 
+/// # 参数
+/// * `user_pubkey` - 用户公钥，直接指定账户地址查询时为None
+/// * `lookup` - 功德账户查询结果
+/// * `merit_display` - 功德的显示方式，粗略显示时日志和JSON字段都不含准确值
+pub fn print_gongde_info(user_pubkey: Option<&Pubkey>, lookup: &GongDeAccountLookup, merit_display: MeritDisplay) {
+    // 设置了头像时显示在用户旁边
+    let emoji = emoji_suffix(lookup.live().and_then(|info| info.emoji));
+    match user_pubkey {
+        Some(user_pubkey) => info!("👤 用户地址: {}{}", user_pubkey, emoji),
+        None => info!("👤 用户地址: 未知（直接指定了账户地址）"),
+    }
+    
+    match *lookup {
+        GongDeAccountLookup::Live(info) => {
+            if info.archived {
+                info!("🗄️  功德账户已归档：功德原样保留，取消归档之前不能增加或转出");
+            } else {
+                info!("✅ 功德账户已存在");
+            }
+            info!("📍 功德账户地址: {}", info.pubkey);
+            // 回滚和转出会调低当前功德，累计功德只增不减
+            info!(
+                merit = merit_display.format(info.value),
+                "🙏 功德: 当前 {} / 累计 {}",
+                merit_display.format(info.value),
+                merit_display.format_total(info.lifetime_total)
+            );
+            info!("💰 账户余额: {}", format_sol_balance(info.lamports));
+            // 新账户在第一次操作之前记录为0
+            if let Some(last_active) = info.last_active.filter(|last_active| *last_active > 0) {
+                info!("🕐 最后操作: {}", format_utc_datetime(last_active));
+            }
+            
+            // 功德等级判断
+            info!(merit_level = info.level.id(), "🏅 功德等级: {}", info.level);
+        },
+        GongDeAccountLookup::ClosedButPresent { pubkey: gongde_pubkey, data_len } => {
+            info!("🔒 功德账户已关闭");
+            info!("📍 功德账户地址: {}", gongde_pubkey);
+            info!("🗑️  余额为0，残留的 {} 字节数据等待回收，不再计入功德", data_len);
+            info!("💡 提示: 可以使用 client.rs 重新创建功德账户");
+        },
+        GongDeAccountLookup::NotFound { pubkey: gongde_pubkey } => {
+            info!("❌ 功德账户不存在");
+            info!("📍 功德账户地址: {}", gongde_pubkey);
+            info!("💡 提示: 可以使用 client.rs 创建功德账户");
+        }
+    }
+}
+
+// ========================================
+// 账户转储 - 排查问题时一次列出账户的全部信息，附在问题反馈里
+// ========================================
+
+/// 原始数据每行显示的字节数
+const DUMP_BYTES_PER_LINE: usize = 16;
+
+/// 账户的完整转储，用 Display 输出基本信息、租金、解析结果、原始数据和检查结果五个部分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDump {
+    /// 账户地址
+    pub pubkey: Pubkey,
+    /// 本合约的程序ID，用于检查所有者
+    pub program_id: Pubkey,
+    /// 查到的账户，不存在时为None
+    pub account: Option<Account>,
+    /// 按数据长度计算的免租金最低余额
+    pub rent_exempt_minimum: u64,
+}
+
+impl AccountDump {
+    /// 检查账户中的不一致
+    ///
+    /// # 返回
+    /// * `Vec<String>` - 发现的问题，没有问题时为空
+    pub fn issues(&self) -> Vec<String> {
+        let Some(account) = &self.account else {
+            return vec!["链上没有这个账户：从未创建，或关闭后已被回收".to_string()];
+        };
+        let mut issues = Vec::new();
+        if account.owner != self.program_id {
+            issues.push(format!("所有者 {} 不是本程序 {}", account.owner, self.program_id));
+        }
+        if account.lamports == 0 {
+            issues.push("余额为0：账户已关闭，残留的数据等待回收".to_string());
+        } else if account.lamports < self.rent_exempt_minimum {
+            issues.push(format!("余额低于免租金要求，还差 {} lamports", self.rent_exempt_minimum - account.lamports));
+        }
+        let Some(version) = layout_version(account.data.len()) else {
+            issues.push(format!("数据只有 {} 字节，不是功德账户", account.data.len()));
+            return issues;
+        };
+        if version < GONGDE_LAYOUT_VERSION {
+            issues.push(format!("布局 v{} 旧于程序当前布局 v{}，可以运行 migrate 迁移", version, GONGDE_LAYOUT_VERSION));
+        }
+        if let Ok(gongde) = GongDeAccount::unpack(&account.data) {
+            // 只有4字节的旧账户没有标志位，按未初始化的公开账户使用，不算问题
+            if version >= 2 && !gongde.is_initialized() {
+                issues.push("标志位中没有初始化标记".to_string());
+            }
+            if version >= 3 && u64::from(gongde.value) > gongde.lifetime_total {
+                issues.push(format!("当前功德 {} 大于累计功德 {}", gongde.value, gongde.lifetime_total));
+            }
+        }
+        issues
+    }
+
+    /// 输出解析结果部分
+    fn fmt_decoded(&self, f: &mut std::fmt::Formatter<'_>, data: &[u8]) -> std::fmt::Result {
+        let (Some(version), Ok(gongde)) = (layout_version(data.len()), GongDeAccount::unpack(data)) else {
+            return writeln!(f, "  无法解析为功德账户");
+        };
+        writeln!(f, "  布局版本: v{}（程序当前 v{}）", version, GONGDE_LAYOUT_VERSION)?;
+        writeln!(f, "  功德: 当前 {} / 累计 {}", gongde.value, gongde.lifetime_total)?;
+        writeln!(f, "  等级: {}", MeritLevel::from_value(gongde.value))?;
+        writeln!(
+            f,
+            "  标志位: 0x{:02x}（{}，{}{}）",
+            gongde.flags,
+            if gongde.is_initialized() { "已初始化" } else { "未初始化" },
+            if gongde.is_public() { "公开功德箱" } else { "私有功德箱" },
+            if gongde.is_archived() { "，已归档" } else { "" },
+        )?;
+        if version >= 4 {
+            let milestones = read_milestones(data);
+            if milestones.is_empty() {
+                writeln!(f, "  里程碑: 还没有达成")?;
+            }
+            for milestone in milestones {
+                writeln!(f, "  里程碑: {} 功德，达成于 {}", milestone.milestone, format_utc_datetime(milestone.reached_at))?;
+            }
+        }
+        if let Some(last_active) = read_last_active(data) {
+            writeln!(f, "  最后操作: {}", if last_active > 0 { format_utc_datetime(last_active) } else { "还没有操作".to_string() })?;
+            match read_beneficiary(data) {
+                Some((beneficiary, timeout_days)) => writeln!(f, "  受益人: {}（等待 {} 天）", beneficiary, timeout_days)?,
+                None => writeln!(f, "  受益人: 未指定")?,
+            }
+            match read_authority(data) {
+                Some(authority) => writeln!(f, "  账户主人: 已被接管 → {}", authority)?,
+                None => writeln!(f, "  账户主人: 派生出地址的用户")?,
+            }
+        }
+        if let Ok(stake) = StakeState::unpack(data) {
+            writeln!(
+                f,
+                "  质押: {} lamports，上次结算slot {}，零头 {}",
+                stake.staked, stake.last_claim_slot, stake.remainder
+            )?;
+        }
+        if let Some(slot) = read_last_increment_slot(data) {
+            writeln!(f, "  工作量证明: 上次证明的slot {}", slot)?;
+        }
+        if version >= 8 {
+            match read_emoji(data) {
+                Some(emoji) => writeln!(f, "  头像: {}（U+{:04X}）", emoji, emoji as u32)?,
+                None => writeln!(f, "  头像: 未设置")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for AccountDump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "📦 账户转储: {}", self.pubkey)?;
+        writeln!(f, "== 基本信息 ==")?;
+        match &self.account {
+            None => writeln!(f, "  账户不存在")?,
+            Some(account) => {
+                let owner_note = if account.owner == self.program_id { "本程序" } else { "⚠️ 不是本程序" };
+                writeln!(f, "  所有者: {}（{}）", account.owner, owner_note)?;
+                writeln!(f, "  余额: {} ({} lamports)", format_sol_balance(account.lamports), account.lamports)?;
+                writeln!(f, "  数据长度: {} 字节", account.data.len())?;
+                writeln!(f, "  可执行: {}", if account.executable { "是" } else { "否" })?;
+
+                writeln!(f, "== 租金 ==")?;
+                writeln!(f, "  免租金最低余额: {} lamports", self.rent_exempt_minimum)?;
+                let rent_status = if account.lamports >= self.rent_exempt_minimum { "已免租金" } else { "⚠️ 低于免租金要求" };
+                writeln!(f, "  状态: {}", rent_status)?;
+
+                writeln!(f, "== 解析结果 ==")?;
+                self.fmt_decoded(f, &account.data)?;
+
+                writeln!(f, "== 原始数据 ==")?;
+                for (line, chunk) in account.data.chunks(DUMP_BYTES_PER_LINE).enumerate() {
+                    let bytes: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+                    writeln!(f, "  {:04x}  {}", line * DUMP_BYTES_PER_LINE, bytes.join(" "))?;
+                }
+            }
+        }
+
+        writeln!(f, "== 检查结果 ==")?;
+        let issues = self.issues();
+        if issues.is_empty() {
+            writeln!(f, "  ✅ 没有发现问题")?;
+        }
+        for issue in issues {
+            writeln!(f, "  ⚠️ {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// 查询账户并生成完整转储，打印结果即可附在问题反馈里
+///
+/// # 参数
+/// * `client` - 账户查询接口，通常是RPC客户端
+/// * `pubkey` - 要转储的账户地址
+/// * `program_id` - 本合约的程序ID
+///
+/// # 返回
+/// * `ClientResult<AccountDump>` - 账户转储；账户不存在时也会返回，在检查结果中注明
+///
+/// # 错误
+/// * RPC请求失败
+pub fn dump_account<C: AccountFetcher + ?Sized>(client: &C, pubkey: &Pubkey, program_id: &Pubkey) -> ClientResult<AccountDump> {
+    let account = client.fetch_account(pubkey)?;
+    let data_len = account.as_ref().map_or(0, |account| account.data.len());
+    Ok(AccountDump {
+        pubkey: *pubkey,
+        program_id: *program_id,
+        account,
+        rent_exempt_minimum: Rent::default().minimum_balance(data_len),
+    })
+}
+
+// ========================================
+// 命令行参数 - 示例程序共用的 `--name value` 参数读取
+// ========================================
+
+/// 读取命令行中 `--name value` 形式的参数
+///
+/// # 参数
+/// * `args` - 命令行参数
+/// * `name` - 参数名，例如 `--out`
+///
+/// # 返回
+/// * `Option<&str>` - 参数名之后的值；没有这个参数，或参数名是最后一个时返回None
+pub fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|arg| arg == name).and_then(|index| args.get(index + 1)).map(String::as_str)
+}
+
+/// 读取并解析 `--name value` 形式的参数，未提供时使用默认值
+///
+/// # 错误
+/// * 参数值无法解析时返回带参数名的说明
+pub fn parse_arg<T: std::str::FromStr>(args: &[String], name: &str, default: T) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    match arg_value(args, name) {
+        Some(value) => value.parse().map_err(|e| format!("参数 {} 无效: {}", name, e)),
+        None => Ok(default),
+    }
+}
+
+// ========================================
+// 进度条
+// ========================================
+
+/// 进度条的格数
+pub const PROGRESS_BAR_LEN: usize = 10;
+
+/// 生成功德进度条，长度固定为 PROGRESS_BAR_LEN 个字符
+///
+/// # 参数
+/// * `value` - 功德值
+///
+/// # 返回
+/// * `String` - 例如 "▰▰▱▱▱▱▱▱▱▱"
+pub fn progress_bar(value: u32) -> String {
+    // 最高等级点满，其余等级每级点亮一格
+    let filled = match MeritLevel::from_value(value) {
+        MeritLevel::Perfect => PROGRESS_BAR_LEN,
+        level => level as usize,
+    };
+    "▰".repeat(filled) + &"▱".repeat(PROGRESS_BAR_LEN - filled)
+}
+
+// ========================================
+// 转赠额度预测 - 使用集群时间而不是本地时间，和链上按同一个“今天”计算
+// ========================================
+
+/// 今日转赠额度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferQuota {
+    /// 今天还能转出的功德
+    pub remaining: u32,
+    /// 距离额度恢复（UTC零点）的秒数
+    pub resets_in: i64,
+}
+
+/// 根据转赠状态预测今日额度，判断逻辑与链上 TransferMerit 一致
+///
+/// # 参数
+/// * `state` - 转赠状态PDA的内容，从未转赠过时为None
+/// * `now` - 集群时间（Unix时间戳）
+pub fn predict_transfer_quota(state: Option<TransferState>, now: i64) -> TransferQuota {
+    let remaining = state.unwrap_or_default().remaining_today(day_index(now), DAILY_TRANSFER_CAP);
+    TransferQuota { remaining, resets_in: seconds_until_next_day(now) }
+}
+
+/// 查询集群当前时间：取最新已最终确认区块的出块时间
+/// 验证节点的时钟与本地时钟可能相差几秒到几分钟，换日前后用本地时间预测会和链上结果不一致
+///
+/// # 返回
+/// * `ClientResult<i64>` - Unix时间戳（秒），比链上Clock略早（最终确认有十几秒延迟）
+pub fn cluster_unix_timestamp(client: &RpcClient) -> ClientResult<i64> {
+    let slot = client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    client.get_block_time(slot)
+}
+
+/// 查询用户今日剩余的转赠额度
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `program_id` - 程序ID
+/// * `sender` - 转出者公钥
+///
+/// # 返回
+/// * `ClientResult<TransferQuota>` - 按集群时间计算的额度
+pub fn fetch_transfer_quota(client: &RpcClient, program_id: &Pubkey, sender: &Pubkey) -> ClientResult<TransferQuota> {
+    let (state_pubkey, _) = derive_transfer_state_address(sender, program_id);
+    let state = client
+        .get_account_with_commitment(&state_pubkey, client.commitment())?
+        .value
+        .and_then(|account| TransferState::unpack(&account.data).ok());
+    Ok(predict_transfer_quota(state, cluster_unix_timestamp(client)?))
+}
+
+/// 把秒数格式化为“X小时Y分”
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = (seconds + 59) / 60;
+    format!("{}小时{}分", minutes / 60, minutes % 60)
+}
+
+/// 把Unix时间戳格式化为UTC日期时间“YYYY-MM-DD HH:MM UTC”，与链上按UTC零点换日一致
+pub fn format_utc_datetime(unix_timestamp: i64) -> String {
+    let days = unix_timestamp.div_euclid(SECONDS_PER_DAY);
+    let minutes = unix_timestamp.rem_euclid(SECONDS_PER_DAY) / 60;
+    // 按天数换算公历日期（Howard Hinnant 的 civil_from_days 算法）
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, minutes / 60, minutes % 60)
+}
+
+/// 令牌桶限流器：每秒补充 rate 个令牌，最多积攒 burst 个
+/// 每个请求消耗一个令牌，令牌不足时需要等待
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate_per_sec: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// 创建限流器，初始令牌是满的
+    ///
+    /// # 参数
+    /// * `rate_per_sec` - 每秒允许的请求数
+    /// * `burst` - 允许的突发请求数
+    /// * `now` - 起始时间
+    pub fn new(rate_per_sec: f64, burst: u32, now: Instant) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate_per_sec: rate_per_sec.max(f64::MIN_POSITIVE),
+            burst,
+            tokens: burst,
+            last_refill: now,
+        }
+    }
+
+    /// 申请一个令牌，返回在发送请求前需要等待的时间
+    /// 令牌按时间补充，等待期间补充的令牌会被这次请求用掉
+    ///
+    /// # 参数
+    /// * `now` - 当前时间（测试中可以传入模拟时间）
+    ///
+    /// # 返回
+    /// * `Duration` - 需要等待的时间，令牌充足时为0
+    pub fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.burst);
+        self.last_refill = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate_per_sec)
+        }
+    }
+}
+
+/// 判断错误是否为RPC限流（HTTP 429 或节点返回的限流错误）
+pub fn is_rate_limited(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Reqwest(e) => e.status().map(|status| status.as_u16() == 429).unwrap_or(false),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+            *code == 429 || *code == -32429 || message.to_lowercase().contains("too many requests")
+        }
+        _ => false,
+    }
+}
+
+/// 从限流错误中提取节点建议的重试等待时间
+/// 部分RPC服务商会在错误信息里带上 "retry after 2s" 之类的提示
+pub fn retry_after_hint(err: &ClientError) -> Option<Duration> {
+    let message = match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { message, .. }) => message.to_lowercase(),
+        _ => return None,
+    };
+    let rest = &message[message.find("retry after")? + "retry after".len()..];
+    let seconds: String = rest.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+    seconds.parse().ok().map(Duration::from_secs)
+}
+
+/// 批量操作的请求节流器：令牌桶限速 + 429退避重试 + 进度显示
+pub struct Throttle {
+    bucket: TokenBucket,
+    max_retries: u32,
+    base_backoff: Duration,
+    max_backoff: Duration,
+    sleep: Box<dyn FnMut(Duration)>,
+}
+
+impl Throttle {
+    /// 创建节流器
+    ///
+    /// # 参数
+    /// * `rate_per_sec` - 每秒允许的请求数
+    /// * `burst` - 允许的突发请求数
+    pub fn new(rate_per_sec: f64, burst: u32) -> Self {
+        Self {
+            bucket: TokenBucket::new(rate_per_sec, burst, Instant::now()),
+            max_retries: 5,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            sleep: Box::new(std::thread::sleep),
+        }
+    }
+
+    /// 替换等待函数，测试中用来记录等待时间而不真正睡眠
+    pub fn with_sleep(mut self, sleep: impl FnMut(Duration) + 'static) -> Self {
+        self.sleep = Box::new(sleep);
+        self
+    }
+
+    /// 第 attempt 次重试前的退避时间：优先使用节点提示，否则指数增长
+    pub fn backoff_delay(&self, attempt: u32, hint: Option<Duration>) -> Duration {
+        hint.unwrap_or_else(|| self.base_backoff.saturating_mul(1 << attempt.min(16)))
+            .min(self.max_backoff)
+    }
+
+    /// 限速执行一个请求，遇到429时退避重试，其他错误直接返回
+    pub fn run<T>(&mut self, mut op: impl FnMut() -> ClientResult<T>) -> ClientResult<T> {
+        let mut attempt = 0;
+        loop {
+            let wait = self.bucket.reserve(Instant::now());
+            if !wait.is_zero() {
+                (self.sleep)(wait);
+            }
+
+            match op() {
+                Err(err) if is_rate_limited(&err) && attempt < self.max_retries => {
+                    let delay = self.backoff_delay(attempt, retry_after_hint(&err));
+                    warn!("⏳ RPC限流，{:.1}秒后重试 ({}/{})", delay.as_secs_f64(), attempt + 1, self.max_retries);
+                    (self.sleep)(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for Throttle {
+    /// 公共devnet节点的保守默认值：每秒5个请求，突发10个
+    fn default() -> Self {
+        Self::new(DEFAULT_RPC_RATE, 10)
+    }
+}
+
+/// 批量扫描时每秒请求数的环境变量，付费节点可以调高
+pub const RPC_RATE_ENV: &str = "GONGDE_RPC_RATE";
+
+/// 未配置 GONGDE_RPC_RATE 时每秒的请求数
+pub const DEFAULT_RPC_RATE: f64 = 5.0;
+
+/// 解析每秒请求数，未设置或不是正数时使用默认值
+fn parse_rpc_rate(value: Option<&str>) -> f64 {
+    match value.map(|value| value.trim().parse::<f64>()) {
+        Some(Ok(rate)) if rate.is_finite() && rate > 0.0 => rate,
+        Some(_) => {
+            warn!("⚠️ {} 不是有效的每秒请求数，使用默认值 {}", RPC_RATE_ENV, DEFAULT_RPC_RATE);
+            DEFAULT_RPC_RATE
+        }
+        None => DEFAULT_RPC_RATE,
+    }
+}
+
+impl Throttle {
+    /// 按 GONGDE_RPC_RATE 环境变量创建节流器，突发额度为2秒的请求数
+    pub fn from_env() -> Self {
+        let rate = parse_rpc_rate(std::env::var(RPC_RATE_ENV).ok().as_deref());
+        Self::new(rate, (rate * 2.0).ceil() as u32)
+    }
+}
+
+/// 打印批量请求进度
+pub fn print_progress(issued: usize, total: usize) {
+    debug!("📡 请求进度: {}/{} (剩余 {})", issued, total, total.saturating_sub(issued));
+}
+
+/// 批量查询时单个账户的解码错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// 账户不存在
+    NotFound,
+    /// 账户已关闭（余额为0）
+    Closed,
+    /// 账户不归本程序所有
+    WrongOwner(Pubkey),
+    /// 数据长度不足，无法读取功德值
+    DataTooSmall(usize),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::NotFound => write!(f, "账户不存在"),
+            DecodeError::Closed => write!(f, "账户已关闭"),
+            DecodeError::WrongOwner(owner) => write!(f, "账户不归本程序所有，所有者: {}", owner),
+            DecodeError::DataTooSmall(len) => write!(f, "账户数据只有{}字节，无法解析功德值", len),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// 解码单个查询结果
+/// 
+/// # 参数
+/// * `account` - RPC返回的账户，None表示不存在
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `Result<GongDeAccount, DecodeError>` - 解码后的功德账户或失败原因
+pub fn decode_gongde_account(
+    account: Option<&Account>,
+    program_id: &Pubkey,
+) -> Result<GongDeAccount, DecodeError> {
+    let account = account.ok_or(DecodeError::NotFound)?;
+    if account.lamports == 0 {
+        return Err(DecodeError::Closed);
+    }
+    if account.owner != *program_id {
+        return Err(DecodeError::WrongOwner(account.owner));
+    }
+    GongDeAccount::unpack(&account.data).map_err(|_| DecodeError::DataTooSmall(account.data.len()))
+}
+
+/// 批量查询并解码功德账户，单个账户失败不影响其他账户
+/// 超过RPC单次上限时自动分批查询，每批请求都经过节流器
+/// 
+/// # 参数
+/// * `client` - RPC客户端
+/// * `pubkeys` - 要查询的功德账户地址
+/// * `program_id` - 程序ID
+/// * `throttle` - 请求节流器
+/// 
+/// # 返回
+/// * `Result<Vec<Result<GongDeAccount, DecodeError>>, Box<dyn std::error::Error>>` - 
+///   与输入地址一一对应的解码结果；只有RPC请求本身失败时才返回外层错误
+pub fn fetch_gongde_accounts(
+    client: &RpcClient,
+    pubkeys: &[Pubkey],
+    program_id: &Pubkey,
+    throttle: &mut Throttle,
+) -> Result<Vec<Result<GongDeAccount, DecodeError>>, Box<dyn std::error::Error>> {
+    let mut results = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = throttle.run(|| client.get_multiple_accounts(chunk))?;
+        print_progress(results.len() + chunk.len(), pubkeys.len());
+        results.extend(
+            accounts
+                .iter()
+                .map(|account| decode_gongde_account(account.as_ref(), program_id)),
+        );
+    }
+    Ok(results)
+}
+
+/// 用户列表按功德账户现状分类的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserPartition {
+    /// 还没有功德账户，需要开通
+    pub new_users: Vec<Pubkey>,
+    /// 已有功德账户，不需要再开通
+    pub existing: Vec<Pubkey>,
+    /// 地址被占用但不是有效的功德账户，无法开通，需要人工处理
+    pub blocked: Vec<(Pubkey, DecodeError)>,
+}
+
+/// 根据批量查询结果把用户分类
+///
+/// # 参数
+/// * `users` - 用户公钥
+/// * `results` - 与 users 一一对应的功德账户查询结果
+pub fn partition_users(users: &[Pubkey], results: Vec<Result<GongDeAccount, DecodeError>>) -> UserPartition {
+    let mut partition = UserPartition::default();
+    for (user, result) in users.iter().zip(results) {
+        match result {
+            Ok(_) => partition.existing.push(*user),
+            Err(DecodeError::NotFound | DecodeError::Closed) => partition.new_users.push(*user),
+            Err(e) => partition.blocked.push((*user, e)),
+        }
+    }
+    partition
+}
+
+/// 等待账户出现时使用的账户来源，RpcClient实现它，测试中用模拟客户端代替
+pub trait AccountFetcher {
+    /// 查询账户，不存在时返回None
+    fn fetch_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>>;
+}
+
+impl AccountFetcher for RpcClient {
+    fn fetch_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        Ok(self.get_account_with_commitment(pubkey, self.commitment())?.value)
+    }
+}
+
+/// 等待账户出现失败的原因
+#[derive(Debug)]
+pub enum WaitError {
+    /// 达到最大轮询次数仍未找到账户
+    Timeout { attempts: u32, waited: Duration },
+    /// RPC请求失败
+    Rpc(ClientError),
+}
+
+impl std::fmt::Display for WaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitError::Timeout { attempts, waited } => {
+                write!(f, "等待账户超时: 轮询{}次，共等待{:.1}秒", attempts, waited.as_secs_f64())
+            }
+            WaitError::Rpc(err) => write!(f, "查询账户失败: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+/// 账户轮询器：有限次数轮询，两次查询之间的等待时间指数增长，避免频繁请求RPC
+/// 用于空投到账、创建账户后等待可见等场景
+pub struct AccountPoller {
+    base_delay: Duration,
+    max_delay: Duration,
+    max_attempts: u32,
+    sleep: Box<dyn FnMut(Duration)>,
+}
+
+impl AccountPoller {
+    /// 创建轮询器
+    ///
+    /// # 参数
+    /// * `base_delay` - 第一次重试前的等待时间
+    /// * `max_delay` - 单次等待时间上限
+    /// * `max_attempts` - 最多查询次数
+    pub fn new(base_delay: Duration, max_delay: Duration, max_attempts: u32) -> Self {
+        Self {
+            base_delay,
+            max_delay,
+            max_attempts: max_attempts.max(1),
+            sleep: Box::new(std::thread::sleep),
+        }
+    }
+
+    /// 替换等待函数，测试中用来记录等待时间而不真正睡眠
+    pub fn with_sleep(mut self, sleep: impl FnMut(Duration) + 'static) -> Self {
+        self.sleep = Box::new(sleep);
+        self
+    }
+
+    /// 第 attempt 次查询失败后的等待时间
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay.saturating_mul(1 << attempt.min(16)).min(self.max_delay)
+    }
+
+    /// 轮询直到账户出现
+    ///
+    /// # 参数
+    /// * `fetcher` - 账户来源
+    /// * `pubkey` - 要等待的账户地址
+    ///
+    /// # 返回
+    /// * `Result<Account, WaitError>` - 找到的账户，或超时/RPC错误
+    pub fn wait_for_account(
+        &mut self,
+        fetcher: &impl AccountFetcher,
+        pubkey: &Pubkey,
+    ) -> Result<Account, WaitError> {
+        let mut waited = Duration::ZERO;
+        for attempt in 0..self.max_attempts {
+            if let Some(account) = fetcher.fetch_account(pubkey).map_err(WaitError::Rpc)? {
+                return Ok(account);
+            }
+            if attempt + 1 < self.max_attempts {
+                let delay = self.delay(attempt);
+                debug!("⏳ 账户 {} 尚未出现，{:.1}秒后重试", pubkey, delay.as_secs_f64());
+                (self.sleep)(delay);
+                waited += delay;
+            }
+        }
+        Err(WaitError::Timeout { attempts: self.max_attempts, waited })
+    }
+}
+
+impl Default for AccountPoller {
+    /// 默认从0.5秒开始翻倍，单次最多等5秒，最多查询10次
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_secs(5), 10)
+    }
+}
+
+/// Anchor 程序收到无法识别的指令时返回的错误码范围
+/// （InstructionMissing=100, InstructionFallbackNotFound=101, InstructionDidNotDeserialize=102）
+const ANCHOR_INSTRUCTION_ERRORS: std::ops::RangeInclusive<u32> = 100..=102;
+
+/// 发送交易前检查目标程序时使用的接口，RpcClient实现它，测试中用模拟客户端代替
+pub trait ProgramProbe: AccountFetcher {
+    /// 模拟执行 ProtocolVersion 指令
+    ///
+    /// # 返回
+    /// * `ClientResult<Result<Vec<u8>, TransactionError>>` - 模拟成功时返回 return data，程序报错时返回交易错误
+    fn probe_protocol_version(&self, program_id: &Pubkey, payer: &Pubkey) -> ClientResult<Result<Vec<u8>, TransactionError>>;
+}
+
+impl ProgramProbe for RpcClient {
+    fn probe_protocol_version(&self, program_id: &Pubkey, payer: &Pubkey) -> ClientResult<Result<Vec<u8>, TransactionError>> {
+        let instruction = instruction::protocol_version(program_id);
+        let transaction = Transaction::new_unsigned(Message::new(&[instruction], Some(payer)));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self.simulate_transaction_with_config(&transaction, config)?.value;
+        if let Some(err) = result.err {
+            return Ok(Err(err));
+        }
+        let data = match result.return_data {
+            Some(return_data) => BASE64
+                .decode(&return_data.data.0)
+                .map_err(|e| ClientErrorKind::Custom(format!("无法解析 return data: {}", e)))?,
+            None => Vec::new(),
+        };
+        Ok(Ok(data))
+    }
+}
+
+/// 根据版本查询的结果判断程序类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramFlavor {
+    /// 本仓库的原生程序，支持版本查询
+    Native(ProtocolInfo),
+    /// 不支持版本查询的旧版原生程序
+    NativeLegacy,
+    /// Anchor 程序，指令编码不同
+    Anchor,
+    /// 无法识别的程序
+    Unknown(String),
+}
+
+impl ProgramFlavor {
+    /// 是否可以接收原生编码的指令
+    pub fn accepts_native_instructions(&self) -> bool {
+        matches!(self, ProgramFlavor::Native(_) | ProgramFlavor::NativeLegacy)
+    }
+}
+
+/// 解析版本查询结果
+///
+/// # 参数
+/// * `probe` - 模拟执行 ProtocolVersion 指令的结果
+///
+/// # 返回
+/// * `ProgramFlavor` - 程序类型
+pub fn detect_program_flavor(probe: &Result<Vec<u8>, TransactionError>) -> ProgramFlavor {
+    match probe {
+        Ok(data) => match ProtocolInfo::from_bytes(data) {
+            Ok(info) => ProgramFlavor::Native(info),
+            Err(_) => ProgramFlavor::Unknown(format!("无法识别的 return data: {:?}", data)),
+        },
+        Err(TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)) => ProgramFlavor::NativeLegacy,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(code))) if ANCHOR_INSTRUCTION_ERRORS.contains(code) => {
+            ProgramFlavor::Anchor
+        }
+        Err(err) => ProgramFlavor::Unknown(format!("{:?}", err)),
+    }
+}
+
+/// 目标程序检查失败的原因
+#[derive(Debug)]
+pub enum ProgramCheckError {
+    /// 程序账户不存在
+    NotFound(Pubkey),
+    /// 账户存在但不是可执行程序
+    NotExecutable(Pubkey),
+    /// 账户不归BPF加载器所有
+    WrongLoader { program_id: Pubkey, owner: Pubkey },
+    /// 程序类型与客户端的指令编码不匹配
+    FlavorMismatch { program_id: Pubkey, flavor: ProgramFlavor },
+    /// RPC请求失败
+    Rpc(ClientError),
+}
+
+impl std::fmt::Display for ProgramCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramCheckError::NotFound(program_id) => write!(f, "程序 {} 不存在，请检查程序ID和网络", program_id),
+            ProgramCheckError::NotExecutable(program_id) => write!(f, "账户 {} 不是可执行程序", program_id),
+            ProgramCheckError::WrongLoader { program_id, owner } => {
+                write!(f, "程序 {} 的所有者 {} 不是BPF加载器", program_id, owner)
+            }
+            ProgramCheckError::FlavorMismatch { program_id, flavor } => {
+                write!(f, "程序 {} 看起来是 {:?}，与原生指令编码不匹配，确认无误可加 --force 跳过检查", program_id, flavor)
+            }
+            ProgramCheckError::Rpc(err) => write!(f, "检查程序失败: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProgramCheckError {}
+
+/// 发送交易前的目标程序检查，避免把指令发给错误的程序白白浪费手续费
+/// 检查通过的程序在本次会话中缓存，不重复请求
+pub struct ProgramGuard {
+    verified: HashSet<Pubkey>,
+    force: bool,
+}
+
+impl ProgramGuard {
+    /// 创建检查器
+    ///
+    /// # 参数
+    /// * `force` - 为true时检查失败只打印警告，不阻止发送
+    pub fn new(force: bool) -> Self {
+        Self { verified: HashSet::new(), force }
+    }
+
+    /// 确认目标程序存在、可执行、由BPF加载器部署，并且能接收原生编码的指令
+    ///
+    /// # 参数
+    /// * `probe` - RPC客户端
+    /// * `program_id` - 目标程序ID
+    /// * `payer` - 模拟交易使用的付款账户
+    ///
+    /// # 返回
+    /// * `Result<(), ProgramCheckError>` - 检查失败的原因
+    pub fn ensure(&mut self, probe: &impl ProgramProbe, program_id: &Pubkey, payer: &Pubkey) -> Result<(), ProgramCheckError> {
+        if self.verified.contains(program_id) {
+            return Ok(());
+        }
+        match check_program(probe, program_id, payer) {
+            Ok(flavor) => {
+                debug!("🔍 目标程序检查通过: {} ({:?})", program_id, flavor);
+            }
+            Err(err @ ProgramCheckError::Rpc(_)) => return Err(err),
+            Err(err) if self.force => warn!("⚠️  {}，已使用 --force 继续", err),
+            Err(err) => return Err(err),
+        }
+        self.verified.insert(*program_id);
+        Ok(())
+    }
+}
+
+/// 依次检查程序账户和程序类型
+fn check_program(probe: &impl ProgramProbe, program_id: &Pubkey, payer: &Pubkey) -> Result<ProgramFlavor, ProgramCheckError> {
+    let account = probe
+        .fetch_account(program_id)
+        .map_err(ProgramCheckError::Rpc)?
+        .ok_or(ProgramCheckError::NotFound(*program_id))?;
+    if !account.executable {
+        return Err(ProgramCheckError::NotExecutable(*program_id));
+    }
+    let loaders = [bpf_loader::id(), bpf_loader_deprecated::id(), bpf_loader_upgradeable::id()];
+    if !loaders.contains(&account.owner) {
+        return Err(ProgramCheckError::WrongLoader { program_id: *program_id, owner: account.owner });
+    }
+
+    let probe_result = probe.probe_protocol_version(program_id, payer).map_err(ProgramCheckError::Rpc)?;
+    let flavor = detect_program_flavor(&probe_result);
+    if !flavor.accepts_native_instructions() {
+        return Err(ProgramCheckError::FlavorMismatch { program_id: *program_id, flavor });
+    }
+    Ok(flavor)
+}
+
+// ========================================
+// 链上错误解读 - 交易失败时把嵌套在 InstructionError 里的错误码翻译成说明和下一步建议
+// ========================================
+
+/// Anchor 程序自定义错误的起始编号，Anchor 版本的错误与 GongDeError 顺序一致
+const ANCHOR_ERROR_CODE_OFFSET: u32 = 6000;
+
+/// Anchor 的账户约束检查错误码范围（ConstraintMut 等）
+const ANCHOR_CONSTRAINT_ERRORS: std::ops::RangeInclusive<u32> = 2000..=2999;
+
+/// Anchor 的账户检查错误码范围（AccountDiscriminatorMismatch 等）
+const ANCHOR_ACCOUNT_ERRORS: std::ops::RangeInclusive<u32> = 3000..=3999;
+
+/// Anchor 的账户未初始化错误码（AccountNotInitialized）
+const ANCHOR_ACCOUNT_NOT_INITIALIZED: u32 = 3012;
+
+/// 功德账户还没有创建时的建议
+const CREATE_ACCOUNT_SUGGESTION: &str = "请先运行 cargo run --features client --example client 创建功德账户";
+
+/// 程序自定义错误码的编号方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCodeSpace {
+    /// 原生程序：错误码就是 GongDeError
+    Native,
+    /// Anchor 程序：框架错误在6000以下，GongDeError 从6000开始
+    Anchor,
+}
+
+impl From<&ProgramFlavor> for ErrorCodeSpace {
+    fn from(flavor: &ProgramFlavor) -> Self {
+        match flavor {
+            ProgramFlavor::Anchor => ErrorCodeSpace::Anchor,
+            _ => ErrorCodeSpace::Native,
+        }
+    }
+}
+
+/// 解读后的交易错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorExplanation {
+    /// 出错的指令在交易中的序号
+    pub instruction_index: u8,
+    /// 程序返回的自定义错误码，运行时内置的错误为None
+    pub code: Option<u32>,
+    /// 错误说明
+    pub message: String,
+    /// 建议的下一步操作
+    pub suggestion: Option<&'static str>,
+}
+
+impl std::fmt::Display for ErrorExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "指令 {} 执行失败: {}", self.instruction_index, self.message)?;
+        if let Some(code) = self.code {
+            write!(f, "（错误码 {}）", code)?;
+        }
+        Ok(())
+    }
+}
+
+/// 解读自定义错误码
+///
+/// # 返回
+/// * `(String, Option<&'static str>)` - 错误说明和建议，不认识的错误码只能显示原始数字
+fn explain_custom_code(code: u32, space: ErrorCodeSpace) -> (String, Option<&'static str>) {
+    let known = match space {
+        ErrorCodeSpace::Native => GongDeError::from_code(code),
+        ErrorCodeSpace::Anchor => match code {
+            code if ANCHOR_INSTRUCTION_ERRORS.contains(&code) => {
+                return ("程序无法识别指令编码".to_string(), Some("确认程序ID，Anchor 版本需要使用对应的客户端"));
+            }
+            ANCHOR_ACCOUNT_NOT_INITIALIZED => return ("账户还没有初始化".to_string(), Some(CREATE_ACCOUNT_SUGGESTION)),
+            code if ANCHOR_CONSTRAINT_ERRORS.contains(&code) => return ("Anchor 账户约束检查失败".to_string(), None),
+            code if ANCHOR_ACCOUNT_ERRORS.contains(&code) => return ("Anchor 账户检查失败".to_string(), None),
+            code => code.checked_sub(ANCHOR_ERROR_CODE_OFFSET).and_then(GongDeError::from_code),
+        },
+    };
+    match known {
+        Some(error) => (error.message().to_string(), error.suggestion()),
+        None => ("未知的程序错误".to_string(), None),
+    }
+}
+
+/// 解读交易错误
+///
+/// # 参数
+/// * `err` - 交易错误
+/// * `space` - 目标程序的错误码编号方式
+///
+/// # 返回
+/// * `Option<ErrorExplanation>` - 不是指令执行错误（例如手续费不足、blockhash过期）时返回None
+pub fn explain_transaction_error(err: &TransactionError, space: ErrorCodeSpace) -> Option<ErrorExplanation> {
+    let TransactionError::InstructionError(instruction_index, error) = err else {
+        return None;
+    };
+    let (code, message, suggestion) = match error {
+        InstructionError::Custom(code) => {
+            let (message, suggestion) = explain_custom_code(*code, space);
+            (Some(*code), message, suggestion)
+        }
+        InstructionError::UninitializedAccount => (None, "账户还没有初始化".to_string(), Some(CREATE_ACCOUNT_SUGGESTION)),
+        InstructionError::IllegalOwner => (None, "没有操作这个账户的权限".to_string(), Some("确认使用的是账户主人的钱包")),
+        InstructionError::InsufficientFunds => (None, "余额不足".to_string(), Some("请先给钱包充值 SOL")),
+        InstructionError::InvalidInstructionData => {
+            (None, "合约无法解析指令数据".to_string(), Some("确认程序ID指向本合约，并升级客户端"))
+        }
+        other => (None, other.to_string(), None),
+    };
+    Some(ErrorExplanation { instruction_index: *instruction_index, code, message, suggestion })
+}
+
+/// 从RPC错误中取出交易错误并解读，发送前的模拟失败和确认时的执行失败都能处理
+///
+/// # 参数
+/// * `err` - RPC客户端返回的错误
+/// * `space` - 目标程序的错误码编号方式
+///
+/// # 返回
+/// * `Option<ErrorExplanation>` - 错误中没有指令执行错误时返回None
+pub fn explain_client_error(err: &ClientError, space: ErrorCodeSpace) -> Option<ErrorExplanation> {
+    explain_transaction_error(&err.get_transaction_error()?, space)
+}
+
+/// 交易失败时输出解读后的错误和建议，原始错误仍然返回给调用方
+fn report_transaction_failure(operation_name: &str, err: &ClientError) {
+    let Some(explanation) = explain_client_error(err, ErrorCodeSpace::Native) else {
+        return;
+    };
+    error!("❌ {}失败: {}", operation_name, explanation);
+    if let Some(suggestion) = explanation.suggestion {
+        info!("💡 {}", suggestion);
+    }
+}
+
+// ========================================
+// 交易功德变化解析 - 动态信息流用
+// 交易元数据里没有账户数据，合约每次改写功德值都会发出 MeritChangeEvent，从交易日志中解析
+// ========================================
+
+/// 从交易日志中解析本程序发出的功德值变化，同一账户多次变化合并为一条（最早的修改前值、最后的修改后值）
+/// 只接受本程序自己输出的 "Program data:"：其他程序也能写同样格式的日志，按调用栈确定每条日志的归属
+///
+/// # 参数
+/// * `log_messages` - 交易日志
+/// * `program_id` - 本合约的程序ID
+///
+/// # 返回
+/// * `Vec<MeritChangeEvent>` - 按第一次修改的顺序排列的账户变化
+pub fn decode_merit_changes(log_messages: &[String], program_id: &Pubkey) -> Vec<MeritChangeEvent> {
+    let program = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut changes: Vec<MeritChangeEvent> = Vec::new();
+    for log in log_messages {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if stack.last() != Some(&program.as_str()) {
+                continue;
+            }
+            let fields: Result<Vec<Vec<u8>>, _> = data.split(' ').map(|field| BASE64.decode(field)).collect();
+            let Some(event) = fields.ok().and_then(|fields| MeritChangeEvent::from_log_data(&fields)) else {
+                continue;
+            };
+            match changes.iter_mut().find(|change| change.account == event.account) {
+                Some(change) => change.after = event.after,
+                None => changes.push(event),
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            // "Program <id> invoke [n]" 入栈，"Program <id> success" / "Program <id> failed: ..." 出栈
+            let mut parts = rest.split(' ');
+            match (parts.next(), parts.next()) {
+                (Some(id), Some("invoke")) => stack.push(id),
+                (Some(_), Some("success" | "failed:")) => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    changes
+}
+
+/// 从交易元数据中解析功德变化，失败的交易没有生效，返回空列表
+///
+/// # 参数
+/// * `meta` - 交易元数据
+/// * `program_id` - 本合约的程序ID
+pub fn merit_changes_from_meta(meta: &UiTransactionStatusMeta, program_id: &Pubkey) -> Vec<MeritChangeEvent> {
+    match &meta.log_messages {
+        OptionSerializer::Some(logs) if meta.err.is_none() => decode_merit_changes(logs, program_id),
+        _ => Vec::new(),
+    }
+}
+
+/// 查询一笔交易改动了哪些功德账户，以及改前改后的功德值
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `signature` - 交易签名
+/// * `program_id` - 本合约的程序ID
+///
+/// # 返回
+/// * `ClientResult<Vec<MeritChangeEvent>>` - 账户变化，交易没有元数据时返回空列表
+pub fn fetch_merit_changes(client: &RpcClient, signature: &Signature, program_id: &Pubkey) -> ClientResult<Vec<MeritChangeEvent>> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(client.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    let transaction = client.get_transaction_with_config(signature, config)?;
+    Ok(transaction
+        .transaction
+        .meta
+        .map(|meta| merit_changes_from_meta(&meta, program_id))
+        .unwrap_or_default())
+}
+
+/// 从交易元数据中取出 RestoreTo 返回的功德值
+/// 只接受本合约设置的 return data，失败的交易没有生效
+///
+/// # 参数
+/// * `meta` - RestoreTo 交易的元数据
+/// * `program_id` - 本合约的程序ID
+///
+/// # 返回
+/// * `ClientResult<u32>` - 合约写入账户的功德值
+///
+/// # 错误
+/// * 交易失败、没有 return data、return data 不是本合约设置的或长度不对
+pub fn restored_value_from_meta(meta: &UiTransactionStatusMeta, program_id: &Pubkey) -> ClientResult<u32> {
+    if let Some(err) = &meta.err {
+        return Err(ClientErrorKind::Custom(format!("回滚交易执行失败: {}", err)).into());
+    }
+    let OptionSerializer::Some(return_data) = &meta.return_data else {
+        return Err(ClientErrorKind::Custom("回滚交易没有 return data，合约可能是不返回回滚结果的旧版本".to_string()).into());
+    };
+    if return_data.program_id != program_id.to_string() {
+        return Err(ClientErrorKind::Custom(format!("return data 由程序 {} 设置，不是本合约", return_data.program_id)).into());
+    }
+    let data = BASE64
+        .decode(&return_data.data.0)
+        .map_err(|e| ClientErrorKind::Custom(format!("无法解析 return data: {}", e)))?;
+    parse_restore_return_data(&data)
+        .map_err(|e| ClientErrorKind::Custom(format!("回滚结果格式不对: {:?}", e)).into())
+}
+
+/// 确认 RestoreTo 交易真的把功德回滚到了预期的值，只看签名确认无法知道合约写入了什么
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `signature` - 已确认的 RestoreTo 交易签名
+/// * `program_id` - 本合约的程序ID
+/// * `expected` - 预期的功德值，即 RestoreTo 的恢复值
+///
+/// # 返回
+/// * `ClientResult<u32>` - 合约返回的功德值，与 `expected` 相同
+///
+/// # 错误
+/// * 查询交易失败、读不到回滚结果，或者结果与预期不一致
+pub fn verify_restore(client: &RpcClient, signature: &Signature, program_id: &Pubkey, expected: u32) -> ClientResult<u32> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(client.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    let transaction = client.get_transaction_with_config(signature, config)?;
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| ClientErrorKind::Custom(format!("交易 {} 没有元数据", signature)))?;
+    let restored = restored_value_from_meta(&meta, program_id)?;
+    if restored != expected {
+        return Err(ClientErrorKind::Custom(format!("回滚后的功德为 {}，预期 {}", restored, expected)).into());
+    }
+    Ok(restored)
+}
+
+// ========================================
+// 执行后状态校验 - 交易确认后重新读取功德，与本地按链上规则预测的值比较
+// 不一致说明客户端与合约版本不匹配，或者有其他人同时在修改同一个账户
+// ========================================
+
+/// 执行后的功德与预测不一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostStateDivergence {
+    /// 账户说明，例如“个人功德账户”
+    pub label: String,
+    pub pubkey: Pubkey,
+    /// 按链上规则预测的功德
+    pub expected: u32,
+    /// 实际读到的功德，账户不存在或无法解析时为None
+    pub actual: Option<u32>,
+    /// 刚确认的交易签名
+    pub signature: Signature,
+}
+
+impl std::fmt::Display for PostStateDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let actual = self.actual.map_or_else(|| "账户不存在".to_string(), |value| value.to_string());
+        write!(f, "{} {}: 预测 {}，实际 {}，交易 {}", self.label, self.pubkey, self.expected, actual, self.signature)
+    }
+}
+
+/// 交易确认后重新读取功德，与预测值比较，不一致时输出醒目的错误日志
+/// 只负责报告，不中断后续操作：交易已经上链，无法撤回
+///
+/// # 参数
+/// * `fetcher` - RPC客户端
+/// * `signature` - 刚确认的交易签名，写入不一致报告
+/// * `expected` - (账户地址, 账户说明, 预测的功德) 列表，预测值用 `predict_increment` 等与链上共用的函数计算
+///
+/// # 返回
+/// * `ClientResult<Vec<PostStateDivergence>>` - 与预测不一致的账户，全部一致时为空
+pub fn verify_post_state(
+    fetcher: &impl AccountFetcher,
+    signature: &Signature,
+    expected: &[(Pubkey, &str, u32)],
+) -> ClientResult<Vec<PostStateDivergence>> {
+    let mut divergences = Vec::new();
+    for (pubkey, label, expected) in expected {
+        let actual = fetcher
+            .fetch_account(pubkey)?
+            .and_then(|account| crate::utils::read_gongde_value(&account.data).ok());
+        if actual == Some(*expected) {
+            debug!("✅ {} 与预测一致: {}", label, expected);
+            continue;
+        }
+        let divergence = PostStateDivergence {
+            label: label.to_string(),
+            pubkey: *pubkey,
+            expected: *expected,
+            actual,
+            signature: *signature,
+        };
+        error!("❗❗❗ 执行后状态与预测不一致 ❗❗❗");
+        error!("   {}", divergence);
+        error!("💡 可能是客户端与合约版本不匹配，或有其他人同时在修改这个账户");
+        divergences.push(divergence);
+    }
+    Ok(divergences)
+}
+
+// ========================================
+// 计算单元自动调整 - 发送前先模拟一次，按实际消耗加上余量设置计算单元上限
+// 优先费按申请的上限计算，上限越贴近实际消耗越省钱；默认每条指令20万的上限对本合约来说大多是浪费
+// ========================================
+
+/// 单笔交易的计算单元上限（运行时限制）
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// 不设置上限时运行时给每条指令的默认计算单元
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u32 = 200_000;
+
+/// 默认的安全余量（百分比），账户状态变化（例如第一次调用时创建全局PDA）会让消耗略有波动
+pub const DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT: u32 = 20;
+
+/// 模拟交易消耗计算单元的接口，RpcClient实现它，测试中用模拟客户端代替
+pub trait ComputeUnitSimulator {
+    /// 模拟执行交易
+    ///
+    /// # 返回
+    /// * `ClientResult<Result<u64, TransactionError>>` - 模拟成功时返回消耗的计算单元，程序报错时返回交易错误
+    fn simulate_compute_units(&self, instructions: &[Instruction], payer: &Pubkey) -> ClientResult<Result<u64, TransactionError>>;
+}
+
+impl ComputeUnitSimulator for RpcClient {
+    fn simulate_compute_units(&self, instructions: &[Instruction], payer: &Pubkey) -> ClientResult<Result<u64, TransactionError>> {
+        let transaction = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self.simulate_transaction_with_config(&transaction, config)?.value;
+        if let Some(err) = result.err {
+            return Ok(Err(err));
+        }
+        let units = result
+            .units_consumed
+            .ok_or_else(|| ClientErrorKind::Custom("节点没有返回 units_consumed".to_string()))?;
+        Ok(Ok(units))
+    }
+}
+
+/// 按实际消耗加上余量计算计算单元上限，向上取整，不超过 MAX_COMPUTE_UNIT_LIMIT
+///
+/// # 参数
+/// * `units_consumed` - 模拟执行消耗的计算单元
+/// * `margin_percent` - 安全余量（百分比）
+pub fn tuned_compute_unit_limit(units_consumed: u64, margin_percent: u32) -> u32 {
+    let limit = (u128::from(units_consumed) * (100 + u128::from(margin_percent))).div_ceil(100);
+    u32::try_from(limit).unwrap_or(u32::MAX).min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// 模拟失败时使用的上限：与不设置上限时运行时的默认值相同
+///
+/// # 参数
+/// * `instruction_count` - 交易中的指令数量（不含计算预算指令）
+pub fn fallback_compute_unit_limit(instruction_count: usize) -> u32 {
+    u32::try_from(instruction_count)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNITS)
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// 计算单元调整器：每种指令组合只模拟一次，结果在本次会话中缓存
+pub struct ComputeUnitTuner {
+    margin_percent: u32,
+    /// 键为交易中每条指令的程序ID和指令码
+    cache: HashMap<Vec<(Pubkey, Option<u8>)>, u32>,
+}
+
+impl ComputeUnitTuner {
+    /// 创建调整器
+    ///
+    /// # 参数
+    /// * `margin_percent` - 在模拟消耗之上增加的安全余量（百分比）
+    pub fn new(margin_percent: u32) -> Self {
+        Self { margin_percent, cache: HashMap::new() }
+    }
+
+    /// 获取交易的计算单元上限，同一种指令组合只模拟一次；模拟失败时使用默认上限且不缓存
+    ///
+    /// # 参数
+    /// * `simulator` - RPC客户端
+    /// * `instructions` - 交易中的指令（不含计算预算指令）
+    /// * `payer` - 付款账户
+    pub fn limit_for(&mut self, simulator: &impl ComputeUnitSimulator, instructions: &[Instruction], payer: &Pubkey) -> u32 {
+        let kinds: Vec<(Pubkey, Option<u8>)> = instructions
+            .iter()
+            .map(|instruction| (instruction.program_id, split_client_version(&instruction.data).1.first().copied()))
+            .collect();
+        if let Some(limit) = self.cache.get(&kinds) {
+            return *limit;
+        }
+
+        // 模拟时申请最大上限，避免较重的交易在默认上限下模拟失败
+        let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)];
+        simulated.extend_from_slice(instructions);
+        let fallback = fallback_compute_unit_limit(instructions.len());
+        match simulator.simulate_compute_units(&simulated, payer) {
+            Ok(Ok(units)) => {
+                let limit = tuned_compute_unit_limit(units, self.margin_percent);
+                debug!("⚙️  模拟消耗 {} 计算单元，上限设为 {}", units, limit);
+                self.cache.insert(kinds, limit);
+                limit
+            }
+            Ok(Err(err)) => {
+                warn!("⚠️  模拟执行失败: {:?}，计算单元上限使用默认值 {}", err, fallback);
+                fallback
+            }
+            Err(err) => {
+                warn!("⚠️  模拟请求失败: {}，计算单元上限使用默认值 {}", err, fallback);
+                fallback
+            }
+        }
+    }
+
+    /// 在指令前加上按模拟结果设置的计算单元上限
+    ///
+    /// # 参数
+    /// * `simulator` - RPC客户端
+    /// * `instructions` - 交易中的指令（不含计算预算指令）
+    /// * `payer` - 付款账户
+    ///
+    /// # 返回
+    /// * `Vec<Instruction>` - 第一条为 set_compute_unit_limit，之后是原来的指令
+    pub fn with_compute_unit_limit(
+        &mut self,
+        simulator: &impl ComputeUnitSimulator,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> Vec<Instruction> {
+        let limit = self.limit_for(simulator, instructions, payer);
+        std::iter::once(ComputeBudgetInstruction::set_compute_unit_limit(limit))
+            .chain(instructions.iter().cloned())
+            .collect()
+    }
+}
+
+impl Default for ComputeUnitTuner {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPUTE_UNIT_MARGIN_PERCENT)
+    }
+}
+
+// ========================================
+// 多交易操作结果 - 批量操作逐笔记录结果，部分成功时也能说清哪些已上链
+// ========================================
+
+/// 部分成功时的退出码
+pub const EXIT_PARTIAL: i32 = 2;
+
+/// 单笔交易的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// 已确认上链
+    Confirmed,
+    /// 确定没有上链：未能发送、预检或执行失败
+    Failed,
+    /// 已发送但没等到确认（超时或网络错误），可能已经上链，需要查询后再决定是否重试
+    Unknown,
+}
+
+impl TransactionStatus {
+    /// 根据发送错误判断交易状态：带有交易错误的一定没有生效，其余情况无法确定
+    pub fn from_error(error: &ClientError) -> Self {
+        if error.get_transaction_error().is_some() {
+            TransactionStatus::Failed
+        } else {
+            TransactionStatus::Unknown
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Confirmed => "confirmed",
+            TransactionStatus::Failed => "failed",
+            TransactionStatus::Unknown => "unknown",
+        }
+    }
+}
+
+/// 单笔交易的记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionOutcome {
+    /// 交易签名，没有签名成功时为None
+    pub signature: Option<Signature>,
+    pub status: TransactionStatus,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 这笔交易处理的账户
+    pub accounts: Vec<Pubkey>,
+    /// 付款人在交易中额外支付的lamports（例如租金），不含交易费
+    pub lamports: u64,
+}
+
+/// 多交易操作的结果汇总，单笔失败不会中断整个操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationReport {
+    /// 操作名称，出现在摘要和JSON中
+    pub operation: String,
+    pub outcomes: Vec<TransactionOutcome>,
+}
+
+impl OperationReport {
+    /// 创建空的操作结果
+    pub fn new(operation: &str) -> Self {
+        Self { operation: operation.to_string(), outcomes: Vec::new() }
+    }
+
+    /// 记录一笔交易的结果
+    ///
+    /// # 参数
+    /// * `signature` - 交易签名，交易还没签名就失败时传None
+    /// * `accounts` - 这笔交易处理的账户
+    /// * `lamports` - 交易成功时付款人额外支付的lamports
+    /// * `result` - 发送结果
+    pub fn record(&mut self, signature: Option<Signature>, accounts: Vec<Pubkey>, lamports: u64, result: ClientResult<()>) {
+        let (status, error) = match result {
+            Ok(()) => (TransactionStatus::Confirmed, None),
+            // 没有签名说明交易没发出去
+            Err(e) if signature.is_none() => (TransactionStatus::Failed, Some(e.to_string())),
+            Err(e) => (TransactionStatus::from_error(&e), Some(e.to_string())),
+        };
+        self.outcomes.push(TransactionOutcome { signature, status, error, accounts, lamports });
+    }
+
+    /// 所有交易都已确认（没有交易时也算完成）
+    pub fn is_complete(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.status == TransactionStatus::Confirmed)
+    }
+
+    /// 已确认交易处理的账户
+    pub fn completed_items(&self) -> Vec<Pubkey> {
+        self.items_with(|status| status == TransactionStatus::Confirmed)
+    }
+
+    /// 未确认交易处理的账户，包括确定失败和状态未知的
+    pub fn failed_items(&self) -> Vec<Pubkey> {
+        self.items_with(|status| status != TransactionStatus::Confirmed)
+    }
+
+    fn items_with(&self, predicate: impl Fn(TransactionStatus) -> bool) -> Vec<Pubkey> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| predicate(outcome.status))
+            .flat_map(|outcome| outcome.accounts.iter().copied())
+            .collect()
+    }
+
+    /// 已确认交易中付款人额外支付的lamports合计
+    pub fn lamports_spent(&self) -> u64 {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.status == TransactionStatus::Confirmed)
+            .map(|outcome| outcome.lamports)
+            .sum()
+    }
+
+    /// 退出码：全部确认为0，全部未确认为1，部分确认为 EXIT_PARTIAL
+    pub fn exit_code(&self) -> i32 {
+        let confirmed = self.outcomes.iter().filter(|outcome| outcome.status == TransactionStatus::Confirmed).count();
+        if confirmed == self.outcomes.len() {
+            0
+        } else if confirmed == 0 {
+            1
+        } else {
+            EXIT_PARTIAL
+        }
+    }
+
+    /// 生成给人看的摘要
+    pub fn render(&self) -> String {
+        let count = |status: TransactionStatus| self.outcomes.iter().filter(|outcome| outcome.status == status).count();
+        let mut lines = vec![
+            format!("📊 === {}结果 ===", self.operation),
+            format!(
+                "交易 {} 笔: ✅ 已确认 {}，❌ 失败 {}，⏳ 状态未知 {}",
+                self.outcomes.len(),
+                count(TransactionStatus::Confirmed),
+                count(TransactionStatus::Failed),
+                count(TransactionStatus::Unknown)
+            ),
+            format!("账户: 完成 {} 个，未完成 {} 个", self.completed_items().len(), self.failed_items().len()),
+        ];
+        for outcome in self.outcomes.iter().filter(|outcome| outcome.status != TransactionStatus::Confirmed) {
+            let mark = if outcome.status == TransactionStatus::Failed { "❌" } else { "⏳" };
+            let signature = outcome.signature.map(|signature| signature.to_string()).unwrap_or_else(|| "未签名".to_string());
+            lines.push(format!("{} {} ({} 个账户): {}", mark, signature, outcome.accounts.len(), outcome.error.as_deref().unwrap_or("")));
+            for account in &outcome.accounts {
+                lines.push(format!("  - {}", account));
+            }
+        }
+        if count(TransactionStatus::Unknown) > 0 {
+            lines.push("⚠️  状态未知的交易可能已经上链，请先查询账户再决定是否重试".to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// 机器可读输出的结构，Schema 见 schemas/report.json
+    pub fn to_output(&self) -> ReportOutput {
+        let transactions = self
+            .outcomes
+            .iter()
+            .map(|outcome| TransactionOutput {
+                signature: outcome.signature.map(|signature| signature.to_string()),
+                status: outcome.status.as_str().to_string(),
+                error: outcome.error.clone(),
+                accounts: outcome.accounts.iter().map(Pubkey::to_string).collect(),
+                lamports: outcome.lamports,
+            })
+            .collect();
+        ReportOutput {
+            operation: self.operation.clone(),
+            complete: self.is_complete(),
+            exit_code: self.exit_code(),
+            lamports_spent: self.lamports_spent(),
+            transactions,
+        }
+    }
+
+    /// 生成机器可读的JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self.to_output()).expect("操作结果总是可以序列化")
+    }
+}
+
+/// 把操作结果写成JSON文件，供脚本判断哪些账户需要重试
+///
+/// # 参数
+/// * `report` - 操作结果
+/// * `path` - 输出文件路径
+pub fn write_report(report: &OperationReport, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(path, serde_json::to_string_pretty(&report.to_json())?)?;
+    info!("📝 结果已写入: {}", path);
+    Ok(())
+}
+
+// ========================================
+// 账户迁移 - 布局升级后批量把旧账户迁移到当前布局
+// ========================================
+
+/// 每笔交易最多打包的迁移指令数，每条指令只引用一个新账户，8条远小于交易大小上限
+pub const MAX_MIGRATIONS_PER_TRANSACTION: usize = 8;
+
+/// 待迁移的旧账户
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationCandidate {
+    pub pubkey: Pubkey,
+    /// 派生出账户地址的用户，迁移指令要传入它
+    pub owner: Pubkey,
+    /// 当前功德值
+    pub value: u32,
+    /// 当前余额（lamports）
+    pub lamports: u64,
+}
+
+impl MigrationCandidate {
+    /// 迁移时需要补足的租金
+    ///
+    /// # 参数
+    /// * `rent` - 当前布局的免租金最低余额
+    pub fn rent_deficit(&self, rent: u64) -> u64 {
+        rent.saturating_sub(self.lamports)
+    }
+}
+
+/// 扫描程序账户时使用的数据来源，RpcClient实现它，测试中用模拟客户端代替
+pub trait ProgramAccountSource {
+    /// 查询本程序下数据长度为 size 的全部账户
+    fn program_accounts_with_size(&self, program_id: &Pubkey, size: usize) -> ClientResult<Vec<(Pubkey, Account)>>;
+}
+
+impl ProgramAccountSource for RpcClient {
+    fn program_accounts_with_size(&self, program_id: &Pubkey, size: usize) -> ClientResult<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(size as u64)]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.get_program_accounts_with_config(program_id, config)
+    }
+}
+
+/// 按账户大小逐个扫描程序账户，每次查询都经过节流器
+/// getProgramAccounts 是公共节点上最容易触发限流的请求，遇到429时退避重试而不是放弃整个扫描
+///
+/// # 参数
+/// * `source` - 账户来源
+/// * `program_id` - 程序ID
+/// * `sizes` - 要扫描的账户大小
+/// * `throttle` - 请求节流器
+///
+/// # 返回
+/// * `ClientResult<Vec<(Pubkey, Account)>>` - 全部大小的账户，按 sizes 的顺序排列
+pub fn scan_program_accounts(
+    source: &impl ProgramAccountSource,
+    program_id: &Pubkey,
+    sizes: &[usize],
+    throttle: &mut Throttle,
+) -> ClientResult<Vec<(Pubkey, Account)>> {
+    let mut accounts = Vec::new();
+    for (index, size) in sizes.iter().enumerate() {
+        accounts.extend(throttle.run(|| source.program_accounts_with_size(program_id, *size))?);
+        print_progress(index + 1, sizes.len());
+    }
+    Ok(accounts)
+}
+
+/// 查询一批用户的功德账户中仍是旧布局（比当前布局小）的账户
+///
+/// 只按用户派生功德账户地址逐个查询，不扫描整个程序：
+/// 全局功德PDA、共享账本等其他账户即使大小相同也不会被当成功德账户
+///
+/// # 参数
+/// * `fetcher` - 账户来源
+/// * `program_id` - 程序ID
+/// * `owners` - 要检查的用户
+/// * `throttle` - 请求节流器
+///
+/// # 返回
+/// * `Result<Vec<MigrationCandidate>, Box<dyn std::error::Error>>` - 需要迁移的账户，
+///   不存在、余额为0的已关闭账户和已是当前布局的账户不计入
+pub fn find_legacy_accounts(
+    fetcher: &impl AccountFetcher,
+    program_id: &Pubkey,
+    owners: &[Pubkey],
+    throttle: &mut Throttle,
+) -> Result<Vec<MigrationCandidate>, Box<dyn std::error::Error>> {
+    let mut candidates = Vec::new();
+    for (index, owner) in owners.iter().enumerate() {
+        let pubkey = get_gongde_account_address(owner, program_id)?;
+        let account = throttle.run(|| fetcher.fetch_account(&pubkey))?;
+        print_progress(index + 1, owners.len());
+        let Some(account) = account.filter(|account| account.lamports > 0 && account.owner == *program_id) else {
+            continue;
+        };
+        if detect_layout_gap(&account.data).is_none() {
+            continue;
+        }
+        if let Ok(gongde) = GongDeAccount::unpack(&account.data) {
+            candidates.push(MigrationCandidate { pubkey, owner: *owner, value: gongde.value, lamports: account.lamports });
+        }
+    }
+    Ok(candidates)
+}
+
+/// 把迁移指令按交易分组
+///
+/// # 参数
+/// * `program_id` - 程序ID
+/// * `payer` - 付款人，支付交易费和租金差额
+/// * `candidates` - 待迁移的账户
+///
+/// # 返回
+/// * `Vec<Vec<Instruction>>` - 每组对应一笔交易，组内顺序与输入一致
+pub fn pack_migrations(program_id: &Pubkey, payer: &Pubkey, candidates: &[MigrationCandidate]) -> Vec<Vec<Instruction>> {
+    candidates
+        .chunks(MAX_MIGRATIONS_PER_TRANSACTION)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|candidate| instruction::migrate(program_id, &candidate.pubkey, &candidate.owner, payer))
+                .collect()
+        })
+        .collect()
+}
+
+/// 交易序列化后的大小，用于确认打包的指令不超过单笔交易上限
+pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    // 签名数量（compact-u16，少于128个时占1字节）+ 每个签名64字节 + 消息
+    1 + usize::from(message.header.num_required_signatures) * 64 + message.serialize().len()
+}
+
+/// 批量迁移账户，单笔交易失败不影响其余交易
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `payer` - 付款人
+/// * `program_id` - 程序ID
+/// * `candidates` - 待迁移的账户
+///
+/// # 返回
+/// * `ClientResult<OperationReport>` - 逐笔交易的迁移结果；只有查询租金失败时返回错误
+pub fn migrate_accounts(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    candidates: &[MigrationCandidate],
+) -> ClientResult<OperationReport> {
+    let rent = client.get_minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE)?;
+    let mut report = OperationReport::new("迁移");
+    let batches = pack_migrations(program_id, &payer.pubkey(), candidates);
+    for (batch, chunk) in batches.iter().zip(candidates.chunks(MAX_MIGRATIONS_PER_TRANSACTION)) {
+        debug_assert!(transaction_size(batch, &payer.pubkey()) <= PACKET_DATA_SIZE);
+        let accounts: Vec<Pubkey> = chunk.iter().map(|candidate| candidate.pubkey).collect();
+        let rent_funded = chunk.iter().map(|candidate| candidate.rent_deficit(rent)).sum::<u64>();
+        match client.get_latest_blockhash() {
+            Ok(blockhash) => {
+                let transaction = Transaction::new_signed_with_payer(batch, Some(&payer.pubkey()), &[payer], blockhash);
+                let sent = send_and_confirm_with_subscription(client, &transaction).map(|_| ());
+                match &sent {
+                    Ok(()) => info!("✅ 已迁移 {} 个账户: {}", chunk.len(), transaction.signatures[0]),
+                    Err(e) => warn!("❌ {} 个账户迁移失败: {}", chunk.len(), e),
+                }
+                report.record(Some(transaction.signatures[0]), accounts, rent_funded, sent);
+            }
+            Err(e) => {
+                warn!("❌ {} 个账户迁移失败: {}", chunk.len(), e);
+                report.record(None, accounts, rent_funded, Err(e));
+            }
+        }
+    }
+    Ok(report)
+}
+
+// ========================================
+// 自动迁移 - 读到旧布局的账户时，经用户同意先迁移，再继续原来的操作
+// ========================================
+
+/// 账户布局落后于程序当前版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutGap {
+    /// 账户的布局版本
+    pub account_version: u8,
+    /// 程序当前的布局版本
+    pub current_version: u8,
+}
+
+impl std::fmt::Display for LayoutGap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "账户布局 v{}，程序当前布局 v{}", self.account_version, self.current_version)
+    }
+}
+
+/// 检查账户布局是否落后于程序当前版本
+///
+/// # 参数
+/// * `data` - 账户数据
+///
+/// # 返回
+/// * `Option<LayoutGap>` - 旧布局账户返回版本差异；已是当前布局或不是功德账户时返回None
+pub fn detect_layout_gap(data: &[u8]) -> Option<LayoutGap> {
+    match layout_version(data.len()) {
+        Some(version) if version < GONGDE_LAYOUT_VERSION => Some(LayoutGap {
+            account_version: version,
+            current_version: GONGDE_LAYOUT_VERSION,
+        }),
+        _ => None,
+    }
+}
+
+/// 自动迁移时发送交易使用的接口，RpcClient实现它，测试中用模拟客户端代替
+pub trait MigrationSender: AccountFetcher {
+    /// 发送一笔迁移交易并等待确认
+    fn send_migration(&self, instruction: Instruction, payer: &Keypair) -> ClientResult<Signature>;
+}
+
+impl MigrationSender for RpcClient {
+    fn send_migration(&self, instruction: Instruction, payer: &Keypair) -> ClientResult<Signature> {
+        let blockhash = self.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+        send_and_confirm_with_subscription(self, &transaction).map(|(signature, _)| signature)
+    }
+}
+
+/// 询问用户是否迁移：带了 --migrate 直接同意；交互终端中输入 y 同意；
+/// 非交互环境（管道、CI）不会等待输入，视为不同意
+///
+/// # 参数
+/// * `gap` - 版本差异（用于提示）
+/// * `auto_migrate` - 是否带了 --migrate
+/// * `interactive` - 标准输入是否为终端
+/// * `input` - 读取回答的输入
+///
+/// # 返回
+/// * `bool` - 是否同意迁移
+pub fn ask_migration_consent(
+    gap: &LayoutGap,
+    auto_migrate: bool,
+    interactive: bool,
+    input: &mut impl std::io::BufRead,
+) -> bool {
+    if auto_migrate {
+        return true;
+    }
+    if !interactive {
+        return false;
+    }
+
+    eprint!("🔄 {}，是否现在迁移？迁移补足的租金由你支付 [y/N]: ", gap);
+    let _ = std::io::Write::flush(&mut std::io::stderr());
+    let mut line = String::new();
+    matches!(input.read_line(&mut line), Ok(read) if read > 0 && line.trim().eq_ignore_ascii_case("y"))
+}
+
+/// 执行操作前检查功德账户的布局版本，落后于当前版本且用户同意时先发送 Migrate 交易，
+/// 迁移确认后再执行原来的操作，操作读到的就是当前布局。
+/// 用户不同意时按旧布局继续（旧账户仍然可以增加功德，只是没有标志位）
+///
+/// # 参数
+/// * `client` - 链上客户端
+/// * `payer` - 支付迁移租金差额和交易费的账户
+/// * `program_id` - 程序ID
+/// * `owner` - 派生出功德账户地址的用户
+/// * `gongde_pubkey` - 功德账户地址
+/// * `consent` - 发现版本差异时询问是否迁移
+/// * `operation` - 原来的操作
+///
+/// # 返回
+/// * `Result<T, Box<dyn std::error::Error>>` - 操作的结果
+///
+/// # 错误
+/// * 查询账户或迁移交易失败时返回错误，不再执行操作
+pub fn with_auto_migration<C: MigrationSender + ?Sized, T>(
+    client: &C,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    consent: impl FnOnce(&LayoutGap) -> bool,
+    operation: impl FnOnce(&C) -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let gap = client
+        .fetch_account(gongde_pubkey)?
+        .filter(|account| account.lamports > 0 && account.owner == *program_id)
+        .and_then(|account| detect_layout_gap(&account.data));
+    if let Some(gap) = gap {
+        warn!("⚠️  功德账户 {} 需要迁移: {}", gongde_pubkey, gap);
+        if consent(&gap) {
+            let instruction = instruction::migrate(program_id, gongde_pubkey, owner, &payer.pubkey());
+            let signature = client.send_migration(instruction, payer)?;
+            info!("✅ 已迁移到布局 v{}: {}", gap.current_version, signature);
+        } else {
+            info!("ℹ️  未迁移，按旧布局继续");
+        }
+    }
+    operation(client)
+}
+
+// ========================================
+// 单账户迁移 - migrate 默认只检查一个账户：说明要改什么，迁移后确认功德值不变
+// ========================================
+
+/// 单个账户迁移前的说明
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationPreview {
+    /// 待迁移的账户
+    pub candidate: MigrationCandidate,
+    /// 账户布局版本与当前版本
+    pub gap: LayoutGap,
+    /// 迁移前的数据大小
+    pub current_size: usize,
+    /// 迁移后的数据大小
+    pub new_size: usize,
+    /// 需要补足的租金（lamports），由付款人支付
+    pub rent_deficit: u64,
+}
+
+impl MigrationPreview {
+    /// 打印迁移会改变什么
+    pub fn print(&self) {
+        info!("📝 功德账户: {}", self.candidate.pubkey);
+        info!("🔄 布局: v{} → v{}", self.gap.account_version, self.gap.current_version);
+        info!("💾 数据大小: {} → {} 字节", self.current_size, self.new_size);
+        info!("💰 需要补足租金: {} lamports ({})", self.rent_deficit, format_sol_balance(self.rent_deficit));
+        info!("🙏 功德值 {} 保持不变", self.candidate.value);
+    }
+}
+
+/// 检查单个功德账户，生成迁移说明
+///
+/// # 参数
+/// * `owner` - 派生出功德账户地址的用户
+/// * `pubkey` - 功德账户地址
+/// * `account` - 查询到的账户
+/// * `program_id` - 程序ID
+/// * `rent` - 当前布局的免租金最低余额
+///
+/// # 错误
+/// * 账户不存在、已关闭、不属于本程序、不是功德账户，或已经是当前布局时返回错误，不发送交易
+pub fn preview_migration(
+    owner: &Pubkey,
+    pubkey: &Pubkey,
+    account: Option<Account>,
+    program_id: &Pubkey,
+    rent: u64,
+) -> Result<MigrationPreview, String> {
+    let account = account
+        .filter(|account| account.lamports > 0)
+        .ok_or_else(|| format!("功德账户 {} 不存在或已关闭", pubkey))?;
+    if account.owner != *program_id {
+        return Err(format!("账户 {} 不属于本程序，所有者: {}", pubkey, account.owner));
+    }
+    let gongde = GongDeAccount::unpack(&account.data).map_err(|e| format!("账户 {} 不是功德账户: {:?}", pubkey, e))?;
+    let gap = detect_layout_gap(&account.data)
+        .ok_or_else(|| format!("功德账户 {} 已是当前布局 v{}，无需迁移", pubkey, GONGDE_LAYOUT_VERSION))?;
+    let candidate = MigrationCandidate { pubkey: *pubkey, owner: *owner, value: gongde.value, lamports: account.lamports };
+    Ok(MigrationPreview {
+        candidate,
+        gap,
+        current_size: account.data.len(),
+        new_size: GONGDE_ACCOUNT_SIZE,
+        rent_deficit: candidate.rent_deficit(rent),
+    })
+}
+
+/// 迁移单个账户，并重新读取账户确认已是当前布局且功德值不变
+///
+/// # 参数
+/// * `client` - 链上客户端
+/// * `payer` - 支付租金差额和交易费的账户
+/// * `program_id` - 程序ID
+/// * `preview` - 迁移说明
+///
+/// # 返回
+/// * `Result<Signature, Box<dyn std::error::Error>>` - 迁移交易的签名
+///
+/// # 错误
+/// * 迁移交易失败，或迁移后的账户与预期不符（功德值变化、仍是旧布局）
+pub fn migrate_single<C: MigrationSender + ?Sized>(
+    client: &C,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    preview: &MigrationPreview,
+) -> Result<Signature, Box<dyn std::error::Error>> {
+    let pubkey = preview.candidate.pubkey;
+    let instruction = instruction::migrate(program_id, &pubkey, &preview.candidate.owner, &payer.pubkey());
+    let signature = client.send_migration(instruction, payer)?;
+
+    let account = client.fetch_account(&pubkey)?.ok_or_else(|| format!("迁移后找不到账户 {}（交易 {}）", pubkey, signature))?;
+    let gongde = GongDeAccount::unpack(&account.data).map_err(|e| format!("迁移后的账户无法解析: {:?}（交易 {}）", e, signature))?;
+    if let Some(gap) = detect_layout_gap(&account.data) {
+        return Err(format!("迁移后仍是旧布局: {}（交易 {}）", gap, signature).into());
+    }
+    if gongde.value != preview.candidate.value {
+        return Err(format!(
+            "迁移后功德值不一致: 迁移前 {}，迁移后 {}（交易 {}），可能有其他人同时修改了这个账户",
+            preview.candidate.value, gongde.value, signature
+        )
+        .into());
+    }
+    info!("✅ 已迁移到布局 v{}，功德值 {} 不变: {}", preview.gap.current_version, gongde.value, signature);
+    Ok(signature)
+}
+
+// ========================================
+// 批量开通 - 管理员代付租金，为一批用户创建功德账户
+// ========================================
+
+/// 构建代为开通一批用户的指令，每个用户依次是创建账户和初始化两条指令
+///
+/// 功德账户是以用户为base的种子地址，创建和初始化都需要用户签名；
+/// 付款人（sponsor）只负责支付租金和交易费
+///
+/// # 参数
+/// * `program_id` - 程序ID
+/// * `sponsor` - 付款人
+/// * `users` - 要开通的用户
+/// * `rent` - 每个账户的免租金最低余额
+/// * `is_public` - 是否开通为公开功德箱
+///
+/// # 返回
+/// * `Result<Vec<Instruction>, Box<dyn std::error::Error>>` - 按用户顺序排列的指令
+pub fn onboard_instructions(
+    program_id: &Pubkey,
+    sponsor: &Pubkey,
+    users: &[Pubkey],
+    rent: u64,
+    is_public: bool,
+) -> Result<Vec<Instruction>, Box<dyn std::error::Error>> {
+    let mut instructions = Vec::with_capacity(users.len() * 2);
+    for user in users {
+        let gongde_pubkey = get_gongde_account_address(user, program_id)?;
+        instructions.push(system_instruction::create_account_with_seed(
+            sponsor,
+            &gongde_pubkey,
+            user,
+            GONGDE_ACCOUNT_SEED,
+            rent,
+            GONGDE_ACCOUNT_SIZE as u64,
+            program_id,
+        ));
+        instructions.push(instruction::initialize(program_id, &gongde_pubkey, user, is_public));
+    }
+    Ok(instructions)
+}
+
+/// 把待开通的用户按交易大小分组，每笔交易尽量多放用户
+///
+/// 每个用户都要签名，一笔交易能放下的用户数由签名和账户数决定，这里直接按序列化大小计算
+///
+/// # 参数
+/// * `program_id` - 程序ID
+/// * `sponsor` - 付款人
+/// * `users` - 要开通的用户
+/// * `rent` - 每个账户的免租金最低余额
+///
+/// # 返回
+/// * `Result<Vec<Vec<Pubkey>>, Box<dyn std::error::Error>>` - 每组对应一笔交易，组内顺序与输入一致
+pub fn pack_onboardings(
+    program_id: &Pubkey,
+    sponsor: &Pubkey,
+    users: &[Pubkey],
+    rent: u64,
+) -> Result<Vec<Vec<Pubkey>>, Box<dyn std::error::Error>> {
+    let mut batches: Vec<Vec<Pubkey>> = Vec::new();
+    let mut current: Vec<Pubkey> = Vec::new();
+    for user in users {
+        current.push(*user);
+        // 公开与否只影响一个字节的指令数据，不影响分组
+        let instructions = onboard_instructions(program_id, sponsor, &current, rent, true)?;
+        if current.len() > 1 && transaction_size(&instructions, sponsor) > PACKET_DATA_SIZE {
+            current.pop();
+            batches.push(std::mem::replace(&mut current, vec![*user]));
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    Ok(batches)
+}
+
+/// 批量开通功德账户，单笔交易失败不影响其余交易
+///
+/// 调用前应先排除已有账户的用户；重新运行时已开通的用户会被查询结果排除，可以从中断处继续
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `sponsor` - 付款人，支付租金和交易费
+/// * `program_id` - 程序ID
+/// * `users` - 要开通的用户，需要他们的密钥签名
+/// * `is_public` - 是否开通为公开功德箱
+///
+/// # 返回
+/// * `Result<OperationReport, Box<dyn std::error::Error>>` - 逐笔交易的开通结果，账户列表为用户公钥；
+///   只有查询租金或生成地址失败时返回错误
+pub fn onboard_users(
+    client: &RpcClient,
+    sponsor: &Keypair,
+    program_id: &Pubkey,
+    users: &[Keypair],
+    is_public: bool,
+) -> Result<OperationReport, Box<dyn std::error::Error>> {
+    let rent = client.get_minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE)?;
+    let pubkeys: Vec<Pubkey> = users.iter().map(Signer::pubkey).collect();
+    let mut report = OperationReport::new("开通");
+    let mut offset = 0;
+    for batch in pack_onboardings(program_id, &sponsor.pubkey(), &pubkeys, rent)? {
+        let signers: Vec<&Keypair> = std::iter::once(sponsor).chain(&users[offset..offset + batch.len()]).collect();
+        offset += batch.len();
+        let instructions = onboard_instructions(program_id, &sponsor.pubkey(), &batch, rent, is_public)?;
+        let rent_paid = rent * batch.len() as u64;
+        match client.get_latest_blockhash() {
+            Ok(blockhash) => {
+                let transaction = Transaction::new_signed_with_payer(&instructions, Some(&sponsor.pubkey()), &signers, blockhash);
+                let sent = send_and_confirm_with_subscription(client, &transaction).map(|_| ());
+                match &sent {
+                    Ok(()) => info!("✅ 已开通 {} 个账户: {}", batch.len(), transaction.signatures[0]),
+                    Err(e) => warn!("❌ {} 个账户开通失败: {}", batch.len(), e),
+                }
+                report.record(Some(transaction.signatures[0]), batch, rent_paid, sent);
+            }
+            Err(e) => {
+                warn!("❌ {} 个账户开通失败: {}", batch.len(), e);
+                report.record(None, batch, rent_paid, Err(e));
+            }
+        }
+    }
+    Ok(report)
+}
+
+// ========================================
+// 批量背书 - 活动组织者一次给到场的所有人背书，按交易大小分组发送 EndorseMany 指令
+// ========================================
+
+/// 把批量背书的目标按交易分组，每组不超过 MAX_ENDORSE_MANY_TARGETS 个并且放得进一笔交易
+///
+/// # 参数
+/// * `program_id` - 程序ID
+/// * `endorser` - 背书人，也是付款人
+/// * `endorser_gongde` - 背书人自己的功德账户地址
+/// * `endorsee_gongdes` - 被背书的功德账户地址
+///
+/// # 返回
+/// * `Vec<Vec<Pubkey>>` - 每组对应一笔交易，组内顺序与输入一致
+pub fn pack_endorsements(
+    program_id: &Pubkey,
+    endorser: &Pubkey,
+    endorser_gongde: &Pubkey,
+    endorsee_gongdes: &[Pubkey],
+) -> Vec<Vec<Pubkey>> {
+    let mut batches: Vec<Vec<Pubkey>> = Vec::new();
+    let mut current: Vec<Pubkey> = Vec::new();
+    for endorsee_gongde in endorsee_gongdes {
+        current.push(*endorsee_gongde);
+        let overfull = current.len() > MAX_ENDORSE_MANY_TARGETS || {
+            let instruction = instruction::endorse_many(program_id, endorser, endorser_gongde, &current);
+            transaction_size(&[instruction], endorser) > PACKET_DATA_SIZE
+        };
+        if current.len() > 1 && overfull {
+            current.pop();
+            batches.push(std::mem::replace(&mut current, vec![*endorsee_gongde]));
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// 批量查询背书人还没有背过书的目标
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `program_id` - 程序ID
+/// * `endorser` - 背书人
+/// * `endorsee_gongdes` - 被背书的功德账户地址
+/// * `throttle` - 请求节流器
+///
+/// # 返回
+/// * `ClientResult<Vec<Pubkey>>` - 背书记录还不存在的功德账户，顺序与输入一致
+pub fn pending_endorsements(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    endorser: &Pubkey,
+    endorsee_gongdes: &[Pubkey],
+    throttle: &mut Throttle,
+) -> ClientResult<Vec<Pubkey>> {
+    let mut pending = Vec::new();
+    for chunk in endorsee_gongdes.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let records: Vec<Pubkey> = chunk
+            .iter()
+            .map(|endorsee_gongde| derive_endorsement_address(endorser, endorsee_gongde, program_id).0)
+            .collect();
+        let accounts = throttle.run(|| client.get_multiple_accounts(&records))?;
+        pending.extend(chunk.iter().zip(accounts).filter(|(_, account)| account.is_none()).map(|(pubkey, _)| *pubkey));
+    }
+    Ok(pending)
+}
+
+/// 批量背书，单笔交易失败不影响其余交易
+///
+/// 调用前应先用 pending_endorsements 排除已经背过书的目标；发送期间别人的交易抢先背书时，
+/// 合约跳过这个目标而不是让整笔交易失败
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `endorser` - 背书人，支付背书记录的租金和交易费
+/// * `program_id` - 程序ID
+/// * `endorser_gongde` - 背书人自己的功德账户地址
+/// * `endorsee_gongdes` - 被背书的功德账户地址
+///
+/// # 返回
+/// * `ClientResult<OperationReport>` - 逐笔交易的背书结果，账户列表为被背书的功德账户；只有查询租金失败时返回错误
+pub fn endorse_users(
+    client: &RpcClient,
+    endorser: &Keypair,
+    program_id: &Pubkey,
+    endorser_gongde: &Pubkey,
+    endorsee_gongdes: &[Pubkey],
+) -> ClientResult<OperationReport> {
+    let rent = client.get_minimum_balance_for_rent_exemption(ENDORSEMENT_SIZE)?;
+    let mut report = OperationReport::new("背书");
+    for batch in pack_endorsements(program_id, &endorser.pubkey(), endorser_gongde, endorsee_gongdes) {
+        let endorse_many = instruction::endorse_many(program_id, &endorser.pubkey(), endorser_gongde, &batch);
+        let rent_paid = rent * batch.len() as u64;
+        match client.get_latest_blockhash() {
+            Ok(blockhash) => {
+                let transaction = Transaction::new_signed_with_payer(&[endorse_many], Some(&endorser.pubkey()), &[endorser], blockhash);
+                let sent = send_and_confirm_with_subscription(client, &transaction).map(|_| ());
+                match &sent {
+                    Ok(()) => info!("✅ 已背书 {} 个账户: {}", batch.len(), transaction.signatures[0]),
+                    Err(e) => warn!("❌ {} 个账户背书失败: {}", batch.len(), e),
+                }
+                report.record(Some(transaction.signatures[0]), batch, rent_paid, sent);
+            }
+            Err(e) => {
+                warn!("❌ {} 个账户背书失败: {}", batch.len(), e);
+                report.record(None, batch, rent_paid, Err(e));
+            }
+        }
+    }
+    Ok(report)
+}
+
+// ========================================
+// 集群识别 - 破坏性操作前确认当前连接的是哪个集群，避免CLI配置还指向主网时误操作
+// ========================================
+
+/// 主网创世区块哈希
+const MAINNET_BETA_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+/// 测试网创世区块哈希
+const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
+
+/// 开发网创世区块哈希
+const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+
+/// 根据创世区块哈希识别的集群
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cluster {
+    MainnetBeta,
+    Testnet,
+    Devnet,
+    /// 本地测试节点或其他自定义集群，每次启动的创世哈希都不同
+    Unknown(Hash),
+}
+
+impl Cluster {
+    /// 根据创世区块哈希识别集群
+    ///
+    /// # 参数
+    /// * `genesis_hash` - `get_genesis_hash` 返回的哈希
+    ///
+    /// # 返回
+    /// * `Cluster` - 识别结果，不认识的哈希为 `Unknown`
+    pub fn from_genesis_hash(genesis_hash: &Hash) -> Self {
+        match genesis_hash.to_string().as_str() {
+            MAINNET_BETA_GENESIS_HASH => Cluster::MainnetBeta,
+            TESTNET_GENESIS_HASH => Cluster::Testnet,
+            DEVNET_GENESIS_HASH => Cluster::Devnet,
+            _ => Cluster::Unknown(*genesis_hash),
+        }
+    }
+
+    /// 集群名称，与 solana CLI 的 --url 简写一致，也是主网确认时要输入的内容
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Testnet => "testnet",
+            Cluster::Devnet => "devnet",
+            Cluster::Unknown(_) => "unknown",
+        }
+    }
+
+    /// 是否为主网
+    pub fn is_mainnet(&self) -> bool {
+        matches!(self, Cluster::MainnetBeta)
+    }
+}
+
+impl std::fmt::Display for Cluster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cluster::Unknown(hash) => write!(f, "本地或自定义集群 (创世哈希 {})", hash),
+            cluster => write!(f, "{}", cluster.name()),
+        }
+    }
+}
+
+/// 主网上的破坏性操作被拒绝
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClusterGuardError {
+    /// 不是交互终端，又没有 --allow-mainnet
+    NonInteractive,
+    /// 输入的集群名称不对（或输入被关闭）
+    NotConfirmed,
+}
+
+impl std::fmt::Display for ClusterGuardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClusterGuardError::NonInteractive => {
+                write!(f, "当前连接的是主网，非交互环境下必须加 --allow-mainnet 才能继续")
+            }
+            ClusterGuardError::NotConfirmed => write!(f, "未确认主网操作，已取消"),
+        }
+    }
+}
+
+impl std::error::Error for ClusterGuardError {}
+
+/// 破坏性操作前确认集群：非主网直接放行；主网需要 --allow-mainnet，
+/// 或者在交互终端中输入集群名称确认。非交互环境（管道、CI）不会等待输入，直接拒绝
+///
+/// # 参数
+/// * `cluster` - 当前集群
+/// * `allow_mainnet` - 是否带了 --allow-mainnet
+/// * `interactive` - 标准输入是否为终端
+/// * `input` - 读取确认内容的输入
+///
+/// # 返回
+/// * `Result<(), ClusterGuardError>` - 允许继续时返回Ok
+pub fn confirm_destructive_operation(
+    cluster: &Cluster,
+    allow_mainnet: bool,
+    interactive: bool,
+    input: &mut impl std::io::BufRead,
+) -> Result<(), ClusterGuardError> {
+    if !cluster.is_mainnet() || allow_mainnet {
+        return Ok(());
+    }
+    if !interactive {
+        return Err(ClusterGuardError::NonInteractive);
+    }
+
+    eprint!("⚠️  请输入集群名称 {} 确认继续: ", cluster.name());
+    let _ = std::io::Write::flush(&mut std::io::stderr());
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(read) if read > 0 && line.trim() == cluster.name() => Ok(()),
+        _ => Err(ClusterGuardError::NotConfirmed),
+    }
+}
+
+/// 查询当前集群并醒目地显示，主网上要求额外确认
+///
+/// # 参数
+/// * `client` - RPC客户端
+/// * `operation` - 要执行的破坏性操作（用于提示）
+/// * `allow_mainnet` - 是否带了 --allow-mainnet
+///
+/// # 返回
+/// * `Result<Cluster, Box<dyn std::error::Error>>` - 当前集群
+pub fn guard_destructive_operation(
+    client: &RpcClient,
+    operation: &str,
+    allow_mainnet: bool,
+) -> Result<Cluster, Box<dyn std::error::Error>> {
+    use std::io::IsTerminal;
+
+    let cluster = Cluster::from_genesis_hash(&client.get_genesis_hash()?);
+    if cluster.is_mainnet() {
+        warn!("\n🚨🚨🚨 当前集群: {} ({}) 🚨🚨🚨", cluster, client.url());
+        warn!("🚨 即将在主网上执行: {}", operation);
+    } else {
+        info!("\n🌐 当前集群: {} ({})", cluster, client.url());
+    }
+
+    let stdin = std::io::stdin();
+    confirm_destructive_operation(&cluster, allow_mainnet, stdin.is_terminal(), &mut stdin.lock())?;
+    Ok(cluster)
+}
+
+// ========================================
+// 功德排行榜 - 扫描本程序下全部布局的功德账户，按当前功德排名
+// ========================================
+
+/// 排行榜中的一个功德账户
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    pub pubkey: Pubkey,
+    /// 当前功德
+    pub value: u32,
+    /// 累计功德
+    pub lifetime_total: u64,
+    /// 是否已归档
+    pub archived: bool,
+    /// 头像emoji，显示在地址旁边
+    pub emoji: Option<char>,
+}
+
+/// 把扫描到的账户整理成排行榜，按当前功德从高到低排列，功德相同时按地址排列保证输出稳定
+/// 跳过已关闭、未初始化的账户和全局功德PDA（它和旧的4字节账户大小相同）
+///
+/// # 参数
+/// * `accounts` - 扫描到的账户
+/// * `global_pubkey` - 全局功德PDA地址
+/// * `include_archived` - 是否包含已归档的账户
+///
+/// # 返回
+/// * `Vec<LeaderboardEntry>` - 排好序的账户
+pub fn rank_accounts(accounts: Vec<(Pubkey, Account)>, global_pubkey: &Pubkey, include_archived: bool) -> Vec<LeaderboardEntry> {
+    let mut entries: Vec<LeaderboardEntry> = accounts
+        .into_iter()
+        .filter(|(pubkey, account)| pubkey != global_pubkey && account.lamports > 0 && is_usable_account(&account.data))
+        .filter_map(|(pubkey, account)| {
+            let gongde = GongDeAccount::unpack(&account.data).ok()?;
+            Some(LeaderboardEntry {
+                pubkey,
+                value: gongde.value,
+                lifetime_total: gongde.lifetime_total,
+                archived: gongde.is_archived(),
+                emoji: read_emoji(&account.data),
+            })
+        })
+        .filter(|entry| include_archived || !entry.archived)
+        .collect();
+    entries.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.pubkey.cmp(&b.pubkey)));
+    entries
+}
+
+/// 扫描本程序下全部布局版本的功德账户并排名
+///
+/// # 参数
+/// * `source` - 账户来源
+/// * `program_id` - 程序ID
+/// * `global_pubkey` - 全局功德PDA地址，不参与排名
+/// * `include_archived` - 是否包含已归档的账户
+/// * `throttle` - 请求节流器
+///
+/// # 返回
+/// * `ClientResult<Vec<LeaderboardEntry>>` - 排好序的账户
+pub fn fetch_leaderboard(
+    source: &impl ProgramAccountSource,
+    program_id: &Pubkey,
+    global_pubkey: &Pubkey,
+    include_archived: bool,
+    throttle: &mut Throttle,
+) -> ClientResult<Vec<LeaderboardEntry>> {
+    let sizes: Vec<usize> = (1..=GONGDE_LAYOUT_VERSION).filter_map(gongde_account_size).collect();
+    let accounts = scan_program_accounts(source, program_id, &sizes, throttle)?;
+    Ok(rank_accounts(accounts, global_pubkey, include_archived))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        derive_global_gongde_pda_address,
+        enumerate_user_pdas,
+        predict_increment,
+        touch_last_active,
+        write_archived_flag,
+        write_emoji,
+        write_gongde_value,
+        write_initialized_flags,
+        write_lifetime_total,
+        EnumerateOptions,
+        GONGDE_ACCOUNT_SIZE,
+        GONGDE_VALUE_SIZE,
+        TRANSFER_STATE_SEED,
+    };
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_bump_cache_matches_find_program_address() {
+        let program_id = Pubkey::new_unique();
+        let mut cache = BumpCache::new();
+        for _ in 0..20 {
+            let sender = Pubkey::new_unique();
+            let seeds: [&[u8]; 2] = [TRANSFER_STATE_SEED, sender.as_ref()];
+            let expected = derive_transfer_state_address(&sender, &program_id);
+            // 第一次走 find_program_address，第二次走缓存的 bump，结果都一样
+            assert_eq!(cache.derive(&seeds, &program_id), expected);
+            assert_eq!(cache.derive(&seeds, &program_id), expected);
+            assert_eq!(create_with_bump(&seeds, expected.1, &program_id), Some(expected.0));
+        }
+
+        // 从地址清单得到的 bump 校验后记录
+        let user = Pubkey::new_unique();
+        let listed = enumerate_user_pdas(&user, &program_id, &EnumerateOptions::default());
+        let transfer_state = listed.iter().find(|pda| pda.name == "transfer_state").unwrap();
+        let seeds: [&[u8]; 2] = [TRANSFER_STATE_SEED, user.as_ref()];
+        let mut cache = BumpCache::new();
+        assert_eq!(cache.insert_verified(&seeds, transfer_state.bump.unwrap(), &program_id, &transfer_state.address), Ok(()));
+        assert_eq!(cache.derive(&seeds, &program_id), (transfer_state.address, transfer_state.bump.unwrap()));
+
+        // bump 与账户地址对不上时不记录
+        let mut cache = BumpCache::new();
+        let wrong_bump = transfer_state.bump.unwrap().wrapping_sub(1);
+        assert!(cache.insert_verified(&seeds, wrong_bump, &program_id, &transfer_state.address).is_err());
+        assert!(cache.bumps.is_empty());
+    }
+
+    fn rate_limited(message: &str) -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: 429,
+            message: message.to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        })
+        .into()
+    }
+
+    fn snapshot(lamports: u64, value: Option<u32>) -> AccountSnapshot {
+        AccountSnapshot { lamports, gongde: value.map(|value| GongDeAccount { value, flags: 0, lifetime_total: u64::from(value) }) }
+    }
+
+    #[test]
+    fn test_diff_account_updated() {
+        let change = diff_account(Some(&snapshot(1_000, Some(3))), Some(&snapshot(1_000, Some(4))));
+        assert_eq!(change, AccountChange::Updated { value: Some((3, 4)), lamport_delta: 0 });
+        assert_eq!(change.to_string(), "功德 3 → 4");
+
+        // 全局PDA账户收到手续费
+        let change = diff_account(Some(&snapshot(900_000, Some(10))), Some(&snapshot(905_000, Some(11))));
+        assert_eq!(change.to_string(), "功德 10 → 11，余额 +5000 lamports");
+
+        // 付款人不是功德账户，只有余额变化
+        let change = diff_account(Some(&snapshot(2_000_000, None)), Some(&snapshot(1_990_000, None)));
+        assert_eq!(change, AccountChange::Updated { value: None, lamport_delta: -10_000 });
+    }
+
+    #[test]
+    fn test_diff_account_created_and_closed() {
+        let created = diff_account(None, Some(&snapshot(946_560, Some(0))));
+        assert_eq!(created, AccountChange::Created { lamports: 946_560, value: Some(0) });
+        assert_eq!(created.to_string(), "新建账户，功德 0，余额 946560 lamports");
+
+        let closed = diff_account(Some(&snapshot(946_560, Some(7))), None);
+        assert_eq!(closed, AccountChange::Closed { lamports: 946_560, value: Some(7) });
+        assert_eq!(closed.to_string(), "账户已关闭，关闭前功德 7，转出 946560 lamports");
+
+        assert_eq!(diff_account(None, None), AccountChange::Updated { value: None, lamport_delta: 0 });
+    }
+
+    #[test]
+    fn test_snapshot_from_account() {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_gongde_value(&mut data, 42).unwrap();
+        crate::utils::write_lifetime_total(&mut data, 42).unwrap();
+        let account = Account { lamports: 100, data, ..Account::default() };
+        assert_eq!(AccountSnapshot::from_account(Some(&account)), Some(snapshot(100, Some(42))));
+
+        // 关闭后余额为0的账户视为不存在
+        let closed = Account { lamports: 0, ..account };
+        assert_eq!(AccountSnapshot::from_account(Some(&closed)), None);
+        assert_eq!(AccountSnapshot::from_account(None), None);
+
+        // 没有数据的系统账户不解码功德值
+        let wallet = Account { lamports: 5, ..Account::default() };
+        assert_eq!(AccountSnapshot::from_account(Some(&wallet)), Some(snapshot(5, None)));
+    }
+
+    fn legacy_candidates(count: usize) -> Vec<MigrationCandidate> {
+        (0..count)
+            .map(|index| MigrationCandidate {
+                pubkey: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                value: index as u32,
+                lamports: 918_720,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pack_migrations_respects_limits() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let candidates = legacy_candidates(MAX_MIGRATIONS_PER_TRANSACTION * 2 + 3);
+
+        let batches = pack_migrations(&program_id, &payer, &candidates);
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![MAX_MIGRATIONS_PER_TRANSACTION, MAX_MIGRATIONS_PER_TRANSACTION, 3]
+        );
+        // 顺序与输入一致，且每笔交易都不超过大小上限
+        assert_eq!(batches[2][0].accounts[0].pubkey, candidates[MAX_MIGRATIONS_PER_TRANSACTION * 2].pubkey);
+        for batch in &batches {
+            assert!(transaction_size(batch, &payer) <= PACKET_DATA_SIZE);
+        }
+        assert!(pack_migrations(&program_id, &payer, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_pack_onboardings_fits_transactions() {
+        let program_id = Pubkey::new_unique();
+        let sponsor = Pubkey::new_unique();
+        let users: Vec<Pubkey> = (0..12).map(|_| Pubkey::new_unique()).collect();
+
+        let batches = pack_onboardings(&program_id, &sponsor, &users, 946_560).unwrap();
+        assert!(batches.len() > 1, "12个用户的签名放不进一笔交易");
+        // 分组后顺序不变、不遗漏，每笔交易都不超过大小上限
+        assert_eq!(batches.concat(), users);
+        for batch in &batches {
+            let instructions = onboard_instructions(&program_id, &sponsor, batch, 946_560, true).unwrap();
+            assert!(transaction_size(&instructions, &sponsor) <= PACKET_DATA_SIZE);
+            // 付款人加上每个用户各一个签名
+            assert_eq!(Message::new(&instructions, Some(&sponsor)).header.num_required_signatures as usize, batch.len() + 1);
+        }
+        // 除最后一组外，每组再多放一个用户就会超出上限
+        let first = &batches[0];
+        let mut overfull = first.clone();
+        overfull.push(batches[1][0]);
+        let instructions = onboard_instructions(&program_id, &sponsor, &overfull, 946_560, true).unwrap();
+        assert!(transaction_size(&instructions, &sponsor) > PACKET_DATA_SIZE);
+
+        assert!(pack_onboardings(&program_id, &sponsor, &[], 946_560).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pack_endorsements_respects_target_limit() {
+        let program_id = Pubkey::new_unique();
+        let (endorser, endorser_gongde) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let endorsees: Vec<Pubkey> = (0..MAX_ENDORSE_MANY_TARGETS * 2 + 3).map(|_| Pubkey::new_unique()).collect();
+
+        let batches = pack_endorsements(&program_id, &endorser, &endorser_gongde, &endorsees);
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![MAX_ENDORSE_MANY_TARGETS, MAX_ENDORSE_MANY_TARGETS, 3]
+        );
+        // 分组后顺序不变、不遗漏，每笔交易都不超过大小上限
+        assert_eq!(batches.concat(), endorsees);
+        for batch in &batches {
+            let instruction = instruction::endorse_many(&program_id, &endorser, &endorser_gongde, batch);
+            assert!(transaction_size(&[instruction], &endorser) <= PACKET_DATA_SIZE);
+        }
+        assert!(pack_endorsements(&program_id, &endorser, &endorser_gongde, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_partition_users_excludes_existing() {
+        let users = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let owner = Pubkey::new_unique();
+        let existing = GongDeAccount::unpack(&[1, 0, 0, 0, 3]).unwrap();
+        let results = vec![
+            Ok(existing),
+            Err(DecodeError::NotFound),
+            Err(DecodeError::Closed),
+            Err(DecodeError::WrongOwner(owner)),
+        ];
+
+        let partition = partition_users(&users, results);
+        assert_eq!(partition.existing, vec![users[0]]);
+        assert_eq!(partition.new_users, vec![users[1], users[2]]);
+        assert_eq!(partition.blocked, vec![(users[3], DecodeError::WrongOwner(owner))]);
+    }
+
+    fn confirmed(report: &mut OperationReport, accounts: Vec<Pubkey>, lamports: u64) {
+        report.record(Some(Signature::new_unique()), accounts, lamports, Ok(()));
+    }
+
+    #[test]
+    fn test_operation_report_mixed_outcomes() {
+        let accounts: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let mut report = OperationReport::new("迁移");
+        confirmed(&mut report, accounts[0..2].to_vec(), 100);
+        // 执行失败：一定没有上链
+        let execution_error: ClientError = TransactionError::InstructionError(0, InstructionError::InvalidArgument).into();
+        report.record(Some(Signature::new_unique()), vec![accounts[2]], 50, Err(execution_error));
+        // 确认超时：可能已经上链
+        let timeout = || ClientError::from(ClientErrorKind::Custom("等待交易确认超时".to_string()));
+        report.record(Some(Signature::new_unique()), vec![accounts[3]], 50, Err(timeout()));
+        // 没拿到blockhash，交易没发出去
+        report.record(None, vec![accounts[4]], 50, Err(timeout()));
+
+        let statuses: Vec<TransactionStatus> = report.outcomes.iter().map(|outcome| outcome.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                TransactionStatus::Confirmed,
+                TransactionStatus::Failed,
+                TransactionStatus::Unknown,
+                TransactionStatus::Failed,
+            ]
+        );
+        assert!(!report.is_complete());
+        assert_eq!(report.completed_items(), accounts[0..2].to_vec());
+        assert_eq!(report.failed_items(), accounts[2..].to_vec());
+        // 只统计已确认交易的花费
+        assert_eq!(report.lamports_spent(), 100);
+        assert_eq!(report.exit_code(), EXIT_PARTIAL);
+
+        let rendered = report.render();
+        assert!(rendered.contains("交易 4 笔: ✅ 已确认 1，❌ 失败 2，⏳ 状态未知 1"), "{}", rendered);
+        assert!(rendered.contains("账户: 完成 2 个，未完成 3 个"), "{}", rendered);
+        assert!(rendered.contains(&accounts[3].to_string()), "{}", rendered);
+        assert!(rendered.contains("可能已经上链"), "{}", rendered);
+
+        let json = report.to_json();
+        assert_eq!(json["operation"], "迁移");
+        assert_eq!(json["complete"], false);
+        assert_eq!(json["exit_code"], EXIT_PARTIAL);
+        assert_eq!(json["transactions"][2]["status"], "unknown");
+        assert_eq!(json["transactions"][3]["signature"], serde_json::Value::Null);
+        assert_eq!(json["transactions"][0]["accounts"][1], accounts[1].to_string());
+    }
+
+    #[test]
+    fn test_operation_report_exit_codes() {
+        let mut report = OperationReport::new("开通");
+        // 没有任何交易也算完成
+        assert!(report.is_complete());
+        assert_eq!(report.exit_code(), 0);
+
+        confirmed(&mut report, vec![Pubkey::new_unique()], 10);
+        assert!(report.is_complete());
+        assert_eq!(report.exit_code(), 0);
+        assert!(!report.render().contains("可能已经上链"));
+
+        let mut failed = OperationReport::new("开通");
+        let error: ClientError = TransactionError::AccountInUse.into();
+        failed.record(Some(Signature::new_unique()), vec![Pubkey::new_unique()], 10, Err(error));
+        assert_eq!(failed.exit_code(), 1);
+        assert_eq!(failed.lamports_spent(), 0);
+    }
+
+    #[test]
+    fn test_predict_transfer_quota_uses_cluster_day() {
+        // 链上按集群时间换日：集群时间还在23:59:59时，即使本地时钟已经过了零点，额度也还没恢复
+        let cluster_now = 1_710_115_199;
+        let state = TransferState { day: day_index(cluster_now), transferred_today: DAILY_TRANSFER_CAP };
+        assert_eq!(predict_transfer_quota(Some(state), cluster_now), TransferQuota { remaining: 0, resets_in: 1 });
+        assert_eq!(
+            predict_transfer_quota(Some(state), cluster_now + 1),
+            TransferQuota { remaining: DAILY_TRANSFER_CAP, resets_in: SECONDS_PER_DAY }
+        );
+        // 从未转赠过
+        assert_eq!(predict_transfer_quota(None, cluster_now).remaining, DAILY_TRANSFER_CAP);
+    }
+
+    #[test]
+    fn test_arg_value() {
+        let args: Vec<String> =
+            ["example", "--out", "idl.json", "--force", "--top"].iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(arg_value(&args, "--out"), Some("idl.json"));
+        // 取的是参数名之后的那一项，即使它也是参数名
+        assert_eq!(arg_value(&args, "--force"), Some("--top"));
+        // 参数名在最后、没有这个参数都返回None
+        assert_eq!(arg_value(&args, "--top"), None);
+        assert_eq!(arg_value(&args, "--format"), None);
+
+        // 重复出现时取第一个
+        let args: Vec<String> = ["--id", "1", "--id", "2"].iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(arg_value(&args, "--id"), Some("1"));
+    }
+
+    #[test]
+    fn test_parse_arg() {
+        let args: Vec<String> = ["--id", "7", "--price", "abc"].iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(parse_arg(&args, "--id", 0u64), Ok(7));
+        assert_eq!(parse_arg(&args, "--days", 7i64), Ok(7));
+        let err = parse_arg(&args, "--price", 0u32).unwrap_err();
+        assert!(err.starts_with("参数 --price 无效: "), "{}", err);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(1), "0小时1分");
+        assert_eq!(format_duration(3_600), "1小时0分");
+        assert_eq!(format_duration(SECONDS_PER_DAY), "24小时0分");
+    }
+
+    #[test]
+    fn test_format_utc_datetime() {
+        assert_eq!(format_utc_datetime(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_utc_datetime(1_700_000_000), "2023-11-14 22:13 UTC");
+        // 闰日和时间戳为负数（1970年以前）
+        assert_eq!(format_utc_datetime(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(format_utc_datetime(-60), "1969-12-31 23:59 UTC");
+    }
+
+    #[test]
+    fn test_format_count_small_numbers() {
+        for style in [SeparatorStyle::Comma, SeparatorStyle::Space, SeparatorStyle::Dot, SeparatorStyle::None] {
+            assert_eq!(format_count_with(0, style), "0");
+            assert_eq!(format_count_with(7, style), "7");
+            assert_eq!(format_count_with(999, style), "999");
+        }
+    }
+
+    #[test]
+    fn test_format_count_separators() {
+        assert_eq!(format_count_with(1_000, SeparatorStyle::Comma), "1,000");
+        assert_eq!(format_count_with(1_234_567, SeparatorStyle::Comma), "1,234,567");
+        assert_eq!(format_count_with(12_345, SeparatorStyle::Space), "12 345");
+        assert_eq!(format_count_with(1_234_567, SeparatorStyle::Dot), "1.234.567");
+        assert_eq!(format_count_with(1_234_567, SeparatorStyle::None), "1234567");
+        assert_eq!(format_count_with(u64::MAX, SeparatorStyle::Comma), "18,446,744,073,709,551,615");
+        assert_eq!(format_count_with(u64::MAX, SeparatorStyle::None), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn test_merit_display_selection_and_format() {
+        assert_eq!(MeritDisplay::select(&["query"], None), MeritDisplay::Exact);
+        assert_eq!(MeritDisplay::select(&["query", "--rough"], None), MeritDisplay::Rough);
+        assert_eq!(MeritDisplay::select(&["query"], Some("1")), MeritDisplay::Rough);
+        assert_eq!(MeritDisplay::select(&["query"], Some(" TRUE ")), MeritDisplay::Rough);
+        assert_eq!(MeritDisplay::select(&["query"], Some("0")), MeritDisplay::Exact);
+        // --exact 优先于环境变量
+        assert_eq!(MeritDisplay::select(&["query", "--exact"], Some("1")), MeritDisplay::Exact);
+
+        assert_eq!(MeritDisplay::Rough.format(0), "0");
+        assert_eq!(MeritDisplay::Rough.format(150), "101+");
+        assert_eq!(MeritDisplay::Rough.format_total(u64::MAX), "10001+");
+        assert_eq!(MeritDisplay::Exact.format_total(1_234), format_count(1_234));
+    }
+
+    #[test]
+    fn test_separator_style_selection() {
+        assert_eq!(SeparatorStyle::from_locale("zh_CN.UTF-8"), SeparatorStyle::Comma);
+        assert_eq!(SeparatorStyle::from_locale("de_DE.UTF-8"), SeparatorStyle::Dot);
+        assert_eq!(SeparatorStyle::from_locale("fr-FR"), SeparatorStyle::Space);
+        assert_eq!(SeparatorStyle::from_locale("C"), SeparatorStyle::Comma);
+        assert_eq!(SeparatorStyle::from_name(" Space "), Some(SeparatorStyle::Space));
+        assert_eq!(SeparatorStyle::from_name("semicolon"), None);
+    }
+
+    #[test]
+    fn test_rent_deficit() {
+        let candidate = legacy_candidates(1)[0];
+        assert_eq!(candidate.rent_deficit(946_560), 946_560 - 918_720);
+        // 余额已经足够时不再补
+        assert_eq!(candidate.rent_deficit(900_000), 0);
+    }
+
+    /// 模拟链：只有一个功德账户，收到迁移指令时按合约的方式扩容并写入公开标志
+    struct MockChain {
+        program_id: Pubkey,
+        account: RefCell<Account>,
+        migrations: RefCell<Vec<Instruction>>,
+    }
+
+    impl MockChain {
+        fn legacy(value: u32) -> Self {
+            let program_id = Pubkey::new_unique();
+            let mut account = Account::new(918_720, GONGDE_VALUE_SIZE, &program_id);
+            write_gongde_value(&mut account.data, value).unwrap();
+            Self { program_id, account: RefCell::new(account), migrations: RefCell::default() }
+        }
+    }
+
+    impl AccountFetcher for MockChain {
+        fn fetch_account(&self, _pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(Some(self.account.borrow().clone()))
+        }
+    }
+
+    impl MigrationSender for MockChain {
+        fn send_migration(&self, instruction: Instruction, _payer: &Keypair) -> ClientResult<Signature> {
+            let mut account = self.account.borrow_mut();
+            let value = u64::from(GongDeAccount::unpack(&account.data).unwrap().value);
+            account.data.resize(GONGDE_ACCOUNT_SIZE, 0);
+            account.lamports = 981_360;
+            write_initialized_flags(&mut account.data, true).unwrap();
+            write_lifetime_total(&mut account.data, value).unwrap();
+            self.migrations.borrow_mut().push(instruction);
+            Ok(Signature::default())
+        }
+    }
+
+    fn read_gongde(chain: &MockChain) -> Result<(usize, GongDeAccount), Box<dyn std::error::Error>> {
+        let account = chain.fetch_account(&Pubkey::default())?.ok_or("账户不存在")?;
+        Ok((account.data.len(), GongDeAccount::unpack(&account.data).map_err(|e| format!("{:?}", e))?))
+    }
+
+    #[test]
+    fn test_auto_migration_then_reads_current_layout() {
+        let chain = MockChain::legacy(7);
+        let payer = Keypair::new();
+        let owner = Pubkey::new_unique();
+        let gongde_pubkey = Pubkey::new_unique();
+        let mut asked = None;
+
+        let (len, gongde) = with_auto_migration(
+            &chain,
+            &payer,
+            &chain.program_id,
+            &owner,
+            &gongde_pubkey,
+            |gap| {
+                asked = Some(*gap);
+                true
+            },
+            read_gongde,
+        )
+        .unwrap();
+
+        assert_eq!(asked, Some(LayoutGap { account_version: 1, current_version: GONGDE_LAYOUT_VERSION }));
+        let migrations = chain.migrations.borrow();
+        assert_eq!(*migrations, vec![instruction::migrate(&chain.program_id, &gongde_pubkey, &owner, &payer.pubkey())]);
+        // 原来的操作在迁移之后执行，读到的是当前布局，功德值不变
+        assert_eq!(len, GONGDE_ACCOUNT_SIZE);
+        assert_eq!(gongde.value, 7);
+        assert_eq!(gongde.lifetime_total, 7);
+        assert!(gongde.is_initialized());
+        assert!(gongde.is_public());
+    }
+
+    #[test]
+    fn test_auto_migration_declined_or_not_needed() {
+        // 用户不同意时不发送交易，按旧布局继续
+        let chain = MockChain::legacy(3);
+        let (owner, gongde_pubkey) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (len, gongde) =
+            with_auto_migration(&chain, &Keypair::new(), &chain.program_id, &owner, &gongde_pubkey, |_| false, read_gongde)
+                .unwrap();
+        assert!(chain.migrations.borrow().is_empty());
+        assert_eq!((len, gongde.value, gongde.is_initialized()), (GONGDE_VALUE_SIZE, 3, false));
+
+        // 已是当前布局时不询问
+        let current = MockChain::legacy(3);
+        current.account.borrow_mut().data.resize(GONGDE_ACCOUNT_SIZE, 0);
+        let asked = std::cell::Cell::new(false);
+        with_auto_migration(&current, &Keypair::new(), &current.program_id, &owner, &gongde_pubkey, |_| asked.replace(true), read_gongde)
+            .unwrap();
+        assert!(!asked.get());
+        assert!(current.migrations.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_ask_migration_consent() {
+        let gap = LayoutGap { account_version: 1, current_version: GONGDE_LAYOUT_VERSION };
+        assert!(ask_migration_consent(&gap, true, false, &mut std::io::empty()));
+        // 非交互环境不读取输入
+        assert!(!ask_migration_consent(&gap, false, false, &mut std::io::Cursor::new("y\n")));
+        assert!(ask_migration_consent(&gap, false, true, &mut std::io::Cursor::new("Y\n")));
+        assert!(!ask_migration_consent(&gap, false, true, &mut std::io::Cursor::new("n\n")));
+        assert!(!ask_migration_consent(&gap, false, true, &mut std::io::empty()));
+    }
+
+    #[test]
+    fn test_cluster_from_genesis_hash() {
+        let parse = |hash: &str| Cluster::from_genesis_hash(&hash.parse().unwrap());
+        assert_eq!(parse(MAINNET_BETA_GENESIS_HASH), Cluster::MainnetBeta);
+        assert_eq!(parse(TESTNET_GENESIS_HASH), Cluster::Testnet);
+        assert_eq!(parse(DEVNET_GENESIS_HASH), Cluster::Devnet);
+
+        let local = Hash::new_unique();
+        assert_eq!(Cluster::from_genesis_hash(&local), Cluster::Unknown(local));
+        assert!(parse(MAINNET_BETA_GENESIS_HASH).is_mainnet());
+        assert!(!Cluster::Unknown(local).is_mainnet());
+        assert_eq!(Cluster::MainnetBeta.to_string(), "mainnet-beta");
+    }
+
+    #[test]
+    fn test_confirm_destructive_operation() {
+        let empty = || std::io::Cursor::new(Vec::<u8>::new());
+
+        // 非主网不需要确认，也不读输入
+        assert_eq!(confirm_destructive_operation(&Cluster::Devnet, false, false, &mut empty()), Ok(()));
+        assert_eq!(
+            confirm_destructive_operation(&Cluster::Unknown(Hash::default()), false, true, &mut empty()),
+            Ok(())
+        );
+
+        // 主网：--allow-mainnet 放行，非交互环境直接拒绝
+        assert_eq!(confirm_destructive_operation(&Cluster::MainnetBeta, true, false, &mut empty()), Ok(()));
+        assert_eq!(
+            confirm_destructive_operation(&Cluster::MainnetBeta, false, false, &mut std::io::Cursor::new("mainnet-beta\n")),
+            Err(ClusterGuardError::NonInteractive)
+        );
+
+        // 交互终端中输入集群名称确认
+        assert_eq!(
+            confirm_destructive_operation(&Cluster::MainnetBeta, false, true, &mut std::io::Cursor::new("mainnet-beta\n")),
+            Ok(())
+        );
+        assert_eq!(
+            confirm_destructive_operation(&Cluster::MainnetBeta, false, true, &mut std::io::Cursor::new("y\n")),
+            Err(ClusterGuardError::NotConfirmed)
+        );
+        // 输入被关闭（EOF）视为未确认
+        assert_eq!(
+            confirm_destructive_operation(&Cluster::MainnetBeta, false, true, &mut empty()),
+            Err(ClusterGuardError::NotConfirmed)
+        );
+    }
+
+    /// 模拟客户端：按顺序返回预设的查询结果
+    struct MockFetcher {
+        responses: RefCell<std::collections::VecDeque<Option<Account>>>,
+    }
+
+    impl AccountFetcher for MockFetcher {
+        fn fetch_account(&self, _pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(self.responses.borrow_mut().pop_front().flatten())
+        }
+    }
+
+    fn recording_poller(max_attempts: u32) -> (AccountPoller, Rc<RefCell<Vec<Duration>>>) {
+        let sleeps = Rc::new(RefCell::new(Vec::new()));
+        let recorded = Rc::clone(&sleeps);
+        let poller = AccountPoller::new(Duration::from_millis(100), Duration::from_secs(1), max_attempts)
+            .with_sleep(move |delay| recorded.borrow_mut().push(delay));
+        (poller, sleeps)
+    }
+
+    struct MockStatuses {
+        statuses: RefCell<std::collections::VecDeque<Option<SignatureStatus>>>,
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl MockStatuses {
+        fn new(statuses: Vec<Option<SignatureStatus>>) -> Self {
+            Self { statuses: RefCell::new(statuses.into()), calls: std::cell::Cell::new(0) }
+        }
+    }
+
+    impl SignatureStatusSource for MockStatuses {
+        fn fetch_signature_status(&self, _signature: &Signature) -> ClientResult<Option<SignatureStatus>> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(self.statuses.borrow_mut().pop_front().flatten())
+        }
+    }
+
+    fn signature_status(level: TransactionConfirmationStatus, err: Option<TransactionError>) -> SignatureStatus {
+        SignatureStatus {
+            slot: 1,
+            // 与节点一致：已最终确认的交易不再返回确认数
+            confirmations: match level {
+                TransactionConfirmationStatus::Processed => Some(0),
+                TransactionConfirmationStatus::Confirmed => Some(1),
+                TransactionConfirmationStatus::Finalized => None,
+            },
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(level),
+        }
+    }
+
+    fn rising_statuses() -> MockStatuses {
+        MockStatuses::new(vec![
+            None,
+            Some(signature_status(TransactionConfirmationStatus::Processed, None)),
+            Some(signature_status(TransactionConfirmationStatus::Confirmed, None)),
+            Some(signature_status(TransactionConfirmationStatus::Finalized, None)),
+        ])
+    }
+
+    #[test]
+    fn test_poll_signature_status_waits_for_target_commitment() {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let signature = Signature::new_unique();
+
+        let client = rising_statuses();
+        let mut sleeps = 0;
+        poll_signature_status_with_sleep(&client, &signature, CommitmentConfig::confirmed(), deadline, |_| sleeps += 1).unwrap();
+        assert_eq!(client.calls.get(), 3);
+        assert_eq!(sleeps, 2);
+
+        let client = rising_statuses();
+        poll_signature_status_with_sleep(&client, &signature, CommitmentConfig::finalized(), deadline, |_| {}).unwrap();
+        assert_eq!(client.calls.get(), 4);
+    }
+
+    #[test]
+    fn test_poll_signature_status_timeout_and_failure() {
+        let signature = Signature::new_unique();
+
+        // 截止时间已过，只查一次就报告最后看到的级别
+        let client = rising_statuses();
+        client.statuses.borrow_mut().pop_front();
+        match poll_signature_status_with_sleep(&client, &signature, CommitmentConfig::finalized(), Instant::now(), |_| panic!("不应该等待")) {
+            Err(SignatureWaitError::Timeout { reached, .. }) => assert_eq!(reached, Some(TransactionConfirmationStatus::Processed)),
+            other => panic!("应该超时: {:?}", other),
+        }
+        assert_eq!(client.calls.get(), 1);
+
+        // 执行失败立即返回，不等到目标级别
+        let failed = MockStatuses::new(vec![Some(signature_status(
+            TransactionConfirmationStatus::Processed,
+            Some(TransactionError::InstructionError(0, InstructionError::Custom(2))),
+        ))]);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        match poll_signature_status_with_sleep(&failed, &signature, CommitmentConfig::confirmed(), deadline, |_| {}) {
+            Err(SignatureWaitError::Failed(err)) => {
+                assert_eq!(err, TransactionError::InstructionError(0, InstructionError::Custom(2)))
+            }
+            other => panic!("应该失败: {:?}", other),
+        }
+    }
+
+    fn lookup_with(account: Option<Account>) -> (Pubkey, GongDeAccountLookup) {
+        let user = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let fetcher = MockFetcher {
+            responses: RefCell::new(vec![account].into()),
+        };
+        let lookup = query_gongde_account(&fetcher, &user, &program_id).unwrap();
+        assert_eq!(lookup.pubkey(), get_gongde_account_address(&user, &program_id).unwrap());
+        (lookup.pubkey(), lookup)
+    }
+
+    #[test]
+    fn test_query_gongde_account_live() {
+        let mut account = Account::new(1_000_000, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique());
+        account.data[..4].copy_from_slice(&42u32.to_le_bytes());
+        let (pubkey, lookup) = lookup_with(Some(account));
+        let info = lookup.live().expect("应该是可用账户");
+        assert_eq!(info.pubkey, pubkey);
+        assert_eq!(info.value, 42);
+        assert_eq!(info.lamports, 1_000_000);
+        assert_eq!(lookup.value(), 42);
+    }
+
+    #[test]
+    fn test_gongde_account_info_fields() {
+        let pubkey = Pubkey::new_unique();
+        let mut account = Account::new(2_000_000, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique());
+        write_gongde_value(&mut account.data, 150).unwrap();
+        write_initialized_flags(&mut account.data, false).unwrap();
+        write_lifetime_total(&mut account.data, 300).unwrap();
+        write_archived_flag(&mut account.data, true).unwrap();
+        touch_last_active(&mut account.data, 1_700_000_000);
+        write_emoji(&mut account.data, '🙏' as u32).unwrap();
+
+        let info = GongDeAccountInfo::from_account(pubkey, &account).unwrap();
+        assert_eq!(
+            info,
+            GongDeAccountInfo {
+                pubkey,
+                value: 150,
+                lifetime_total: 300,
+                lamports: 2_000_000,
+                last_active: Some(1_700_000_000),
+                level: MeritLevel::from_value(150),
+                public: false,
+                archived: true,
+                emoji: Some('🙏'),
+            }
+        );
+        assert_eq!(emoji_suffix(info.emoji), " 🙏");
+        assert_eq!(classify_gongde_account(pubkey, Some(account)).unwrap(), GongDeAccountLookup::Live(info));
+
+        // 旧的4字节账户没有这些字段：公开、没有操作时间
+        let mut legacy = Account::new(1_000_000, GONGDE_VALUE_SIZE, &Pubkey::new_unique());
+        write_gongde_value(&mut legacy.data, 7).unwrap();
+        let info = GongDeAccountInfo::from_account(pubkey, &legacy).unwrap();
+        assert_eq!((info.value, info.lifetime_total, info.last_active, info.public, info.archived), (7, 7, None, true, false));
+        assert_eq!((info.emoji, emoji_suffix(info.emoji)), (None, String::new()));
+
+        assert_eq!(
+            GongDeAccountInfo::from_account(pubkey, &Account::new(1_000_000, 2, &Pubkey::new_unique())),
+            Err(StateError::AccountDataTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_query_gongde_account_closed_but_present() {
+        // 余额已转走但数据还没回收，残留的功德不能算数
+        let mut account = Account::new(0, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique());
+        account.data[..4].copy_from_slice(&42u32.to_le_bytes());
+        let (pubkey, lookup) = lookup_with(Some(account));
+        assert_eq!(
+            lookup,
+            GongDeAccountLookup::ClosedButPresent { pubkey, data_len: GONGDE_ACCOUNT_SIZE }
+        );
+        assert_eq!(lookup.live(), None);
+        assert_eq!(lookup.value(), 0);
+    }
+
+    #[test]
+    fn test_query_gongde_account_never_existed() {
+        let (pubkey, lookup) = lookup_with(None);
+        assert_eq!(lookup, GongDeAccountLookup::NotFound { pubkey });
+        assert_eq!(lookup.live(), None);
+        assert_eq!(lookup.value(), 0);
+    }
+
+    /// 模拟客户端：按地址返回账户
+    struct MockAccounts(HashMap<Pubkey, Account>);
+
+    impl AccountFetcher for MockAccounts {
+        fn fetch_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(self.0.get(pubkey).cloned())
+        }
+    }
+
+    #[test]
+    fn test_query_gongde_account_at_reads_non_derived_address() {
+        // 客户端随机生成的密钥对账户，不在用户的派生地址上
+        let user = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let mut account = Account::new(1_000_000, GONGDE_ACCOUNT_SIZE, &program_id);
+        account.data[..4].copy_from_slice(&42u32.to_le_bytes());
+        let client = MockAccounts(HashMap::from([(address, account)]));
+
+        let lookup = query_gongde_account_at(&client, &address, &program_id).unwrap();
+        assert_eq!(lookup.pubkey(), address);
+        assert_eq!(lookup.value(), 42);
+        // 按用户公钥派生时找不到这个账户
+        let derived = query_gongde_account(&client, &user, &program_id).unwrap();
+        assert_eq!(derived, GongDeAccountLookup::NotFound { pubkey: get_gongde_account_address(&user, &program_id).unwrap() });
+
+        let missing = Pubkey::new_unique();
+        let lookup = query_gongde_account_at(&client, &missing, &program_id).unwrap();
+        assert_eq!(lookup, GongDeAccountLookup::NotFound { pubkey: missing });
+    }
+
+    #[test]
+    fn test_query_gongde_account_at_validates_account() {
+        let program_id = Pubkey::new_unique();
+        let (foreign, truncated) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let client = MockAccounts(HashMap::from([
+            (foreign, Account::new(1_000_000, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique())),
+            (truncated, Account::new(1_000_000, 2, &program_id)),
+        ]));
+
+        let err = query_gongde_account_at(&client, &foreign, &program_id).unwrap_err();
+        assert!(err.to_string().contains("不归本程序所有"), "{}", err);
+        let err = query_gongde_account_at(&client, &truncated, &program_id).unwrap_err();
+        assert!(err.to_string().contains("数据无法解析"), "{}", err);
+    }
+
+    #[test]
+    fn test_dump_account_lists_every_section() {
+        let program_id = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let rent = Rent::default().minimum_balance(GONGDE_ACCOUNT_SIZE);
+        let mut account = Account::new(rent, GONGDE_ACCOUNT_SIZE, &program_id);
+        write_gongde_value(&mut account.data, 42).unwrap();
+        write_lifetime_total(&mut account.data, 50).unwrap();
+        write_initialized_flags(&mut account.data, true).unwrap();
+        let client = MockAccounts(HashMap::from([(address, account)]));
+
+        let dump = dump_account(&client, &address, &program_id).unwrap();
+        assert_eq!(dump.issues(), Vec::<String>::new());
+        let text = dump.to_string();
+        for section in ["== 基本信息 ==", "== 租金 ==", "== 解析结果 ==", "== 原始数据 ==", "== 检查结果 =="] {
+            assert!(text.contains(section), "缺少 {}:\n{}", section, text);
+        }
+        assert!(text.contains(&format!("所有者: {}（本程序）", program_id)), "{}", text);
+        assert!(text.contains("功德: 当前 42 / 累计 50"), "{}", text);
+        assert!(text.contains("  0000  2a 00 00 00 03 32 00"), "{}", text);
+        assert!(text.contains("✅ 没有发现问题"), "{}", text);
+    }
+
+    #[test]
+    fn test_dump_account_flags_inconsistencies() {
+        let program_id = Pubkey::new_unique();
+        let (foreign, missing) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // 不归本程序所有、余额不足免租金、功德大于累计功德
+        let mut account = Account::new(1_000, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique());
+        write_gongde_value(&mut account.data, 42).unwrap();
+        write_initialized_flags(&mut account.data, true).unwrap();
+        let client = MockAccounts(HashMap::from([(foreign, account)]));
+
+        let dump = dump_account(&client, &foreign, &program_id).unwrap();
+        let issues = dump.issues();
+        assert_eq!(issues.len(), 3, "{:?}", issues);
+        assert!(issues[0].contains("不是本程序"));
+        assert!(issues[1].contains("低于免租金要求"));
+        assert!(issues[2].contains("大于累计功德"));
+        assert!(dump.to_string().contains("⚠️ 不是本程序"));
+
+        // 账户不存在时只有基本信息和检查结果
+        let text = dump_account(&client, &missing, &program_id).unwrap().to_string();
+        assert!(text.contains("账户不存在") && text.contains("== 检查结果 =="), "{}", text);
+        assert!(!text.contains("== 原始数据 =="), "{}", text);
+    }
+
+    #[test]
+    fn test_wait_for_account_backs_off() {
+        let account = Account::new(1_000_000, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique());
+        let fetcher = MockFetcher {
+            responses: RefCell::new(vec![None, None, Some(account.clone())].into()),
+        };
+        let (mut poller, sleeps) = recording_poller(5);
+
+        let found = poller.wait_for_account(&fetcher, &Pubkey::new_unique()).unwrap();
+        assert_eq!(found, account);
+        // 两次未找到，等待时间递增
+        assert_eq!(*sleeps.borrow(), vec![Duration::from_millis(100), Duration::from_millis(200)]);
+    }
+
+    #[test]
+    fn test_wait_for_account_timeout() {
+        let fetcher = MockFetcher { responses: RefCell::new(Default::default()) };
+        let (mut poller, sleeps) = recording_poller(6);
+
+        match poller.wait_for_account(&fetcher, &Pubkey::new_unique()) {
+            Err(WaitError::Timeout { attempts, waited }) => {
+                assert_eq!(attempts, 6);
+                // 100 + 200 + 400 + 800 + 1000(上限)
+                assert_eq!(waited, Duration::from_millis(2500));
+            }
+            other => panic!("应该超时: {:?}", other),
+        }
+        assert_eq!(sleeps.borrow().len(), 5);
+        assert_eq!(sleeps.borrow()[4], Duration::from_secs(1));
+    }
+
+    /// 模拟程序：固定的程序账户和版本查询结果，并记录查询次数
+    struct MockProgram {
+        account: Option<Account>,
+        probe: Result<Vec<u8>, TransactionError>,
+        fetches: RefCell<u32>,
+    }
+
+    impl MockProgram {
+        fn new(account: Option<Account>, probe: Result<Vec<u8>, TransactionError>) -> Self {
+            Self { account, probe, fetches: RefCell::new(0) }
+        }
+
+        fn deployed(probe: Result<Vec<u8>, TransactionError>) -> Self {
+            let mut account = Account::new(1_000_000, 36, &bpf_loader_upgradeable::id());
+            account.executable = true;
+            Self::new(Some(account), probe)
+        }
+    }
+
+    impl AccountFetcher for MockProgram {
+        fn fetch_account(&self, _pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            *self.fetches.borrow_mut() += 1;
+            Ok(self.account.clone())
+        }
+    }
+
+    impl ProgramProbe for MockProgram {
+        fn probe_protocol_version(&self, _program_id: &Pubkey, _payer: &Pubkey) -> ClientResult<Result<Vec<u8>, TransactionError>> {
+            Ok(self.probe.clone())
+        }
+    }
+
+    fn anchor_rejection() -> Result<Vec<u8>, TransactionError> {
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(101)))
+    }
+
+    #[test]
+    fn test_program_guard_rejects_missing_and_non_executable() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        let missing = MockProgram::new(None, Ok(vec![]));
+        assert!(matches!(
+            ProgramGuard::new(false).ensure(&missing, &program_id, &payer),
+            Err(ProgramCheckError::NotFound(_))
+        ));
+
+        let data_account = MockProgram::new(Some(Account::new(1_000_000, 5, &Pubkey::new_unique())), Ok(vec![]));
+        assert!(matches!(
+            ProgramGuard::new(false).ensure(&data_account, &program_id, &payer),
+            Err(ProgramCheckError::NotExecutable(_))
+        ));
+    }
+
+    #[test]
+    fn test_program_guard_flavor_mismatch() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let anchor = MockProgram::deployed(anchor_rejection());
+
+        match ProgramGuard::new(false).ensure(&anchor, &program_id, &payer) {
+            Err(ProgramCheckError::FlavorMismatch { flavor, .. }) => assert_eq!(flavor, ProgramFlavor::Anchor),
+            other => panic!("应该识别为Anchor程序: {:?}", other),
+        }
+        // --force 时只警告不阻止
+        assert!(ProgramGuard::new(true).ensure(&anchor, &program_id, &payer).is_ok());
+    }
+
+    #[test]
+    fn test_program_guard_caches_per_session() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let native = MockProgram::deployed(Ok(ProtocolInfo::CURRENT.to_bytes().to_vec()));
+        let mut guard = ProgramGuard::new(false);
+
+        guard.ensure(&native, &program_id, &payer).unwrap();
+        guard.ensure(&native, &program_id, &payer).unwrap();
+        assert_eq!(*native.fetches.borrow(), 1);
+    }
+
+    #[test]
+    fn test_detect_program_flavor() {
+        let legacy = Err(TransactionError::InstructionError(0, InstructionError::InvalidInstructionData));
+        assert_eq!(detect_program_flavor(&legacy), ProgramFlavor::NativeLegacy);
+        assert_eq!(detect_program_flavor(&anchor_rejection()), ProgramFlavor::Anchor);
+        assert!(matches!(detect_program_flavor(&Ok(vec![1])), ProgramFlavor::Unknown(_)));
+    }
+
+    /// 发送交易时模拟失败的RPC响应（sendTransaction 预检），`err` 为交易错误的JSON
+    fn preflight_failure(err: &str) -> ClientError {
+        let response: serde_json::Value = serde_json::from_str(&format!(
+            r#"{{
+                "jsonrpc": "2.0",
+                "error": {{
+                    "code": -32002,
+                    "message": "Transaction simulation failed: Error processing Instruction 1",
+                    "data": {{
+                        "accounts": null,
+                        "err": {},
+                        "innerInstructions": null,
+                        "logs": [
+                            "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+                            "Program ComputeBudget111111111111111111111111111111 success"
+                        ],
+                        "replacementBlockhash": null,
+                        "returnData": null,
+                        "unitsConsumed": 2875
+                    }}
+                }},
+                "id": 1
+            }}"#,
+            err
+        ))
+        .unwrap();
+        let error = &response["error"];
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: error["code"].as_i64().unwrap(),
+            message: error["message"].as_str().unwrap().to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::SendTransactionPreflightFailure(
+                serde_json::from_value(error["data"].clone()).unwrap(),
+            ),
+        })
+        .into()
+    }
+
+    #[test]
+    fn test_gongde_error_codes_round_trip() {
+        for error in GongDeError::ALL {
+            assert_eq!(GongDeError::from_code(error as u32), Some(error));
+        }
+        assert_eq!(GongDeError::from_code(GongDeError::ALL.len() as u32), None);
+    }
+
+    #[test]
+    fn test_explain_preflight_failure() {
+        let err = preflight_failure(r#"{ "InstructionError": [1, { "Custom": 1 }] }"#);
+        let explanation = explain_client_error(&err, ErrorCodeSpace::Native).unwrap();
+        assert_eq!(explanation.instruction_index, 1);
+        assert_eq!(explanation.code, Some(GongDeError::DailyCapReached as u32));
+        assert_eq!(explanation.suggestion, Some("功德已达今日上限，请明天再来"));
+        assert_eq!(explanation.to_string(), "指令 1 执行失败: 当天转出的功德超过每日额度（错误码 1）");
+
+        // 运行时内置的错误没有错误码
+        let err = preflight_failure(r#"{ "InstructionError": [0, "UninitializedAccount"] }"#);
+        let explanation = explain_client_error(&err, ErrorCodeSpace::Native).unwrap();
+        assert_eq!(explanation.code, None);
+        assert_eq!(explanation.suggestion, Some(CREATE_ACCOUNT_SUGGESTION));
+    }
+
+    #[test]
+    fn test_explain_confirmed_failure() {
+        // 确认时拿到的是 getSignatureStatuses / signatureSubscribe 中的 err 字段
+        let err: TransactionError = serde_json::from_str(r#"{ "InstructionError": [0, { "Custom": 12 }] }"#).unwrap();
+        let explanation = explain_client_error(&ClientError::from(err), ErrorCodeSpace::Native).unwrap();
+        assert_eq!(explanation.message, GongDeError::ClientVersionTooOld.message());
+        assert_eq!(explanation.suggestion, Some("请升级客户端后重试"));
+
+        // 不是指令执行错误的无法解读，交给调用方按原样输出
+        let err: TransactionError = serde_json::from_str(r#""BlockhashNotFound""#).unwrap();
+        assert_eq!(explain_client_error(&ClientError::from(err), ErrorCodeSpace::Native), None);
+        assert_eq!(explain_client_error(&rate_limited("Too many requests"), ErrorCodeSpace::Native), None);
+    }
+
+    #[test]
+    fn test_explain_unknown_code_keeps_number() {
+        let err = preflight_failure(r#"{ "InstructionError": [0, { "Custom": 42 }] }"#);
+        let explanation = explain_client_error(&err, ErrorCodeSpace::Native).unwrap();
+        assert_eq!(explanation.code, Some(42));
+        assert_eq!(explanation.suggestion, None);
+        assert_eq!(explanation.to_string(), "指令 0 执行失败: 未知的程序错误（错误码 42）");
+    }
+
+    #[test]
+    fn test_explain_anchor_error_codes() {
+        let explain = |code: u32| {
+            let err = TransactionError::InstructionError(0, InstructionError::Custom(code));
+            explain_transaction_error(&err, ErrorCodeSpace::from(&ProgramFlavor::Anchor)).unwrap()
+        };
+        // 自定义错误从6000开始编号
+        assert_eq!(explain(6001).message, GongDeError::DailyCapReached.message());
+        assert_eq!(explain(3012).suggestion, Some(CREATE_ACCOUNT_SUGGESTION));
+        assert_eq!(explain(2003).message, "Anchor 账户约束检查失败");
+        assert_eq!(explain(101).message, "程序无法识别指令编码");
+        // 6000以下没有对应的 GongDeError
+        assert_eq!(explain(1).message, "未知的程序错误");
+        assert_eq!(ErrorCodeSpace::from(&ProgramFlavor::NativeLegacy), ErrorCodeSpace::Native);
+    }
+
+    #[test]
+    fn test_sum_transaction_fees() {
+        let meta = |fee: u64| -> UiTransactionStatusMeta {
+            serde_json::from_value(serde_json::json!({
+                "err": null,
+                "status": { "Ok": null },
+                "fee": fee,
+                "preBalances": [1_000_000_000u64, 0],
+                "postBalances": [1_000_000_000u64 - fee, 0],
+            }))
+            .unwrap()
+        };
+        let metas = [meta(5000), meta(5000), meta(10000)];
+
+        assert_eq!(sum_transaction_fees(&metas), 20000);
+        assert_eq!(sum_transaction_fees(&[]), 0);
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+        assert_eq!(websocket_url("https://api.devnet.solana.com"), "wss://api.devnet.solana.com");
+        assert_eq!(websocket_url("https://rpc.example.com:443/key"), "wss://rpc.example.com:444/key");
+    }
+
+    /// 需要本地验证节点：先运行 solana-test-validator，再执行
+    /// cargo test --lib --features client client::utils -- --ignored
+    #[test]
+    #[ignore = "需要本地验证节点 solana-test-validator"]
+    fn test_confirm_via_subscription_on_localnet() {
+        use solana_sdk::{
+            commitment_config::CommitmentConfig,
+            native_token::LAMPORTS_PER_SOL,
+            signature::{Keypair, Signer},
+        };
+
+        let client = RpcClient::new_with_commitment("http://127.0.0.1:8899".to_string(), CommitmentConfig::confirmed());
+        let payer = Keypair::new();
+        let airdrop = client.request_airdrop(&payer.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        poll_signature_status(&client, &airdrop, client.commitment(), Instant::now() + CONFIRM_TIMEOUT).unwrap();
+
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), LAMPORTS_PER_SOL / 10);
+        let transaction = Transaction::new_signed_with_payer(
+            &[transfer],
+            Some(&payer.pubkey()),
+            &[&payer],
+            client.get_latest_blockhash().unwrap(),
+        );
+        let (signature, method) = send_and_confirm_with_subscription(&client, &transaction).unwrap();
+
+        assert_eq!(signature, transaction.signatures[0]);
+        assert_eq!(method, ConfirmMethod::Subscription);
+        assert!(client.confirm_transaction(&signature).unwrap());
+    }
+
+    #[test]
+    fn test_token_bucket_pacing() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 2, start);
+
+        // 突发额度内不需要等待
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        // 额度用完后按速率排队：每秒2个，依次等待0.5秒、1秒
+        assert_eq!(bucket.reserve(start), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(1000));
+
+        // 过了足够长时间令牌补满，但不超过突发上限
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let throttle = Throttle::new(5.0, 10);
+        assert_eq!(throttle.backoff_delay(0, None), Duration::from_millis(500));
+        assert_eq!(throttle.backoff_delay(1, None), Duration::from_millis(1000));
+        assert_eq!(throttle.backoff_delay(2, None), Duration::from_millis(2000));
+        assert_eq!(throttle.backoff_delay(20, None), Duration::from_secs(30));
+        assert_eq!(throttle.backoff_delay(0, Some(Duration::from_secs(3))), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_retry_after_hint() {
+        assert_eq!(retry_after_hint(&rate_limited("Too many requests, retry after 2s")), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after_hint(&rate_limited("Too many requests")), None);
+    }
+
+    #[test]
+    fn test_rate_limited_request_backs_off_then_succeeds() {
+        let sleeps = Rc::new(RefCell::new(Vec::new()));
+        let recorded = sleeps.clone();
+        let mut throttle = Throttle::new(1000.0, 10).with_sleep(move |d| recorded.borrow_mut().push(d));
+
+        let mut responses = vec![Ok(42u64), Err(rate_limited("Too many requests")), Err(rate_limited("retry after 3s"))];
+        let result = throttle.run(|| responses.pop().unwrap());
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(*sleeps.borrow(), vec![Duration::from_secs(3), Duration::from_millis(1000)]);
+    }
+
+    #[test]
+    fn test_parse_rpc_rate() {
+        assert_eq!(parse_rpc_rate(None), DEFAULT_RPC_RATE);
+        assert_eq!(parse_rpc_rate(Some(" 20 ")), 20.0);
+        assert_eq!(parse_rpc_rate(Some("0.5")), 0.5);
+        assert_eq!(parse_rpc_rate(Some("0")), DEFAULT_RPC_RATE);
+        assert_eq!(parse_rpc_rate(Some("fast")), DEFAULT_RPC_RATE);
+    }
+
+    /// 一次程序账户查询的预设结果
+    type ProgramAccountsResponse = ClientResult<Vec<(Pubkey, Account)>>;
+
+    /// 按顺序返回预设结果的程序账户来源，记录每次查询的账户大小
+    struct ScriptedProgramAccounts {
+        responses: RefCell<Vec<ProgramAccountsResponse>>,
+        queried: RefCell<Vec<usize>>,
+    }
+
+    impl ProgramAccountSource for ScriptedProgramAccounts {
+        fn program_accounts_with_size(&self, _program_id: &Pubkey, size: usize) -> ClientResult<Vec<(Pubkey, Account)>> {
+            self.queried.borrow_mut().push(size);
+            self.responses.borrow_mut().remove(0)
+        }
+    }
+
+    #[test]
+    fn test_rank_accounts_excludes_archived_by_default() {
+        let program_id = Pubkey::new_unique();
+        let global = Pubkey::new_unique();
+        let (top, archived, closed, low) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let initialized = |value| {
+            let mut account = gongde_account(program_id, value);
+            write_initialized_flags(&mut account.data, true).unwrap();
+            account
+        };
+        let mut archived_account = initialized(500);
+        write_archived_flag(&mut archived_account.data, true).unwrap();
+        let mut closed_account = initialized(900);
+        closed_account.lamports = 0;
+        let mut global_account = gongde_account(program_id, 10_000);
+        global_account.data.truncate(GONGDE_VALUE_SIZE);
+        let mut top_account = initialized(42);
+        write_emoji(&mut top_account.data, '🪷' as u32).unwrap();
+        let accounts = vec![
+            (low, initialized(3)),
+            (archived, archived_account),
+            (closed, closed_account),
+            (global, global_account),
+            (top, top_account),
+            // 创建了但还没有初始化的账户
+            (Pubkey::new_unique(), gongde_account(program_id, 0)),
+        ];
+
+        let ranked = rank_accounts(accounts.clone(), &global, false);
+        assert_eq!(ranked.iter().map(|entry| (entry.pubkey, entry.value)).collect::<Vec<_>>(), vec![(top, 42), (low, 3)]);
+        assert_eq!(ranked.iter().map(|entry| entry.emoji).collect::<Vec<_>>(), vec![Some('🪷'), None]);
+
+        let ranked = rank_accounts(accounts, &global, true);
+        assert_eq!(
+            ranked.iter().map(|entry| (entry.pubkey, entry.archived)).collect::<Vec<_>>(),
+            vec![(archived, true), (top, false), (low, false)]
+        );
+    }
+
+    #[test]
+    fn test_scan_retries_rate_limited_queries() {
+        let program_id = Pubkey::new_unique();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let source = ScriptedProgramAccounts {
+            responses: RefCell::new(vec![
+                Err(rate_limited("Too many requests")),
+                Err(rate_limited("Too many requests, retry after 2s")),
+                Ok(vec![(first, gongde_account(program_id, 1))]),
+                Ok(vec![(second, gongde_account(program_id, 2))]),
+            ]),
+            queried: RefCell::new(Vec::new()),
+        };
+        let sleeps = Rc::new(RefCell::new(Vec::new()));
+        let recorded = sleeps.clone();
+        let mut throttle = Throttle::new(1000.0, 10).with_sleep(move |d| recorded.borrow_mut().push(d));
+
+        let accounts = scan_program_accounts(&source, &program_id, &[77, 151], &mut throttle).unwrap();
+
+        assert_eq!(accounts.iter().map(|(pubkey, _)| *pubkey).collect::<Vec<_>>(), vec![first, second]);
+        // 第一个大小被限流两次，退避后重试同一个查询，不会跳过
+        assert_eq!(*source.queried.borrow(), vec![77, 77, 77, 151]);
+        assert_eq!(*sleeps.borrow(), vec![Duration::from_millis(500), Duration::from_secs(2)]);
+    }
+
+    #[test]
+    fn test_other_errors_are_not_retried() {
+        let mut throttle = Throttle::new(1000.0, 10).with_sleep(|_| panic!("不应该等待"));
+        let mut calls = 0;
+        let result: ClientResult<u64> = throttle.run(|| {
+            calls += 1;
+            Err(ClientErrorKind::Custom("交易执行失败".to_string()).into())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    fn gongde_account(owner: Pubkey, value: u32) -> Account {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_gongde_value(&mut data, value).unwrap();
+        Account {
+            lamports: 1_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_mixed_accounts() {
+        let program_id = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+
+        let present = gongde_account(program_id, 42);
+        let mut closed = gongde_account(program_id, 3);
+        closed.lamports = 0;
+        let foreign = gongde_account(other_program, 1);
+        let mut corrupt = gongde_account(program_id, 0);
+        corrupt.data.truncate(2);
+
+        let fetched = [Some(present), None, Some(closed), Some(foreign), Some(corrupt)];
+        let results: Vec<_> = fetched
+            .iter()
+            .map(|account| decode_gongde_account(account.as_ref(), &program_id))
+            .collect();
+
+        // 结果与输入一一对应
+        assert_eq!(results.len(), fetched.len());
+        assert_eq!(results[0].as_ref().map(|account| account.value), Ok(42));
+        assert_eq!(results[1], Err(DecodeError::NotFound));
+        assert_eq!(results[2], Err(DecodeError::Closed));
+        assert_eq!(results[3], Err(DecodeError::WrongOwner(other_program)));
+        assert_eq!(results[4], Err(DecodeError::DataTooSmall(2)));
+    }
+
+    #[test]
+    fn test_progress_bar_boundaries() {
+        let cases = [
+            (0, "▱▱▱▱▱▱▱▱▱▱"),
+            (1, "▰▱▱▱▱▱▱▱▱▱"),
+            (10, "▰▱▱▱▱▱▱▱▱▱"),
+            (11, "▰▰▱▱▱▱▱▱▱▱"),
+            (100, "▰▰▱▱▱▱▱▱▱▱"),
+            (101, "▰▰▰▱▱▱▱▱▱▱"),
+            (1000, "▰▰▰▱▱▱▱▱▱▱"),
+            (1001, "▰▰▰▰▱▱▱▱▱▱"),
+            (10000, "▰▰▰▰▱▱▱▱▱▱"),
+            (10001, "▰▰▰▰▰▰▰▰▰▰"),
+            (u32::MAX, "▰▰▰▰▰▰▰▰▰▰"),
+        ];
+        for (value, bar) in cases {
+            assert_eq!(progress_bar(value), bar, "功德 {}", value);
+        }
+    }
+
+    #[test]
+    fn test_progress_bar_length_is_fixed() {
+        let thresholds = MeritLevel::ALL.iter().map(|level| level.threshold());
+        let values = thresholds
+            .flat_map(|threshold| [threshold.saturating_sub(1), threshold, threshold.saturating_add(1)])
+            .chain([u32::MAX - 1, u32::MAX]);
+        for value in values {
+            assert_eq!(progress_bar(value).chars().count(), PROGRESS_BAR_LEN, "功德 {}", value);
+        }
+    }
+
+    /// 模拟客户端：固定的模拟结果，并记录每次模拟的指令
+    struct MockSimulator {
+        result: fn() -> ClientResult<Result<u64, TransactionError>>,
+        calls: RefCell<Vec<Vec<Instruction>>>,
+    }
+
+    impl MockSimulator {
+        fn new(result: fn() -> ClientResult<Result<u64, TransactionError>>) -> Self {
+            Self { result, calls: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl ComputeUnitSimulator for MockSimulator {
+        fn simulate_compute_units(&self, instructions: &[Instruction], _payer: &Pubkey) -> ClientResult<Result<u64, TransactionError>> {
+            self.calls.borrow_mut().push(instructions.to_vec());
+            (self.result)()
+        }
+    }
+
+    #[test]
+    fn test_tuned_compute_unit_limit() {
+        assert_eq!(tuned_compute_unit_limit(1_000, 20), 1_200);
+        // 向上取整，宁多勿少
+        assert_eq!(tuned_compute_unit_limit(1_001, 20), 1_202);
+        assert_eq!(tuned_compute_unit_limit(1_000, 0), 1_000);
+        assert_eq!(tuned_compute_unit_limit(1_300_000, 20), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(tuned_compute_unit_limit(u64::MAX, u32::MAX), MAX_COMPUTE_UNIT_LIMIT);
+
+        assert_eq!(fallback_compute_unit_limit(2), 2 * DEFAULT_INSTRUCTION_COMPUTE_UNITS);
+        assert_eq!(fallback_compute_unit_limit(100), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn test_compute_unit_tuner_caches_per_instruction_kind() {
+        let program_id = Pubkey::new_unique();
+        let (gongde, payer, global) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let simulator = MockSimulator::new(|| Ok(Ok(10_000)));
+        let mut tuner = ComputeUnitTuner::new(25);
+
+        let increment = [instruction::increment(&program_id, &gongde, &payer, &global)];
+        let tuned = tuner.with_compute_unit_limit(&simulator, &increment, &payer);
+        assert_eq!(tuned[0], ComputeBudgetInstruction::set_compute_unit_limit(12_500));
+        assert_eq!(&tuned[1..], &increment);
+        // 模拟时申请最大上限
+        assert_eq!(simulator.calls.borrow()[0][0], ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT));
+        assert_eq!(simulator.calls.borrow()[0][0].program_id, solana_sdk::compute_budget::id());
+
+        // 同一种指令只模拟一次，账户不同也一样
+        let other = [instruction::increment(&program_id, &Pubkey::new_unique(), &payer, &global)];
+        assert_eq!(tuner.limit_for(&simulator, &other, &payer), 12_500);
+        assert_eq!(simulator.calls.borrow().len(), 1);
+
+        // 不同的指令重新模拟
+        let close = [instruction::close(&program_id, &gongde, &payer)];
+        tuner.limit_for(&simulator, &close, &payer);
+        assert_eq!(simulator.calls.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_compute_unit_tuner_falls_back_when_simulation_fails() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let instructions = [
+            instruction::protocol_version(&program_id),
+            instruction::protocol_version(&program_id),
+        ];
+
+        let failing = MockSimulator::new(|| Ok(Err(TransactionError::InstructionError(1, InstructionError::Custom(0)))));
+        let mut tuner = ComputeUnitTuner::default();
+        assert_eq!(tuner.limit_for(&failing, &instructions, &payer), 2 * DEFAULT_INSTRUCTION_COMPUTE_UNITS);
+        // 失败的结果不缓存，下次重新模拟
+        tuner.limit_for(&failing, &instructions, &payer);
+        assert_eq!(failing.calls.borrow().len(), 2);
+
+        let unreachable = MockSimulator::new(|| Err(ClientErrorKind::Custom("连接失败".to_string()).into()));
+        assert_eq!(tuner.limit_for(&unreachable, &instructions, &payer), 2 * DEFAULT_INSTRUCTION_COMPUTE_UNITS);
+    }
+
+    /// 模拟账本：按地址保存功德值，写入方可以是本客户端也可以是并发的其他用户
+    #[derive(Default)]
+    struct MockLedger {
+        values: RefCell<HashMap<Pubkey, u32>>,
+    }
+
+    impl MockLedger {
+        /// 按链上规则执行一次增加功德
+        fn increment(&self, gongde: &Pubkey, global: &Pubkey, amount: u32) {
+            let mut values = self.values.borrow_mut();
+            let (value, total) = predict_increment(values[gongde], values[global], amount);
+            values.insert(*gongde, value);
+            values.insert(*global, total);
+        }
+    }
+
+    impl AccountFetcher for MockLedger {
+        fn fetch_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+            Ok(self.values.borrow().get(pubkey).map(|value| {
+                let mut account = Account::new(1_000_000, GONGDE_ACCOUNT_SIZE, &Pubkey::new_unique());
+                write_gongde_value(&mut account.data, *value).unwrap();
+                account
+            }))
+        }
+    }
+
+    #[test]
+    fn test_verify_post_state_matches_prediction() {
+        let (gongde, global) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ledger = MockLedger::default();
+        ledger.values.borrow_mut().extend([(gongde, 7), (global, 40)]);
+
+        let (expected_value, expected_global) = predict_increment(7, 40, 3);
+        ledger.increment(&gongde, &global, 3);
+        let expected = [(gongde, "个人功德账户", expected_value), (global, "全局功德PDA账户", expected_global)];
+        assert_eq!(verify_post_state(&ledger, &Signature::default(), &expected).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_verify_post_state_reports_concurrent_writer() {
+        let (gongde, global) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let other_gongde = Pubkey::new_unique();
+        let ledger = MockLedger::default();
+        ledger.values.borrow_mut().extend([(gongde, 7), (global, 40), (other_gongde, 0)]);
+
+        // 本次交易确认后、重新读取前，另一个用户也增加了功德，全局功德比预测多1
+        let (expected_value, expected_global) = predict_increment(7, 40, 1);
+        ledger.increment(&gongde, &global, 1);
+        ledger.increment(&other_gongde, &global, 1);
+
+        let signature = Signature::new_unique();
+        let expected = [(gongde, "个人功德账户", expected_value), (global, "全局功德PDA账户", expected_global)];
+        let divergences = verify_post_state(&ledger, &signature, &expected).unwrap();
+        assert_eq!(
+            divergences,
+            vec![PostStateDivergence {
+                label: "全局功德PDA账户".to_string(),
+                pubkey: global,
+                expected: 41,
+                actual: Some(42),
+                signature,
+            }]
+        );
+        let report = divergences[0].to_string();
+        assert!(report.contains("预测 41，实际 42") && report.contains(&signature.to_string()));
+
+        // 账户被别人关闭时同样报告
+        ledger.values.borrow_mut().remove(&gongde);
+        let divergences = verify_post_state(&ledger, &signature, &expected[..1]).unwrap();
+        assert_eq!(divergences[0].actual, None);
+        assert!(divergences[0].to_string().contains("账户不存在"));
+    }
+
+    /// 示例交易日志：本合约增加功德（CPI调用系统程序收手续费），同一交易中另一个程序伪造了同格式的事件
+    fn sample_merit_logs(program_id: &Pubkey, gongde: &Pubkey, global: &Pubkey, forger: &Pubkey) -> Vec<String> {
+        let event = |account: &Pubkey, before: u32, after: u32| {
+            let event = MeritChangeEvent { account: *account, before, after };
+            format!(
+                "Program data: {} {}",
+                BASE64.encode(crate::utils::MERIT_CHANGE_EVENT_TAG),
+                BASE64.encode(event.pack())
+            )
+        };
+        let system = solana_system_interface::program::id();
+        vec![
+            format!("Program {} invoke [1]", program_id),
+            format!("Program {} invoke [2]", system),
+            format!("Program {} success", system),
+            event(gongde, 7, 8),
+            event(global, 40, 41),
+            "Program log: 个人功德: {}".to_string(),
+            "Program log: 0x0, 0x0, 0x0, 0x0, 0x8".to_string(),
+            format!("Program {} consumed 4000 of 200000 compute units", program_id),
+            format!("Program {} success", program_id),
+            format!("Program {} invoke [1]", program_id),
+            format!("Program {} invoke [2]", system),
+            format!("Program {} success", system),
+            event(gongde, 8, 9),
+            event(global, 41, 42),
+            format!("Program {} success", program_id),
+            format!("Program {} invoke [1]", forger),
+            event(gongde, 9, 1_000_000),
+            format!("Program {} success", forger),
+        ]
+    }
+
+    #[test]
+    fn test_merit_changes_from_meta() {
+        let program_id = Pubkey::new_unique();
+        let (gongde, global, forger) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let meta = |err: serde_json::Value| -> UiTransactionStatusMeta {
+            serde_json::from_value(serde_json::json!({
+                "err": err,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [1_000_000_000u64, 0],
+                "postBalances": [1_000_000_000u64 - 15000, 0],
+                "logMessages": sample_merit_logs(&program_id, &gongde, &global, &forger),
+            }))
+            .unwrap()
+        };
+
+        // 两次增加合并为一条；其他程序伪造的事件被忽略
+        assert_eq!(
+            merit_changes_from_meta(&meta(serde_json::Value::Null), &program_id),
+            vec![
+                MeritChangeEvent { account: gongde, before: 7, after: 9 },
+                MeritChangeEvent { account: global, before: 40, after: 42 },
+            ]
+        );
+        assert_eq!(merit_changes_from_meta(&meta(serde_json::Value::Null), &program_id)[0].delta(), 2);
+
+        // 失败的交易没有生效
+        let failed = meta(serde_json::json!({ "InstructionError": [1, { "Custom": 2 }] }));
+        assert!(merit_changes_from_meta(&failed, &program_id).is_empty());
+    }
+
+    #[test]
+    fn test_restored_value_from_meta() {
+        let program_id = Pubkey::new_unique();
+        let meta = |err: serde_json::Value, setter: &Pubkey, data: &[u8]| -> UiTransactionStatusMeta {
+            serde_json::from_value(serde_json::json!({
+                "err": err,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [1_000_000_000u64],
+                "postBalances": [1_000_000_000u64 - 5000],
+                "returnData": { "programId": setter.to_string(), "data": [BASE64.encode(data), "base64"] },
+            }))
+            .unwrap()
+        };
+
+        // 回滚到0和恢复值等于当前功德（功德不变）都返回写入后的值
+        let ok = serde_json::Value::Null;
+        assert_eq!(restored_value_from_meta(&meta(ok.clone(), &program_id, &0u32.to_le_bytes()), &program_id).unwrap(), 0);
+        assert_eq!(restored_value_from_meta(&meta(ok.clone(), &program_id, &9u32.to_le_bytes()), &program_id).unwrap(), 9);
+
+        // 其他程序设置的、长度不对的 return data 和失败的交易都不算
+        let other = Pubkey::new_unique();
+        assert!(restored_value_from_meta(&meta(ok.clone(), &other, &0u32.to_le_bytes()), &program_id).is_err());
+        assert!(restored_value_from_meta(&meta(ok, &program_id, &[0; 8]), &program_id).is_err());
+        let failed = meta(serde_json::json!({ "InstructionError": [0, { "Custom": 2 }] }), &program_id, &0u32.to_le_bytes());
+        assert!(restored_value_from_meta(&failed, &program_id).is_err());
+    }
+
+    #[test]
+    fn test_find_legacy_accounts_only_checks_owners() {
+        let program_id = Pubkey::new_unique();
+        let (legacy, current, closed, missing) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let address = |owner: &Pubkey| get_gongde_account_address(owner, &program_id).unwrap();
+        let mut legacy_account = Account::new(918_720, GONGDE_VALUE_SIZE, &program_id);
+        legacy_account.data.copy_from_slice(&7u32.to_le_bytes());
+        let (global_pda, _) = derive_global_gongde_pda_address(&program_id).unwrap();
+        let client = MockAccounts(HashMap::from([
+            (address(&legacy), legacy_account.clone()),
+            (address(&current), Account::new(981_360, GONGDE_ACCOUNT_SIZE, &program_id)),
+            (address(&closed), Account { lamports: 0, ..legacy_account.clone() }),
+            // 全局功德PDA和旧布局一样是4字节，但不是任何用户派生的地址
+            (global_pda, legacy_account),
+        ]));
+
+        let mut throttle = Throttle::new(1000.0, 10).with_sleep(|_| panic!("不应该等待"));
+        let candidates =
+            find_legacy_accounts(&client, &program_id, &[legacy, current, closed, missing], &mut throttle).unwrap();
+        assert_eq!(
+            candidates,
+            vec![MigrationCandidate { pubkey: address(&legacy), owner: legacy, value: 7, lamports: 918_720 }]
+        );
+    }
+
+    #[test]
+    fn test_preview_migration() {
+        let chain = MockChain::legacy(7);
+        let owner = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let account = chain.account.borrow().clone();
+        let preview = preview_migration(&owner, &pubkey, Some(account.clone()), &chain.program_id, 981_360).unwrap();
+        assert_eq!(preview.gap, LayoutGap { account_version: 1, current_version: GONGDE_LAYOUT_VERSION });
+        assert_eq!((preview.current_size, preview.new_size), (GONGDE_VALUE_SIZE, GONGDE_ACCOUNT_SIZE));
+        assert_eq!(preview.rent_deficit, 981_360 - 918_720);
+        assert_eq!((preview.candidate.owner, preview.candidate.value), (owner, 7));
+
+        // 不存在、不属于本程序的账户不迁移
+        assert!(preview_migration(&owner, &pubkey, None, &chain.program_id, 981_360).is_err());
+        assert!(preview_migration(&owner, &pubkey, Some(account), &Pubkey::new_unique(), 981_360).is_err());
+
+        // 已是当前布局的账户拒绝迁移
+        let mut current = Account::new(981_360, GONGDE_ACCOUNT_SIZE, &chain.program_id);
+        write_initialized_flags(&mut current.data, true).unwrap();
+        let err = preview_migration(&owner, &pubkey, Some(current), &chain.program_id, 981_360).unwrap_err();
+        assert!(err.contains("无需迁移"));
+    }
+
+    #[test]
+    fn test_migrate_single_verifies_value() {
+        let chain = MockChain::legacy(7);
+        let owner = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let preview = preview_migration(&owner, &pubkey, Some(chain.account.borrow().clone()), &chain.program_id, 981_360).unwrap();
+        migrate_single(&chain, &payer, &chain.program_id, &preview).unwrap();
+        assert_eq!(chain.migrations.borrow().len(), 1);
+        assert_eq!(chain.migrations.borrow()[0], instruction::migrate(&chain.program_id, &pubkey, &owner, &payer.pubkey()));
+
+        // 迁移说明之后功德值被其他人改动，迁移后校验失败
+        let chain = MockChain::legacy(7);
+        let preview = preview_migration(&owner, &pubkey, Some(chain.account.borrow().clone()), &chain.program_id, 981_360).unwrap();
+        write_gongde_value(&mut chain.account.borrow_mut().data, 8).unwrap();
+        let err = migrate_single(&chain, &payer, &chain.program_id, &preview).unwrap_err();
+        assert!(err.to_string().contains("迁移前 7，迁移后 8"));
+    }
+}
@@ -0,0 +1,218 @@
+// ========================================
+// 自定义错误 - 合约特有的错误码
+// ========================================
+
+use std::fmt;
+
+use crate::state::GongDeInstruction;
+
+#[cfg(feature = "program")]
+use solana_program::program_error::ProgramError;
+
+/// 合约自定义错误，链上以 `ProgramError::Custom(错误码)` 返回
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GongDeError {
+    /// 账户功德值不为0，不能按"空账户"关闭
+    NotEmpty = 0,
+    /// 当天转出的功德超过每日额度，客户端应匹配这个错误
+    DailyCapReached = 1,
+    /// 转出的功德超过账户现有功德
+    InsufficientMerit = 2,
+    /// 增加的功德数量为0或超出允许范围
+    InvalidAmount = 3,
+    /// 账户主人最近还有操作，受益人的等待期还没有结束
+    BeneficiaryClaimTooEarly = 4,
+    /// 取回的 lamports 超过已质押的数量
+    InsufficientStake = 5,
+    /// 不在彩票的投注时间内
+    LotteryClosed = 6,
+    /// 彩票还没有到结束slot，不能开奖
+    LotteryNotEnded = 7,
+    /// 彩票已经开过奖
+    LotteryAlreadySettled = 8,
+    /// 结束slot的哈希不在 SlotHashes 中，无法开奖；超过 SLOT_HASHES_MAX_ENTRIES 个slot之后开奖会作废本轮并退还票款
+    SlotHashUnavailable = 9,
+    /// 账户已归档，需要先取消归档
+    AccountArchived = 10,
+    /// 账户没有归档，不需要取消归档
+    AccountNotArchived = 11,
+    /// 客户端版本低于全局配置中的最低版本，需要升级客户端
+    ClientVersionTooOld = 12,
+    /// 全局配置开启了工作量证明，增加功德必须使用 IncrementWithProof
+    ProofOfWorkRequired = 13,
+    /// 工作量证明的哈希没有达到全局配置中的难度
+    InvalidProofOfWork = 14,
+    /// 头像emoji不是可以显示的Unicode字符
+    InvalidEmoji = 15,
+}
+
+impl GongDeError {
+    /// 全部自定义错误，按错误码顺序排列
+    pub const ALL: [GongDeError; 16] = [
+        GongDeError::NotEmpty,
+        GongDeError::DailyCapReached,
+        GongDeError::InsufficientMerit,
+        GongDeError::InvalidAmount,
+        GongDeError::BeneficiaryClaimTooEarly,
+        GongDeError::InsufficientStake,
+        GongDeError::LotteryClosed,
+        GongDeError::LotteryNotEnded,
+        GongDeError::LotteryAlreadySettled,
+        GongDeError::SlotHashUnavailable,
+        GongDeError::AccountArchived,
+        GongDeError::AccountNotArchived,
+        GongDeError::ClientVersionTooOld,
+        GongDeError::ProofOfWorkRequired,
+        GongDeError::InvalidProofOfWork,
+        GongDeError::InvalidEmoji,
+    ];
+
+    /// 根据链上返回的错误码查找自定义错误
+    ///
+    /// # 参数
+    /// * `code` - `ProgramError::Custom` 中的错误码
+    ///
+    /// # 返回
+    /// * `Option<GongDeError>` - 不是本合约的错误码时返回None
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|error| *error as u32 == code)
+    }
+
+    /// 给用户看的错误说明
+    pub fn message(self) -> &'static str {
+        match self {
+            GongDeError::NotEmpty => "账户功德值不为0，不能按空账户关闭",
+            GongDeError::DailyCapReached => "当天转出的功德超过每日额度",
+            GongDeError::InsufficientMerit => "转出的功德超过账户现有功德",
+            GongDeError::InvalidAmount => "功德数量为0或超出允许范围",
+            GongDeError::BeneficiaryClaimTooEarly => "账户主人最近还有操作，受益人的等待期还没有结束",
+            GongDeError::InsufficientStake => "取回的 lamports 超过已质押的数量",
+            GongDeError::LotteryClosed => "不在彩票的投注时间内",
+            GongDeError::LotteryNotEnded => "彩票还没有到结束slot",
+            GongDeError::LotteryAlreadySettled => "彩票已经开过奖",
+            GongDeError::SlotHashUnavailable => "结束slot的哈希不在 SlotHashes 中",
+            GongDeError::AccountArchived => "账户已归档",
+            GongDeError::AccountNotArchived => "账户没有归档",
+            GongDeError::ClientVersionTooOld => "客户端版本低于合约要求的最低版本",
+            GongDeError::ProofOfWorkRequired => "合约要求增加功德时附带工作量证明",
+            GongDeError::InvalidProofOfWork => "工作量证明没有达到要求的难度",
+            GongDeError::InvalidEmoji => "头像emoji不是可以显示的Unicode字符",
+        }
+    }
+
+    /// 建议的下一步操作
+    ///
+    /// # 返回
+    /// * `Option<&'static str>` - 没有可以给出的建议时返回None
+    pub fn suggestion(self) -> Option<&'static str> {
+        match self {
+            GongDeError::NotEmpty => Some("确认要放弃账户中的功德后，改用普通的关闭指令（Close）"),
+            GongDeError::DailyCapReached => Some("功德已达今日上限，请明天再来"),
+            GongDeError::InsufficientMerit => Some("先查询账户功德，减少转出数量后重试"),
+            GongDeError::InvalidAmount => Some("使用大于0且不超过上限的数量"),
+            GongDeError::BeneficiaryClaimTooEarly => Some("等待期结束后再领取"),
+            GongDeError::InsufficientStake => Some("先查询已质押的数量，减少取回数量后重试"),
+            GongDeError::LotteryClosed => Some("查看彩票的投注时间，在结束slot之前投注"),
+            GongDeError::LotteryNotEnded => Some("等到结束slot之后再开奖"),
+            GongDeError::AccountArchived => Some("先取消归档（Unarchive）再操作"),
+            GongDeError::ClientVersionTooOld => Some("请升级客户端后重试"),
+            GongDeError::ProofOfWorkRequired => Some("升级客户端，由客户端自动计算工作量证明后重试"),
+            GongDeError::InvalidProofOfWork => Some("重新读取账户中上次增加功德的slot，重新计算 nonce 后重试"),
+            GongDeError::InvalidEmoji => Some("使用一个可见的emoji字符，或者用0清除头像"),
+            GongDeError::SlotHashUnavailable => Some("稍后重试；结束slot之后超过512个slot再开奖会作废本轮并退还票款"),
+            GongDeError::LotteryAlreadySettled | GongDeError::AccountNotArchived => None,
+        }
+    }
+}
+
+impl fmt::Display for GongDeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+#[cfg(feature = "program")]
+impl From<GongDeError> for ProgramError {
+    fn from(e: GongDeError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+/// 纯数据逻辑（state 模块）的错误，不依赖 solana-program
+/// 链上转换为同名的 ProgramError，合约自定义错误转换为 `ProgramError::Custom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// 账户数据长度不足
+    AccountDataTooSmall,
+    /// 账户已经初始化过
+    AccountAlreadyInitialized,
+    /// 指令数据无效
+    InvalidInstructionData,
+    /// 指令数据为空，缺少指令码
+    EmptyInstructionData,
+    /// 不认识的指令码
+    UnknownInstruction(u8),
+    /// 指令码之后的参数不完整，长度都包含1字节指令码
+    TruncatedInstructionData { instruction: GongDeInstruction, expected: usize, actual: usize },
+    /// 合约自定义错误
+    Custom(GongDeError),
+}
+
+impl From<GongDeError> for StateError {
+    fn from(e: GongDeError) -> Self {
+        StateError::Custom(e)
+    }
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::AccountDataTooSmall => f.write_str("账户数据长度不足"),
+            StateError::AccountAlreadyInitialized => f.write_str("账户已经初始化过"),
+            StateError::InvalidInstructionData => f.write_str("指令数据无效"),
+            StateError::EmptyInstructionData => f.write_str("指令数据为空，缺少指令码"),
+            StateError::UnknownInstruction(tag) => write!(f, "不认识的指令码 {}", tag),
+            StateError::TruncatedInstructionData { instruction, expected, actual } => {
+                write!(f, "{} 指令的参数不完整，数据应为 {} 字节，实际 {} 字节", instruction, expected, actual)
+            }
+            StateError::Custom(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+#[cfg(feature = "program")]
+impl From<StateError> for ProgramError {
+    fn from(e: StateError) -> Self {
+        match e {
+            StateError::AccountDataTooSmall => ProgramError::AccountDataTooSmall,
+            StateError::AccountAlreadyInitialized => ProgramError::AccountAlreadyInitialized,
+            StateError::InvalidInstructionData
+            | StateError::EmptyInstructionData
+            | StateError::UnknownInstruction(_)
+            | StateError::TruncatedInstructionData { .. } => ProgramError::InvalidInstructionData,
+            StateError::Custom(e) => e.into(),
+        }
+    }
+}
+
+/// 名称解析失败：输入的指令名或功德等级不认识
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNameError {
+    /// 解析的类型，例如"指令"
+    pub kind: &'static str,
+    /// 用户输入的名称
+    pub input: String,
+    /// 可以使用的名称
+    pub valid: Vec<&'static str>,
+}
+
+impl fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "未知的{} \"{}\"，可选值: {}", self.kind, self.input, self.valid.join(", "))
+    }
+}
+
+impl std::error::Error for ParseNameError {}
@@ -0,0 +1,1752 @@
+// ========================================
+// 指令构建与校验 - 客户端在发送交易前检查账户列表
+// 缺少签名标记、账户顺序写错这类问题在本地就能发现，不用等到链上失败白白支付手续费
+// ========================================
+
+use std::fmt;
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_system_interface::program as system_program;
+
+use crate::{
+    error::{GongDeError, StateError},
+    utils::{
+        derive_accrual_state_address, derive_campaign_address, derive_config_address, derive_endorsement_address,
+        derive_lottery_address, derive_shared_ledger_address, derive_transfer_state_address, parse_create_campaign_args,
+        parse_create_lottery_args, parse_enter_lottery_args, parse_increment_by_args, parse_initialize_args,
+        parse_increment_with_proof_args, parse_restore_args, parse_set_beneficiary_args,
+        parse_set_emoji_args, parse_set_minimum_client_version_args, parse_set_pow_difficulty_args, parse_stake_args,
+        parse_transfer_args, split_client_version, stamp_client_version, validate_increment_amount,
+        validate_restore_value, CreateCampaignArgs, CreateLotteryArgs, GongDeInstruction, CLIENT_VERSION,
+        MAX_ENDORSE_MANY_TARGETS, MAX_LOTTERY_REFUND_ACCOUNTS, MAX_REPORT_ACCOUNTS,
+    },
+};
+
+/// 指令中单个账户的要求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountSpec {
+    /// 账户用途，出现在错误信息中
+    pub name: &'static str,
+    /// 是否必须签名
+    pub is_signer: bool,
+    /// 是否必须可写
+    pub is_writable: bool,
+    /// 固定地址的账户（例如系统程序），用于发现账户顺序错误
+    pub address: Option<Pubkey>,
+    /// 账户在指令中的作用，给钱包和浏览器展示
+    pub role: &'static str,
+}
+
+impl AccountSpec {
+    const fn new(name: &'static str, is_signer: bool, is_writable: bool, role: &'static str) -> Self {
+        Self { name, is_signer, is_writable, address: None, role }
+    }
+}
+
+/// 系统程序账户
+const SYSTEM_PROGRAM: AccountSpec = AccountSpec {
+    name: "system_program",
+    is_signer: false,
+    is_writable: false,
+    address: Some(system_program::ID),
+    role: "系统程序",
+};
+
+/// Increment / IncrementBy / IncrementWithProof 的账户列表
+const INCREMENT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "被增加功德的账户"),
+    AccountSpec::new("payer", true, true, "支付手续费的用户"),
+    AccountSpec::new("global_pda", false, true, "全局功德PDA账户，收取手续费"),
+    SYSTEM_PROGRAM,
+];
+
+/// Increment / IncrementBy / IncrementWithProof 可选的账户，放在系统程序之后：配捐活动账户，以及账户不存在时用来创建的账户主人
+const INCREMENT_OPTIONAL_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("campaign", false, true, "配捐活动账户"),
+    AccountSpec::new("owner", true, false, "账户不存在时用来创建的账户主人"),
+];
+
+/// Close / CloseIfEmpty 的账户列表，租金退还给账户主人
+const CLOSE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要关闭的功德账户"),
+    AccountSpec::new("owner", true, true, "账户主人，收回租金"),
+];
+
+/// Initialize 的账户列表
+const INITIALIZE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要初始化的功德账户"),
+    AccountSpec::new("owner", true, false, "账户主人"),
+];
+
+/// TransferMerit 的账户列表
+const TRANSFER_MERIT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("sender_gongde", false, true, "转出方的功德账户"),
+    AccountSpec::new("sender", true, true, "转出方，支付转账记录的租金"),
+    AccountSpec::new("recipient_gongde", false, true, "接收方的功德账户"),
+    AccountSpec::new("transfer_state", false, true, "防止重复转账的转账记录账户"),
+    SYSTEM_PROGRAM,
+];
+
+/// Migrate 的账户列表，最后的用户不需要签名，合约用它确认功德账户地址由这个用户派生
+const MIGRATE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要升级布局的功德账户"),
+    AccountSpec::new("payer", true, true, "补足新增租金的用户"),
+    SYSTEM_PROGRAM,
+    AccountSpec::new("owner", false, false, "派生出功德账户地址的用户，不需要签名"),
+];
+
+/// RestoreTo 的账户列表，只修改功德值，主人不需要可写
+const RESTORE_TO_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要恢复功德值的账户"),
+    AccountSpec::new("owner", true, false, "账户主人"),
+];
+
+/// CreateCampaign 的账户列表
+const CREATE_CAMPAIGN_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("organizer_gongde", false, true, "发起人的功德账户，扣除配捐预算"),
+    AccountSpec::new("organizer", true, true, "活动发起人，支付活动账户的租金"),
+    AccountSpec::new("campaign", false, true, "新建的配捐活动账户"),
+    SYSTEM_PROGRAM,
+];
+
+/// SharedIncrement 的账户列表
+const SHARED_INCREMENT_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("shared_ledger", false, true, "共享账本"),
+    AccountSpec::new("user", true, true, "增加功德并支付手续费的用户"),
+    AccountSpec::new("global_pda", false, true, "全局功德PDA账户，收取手续费"),
+    SYSTEM_PROGRAM,
+];
+
+/// SetBeneficiary 的账户列表，只修改受益人设置，主人不需要可写
+const SET_BENEFICIARY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要指定受益人的功德账户"),
+    AccountSpec::new("authority", true, false, "账户主人"),
+];
+
+/// SetEmoji 的账户列表，只修改头像，主人不需要可写
+const SET_EMOJI_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要设置头像的功德账户"),
+    AccountSpec::new("authority", true, false, "账户主人"),
+];
+
+/// ClaimAsBeneficiary 的账户列表，合并时租金退还给受益人
+const CLAIM_AS_BENEFICIARY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "被接管的功德账户"),
+    AccountSpec::new("beneficiary", true, true, "受益人，合并时收回租金"),
+];
+
+/// ClaimAsBeneficiary 可选的账户：受益人自己的功德账户，传入时把功德合并过去并关闭被接管的账户
+const CLAIM_AS_BENEFICIARY_OPTIONAL_ACCOUNTS: &[AccountSpec] =
+    &[AccountSpec::new("beneficiary_gongde", false, true, "受益人自己的功德账户，功德合并到这里")];
+
+/// Endorse 的账户列表，背书人的功德账户只用来计算权重，不需要可写
+const ENDORSE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("endorsee_gongde", false, true, "被背书的功德账户"),
+    AccountSpec::new("endorser", true, true, "背书人，支付背书记录的租金"),
+    AccountSpec::new("endorser_gongde", false, false, "背书人的功德账户，背书权重的依据"),
+    AccountSpec::new("endorsement", false, true, "新建的背书记录账户"),
+    SYSTEM_PROGRAM,
+];
+
+/// EndorseMany 每个目标的功德账户
+const ENDORSED_GONGDE: AccountSpec = AccountSpec::new("endorsee_gongde", false, true, "被背书的功德账户");
+
+/// EndorseMany 每个目标的背书记录，已经背过书的目标不会重新创建
+const ENDORSED_RECORD: AccountSpec = AccountSpec::new("endorsement", false, true, "背书记录账户，已存在时跳过这个目标");
+
+/// EndorseMany 的账户列表，之后每个目标依次是功德账户和背书记录，至少一个目标
+const ENDORSE_MANY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("endorser", true, true, "背书人，支付背书记录的租金"),
+    AccountSpec::new("endorser_gongde", false, false, "背书人的功德账户，背书权重的依据"),
+    SYSTEM_PROGRAM,
+    ENDORSED_GONGDE,
+    ENDORSED_RECORD,
+];
+
+/// EndorseMany 可选的账户：更多目标，目标总数不超过 MAX_ENDORSE_MANY_TARGETS
+const ENDORSE_MANY_OPTIONAL_ACCOUNTS: &[AccountSpec] = &endorse_many_optional_accounts();
+
+const fn endorse_many_optional_accounts() -> [AccountSpec; 2 * (MAX_ENDORSE_MANY_TARGETS - 1)] {
+    let mut accounts = [ENDORSED_GONGDE; 2 * (MAX_ENDORSE_MANY_TARGETS - 1)];
+    let mut index = 1;
+    while index < accounts.len() {
+        accounts[index] = ENDORSED_RECORD;
+        index += 2;
+    }
+    accounts
+}
+
+/// Accrue 的账户列表，挂机功德状态在第一次结算时由账户主人支付租金创建
+const ACCRUE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "积累挂机功德的账户"),
+    AccountSpec::new("authority", true, true, "账户主人，支付挂机状态的租金"),
+    AccountSpec::new("accrual_state", false, true, "记录上次结算时间的挂机状态账户"),
+    SYSTEM_PROGRAM,
+];
+
+/// Stake 的账户列表，质押的 SOL 从账户主人转入自己的功德账户
+const STAKE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "存放质押的功德账户"),
+    AccountSpec::new("authority", true, true, "账户主人，支付质押的 SOL"),
+    SYSTEM_PROGRAM,
+];
+
+/// ClaimAccrued 的账户列表，只修改功德账户，主人不需要可写
+const CLAIM_ACCRUED_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "结算质押功德的账户"),
+    AccountSpec::new("authority", true, false, "账户主人"),
+];
+
+/// Unstake 的账户列表，取回的 SOL 直接从功德账户转给账户主人
+const UNSTAKE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "存放质押的功德账户"),
+    AccountSpec::new("authority", true, true, "账户主人，收回质押的 SOL"),
+];
+
+/// CreateLottery 的账户列表，彩票轮次由管理员支付租金创建
+const CREATE_LOTTERY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("lottery", false, true, "新建的彩票轮次账户"),
+    AccountSpec::new("admin", true, true, "管理员，支付彩票轮次的租金"),
+    SYSTEM_PROGRAM,
+];
+
+/// EnterLottery 的账户列表，彩票轮次扩容存放投注条目，新增的租金由投注人支付
+const ENTER_LOTTERY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "扣除票款的功德账户"),
+    AccountSpec::new("player", true, true, "投注人，支付投注条目的租金"),
+    AccountSpec::new("lottery", false, true, "记录投注的彩票轮次账户"),
+    SYSTEM_PROGRAM,
+];
+
+/// SettleLottery 的账户列表，任何人都可以开奖，不需要签名
+const SETTLE_LOTTERY_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("lottery", false, true, "要开奖的彩票轮次账户"),
+    AccountSpec {
+        name: "slot_hashes",
+        is_signer: false,
+        is_writable: false,
+        address: Some(sysvar::slot_hashes::ID),
+        role: "SlotHashes 系统变量，结束slot的哈希决定中奖票号",
+    },
+];
+
+/// SettleLottery 可选的账户：有人投注时必须传入中奖者的功德账户；
+/// 结束slot的哈希过期、本轮作废时改为传入要退款的投注功德账户，最多 MAX_LOTTERY_REFUND_ACCOUNTS 个
+const SETTLE_LOTTERY_OPTIONAL_ACCOUNTS: &[AccountSpec] = &[LOTTERY_GONGDE; MAX_LOTTERY_REFUND_ACCOUNTS];
+
+const LOTTERY_GONGDE: AccountSpec =
+    AccountSpec::new("gongde", false, true, "中奖者的功德账户，奖池加到这里；本轮作废时为要退还票款的投注功德账户");
+
+/// Archive / Unarchive 的账户列表，只修改标志位，主人不需要可写
+const ARCHIVE_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde", false, true, "要归档或取消归档的功德账户"),
+    AccountSpec::new("authority", true, false, "账户主人"),
+];
+
+/// SwapCounts 的账户列表，两个功德账户各自的主人都要签名
+const SWAP_COUNTS_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("gongde_a", false, true, "第一个功德账户"),
+    AccountSpec::new("authority_a", true, false, "第一个功德账户的主人"),
+    AccountSpec::new("gongde_b", false, true, "第二个功德账户"),
+    AccountSpec::new("authority_b", true, false, "第二个功德账户的主人"),
+];
+
+/// Report 要汇总的功德账户，只读
+const REPORTED_GONGDE: AccountSpec = AccountSpec::new("gongde", false, false, "要汇总的功德账户");
+
+/// Report 的账户列表，至少汇总一个功德账户
+const REPORT_ACCOUNTS: &[AccountSpec] = &[REPORTED_GONGDE];
+
+/// Report 可选的账户：更多要汇总的功德账户，总数不超过 MAX_REPORT_ACCOUNTS
+const REPORT_OPTIONAL_ACCOUNTS: &[AccountSpec] = &[REPORTED_GONGDE; MAX_REPORT_ACCOUNTS - 1];
+
+/// SetMinimumClientVersion / SetPowDifficulty 的账户列表，全局配置第一次设置时由创作者支付租金创建
+/// 全局功德PDA中同步保存一份配置，旧编码的增加功德指令不附带全局配置，从这里读取
+const SET_CONFIG_ACCOUNTS: &[AccountSpec] = &[
+    AccountSpec::new("config", false, true, "全局配置PDA账户"),
+    AccountSpec::new("global_pda", false, true, "全局功德PDA账户，保存全局配置的副本"),
+    AccountSpec::new("creator", true, true, "创作者，第一次设置时支付全局配置的租金"),
+    SYSTEM_PROGRAM,
+];
+
+/// 指令参数的类型，全部按小端序定长编码，名称与 Anchor IDL 的基础类型一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    /// 1字节，0=false，1=true
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I64,
+    /// 32字节公钥
+    Pubkey,
+}
+
+impl ArgType {
+    /// IDL中的类型名称
+    pub const fn idl_name(self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::I64 => "i64",
+            Self::Pubkey => "pubkey",
+        }
+    }
+
+    /// 编码后的字节数
+    pub const fn size(self) -> usize {
+        match self {
+            Self::Bool | Self::U8 => 1,
+            Self::U16 => 2,
+            Self::U32 => 4,
+            Self::U64 | Self::I64 => 8,
+            Self::Pubkey => 32,
+        }
+    }
+
+    /// 把编码后的参数解码为可读字符串
+    ///
+    /// # 参数
+    /// * `bytes` - 参数的字节，长度必须等于 `size()`
+    ///
+    /// # 返回
+    /// * `Option<String>` - 长度不对或布尔值不是0/1时返回None
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        if bytes.len() != self.size() {
+            return None;
+        }
+        Some(match self {
+            Self::Bool => match bytes[0] {
+                0 => "false".to_string(),
+                1 => "true".to_string(),
+                _ => return None,
+            },
+            Self::U8 => bytes[0].to_string(),
+            Self::U16 => u16::from_le_bytes(bytes.try_into().ok()?).to_string(),
+            Self::U32 => u32::from_le_bytes(bytes.try_into().ok()?).to_string(),
+            Self::U64 => u64::from_le_bytes(bytes.try_into().ok()?).to_string(),
+            Self::I64 => i64::from_le_bytes(bytes.try_into().ok()?).to_string(),
+            Self::Pubkey => Pubkey::try_from(bytes).ok()?.to_string(),
+        })
+    }
+}
+
+/// 指令数据中的单个参数，紧跟在1字节指令码之后依次排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub ty: ArgType,
+}
+
+impl ArgSpec {
+    const fn new(name: &'static str, ty: ArgType) -> Self {
+        Self { name, ty }
+    }
+}
+
+const INITIALIZE_ARGS: &[ArgSpec] = &[ArgSpec::new("is_public", ArgType::Bool)];
+const TRANSFER_MERIT_ARGS: &[ArgSpec] = &[ArgSpec::new("amount", ArgType::U32)];
+/// IncrementBy / SharedIncrement 的参数
+const AMOUNT_U64_ARGS: &[ArgSpec] = &[ArgSpec::new("amount", ArgType::U64)];
+const RESTORE_TO_ARGS: &[ArgSpec] = &[ArgSpec::new("value", ArgType::U64), ArgSpec::new("checkpoint", ArgType::I64)];
+const CREATE_CAMPAIGN_ARGS: &[ArgSpec] = &[
+    ArgSpec::new("campaign_id", ArgType::U64),
+    ArgSpec::new("start", ArgType::I64),
+    ArgSpec::new("end", ArgType::I64),
+    ArgSpec::new("multiplier", ArgType::U32),
+    ArgSpec::new("budget", ArgType::U32),
+];
+const LAMPORTS_ARGS: &[ArgSpec] = &[ArgSpec::new("lamports", ArgType::U64)];
+const CREATE_LOTTERY_ARGS: &[ArgSpec] = &[
+    ArgSpec::new("round_id", ArgType::U64),
+    ArgSpec::new("start_slot", ArgType::U64),
+    ArgSpec::new("end_slot", ArgType::U64),
+    ArgSpec::new("ticket_price", ArgType::U32),
+];
+const ENTER_LOTTERY_ARGS: &[ArgSpec] = &[ArgSpec::new("tickets", ArgType::U16)];
+const SET_MINIMUM_CLIENT_VERSION_ARGS: &[ArgSpec] = &[ArgSpec::new("minimum_client_version", ArgType::U8)];
+const SET_POW_DIFFICULTY_ARGS: &[ArgSpec] = &[ArgSpec::new("difficulty", ArgType::U8)];
+const INCREMENT_WITH_PROOF_ARGS: &[ArgSpec] = &[ArgSpec::new("nonce", ArgType::U64)];
+const SET_BENEFICIARY_ARGS: &[ArgSpec] =
+    &[ArgSpec::new("beneficiary", ArgType::Pubkey), ArgSpec::new("timeout_days", ArgType::U16)];
+const SET_EMOJI_ARGS: &[ArgSpec] = &[ArgSpec::new("codepoint", ArgType::U32)];
+
+/// 某种指令期望的账户列表，顺序与合约中 next_account_info 的读取顺序一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionSpec {
+    pub instruction: GongDeInstruction,
+    pub accounts: &'static [AccountSpec],
+    /// 排在必需账户之后、可以省略的账户；每个都可以单独省略，按签名标记对应
+    pub optional_accounts: &'static [AccountSpec],
+    /// 指令码之后的参数，顺序与合约解析的顺序一致
+    pub args: &'static [ArgSpec],
+}
+
+impl InstructionSpec {
+    /// 指令数据的总长度：1字节指令码加全部参数
+    pub fn data_len(&self) -> usize {
+        1 + self.args.iter().map(|arg| arg.ty.size()).sum::<usize>()
+    }
+
+    /// 全局功德PDA在必需账户中的位置，旧编码的指令不附带全局配置，合约从全局功德PDA读取配置的副本
+    ///
+    /// # 返回
+    /// * `Option<usize>` - 不需要全局功德PDA的指令返回None
+    pub fn global_pda_index(&self) -> Option<usize> {
+        self.accounts.iter().position(|account| account.name == "global_pda")
+    }
+
+    /// 获取指定指令的账户要求
+    ///
+    /// # 参数
+    /// * `instruction` - 指令类型
+    ///
+    /// # 返回
+    /// * `InstructionSpec` - 该指令期望的账户列表
+    pub const fn of(instruction: GongDeInstruction) -> Self {
+        let accounts = match instruction {
+            GongDeInstruction::Increment | GongDeInstruction::IncrementBy | GongDeInstruction::IncrementWithProof => {
+                INCREMENT_ACCOUNTS
+            }
+            GongDeInstruction::Close | GongDeInstruction::CloseIfEmpty => CLOSE_ACCOUNTS,
+            GongDeInstruction::Initialize => INITIALIZE_ACCOUNTS,
+            GongDeInstruction::ProtocolVersion => &[],
+            GongDeInstruction::TransferMerit => TRANSFER_MERIT_ACCOUNTS,
+            GongDeInstruction::Migrate => MIGRATE_ACCOUNTS,
+            GongDeInstruction::RestoreTo => RESTORE_TO_ACCOUNTS,
+            GongDeInstruction::CreateCampaign => CREATE_CAMPAIGN_ACCOUNTS,
+            GongDeInstruction::SharedIncrement => SHARED_INCREMENT_ACCOUNTS,
+            GongDeInstruction::SetBeneficiary => SET_BENEFICIARY_ACCOUNTS,
+            GongDeInstruction::ClaimAsBeneficiary => CLAIM_AS_BENEFICIARY_ACCOUNTS,
+            GongDeInstruction::Endorse => ENDORSE_ACCOUNTS,
+            GongDeInstruction::Accrue => ACCRUE_ACCOUNTS,
+            GongDeInstruction::Stake => STAKE_ACCOUNTS,
+            GongDeInstruction::ClaimAccrued => CLAIM_ACCRUED_ACCOUNTS,
+            GongDeInstruction::Unstake => UNSTAKE_ACCOUNTS,
+            GongDeInstruction::CreateLottery => CREATE_LOTTERY_ACCOUNTS,
+            GongDeInstruction::EnterLottery => ENTER_LOTTERY_ACCOUNTS,
+            GongDeInstruction::SettleLottery => SETTLE_LOTTERY_ACCOUNTS,
+            GongDeInstruction::Archive | GongDeInstruction::Unarchive => ARCHIVE_ACCOUNTS,
+            GongDeInstruction::SetMinimumClientVersion | GongDeInstruction::SetPowDifficulty => SET_CONFIG_ACCOUNTS,
+            GongDeInstruction::SwapCounts => SWAP_COUNTS_ACCOUNTS,
+            GongDeInstruction::Report => REPORT_ACCOUNTS,
+            GongDeInstruction::EndorseMany => ENDORSE_MANY_ACCOUNTS,
+            GongDeInstruction::SetEmoji => SET_EMOJI_ACCOUNTS,
+        };
+        let optional_accounts = match instruction {
+            GongDeInstruction::Increment | GongDeInstruction::IncrementBy | GongDeInstruction::IncrementWithProof => {
+                INCREMENT_OPTIONAL_ACCOUNTS
+            }
+            GongDeInstruction::ClaimAsBeneficiary => CLAIM_AS_BENEFICIARY_OPTIONAL_ACCOUNTS,
+            GongDeInstruction::SettleLottery => SETTLE_LOTTERY_OPTIONAL_ACCOUNTS,
+            GongDeInstruction::Report => REPORT_OPTIONAL_ACCOUNTS,
+            GongDeInstruction::EndorseMany => ENDORSE_MANY_OPTIONAL_ACCOUNTS,
+            _ => &[],
+        };
+        let args: &'static [ArgSpec] = match instruction {
+            GongDeInstruction::Initialize => INITIALIZE_ARGS,
+            GongDeInstruction::TransferMerit => TRANSFER_MERIT_ARGS,
+            GongDeInstruction::IncrementBy | GongDeInstruction::SharedIncrement => AMOUNT_U64_ARGS,
+            GongDeInstruction::RestoreTo => RESTORE_TO_ARGS,
+            GongDeInstruction::CreateCampaign => CREATE_CAMPAIGN_ARGS,
+            GongDeInstruction::SetBeneficiary => SET_BENEFICIARY_ARGS,
+            GongDeInstruction::Stake | GongDeInstruction::Unstake => LAMPORTS_ARGS,
+            GongDeInstruction::CreateLottery => CREATE_LOTTERY_ARGS,
+            GongDeInstruction::EnterLottery => ENTER_LOTTERY_ARGS,
+            GongDeInstruction::SetMinimumClientVersion => SET_MINIMUM_CLIENT_VERSION_ARGS,
+            GongDeInstruction::SetPowDifficulty => SET_POW_DIFFICULTY_ARGS,
+            GongDeInstruction::IncrementWithProof => INCREMENT_WITH_PROOF_ARGS,
+            GongDeInstruction::SetEmoji => SET_EMOJI_ARGS,
+            GongDeInstruction::Increment
+            | GongDeInstruction::Close
+            | GongDeInstruction::CloseIfEmpty
+            | GongDeInstruction::ProtocolVersion
+            | GongDeInstruction::Migrate
+            | GongDeInstruction::ClaimAsBeneficiary
+            | GongDeInstruction::Endorse
+            | GongDeInstruction::Accrue
+            | GongDeInstruction::ClaimAccrued
+            | GongDeInstruction::SettleLottery
+            | GongDeInstruction::Archive
+            | GongDeInstruction::Unarchive
+            | GongDeInstruction::SwapCounts
+            | GongDeInstruction::Report
+            | GongDeInstruction::EndorseMany => &[],
+        };
+        Self { instruction, accounts, optional_accounts, args }
+    }
+
+    /// 指令的用途说明，给钱包和浏览器展示
+    pub const fn summary(&self) -> &'static str {
+        match self.instruction {
+            GongDeInstruction::Increment => "功德+1",
+            GongDeInstruction::Close => "关闭功德账户并收回租金",
+            GongDeInstruction::Initialize => "初始化功德账户",
+            GongDeInstruction::CloseIfEmpty => "功德为0时关闭功德账户并收回租金",
+            GongDeInstruction::ProtocolVersion => "查询合约协议版本",
+            GongDeInstruction::TransferMerit => "把功德转给别人",
+            GongDeInstruction::IncrementBy => "一次增加多点功德",
+            GongDeInstruction::Migrate => "把功德账户升级到最新布局",
+            GongDeInstruction::RestoreTo => "把功德值恢复到检查点",
+            GongDeInstruction::CreateCampaign => "创建配捐活动",
+            GongDeInstruction::SharedIncrement => "在共享账本中增加功德",
+            GongDeInstruction::SetBeneficiary => "指定或清除受益人",
+            GongDeInstruction::ClaimAsBeneficiary => "受益人接管长期不活跃的功德账户",
+            GongDeInstruction::Endorse => "给别人的功德账户背书，功德越多背书权重越高",
+            GongDeInstruction::Accrue => "结算按时间积累的挂机功德",
+            GongDeInstruction::Stake => "把 SOL 质押到功德账户",
+            GongDeInstruction::ClaimAccrued => "结算质押积累的功德",
+            GongDeInstruction::Unstake => "取回质押的 SOL",
+            GongDeInstruction::CreateLottery => "创建一轮彩票",
+            GongDeInstruction::EnterLottery => "花功德买彩票",
+            GongDeInstruction::SettleLottery => "彩票开奖，奖池加给中奖者；结束slot的哈希过期时本轮作废，退还票款",
+            GongDeInstruction::Archive => "归档功德账户，保留功德但暂停使用",
+            GongDeInstruction::Unarchive => "取消归档，恢复使用功德账户",
+            GongDeInstruction::SetMinimumClientVersion => "设置最低客户端版本，更旧的客户端需要升级",
+            GongDeInstruction::SwapCounts => "双方都签名后交换两个功德账户的功德",
+            GongDeInstruction::Report => "只读汇总多个功德账户的功德，返回合计和每个账户的功德",
+            GongDeInstruction::EndorseMany => "一次给多个功德账户背书，已经背过书的跳过",
+            GongDeInstruction::SetPowDifficulty => "设置增加功德的工作量证明难度，0表示关闭",
+            GongDeInstruction::IncrementWithProof => "附带工作量证明的功德+1",
+            GongDeInstruction::SetEmoji => "设置或清除头像emoji",
+        }
+    }
+}
+
+/// 解码后的单个参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedArg {
+    pub name: &'static str,
+    pub value: String,
+}
+
+/// 指令的可读描述，钱包和浏览器插件用它代替十六进制的指令数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionDescription {
+    /// 指令的账户和参数表
+    pub spec: InstructionSpec,
+    /// 按顺序解码出的参数
+    pub args: Vec<DecodedArg>,
+}
+
+impl InstructionDescription {
+    /// 指令名称
+    pub fn name(&self) -> &'static str {
+        self.spec.instruction.name()
+    }
+
+    /// 指令的用途说明
+    pub fn summary(&self) -> &'static str {
+        self.spec.summary()
+    }
+}
+
+impl fmt::Display for InstructionDescription {
+    /// 输出形如 `increment-by(amount=5)` 的单行描述
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = self.args.iter().map(|arg| format!("{}={}", arg.name, arg.value)).collect::<Vec<_>>();
+        write!(f, "{}({})", self.name(), args.join(", "))
+    }
+}
+
+/// 解析指令数据，返回指令名称和解码后的参数
+///
+/// # 参数
+/// * `data` - 指令数据，第1字节是指令码，或者是带客户端版本的编码
+///
+/// # 返回
+/// * `Option<InstructionDescription>` - 指令码未知、数据长度与参数表不符或参数无法解码时返回None
+pub fn describe_instruction(data: &[u8]) -> Option<InstructionDescription> {
+    let (_client_version, data) = split_client_version(data);
+    let spec = InstructionSpec::of(GongDeInstruction::from_instruction_data(data).ok()?);
+    if data.len() != spec.data_len() {
+        return None;
+    }
+    let mut offset = 1;
+    let args = spec
+        .args
+        .iter()
+        .map(|arg| {
+            let bytes = &data[offset..offset + arg.ty.size()];
+            offset += arg.ty.size();
+            arg.ty.decode(bytes).map(|value| DecodedArg { name: arg.name, value })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(InstructionDescription { spec, args })
+}
+
+/// 指令校验失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// 指令数据的第1字节与期望的指令类型不符
+    WrongInstruction { expected: GongDeInstruction, found: Option<u8> },
+    /// 账户数量不对
+    WrongAccountCount { expected: usize, found: usize },
+    /// 账户缺少签名标记
+    MissingSigner { index: usize, name: &'static str },
+    /// 不应签名的账户带了签名标记
+    UnexpectedSigner { index: usize, name: &'static str },
+    /// 账户缺少可写标记
+    NotWritable { index: usize, name: &'static str },
+    /// 只读账户被标记为可写
+    UnexpectedWritable { index: usize, name: &'static str },
+    /// 固定地址的账户地址不对，通常是账户顺序写错
+    WrongAddress { index: usize, name: &'static str, expected: Pubkey, found: Pubkey },
+    /// 带客户端版本的指令最后一个账户不是全局配置
+    MissingConfig { expected: Pubkey, found: Option<Pubkey> },
+    /// 指令参数会被合约拒绝，附带合约处理时返回的错误
+    InvalidArgs { instruction: Option<GongDeInstruction>, error: StateError },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongInstruction { expected, found } => {
+                write!(f, "指令类型不符: 期望 {:?}，实际 {:?}", expected, found)
+            }
+            Self::WrongAccountCount { expected, found } => {
+                write!(f, "账户数量不对: 期望 {} 个，实际 {} 个", expected, found)
+            }
+            Self::MissingSigner { index, name } => write!(f, "第 {} 个账户 {} 缺少签名标记", index, name),
+            Self::UnexpectedSigner { index, name } => write!(f, "第 {} 个账户 {} 不应签名", index, name),
+            Self::NotWritable { index, name } => write!(f, "第 {} 个账户 {} 必须可写", index, name),
+            Self::UnexpectedWritable { index, name } => write!(f, "第 {} 个账户 {} 应为只读", index, name),
+            Self::WrongAddress { index, name, expected, found } => {
+                write!(f, "第 {} 个账户 {} 地址不对: 期望 {}，实际 {}", index, name, expected, found)
+            }
+            Self::MissingConfig { expected, found } => {
+                write!(f, "带客户端版本的指令最后一个账户必须是全局配置 {}，实际 {:?}", expected, found)
+            }
+            Self::InvalidArgs { instruction: Some(instruction), error } => write!(f, "{} 指令的参数无效: {}", instruction, error),
+            Self::InvalidArgs { instruction: None, error } => write!(f, "指令参数无效: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// 去掉带版本编码的版本字段和末尾的全局配置，得到与旧编码一样的指令；旧编码的指令原样返回
+///
+/// # 参数
+/// * `instruction` - 待发送的指令
+///
+/// # 返回
+/// * `Result<(Option<u8>, Instruction), ValidationError>` - (客户端版本，旧编码为None；旧编码的指令)
+///
+/// # 错误
+/// * `ValidationError::MissingConfig` - 如果带版本的指令最后一个账户不是全局配置
+pub fn unwrap_client_version(instruction: &Instruction) -> Result<(Option<u8>, Instruction), ValidationError> {
+    let (client_version, data) = split_client_version(&instruction.data);
+    let mut accounts = instruction.accounts.clone();
+    if client_version.is_some() {
+        let (expected, _bump) = derive_config_address(&instruction.program_id);
+        match accounts.pop() {
+            Some(meta) if meta.pubkey == expected => {}
+            found => return Err(ValidationError::MissingConfig { expected, found: found.map(|meta| meta.pubkey) }),
+        }
+    }
+    Ok((client_version, Instruction { program_id: instruction.program_id, accounts, data: data.to_vec() }))
+}
+
+/// 按指令要求检查指令的类型和账户列表，带客户端版本的指令先去掉版本字段和全局配置再检查
+///
+/// # 参数
+/// * `instruction` - 待发送的指令
+/// * `spec` - 该指令的账户要求，通常为 `InstructionSpec::of(..)`
+///
+/// # 返回
+/// * `Result<(), ValidationError>` - 校验通过返回Ok
+///
+/// # 错误
+/// * `ValidationError` - 第一个不符合要求的地方
+pub fn validate_instruction(instruction: &Instruction, spec: &InstructionSpec) -> Result<(), ValidationError> {
+    let (_client_version, instruction) = unwrap_client_version(instruction)?;
+    let instruction = &instruction;
+    let tag = instruction.data.first().copied();
+    if tag != Some(spec.instruction as u8) {
+        return Err(ValidationError::WrongInstruction { expected: spec.instruction, found: tag });
+    }
+    let max_accounts = spec.accounts.len() + spec.optional_accounts.len();
+    if instruction.accounts.len() < spec.accounts.len() || instruction.accounts.len() > max_accounts {
+        return Err(ValidationError::WrongAccountCount {
+            expected: instruction.accounts.len().clamp(spec.accounts.len(), max_accounts),
+            found: instruction.accounts.len(),
+        });
+    }
+    let specs = match_account_specs(instruction, spec)?;
+    let specs = || specs.iter().copied();
+    // 先检查固定地址，账户顺序写错时报告顺序问题而不是随之出现的标记问题
+    for (index, (meta, expected)) in instruction.accounts.iter().zip(specs()).enumerate() {
+        if let Some(address) = expected.address.filter(|address| meta.pubkey != *address) {
+            return Err(ValidationError::WrongAddress { index, name: expected.name, expected: address, found: meta.pubkey });
+        }
+    }
+    for (index, (meta, expected)) in instruction.accounts.iter().zip(specs()).enumerate() {
+        let name = expected.name;
+        match (expected.is_signer, meta.is_signer) {
+            (true, false) => return Err(ValidationError::MissingSigner { index, name }),
+            (false, true) => return Err(ValidationError::UnexpectedSigner { index, name }),
+            _ => {}
+        }
+        match (expected.is_writable, meta.is_writable) {
+            (true, false) => return Err(ValidationError::NotWritable { index, name }),
+            (false, true) => return Err(ValidationError::UnexpectedWritable { index, name }),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 按合约处理函数的规则检查指令参数，与合约使用同一套解析函数，
+/// 数量为0、超过上限、时间范围颠倒这类会被合约拒绝的参数在签名前就能发现
+///
+/// # 参数
+/// * `instruction` - 待发送的指令，带客户端版本的编码也能识别
+///
+/// # 返回
+/// * `Result<(), ValidationError>` - 合约会接受这些参数时返回Ok
+///
+/// # 错误
+/// * `ValidationError::InvalidArgs` - 附带合约处理同样的指令数据时返回的错误
+pub fn validate_args(instruction: &Instruction) -> Result<(), ValidationError> {
+    let data = split_client_version(&instruction.data).1;
+    let kind = GongDeInstruction::from_instruction_data(data)
+        .map_err(|error| ValidationError::InvalidArgs { instruction: None, error })?;
+    let invalid = |error| ValidationError::InvalidArgs { instruction: Some(kind), error };
+    // 与合约一样，参数之后不允许有多余的字节
+    if data.len() != InstructionSpec::of(kind).data_len() {
+        return Err(invalid(StateError::InvalidInstructionData));
+    }
+    let checked = match kind {
+        GongDeInstruction::IncrementBy | GongDeInstruction::SharedIncrement => {
+            parse_increment_by_args(data).and_then(validate_increment_amount).map(drop)
+        }
+        GongDeInstruction::Initialize => parse_initialize_args(data).map(drop),
+        GongDeInstruction::TransferMerit => parse_transfer_args(data).map(drop),
+        GongDeInstruction::RestoreTo => parse_restore_args(data).map(drop),
+        GongDeInstruction::CreateCampaign => parse_create_campaign_args(data).map(drop),
+        GongDeInstruction::SetBeneficiary => parse_set_beneficiary_args(data).map(drop),
+        GongDeInstruction::Stake | GongDeInstruction::Unstake => parse_stake_args(data).map(drop),
+        GongDeInstruction::CreateLottery => parse_create_lottery_args(data).map(drop),
+        GongDeInstruction::EnterLottery => parse_enter_lottery_args(data).map(drop),
+        GongDeInstruction::SetMinimumClientVersion => parse_set_minimum_client_version_args(data).map(drop),
+        GongDeInstruction::SetPowDifficulty => parse_set_pow_difficulty_args(data).map(drop),
+        GongDeInstruction::IncrementWithProof => parse_increment_with_proof_args(data).map(drop),
+        GongDeInstruction::SetEmoji => parse_set_emoji_args(data).map(drop),
+        _ => Ok(()),
+    };
+    checked.map_err(invalid)
+}
+
+/// 在 validate_args 的基础上，按账户当前功德检查指令要扣减或恢复的功德，规则与合约处理函数一致
+///
+/// 转赠和设立配捐奖池不能超过当前功德，回滚只能调低功德；其他指令只做参数检查
+///
+/// # 参数
+/// * `instruction` - 待发送的指令
+/// * `current` - 发送前查询到的账户当前功德
+///
+/// # 返回
+/// * `Result<(), ValidationError>` - 按当前功德合约会接受时返回Ok；发送前功德仍可能被其他交易改变
+///
+/// # 错误
+/// * `ValidationError::InvalidArgs` - 附带合约会返回的错误，功德不足时为 `GongDeError::InsufficientMerit`
+pub fn validate_spend(instruction: &Instruction, current: u32) -> Result<(), ValidationError> {
+    validate_args(instruction)?;
+    let data = split_client_version(&instruction.data).1;
+    let Ok(kind) = GongDeInstruction::from_instruction_data(data) else {
+        return Ok(());
+    };
+    let require = |required: u64| {
+        if u64::from(current) < required {
+            return Err(StateError::from(GongDeError::InsufficientMerit));
+        }
+        Ok(())
+    };
+    let checked = match kind {
+        GongDeInstruction::TransferMerit => parse_transfer_args(data).and_then(|amount| require(amount.into())),
+        GongDeInstruction::CreateCampaign => parse_create_campaign_args(data).and_then(|args| require(args.budget.into())),
+        GongDeInstruction::RestoreTo => {
+            parse_restore_args(data).and_then(|(value, _checkpoint)| validate_restore_value(value, current)).map(drop)
+        }
+        _ => Ok(()),
+    };
+    checked.map_err(|error| ValidationError::InvalidArgs { instruction: Some(kind), error })
+}
+
+/// 把指令中的每个账户对应到账户要求：必需账户按顺序对应；可选账户可以单独省略，
+/// 按签名标记对应到下一个匹配的可选账户（例如配捐活动不签名，账户主人必须签名）
+fn match_account_specs<'a>(instruction: &Instruction, spec: &'a InstructionSpec) -> Result<Vec<&'a AccountSpec>, ValidationError> {
+    let mut specs: Vec<&AccountSpec> = spec.accounts.iter().collect();
+    let mut optional = spec.optional_accounts.iter();
+    for (index, meta) in instruction.accounts.iter().enumerate().skip(spec.accounts.len()) {
+        let matched = optional
+            .by_ref()
+            .find(|candidate| candidate.is_signer == meta.is_signer)
+            .ok_or(ValidationError::WrongAccountCount { expected: index, found: instruction.accounts.len() })?;
+        specs.push(matched);
+    }
+    Ok(specs)
+}
+
+// ========================================
+// 指令构建 - 构建后都会按 InstructionSpec 自检
+// ========================================
+
+/// 按账户要求生成 AccountMeta，写入本SDK的客户端版本并附上全局配置，然后自检
+///
+/// 版本查询保持旧编码，部署了任何版本的合约都能回答
+fn build(program_id: &Pubkey, instruction: GongDeInstruction, data: Vec<u8>, pubkeys: &[Pubkey]) -> Instruction {
+    let spec = InstructionSpec::of(instruction);
+    let mut accounts: Vec<AccountMeta> = pubkeys
+        .iter()
+        .zip(spec.accounts.iter().chain(spec.optional_accounts))
+        .map(|(pubkey, account)| AccountMeta {
+            pubkey: *pubkey,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect();
+    let data = if instruction == GongDeInstruction::ProtocolVersion {
+        data
+    } else {
+        let (config, _bump) = derive_config_address(program_id);
+        accounts.push(AccountMeta::new_readonly(config, false));
+        stamp_client_version(&data, CLIENT_VERSION)
+    };
+    let instruction = Instruction { program_id: *program_id, accounts, data };
+    debug_assert_eq!(validate_instruction(&instruction, &spec), Ok(()));
+    instruction
+}
+
+/// 在指令的账户列表末尾追加账户；带客户端版本的指令追加在全局配置之前，全局配置始终是最后一个
+///
+/// # 参数
+/// * `instruction` - 要追加账户的指令
+/// * `meta` - 追加的账户
+pub fn append_account(instruction: &mut Instruction, meta: AccountMeta) {
+    let index = match split_client_version(&instruction.data) {
+        (Some(_), _) => instruction.accounts.len().saturating_sub(1),
+        (None, _) => instruction.accounts.len(),
+    };
+    instruction.accounts.insert(index, meta);
+}
+
+/// 指令数据对应的指令类型，带客户端版本的编码也能识别
+fn instruction_kind(instruction: &Instruction) -> Option<GongDeInstruction> {
+    GongDeInstruction::from_instruction_data(split_client_version(&instruction.data).1).ok()
+}
+
+/// 构建增加功德指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址（公开功德箱可以是别人的账户）
+/// * `payer` - 支付手续费的用户
+/// * `global_pubkey` - 全局功德PDA账户地址
+pub fn increment(program_id: &Pubkey, gongde_pubkey: &Pubkey, payer: &Pubkey, global_pubkey: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::Increment,
+        vec![GongDeInstruction::Increment as u8],
+        &[*gongde_pubkey, *payer, *global_pubkey, system_program::ID],
+    )
+}
+
+/// 构建一次增加多点功德的指令，参数同 `increment`
+pub fn increment_by(
+    program_id: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    payer: &Pubkey,
+    global_pubkey: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![GongDeInstruction::IncrementBy as u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    build(
+        program_id,
+        GongDeInstruction::IncrementBy,
+        data,
+        &[*gongde_pubkey, *payer, *global_pubkey, system_program::ID],
+    )
+}
+
+/// 构建初始化指令
+pub fn initialize(program_id: &Pubkey, gongde_pubkey: &Pubkey, owner: &Pubkey, is_public: bool) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::Initialize,
+        vec![GongDeInstruction::Initialize as u8, is_public as u8],
+        &[*gongde_pubkey, *owner],
+    )
+}
+
+/// 构建关闭账户指令，租金退还给账户主人
+pub fn close(program_id: &Pubkey, gongde_pubkey: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(program_id, GongDeInstruction::Close, vec![GongDeInstruction::Close as u8], &[*gongde_pubkey, *owner])
+}
+
+/// 构建"空账户才关闭"指令
+pub fn close_if_empty(program_id: &Pubkey, gongde_pubkey: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::CloseIfEmpty,
+        vec![GongDeInstruction::CloseIfEmpty as u8],
+        &[*gongde_pubkey, *owner],
+    )
+}
+
+/// 构建查询协议版本指令，不需要账户
+pub fn protocol_version(program_id: &Pubkey) -> Instruction {
+    build(program_id, GongDeInstruction::ProtocolVersion, vec![GongDeInstruction::ProtocolVersion as u8], &[])
+}
+
+/// 构建转赠功德指令，转赠状态PDA由转出者地址派生
+pub fn transfer_merit(
+    program_id: &Pubkey,
+    sender_gongde: &Pubkey,
+    sender: &Pubkey,
+    recipient_gongde: &Pubkey,
+    amount: u32,
+) -> Instruction {
+    let (transfer_state, _bump) = derive_transfer_state_address(sender, program_id);
+    let mut data = vec![GongDeInstruction::TransferMerit as u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    build(
+        program_id,
+        GongDeInstruction::TransferMerit,
+        data,
+        &[*sender_gongde, *sender, *recipient_gongde, transfer_state, system_program::ID],
+    )
+}
+
+/// 构建迁移指令，把旧布局的账户升级到当前布局
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `owner` - 派生出功德账户地址的用户，不需要签名，可以替别人迁移
+/// * `payer` - 付款人，补足新增的租金
+pub fn migrate(program_id: &Pubkey, gongde_pubkey: &Pubkey, owner: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::Migrate,
+        vec![GongDeInstruction::Migrate as u8],
+        &[*gongde_pubkey, *payer, system_program::ID, *owner],
+    )
+}
+
+/// 构建回滚指令，把功德值恢复到 checkpoint 时记录的 value
+pub fn restore_to(program_id: &Pubkey, gongde_pubkey: &Pubkey, owner: &Pubkey, value: u64, checkpoint: i64) -> Instruction {
+    let mut data = vec![GongDeInstruction::RestoreTo as u8];
+    data.extend_from_slice(&value.to_le_bytes());
+    data.extend_from_slice(&checkpoint.to_le_bytes());
+    build(program_id, GongDeInstruction::RestoreTo, data, &[*gongde_pubkey, *owner])
+}
+
+/// 给 Increment / IncrementBy / IncrementWithProof 指令附上配捐活动账户
+///
+/// 活动进行中时按倍数额外获得功德，活动未开始、已结束或奖池耗尽时只做普通增加
+///
+/// # 参数
+/// * `instruction` - `increment`、`increment_by` 或 `increment_with_proof` 构建的指令
+/// * `campaign` - 配捐活动PDA地址
+pub fn with_campaign(mut instruction: Instruction, campaign: &Pubkey) -> Instruction {
+    append_account(&mut instruction, AccountMeta::new(*campaign, false));
+    debug_assert!(instruction_kind(&instruction)
+        .is_some_and(|kind| validate_instruction(&instruction, &InstructionSpec::of(kind)).is_ok()));
+    instruction
+}
+
+/// 给 Increment / IncrementBy / IncrementWithProof 指令附上账户主人，功德账户不存在时先创建公开功德箱再增加
+///
+/// 新用户的第一笔交易只需要一条 increment；账户已存在时主人的签名不会被使用。
+/// 需要配捐时先调用 `with_campaign`，活动账户排在账户主人之前
+///
+/// # 参数
+/// * `instruction` - `increment`、`increment_by` 或 `increment_with_proof` 构建的指令，功德账户必须由 `owner` 派生
+/// * `owner` - 账户主人，必须签名
+pub fn with_create_if_missing(mut instruction: Instruction, owner: &Pubkey) -> Instruction {
+    append_account(&mut instruction, AccountMeta::new_readonly(*owner, true));
+    debug_assert!(instruction_kind(&instruction)
+        .is_some_and(|kind| validate_instruction(&instruction, &InstructionSpec::of(kind)).is_ok()));
+    instruction
+}
+
+/// 构建创建配捐活动指令，奖池从发起人的功德中扣除
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `organizer_gongde` - 发起人的功德账户地址
+/// * `organizer` - 发起人，支付活动账户租金
+/// * `args` - 活动参数，活动编号与发起人一起决定活动PDA地址
+pub fn create_campaign(
+    program_id: &Pubkey,
+    organizer_gongde: &Pubkey,
+    organizer: &Pubkey,
+    args: &CreateCampaignArgs,
+) -> Instruction {
+    let (campaign, _bump) = derive_campaign_address(organizer, args.campaign_id, program_id);
+    let mut data = vec![GongDeInstruction::CreateCampaign as u8];
+    data.extend_from_slice(&args.campaign_id.to_le_bytes());
+    data.extend_from_slice(&args.start.to_le_bytes());
+    data.extend_from_slice(&args.end.to_le_bytes());
+    data.extend_from_slice(&args.multiplier.to_le_bytes());
+    data.extend_from_slice(&args.budget.to_le_bytes());
+    build(
+        program_id,
+        GongDeInstruction::CreateCampaign,
+        data,
+        &[*organizer_gongde, *organizer, campaign, system_program::ID],
+    )
+}
+
+/// 构建共享账本增加功德指令，给签名用户在共享账本中的条目增加功德
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `user` - 用户，支付手续费和新条目的租金
+/// * `global_pubkey` - 全局功德PDA账户地址
+/// * `amount` - 增加的功德，范围同 `increment_by`
+pub fn shared_increment(program_id: &Pubkey, user: &Pubkey, global_pubkey: &Pubkey, amount: u64) -> Instruction {
+    let (shared_ledger, _bump) = derive_shared_ledger_address(program_id);
+    let mut data = vec![GongDeInstruction::SharedIncrement as u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    build(
+        program_id,
+        GongDeInstruction::SharedIncrement,
+        data,
+        &[shared_ledger, *user, *global_pubkey, system_program::ID],
+    )
+}
+
+/// 构建指定受益人指令，`beneficiary` 传 `None` 时清除受益人
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `authority` - 账户主人
+/// * `beneficiary` - (受益人, 等待天数)，等待天数不能少于 MIN_BENEFICIARY_TIMEOUT_DAYS
+pub fn set_beneficiary(
+    program_id: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    authority: &Pubkey,
+    beneficiary: Option<(Pubkey, u16)>,
+) -> Instruction {
+    let (beneficiary, timeout_days) = beneficiary.unwrap_or_default();
+    let mut data = vec![GongDeInstruction::SetBeneficiary as u8];
+    data.extend_from_slice(beneficiary.as_ref());
+    data.extend_from_slice(&timeout_days.to_le_bytes());
+    build(program_id, GongDeInstruction::SetBeneficiary, data, &[*gongde_pubkey, *authority])
+}
+
+/// 构建设置头像指令，`codepoint` 传0时清除头像
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `authority` - 账户主人
+/// * `codepoint` - 头像的Unicode码点，见 `is_valid_emoji`
+pub fn set_emoji(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey, codepoint: u32) -> Instruction {
+    let mut data = vec![GongDeInstruction::SetEmoji as u8];
+    data.extend_from_slice(&codepoint.to_le_bytes());
+    build(program_id, GongDeInstruction::SetEmoji, data, &[*gongde_pubkey, *authority])
+}
+
+/// 构建受益人接管指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 被接管的功德账户地址
+/// * `beneficiary` - 受益人
+/// * `merge_into` - 受益人自己的功德账户；传入时合并功德并关闭被接管的账户，否则受益人成为账户主人
+pub fn claim_as_beneficiary(
+    program_id: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    beneficiary: &Pubkey,
+    merge_into: Option<&Pubkey>,
+) -> Instruction {
+    let mut pubkeys = vec![*gongde_pubkey, *beneficiary];
+    pubkeys.extend(merge_into.copied());
+    build(
+        program_id,
+        GongDeInstruction::ClaimAsBeneficiary,
+        vec![GongDeInstruction::ClaimAsBeneficiary as u8],
+        &pubkeys,
+    )
+}
+
+/// 构建背书指令，背书记录地址由背书人和被背书的功德账户派生
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `endorsee_gongde` - 被背书的功德账户地址
+/// * `endorser` - 背书人
+/// * `endorser_gongde` - 背书人自己的功德账户地址
+pub fn endorse(program_id: &Pubkey, endorsee_gongde: &Pubkey, endorser: &Pubkey, endorser_gongde: &Pubkey) -> Instruction {
+    let (endorsement, _bump) = derive_endorsement_address(endorser, endorsee_gongde, program_id);
+    build(
+        program_id,
+        GongDeInstruction::Endorse,
+        vec![GongDeInstruction::Endorse as u8],
+        &[*endorsee_gongde, *endorser, *endorser_gongde, endorsement, system_program::ID],
+    )
+}
+
+/// 构建批量背书指令，每个目标的背书记录地址由背书人和目标功德账户派生
+/// 目标数量不在允许范围内时直接 panic，避免多出的目标被悄悄丢掉
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `endorser` - 背书人
+/// * `endorser_gongde` - 背书人自己的功德账户地址
+/// * `endorsee_gongdes` - 被背书的功德账户地址，1到 MAX_ENDORSE_MANY_TARGETS 个
+pub fn endorse_many(program_id: &Pubkey, endorser: &Pubkey, endorser_gongde: &Pubkey, endorsee_gongdes: &[Pubkey]) -> Instruction {
+    assert!(
+        (1..=MAX_ENDORSE_MANY_TARGETS).contains(&endorsee_gongdes.len()),
+        "批量背书的目标数量应为1到{}个，实际{}个",
+        MAX_ENDORSE_MANY_TARGETS,
+        endorsee_gongdes.len()
+    );
+    let mut pubkeys = vec![*endorser, *endorser_gongde, system_program::ID];
+    for endorsee_gongde in endorsee_gongdes {
+        let (endorsement, _bump) = derive_endorsement_address(endorser, endorsee_gongde, program_id);
+        pubkeys.extend([*endorsee_gongde, endorsement]);
+    }
+    build(program_id, GongDeInstruction::EndorseMany, vec![GongDeInstruction::EndorseMany as u8], &pubkeys)
+}
+
+/// 构建结算挂机功德指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `authority` - 账户主人
+pub fn accrue(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey) -> Instruction {
+    let (accrual_state, _bump) = derive_accrual_state_address(gongde_pubkey, program_id);
+    build(
+        program_id,
+        GongDeInstruction::Accrue,
+        vec![GongDeInstruction::Accrue as u8],
+        &[*gongde_pubkey, *authority, accrual_state, system_program::ID],
+    )
+}
+
+/// 构建质押指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `authority` - 账户主人，支付质押的 SOL
+/// * `lamports` - 质押数量，不能为0
+pub fn stake(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = vec![GongDeInstruction::Stake as u8];
+    data.extend_from_slice(&lamports.to_le_bytes());
+    build(program_id, GongDeInstruction::Stake, data, &[*gongde_pubkey, *authority, system_program::ID])
+}
+
+/// 构建结算质押功德指令
+pub fn claim_accrued(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::ClaimAccrued,
+        vec![GongDeInstruction::ClaimAccrued as u8],
+        &[*gongde_pubkey, *authority],
+    )
+}
+
+/// 构建取回质押指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `authority` - 账户主人，收回质押的 SOL
+/// * `lamports` - 取回数量，不能为0也不能超过已质押的数量
+pub fn unstake(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = vec![GongDeInstruction::Unstake as u8];
+    data.extend_from_slice(&lamports.to_le_bytes());
+    build(program_id, GongDeInstruction::Unstake, data, &[*gongde_pubkey, *authority])
+}
+
+/// 构建创建彩票指令，彩票轮次地址由管理员和轮次编号派生
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `admin` - 管理员，支付彩票轮次的租金
+/// * `args` - 轮次编号、投注时间和票价
+pub fn create_lottery(program_id: &Pubkey, admin: &Pubkey, args: &CreateLotteryArgs) -> Instruction {
+    let (lottery, _bump) = derive_lottery_address(admin, args.round_id, program_id);
+    let mut data = vec![GongDeInstruction::CreateLottery as u8];
+    data.extend_from_slice(&args.round_id.to_le_bytes());
+    data.extend_from_slice(&args.start_slot.to_le_bytes());
+    data.extend_from_slice(&args.end_slot.to_le_bytes());
+    data.extend_from_slice(&args.ticket_price.to_le_bytes());
+    build(program_id, GongDeInstruction::CreateLottery, data, &[lottery, *admin, system_program::ID])
+}
+
+/// 构建买彩票指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 扣除票款的功德账户地址
+/// * `player` - 投注人，必须是功德账户的主人
+/// * `lottery` - 彩票轮次地址
+/// * `tickets` - 票数，不能为0
+pub fn enter_lottery(program_id: &Pubkey, gongde_pubkey: &Pubkey, player: &Pubkey, lottery: &Pubkey, tickets: u16) -> Instruction {
+    let mut data = vec![GongDeInstruction::EnterLottery as u8];
+    data.extend_from_slice(&tickets.to_le_bytes());
+    build(
+        program_id,
+        GongDeInstruction::EnterLottery,
+        data,
+        &[*gongde_pubkey, *player, *lottery, system_program::ID],
+    )
+}
+
+/// 构建开奖指令
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `lottery` - 彩票轮次地址
+/// * `winner_gongde` - 中奖者的功德账户，用 `lottery_ticket_owner` 和 `slot_hash_at` 在本地算出；没有人投注时传 `None`
+pub fn settle_lottery(program_id: &Pubkey, lottery: &Pubkey, winner_gongde: Option<&Pubkey>) -> Instruction {
+    let mut pubkeys = vec![*lottery, sysvar::slot_hashes::ID];
+    pubkeys.extend(winner_gongde.copied());
+    build(
+        program_id,
+        GongDeInstruction::SettleLottery,
+        vec![GongDeInstruction::SettleLottery as u8],
+        &pubkeys,
+    )
+}
+
+/// 构建作废彩票的退款指令：结束slot的哈希过期后开奖会把本轮标记为作废，票款退还给传入的投注者；
+/// 投注者超过 MAX_LOTTERY_REFUND_ACCOUNTS 个时分多条指令发送，已经退款的账户再传入不会重复退款
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `lottery` - 彩票轮次地址
+/// * `entrant_gongdes` - 要退款的投注功德账户，用 `lottery_pending_refunds` 在本地查出
+pub fn refund_lottery(program_id: &Pubkey, lottery: &Pubkey, entrant_gongdes: &[Pubkey]) -> Instruction {
+    assert!(
+        entrant_gongdes.len() <= MAX_LOTTERY_REFUND_ACCOUNTS,
+        "一次最多退款{}个功德账户，实际{}个",
+        MAX_LOTTERY_REFUND_ACCOUNTS,
+        entrant_gongdes.len()
+    );
+    let mut pubkeys = vec![*lottery, sysvar::slot_hashes::ID];
+    pubkeys.extend_from_slice(entrant_gongdes);
+    build(
+        program_id,
+        GongDeInstruction::SettleLottery,
+        vec![GongDeInstruction::SettleLottery as u8],
+        &pubkeys,
+    )
+}
+
+/// 构建归档指令：功德保留，取消归档之前除了关闭之外的修改都会失败
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `authority` - 账户主人
+pub fn archive(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(program_id, GongDeInstruction::Archive, vec![GongDeInstruction::Archive as u8], &[*gongde_pubkey, *authority])
+}
+
+/// 构建取消归档指令
+pub fn unarchive(program_id: &Pubkey, gongde_pubkey: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::Unarchive,
+        vec![GongDeInstruction::Unarchive as u8],
+        &[*gongde_pubkey, *authority],
+    )
+}
+
+/// 构建设置最低客户端版本指令，只有创作者可以发送
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `creator` - 创作者，第一次设置时支付全局配置的租金
+/// * `global_pubkey` - 全局功德PDA账户地址，同步保存配置的副本
+/// * `minimum_client_version` - 最低客户端版本，0表示不限制
+pub fn set_minimum_client_version(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    global_pubkey: &Pubkey,
+    minimum_client_version: u8,
+) -> Instruction {
+    let (config, _bump) = derive_config_address(program_id);
+    build(
+        program_id,
+        GongDeInstruction::SetMinimumClientVersion,
+        vec![GongDeInstruction::SetMinimumClientVersion as u8, minimum_client_version],
+        &[config, *global_pubkey, *creator, system_program::ID],
+    )
+}
+
+/// 构建设置工作量证明难度的指令，只有创作者能签名
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `creator` - 创作者，全局配置不存在或需要扩容时支付租金
+/// * `global_pubkey` - 全局功德PDA账户地址，同步保存配置的副本
+/// * `difficulty` - 前导0比特数，0表示关闭，最大 MAX_POW_DIFFICULTY
+pub fn set_pow_difficulty(program_id: &Pubkey, creator: &Pubkey, global_pubkey: &Pubkey, difficulty: u8) -> Instruction {
+    let (config, _bump) = derive_config_address(program_id);
+    build(
+        program_id,
+        GongDeInstruction::SetPowDifficulty,
+        vec![GongDeInstruction::SetPowDifficulty as u8, difficulty],
+        &[config, *global_pubkey, *creator, system_program::ID],
+    )
+}
+
+/// 构建附带工作量证明的增加功德指令，账户同 `increment`，也可以用 `with_campaign`、`with_create_if_missing` 追加账户
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_pubkey` - 功德账户地址
+/// * `payer` - 支付手续费的用户
+/// * `global_pubkey` - 全局功德PDA账户地址
+/// * `nonce` - 客户端按功德账户地址算出的 nonce，见 `client::pow::mine_nonce`
+pub fn increment_with_proof(
+    program_id: &Pubkey,
+    gongde_pubkey: &Pubkey,
+    payer: &Pubkey,
+    global_pubkey: &Pubkey,
+    nonce: u64,
+) -> Instruction {
+    let mut data = vec![GongDeInstruction::IncrementWithProof as u8];
+    data.extend_from_slice(&nonce.to_le_bytes());
+    build(
+        program_id,
+        GongDeInstruction::IncrementWithProof,
+        data,
+        &[*gongde_pubkey, *payer, *global_pubkey, system_program::ID],
+    )
+}
+
+/// 构建交换功德指令：两个账户的功德值互换，两位主人都要签名
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_a` - 第一个功德账户地址
+/// * `authority_a` - 第一个功德账户的主人
+/// * `gongde_b` - 第二个功德账户地址，不能与第一个相同
+/// * `authority_b` - 第二个功德账户的主人
+pub fn swap_counts(
+    program_id: &Pubkey,
+    gongde_a: &Pubkey,
+    authority_a: &Pubkey,
+    gongde_b: &Pubkey,
+    authority_b: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        GongDeInstruction::SwapCounts,
+        vec![GongDeInstruction::SwapCounts as u8],
+        &[*gongde_a, *authority_a, *gongde_b, *authority_b],
+    )
+}
+
+/// 构建功德汇总指令：只读取账户，通过 return data 返回合计和每个账户的功德
+/// 账户数量不在允许范围内时直接 panic，避免多出的账户被悄悄丢掉
+///
+/// # 参数
+/// * `program_id` - 合约程序ID
+/// * `gongde_accounts` - 要汇总的功德账户地址，1到 MAX_REPORT_ACCOUNTS 个且不能重复
+pub fn report(program_id: &Pubkey, gongde_accounts: &[Pubkey]) -> Instruction {
+    assert!(
+        (1..=MAX_REPORT_ACCOUNTS).contains(&gongde_accounts.len()),
+        "汇总的功德账户数量应为1到{}个，实际{}个",
+        MAX_REPORT_ACCOUNTS,
+        gongde_accounts.len()
+    );
+    build(program_id, GongDeInstruction::Report, vec![GongDeInstruction::Report as u8], gongde_accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn increment_fixture() -> Instruction {
+        increment_fixture_for(&Pubkey::new_unique())
+    }
+
+    fn increment_fixture_for(program_id: &Pubkey) -> Instruction {
+        increment(program_id, &Pubkey::new_unique(), &Pubkey::new_unique(), &Pubkey::new_unique())
+    }
+
+    #[test]
+    fn test_builders_pass_validation() {
+        let program_id = Pubkey::new_unique();
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let campaign_args = CreateCampaignArgs { campaign_id: 1, start: 0, end: 60, multiplier: 1, budget: 10 };
+        let cases = [
+            (increment(&program_id, &a, &b, &c), GongDeInstruction::Increment),
+            (increment_by(&program_id, &a, &b, &c, 5), GongDeInstruction::IncrementBy),
+            (initialize(&program_id, &a, &b, true), GongDeInstruction::Initialize),
+            (close(&program_id, &a, &b), GongDeInstruction::Close),
+            (close_if_empty(&program_id, &a, &b), GongDeInstruction::CloseIfEmpty),
+            (protocol_version(&program_id), GongDeInstruction::ProtocolVersion),
+            (transfer_merit(&program_id, &a, &b, &c, 3), GongDeInstruction::TransferMerit),
+            (migrate(&program_id, &a, &c, &b), GongDeInstruction::Migrate),
+            (restore_to(&program_id, &a, &b, 1, 0), GongDeInstruction::RestoreTo),
+            (create_campaign(&program_id, &a, &b, &campaign_args), GongDeInstruction::CreateCampaign),
+            (with_campaign(increment(&program_id, &a, &b, &c), &c), GongDeInstruction::Increment),
+            (with_campaign(increment_by(&program_id, &a, &b, &c, 5), &c), GongDeInstruction::IncrementBy),
+            (with_create_if_missing(increment(&program_id, &a, &b, &c), &b), GongDeInstruction::Increment),
+            (
+                with_create_if_missing(with_campaign(increment_by(&program_id, &a, &b, &c, 5), &c), &b),
+                GongDeInstruction::IncrementBy,
+            ),
+            (shared_increment(&program_id, &a, &b, 5), GongDeInstruction::SharedIncrement),
+            (set_beneficiary(&program_id, &a, &b, Some((c, 30))), GongDeInstruction::SetBeneficiary),
+            (set_beneficiary(&program_id, &a, &b, None), GongDeInstruction::SetBeneficiary),
+            (claim_as_beneficiary(&program_id, &a, &b, None), GongDeInstruction::ClaimAsBeneficiary),
+            (claim_as_beneficiary(&program_id, &a, &b, Some(&c)), GongDeInstruction::ClaimAsBeneficiary),
+            (endorse(&program_id, &a, &b, &c), GongDeInstruction::Endorse),
+            (accrue(&program_id, &a, &b), GongDeInstruction::Accrue),
+            (set_minimum_client_version(&program_id, &a, &b, 2), GongDeInstruction::SetMinimumClientVersion),
+            (swap_counts(&program_id, &a, &b, &c, &b), GongDeInstruction::SwapCounts),
+            (report(&program_id, &[a]), GongDeInstruction::Report),
+            (report(&program_id, &[a, b, c]), GongDeInstruction::Report),
+            (endorse_many(&program_id, &a, &b, &[c]), GongDeInstruction::EndorseMany),
+            (set_pow_difficulty(&program_id, &a, &b, 8), GongDeInstruction::SetPowDifficulty),
+            (increment_with_proof(&program_id, &a, &b, &c, 42), GongDeInstruction::IncrementWithProof),
+            (
+                with_create_if_missing(increment_with_proof(&program_id, &a, &b, &c, 42), &b),
+                GongDeInstruction::IncrementWithProof,
+            ),
+            (set_emoji(&program_id, &a, &b, '🙏' as u32), GongDeInstruction::SetEmoji),
+            (set_emoji(&program_id, &a, &b, 0), GongDeInstruction::SetEmoji),
+            (
+                endorse_many(&program_id, &a, &b, &[c; MAX_ENDORSE_MANY_TARGETS]),
+                GongDeInstruction::EndorseMany,
+            ),
+            (settle_lottery(&program_id, &a, None), GongDeInstruction::SettleLottery),
+            (settle_lottery(&program_id, &a, Some(&b)), GongDeInstruction::SettleLottery),
+            (
+                refund_lottery(&program_id, &a, &[b; MAX_LOTTERY_REFUND_ACCOUNTS]),
+                GongDeInstruction::SettleLottery,
+            ),
+        ];
+        for (instruction, kind) in cases {
+            assert_eq!(validate_instruction(&instruction, &InstructionSpec::of(kind)), Ok(()), "{:?}", kind);
+            assert_eq!(validate_args(&instruction), Ok(()), "{:?}", kind);
+            // 参数表与构建出的指令数据长度一致，导出的IDL才能正确解码
+            let (_client_version, data) = split_client_version(&instruction.data);
+            assert_eq!(data.len(), InstructionSpec::of(kind).data_len(), "{:?}", kind);
+            assert_eq!(GongDeInstruction::from_instruction_data(data), Ok(kind));
+        }
+        let instruction = create_campaign(&program_id, &a, &b, &campaign_args);
+        assert_eq!(crate::utils::parse_create_campaign_args(split_client_version(&instruction.data).1), Ok(campaign_args));
+    }
+
+    #[test]
+    fn test_builders_stamp_client_version() {
+        let program_id = Pubkey::new_unique();
+        let (config, _bump) = derive_config_address(&program_id);
+        let owner = Pubkey::new_unique();
+
+        // 版本字段在指令码前面，全局配置始终是最后一个账户，追加的可选账户排在它之前
+        let instruction = with_create_if_missing(increment_fixture_for(&program_id), &owner);
+        assert_eq!(split_client_version(&instruction.data), (Some(CLIENT_VERSION), &[GongDeInstruction::Increment as u8][..]));
+        assert_eq!(instruction.accounts.last(), Some(&AccountMeta::new_readonly(config, false)));
+        assert_eq!(instruction.accounts[4], AccountMeta::new_readonly(owner, true));
+        let (client_version, unwrapped) = unwrap_client_version(&instruction).unwrap();
+        assert_eq!(client_version, Some(CLIENT_VERSION));
+        assert_eq!(unwrapped.data, vec![GongDeInstruction::Increment as u8]);
+        assert_eq!(unwrapped.accounts, instruction.accounts[..5]);
+
+        // 版本查询保持旧编码
+        let instruction = protocol_version(&program_id);
+        assert_eq!(instruction.data, vec![GongDeInstruction::ProtocolVersion as u8]);
+        assert!(instruction.accounts.is_empty());
+
+        // 去掉全局配置后校验失败
+        let mut instruction = increment_fixture_for(&program_id);
+        instruction.accounts.pop();
+        assert_eq!(
+            validate_instruction(&instruction, &InstructionSpec::of(GongDeInstruction::Increment)),
+            Err(ValidationError::MissingConfig { expected: config, found: Some(system_program::ID) })
+        );
+    }
+
+    /// 每种指令的一条示例，match 没有通配分支，新增指令时必须补上
+    fn sample_instruction(kind: GongDeInstruction, program_id: &Pubkey, a: &Pubkey, b: &Pubkey) -> (Instruction, Vec<String>) {
+        match kind {
+            GongDeInstruction::Increment => (increment(program_id, a, b, a), vec![]),
+            GongDeInstruction::Close => (close(program_id, a, b), vec![]),
+            GongDeInstruction::Initialize => (initialize(program_id, a, b, true), vec!["true".to_string()]),
+            GongDeInstruction::CloseIfEmpty => (close_if_empty(program_id, a, b), vec![]),
+            GongDeInstruction::ProtocolVersion => (protocol_version(program_id), vec![]),
+            GongDeInstruction::TransferMerit => (transfer_merit(program_id, a, b, a, 3), vec!["3".to_string()]),
+            GongDeInstruction::IncrementBy => (increment_by(program_id, a, b, a, 5), vec!["5".to_string()]),
+            GongDeInstruction::Migrate => (migrate(program_id, a, a, b), vec![]),
+            GongDeInstruction::RestoreTo => (restore_to(program_id, a, b, 7, -1), vec!["7".to_string(), "-1".to_string()]),
+            GongDeInstruction::CreateCampaign => {
+                let args = CreateCampaignArgs { campaign_id: 1, start: 0, end: 60, multiplier: 2, budget: 10 };
+                let values = ["1", "0", "60", "2", "10"].map(String::from).to_vec();
+                (create_campaign(program_id, a, b, &args), values)
+            }
+            GongDeInstruction::SharedIncrement => (shared_increment(program_id, a, b, 9), vec!["9".to_string()]),
+            GongDeInstruction::SetBeneficiary => {
+                (set_beneficiary(program_id, a, b, Some((*b, 30))), vec![b.to_string(), "30".to_string()])
+            }
+            GongDeInstruction::ClaimAsBeneficiary => (claim_as_beneficiary(program_id, a, b, None), vec![]),
+            GongDeInstruction::Endorse => (endorse(program_id, a, b, b), vec![]),
+            GongDeInstruction::Accrue => (accrue(program_id, a, b), vec![]),
+            GongDeInstruction::Stake => (stake(program_id, a, b, 1_000), vec!["1000".to_string()]),
+            GongDeInstruction::ClaimAccrued => (claim_accrued(program_id, a, b), vec![]),
+            GongDeInstruction::Unstake => (unstake(program_id, a, b, 500), vec!["500".to_string()]),
+            GongDeInstruction::CreateLottery => {
+                let args = CreateLotteryArgs { round_id: 2, start_slot: 10, end_slot: 20, ticket_price: 3 };
+                let values = ["2", "10", "20", "3"].map(String::from).to_vec();
+                (create_lottery(program_id, b, &args), values)
+            }
+            GongDeInstruction::EnterLottery => (enter_lottery(program_id, a, b, a, 4), vec!["4".to_string()]),
+            GongDeInstruction::SettleLottery => (settle_lottery(program_id, a, Some(b)), vec![]),
+            GongDeInstruction::Archive => (archive(program_id, a, b), vec![]),
+            GongDeInstruction::Unarchive => (unarchive(program_id, a, b), vec![]),
+            GongDeInstruction::SetMinimumClientVersion => {
+                (set_minimum_client_version(program_id, b, a, 2), vec!["2".to_string()])
+            }
+            GongDeInstruction::SwapCounts => (swap_counts(program_id, a, b, b, a), vec![]),
+            GongDeInstruction::Report => (report(program_id, &[*a, *b]), vec![]),
+            GongDeInstruction::EndorseMany => (endorse_many(program_id, a, b, &[*b]), vec![]),
+            GongDeInstruction::SetPowDifficulty => (set_pow_difficulty(program_id, b, a, 12), vec!["12".to_string()]),
+            GongDeInstruction::IncrementWithProof => {
+                (increment_with_proof(program_id, a, b, a, 99), vec!["99".to_string()])
+            }
+            GongDeInstruction::SetEmoji => (set_emoji(program_id, a, b, 0x1F64F), vec!["128591".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_payload_len_matches_arg_spec() {
+        // 合约解析指令时用 payload_len 检查参数是否完整，必须与参数表一致
+        for kind in GongDeInstruction::ALL {
+            assert_eq!(1 + kind.payload_len(), InstructionSpec::of(kind).data_len(), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_global_pda_index() {
+        // 旧编码的增加功德指令从这个位置的全局功德PDA读取全局配置的副本
+        for kind in [GongDeInstruction::Increment, GongDeInstruction::IncrementBy, GongDeInstruction::SharedIncrement] {
+            assert_eq!(InstructionSpec::of(kind).global_pda_index(), Some(2), "{:?}", kind);
+        }
+        assert_eq!(InstructionSpec::of(GongDeInstruction::SetPowDifficulty).global_pda_index(), Some(1));
+        assert_eq!(InstructionSpec::of(GongDeInstruction::Close).global_pda_index(), None);
+    }
+
+    #[test]
+    fn test_describe_every_instruction() {
+        let (program_id, a, b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        for kind in GongDeInstruction::ALL {
+            let (instruction, values) = sample_instruction(kind, &program_id, &a, &b);
+            let description = describe_instruction(&instruction.data).unwrap_or_else(|| panic!("无法描述 {:?}", kind));
+            assert_eq!(description.name(), kind.name());
+            assert!(!description.summary().is_empty(), "{:?}", kind);
+            assert_eq!(description.args.iter().map(|arg| arg.value.clone()).collect::<Vec<_>>(), values, "{:?}", kind);
+            for account in description.spec.accounts.iter().chain(description.spec.optional_accounts) {
+                assert!(!account.role.is_empty(), "{:?} {}", kind, account.name);
+            }
+        }
+
+        let description = describe_instruction(&increment_by(&program_id, &a, &b, &a, 5).data).unwrap();
+        assert_eq!(description.to_string(), "increment-by(amount=5)");
+    }
+
+    #[test]
+    fn test_describe_rejects_malformed_data() {
+        assert_eq!(describe_instruction(&[]), None);
+        assert_eq!(describe_instruction(&[255]), None);
+        // 参数缺少字节
+        assert_eq!(describe_instruction(&[GongDeInstruction::IncrementBy as u8, 1, 0]), None);
+        // 布尔值只能是0或1
+        assert_eq!(describe_instruction(&[GongDeInstruction::Initialize as u8, 2]), None);
+    }
+
+    #[test]
+    fn test_missing_signer() {
+        let mut instruction = increment_fixture();
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            validate_instruction(&instruction, &InstructionSpec::of(GongDeInstruction::Increment)),
+            Err(ValidationError::MissingSigner { index: 1, name: "payer" })
+        );
+    }
+
+    #[test]
+    fn test_wrong_account_count() {
+        // 去掉系统程序，全局配置仍然是最后一个账户
+        let mut instruction = increment_fixture();
+        instruction.accounts.remove(3);
+        assert_eq!(
+            validate_instruction(&instruction, &InstructionSpec::of(GongDeInstruction::Increment)),
+            Err(ValidationError::WrongAccountCount { expected: 4, found: 3 })
+        );
+
+        // 配捐活动账户之后不能再有账户
+        let mut instruction = with_campaign(increment_fixture(), &Pubkey::new_unique());
+        append_account(&mut instruction, AccountMeta::new(Pubkey::new_unique(), false));
+        assert_eq!(
+            validate_instruction(&instruction, &InstructionSpec::of(GongDeInstruction::Increment)),
+            Err(ValidationError::WrongAccountCount { expected: 5, found: 6 })
+        );
+    }
+
+    #[test]
+    fn test_optional_accounts_matched_by_signer() {
+        let owner = Pubkey::new_unique();
+        let spec = InstructionSpec::of(GongDeInstruction::Increment);
+
+        // 只传账户主人时对应到 owner，而不是排在前面的 campaign
+        let mut instruction = with_create_if_missing(increment_fixture(), &owner);
+        assert_eq!(validate_instruction(&instruction, &spec), Ok(()));
+        instruction.accounts[4].is_writable = true;
+        assert_eq!(
+            validate_instruction(&instruction, &spec),
+            Err(ValidationError::UnexpectedWritable { index: 4, name: "owner" })
+        );
+
+        // 账户主人之后不能再传配捐活动
+        let mut instruction = with_create_if_missing(increment_fixture(), &owner);
+        append_account(&mut instruction, AccountMeta::new(Pubkey::new_unique(), false));
+        assert_eq!(
+            validate_instruction(&instruction, &spec),
+            Err(ValidationError::WrongAccountCount { expected: 5, found: 6 })
+        );
+    }
+
+    #[test]
+    fn test_wrong_account_order() {
+        // 全局PDA和系统程序顺序写反
+        let mut instruction = increment_fixture();
+        instruction.accounts.swap(2, 3);
+        assert!(matches!(
+            validate_instruction(&instruction, &InstructionSpec::of(GongDeInstruction::Increment)),
+            Err(ValidationError::WrongAddress { index: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_wrong_instruction_tag() {
+        let instruction = increment_fixture();
+        assert_eq!(
+            validate_instruction(&instruction, &InstructionSpec::of(GongDeInstruction::Close)),
+            Err(ValidationError::WrongInstruction { expected: GongDeInstruction::Close, found: Some(0) })
+        );
+    }
+
+    #[test]
+    fn test_validate_args_rejects_invalid_arguments() {
+        let program_id = Pubkey::new_unique();
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let invalid = |instruction, error: GongDeError| ValidationError::InvalidArgs {
+            instruction: Some(instruction),
+            error: error.into(),
+        };
+        let campaign_args = CreateCampaignArgs { campaign_id: 1, start: 60, end: 60, multiplier: 1, budget: 10 };
+        let lottery_args = CreateLotteryArgs { round_id: 1, start_slot: 0, end_slot: 100, ticket_price: 0 };
+        let cases = [
+            (increment_by(&program_id, &a, &b, &c, 0), Err(invalid(GongDeInstruction::IncrementBy, GongDeError::InvalidAmount))),
+            (
+                increment_by(&program_id, &a, &b, &c, crate::utils::MAX_INCREMENT + 1),
+                Err(invalid(GongDeInstruction::IncrementBy, GongDeError::InvalidAmount)),
+            ),
+            (shared_increment(&program_id, &a, &b, 0), Err(invalid(GongDeInstruction::SharedIncrement, GongDeError::InvalidAmount))),
+            // 转赠数量为0时合约报告指令数据无效
+            (
+                transfer_merit(&program_id, &a, &b, &c, 0),
+                Err(ValidationError::InvalidArgs {
+                    instruction: Some(GongDeInstruction::TransferMerit),
+                    error: StateError::InvalidInstructionData,
+                }),
+            ),
+            (stake(&program_id, &a, &b, 0), Err(invalid(GongDeInstruction::Stake, GongDeError::InvalidAmount))),
+            (unstake(&program_id, &a, &b, 0), Err(invalid(GongDeInstruction::Unstake, GongDeError::InvalidAmount))),
+            (enter_lottery(&program_id, &a, &b, &c, 0), Err(invalid(GongDeInstruction::EnterLottery, GongDeError::InvalidAmount))),
+            (create_lottery(&program_id, &a, &lottery_args), Err(invalid(GongDeInstruction::CreateLottery, GongDeError::InvalidAmount))),
+            (
+                set_beneficiary(&program_id, &a, &b, Some((c, crate::utils::MIN_BENEFICIARY_TIMEOUT_DAYS - 1))),
+                Err(invalid(GongDeInstruction::SetBeneficiary, GongDeError::InvalidAmount)),
+            ),
+            (set_emoji(&program_id, &a, &b, 0xD800), Err(invalid(GongDeInstruction::SetEmoji, GongDeError::InvalidEmoji))),
+            // 结束时间不晚于开始时间
+            (
+                create_campaign(&program_id, &a, &b, &campaign_args),
+                Err(ValidationError::InvalidArgs {
+                    instruction: Some(GongDeInstruction::CreateCampaign),
+                    error: StateError::InvalidInstructionData,
+                }),
+            ),
+            (increment_by(&program_id, &a, &b, &c, 1), Ok(())),
+        ];
+        for (instruction, expected) in cases {
+            assert_eq!(validate_args(&instruction), expected, "{:?}", instruction.data);
+        }
+
+        // 参数之后多出的字节和不完整的参数同样会被合约拒绝
+        let mut instruction = transfer_merit(&program_id, &a, &b, &c, 1);
+        instruction.data.push(0);
+        assert_eq!(
+            validate_args(&instruction),
+            Err(ValidationError::InvalidArgs {
+                instruction: Some(GongDeInstruction::TransferMerit),
+                error: StateError::InvalidInstructionData,
+            })
+        );
+        instruction.data.truncate(4);
+        assert!(matches!(
+            validate_args(&instruction),
+            Err(ValidationError::InvalidArgs { instruction: None, error: StateError::TruncatedInstructionData { .. } })
+        ));
+    }
+
+    #[test]
+    fn test_validate_spend_checks_current_merit() {
+        let program_id = Pubkey::new_unique();
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let insufficient = |instruction| ValidationError::InvalidArgs {
+            instruction: Some(instruction),
+            error: GongDeError::InsufficientMerit.into(),
+        };
+
+        let transfer = transfer_merit(&program_id, &a, &b, &c, 5);
+        assert_eq!(validate_spend(&transfer, 5), Ok(()));
+        assert_eq!(validate_spend(&transfer, 4), Err(insufficient(GongDeInstruction::TransferMerit)));
+
+        let campaign_args = CreateCampaignArgs { campaign_id: 1, start: 0, end: 60, multiplier: 1, budget: 10 };
+        let campaign = create_campaign(&program_id, &a, &b, &campaign_args);
+        assert_eq!(validate_spend(&campaign, 10), Ok(()));
+        assert_eq!(validate_spend(&campaign, 9), Err(insufficient(GongDeInstruction::CreateCampaign)));
+
+        // 回滚只能调低功德
+        let restore = restore_to(&program_id, &a, &b, 7, 0);
+        assert_eq!(validate_spend(&restore, 7), Ok(()));
+        assert_eq!(
+            validate_spend(&restore, 6),
+            Err(ValidationError::InvalidArgs {
+                instruction: Some(GongDeInstruction::RestoreTo),
+                error: GongDeError::InvalidAmount.into(),
+            })
+        );
+
+        // 不扣减功德的指令只做参数检查
+        assert_eq!(validate_spend(&increment_by(&program_id, &a, &b, &c, 5), 0), Ok(()));
+        assert!(validate_spend(&increment_by(&program_id, &a, &b, &c, 0), 100).is_err());
+    }
+}
@@ -0,0 +1,2488 @@
+// ========================================
+// 链上程序 - 入口和各指令的处理函数
+// ========================================
+// 在传统编程中，我们调用函数时会指定函数名和参数
+// 在Solana中，所有调用都通过这个统一的入口函数，通过instruction_data来区分"函数名"
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, 
+    entrypoint, 
+    entrypoint::ProgramResult, 
+    msg, 
+    program_error::ProgramError,
+    program::{invoke, invoke_signed, set_return_data},
+    log::sol_log_data,
+    pubkey::Pubkey,
+    rent::Rent,
+    clock::Clock,
+    sysvar::{self, Sysvar},
+};
+use solana_system_interface::instruction as system_instruction;
+
+use crate::error::{GongDeError, StateError};
+use crate::utils::{
+    drain_lamports,
+    move_lamports,
+    read_gongde_value, 
+    write_gongde_value, 
+    validate_account_data_size, 
+    is_public_account,
+    is_usable_account,
+    is_archived_account,
+    write_archived_flag,
+    needs_migration,
+    is_gongde_layout_size,
+    write_initialized_flags,
+    add_lifetime_total,
+    increment_merit,
+    write_lifetime_total,
+    record_milestones,
+    read_gongde_flags,
+    parse_initialize_args,
+    parse_transfer_args,
+    parse_increment_by_args,
+    validate_increment_amount,
+    parse_restore_args,
+    validate_restore_value,
+    parse_create_campaign_args,
+    parse_set_beneficiary_args,
+    day_index,
+    touch_last_active,
+    read_last_active,
+    read_beneficiary,
+    write_beneficiary,
+    read_authority,
+    write_authority,
+    beneficiary_claimable_at,
+    can_claim_as_beneficiary,
+    TransferState,
+    Endorsement,
+    accrue_merit,
+    read_last_accrued,
+    write_last_accrued,
+    parse_stake_args,
+    StakeState,
+    parse_create_lottery_args,
+    parse_enter_lottery_args,
+    LotteryRound,
+    lottery_size,
+    lottery_ticket_owner,
+    lottery_refund_due,
+    mark_lottery_refunded,
+    MAX_LOTTERY_REFUND_ACCOUNTS,
+    SLOT_HASHES_MAX_ENTRIES,
+    write_lottery_entry,
+    slot_hash_at,
+    winning_ticket,
+    Campaign,
+    RestoreEvent,
+    RESTORE_EVENT_TAG,
+    MeritChangeEvent,
+    MERIT_CHANGE_EVENT_TAG,
+    GongDeInstruction,
+    ProtocolInfo,
+    MeritReport,
+    MAX_REPORT_ACCOUNTS,
+    EndorseManyResult,
+    MAX_ENDORSE_MANY_TARGETS,
+    derive_gongde_account_address,
+    derive_global_gongde_pda_address,
+    derive_transfer_state_address,
+    derive_campaign_address,
+    derive_endorsement_address,
+    derive_accrual_state_address,
+    derive_lottery_address,
+    derive_shared_ledger_address,
+    derive_config_address,
+    get_creator_address,
+    split_client_version,
+    GlobalConfig,
+    read_global_config_copy,
+    parse_set_minimum_client_version_args,
+    parse_set_pow_difficulty_args,
+    parse_increment_with_proof_args,
+    read_last_increment_slot,
+    write_last_increment_slot,
+    parse_set_emoji_args,
+    write_emoji,
+    meets_pow_difficulty,
+    pow_hash,
+    shared_ledger_increment,
+    shared_ledger_len,
+    shared_ledger_search,
+    shared_ledger_size,
+    GONGDE_VALUE_SIZE,
+    GONGDE_ACCOUNT_SIZE,
+    GONGDE_FLAGS_OFFSET,
+    GONGDE_MILESTONES_OFFSET,
+    GONGDE_ACCOUNT_SEED,
+    GLOBAL_GONGDE_ACCOUNT_SEED,
+    TRANSFER_STATE_SEED,
+    TRANSFER_STATE_SIZE,
+    DAILY_TRANSFER_CAP,
+    CAMPAIGN_SEED,
+    CAMPAIGN_SIZE,
+    SHARED_LEDGER_SEED,
+    ENDORSEMENT_SEED,
+    ENDORSEMENT_SIZE,
+    ACCRUAL_STATE_SEED,
+    ACCRUAL_STATE_SIZE,
+    ACCRUE_SECONDS_PER_MERIT,
+    LOTTERY_SEED,
+    LOTTERY_HEADER_SIZE,
+    CONFIG_SEED,
+    CONFIG_SIZE,
+    GLOBAL_CONFIG_COPY_OFFSET,
+    GLOBAL_PDA_WITH_CONFIG_SIZE,
+    MIN_BENEFICIARY_TIMEOUT_DAYS,
+};
+use crate::instruction::InstructionSpec;
+
+// ========================================
+// 链上日志
+// 📝 msg! 带参数时要在链上跑格式化代码，每条几百CU，还会把整套格式化逻辑编进程序；
+// 默认只输出静态的模板文本，后面每个值单独一行：数值用 sol_log_64（十六进制，在最后一列），账户地址用 sol_log_pubkey。
+// 本地调试时用 `cargo build-sbf --features debug-logs` 编译，输出和模板拼好的完整中文说明
+// ========================================
+
+// 📝 用法和 msg! 一样，模板中每个 {} 依次对应一个值
+macro_rules! log_msg {
+    ($template:literal, $($value:expr),+ $(,)?) => {{
+        #[cfg(feature = "debug-logs")]
+        msg!($template, $($value),+);
+        #[cfg(not(feature = "debug-logs"))]
+        {
+            solana_program::log::sol_log($template);
+            $(log_values::LogValue::log_value(&$value);)+
+        }
+    }};
+}
+
+#[cfg(not(feature = "debug-logs"))]
+mod log_values {
+    use solana_program::{log::sol_log_64, pubkey::Pubkey};
+
+    use crate::utils::GongDeInstruction;
+
+    // 🔢 不经过格式化就能写入日志的值
+    pub trait LogValue {
+        fn log_value(&self);
+    }
+
+    macro_rules! impl_log_number {
+        ($($ty:ty),+) => {
+            $(impl LogValue for $ty {
+                fn log_value(&self) {
+                    sol_log_64(0, 0, 0, 0, *self as u64);
+                }
+            })+
+        };
+    }
+    impl_log_number!(bool, u8, u16, u32, u64, usize, i64);
+
+    impl LogValue for Pubkey {
+        fn log_value(&self) {
+            self.log();
+        }
+    }
+
+    // 📋 文字单独一行原样输出，例如指令期望的账户名
+    impl LogValue for str {
+        fn log_value(&self) {
+            solana_program::log::sol_log(self);
+        }
+    }
+
+    // 🏷️ 指令输出指令码，对照 GongDeInstruction 的定义
+    impl LogValue for GongDeInstruction {
+        fn log_value(&self) {
+            sol_log_64(0, 0, 0, 0, *self as u64);
+        }
+    }
+
+    impl<T: LogValue + ?Sized> LogValue for &T {
+        fn log_value(&self) {
+            (**self).log_value();
+        }
+    }
+}
+
+// 声明这是合约的入口点 - 类似main函数
+entrypoint!(process_instruction);
+
+// 🎯 这是合约的主入口函数，所有调用都从这里开始
+// 类比：这就像一个函数调度器，根据instruction_data决定调用哪个"函数"
+pub fn process_instruction(
+    program_id: &Pubkey,           // 🆔 合约的唯一标识（类似类名）
+    accounts: &[AccountInfo],      // 📁 函数需要操作的数据账户（类似函数参数中的对象引用）
+    instruction_data: &[u8],       // 📋 调用指令和参数数据（类似函数名+参数的编码）
+) -> ProgramResult {
+    // 🔍 指令数据解析失败时记录收到的完整长度，方便排查客户端编码问题
+    dispatch_instruction(program_id, accounts, instruction_data).inspect_err(|e| {
+        if *e == ProgramError::InvalidInstructionData {
+            log_msg!("指令数据解析失败，收到 {} 字节", instruction_data.len());
+        }
+    })
+}
+
+// 🚦 拆出客户端版本、检查指令数据长度，再分发到各指令的处理函数
+fn dispatch_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    // 🏷️ 带版本的编码最前面是客户端版本，旧客户端的编码直接以指令码开头
+    let (client_version, instruction_data) = split_client_version(instruction_data);
+
+    // 🎯 解析"函数名" - 从instruction_data解析指令类型
+    // 类比：从消息中解析出要调用的函数名
+    // 空数据、不认识的指令码和参数不完整分别说明，方便排查客户端编码问题
+    let instruction = GongDeInstruction::from_instruction_data(instruction_data).inspect_err(|e| match *e {
+        StateError::EmptyInstructionData => msg!("指令数据为空，缺少指令码"),
+        StateError::UnknownInstruction(tag) => {
+            log_msg!("不支持的指令码 {}，请先用 ProtocolVersion 指令查询合约支持的指令范围", tag);
+        }
+        StateError::TruncatedInstructionData { instruction, expected, actual } => {
+            log_msg!("{} 指令的参数不完整，数据应为 {} 字节（不含版本字段），实际 {} 字节", instruction, expected, actual);
+        }
+        _ => {}
+    })?;
+
+    // 📏 参数之后不允许有多余的字节，长度不符说明客户端和合约对编码的理解不一致
+    let expected_len = InstructionSpec::of(instruction).data_len();
+    if instruction_data.len() != expected_len {
+        log_msg!("{} 指令的数据应为 {} 字节（不含版本字段），实际 {} 字节", instruction, expected_len, instruction_data.len());
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // 🆙 带版本的指令在账户列表最后附上全局配置，去掉之后各处理函数看到的账户与旧编码完全一样；
+    // 旧编码不带版本（视为0）也不附带全局配置，从全局功德PDA中的配置副本检查（见 legacy_global_config）
+    // 版本查询和修改全局配置本身不受限制，旧客户端也能查到合约信息，创作者也不会把自己锁在外面
+    let (config, accounts) = match client_version {
+        Some(_) => split_config_account(program_id, accounts)?,
+        None => (legacy_global_config(program_id, instruction, accounts)?, accounts),
+    };
+    let client_version = client_version.unwrap_or(0);
+    let exempt = matches!(
+        instruction,
+        GongDeInstruction::ProtocolVersion | GongDeInstruction::SetMinimumClientVersion | GongDeInstruction::SetPowDifficulty
+    );
+    if !exempt {
+        config.check_client_version(client_version).inspect_err(|_| {
+            log_msg!(
+                "客户端版本 {} 低于合约要求的最低版本 {}，请升级客户端后重试",
+                client_version,
+                config.minimum_client_version
+            );
+        })?;
+    }
+
+    // 📋 账户不够时先列出这条指令期望的账户，不让处理函数读到一半才报出笼统的 NotEnoughAccountKeys
+    check_account_count(instruction, accounts)?;
+
+    // 🏷️ 版本查询不需要任何账户，直接返回
+    if instruction == GongDeInstruction::ProtocolVersion {
+        return process_protocol_version();
+    }
+
+    // ⚙️ 全局配置第一次设置时创建，不做下面的功德账户检查
+    if instruction == GongDeInstruction::SetMinimumClientVersion {
+        return process_set_minimum_client_version(program_id, accounts, instruction_data);
+    }
+    if instruction == GongDeInstruction::SetPowDifficulty {
+        return process_set_pow_difficulty(program_id, accounts, instruction_data);
+    }
+
+    // ⛏️ 开启工作量证明后，不带证明的增加功德一律拒绝，提示改用 IncrementWithProof；
+    // 旧编码同样从全局功德PDA中的配置副本读到难度，不能借旧编码绕过
+    if config.pow_enabled() && matches!(instruction, GongDeInstruction::Increment | GongDeInstruction::IncrementBy) {
+        log_msg!("已开启工作量证明（难度 {} 比特），请使用 IncrementWithProof 指令", config.pow_difficulty);
+        return Err(GongDeError::ProofOfWorkRequired.into());
+    }
+    // 共享账本的条目没有记录上次增加的slot，无法附带证明，开启期间只能用个人功德账户增加
+    if config.pow_enabled() && instruction == GongDeInstruction::SharedIncrement {
+        log_msg!("已开启工作量证明（难度 {} 比特），共享账本暂停增加，请创建个人功德账户后使用 IncrementWithProof 指令", config.pow_difficulty);
+        return Err(GongDeError::ProofOfWorkRequired.into());
+    }
+
+    // 📒 共享账本第一次使用时还不存在，不做下面的功德账户检查
+    if instruction == GongDeInstruction::SharedIncrement {
+        let amount = validate_increment_amount(parse_increment_by_args(instruction_data)?)?;
+        return process_shared_increment(program_id, accounts, amount);
+    }
+
+    // 🎰 彩票轮次在这条指令中创建，还不存在，不做下面的功德账户检查
+    if instruction == GongDeInstruction::CreateLottery {
+        return process_create_lottery(program_id, accounts, instruction_data);
+    }
+
+    // 📊 汇总只读取账户，所有账户都是只读的，不做下面的可写检查
+    if instruction == GongDeInstruction::Report {
+        return process_report(program_id, accounts);
+    }
+
+    // 🤝 批量背书的第一个账户是背书人，每个被背书的功德账户在处理时单独检查
+    if instruction == GongDeInstruction::EndorseMany {
+        return process_endorse_many(program_id, accounts);
+    }
+
+    // 📥 从传入的账户列表中获取第一个账户（功德数据账户）
+    // 类比：这就像从函数参数中取出第一个对象
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?;
+
+    // 🔒 安全检查：确保账户可以被修改
+    // 类比：检查对象是否有写权限
+    if !gongde_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // 🌱 新用户第一次增加功德时可以顺便创建功德账户
+    if matches!(
+        instruction,
+        GongDeInstruction::Increment | GongDeInstruction::IncrementBy | GongDeInstruction::IncrementWithProof
+    ) {
+        create_gongde_account_if_missing(program_id, accounts)?;
+    }
+
+    // 📏 检查数据空间是否足够（需要4字节存储u32）
+    // 类比：检查内存是否够存储数据
+    validate_account_data_size(gongde_account.data_len())?;
+
+    // 🧾 增加功德、迁移和背书会改写账户数据，先确认传入的确实是功德账户，再看标志位
+    let rewrites_gongde = matches!(
+        instruction,
+        GongDeInstruction::Increment
+            | GongDeInstruction::IncrementBy
+            | GongDeInstruction::IncrementWithProof
+            | GongDeInstruction::Migrate
+            | GongDeInstruction::Endorse
+    );
+    if rewrites_gongde && gongde_account.owner == program_id {
+        require_gongde_layout(gongde_account)?;
+    }
+
+    // 🗄️ 已归档的账户只能取消归档或关闭；开奖的第一个账户是彩票轮次，中奖账户在开奖时单独处理
+    let archive_exempt = matches!(
+        instruction,
+        GongDeInstruction::Unarchive
+            | GongDeInstruction::Close
+            | GongDeInstruction::CloseIfEmpty
+            | GongDeInstruction::SettleLottery
+    );
+    if !archive_exempt && gongde_account.owner == program_id {
+        require_not_archived(gongde_account)?;
+    }
+
+    // 🚦 根据指令类型调用对应的"函数" - 这就是函数分发
+    match instruction {
+        GongDeInstruction::Increment => process_increment(program_id, accounts, 1),
+        GongDeInstruction::IncrementBy => {
+            let amount = validate_increment_amount(parse_increment_by_args(instruction_data)?)?;
+            process_increment(program_id, accounts, amount)
+        }
+        GongDeInstruction::Close => process_close(program_id, accounts),
+        GongDeInstruction::Initialize => process_initialize(program_id, accounts, instruction_data),
+        GongDeInstruction::CloseIfEmpty => process_close_if_empty(program_id, accounts),
+        GongDeInstruction::TransferMerit => process_transfer_merit(program_id, accounts, instruction_data),
+        GongDeInstruction::Migrate => process_migrate(program_id, accounts),
+        GongDeInstruction::RestoreTo => process_restore_to(program_id, accounts, instruction_data),
+        GongDeInstruction::CreateCampaign => process_create_campaign(program_id, accounts, instruction_data),
+        GongDeInstruction::SetBeneficiary => process_set_beneficiary(program_id, accounts, instruction_data),
+        GongDeInstruction::ClaimAsBeneficiary => process_claim_as_beneficiary(program_id, accounts),
+        GongDeInstruction::Endorse => process_endorse(program_id, accounts),
+        GongDeInstruction::Accrue => process_accrue(program_id, accounts),
+        GongDeInstruction::Stake => process_stake(program_id, accounts, instruction_data),
+        GongDeInstruction::ClaimAccrued => process_claim_accrued(program_id, accounts),
+        GongDeInstruction::Unstake => process_unstake(program_id, accounts, instruction_data),
+        GongDeInstruction::EnterLottery => process_enter_lottery(program_id, accounts, instruction_data),
+        GongDeInstruction::SettleLottery => process_settle_lottery(program_id, accounts),
+        GongDeInstruction::Archive => process_set_archived(program_id, accounts, true),
+        GongDeInstruction::Unarchive => process_set_archived(program_id, accounts, false),
+        GongDeInstruction::SwapCounts => process_swap_counts(program_id, accounts),
+        GongDeInstruction::IncrementWithProof => process_increment_with_proof(program_id, accounts, &config, instruction_data),
+        GongDeInstruction::SetEmoji => process_set_emoji(program_id, accounts, instruction_data),
+        GongDeInstruction::ProtocolVersion
+        | GongDeInstruction::SharedIncrement
+        | GongDeInstruction::CreateLottery
+        | GongDeInstruction::SetMinimumClientVersion
+        | GongDeInstruction::SetPowDifficulty
+        | GongDeInstruction::Report
+        | GongDeInstruction::EndorseMany => {
+            unreachable!("版本查询、共享账本、创建彩票、修改全局配置、汇总和批量背书已在账户检查前处理")
+        }
+    }
+}
+
+// 📋 账户数量检查：必需账户按 InstructionSpec 中的顺序列出名字，客户端开发者对照就能发现漏传了哪个；
+// 可选账户和批量指令的成对账户由处理函数自己检查
+fn check_account_count(instruction: GongDeInstruction, accounts: &[AccountInfo]) -> ProgramResult {
+    let expected = InstructionSpec::of(instruction).accounts;
+    if accounts.len() < expected.len() {
+        let names = expected.iter().map(|account| account.name).collect::<Vec<_>>().join(", ");
+        log_msg!(
+            "{} 指令需要 {} 个账户，实际只传了 {} 个，依次为: {}",
+            instruction,
+            expected.len(),
+            accounts.len(),
+            names.as_str()
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    Ok(())
+}
+
+// 🔢 函数名：increment() - 增加功德并支付创作者手续费
+// 📈 increment_by(amount) 复用同一逻辑，一次增加 amount 点功德，手续费按次数计算
+// 🎉 系统程序之后可以再传一个配捐活动账户，活动进行中时额外从奖池获得功德
+// 🌱 再传入签名的账户主人时，账户不存在会先创建（见 create_gongde_account_if_missing）
+fn process_increment(program_id: &Pubkey, accounts: &[AccountInfo], amount: u32) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?;
+    require_program_owned(program_id, gongde_account)?;
+
+    // 📖 读取当前的功德值（使用工具函数）
+    let mut data = gongde_account.data.borrow_mut();
+    let current = read_gongde_value(&data)?;
+
+    // 🛡️ 未初始化或已关闭（数据被清零）的账户不能增加功德，防止同一交易内转回租金复活账户
+    if !is_usable_account(&data) {
+        msg!("功德账户未初始化或已关闭");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    
+    // ⚠️ 检查是否已达到最大值，如果是则直接结束，不再增加（规则与客户端预测共用 increment_merit）
+    let Some(mut new_value) = increment_merit(current, amount) else {
+        msg!("功德圆满");
+        return Ok(());
+    };
+    
+    let user_account = next_account_info(accounts_iter)?; // 用户账户（支付手续费）
+    let global_pda_account = next_account_info(accounts_iter)?; // 全局PDA功德账户（可写）
+    let system_program = next_account_info(accounts_iter)?; // 系统程序
+
+    // 🌍 全局PDA的大小和旧的4字节功德账户一样，按地址区分，不能当作个人功德账户增加
+    if gongde_account.key == global_pda_account.key {
+        msg!("全局PDA不是个人功德账户");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // 🔐 私有功德箱只允许账户主人本人增加功德，公开功德箱任何人都可以
+    let by_authority = user_account.is_signer && is_gongde_authority(program_id, gongde_account.key, &data, user_account.key)?;
+    if !is_public_account(&data) && !by_authority {
+        if !user_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        msg!("私有功德箱只能由主人增加功德");
+        return Err(ProgramError::IllegalOwner);
+    }
+    
+    // 💰 手续费直接转到全局PDA账户
+    charge_increment_fee(program_id, user_account, global_pda_account, system_program, amount)?;
+    
+    // 🎉 配捐：活动未开始、已结束或奖池耗尽时跳过，不影响本次增加
+    let (campaign_account, owner) = optional_increment_accounts(accounts_iter.as_slice())?;
+    if let Some(campaign_account) = campaign_account {
+        // 只有本合约能写入自己的账户，归本合约所有且大小为 CAMPAIGN_SIZE 的账户就是活动账户
+        if campaign_account.key == gongde_account.key
+            || campaign_account.owner != program_id
+            || campaign_account.data_len() != CAMPAIGN_SIZE
+            || !campaign_account.is_writable
+        {
+            msg!("配捐活动账户无效");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut campaign_data = campaign_account.data.borrow_mut();
+        let mut campaign = Campaign::unpack(&campaign_data)?;
+        let bonus = campaign.take_bonus(Clock::get()?.unix_timestamp, amount, u32::MAX - new_value);
+        if bonus > 0 {
+            campaign.pack(&mut campaign_data)?;
+            new_value += bonus;
+            log_msg!("配捐功德: {}，奖池剩余: {}", bonus, campaign.remaining);
+        } else {
+            msg!("配捐活动不在进行中或奖池已耗尽，跳过配捐");
+        }
+    }
+    
+    // 💾 将新值写回账户数据（使用工具函数），累计功德同步增加，回滚和转出都不会减少它
+    // 配捐是从奖池转来的功德，和转赠一样不计入累计功德
+    write_gongde_value(&mut data, new_value)?;
+    emit_merit_change(gongde_account.key, current, new_value);
+    add_lifetime_total(&mut data, amount)?;
+
+    // 🏁 第一次跨过里程碑时记录达成时间
+    let now = Clock::get()?.unix_timestamp;
+    for milestone in record_milestones(&mut data, current, new_value, now) {
+        log_msg!("达成功德里程碑: {}", milestone);
+    }
+
+    // ⏱️ 账户主人本人增加功德才算一次操作，别人往公开功德箱里增加不会推迟受益人的等待期
+    let owner_signed = match owner {
+        Some(owner) => is_gongde_authority(program_id, gongde_account.key, &data, owner.key)?,
+        None => false,
+    };
+    if by_authority || owner_signed {
+        touch_last_active(&mut data, now);
+    }
+    
+    // 🌍 同时增加全局功德账户
+    add_global_merit(global_pda_account, amount)?;
+    
+    // 📢 输出日志
+    log_msg!("个人功德: {}", new_value);
+
+    Ok(())
+}
+
+// 📎 Increment / IncrementBy 在系统程序之后的可选账户：配捐活动（不签名）和账户主人（签名），
+// 两者都可以单独省略，同时传入时活动在前；配捐活动是PDA不可能签名，所以按签名标记区分
+fn optional_increment_accounts<'b, 'a>(
+    extra: &'b [AccountInfo<'a>],
+) -> Result<(Option<&'b AccountInfo<'a>>, Option<&'b AccountInfo<'a>>), ProgramError> {
+    match extra {
+        [] => Ok((None, None)),
+        [owner] if owner.is_signer => Ok((None, Some(owner))),
+        [campaign] => Ok((Some(campaign), None)),
+        [campaign, owner] if !campaign.is_signer && owner.is_signer => Ok((Some(campaign), Some(owner))),
+        _ => {
+            msg!("系统程序之后只能依次传入配捐活动账户和账户主人");
+            Err(ProgramError::InvalidArgument)
+        }
+    }
+}
+
+// 🌱 功德账户不存在时先创建再增加：新用户的第一笔交易只需要一条 increment，
+// 在系统程序之后传入账户主人（签名）即可，不传时账户不存在照旧失败；创建的是公开功德箱，与客户端默认一致
+// 🛡️ 有人抢先往这个地址转账时账户已经有余额，create_account 会失败；这时改为补足租金后分配空间并指定所有者，
+// 抢先转入的 lamports 留在账户里，无法借此阻止用户创建账户；分配和指定所有者都需要账户主人签名，别人无法抢先创建
+fn create_gongde_account_if_missing(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let [gongde_account, payer, _global_pda_account, system_program, extra @ ..] = accounts else {
+        return Ok(());
+    };
+    // 已存在的账户（包括旧布局和已关闭的账户）走原来的逻辑
+    if gongde_account.owner != &solana_system_interface::program::id() || gongde_account.data_len() != 0 {
+        return Ok(());
+    }
+    let (_campaign, owner) = optional_increment_accounts(extra)?;
+    let Some(owner) = owner else {
+        return Ok(());
+    };
+
+    // 🔍 只能为签名的账户主人创建他自己的功德账户
+    let expected_gongde = derive_gongde_account_address(owner.key, program_id)?;
+    if gongde_account.key != &expected_gongde {
+        msg!("账户主人与功德账户地址不匹配");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let required_lamports = Rent::get()?.minimum_balance(GONGDE_ACCOUNT_SIZE);
+    if gongde_account.lamports() == 0 {
+        invoke(
+            &system_instruction::create_account_with_seed(
+                payer.key,
+                gongde_account.key,
+                owner.key,
+                GONGDE_ACCOUNT_SEED,
+                required_lamports,
+                GONGDE_ACCOUNT_SIZE as u64,
+                program_id,
+            ),
+            &[payer.clone(), gongde_account.clone(), owner.clone(), system_program.clone()],
+        )?;
+    } else {
+        log_msg!("功德账户地址已有 {} lamports，补足租金后分配空间", gongde_account.lamports());
+        let deficit = required_lamports.saturating_sub(gongde_account.lamports());
+        if deficit > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, gongde_account.key, deficit),
+                &[payer.clone(), gongde_account.clone(), system_program.clone()],
+            )?;
+        }
+        invoke(
+            &system_instruction::allocate_with_seed(
+                gongde_account.key,
+                owner.key,
+                GONGDE_ACCOUNT_SEED,
+                GONGDE_ACCOUNT_SIZE as u64,
+                program_id,
+            ),
+            &[gongde_account.clone(), owner.clone(), system_program.clone()],
+        )?;
+        invoke(
+            &system_instruction::assign_with_seed(gongde_account.key, owner.key, GONGDE_ACCOUNT_SEED, program_id),
+            &[gongde_account.clone(), owner.clone(), system_program.clone()],
+        )?;
+    }
+
+    let mut data = gongde_account.data.borrow_mut();
+    write_initialized_flags(&mut data, true)?;
+    touch_last_active(&mut data, Clock::get()?.unix_timestamp);
+    msg!("功德账户不存在，已创建公开功德箱");
+    Ok(())
+}
+
+// 🏗️ 由本合约签名创建PDA账户，租金由付款人支付；调用前确认账户仍归系统程序所有
+// 🛡️ PDA地址谁都能算出来，有人抢先转入1 lamport 就能让 create_account 失败；
+// 和 create_gongde_account_if_missing 一样，有余额时改为补足租金后分配空间并指定所有者，抢先转入的 lamports 留在账户里
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    size: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let required_lamports = Rent::get()?.minimum_balance(size);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, required_lamports, size as u64, program_id),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[signer_seeds],
+        );
+    }
+
+    log_msg!("PDA账户地址已有 {} lamports，补足租金后分配空间", account.lamports());
+    let deficit = required_lamports.saturating_sub(account.lamports());
+    if deficit > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, deficit),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, size as u64),
+        &[account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+// 💰 增加功德的手续费：每点功德5000 lamports，由付款人转到全局PDA账户，全局PDA不存在时先创建
+// Increment / IncrementBy / SharedIncrement 共用
+fn charge_increment_fee<'a>(
+    program_id: &Pubkey,
+    user_account: &AccountInfo<'a>,
+    global_pda_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    amount: u32,
+) -> ProgramResult {
+    let fee_amount = 5000u64 * u64::from(amount); // 手续费：每点功德5000 lamports（约0.000005 SOL）
+    
+    // 🔍 验证全局PDA账户地址是否正确
+    let (expected_global_pda, bump) = derive_global_gongde_pda_address(program_id)?;
+    if global_pda_account.key != &expected_global_pda {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    // 验证全局账户可写
+    if !global_pda_account.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    // 🏗️ 检查全局PDA账户是否需要初始化（有人抢先转账时账户有余额但仍归系统程序所有）
+    if global_pda_account.owner == &solana_system_interface::program::id() {
+        msg!("初始化全局PDA账户");
+        
+        // 使用PDA签名来创建账户，租金由付款者支付
+        let signers_seeds = &[GLOBAL_GONGDE_ACCOUNT_SEED.as_bytes(), &[bump]];
+        create_pda_account(program_id, user_account, global_pda_account, system_program, GONGDE_VALUE_SIZE, signers_seeds)?;
+        
+        // 初始化数据为0
+        let mut global_data = global_pda_account.data.borrow_mut();
+        write_gongde_value(&mut global_data, 0)?;
+        msg!("全局PDA账户初始化完成");
+    }
+    
+    // 验证系统程序
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // 用户账户必须有足够余额支付手续费
+    if user_account.lamports() < fee_amount {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    
+    // 使用系统程序将手续费转账到全局PDA账户
+    let transfer_instruction = system_instruction::transfer(
+        user_account.key,
+        global_pda_account.key,
+        fee_amount,
+    );
+    
+    invoke(
+        &transfer_instruction,
+        &[
+            user_account.clone(),
+            global_pda_account.clone(),
+            system_program.clone(),
+        ],
+    )?;
+    
+    log_msg!("用户支付手续费: {} lamports 到全局账户", fee_amount);
+    Ok(())
+}
+
+// 🌍 增加全局功德，达到上限后不再增加
+fn add_global_merit(global_pda_account: &AccountInfo, amount: u32) -> ProgramResult {
+    // 检查全局账户数据大小
+    validate_account_data_size(global_pda_account.data_len())?;
+    
+    // 读取全局功德值
+    let mut global_data = global_pda_account.data.borrow_mut();
+    let current_global = read_gongde_value(&global_data)?;
+    
+    // 检查是否已达到最大值
+    match increment_merit(current_global, amount) {
+        Some(new_global_value) => {
+            write_gongde_value(&mut global_data, new_global_value)?;
+            emit_merit_change(global_pda_account.key, current_global, new_global_value);
+            log_msg!("全局功德: {}", new_global_value);
+        }
+        None => msg!("全局功德已圆满"),
+    }
+    Ok(())
+}
+
+// 📜 功德值变化事件：每次改写功德账户或全局PDA的功德值后调用
+// 交易元数据里没有账户数据，链下据此知道一笔交易改了哪些账户、改前改后的值
+fn emit_merit_change(account: &Pubkey, before: u32, after: u32) {
+    let event = MeritChangeEvent { account: *account, before, after };
+    sol_log_data(&[MERIT_CHANGE_EVENT_TAG, &event.pack()]);
+}
+
+// 🗑️ 函数名：close() - 关闭账户并回收租金
+// 类比：调用 gongde.close(user) 方法
+// 功德账户是 create_account_with_seed 创建的种子地址而不是PDA，合约不需要签名，
+// 账户归本合约所有，合约可以直接扣减余额；只要确认地址由签名用户派生即可
+fn process_close(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?;
+
+    // 👤 获取第二个账户参数（用户账户，接收退款）
+    let user = next_account_info(accounts_iter)?;
+    
+    // ✍️ 验证用户是否为交易签名者（权限检查）
+    // 类比：验证用户是否有删除权限
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // 🔍 只能关闭自己的账户：地址必须是 create_with_seed(用户, 种子, 程序ID)（被受益人接管后是接管人），且归本合约所有
+    if !is_gongde_authority(program_id, gongde_account.key, &gongde_account.data.borrow(), user.key)? {
+        msg!("只能关闭自己的功德账户");
+        return Err(ProgramError::IllegalOwner);
+    }
+    require_program_owned(program_id, gongde_account)?;
+
+    close_gongde_account(gongde_account, user)
+}
+
+// 🧺 函数名：close_if_empty() - 只关闭功德值为0的账户
+// 类比：清理工具只删除空对象，防止误删有功德的账户
+fn process_close_if_empty(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+
+    // ✍️ 必须由账户主人签名
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    if !is_gongde_authority(program_id, gongde_account.key, &gongde_account.data.borrow(), user.key)? {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // 🔍 只有功德值为0才允许关闭
+    let current = read_gongde_value(&gongde_account.data.borrow())?;
+    if current != 0 {
+        log_msg!("功德账户不为空，当前功德: {}", current);
+        return Err(GongDeError::NotEmpty.into());
+    }
+
+    close_gongde_account(gongde_account, user)
+}
+
+// 🧹 关闭账户的公共逻辑：租金转给用户并清空数据
+fn close_gongde_account(gongde_account: &AccountInfo, user: &AccountInfo) -> ProgramResult {
+    // 💰 将功德账户的所有租金转移给用户
+    // 类比：退还押金给用户
+    drain_lamports(gongde_account, user)?;
+
+    // 🧹 清空账户数据（相当于删除对象）
+    let mut data = gongde_account.data.borrow_mut();
+    data.fill(0);
+
+    msg!("功德账户关闭成功，租金已返还");
+
+    Ok(())
+}
+
+// 📏 写入当前布局的字段前检查账户大小：旧布局账户少了后面的字段，直接写入会失败或只写一半
+// 🗄️ 已归档的账户不能修改，转赠、合并等指令中的第二个功德账户也要检查
+fn require_not_archived(gongde_account: &AccountInfo) -> ProgramResult {
+    if is_archived_account(&gongde_account.data.borrow()) {
+        log_msg!("功德账户 {} 已归档，请先发送 Unarchive 指令取消归档", gongde_account.key);
+        return Err(GongDeError::AccountArchived.into());
+    }
+    Ok(())
+}
+
+fn require_current_layout(gongde_account: &AccountInfo) -> ProgramResult {
+    let data_len = gongde_account.data_len();
+    if data_len < GONGDE_ACCOUNT_SIZE {
+        log_msg!(
+            "功德账户数据只有 {} 字节，当前布局需要 {} 字节，请先发送 Migrate 指令迁移",
+            data_len,
+            GONGDE_ACCOUNT_SIZE
+        );
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    Ok(())
+}
+
+// 🧾 共享账本、配捐活动、彩票等PDA也归本合约所有，大小不是任何一个布局版本的功德账户就拒绝，
+// 防止增加功德或迁移把它们当作功德账户改写
+fn require_gongde_layout(gongde_account: &AccountInfo) -> ProgramResult {
+    if !is_gongde_layout_size(gongde_account.data_len()) {
+        log_msg!("账户 {} 的数据有 {} 字节，不是功德账户", gongde_account.key, gongde_account.data_len());
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// 🎯 转赠、背书这类给别人加功德的指令，目标账户不需要签名，攻击者可以填入任意一个本合约的账户；
+// 除了大小，还要按地址排除全局功德PDA：没有设置全局配置时它和旧的4字节功德账户一样大。
+// 转赠状态、配捐活动、共享账本、彩票、背书记录、质押状态和全局配置的大小都不是功德账户布局，由大小检查拒绝
+fn require_gongde_target(program_id: &Pubkey, target: &AccountInfo) -> ProgramResult {
+    require_gongde_layout(target)?;
+    // 只有4字节的账户可能是全局功德PDA，其他大小不必花计算单元派生地址
+    if target.data_len() == GONGDE_VALUE_SIZE {
+        let (global_pda, _bump) = derive_global_gongde_pda_address(program_id)?;
+        if target.key == &global_pda {
+            msg!("全局PDA不是个人功德账户");
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    Ok(())
+}
+
+// 🏷️ 功德账户必须归当前程序所有：同一份合约可以部署在多个程序ID下（新旧版本并存），
+// 账户地址由程序ID派生，别的版本创建的账户只能由它自己的程序修改，这里拒绝跨版本操作
+fn require_program_owned(program_id: &Pubkey, gongde_account: &AccountInfo) -> ProgramResult {
+    if gongde_account.owner != program_id {
+        log_msg!("功德账户 {} 归程序 {} 所有，不是当前程序 {}，可能是其他版本创建的", gongde_account.key, gongde_account.owner, program_id);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+// 👤 signer 是否为功德账户的主人：被受益人接管的账户是记录的接管人，否则是派生出账户地址的用户
+// 接管后原来的用户即使还能派生出同一个地址，也不再是账户主人
+fn is_gongde_authority(program_id: &Pubkey, gongde_key: &Pubkey, data: &[u8], signer: &Pubkey) -> Result<bool, ProgramError> {
+    match read_authority(data) {
+        Some(authority) => Ok(&authority == signer),
+        None => Ok(gongde_key == &derive_gongde_account_address(signer, program_id)?),
+    }
+}
+
+// 🏷️ 函数名：initialize(is_public) - 初始化账户并设置是否公开
+// 类比：构造函数，只能由账户主人调用一次
+fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+
+    // 📋 解析参数：是否为公开功德箱
+    let is_public = parse_initialize_args(instruction_data)?;
+
+    // ✍️ 只有账户主人可以初始化
+    if !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // 🔍 账户地址必须由该用户派生，且归本合约所有
+    let expected_gongde = derive_gongde_account_address(user.key, program_id)?;
+    if gongde_account.key != &expected_gongde {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // ♻️ 关闭后的账户在交易结束时被运行时回收（余额为0、归系统程序所有），
+    // 用 create_account_with_seed 在同一地址重新创建后即可再次初始化，数据全新，功德从0开始
+    require_program_owned(program_id, gongde_account)?;
+
+    // 📏 写入前确认账户是当前布局的大小，旧布局的账户提示先迁移
+    require_current_layout(gongde_account)?;
+
+    let mut data = gongde_account.data.borrow_mut();
+    write_initialized_flags(&mut data, is_public)?;
+    touch_last_active(&mut data, Clock::get()?.unix_timestamp);
+
+    log_msg!("功德账户初始化完成，公开: {}", is_public);
+    Ok(())
+}
+
+// 🏷️ 函数名：protocol_version() - 返回合约支持的协议版本和指令范围
+// 类比：API的 /version 接口，客户端发送新指令前先确认合约是否支持
+fn process_protocol_version() -> ProgramResult {
+    set_return_data(&ProtocolInfo::CURRENT.to_bytes());
+    Ok(())
+}
+
+// 🆙 带版本的指令把全局配置附在账户列表最后，拆出来读取最低客户端版本
+// 全局配置还没有设置过时账户不存在，等同于不做限制
+fn split_config_account<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<(GlobalConfig, &'a [AccountInfo<'info>]), ProgramError> {
+    let (config_account, accounts) = accounts.split_last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (expected_config, _bump) = derive_config_address(program_id);
+    if config_account.key != &expected_config {
+        log_msg!("带版本的指令必须在账户列表最后附上全局配置 {}", expected_config);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let config = if config_account.owner == program_id {
+        GlobalConfig::unpack(&config_account.data.borrow())?
+    } else {
+        GlobalConfig::default()
+    };
+    Ok((config, accounts))
+}
+
+// 🪞 旧编码的指令不附带全局配置PDA；带着全局功德PDA的指令（增加功德、共享账本）读取其中的配置副本，
+// 修改全局配置时同步写入（见 update_global_config）。其他旧编码的指令没有可读的配置，按默认配置处理；
+// 全局功德PDA地址不对时按默认配置处理，由处理函数报错
+fn legacy_global_config(
+    program_id: &Pubkey,
+    instruction: GongDeInstruction,
+    accounts: &[AccountInfo],
+) -> Result<GlobalConfig, ProgramError> {
+    let Some(global_pda_account) = InstructionSpec::of(instruction).global_pda_index().and_then(|index| accounts.get(index))
+    else {
+        return Ok(GlobalConfig::default());
+    };
+    let (expected_global_pda, _bump) = derive_global_gongde_pda_address(program_id)?;
+    if global_pda_account.key != &expected_global_pda || global_pda_account.owner != program_id {
+        return Ok(GlobalConfig::default());
+    }
+    Ok(read_global_config_copy(&global_pda_account.data.borrow())?)
+}
+
+// 🎁 函数名：transfer_merit(amount) - 把自己的功德转赠给别人
+// 类比：转账，但每天转出的数量有上限，防止来回倒腾功德刷排行
+fn process_transfer_merit(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let sender_gongde = next_account_info(accounts_iter)?;   // 转出者的功德账户（可写）
+    let sender = next_account_info(accounts_iter)?;          // 转出者（签名者，支付状态账户租金）
+    let recipient_gongde = next_account_info(accounts_iter)?; // 接收者的功德账户（可写）
+    let transfer_state = next_account_info(accounts_iter)?;  // 转出者的转赠状态PDA（可写）
+    let system_program = next_account_info(accounts_iter)?;  // 系统程序
+
+    let amount = parse_transfer_args(instruction_data)?;
+
+    // ✍️ 只能转出自己的功德
+    if !sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, sender_gongde)?;
+    if !is_gongde_authority(program_id, sender_gongde.key, &sender_gongde.data.borrow(), sender.key)? {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // 🔍 接收者必须是本合约已初始化的功德账户，不能转给自己
+    if recipient_gongde.key == sender_gongde.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if recipient_gongde.owner != program_id || !recipient_gongde.is_writable {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    require_gongde_target(program_id, recipient_gongde)?;
+    if !is_usable_account(&sender_gongde.data.borrow()) || !is_usable_account(&recipient_gongde.data.borrow()) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    require_not_archived(recipient_gongde)?;
+
+    // 📅 检查并更新转出者的每日额度，状态账户在第一次转赠时创建
+    let (expected_state, bump) = derive_transfer_state_address(sender.key, program_id);
+    if transfer_state.key != &expected_state {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if transfer_state.owner == &solana_system_interface::program::id() {
+        create_pda_account(
+            program_id,
+            sender,
+            transfer_state,
+            system_program,
+            TRANSFER_STATE_SIZE,
+            &[TRANSFER_STATE_SEED, sender.key.as_ref(), &[bump]],
+        )?;
+    } else if transfer_state.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let today = day_index(now);
+    let mut state_data = transfer_state.data.borrow_mut();
+    let mut state = TransferState::unpack(&state_data)?;
+    state.record_transfer(today, amount, DAILY_TRANSFER_CAP).inspect_err(|_| {
+        log_msg!("今日转赠额度不足，已转出: {}，额度: {}", state.transferred_today, DAILY_TRANSFER_CAP);
+    })?;
+    state.pack(&mut state_data)?;
+
+    // 💸 扣减转出者功德，增加接收者功德；接收不受额度限制
+    let mut sender_data = sender_gongde.data.borrow_mut();
+    let sender_value = read_gongde_value(&sender_data)?;
+    if sender_value < amount {
+        log_msg!("功德不足，当前功德: {}，转出: {}", sender_value, amount);
+        return Err(GongDeError::InsufficientMerit.into());
+    }
+    let mut recipient_data = recipient_gongde.data.borrow_mut();
+    let recipient_value = read_gongde_value(&recipient_data)?
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    write_gongde_value(&mut sender_data, sender_value - amount)?;
+    write_gongde_value(&mut recipient_data, recipient_value)?;
+    touch_last_active(&mut sender_data, now);
+    emit_merit_change(sender_gongde.key, sender_value, sender_value - amount);
+    emit_merit_change(recipient_gongde.key, recipient_value - amount, recipient_value);
+
+    log_msg!("转赠功德: {}，今日已转出: {}/{}", amount, state.transferred_today, DAILY_TRANSFER_CAP);
+    Ok(())
+}
+
+// 🔄 函数名：migrate() - 把旧布局的账户升级到当前布局
+// 类比：数据库迁移，4字节账户本来就是公开的，迁移后标记为已初始化的公开功德箱；5字节账户保留原来的标志位
+// 功德值保持不变，旧账户没有记录累计功德，迁移时以当前功德值作为累计功德的起点；已有累计功德的账户保留原值
+// 迁移前达成的里程碑无从得知，里程碑记录从空白开始；最后操作时间记为迁移时间，没有受益人
+// 迁移不改变账户的行为，所以任何人都可以代为迁移，补足的租金由付款人支付；
+// 但要传入派生出账户地址的用户（不需要签名），证明这是用户的功德账户，全局功德PDA、共享账本等其他账户不能迁移
+fn process_migrate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 待迁移的功德账户（可写）
+    let payer = next_account_info(accounts_iter)?;          // 付款人（签名者，支付租金差额）
+    let system_program = next_account_info(accounts_iter)?; // 系统程序
+    let owner = next_account_info(accounts_iter)?;          // 派生出功德账户地址的用户（不需要签名）
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    if gongde_account.key != &derive_gongde_account_address(owner.key, program_id)? {
+        msg!("功德账户地址不是由传入的用户派生的，只能迁移用户的功德账户");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // ✅ 已经是当前布局，重复迁移直接跳过，方便客户端批量发送
+    if !needs_migration(gongde_account.data_len()) {
+        msg!("功德账户已是最新布局，无需迁移");
+        return Ok(());
+    }
+
+    // 💰 扩容后需要更多租金，差额由付款人补足
+    let required_lamports = Rent::get()?.minimum_balance(GONGDE_ACCOUNT_SIZE);
+    let deficit = required_lamports.saturating_sub(gongde_account.lamports());
+    if deficit > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, gongde_account.key, deficit),
+            &[payer.clone(), gongde_account.clone(), system_program.clone()],
+        )?;
+    }
+
+    let had_flags = gongde_account.data_len() > GONGDE_FLAGS_OFFSET;
+    let had_lifetime = gongde_account.data_len() >= GONGDE_MILESTONES_OFFSET;
+    gongde_account.resize(GONGDE_ACCOUNT_SIZE)?;
+    let mut data = gongde_account.data.borrow_mut();
+    if !had_flags {
+        write_initialized_flags(&mut data, true)?;
+    }
+    let value = read_gongde_value(&data)?;
+    if !had_lifetime {
+        write_lifetime_total(&mut data, u64::from(value))?;
+    }
+    // 旧账户没有记录最后操作时间，从迁移时开始计算受益人的等待期
+    touch_last_active(&mut data, Clock::get()?.unix_timestamp);
+
+    log_msg!("功德账户迁移完成，功德: {}，标志位: {}，补足租金: {}", value, read_gongde_flags(&data), deficit);
+    Ok(())
+}
+
+// ⏪ 函数名：restore_to(value, checkpoint) - 把功德值回滚到检查点记录的值
+// 类比：数据库按备份回滚，用于修复bug导致的功德虚高；只能调低，全局功德不受影响
+// 每次回滚都通过 sol_log_data 发出 RestoreEvent，链上日志即审计记录
+fn process_restore_to(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let owner = next_account_info(accounts_iter)?;          // 账户主人（签名者）
+
+    let (value, checkpoint) = parse_restore_args(instruction_data)?;
+
+    // ✍️ 只有账户主人可以回滚
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !is_gongde_authority(program_id, gongde_account.key, &gongde_account.data.borrow(), owner.key)? {
+        msg!("只能回滚自己的功德账户");
+        return Err(ProgramError::IllegalOwner);
+    }
+    require_program_owned(program_id, gongde_account)?;
+
+    // 🕰️ 检查点必须是已经发生过的时间
+    let now = Clock::get()?.unix_timestamp;
+    if checkpoint > now {
+        log_msg!("检查点时间 {} 晚于当前时间", checkpoint);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut data = gongde_account.data.borrow_mut();
+    if !is_usable_account(&data) {
+        msg!("功德账户未初始化或已关闭");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let previous = read_gongde_value(&data)?;
+    let restored = validate_restore_value(value, previous).inspect_err(|_| {
+        log_msg!("回滚只能调低功德，当前功德: {}，恢复值: {}", previous, value);
+    })?;
+    write_gongde_value(&mut data, restored)?;
+    emit_merit_change(gongde_account.key, previous, restored);
+    touch_last_active(&mut data, now);
+
+    // 📜 审计事件
+    let event = RestoreEvent { gongde: *gongde_account.key, previous, restored, checkpoint };
+    sol_log_data(&[RESTORE_EVENT_TAG, &event.pack()]);
+
+    log_msg!("功德回滚: {} → {}，检查点: {}", previous, restored, checkpoint);
+
+    // ↩️ 回滚后的功德值写入 return data，客户端不用再查询账户就能确认回滚真的生效
+    set_return_data(&restored.to_le_bytes());
+    Ok(())
+}
+
+// 🎉 函数名：create_campaign(id, start, end, multiplier, budget) - 发起配捐活动
+// 类比：募捐活动的配捐基金，发起人先从自己的功德中划出奖池，活动期间别人每增加1点功德就从奖池配捐 multiplier 点
+// 奖池只能来自发起人已有的功德，配捐不会凭空产生功德
+fn process_create_campaign(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let organizer_gongde = next_account_info(accounts_iter)?; // 发起人的功德账户（可写）
+    let organizer = next_account_info(accounts_iter)?;        // 发起人（签名者，支付活动账户租金）
+    let campaign_account = next_account_info(accounts_iter)?; // 配捐活动PDA（可写）
+    let system_program = next_account_info(accounts_iter)?;   // 系统程序
+
+    let args = parse_create_campaign_args(instruction_data)?;
+
+    // ✍️ 只能用自己的功德设立奖池
+    if !organizer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !is_gongde_authority(program_id, organizer_gongde.key, &organizer_gongde.data.borrow(), organizer.key)? {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&organizer_gongde.data.borrow()) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let (expected_campaign, bump) = derive_campaign_address(organizer.key, args.campaign_id, program_id);
+    if campaign_account.key != &expected_campaign {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 有人抢先转账时地址上有余额但账户仍归系统程序所有，不算已使用
+    if campaign_account.owner == program_id || !campaign_account.data_is_empty() {
+        log_msg!("活动编号 {} 已被使用", args.campaign_id);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 💸 先确认功德足够再创建账户
+    let mut organizer_data = organizer_gongde.data.borrow_mut();
+    let organizer_value = read_gongde_value(&organizer_data)?;
+    if organizer_value < args.budget {
+        log_msg!("功德不足，当前功德: {}，奖池: {}", organizer_value, args.budget);
+        return Err(GongDeError::InsufficientMerit.into());
+    }
+
+    create_pda_account(
+        program_id,
+        organizer,
+        campaign_account,
+        system_program,
+        CAMPAIGN_SIZE,
+        &[CAMPAIGN_SEED, organizer.key.as_ref(), &args.campaign_id.to_le_bytes(), &[bump]],
+    )?;
+
+    let campaign = Campaign {
+        organizer: *organizer.key,
+        start: args.start,
+        end: args.end,
+        multiplier: args.multiplier,
+        remaining: args.budget,
+    };
+    campaign.pack(&mut campaign_account.data.borrow_mut())?;
+    write_gongde_value(&mut organizer_data, organizer_value - args.budget)?;
+    emit_merit_change(organizer_gongde.key, organizer_value, organizer_value - args.budget);
+    touch_last_active(&mut organizer_data, Clock::get()?.unix_timestamp);
+
+    log_msg!("配捐活动已创建: 倍数 {}，奖池 {}，时间 [{}, {})", args.multiplier, args.budget, args.start, args.end);
+    Ok(())
+}
+
+// 📒 函数名：shared_increment(amount) - 在共享账本中给自己增加功德
+// 类比：所有人共用一张表，每个用户占一行，而不是每人一个文件
+// 新用户的那一行由本人支付租金（约36字节），比单独开一个账户便宜得多；手续费和全局功德与 increment() 相同
+fn process_shared_increment(program_id: &Pubkey, accounts: &[AccountInfo], amount: u32) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let ledger_account = next_account_info(accounts_iter)?;     // 共享账本PDA（可写）
+    let user_account = next_account_info(accounts_iter)?;       // 用户（签名者，支付手续费和新条目的租金）
+    let global_pda_account = next_account_info(accounts_iter)?; // 全局PDA功德账户（可写）
+    let system_program = next_account_info(accounts_iter)?;     // 系统程序
+
+    // ✍️ 只能给自己的条目增加功德
+    if !user_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (expected_ledger, bump) = derive_shared_ledger_address(program_id);
+    if ledger_account.key != &expected_ledger || !ledger_account.is_writable {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 🏗️ 共享账本第一次使用时创建，只有头部；下面马上加入第一个条目，账本不会停留在和旧功德账户一样的4字节
+    let rent = Rent::get()?;
+    if ledger_account.owner == &solana_system_interface::program::id() {
+        create_pda_account(
+            program_id,
+            user_account,
+            ledger_account,
+            system_program,
+            shared_ledger_size(0),
+            &[SHARED_LEDGER_SEED, &[bump]],
+        )?;
+        msg!("共享账本初始化完成");
+    } else if ledger_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 📏 新用户：账本扩容一个条目，补足租金差额
+    let is_new_user = shared_ledger_search(&ledger_account.data.borrow(), user_account.key)?.is_err();
+    if is_new_user {
+        let new_size = shared_ledger_size(shared_ledger_len(&ledger_account.data.borrow())? + 1);
+        let shortfall = rent.minimum_balance(new_size).saturating_sub(ledger_account.lamports());
+        if shortfall > 0 {
+            invoke(
+                &system_instruction::transfer(user_account.key, ledger_account.key, shortfall),
+                &[user_account.clone(), ledger_account.clone(), system_program.clone()],
+            )?;
+        }
+        ledger_account.resize(new_size)?;
+    }
+
+    charge_increment_fee(program_id, user_account, global_pda_account, system_program, amount)?;
+
+    let new_value = shared_ledger_increment(&mut ledger_account.data.borrow_mut(), user_account.key, amount)?;
+    add_global_merit(global_pda_account, amount)?;
+
+    log_msg!("共享账本功德: {}", new_value);
+    Ok(())
+}
+
+// 🧓 函数名：set_beneficiary(beneficiary, timeout_days) - 指定受益人
+// 类比：遗嘱，账户主人连续 timeout_days 天没有操作后，受益人可以接管账户；受益人为全0公钥时清除
+// 设置本身也算一次操作，等待期从现在开始计算
+fn process_set_beneficiary(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者）
+
+    let beneficiary = parse_set_beneficiary_args(instruction_data).inspect_err(|_| {
+        log_msg!("受益人的等待天数不能少于 {} 天", MIN_BENEFICIARY_TIMEOUT_DAYS);
+    })?;
+
+    // ✍️ 只有账户主人可以指定受益人
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    require_current_layout(gongde_account)?;
+
+    let mut data = gongde_account.data.borrow_mut();
+    if !is_gongde_authority(program_id, gongde_account.key, &data, authority.key)? {
+        msg!("只能为自己的功德账户指定受益人");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if beneficiary.is_some_and(|(beneficiary, _)| &beneficiary == authority.key) {
+        msg!("受益人不能是自己");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    write_beneficiary(&mut data, beneficiary)?;
+    touch_last_active(&mut data, Clock::get()?.unix_timestamp);
+    match beneficiary {
+        Some((beneficiary, timeout_days)) => log_msg!("受益人: {}，等待天数: {}", beneficiary, timeout_days),
+        None => msg!("已清除受益人"),
+    }
+    Ok(())
+}
+
+// 🗝️ 函数名：claim_as_beneficiary() - 受益人接管长期没有操作的账户
+// 账户主人最后一次操作之后超过等待天数才可以接管：
+//   - 不传自己的功德账户：成为这个账户的主人，之后关闭、转赠、回滚等都由受益人签名，原来的用户不再有权限
+//   - 传入自己的功德账户：功德合并过去，这个账户关闭，租金退给受益人
+fn process_claim_as_beneficiary(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 被接管的功德账户（可写）
+    let claimer = next_account_info(accounts_iter)?;        // 受益人（签名者，可写，合并时接收租金）
+    let merge_into = next_account_info(accounts_iter).ok(); // 可选：受益人自己的功德账户（可写）
+
+    if !claimer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    require_current_layout(gongde_account)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    {
+        let data = gongde_account.data.borrow();
+        if !is_usable_account(&data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let Some((beneficiary, timeout_days)) = read_beneficiary(&data).filter(|(beneficiary, _)| beneficiary == claimer.key) else {
+            msg!("签名者不是这个功德账户的受益人");
+            return Err(ProgramError::IllegalOwner);
+        };
+        let last_active = read_last_active(&data).unwrap_or(now);
+        if !can_claim_as_beneficiary(last_active, timeout_days, now) {
+            log_msg!("账户主人最近还有操作，{} 之后受益人 {} 才可以接管", beneficiary_claimable_at(last_active, timeout_days), beneficiary);
+            return Err(GongDeError::BeneficiaryClaimTooEarly.into());
+        }
+    }
+
+    let Some(target) = merge_into else {
+        // 🔑 接管：受益人成为账户主人，受益人设置清空，等待期从接管时重新计算
+        let mut data = gongde_account.data.borrow_mut();
+        write_authority(&mut data, claimer.key)?;
+        write_beneficiary(&mut data, None)?;
+        touch_last_active(&mut data, now);
+        log_msg!("受益人 {} 已接管功德账户", claimer.key);
+        return Ok(());
+    };
+
+    // 🔀 合并：目标必须是受益人自己的、已初始化的功德账户
+    if target.key == gongde_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if target.owner != program_id || !target.is_writable {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    validate_account_data_size(target.data_len())?;
+    require_not_archived(target)?;
+    let mut target_data = target.data.borrow_mut();
+    if !is_gongde_authority(program_id, target.key, &target_data, claimer.key)? {
+        msg!("只能合并到受益人自己的功德账户");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&target_data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let value = read_gongde_value(&gongde_account.data.borrow())?;
+    let target_value = read_gongde_value(&target_data)?;
+    let merged = target_value.checked_add(value).ok_or(ProgramError::ArithmeticOverflow)?;
+    write_gongde_value(&mut target_data, merged)?;
+    touch_last_active(&mut target_data, now);
+    drop(target_data);
+    emit_merit_change(gongde_account.key, value, 0);
+    emit_merit_change(target.key, target_value, merged);
+
+    log_msg!("受益人合并功德: {}", value);
+    close_gongde_account(gongde_account, claimer)
+}
+
+// 🤝 函数名：endorse() - 给别人的功德账户背书
+// 类比：推荐信，推荐人越有名望分量越重：被背书人获得 1 + floor(log10(背书人功德)) 点功德
+// 每对用户只能背书一次，背书记录PDA保存权重和依据；背书转出的不是背书人的功德，背书人的功德不变
+fn process_endorse(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let endorsee_gongde = next_account_info(accounts_iter)?; // 被背书的功德账户（可写）
+    let endorser = next_account_info(accounts_iter)?;        // 背书人（签名者，支付背书记录租金）
+    let endorser_gongde = next_account_info(accounts_iter)?; // 背书人的功德账户（只读，权重依据）
+    let endorsement = next_account_info(accounts_iter)?;     // 背书记录PDA（可写）
+    let system_program = next_account_info(accounts_iter)?;  // 系统程序
+
+    let endorser_value = read_endorser_value(program_id, endorser, endorser_gongde)?;
+    if !endorse_target(program_id, endorser, endorser_gongde, endorser_value, endorsee_gongde, endorsement, system_program)? {
+        msg!("已经给这个功德账户背过书了");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    Ok(())
+}
+
+// 🤝 函数名：endorse_many() - 一次给多个功德账户背书
+// 类比：活动组织者给到场的人逐个写推荐信，已经写过的跳过；结果位图通过 return data 返回
+// 账户列表：背书人、背书人的功德账户、系统程序，之后每个目标依次是功德账户和背书记录PDA
+fn process_endorse_many(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let endorser = next_account_info(accounts_iter)?;        // 背书人（签名者，支付背书记录租金）
+    let endorser_gongde = next_account_info(accounts_iter)?; // 背书人的功德账户（只读，权重依据）
+    let system_program = next_account_info(accounts_iter)?;  // 系统程序
+    let targets = accounts_iter.as_slice().chunks_exact(2);
+
+    // 🔢 每个目标两个账户，目标数有上限，避免一条指令创建太多账户耗尽计算单元
+    let target_count = targets.len();
+    if target_count == 0 || !targets.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if target_count > MAX_ENDORSE_MANY_TARGETS {
+        log_msg!("一次最多给 {} 个功德账户背书，实际 {} 个", MAX_ENDORSE_MANY_TARGETS, target_count);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 🔒 先检查全部目标再修改：和单个背书一样，被背书的必须是可写、没有归档的功德账户，
+    // 混进一个别的账户整批拒绝，不会先给前面的目标加上功德
+    for pair in targets.clone() {
+        let endorsee_gongde = &pair[0];
+        if !endorsee_gongde.is_writable {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        require_program_owned(program_id, endorsee_gongde)?;
+        require_gongde_target(program_id, endorsee_gongde)?;
+        require_not_archived(endorsee_gongde)?;
+    }
+
+    let endorser_value = read_endorser_value(program_id, endorser, endorser_gongde)?;
+    let mut result = EndorseManyResult::default();
+    for (index, pair) in targets.enumerate() {
+        let (endorsee_gongde, endorsement) = (&pair[0], &pair[1]);
+        // ⏭️ 已经背过书的跳过，其他错误整批失败
+        if endorse_target(program_id, endorser, endorser_gongde, endorser_value, endorsee_gongde, endorsement, system_program)? {
+            result.mark_endorsed(index);
+        } else {
+            log_msg!("跳过已背书的功德账户 {}", endorsee_gongde.key);
+        }
+    }
+
+    log_msg!("新背书 {} 个功德账户，共 {} 个", result.endorsed_count(), target_count);
+    set_return_data(&result.to_bytes());
+    Ok(())
+}
+
+// ✍️ 权重按背书人自己的功德账户计算，必须是签名者本人的账户，不能借别人的功德抬高权重
+fn read_endorser_value(program_id: &Pubkey, endorser: &AccountInfo, endorser_gongde: &AccountInfo) -> Result<u32, ProgramError> {
+    if !endorser.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, endorser_gongde)?;
+    validate_account_data_size(endorser_gongde.data_len())?;
+    let data = endorser_gongde.data.borrow();
+    if !is_gongde_authority(program_id, endorser_gongde.key, &data, endorser.key)? {
+        msg!("背书人的功德账户必须属于签名者");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(read_gongde_value(&data)?)
+}
+
+// 🤝 创建一条背书记录并给被背书人加功德；已经背过书时不做任何修改，返回 false
+fn endorse_target<'a>(
+    program_id: &Pubkey,
+    endorser: &AccountInfo<'a>,
+    endorser_gongde: &AccountInfo<'a>,
+    endorser_value: u32,
+    endorsee_gongde: &AccountInfo<'a>,
+    endorsement: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<bool, ProgramError> {
+    require_program_owned(program_id, endorsee_gongde)?;
+    require_gongde_target(program_id, endorsee_gongde)?;
+
+    // 🔍 不能给自己背书
+    if endorsee_gongde.key == endorser_gongde.key {
+        msg!("不能给自己背书");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !is_usable_account(&endorsee_gongde.data.borrow()) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // 📝 归本合约所有的背书记录已存在说明背过书了；只有余额的地址是别人抢先转账，照常创建
+    let (expected_endorsement, bump) = derive_endorsement_address(endorser.key, endorsee_gongde.key, program_id);
+    if endorsement.key != &expected_endorsement {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if endorsement.owner == program_id && endorsement.data_len() == ENDORSEMENT_SIZE {
+        return Ok(false);
+    }
+    if endorsement.owner != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    create_pda_account(
+        program_id,
+        endorser,
+        endorsement,
+        system_program,
+        ENDORSEMENT_SIZE,
+        &[ENDORSEMENT_SEED, endorser.key.as_ref(), endorsee_gongde.key.as_ref(), &[bump]],
+    )?;
+
+    let record = Endorsement::new(endorser_value, Clock::get()?.unix_timestamp);
+    record.pack(&mut endorsement.data.borrow_mut())?;
+
+    // 💸 背书获得的功德和配捐一样不计入累计功德
+    let mut data = endorsee_gongde.data.borrow_mut();
+    let current = read_gongde_value(&data)?;
+    let new_value = current.checked_add(record.weight).ok_or(ProgramError::ArithmeticOverflow)?;
+    write_gongde_value(&mut data, new_value)?;
+    emit_merit_change(endorsee_gongde.key, current, new_value);
+
+    log_msg!("背书权重: {}（背书人功德 {}），被背书人功德: {}", record.weight, endorser_value, new_value);
+    Ok(true)
+}
+
+// ⏳ 函数名：accrue() - 结算挂机功德
+// 类比：放置类游戏的离线收益，每过 ACCRUE_SECONDS_PER_MERIT 秒积累1点功德，单次最多 MAX_ACCRUED_MERIT 点
+// 结算时间存放在 [ACCRUAL_STATE_SEED, 功德账户] PDA中，第一次调用时创建并开始计时，不积累功德
+// 挂机功德不收手续费，和配捐一样不计入累计功德和全局功德
+fn process_accrue(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者，支付状态账户租金）
+    let accrual_state = next_account_info(accounts_iter)?;  // 挂机功德状态PDA（可写）
+    let system_program = next_account_info(accounts_iter)?; // 系统程序
+
+    // ✍️ 只有账户主人可以结算
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    {
+        let data = gongde_account.data.borrow();
+        if !is_gongde_authority(program_id, gongde_account.key, &data, authority.key)? {
+            msg!("只能结算自己的功德账户");
+            return Err(ProgramError::IllegalOwner);
+        }
+        if !is_usable_account(&data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+    }
+
+    let (expected_state, bump) = derive_accrual_state_address(gongde_account.key, program_id);
+    if accrual_state.key != &expected_state {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if accrual_state.owner == &solana_system_interface::program::id() {
+        // 🌱 第一次结算：创建状态账户，从现在开始计时
+        create_pda_account(
+            program_id,
+            authority,
+            accrual_state,
+            system_program,
+            ACCRUAL_STATE_SIZE,
+            &[ACCRUAL_STATE_SEED, gongde_account.key.as_ref(), &[bump]],
+        )?;
+        write_last_accrued(&mut accrual_state.data.borrow_mut(), now)?;
+        touch_last_active(&mut gongde_account.data.borrow_mut(), now);
+        log_msg!("开始积累挂机功德，每 {} 秒1点", ACCRUE_SECONDS_PER_MERIT);
+        return Ok(());
+    }
+    if accrual_state.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut state_data = accrual_state.data.borrow_mut();
+    let (accrued, last_accrued) = accrue_merit(read_last_accrued(&state_data)?, now);
+    write_last_accrued(&mut state_data, last_accrued)?;
+
+    let mut data = gongde_account.data.borrow_mut();
+    touch_last_active(&mut data, now);
+    let current = read_gongde_value(&data)?;
+    // ⚠️ 已经圆满或不足一个周期时只更新结算时间
+    let Some(new_value) = increment_merit(current, accrued).filter(|_| accrued > 0) else {
+        msg!("本次没有积累挂机功德");
+        return Ok(());
+    };
+    write_gongde_value(&mut data, new_value)?;
+    emit_merit_change(gongde_account.key, current, new_value);
+
+    log_msg!("挂机功德: {}，个人功德: {}", accrued, new_value);
+    Ok(())
+}
+
+// 💎 质押相关指令共用的检查：账户主人签名、账户属于本合约、已初始化并且是当前布局
+fn require_stake_authority(program_id: &Pubkey, gongde_account: &AccountInfo, authority: &AccountInfo) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    let data = gongde_account.data.borrow();
+    if !is_gongde_authority(program_id, gongde_account.key, &data, authority.key)? {
+        msg!("只能操作自己功德账户中的质押");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    drop(data);
+    require_current_layout(gongde_account)
+}
+
+// 💎 把质押积累的功德结算到当前slot，Stake/Unstake 改变质押数量前都要先调用，保证每段时间按当时的数量计算
+// 不足1点的零头留在账户中；和挂机功德一样不计入累计功德和全局功德，已经圆满时只推进结算slot
+// 返回结算后的质押状态和本次增加的功德
+fn settle_stake(gongde_account: &AccountInfo, data: &mut [u8], slot: u64) -> Result<(StakeState, u32), ProgramError> {
+    let mut stake = StakeState::unpack(data)?;
+    let merit = u32::try_from(stake.settle(slot)).unwrap_or(u32::MAX);
+    stake.pack(data)?;
+
+    let current = read_gongde_value(data)?;
+    let Some(new_value) = increment_merit(current, merit).filter(|_| merit > 0) else {
+        return Ok((stake, 0));
+    };
+    write_gongde_value(data, new_value)?;
+    emit_merit_change(gongde_account.key, current, new_value);
+    log_msg!("质押功德: {}，个人功德: {}", new_value - current, new_value);
+    Ok((stake, new_value - current))
+}
+
+// 💎 函数名：stake(lamports) - 把 SOL 质押到自己的功德账户
+// 类比：定期存款，质押的 SOL 存放在功德账户本身，和租金分开记账；按 质押数量 × 经过的slot 积累功德
+// 先按原来的数量结算到现在，新增的质押从这个slot开始计算
+fn process_stake(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let lamports = parse_stake_args(instruction_data)?;
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者，支付质押的 SOL）
+    let system_program = next_account_info(accounts_iter)?; // 系统程序
+
+    require_stake_authority(program_id, gongde_account, authority)?;
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let clock = Clock::get()?;
+    let (mut stake, _) = settle_stake(gongde_account, &mut gongde_account.data.borrow_mut(), clock.slot)?;
+    stake.staked = stake.staked.checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    invoke(
+        &system_instruction::transfer(authority.key, gongde_account.key, lamports),
+        &[authority.clone(), gongde_account.clone(), system_program.clone()],
+    )?;
+
+    let mut data = gongde_account.data.borrow_mut();
+    stake.pack(&mut data)?;
+    touch_last_active(&mut data, clock.unix_timestamp);
+    log_msg!("质押 {} lamports，共质押 {} lamports", lamports, stake.staked);
+    Ok(())
+}
+
+// 💰 函数名：claim_accrued() - 结算质押积累的功德
+// 功德 = 质押数量 × 经过的slot ÷ STAKE_LAMPORT_SLOTS_PER_MERIT，余数留在账户中下次继续累加，不会因为频繁结算丢失
+fn process_claim_accrued(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者）
+
+    require_stake_authority(program_id, gongde_account, authority)?;
+
+    let clock = Clock::get()?;
+    let mut data = gongde_account.data.borrow_mut();
+    let (stake, merit) = settle_stake(gongde_account, &mut data, clock.slot)?;
+    touch_last_active(&mut data, clock.unix_timestamp);
+    if merit == 0 {
+        log_msg!("本次没有积累质押功德，质押 {} lamports", stake.staked);
+    }
+    Ok(())
+}
+
+// 🏧 函数名：unstake(lamports) - 取回质押的 SOL
+// 先按原来的数量结算到现在，再减少质押；租金不算在质押里，取回后账户仍然免租
+fn process_unstake(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let lamports = parse_stake_args(instruction_data)?;
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者，收回质押的 SOL）
+
+    require_stake_authority(program_id, gongde_account, authority)?;
+
+    let clock = Clock::get()?;
+    let mut data = gongde_account.data.borrow_mut();
+    let (mut stake, _) = settle_stake(gongde_account, &mut data, clock.slot)?;
+    stake.staked = stake.staked.checked_sub(lamports).ok_or(GongDeError::InsufficientStake)?;
+    stake.pack(&mut data)?;
+    touch_last_active(&mut data, clock.unix_timestamp);
+    drop(data);
+
+    // 🏦 正常情况下质押记账不会动到租金，这里再检查一次，防止取走租金后账户被回收
+    let rent_minimum = Rent::get()?.minimum_balance(gongde_account.data_len());
+    if gongde_account.lamports().saturating_sub(lamports) < rent_minimum {
+        log_msg!("取回后账户余额低于免租金额 {} lamports", rent_minimum);
+        return Err(ProgramError::InsufficientFunds);
+    }
+    move_lamports(gongde_account, authority, lamports)?;
+    log_msg!("取回 {} lamports，剩余质押 {} lamports", lamports, stake.staked);
+    Ok(())
+}
+
+// 🎰 彩票账户必须归本合约所有，并且地址由记录的管理员和轮次编号派生，防止拿别的账户冒充
+fn load_lottery(program_id: &Pubkey, lottery_account: &AccountInfo) -> Result<LotteryRound, ProgramError> {
+    if lottery_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let round = LotteryRound::unpack(&lottery_account.data.borrow())?;
+    let (expected_lottery, _bump) = derive_lottery_address(&round.admin, round.round_id, program_id);
+    if lottery_account.key != &expected_lottery {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(round)
+}
+
+// 🎰 函数名：create_lottery(round_id, start_slot, end_slot, ticket_price) - 管理员创建一轮彩票
+// 类比：开一期彩票，投注时间 [start_slot, end_slot)，结束slot的哈希决定中奖票号，票款全部进入奖池
+// 轮次地址由 [LOTTERY_SEED, 管理员, 轮次编号] 派生，每个管理员的轮次编号互不影响
+fn process_create_lottery(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let lottery_account = next_account_info(accounts_iter)?; // 彩票轮次PDA（可写）
+    let admin = next_account_info(accounts_iter)?;           // 管理员（签名者，支付租金）
+    let system_program = next_account_info(accounts_iter)?;  // 系统程序
+
+    let args = parse_create_lottery_args(instruction_data)?;
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (expected_lottery, bump) = derive_lottery_address(admin.key, args.round_id, program_id);
+    if lottery_account.key != &expected_lottery {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 有人抢先转账时地址上有余额但账户仍归系统程序所有，不算已使用
+    if lottery_account.owner == program_id || !lottery_account.data_is_empty() {
+        log_msg!("轮次编号 {} 已被使用", args.round_id);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    // ⏰ 已经过了结束slot的轮次没有人能投注
+    if Clock::get()?.slot >= args.end_slot {
+        return Err(GongDeError::LotteryClosed.into());
+    }
+
+    create_pda_account(
+        program_id,
+        admin,
+        lottery_account,
+        system_program,
+        LOTTERY_HEADER_SIZE,
+        &[LOTTERY_SEED, admin.key.as_ref(), &args.round_id.to_le_bytes(), &[bump]],
+    )?;
+
+    let round = LotteryRound {
+        admin: *admin.key,
+        round_id: args.round_id,
+        start_slot: args.start_slot,
+        end_slot: args.end_slot,
+        ticket_price: args.ticket_price,
+        total_tickets: 0,
+        settled: false,
+        voided: false,
+        winning_ticket: 0,
+        entry_count: 0,
+    };
+    round.pack(&mut lottery_account.data.borrow_mut())?;
+
+    log_msg!("彩票已创建: 票价 {}，投注slot [{}, {})", args.ticket_price, args.start_slot, args.end_slot);
+    Ok(())
+}
+
+// 🎟️ 函数名：enter_lottery(tickets) - 花功德买彩票
+// 票款从功德中扣除进入奖池，累计功德不变；每次投注在彩票轮次末尾追加一个条目，扩容的租金由投注人支付
+fn process_enter_lottery(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?;  // 投注人的功德账户（可写）
+    let player = next_account_info(accounts_iter)?;          // 投注人（签名者，支付条目租金）
+    let lottery_account = next_account_info(accounts_iter)?; // 彩票轮次PDA（可写）
+    let system_program = next_account_info(accounts_iter)?;  // 系统程序
+
+    let tickets = parse_enter_lottery_args(instruction_data)?;
+
+    // ✍️ 只能花自己的功德
+    if !player.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    if !is_gongde_authority(program_id, gongde_account.key, &gongde_account.data.borrow(), player.key)? {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&gongde_account.data.borrow()) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut round = load_lottery(program_id, lottery_account)?;
+    let clock = Clock::get()?;
+    if !round.is_open(clock.slot) {
+        log_msg!("不在投注时间内，当前slot: {}，投注slot [{}, {})", clock.slot, round.start_slot, round.end_slot);
+        return Err(GongDeError::LotteryClosed.into());
+    }
+
+    // 💸 先确认功德足够再扩容
+    let cost = round.cost(tickets);
+    let value = read_gongde_value(&gongde_account.data.borrow())?;
+    if u64::from(value) < cost {
+        log_msg!("功德不足，当前功德: {}，票款: {}", value, cost);
+        return Err(GongDeError::InsufficientMerit.into());
+    }
+
+    // 📏 追加一个条目，补足扩容后的租金
+    let index = round.entry_count as usize;
+    let new_size = lottery_size(index + 1);
+    let deficit = Rent::get()?.minimum_balance(new_size).saturating_sub(lottery_account.lamports());
+    if deficit > 0 {
+        invoke(
+            &system_instruction::transfer(player.key, lottery_account.key, deficit),
+            &[player.clone(), lottery_account.clone(), system_program.clone()],
+        )?;
+    }
+    lottery_account.resize(new_size)?;
+
+    let ticket_end = round.record_entry(tickets)?;
+    let mut lottery_data = lottery_account.data.borrow_mut();
+    write_lottery_entry(&mut lottery_data, index, gongde_account.key, ticket_end)?;
+    round.pack(&mut lottery_data)?;
+
+    // cost 不超过 value，一定能放进 u32
+    let new_value = value - cost as u32;
+    let mut data = gongde_account.data.borrow_mut();
+    write_gongde_value(&mut data, new_value)?;
+    touch_last_active(&mut data, clock.unix_timestamp);
+    emit_merit_change(gongde_account.key, value, new_value);
+
+    log_msg!("买入 {} 张彩票，票号 [{}, {})，奖池 {}", tickets, ticket_end - u32::from(tickets), ticket_end, round.jackpot());
+    Ok(())
+}
+
+// 🏆 函数名：settle_lottery() - 彩票开奖
+// 结束slot之后任何人都可以开奖：取结束slot的哈希（见 slot_hash_at）算出中奖票号，奖池全部加给中奖者的功德账户
+// 中奖者由链上数据唯一确定，开奖人只能传入正确的账户；没有人投注时只标记为已开奖
+// 结束slot的哈希只在 SlotHashes 中保留约512个slot，超过后本轮作废，票款退还给投注者（见 refund_lottery_entrants）
+fn process_settle_lottery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let lottery_account = next_account_info(accounts_iter)?; // 彩票轮次PDA（可写）
+    let slot_hashes = next_account_info(accounts_iter)?;     // SlotHashes 系统变量
+    let gongde_accounts = accounts_iter.as_slice();          // 中奖者的功德账户；作废时为要退款的投注功德账户
+
+    let mut round = load_lottery(program_id, lottery_account)?;
+    // 作废的轮次可以多次调用，每次给传入的投注者退款
+    if round.voided {
+        return refund_lottery_entrants(program_id, lottery_account, gongde_accounts);
+    }
+    if round.settled {
+        return Err(GongDeError::LotteryAlreadySettled.into());
+    }
+    let slot = Clock::get()?.slot;
+    if slot <= round.end_slot {
+        log_msg!("还没有到开奖时间，当前slot: {}，结束slot: {}", slot, round.end_slot);
+        return Err(GongDeError::LotteryNotEnded.into());
+    }
+
+    round.settled = true;
+    if round.total_tickets == 0 {
+        round.pack(&mut lottery_account.data.borrow_mut())?;
+        msg!("没有人投注，本轮结束");
+        return Ok(());
+    }
+
+    if slot_hashes.key != &sysvar::slot_hashes::ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let Some(slot_hash) = slot_hash_at(&slot_hashes.data.borrow(), round.end_slot) else {
+        // 按当前slot判断是否已经过期，在过期之前开奖人不能让本轮作废
+        if slot - round.end_slot <= SLOT_HASHES_MAX_ENTRIES {
+            log_msg!("结束slot {} 的哈希不在 SlotHashes 中", round.end_slot);
+            return Err(GongDeError::SlotHashUnavailable.into());
+        }
+        round.voided = true;
+        round.pack(&mut lottery_account.data.borrow_mut())?;
+        log_msg!("结束slot {} 的哈希已过期，本轮作废，奖池 {} 退还给投注者", round.end_slot, round.jackpot());
+        return refund_lottery_entrants(program_id, lottery_account, gongde_accounts);
+    };
+    round.winning_ticket = winning_ticket(&slot_hash, round.total_tickets);
+    let winner = lottery_ticket_owner(&lottery_account.data.borrow(), round.winning_ticket)?
+        .ok_or(ProgramError::InvalidAccountData)?;
+    round.pack(&mut lottery_account.data.borrow_mut())?;
+
+    // 🔍 开奖人必须传入中奖者的功德账户
+    let Some(winner_gongde) = gongde_accounts.first().filter(|account| account.key == &winner) else {
+        log_msg!("中奖票号 {}，请传入中奖的功德账户 {}", round.winning_ticket, winner);
+        return Err(ProgramError::InvalidArgument);
+    };
+    if !winner_gongde.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // 中奖者投注后关闭或归档了功德账户时奖池作废，本轮照常结束；结束slot的哈希很快会过期，不能等中奖者取消归档
+    if winner_gongde.owner != program_id || winner_gongde.lamports() == 0 {
+        log_msg!("中奖的功德账户 {} 已关闭，奖池 {} 作废", winner, round.jackpot());
+        return Ok(());
+    }
+    if is_archived_account(&winner_gongde.data.borrow()) {
+        log_msg!("中奖的功德账户 {} 已归档，奖池 {} 作废", winner, round.jackpot());
+        return Ok(());
+    }
+
+    let jackpot = u32::try_from(round.jackpot()).unwrap_or(u32::MAX);
+    let mut data = winner_gongde.data.borrow_mut();
+    let current = read_gongde_value(&data)?;
+    if let Some(new_value) = increment_merit(current, jackpot) {
+        write_gongde_value(&mut data, new_value)?;
+        emit_merit_change(winner_gongde.key, current, new_value);
+    }
+
+    log_msg!("中奖票号 {}，中奖账户 {}，奖池 {}", round.winning_ticket, winner, jackpot);
+    Ok(())
+}
+
+// 💸 作废的彩票给传入的投注者退还票款，只退功德值，不计入累计功德
+// 退款后条目标记为已退款，同一个账户重复传入或再次调用不会重复退款
+// 已关闭或归档的功德账户跳过，不标记，恢复之后还可以再退款
+fn refund_lottery_entrants(program_id: &Pubkey, lottery_account: &AccountInfo, gongde_accounts: &[AccountInfo]) -> ProgramResult {
+    if gongde_accounts.len() > MAX_LOTTERY_REFUND_ACCOUNTS {
+        log_msg!("一次最多退款 {} 个功德账户，实际 {} 个", MAX_LOTTERY_REFUND_ACCOUNTS, gongde_accounts.len());
+        return Err(ProgramError::InvalidArgument);
+    }
+    for gongde_account in gongde_accounts {
+        let due = lottery_refund_due(&lottery_account.data.borrow(), gongde_account.key)?;
+        if due == 0 {
+            log_msg!("功德账户 {} 没有待退还的票款", gongde_account.key);
+            continue;
+        }
+        if !gongde_account.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if gongde_account.owner != program_id || gongde_account.lamports() == 0 {
+            log_msg!("功德账户 {} 已关闭，暂不退款", gongde_account.key);
+            continue;
+        }
+        if is_archived_account(&gongde_account.data.borrow()) {
+            log_msg!("功德账户 {} 已归档，取消归档后再退款", gongde_account.key);
+            continue;
+        }
+        require_gongde_layout(gongde_account)?;
+
+        mark_lottery_refunded(&mut lottery_account.data.borrow_mut(), gongde_account.key)?;
+        let refund = u32::try_from(due).unwrap_or(u32::MAX);
+        let mut data = gongde_account.data.borrow_mut();
+        let current = read_gongde_value(&data)?;
+        if let Some(new_value) = increment_merit(current, refund) {
+            write_gongde_value(&mut data, new_value)?;
+            emit_merit_change(gongde_account.key, current, new_value);
+        }
+        log_msg!("功德账户 {} 退还票款 {}", gongde_account.key, refund);
+    }
+    Ok(())
+}
+
+// 🗄️ 函数名：archive() / unarchive() - 归档或取消归档功德账户
+// 类比：把账本收进档案柜，功德原样保留，但在取出来之前不能再记账；和关闭不同，随时可以恢复
+// 重复归档返回 AccountArchived（由入口的归档检查拦下），没有归档时取消归档返回 AccountNotArchived
+fn process_set_archived(program_id: &Pubkey, accounts: &[AccountInfo], archived: bool) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者）
+
+    // ✍️ 只有账户主人可以归档或取消归档
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    require_current_layout(gongde_account)?;
+
+    let mut data = gongde_account.data.borrow_mut();
+    if !is_gongde_authority(program_id, gongde_account.key, &data, authority.key)? {
+        msg!("只能归档自己的功德账户");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if is_archived_account(&data) == archived {
+        return Err(if archived { GongDeError::AccountArchived } else { GongDeError::AccountNotArchived }.into());
+    }
+
+    write_archived_flag(&mut data, archived)?;
+    touch_last_active(&mut data, Clock::get()?.unix_timestamp);
+    let value = read_gongde_value(&data)?;
+    if archived {
+        log_msg!("已归档，功德: {}", value);
+    } else {
+        log_msg!("已取消归档，功德: {}", value);
+    }
+    Ok(())
+}
+
+// 🔀 函数名：swap_counts() - 挑战结束后两个人交换功德
+// 类比：两个变量交换值，两位主人都签名才算双方同意；只交换当前功德，累计功德和其他字段保持不变
+fn process_swap_counts(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_a = next_account_info(accounts_iter)?;    // 第一个功德账户（可写）
+    let authority_a = next_account_info(accounts_iter)?; // 第一个功德账户的主人（签名者）
+    let gongde_b = next_account_info(accounts_iter)?;    // 第二个功德账户（可写）
+    let authority_b = next_account_info(accounts_iter)?; // 第二个功德账户的主人（签名者）
+
+    // ✍️ 双方都必须签名
+    if !authority_a.is_signer || !authority_b.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // 🔍 两个账户不能是同一个，否则 borrow_mut 会冲突，交换也没有意义
+    if gongde_a.key == gongde_b.key {
+        msg!("不能和自己交换功德");
+        return Err(ProgramError::InvalidArgument);
+    }
+    require_program_owned(program_id, gongde_a)?;
+    require_program_owned(program_id, gongde_b)?;
+    if !gongde_b.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    validate_account_data_size(gongde_b.data_len())?;
+    require_not_archived(gongde_b)?;
+
+    let mut data_a = gongde_a.data.borrow_mut();
+    let mut data_b = gongde_b.data.borrow_mut();
+    if !is_gongde_authority(program_id, gongde_a.key, &data_a, authority_a.key)?
+        || !is_gongde_authority(program_id, gongde_b.key, &data_b, authority_b.key)?
+    {
+        msg!("每个功德账户都必须由自己的主人签名");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&data_a) || !is_usable_account(&data_b) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // 🔀 交换功德值，两个账户的主人都算操作了一次
+    let value_a = read_gongde_value(&data_a)?;
+    let value_b = read_gongde_value(&data_b)?;
+    write_gongde_value(&mut data_a, value_b)?;
+    write_gongde_value(&mut data_b, value_a)?;
+    let now = Clock::get()?.unix_timestamp;
+    touch_last_active(&mut data_a, now);
+    touch_last_active(&mut data_b, now);
+    emit_merit_change(gongde_a.key, value_a, value_b);
+    emit_merit_change(gongde_b.key, value_b, value_a);
+
+    log_msg!("交换功德: {} ⇄ {}", value_a, value_b);
+    Ok(())
+}
+
+// 📊 函数名：report() - 只读汇总多个功德账户的功德
+// 类比：看板的批量查询接口，一次交易读出一批账户，合计和每个账户的功德通过 return data 返回，不修改任何账户
+fn process_report(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    // 🔢 账户数量有上限，避免一条指令读太多账户耗尽计算单元
+    if accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if accounts.len() > MAX_REPORT_ACCOUNTS {
+        log_msg!("一次最多汇总 {} 个功德账户，实际 {} 个", MAX_REPORT_ACCOUNTS, accounts.len());
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut values = Vec::with_capacity(accounts.len());
+    for (index, gongde_account) in accounts.iter().enumerate() {
+        // 🔍 同一个账户传两次会把功德算两遍
+        if accounts[..index].iter().any(|previous| previous.key == gongde_account.key) {
+            log_msg!("功德账户 {} 重复传入", gongde_account.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        require_program_owned(program_id, gongde_account)?;
+        validate_account_data_size(gongde_account.data_len())?;
+        let data = gongde_account.data.borrow();
+        if !is_usable_account(&data) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let value = read_gongde_value(&data)?;
+        log_msg!("功德账户 {}: {}", gongde_account.key, value);
+        values.push(value);
+    }
+
+    let report = MeritReport::new(values);
+    log_msg!("{} 个功德账户合计功德: {}", report.values.len(), report.total);
+    set_return_data(&report.to_bytes());
+    Ok(())
+}
+
+// ⚙️ 函数名：set_minimum_client_version(version) - 创作者设置最低客户端版本
+// 类比：App 的强制更新开关，低于这个版本的带版本指令会被拒绝并提示升级；设为0取消限制
+// 旧客户端的编码不带版本，视为版本0；增加功德和共享账本从全局功德PDA中的配置副本读到最低版本，同样被拒绝
+// 其他旧编码的指令（转赠、关闭、点赞、回滚、质押等）不附带全局配置也不带全局功德PDA，读不到最低版本，不受这个限制
+fn process_set_minimum_client_version(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let minimum_client_version = parse_set_minimum_client_version_args(instruction_data)?;
+    update_global_config(program_id, accounts, |config| config.minimum_client_version = minimum_client_version)?;
+    log_msg!("最低客户端版本已设置为 {}", minimum_client_version);
+    Ok(())
+}
+
+// ⛏️ 函数名：set_pow_difficulty(difficulty) - 创作者设置增加功德的工作量证明难度
+// 类比：验证码开关，开启后 Increment / IncrementBy / SharedIncrement 被拒绝，只能用附带 nonce 的 IncrementWithProof；设为0关闭
+// 旧客户端的编码从全局功德PDA中的配置副本读到难度，同样受这个开关限制
+fn process_set_pow_difficulty(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let difficulty = parse_set_pow_difficulty_args(instruction_data)?;
+    update_global_config(program_id, accounts, |config| config.pow_difficulty = difficulty)?;
+    log_msg!("工作量证明难度已设置为 {} 比特", difficulty);
+    Ok(())
+}
+
+// ⚙️ 修改全局配置的公共部分：只有创作者能签名，第一次设置时创建账户，
+// 旧版本创建的1字节配置先补足租金扩容，没有修改的字段保持原值
+fn update_global_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    update: impl FnOnce(&mut GlobalConfig),
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;     // 全局配置PDA（可写）
+    let global_pda_account = next_account_info(accounts_iter)?; // 全局功德PDA（可写，保存配置的副本）
+    let creator = next_account_info(accounts_iter)?;            // 创作者（签名者，第一次设置或扩容时支付租金）
+    let system_program = next_account_info(accounts_iter)?; // 系统程序
+
+    // ✍️ 只有创作者可以修改全局配置
+    if !creator.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if creator.key != &get_creator_address()? {
+        msg!("只有创作者可以修改全局配置");
+        return Err(ProgramError::IllegalOwner);
+    }
+    let (expected_config, bump) = derive_config_address(program_id);
+    if config_account.key != &expected_config || !config_account.is_writable {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if system_program.key != &solana_system_interface::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 🏗️ 全局配置第一次设置时创建
+    if config_account.owner == &solana_system_interface::program::id() {
+        create_pda_account(program_id, creator, config_account, system_program, CONFIG_SIZE, &[CONFIG_SEED, &[bump]])?;
+    } else if config_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    } else if config_account.data_len() < CONFIG_SIZE {
+        // 📏 旧版本的全局配置只有最低客户端版本一个字节，扩容后新字段为0
+        let deficit = Rent::get()?.minimum_balance(CONFIG_SIZE).saturating_sub(config_account.lamports());
+        if deficit > 0 {
+            invoke(
+                &system_instruction::transfer(creator.key, config_account.key, deficit),
+                &[creator.clone(), config_account.clone(), system_program.clone()],
+            )?;
+        }
+        config_account.resize(CONFIG_SIZE)?;
+    }
+
+    let config = {
+        let mut data = config_account.data.borrow_mut();
+        let mut config = GlobalConfig::unpack(&data)?;
+        update(&mut config);
+        config.pack(&mut data)?;
+        config
+    };
+
+    // 🪞 旧编码的指令不附带全局配置，同步写一份到全局功德PDA的功德值之后，旧客户端增加功德时从这里检查
+    let (expected_global_pda, global_bump) = derive_global_gongde_pda_address(program_id)?;
+    if global_pda_account.key != &expected_global_pda || !global_pda_account.is_writable {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if global_pda_account.owner == &solana_system_interface::program::id() {
+        create_pda_account(
+            program_id,
+            creator,
+            global_pda_account,
+            system_program,
+            GLOBAL_PDA_WITH_CONFIG_SIZE,
+            &[GLOBAL_GONGDE_ACCOUNT_SEED.as_bytes(), &[global_bump]],
+        )?;
+    } else if global_pda_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    } else if global_pda_account.data_len() < GLOBAL_PDA_WITH_CONFIG_SIZE {
+        // 📏 全局功德PDA原来只有4字节的功德值，扩容的租金由创作者补足
+        let deficit = Rent::get()?
+            .minimum_balance(GLOBAL_PDA_WITH_CONFIG_SIZE)
+            .saturating_sub(global_pda_account.lamports());
+        if deficit > 0 {
+            invoke(
+                &system_instruction::transfer(creator.key, global_pda_account.key, deficit),
+                &[creator.clone(), global_pda_account.clone(), system_program.clone()],
+            )?;
+        }
+        global_pda_account.resize(GLOBAL_PDA_WITH_CONFIG_SIZE)?;
+    }
+    config.pack(&mut global_pda_account.data.borrow_mut()[GLOBAL_CONFIG_COPY_OFFSET..])?;
+    Ok(())
+}
+
+// ⛏️ 函数名：increment_with_proof(nonce) - 附带工作量证明的功德+1
+// 类比：先做一道算术题再投票，sha256(功德账户地址 || 上次证明的slot || nonce) 的前导0比特数要达到全局配置的难度；
+// 每次成功后记录当前slot，同一个 nonce 在下一个slot就失效，不能重放；难度为0时等同于 increment()
+fn process_increment_with_proof(program_id: &Pubkey, accounts: &[AccountInfo], config: &GlobalConfig, instruction_data: &[u8]) -> ProgramResult {
+    let nonce = parse_increment_with_proof_args(instruction_data)?;
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    require_program_owned(program_id, gongde_account)?;
+
+    // 📏 上次证明的slot记录在当前布局的末尾，旧布局的账户提示先迁移
+    require_current_layout(gongde_account)?;
+
+    if config.pow_enabled() {
+        let last_slot = read_last_increment_slot(&gongde_account.data.borrow()).unwrap_or(0);
+        if !meets_pow_difficulty(&pow_hash(gongde_account.key, last_slot, nonce), config.pow_difficulty) {
+            log_msg!("nonce {} 没有达到工作量证明难度 {} 比特", nonce, config.pow_difficulty);
+            return Err(GongDeError::InvalidProofOfWork.into());
+        }
+    }
+
+    process_increment(program_id, accounts, 1)?;
+    write_last_increment_slot(&mut gongde_account.data.borrow_mut(), Clock::get()?.slot)?;
+    Ok(())
+}
+
+// 🙂 函数名：set_emoji(codepoint) - 设置头像emoji
+// 类比：昵称旁边的小头像，排行榜和查询结果中显示在用户旁边；码点为0时清除
+// 只接受可以显示的字符，控制字符、空白和私用区字符返回 InvalidEmoji
+fn process_set_emoji(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let gongde_account = next_account_info(accounts_iter)?; // 功德账户（可写）
+    let authority = next_account_info(accounts_iter)?;      // 账户主人（签名者）
+
+    let codepoint = parse_set_emoji_args(instruction_data).inspect_err(|_| {
+        msg!("头像必须是可以显示的Unicode字符");
+    })?;
+
+    // ✍️ 只有账户主人可以设置头像
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    require_program_owned(program_id, gongde_account)?;
+    require_current_layout(gongde_account)?;
+
+    let mut data = gongde_account.data.borrow_mut();
+    if !is_gongde_authority(program_id, gongde_account.key, &data, authority.key)? {
+        msg!("只能为自己的功德账户设置头像");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !is_usable_account(&data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    write_emoji(&mut data, codepoint)?;
+    touch_last_active(&mut data, Clock::get()?.unix_timestamp);
+    if codepoint == 0 {
+        msg!("已清除头像");
+    } else {
+        log_msg!("头像码点: {}", codepoint);
+    }
+    Ok(())
+}
+
+// ========================================
+// 💡 Solana智能合约架构总结
+// ========================================
+// 
+// 🎯 这个合约实现了三十个"函数"：
+// 
+// 1. increment() - 指令码0
+//    - 输入：一个可写的功德账户
+//    - 功能：将账户中的u32值+1（如果未达到最大值），累计功德 lifetime_total 同步+1；
+//      系统程序之后传入签名的账户主人时，账户不存在会先创建公开功德箱（地址被抢先转账也能创建）
+//    - 输出：更新后的值（通过日志）、MeritChangeEvent 功德值变化事件（sol_log_data）
+// 
+// 2. close() - 指令码1  
+//    - 输入：功德账户 + 账户主人（签名者，功德账户必须由其派生）
+//    - 功能：删除功德账户，退还租金给用户
+//    - 输出：成功消息
+// 
+// 3. initialize(is_public) - 指令码2
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：设置是否为公开功德箱，私有功德箱只有主人能增加功德；旧布局大小的账户返回 AccountDataTooSmall，需要先迁移
+//    - 输出：成功消息
+// 
+// 4. close_if_empty() - 指令码3
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：功德值为0时才关闭账户，否则返回 NotEmpty 错误
+//    - 输出：成功消息
+// 
+// 5. protocol_version() - 指令码4
+//    - 输入：无
+//    - 功能：查询协议版本和支持的指令码范围
+//    - 输出：return data [版本号, 最小指令码, 最大指令码]
+// 
+// 6. transfer_merit(amount) - 指令码5
+//    - 输入：转出者功德账户 + 转出者（签名者）+ 接收者功德账户 + 转赠状态PDA + 系统程序
+//    - 功能：转赠功德，每人每天转出不超过 DAILY_TRANSFER_CAP，接收不受限制
+//    - 输出：转赠日志、双方的 MeritChangeEvent
+// 
+// 7. increment_by(amount) - 指令码6
+//    - 输入：与 increment() 相同
+//    - 功能：一次增加 amount 点功德，amount 必须在 [MIN_INCREMENT, MAX_INCREMENT] 内
+//    - 输出：更新后的值（通过日志）、MeritChangeEvent
+// 
+// 8. migrate() - 指令码7
+//    - 输入：旧布局的功德账户 + 付款人（签名者）+ 系统程序
+//    - 功能：扩容到当前布局，4字节账户标记为已初始化的公开功德箱，累计功德从当前功德值开始，补足租金差额；已是最新布局时跳过
+//    - 输出：成功消息
+// 
+// 9. restore_to(value, checkpoint) - 指令码8
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：把功德值回滚到检查点记录的值，只能调低，检查点不能晚于当前时间，累计功德不受影响
+//    - 输出：RestoreEvent 审计事件和 MeritChangeEvent（sol_log_data）、return data 回滚后的功德值（u32 小端序）
+// 
+// 10. create_campaign(id, start, end, multiplier, budget) - 指令码9
+//    - 输入：发起人功德账户 + 发起人（签名者）+ 配捐活动PDA + 系统程序
+//    - 功能：从发起人的功德中划出奖池创建配捐活动；increment()/increment_by() 在系统程序之后传入活动账户，
+//      活动时间窗口 [start, end) 内每点功德额外配捐 multiplier 点，奖池耗尽或活动不在进行中时只做普通增加
+//    - 输出：成功消息、发起人的 MeritChangeEvent
+// 
+// 11. shared_increment(amount) - 指令码10
+//    - 输入：共享账本PDA + 用户（签名者）+ 全局PDA + 系统程序
+//    - 功能：在共享账本中给签名用户的条目增加功德，新用户由本人支付条目租金，手续费和全局功德同 increment_by()
+//    - 输出：更新后的值（通过日志）、全局PDA的 MeritChangeEvent
+// 
+// 12. set_beneficiary(beneficiary, timeout_days) - 指令码11
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：指定受益人和等待天数（不少于 MIN_BENEFICIARY_TIMEOUT_DAYS），受益人为全0公钥时清除；
+//      账户主人本人的 increment、转赠、回滚、发起活动和这条指令都会更新最后操作时间，推迟受益人的等待期
+//    - 输出：成功消息
+// 
+// 13. claim_as_beneficiary() - 指令码12
+//    - 输入：功德账户 + 受益人（签名者）+ 可选的受益人自己的功德账户
+//    - 功能：最后操作时间超过等待天数后，受益人接管账户成为新的主人；传入自己的功德账户时改为合并功德并关闭这个账户，
+//      未到期返回 BeneficiaryClaimTooEarly
+//    - 输出：成功消息，合并时双方的 MeritChangeEvent
+// 
+// 14. endorse() - 指令码13
+//    - 输入：被背书的功德账户 + 背书人（签名者）+ 背书人的功德账户 + 背书记录PDA + 系统程序
+//    - 功能：被背书人获得 1 + floor(log10(背书人功德)) 点功德，背书记录保存权重、背书人当时的功德和时间；
+//      每对用户只能背书一次，不能给自己背书
+//    - 输出：权重日志、被背书人的 MeritChangeEvent
+// 
+// 15. accrue() - 指令码14
+//    - 输入：功德账户 + 账户主人（签名者）+ 挂机功德状态PDA + 系统程序
+//    - 功能：按上次结算以来经过的时间每 ACCRUE_SECONDS_PER_MERIT 秒积累1点功德，单次最多 MAX_ACCRUED_MERIT 点，
+//      不足一个周期的零头留到下次；第一次调用时创建状态账户并开始计时
+//    - 输出：积累日志、MeritChangeEvent
+// 
+// 16. stake(lamports) - 指令码15
+//    - 输入：功德账户 + 账户主人（签名者）+ 系统程序
+//    - 功能：把 SOL 转入自己的功德账户并记为质押（不包含租金），先按原来的质押数量结算积累的功德
+//    - 输出：质押日志，有积累时的 MeritChangeEvent
+// 
+// 17. claim_accrued() - 指令码16
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：按 质押数量 × 经过的slot ÷ STAKE_LAMPORT_SLOTS_PER_MERIT 结算功德，余数留在账户中下次累加
+//    - 输出：积累日志、MeritChangeEvent
+// 
+// 18. unstake(lamports) - 指令码17
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：先结算积累的功德，再把质押的 SOL 转回账户主人，超过已质押数量返回 InsufficientStake，账户始终保留租金；
+//      关闭账户时质押的 SOL 随租金一起退还
+//    - 输出：取回日志，有积累时的 MeritChangeEvent
+// 
+// 19. create_lottery(round_id, start_slot, end_slot, ticket_price) - 指令码18
+//    - 输入：彩票轮次PDA + 管理员（签名者）+ 系统程序
+//    - 功能：创建一轮彩票，投注时间 [start_slot, end_slot)，每张票花费 ticket_price 点功德
+//    - 输出：成功消息
+// 
+// 20. enter_lottery(tickets) - 指令码19
+//    - 输入：投注人的功德账户 + 投注人（签名者）+ 彩票轮次PDA + 系统程序
+//    - 功能：扣除 tickets × 票价 点功德进入奖池，在彩票轮次中追加投注条目；不在投注时间内返回 LotteryClosed
+//    - 输出：票号日志、投注人的 MeritChangeEvent
+// 
+// 21. settle_lottery() - 指令码20
+//    - 输入：彩票轮次PDA + SlotHashes 系统变量 + 中奖者的功德账户（没有人投注时可以省略），作废后改为最多 MAX_LOTTERY_REFUND_ACCOUNTS 个投注人的功德账户
+//    - 功能：结束slot之后任何人都可以开奖，由结束slot的哈希算出中奖票号，奖池全部加给中奖者；
+//      未到结束slot返回 LotteryNotEnded，重复开奖返回 LotteryAlreadySettled，没有人投注时只标记为已开奖；
+//      哈希还在 SlotHashes 窗口内却查不到时返回 SlotHashUnavailable，超过 SLOT_HASHES_MAX_ENTRIES 个slot后本轮作废，传入的投注人退还票款
+//    - 输出：开奖日志、中奖者或退款投注人的 MeritChangeEvent
+// 
+// 22. archive() - 指令码21
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：归档账户，功德保留；之后除了 Unarchive、Close、CloseIfEmpty 之外修改这个账户的指令都返回 AccountArchived
+//    - 输出：归档日志
+// 
+// 23. unarchive() - 指令码22
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：取消归档，恢复正常使用；没有归档时返回 AccountNotArchived
+//    - 输出：取消归档日志
+// 
+// 24. set_minimum_client_version(version) - 指令码23
+//    - 输入：全局配置PDA + 全局功德PDA + 创作者（签名者）+ 系统程序
+//    - 功能：设置最低客户端版本，第一次设置时创建全局配置，并同步写入全局功德PDA中的副本；
+//      低于这个版本的指令返回 ClientVersionTooOld，旧编码视为版本0，增加功德和共享账本从副本读到最低版本
+//    - 输出：设置日志
+// 
+// 25. swap_counts() - 指令码24
+//    - 输入：第一个功德账户 + 其主人（签名者）+ 第二个功德账户 + 其主人（签名者）
+//    - 功能：双方都签名后交换两个账户的当前功德，累计功德和其他字段不变；两个账户相同时返回 InvalidArgument
+//    - 输出：交换日志、双方的 MeritChangeEvent
+// 
+// 26. report() - 指令码25
+//    - 输入：1到 MAX_REPORT_ACCOUNTS 个只读的功德账户
+//    - 功能：按顺序读取每个账户的功德并求和，不修改任何账户；账户重复传入返回 InvalidArgument
+//    - 输出：return data [合计 u64, 每个账户的功德 u32...]（小端序）
+// 
+// 27. endorse_many() - 指令码26
+//    - 输入：背书人（签名者）+ 背书人的功德账户 + 系统程序，之后每个目标依次是功德账户和背书记录PDA（最多 MAX_ENDORSE_MANY_TARGETS 个）
+//    - 功能：逐个给目标背书，权重与 endorse() 相同；已经背过书的目标跳过，其他错误整批失败
+//    - 输出：背书日志、被背书人的 MeritChangeEvent、return data 结果位图（第 i 位表示第 i 个目标这次新背书）
+// 
+// 28. set_pow_difficulty(difficulty) - 指令码27
+//    - 输入：全局配置PDA + 全局功德PDA + 创作者（签名者）+ 系统程序
+//    - 功能：设置工作量证明难度（前导0比特数），非0时 increment()/increment_by()/shared_increment() 返回 ProofOfWorkRequired；
+//      旧编码从全局功德PDA中的配置副本读到难度，同样被拒绝
+//    - 输出：设置日志
+// 
+// 29. increment_with_proof(nonce) - 指令码28
+//    - 输入：与 increment() 相同
+//    - 功能：sha256(功德账户地址 || 上次证明的slot || nonce) 达到难度后功德+1，并记录当前slot；nonce 不达标返回 InvalidProofOfWork
+//    - 输出：更新后的值（通过日志）、MeritChangeEvent
+// 
+// 30. set_emoji(codepoint) - 指令码29
+//    - 输入：功德账户 + 账户主人（签名者）
+//    - 功能：设置头像emoji（Unicode码点），0表示清除；控制字符、空白、私用区和无效码点返回 InvalidEmoji
+//    - 输出：设置日志
+// 
+// 🔑 核心设计特点：
+//   - 单一入口：所有调用都通过process_instruction
+//   - 指令分发：通过指令码区分功能，带版本的编码在指令码前面加上 [0xFF, 客户端版本]，参数之后不能有多余的字节
+//   - 账户模型：数据存储在accounts中，合约只处理逻辑
+//   - 无状态合约：合约本身不存储数据，数据在账户中
+// 
+// 📊 数据流程：
+//   客户端 → 创建Instruction → 打包Transaction → 发送到网络
+//   网络 → 调用process_instruction → 解析指令 → 执行对应逻辑
+//   合约 → 读取/写入账户数据 → 返回结果 → 客户端获取状态
+// 
+// 💰 经济模型：
+//   - 账户需要租金（防止垃圾数据）
+//   - 交易需要手续费（网络资源消耗）
+//   - 账户可关闭退还租金（资源回收）
+//   - 直接修改余额的地方统一使用 utils 中的 move_lamports()/drain_lamports()，不要手动解引用 lamports
+// ========================================
\ No newline at end of file
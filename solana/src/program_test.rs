@@ -0,0 +1,47 @@
+// ========================================
+// ProgramTest 集成 - 其他项目在自己的 solana-program-test 环境里加载本合约
+// 合约以原生函数的方式运行，不需要先 cargo build-sbf；开启 `program-test` feature 后可用
+// ========================================
+
+use solana_program::{pubkey::Pubkey, rent::Rent};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::Account;
+
+use crate::utils::{derive_gongde_account_address, gongde_account_data};
+
+/// 注册到 ProgramTest 的程序名，与 cargo build-sbf 生成的 gong_de_increase.so 同名，
+/// 开启 prefer_bpf 时会改为加载编译好的合约文件
+pub const PROGRAM_NAME: &str = "gong_de_increase";
+
+/// 把本合约的指令处理函数注册到 ProgramTest
+///
+/// # 参数
+/// * `pt` - 调用方的测试环境
+/// * `program_id` - 部署合约的程序ID，功德账户等PDA都从它派生
+pub fn add_to_program_test(pt: &mut ProgramTest, program_id: Pubkey) {
+    pt.add_program(PROGRAM_NAME, program_id, processor!(crate::process_instruction));
+}
+
+/// 在测试开始前直接放入一个已初始化的功德账户，租金刚好免租
+///
+/// # 参数
+/// * `pt` - 调用方的测试环境
+/// * `program_id` - 合约程序ID
+/// * `user` - 账户主人，功德账户地址由它派生
+/// * `value` - 预置的功德
+///
+/// # 返回
+/// * `Pubkey` - 功德账户地址
+pub fn add_gongde_account(pt: &mut ProgramTest, program_id: &Pubkey, user: &Pubkey, value: u32) -> Pubkey {
+    let gongde_pubkey = derive_gongde_account_address(user, program_id).expect("生成功德账户地址失败");
+    let data = gongde_account_data(value, false);
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+    pt.add_account(gongde_pubkey, account);
+    gongde_pubkey
+}
@@ -0,0 +1,3954 @@
+// ========================================
+// 纯数据逻辑 - 账户布局、指令解析、审计事件、功德等级
+// 不依赖 solana-program 和系统调用，链下索引器只开启 `decode` feature 即可使用；
+// 错误使用本地的 StateError，链上通过 From 转换为 ProgramError
+// ========================================
+
+use std::fmt;
+use std::str::FromStr;
+
+use solana_pubkey::Pubkey;
+
+use crate::error::{GongDeError, ParseNameError, StateError};
+
+// ========================================
+// 常量定义 - 消除硬编码
+// ========================================
+
+/// 功德值存储所需的字节数（u32类型需要4字节）
+pub const GONGDE_VALUE_SIZE: usize = 4;
+
+/// 标志位在账户数据中的偏移量（紧跟在功德值之后）
+pub const GONGDE_FLAGS_OFFSET: usize = GONGDE_VALUE_SIZE;
+
+/// 累计功德在账户数据中的偏移量（紧跟在1字节标志位之后）
+pub const GONGDE_LIFETIME_OFFSET: usize = GONGDE_FLAGS_OFFSET + 1;
+
+/// 累计功德存储所需的字节数（u64类型需要8字节）
+pub const GONGDE_LIFETIME_SIZE: usize = 8;
+
+/// 里程碑记录在账户数据中的偏移量（紧跟在累计功德之后）
+pub const GONGDE_MILESTONES_OFFSET: usize = GONGDE_LIFETIME_OFFSET + GONGDE_LIFETIME_SIZE;
+
+/// 每条里程碑记录的大小：里程碑 u64 + 达成时间 i64，里程碑为0表示还没有达成
+pub const MILESTONE_ENTRY_SIZE: usize = 16;
+
+/// 账户中记录的里程碑数量，与 MILESTONES 一一对应
+pub const MAX_MILESTONES: usize = 4;
+
+/// 记录达成时间的功德里程碑
+pub const MILESTONES: [u64; MAX_MILESTONES] = [10, 100, 1_000, 10_000];
+
+/// 账户主人最后一次操作时间在账户数据中的偏移量（紧跟在里程碑记录之后，i64 Unix时间戳）
+pub const GONGDE_LAST_ACTIVE_OFFSET: usize = GONGDE_MILESTONES_OFFSET + MAX_MILESTONES * MILESTONE_ENTRY_SIZE;
+
+/// 受益人公钥在账户数据中的偏移量，全0表示没有指定受益人
+pub const GONGDE_BENEFICIARY_OFFSET: usize = GONGDE_LAST_ACTIVE_OFFSET + 8;
+
+/// 受益人等待天数在账户数据中的偏移量（u16）
+pub const GONGDE_TIMEOUT_DAYS_OFFSET: usize = GONGDE_BENEFICIARY_OFFSET + 32;
+
+/// 接管后的账户主人在账户数据中的偏移量，全0表示账户主人就是派生出账户地址的用户
+pub const GONGDE_AUTHORITY_OFFSET: usize = GONGDE_TIMEOUT_DAYS_OFFSET + 2;
+
+/// 质押的 lamports 在账户数据中的偏移量（u64），不包含租金
+pub const GONGDE_STAKED_OFFSET: usize = GONGDE_AUTHORITY_OFFSET + 32;
+
+/// 上次结算质押功德的slot在账户数据中的偏移量（u64）
+pub const GONGDE_LAST_CLAIM_SLOT_OFFSET: usize = GONGDE_STAKED_OFFSET + 8;
+
+/// 质押功德结算后不足1点的零头在账户数据中的偏移量（u64，单位 lamport·slot）
+pub const GONGDE_STAKE_REMAINDER_OFFSET: usize = GONGDE_LAST_CLAIM_SLOT_OFFSET + 8;
+
+/// 上次带工作量证明增加功德的slot在账户数据中的偏移量（u64），是工作量证明挑战的一部分
+pub const GONGDE_LAST_INCREMENT_SLOT_OFFSET: usize = GONGDE_STAKE_REMAINDER_OFFSET + 8;
+
+/// 头像emoji在账户数据中的偏移量（u32，Unicode码点），0表示没有设置
+pub const GONGDE_EMOJI_OFFSET: usize = GONGDE_LAST_INCREMENT_SLOT_OFFSET + 8;
+
+/// 新建功德账户的完整数据大小（功德值 + 1字节标志位 + 累计功德 + 里程碑记录 + 受益人设置 + 质押 + 工作量证明 + 头像）
+/// 只有4字节的旧账户仍然可用，视为未初始化的公开账户
+pub const GONGDE_ACCOUNT_SIZE: usize = GONGDE_EMOJI_OFFSET + 4;
+
+/// 当前的账户布局版本：1 为只有功德值的旧布局，2 增加了标志位，3 增加了累计功德，4 增加了里程碑记录，
+/// 5 增加了最后操作时间、受益人和接管后的账户主人，6 增加了质押数量、上次结算slot和结算零头，
+/// 7 增加了上次带工作量证明增加功德的slot，8 增加了头像emoji
+pub const GONGDE_LAYOUT_VERSION: u8 = 8;
+
+/// 指定受益人时最少的等待天数，防止误设过短的期限让受益人立刻接管
+pub const MIN_BENEFICIARY_TIMEOUT_DAYS: u16 = 30;
+
+/// 标志位：账户已通过Initialize指令初始化
+pub const FLAG_INITIALIZED: u8 = 1 << 0;
+
+/// 标志位：公开功德箱，任何人都可以增加功德
+pub const FLAG_PUBLIC: u8 = 1 << 1;
+
+/// 标志位：账户已归档，保留功德但除了取消归档和关闭之外的修改都会失败
+pub const FLAG_ARCHIVED: u8 = 1 << 2;
+
+/// 默认的种子前缀，没有指定 GONGDE_SEED_PREFIX 时使用，已上链的账户都是按它生成的
+pub const DEFAULT_SEED_PREFIX: &str = "GongDeIncrease";
+
+/// 种子前缀，编译时用 GONGDE_SEED_PREFIX 环境变量指定（合约和客户端都要用同一个值编译）。
+/// 同一份代码给不同的部署（devnet/mainnet、不同社区）换一个前缀重新编译，功德账户和全局功德PDA的地址就互不相同
+pub const SEED_PREFIX: &str = match option_env!("GONGDE_SEED_PREFIX") {
+    Some(prefix) => prefix,
+    None => DEFAULT_SEED_PREFIX,
+};
+
+/// 全局功德账户种子中加在前缀前面的部分
+pub const GLOBAL_SEED_HEAD: &str = "Global";
+
+/// 功德账户种子字符串，就是种子前缀
+pub const GONGDE_ACCOUNT_SEED: &str = SEED_PREFIX;
+
+/// 全局功德账户种子字符串，为 GLOBAL_SEED_HEAD + 种子前缀
+pub const GLOBAL_GONGDE_ACCOUNT_SEED: &str = match std::str::from_utf8(&GLOBAL_SEED_BYTES) {
+    Ok(seed) => seed,
+    Err(_) => panic!("全局功德账户种子不是有效的UTF-8"),
+};
+
+/// 单个种子的最大长度，与 solana_program::pubkey::MAX_SEED_LEN 相同
+const MAX_SEED_LEN: usize = 32;
+
+const GLOBAL_SEED_BYTES: [u8; GLOBAL_SEED_HEAD.len() + SEED_PREFIX.len()] = concat_seed(GLOBAL_SEED_HEAD, SEED_PREFIX);
+
+// 前缀太长时编译失败，而不是部署后才发现地址算不出来
+const _: () = assert!(
+    !SEED_PREFIX.is_empty() && GLOBAL_GONGDE_ACCOUNT_SEED.len() <= MAX_SEED_LEN,
+    "GONGDE_SEED_PREFIX 不能为空，也不能超过26个字节"
+);
+
+/// 编译期拼接两个种子字符串，N 必须等于两者长度之和
+const fn concat_seed<const N: usize>(head: &str, tail: &str) -> [u8; N] {
+    let (head, tail) = (head.as_bytes(), tail.as_bytes());
+    let mut bytes = [0u8; N];
+    let mut i = 0;
+    while i < head.len() {
+        bytes[i] = head[i];
+        i += 1;
+    }
+    let mut j = 0;
+    while j < tail.len() {
+        bytes[head.len() + j] = tail[j];
+        j += 1;
+    }
+    bytes
+}
+
+/// 转赠状态PDA种子，完整种子为 [TRANSFER_STATE_SEED, 转出者公钥]
+pub const TRANSFER_STATE_SEED: &[u8] = b"xferstate";
+
+/// 转赠状态账户大小：日期序号 i64 + 当天已转出 u32
+pub const TRANSFER_STATE_SIZE: usize = 12;
+
+/// 每个用户每天最多转出的功德
+pub const DAILY_TRANSFER_CAP: u32 = 10;
+
+/// IncrementBy 单次最少增加的功德，0是无意义的操作
+pub const MIN_INCREMENT: u64 = 1;
+
+/// IncrementBy 单次最多增加的功德，防止一次直接刷到上限
+pub const MAX_INCREMENT: u64 = 100;
+
+/// 一天的秒数，按UTC零点换日
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// 配捐活动PDA种子，完整种子为 [CAMPAIGN_SEED, 发起人公钥, 活动编号（u64小端序）]
+pub const CAMPAIGN_SEED: &[u8] = b"campaign";
+
+/// 配捐活动账户大小：发起人 32 + 开始时间 i64 + 结束时间 i64 + 倍数 u32 + 奖池余额 u32
+/// 与功德账户、转赠状态等其他账户的大小都不同，合约据此识别活动账户
+pub const CAMPAIGN_SIZE: usize = 56;
+
+/// 配捐倍数上限，每增加1点功德最多额外获得的功德
+pub const MAX_CAMPAIGN_MULTIPLIER: u32 = 10;
+
+/// 共享账本PDA种子，整个程序只有一个共享账本
+pub const SHARED_LEDGER_SEED: &[u8] = b"ledger";
+
+/// 共享账本头部大小：条目数量 u32
+pub const SHARED_LEDGER_HEADER_SIZE: usize = 4;
+
+/// 共享账本每个条目的大小：用户公钥 32 + 功德值 u32
+pub const SHARED_LEDGER_ENTRY_SIZE: usize = 36;
+
+/// 背书记录PDA种子，完整种子为 [ENDORSEMENT_SEED, 背书人公钥, 被背书的功德账户]
+pub const ENDORSEMENT_SEED: &[u8] = b"endorse";
+
+/// 背书记录大小：权重 u32 + 背书时背书人的功德 u32 + 背书时间 i64
+pub const ENDORSEMENT_SIZE: usize = 16;
+
+/// 挂机功德状态PDA种子，完整种子为 [ACCRUAL_STATE_SEED, 功德账户]
+pub const ACCRUAL_STATE_SEED: &[u8] = b"accrue";
+
+/// 挂机功德状态账户大小：上次结算时间 i64
+pub const ACCRUAL_STATE_SIZE: usize = 8;
+
+/// 挂机功德的速率：每过这么多秒积累1点功德
+pub const ACCRUE_SECONDS_PER_MERIT: i64 = 3_600;
+
+/// 单次结算最多积累的功德，长时间不结算时超出的部分作废，防止一次跳得太多
+pub const MAX_ACCRUED_MERIT: u32 = 24;
+
+/// 质押功德的速率：每 (质押的 lamports × 经过的slot) 达到这个数积累1点功德
+/// 按每slot 400毫秒估算，质押 1 SOL 一天（216,000 个slot）积累1点
+pub const STAKE_LAMPORT_SLOTS_PER_MERIT: u64 = 1_000_000_000 * 216_000;
+
+/// 彩票轮次PDA种子，完整种子为 [LOTTERY_SEED, 管理员公钥, 轮次编号（u64小端序）]
+pub const LOTTERY_SEED: &[u8] = b"lottery";
+
+/// 彩票轮次的固定部分：管理员 32 + 轮次编号 u64 + 开始slot u64 + 结束slot u64 + 票价 u32
+/// + 总票数 u32 + 开奖状态 u8（0未开奖、1已开奖、2作废）+ 中奖票号 u32 + 投注条目数 u32
+pub const LOTTERY_HEADER_SIZE: usize = 73;
+
+/// 彩票每个投注条目的大小：功德账户 32 + 截至这个条目的累计票数 u32
+pub const LOTTERY_ENTRY_SIZE: usize = 36;
+
+/// SlotHashes 系统变量中每条记录的大小：slot u64 + 哈希 32字节
+pub const SLOT_HASH_ENTRY_SIZE: usize = 40;
+
+/// SlotHashes 系统变量最多保留的记录数，结束slot之后超过这么多个slot还没有开奖，结束slot的哈希就查不到了
+pub const SLOT_HASHES_MAX_ENTRIES: u64 = 512;
+
+/// 彩票轮次固定部分中开奖状态字节的取值：已开奖
+pub const LOTTERY_STATUS_SETTLED: u8 = 1;
+
+/// 彩票轮次固定部分中开奖状态字节的取值：结束slot的哈希过期，本轮作废，票款退还给投注者
+pub const LOTTERY_STATUS_VOIDED: u8 = 2;
+
+/// 一条 SettleLottery 指令最多退款的投注功德账户数
+pub const MAX_LOTTERY_REFUND_ACCOUNTS: usize = 16;
+
+/// 全局配置PDA种子，完整种子为 [CONFIG_SEED]
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// 全局配置的大小：最低客户端版本 u8 + 工作量证明难度 u8
+/// 加入工作量证明之前创建的全局配置只有1字节，读取时难度视为0，设置难度时扩容
+pub const CONFIG_SIZE: usize = 2;
+
+/// 全局功德PDA中全局配置副本的偏移量（紧跟在全局功德值之后）
+/// 旧编码的指令不附带全局配置PDA，增加功德的指令都带着全局功德PDA，合约从这里读取配置
+pub const GLOBAL_CONFIG_COPY_OFFSET: usize = GONGDE_VALUE_SIZE;
+
+/// 带全局配置副本的全局功德PDA大小，第一次设置全局配置时从4字节扩容
+pub const GLOBAL_PDA_WITH_CONFIG_SIZE: usize = GLOBAL_CONFIG_COPY_OFFSET + CONFIG_SIZE;
+
+/// 工作量证明难度的上限（前导0比特数），再高客户端就算不出来了
+pub const MAX_POW_DIFFICULTY: u8 = 32;
+
+/// 带客户端版本的指令编码的第1字节，不会分配给任何指令码
+pub const VERSIONED_INSTRUCTION_MARKER: u8 = 0xFF;
+
+/// 本SDK的客户端版本，构建指令时自动写入；旧客户端需要强制升级时加1
+pub const CLIENT_VERSION: u8 = 1;
+
+// ========================================
+// 序列化反序列化工具函数
+// ========================================
+
+/// 从字节数组中读取功德值（u32，小端序）
+/// 
+/// # 参数
+/// * `data` - 字节数据数组
+/// 
+/// # 返回
+/// * `Result<u32, StateError>` - 解析的u32值
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度不足4字节
+pub fn read_gongde_value(data: &[u8]) -> Result<u32, StateError> {
+    if data.len() < GONGDE_VALUE_SIZE {
+        return Err(StateError::AccountDataTooSmall);
+    }
+    
+    Ok(u32::from_le_bytes([
+        data[0], data[1], data[2], data[3]
+    ]))
+}
+
+/// 将功德值写入字节数组（u32，小端序）
+/// 
+/// # 参数
+/// * `data` - 目标字节数据数组（可变引用）
+/// * `value` - 要写入的u32值
+/// 
+/// # 返回
+/// * `Result<(), StateError>` - 成功返回Ok(())
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度不足4字节
+pub fn write_gongde_value(data: &mut [u8], value: u32) -> Result<(), StateError> {
+    if data.len() < GONGDE_VALUE_SIZE {
+        return Err(StateError::AccountDataTooSmall);
+    }
+    
+    let bytes = value.to_le_bytes();
+    data[0..GONGDE_VALUE_SIZE].copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// 验证账户数据长度是否足够存储功德值
+/// 
+/// # 参数
+/// * `data_len` - 账户数据长度
+/// 
+/// # 返回
+/// * `Result<(), StateError>` - 验证通过返回Ok(())
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+pub fn validate_account_data_size(data_len: usize) -> Result<(), StateError> {
+    if data_len < GONGDE_VALUE_SIZE {
+        return Err(StateError::AccountDataTooSmall);
+    }
+    Ok(())
+}
+
+/// 读取账户标志位
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `u8` - 标志位，旧的4字节账户没有标志位，返回0
+pub fn read_gongde_flags(data: &[u8]) -> u8 {
+    data.get(GONGDE_FLAGS_OFFSET).copied().unwrap_or(0)
+}
+
+/// 判断账户是否为公开功德箱
+/// 旧的4字节账户（以及未初始化的账户）视为公开，任何人都可以增加功德
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `bool` - 是否允许任何人增加功德
+pub fn is_public_account(data: &[u8]) -> bool {
+    let flags = read_gongde_flags(data);
+    flags & FLAG_INITIALIZED == 0 || flags & FLAG_PUBLIC != 0
+}
+
+/// 判断账户是否可以增加功德
+/// 旧的4字节账户没有标志位，始终可用；新格式账户必须已初始化
+/// 关闭时数据被清零，同一交易内把租金转回来也无法"复活"继续使用
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `bool` - 是否可以增加功德
+pub fn is_usable_account(data: &[u8]) -> bool {
+    data.len() <= GONGDE_FLAGS_OFFSET || read_gongde_flags(data) & FLAG_INITIALIZED != 0
+}
+
+/// 判断账户是否已归档
+/// 旧的4字节账户没有标志位，不会被归档
+///
+/// # 参数
+/// * `data` - 账户数据字节数组
+///
+/// # 返回
+/// * `bool` - 是否已归档
+pub fn is_archived_account(data: &[u8]) -> bool {
+    read_gongde_flags(data) & FLAG_ARCHIVED != 0
+}
+
+/// 设置或清除归档标志，其他标志位不变
+///
+/// # 参数
+/// * `data` - 可变的账户数据字节数组
+/// * `archived` - 是否归档
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果是没有标志位的旧账户
+pub fn write_archived_flag(data: &mut [u8], archived: bool) -> Result<(), StateError> {
+    let flags = data.get_mut(GONGDE_FLAGS_OFFSET).ok_or(StateError::AccountDataTooSmall)?;
+    if archived {
+        *flags |= FLAG_ARCHIVED;
+    } else {
+        *flags &= !FLAG_ARCHIVED;
+    }
+    Ok(())
+}
+
+/// 读取累计功德：只增不减，回滚和转出都不会减少
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Result<u64, StateError>` - 累计功德；还没有迁移的旧账户没有这个字段，按当前功德值计算（和迁移后写入的值一致）
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度不足4字节
+pub fn read_lifetime_total(data: &[u8]) -> Result<u64, StateError> {
+    match data.get(GONGDE_LIFETIME_OFFSET..GONGDE_MILESTONES_OFFSET) {
+        Some(bytes) => {
+            let mut buffer = [0u8; GONGDE_LIFETIME_SIZE];
+            buffer.copy_from_slice(bytes);
+            Ok(u64::from_le_bytes(buffer))
+        }
+        None => read_gongde_value(data).map(u64::from),
+    }
+}
+
+/// 将累计功德写入字节数组（u64，小端序）
+/// 
+/// # 参数
+/// * `data` - 目标字节数据数组（可变引用）
+/// * `lifetime_total` - 累计功德
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户没有累计功德字段（需要先迁移）
+pub fn write_lifetime_total(data: &mut [u8], lifetime_total: u64) -> Result<(), StateError> {
+    let bytes = data
+        .get_mut(GONGDE_LIFETIME_OFFSET..GONGDE_MILESTONES_OFFSET)
+        .ok_or(StateError::AccountDataTooSmall)?;
+    bytes.copy_from_slice(&lifetime_total.to_le_bytes());
+    Ok(())
+}
+
+/// 增加功德时同步累加累计功德，没有这个字段的旧账户直接跳过（迁移时再按功德值补上）
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组（可变引用）
+/// * `amount` - 本次增加的功德
+pub fn add_lifetime_total(data: &mut [u8], amount: u32) -> Result<(), StateError> {
+    if data.len() < GONGDE_MILESTONES_OFFSET {
+        return Ok(());
+    }
+    let total = read_lifetime_total(data)?.saturating_add(u64::from(amount));
+    write_lifetime_total(data, total)
+}
+
+/// 读取已达成的里程碑，按里程碑从小到大排列
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Vec<Milestone>` - 已达成的里程碑；还没有迁移的旧账户没有这个字段，返回空列表
+pub fn read_milestones(data: &[u8]) -> Vec<Milestone> {
+    let Some(entries) = data.get(GONGDE_MILESTONES_OFFSET..GONGDE_LAST_ACTIVE_OFFSET) else {
+        return Vec::new();
+    };
+    entries
+        .chunks_exact(MILESTONE_ENTRY_SIZE)
+        .filter_map(|entry| {
+            let milestone = u64::from_le_bytes(entry[..8].try_into().ok()?);
+            let reached_at = i64::from_le_bytes(entry[8..].try_into().ok()?);
+            (milestone != 0).then_some(Milestone { milestone, reached_at })
+        })
+        .collect()
+}
+
+/// 功德从 previous 增加到 current 时，记录这次跨过的里程碑的达成时间
+/// 每个里程碑只记录第一次达成的时间：回滚或转出后再次跨过不会覆盖；没有这个字段的旧账户直接跳过
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组（可变引用）
+/// * `previous` - 增加前的功德值
+/// * `current` - 增加后的功德值
+/// * `now` - 集群时间（Unix时间戳）
+/// 
+/// # 返回
+/// * `Vec<u64>` - 本次新记录的里程碑
+pub fn record_milestones(data: &mut [u8], previous: u32, current: u32, now: i64) -> Vec<u64> {
+    let Some(entries) = data.get_mut(GONGDE_MILESTONES_OFFSET..GONGDE_LAST_ACTIVE_OFFSET) else {
+        return Vec::new();
+    };
+    let mut recorded = Vec::new();
+    for (entry, milestone) in entries.chunks_exact_mut(MILESTONE_ENTRY_SIZE).zip(MILESTONES) {
+        let crossed = u64::from(previous) < milestone && milestone <= u64::from(current);
+        if crossed && entry[..8] == [0u8; 8] {
+            entry[..8].copy_from_slice(&milestone.to_le_bytes());
+            entry[8..].copy_from_slice(&now.to_le_bytes());
+            recorded.push(milestone);
+        }
+    }
+    recorded
+}
+
+/// 判断账户是否需要迁移到当前布局
+/// 
+/// # 参数
+/// * `data_len` - 账户数据长度
+/// 
+/// # 返回
+/// * `bool` - 旧布局（4字节、5字节、13字节、77字节、151字节、175字节或183字节）的账户返回true
+pub fn needs_migration(data_len: usize) -> bool {
+    (GONGDE_VALUE_SIZE..GONGDE_ACCOUNT_SIZE).contains(&data_len)
+}
+
+/// 指定布局版本的账户数据大小，用于估算租金
+/// 
+/// # 参数
+/// * `layout_version` - 布局版本，见 GONGDE_LAYOUT_VERSION
+/// 
+/// # 返回
+/// * `Option<usize>` - 账户数据大小，未知版本返回None
+pub fn gongde_account_size(layout_version: u8) -> Option<usize> {
+    match layout_version {
+        1 => Some(GONGDE_VALUE_SIZE),
+        2 => Some(GONGDE_LIFETIME_OFFSET),
+        3 => Some(GONGDE_MILESTONES_OFFSET),
+        4 => Some(GONGDE_LAST_ACTIVE_OFFSET),
+        5 => Some(GONGDE_STAKED_OFFSET),
+        6 => Some(GONGDE_LAST_INCREMENT_SLOT_OFFSET),
+        7 => Some(GONGDE_EMOJI_OFFSET),
+        8 => Some(GONGDE_ACCOUNT_SIZE),
+        _ => None,
+    }
+}
+
+/// 根据账户数据长度判断布局版本，和 `gongde_account_size` 互为反函数
+/// 比当前布局更长的账户（以后新增字段或预留空间）按当前版本处理
+/// 
+/// # 参数
+/// * `data_len` - 账户数据长度
+/// 
+/// # 返回
+/// * `Option<u8>` - 布局版本，数据不足4字节（不是功德账户）返回None
+pub fn layout_version(data_len: usize) -> Option<u8> {
+    (1..=GONGDE_LAYOUT_VERSION)
+        .rev()
+        .find(|version| gongde_account_size(*version).is_some_and(|size| data_len >= size))
+}
+
+/// 判断数据长度是否正好是某个布局版本的功德账户大小
+/// 本合约的其他账户（共享账本、配捐活动、彩票、转赠状态等）大小都不在其中，
+/// 增加功德和迁移据此拒绝把这些账户当作功德账户改写；全局PDA和功德账户一样是4字节，需要按地址区分
+///
+/// # 参数
+/// * `data_len` - 账户数据长度
+///
+/// # 返回
+/// * `bool` - 4、5、13、77、151、175、183或187字节返回true
+pub fn is_gongde_layout_size(data_len: usize) -> bool {
+    layout_version(data_len).and_then(gongde_account_size) == Some(data_len)
+}
+
+/// 写入初始化标志位
+/// 
+/// # 参数
+/// * `data` - 目标字节数据数组（可变引用）
+/// * `is_public` - 是否为公开功德箱
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户没有标志位空间
+/// * `StateError::AccountAlreadyInitialized` - 如果账户已经初始化过
+pub fn write_initialized_flags(data: &mut [u8], is_public: bool) -> Result<(), StateError> {
+    if data.len() <= GONGDE_FLAGS_OFFSET {
+        return Err(StateError::AccountDataTooSmall);
+    }
+    if data[GONGDE_FLAGS_OFFSET] & FLAG_INITIALIZED != 0 {
+        return Err(StateError::AccountAlreadyInitialized);
+    }
+
+    data[GONGDE_FLAGS_OFFSET] = if is_public {
+        FLAG_INITIALIZED | FLAG_PUBLIC
+    } else {
+        FLAG_INITIALIZED
+    };
+    Ok(())
+}
+
+/// 生成最新布局、已初始化的功德账户数据，测试中直接写入账户，不用先发交易增加功德
+/// 累计功德与当前功德相同，其余字段为0（没有里程碑、受益人和质押）
+///
+/// # 参数
+/// * `value` - 当前功德
+/// * `is_public` - 是否为公开功德箱
+///
+/// # 返回
+/// * `Vec<u8>` - GONGDE_ACCOUNT_SIZE 字节的账户数据
+pub fn gongde_account_data(value: u32, is_public: bool) -> Vec<u8> {
+    let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+    data[..GONGDE_VALUE_SIZE].copy_from_slice(&value.to_le_bytes());
+    data[GONGDE_FLAGS_OFFSET] = if is_public { FLAG_INITIALIZED | FLAG_PUBLIC } else { FLAG_INITIALIZED };
+    data[GONGDE_LIFETIME_OFFSET..GONGDE_MILESTONES_OFFSET].copy_from_slice(&u64::from(value).to_le_bytes());
+    data
+}
+
+// ========================================
+// 增加功德的规则 - 链上处理和客户端预测共用同一套函数
+// ========================================
+
+/// 增加功德后的值，个人功德和全局功德使用同样的规则
+/// 
+/// # 参数
+/// * `current` - 当前功德
+/// * `amount` - 增加的功德
+/// 
+/// # 返回
+/// * `Option<u32>` - 增加后的功德，超过上限时停在 u32::MAX；已经圆满（u32::MAX）时返回None，表示不做任何改动
+pub fn increment_merit(current: u32, amount: u32) -> Option<u32> {
+    (current < u32::MAX).then(|| current.saturating_add(amount))
+}
+
+/// 预测一次 Increment / IncrementBy（不带配捐活动）之后的个人功德和全局功德
+/// 个人功德已圆满时指令直接结束，不收手续费，全局功德也不变
+/// 
+/// # 参数
+/// * `value` - 执行前的个人功德
+/// * `global` - 执行前的全局功德
+/// * `amount` - 增加的功德
+/// 
+/// # 返回
+/// * `(u32, u32)` - 执行后的 (个人功德, 全局功德)
+pub fn predict_increment(value: u32, global: u32, amount: u32) -> (u32, u32) {
+    match increment_merit(value, amount) {
+        Some(value) => (value, increment_merit(global, amount).unwrap_or(global)),
+        None => (value, global),
+    }
+}
+
+// ========================================
+// 受益人 - 账户主人长期不操作时由受益人接管
+// ========================================
+
+/// 读取账户主人最后一次操作的时间
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Option<i64>` - Unix时间戳；还没有迁移的旧账户没有这个字段，返回None
+pub fn read_last_active(data: &[u8]) -> Option<i64> {
+    data.get(GONGDE_LAST_ACTIVE_OFFSET..GONGDE_BENEFICIARY_OFFSET)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(i64::from_le_bytes)
+}
+
+/// 记录账户主人的一次操作，受益人的等待期从这里重新计算；没有这个字段的旧账户直接跳过
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组（可变引用）
+/// * `now` - 集群时间（Unix时间戳）
+pub fn touch_last_active(data: &mut [u8], now: i64) {
+    if let Some(bytes) = data.get_mut(GONGDE_LAST_ACTIVE_OFFSET..GONGDE_BENEFICIARY_OFFSET) {
+        bytes.copy_from_slice(&now.to_le_bytes());
+    }
+}
+
+/// 读取受益人设置
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Option<(Pubkey, u16)>` - (受益人, 等待天数)；没有指定受益人或旧账户返回None
+pub fn read_beneficiary(data: &[u8]) -> Option<(Pubkey, u16)> {
+    let beneficiary = read_pubkey_field(data, GONGDE_BENEFICIARY_OFFSET)?;
+    let timeout_days = data
+        .get(GONGDE_TIMEOUT_DAYS_OFFSET..GONGDE_AUTHORITY_OFFSET)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u16::from_le_bytes)?;
+    Some((beneficiary, timeout_days))
+}
+
+/// 写入受益人设置，传入None时清除
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组（可变引用）
+/// * `beneficiary` - (受益人, 等待天数)
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户没有受益人字段（需要先迁移）
+pub fn write_beneficiary(data: &mut [u8], beneficiary: Option<(Pubkey, u16)>) -> Result<(), StateError> {
+    let (beneficiary, timeout_days) = beneficiary.unwrap_or_default();
+    let bytes = data
+        .get_mut(GONGDE_BENEFICIARY_OFFSET..GONGDE_AUTHORITY_OFFSET)
+        .ok_or(StateError::AccountDataTooSmall)?;
+    bytes[..32].copy_from_slice(beneficiary.as_ref());
+    bytes[32..].copy_from_slice(&timeout_days.to_le_bytes());
+    Ok(())
+}
+
+/// 读取接管后的账户主人
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Option<Pubkey>` - 受益人接管后的账户主人；没有被接管（账户主人就是派生出地址的用户）或旧账户返回None
+pub fn read_authority(data: &[u8]) -> Option<Pubkey> {
+    read_pubkey_field(data, GONGDE_AUTHORITY_OFFSET)
+}
+
+/// 写入接管后的账户主人
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户没有这个字段（需要先迁移）
+pub fn write_authority(data: &mut [u8], authority: &Pubkey) -> Result<(), StateError> {
+    data.get_mut(GONGDE_AUTHORITY_OFFSET..GONGDE_STAKED_OFFSET)
+        .ok_or(StateError::AccountDataTooSmall)?
+        .copy_from_slice(authority.as_ref());
+    Ok(())
+}
+
+/// 读取32字节的公钥字段，全0视为没有设置
+fn read_pubkey_field(data: &[u8], offset: usize) -> Option<Pubkey> {
+    let bytes: [u8; 32] = data.get(offset..offset + 32)?.try_into().ok()?;
+    (bytes != [0u8; 32]).then(|| Pubkey::new_from_array(bytes))
+}
+
+/// 受益人最早可以接管的时间：最后一次操作之后满 timeout_days 天，按 SECONDS_PER_DAY 计算
+/// 
+/// # 参数
+/// * `last_active` - 账户主人最后一次操作的时间
+/// * `timeout_days` - 等待天数
+pub fn beneficiary_claimable_at(last_active: i64, timeout_days: u16) -> i64 {
+    last_active.saturating_add(i64::from(timeout_days) * SECONDS_PER_DAY)
+}
+
+/// 受益人现在能否接管：必须超过等待期，正好到期的那一秒还不行
+/// 
+/// # 参数
+/// * `last_active` - 账户主人最后一次操作的时间
+/// * `timeout_days` - 等待天数
+/// * `now` - 集群时间（Unix时间戳）
+pub fn can_claim_as_beneficiary(last_active: i64, timeout_days: u16, now: i64) -> bool {
+    now > beneficiary_claimable_at(last_active, timeout_days)
+}
+
+// ========================================
+// 账户数据结构
+// ========================================
+
+/// 已达成的功德里程碑
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Milestone {
+    /// 里程碑功德值，见 MILESTONES
+    pub milestone: u64,
+    /// 第一次达成的时间（Unix时间戳）
+    pub reached_at: i64,
+}
+
+/// 解码后的功德账户数据
+/// 账户数据按字节布局原地读写，这个结构只用于一次性读取全部字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GongDeAccount {
+    /// 功德值
+    pub value: u32,
+    /// 标志位，旧的4字节账户为0
+    pub flags: u8,
+    /// 累计功德，只增不减
+    pub lifetime_total: u64,
+}
+
+impl GongDeAccount {
+    /// 从账户数据解码
+    /// 只读取已定义的前缀（功德值、标志位和累计功德），后面多出的字节（以后新增的字段或预留空间）直接忽略，
+    /// 旧版本的客户端和合约读到扩容后的账户也不会出错
+    /// 
+    /// # 参数
+    /// * `data` - 账户数据字节数组
+    /// 
+    /// # 返回
+    /// * `Result<Self, StateError>` - 解码后的账户数据
+    /// 
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足4字节
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        Ok(Self {
+            value: read_gongde_value(data)?,
+            flags: read_gongde_flags(data),
+            lifetime_total: read_lifetime_total(data)?,
+        })
+    }
+
+    /// 是否已通过Initialize指令初始化
+    pub fn is_initialized(&self) -> bool {
+        self.flags & FLAG_INITIALIZED != 0
+    }
+
+    /// 是否允许任何人增加功德（未初始化的账户保持公开）
+    pub fn is_public(&self) -> bool {
+        !self.is_initialized() || self.flags & FLAG_PUBLIC != 0
+    }
+
+    /// 是否已归档
+    pub fn is_archived(&self) -> bool {
+        self.flags & FLAG_ARCHIVED != 0
+    }
+}
+
+// ========================================
+// 转赠额度工具函数
+// ========================================
+
+/// 把时间戳换算成日期序号（UTC），签到等按天计算的功能共用
+/// 
+/// # 参数
+/// * `unix_timestamp` - 链上时钟的Unix时间戳（秒）
+/// 
+/// # 返回
+/// * `i64` - 自1970-01-01起的天数
+pub fn day_index(unix_timestamp: i64) -> i64 {
+    unix_timestamp.div_euclid(SECONDS_PER_DAY)
+}
+
+/// 距离下一次换日（UTC零点）还有多少秒，客户端预测额度何时恢复时使用
+///
+/// # 参数
+/// * `unix_timestamp` - Unix时间戳（秒），客户端应使用集群时间而不是本地时间
+///
+/// # 返回
+/// * `i64` - 1到 SECONDS_PER_DAY 之间的秒数，恰好在零点时为一整天
+pub fn seconds_until_next_day(unix_timestamp: i64) -> i64 {
+    SECONDS_PER_DAY - unix_timestamp.rem_euclid(SECONDS_PER_DAY)
+}
+
+/// 转出者的每日转赠状态，存放在 [TRANSFER_STATE_SEED, 转出者] PDA中，首次转赠时创建
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransferState {
+    /// 最近一次转赠的日期序号
+    pub day: i64,
+    /// 该日已转出的功德
+    pub transferred_today: u32,
+}
+
+impl TransferState {
+    /// 从账户数据解码，超出 TRANSFER_STATE_SIZE 的字节忽略
+    /// 
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        if data.len() < TRANSFER_STATE_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        let mut day = [0u8; 8];
+        day.copy_from_slice(&data[0..8]);
+        let mut transferred_today = [0u8; 4];
+        transferred_today.copy_from_slice(&data[8..12]);
+        Ok(Self {
+            day: i64::from_le_bytes(day),
+            transferred_today: u32::from_le_bytes(transferred_today),
+        })
+    }
+
+    /// 编码写入账户数据
+    /// 
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), StateError> {
+        if data.len() < TRANSFER_STATE_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        data[0..8].copy_from_slice(&self.day.to_le_bytes());
+        data[8..12].copy_from_slice(&self.transferred_today.to_le_bytes());
+        Ok(())
+    }
+
+    /// 记录一次转赠，换日后额度清零
+    /// 
+    /// # 参数
+    /// * `today` - 当前日期序号
+    /// * `amount` - 本次转出的功德
+    /// * `cap` - 每日额度
+    /// 
+    /// # 错误
+    /// * `GongDeError::DailyCapReached` - 如果超过当天额度
+    pub fn record_transfer(&mut self, today: i64, amount: u32, cap: u32) -> Result<(), StateError> {
+        if today != self.day {
+            self.day = today;
+            self.transferred_today = 0;
+        }
+        let total = self
+            .transferred_today
+            .checked_add(amount)
+            .filter(|total| *total <= cap)
+            .ok_or(GongDeError::DailyCapReached)?;
+        self.transferred_today = total;
+        Ok(())
+    }
+
+    /// 当天还能转出多少功德，与 record_transfer 的判断一致
+    ///
+    /// # 参数
+    /// * `today` - 当前日期序号
+    /// * `cap` - 每日额度
+    pub fn remaining_today(&self, today: i64, cap: u32) -> u32 {
+        if today != self.day {
+            cap
+        } else {
+            cap.saturating_sub(self.transferred_today)
+        }
+    }
+}
+
+// ========================================
+// 配捐活动
+// ========================================
+
+/// 配捐活动：活动期间每增加1点功德，额外从奖池获得 multiplier 点功德
+/// 奖池由发起人创建活动时从自己的功德中划出，配捐只是转移功德，不会凭空产生
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Campaign {
+    /// 发起人，奖池从其功德中划出
+    pub organizer: Pubkey,
+    /// 开始时间（Unix时间戳，含）
+    pub start: i64,
+    /// 结束时间（Unix时间戳，不含）
+    pub end: i64,
+    /// 每点功德的配捐倍数
+    pub multiplier: u32,
+    /// 奖池剩余功德
+    pub remaining: u32,
+}
+
+impl Campaign {
+    /// 从账户数据解码，超出 CAMPAIGN_SIZE 的字节忽略
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        if data.len() < CAMPAIGN_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        let organizer = Pubkey::try_from(&data[0..32]).map_err(|_| StateError::AccountDataTooSmall)?;
+        let mut start = [0u8; 8];
+        start.copy_from_slice(&data[32..40]);
+        let mut end = [0u8; 8];
+        end.copy_from_slice(&data[40..48]);
+        let mut multiplier = [0u8; 4];
+        multiplier.copy_from_slice(&data[48..52]);
+        let mut remaining = [0u8; 4];
+        remaining.copy_from_slice(&data[52..56]);
+        Ok(Self {
+            organizer,
+            start: i64::from_le_bytes(start),
+            end: i64::from_le_bytes(end),
+            multiplier: u32::from_le_bytes(multiplier),
+            remaining: u32::from_le_bytes(remaining),
+        })
+    }
+
+    /// 编码写入账户数据
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), StateError> {
+        if data.len() < CAMPAIGN_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        data[0..32].copy_from_slice(self.organizer.as_ref());
+        data[32..40].copy_from_slice(&self.start.to_le_bytes());
+        data[40..48].copy_from_slice(&self.end.to_le_bytes());
+        data[48..52].copy_from_slice(&self.multiplier.to_le_bytes());
+        data[52..56].copy_from_slice(&self.remaining.to_le_bytes());
+        Ok(())
+    }
+
+    /// 活动是否在进行中，时间窗口为 [start, end)
+    ///
+    /// # 参数
+    /// * `now` - 链上时钟的Unix时间戳
+    pub fn is_active(&self, now: i64) -> bool {
+        (self.start..self.end).contains(&now)
+    }
+
+    /// 为一次增加功德计算配捐并从奖池扣除；活动不在进行中或奖池耗尽时返回0，不影响本次增加
+    ///
+    /// # 参数
+    /// * `now` - 链上时钟的Unix时间戳
+    /// * `amount` - 本次增加的功德
+    /// * `headroom` - 功德账户距离 u32::MAX 的余量，超出的部分不从奖池扣除
+    ///
+    /// # 返回
+    /// * `u32` - 本次配捐的功德，奖池不足时只配捐剩余部分
+    pub fn take_bonus(&mut self, now: i64, amount: u32, headroom: u32) -> u32 {
+        if !self.is_active(now) {
+            return 0;
+        }
+        let bonus = amount.saturating_mul(self.multiplier).min(self.remaining).min(headroom);
+        self.remaining -= bonus;
+        bonus
+    }
+}
+
+// ========================================
+// 背书 - 功德越多的用户背书越有分量
+// ========================================
+
+/// 背书权重：1 + floor(log10(背书人的功德))，功德为0时权重为1
+/// 只用整数运算，链上不使用浮点数
+///
+/// # 参数
+/// * `endorser_value` - 背书人当前的功德
+///
+/// # 返回
+/// * `u32` - 被背书人获得的功德，1到10之间
+pub fn endorsement_weight(endorser_value: u32) -> u32 {
+    endorser_value.checked_ilog10().map_or(1, |digits| digits + 1)
+}
+
+/// 背书记录，存放在 [ENDORSEMENT_SEED, 背书人, 被背书的功德账户] PDA中，每对用户只能背书一次
+/// 记录背书时的权重和依据，方便事后审计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endorsement {
+    /// 被背书人获得的功德
+    pub weight: u32,
+    /// 背书时背书人的功德，权重据此计算
+    pub endorser_value: u32,
+    /// 背书时间（Unix时间戳）
+    pub endorsed_at: i64,
+}
+
+impl Endorsement {
+    /// 根据背书人当前的功德生成背书记录
+    ///
+    /// # 参数
+    /// * `endorser_value` - 背书人当前的功德
+    /// * `now` - 链上时钟的Unix时间戳
+    pub fn new(endorser_value: u32, now: i64) -> Self {
+        Self { weight: endorsement_weight(endorser_value), endorser_value, endorsed_at: now }
+    }
+
+    /// 从账户数据解码，超出 ENDORSEMENT_SIZE 的字节忽略
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        if data.len() < ENDORSEMENT_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        let mut weight = [0u8; 4];
+        weight.copy_from_slice(&data[0..4]);
+        let mut endorser_value = [0u8; 4];
+        endorser_value.copy_from_slice(&data[4..8]);
+        let mut endorsed_at = [0u8; 8];
+        endorsed_at.copy_from_slice(&data[8..16]);
+        Ok(Self {
+            weight: u32::from_le_bytes(weight),
+            endorser_value: u32::from_le_bytes(endorser_value),
+            endorsed_at: i64::from_le_bytes(endorsed_at),
+        })
+    }
+
+    /// 编码写入账户数据
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), StateError> {
+        if data.len() < ENDORSEMENT_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        data[0..4].copy_from_slice(&self.weight.to_le_bytes());
+        data[4..8].copy_from_slice(&self.endorser_value.to_le_bytes());
+        data[8..16].copy_from_slice(&self.endorsed_at.to_le_bytes());
+        Ok(())
+    }
+}
+
+// ========================================
+// 挂机功德 - 不用一直点击，按经过的时间积累功德
+// ========================================
+
+/// 计算从上次结算到现在积累的功德
+/// 不足一个周期的零头保留到下次结算；超过上限时只给上限，零头和超出的部分都作废
+///
+/// # 参数
+/// * `last_accrued` - 上次结算时间（Unix时间戳）
+/// * `now` - 链上时钟的Unix时间戳
+///
+/// # 返回
+/// * `(u32, i64)` - (本次积累的功德, 新的结算时间)；时钟回拨时积累0且结算时间不变
+pub fn accrue_merit(last_accrued: i64, now: i64) -> (u32, i64) {
+    let periods = now.saturating_sub(last_accrued).max(0) / ACCRUE_SECONDS_PER_MERIT;
+    if periods >= i64::from(MAX_ACCRUED_MERIT) {
+        return (MAX_ACCRUED_MERIT, now);
+    }
+    (periods as u32, last_accrued + periods * ACCRUE_SECONDS_PER_MERIT)
+}
+
+/// 读取挂机功德状态中的上次结算时间
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+pub fn read_last_accrued(data: &[u8]) -> Result<i64, StateError> {
+    let bytes = data.get(..ACCRUAL_STATE_SIZE).ok_or(StateError::AccountDataTooSmall)?;
+    let mut last_accrued = [0u8; 8];
+    last_accrued.copy_from_slice(bytes);
+    Ok(i64::from_le_bytes(last_accrued))
+}
+
+/// 写入挂机功德状态中的上次结算时间
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+pub fn write_last_accrued(data: &mut [u8], last_accrued: i64) -> Result<(), StateError> {
+    let bytes = data.get_mut(..ACCRUAL_STATE_SIZE).ok_or(StateError::AccountDataTooSmall)?;
+    bytes.copy_from_slice(&last_accrued.to_le_bytes());
+    Ok(())
+}
+
+// ========================================
+// 质押功德 - 把 SOL 质押在自己的功德账户中，按数量和时长积累功德
+// ========================================
+
+/// 计算一段时间内质押积累的功德，全部使用整数运算
+/// 功德 = (质押数量 × 经过的slot + 上次的零头) ÷ STAKE_LAMPORT_SLOTS_PER_MERIT，余数作为新的零头
+///
+/// # 参数
+/// * `staked` - 这段时间内质押的 lamports
+/// * `elapsed_slots` - 经过的slot数
+/// * `remainder` - 上次结算留下的零头
+///
+/// # 返回
+/// * `(u64, u64)` - (积累的功德, 新的零头)，零头总是小于 STAKE_LAMPORT_SLOTS_PER_MERIT
+pub fn stake_merit(staked: u64, elapsed_slots: u64, remainder: u64) -> (u64, u64) {
+    // 两个u64相乘再加一个u64不会超过u128
+    let total = u128::from(staked) * u128::from(elapsed_slots) + u128::from(remainder);
+    let rate = u128::from(STAKE_LAMPORT_SLOTS_PER_MERIT);
+    let merit = u64::try_from(total / rate).unwrap_or(u64::MAX);
+    (merit, (total % rate) as u64)
+}
+
+/// 功德账户中的质押状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StakeState {
+    /// 质押的 lamports，不包含租金
+    pub staked: u64,
+    /// 上次结算的slot
+    pub last_claim_slot: u64,
+    /// 上次结算后不足1点功德的零头
+    pub remainder: u64,
+}
+
+impl StakeState {
+    /// 从功德账户数据中读取质押状态
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果账户没有这些字段（需要先迁移）
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        let fields = data.get(GONGDE_STAKED_OFFSET..GONGDE_LAST_INCREMENT_SLOT_OFFSET).ok_or(StateError::AccountDataTooSmall)?;
+        let field = |index: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&fields[index * 8..index * 8 + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        Ok(Self { staked: field(0), last_claim_slot: field(1), remainder: field(2) })
+    }
+
+    /// 把质押状态写回功德账户数据，只覆盖质押字段
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果账户没有这些字段（需要先迁移）
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), StateError> {
+        let fields = data.get_mut(GONGDE_STAKED_OFFSET..GONGDE_LAST_INCREMENT_SLOT_OFFSET).ok_or(StateError::AccountDataTooSmall)?;
+        fields[..8].copy_from_slice(&self.staked.to_le_bytes());
+        fields[8..16].copy_from_slice(&self.last_claim_slot.to_le_bytes());
+        fields[16..].copy_from_slice(&self.remainder.to_le_bytes());
+        Ok(())
+    }
+
+    /// 结算到指定slot，零头留在状态中；质押数量变化前必须先结算，否则新的数量会按旧的时长计算
+    ///
+    /// # 参数
+    /// * `slot` - 当前slot
+    ///
+    /// # 返回
+    /// * `u64` - 本次积累的功德
+    pub fn settle(&mut self, slot: u64) -> u64 {
+        let elapsed_slots = slot.saturating_sub(self.last_claim_slot);
+        let (merit, remainder) = stake_merit(self.staked, elapsed_slots, self.remainder);
+        self.last_claim_slot = self.last_claim_slot.max(slot);
+        self.remainder = remainder;
+        merit
+    }
+}
+
+/// 解析Stake和Unstake指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+///
+/// # 返回
+/// * `Result<u64, StateError>` - 质押或取回的 lamports
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+/// * `GongDeError::InvalidAmount` - 如果数量为0
+pub fn parse_stake_args(instruction_data: &[u8]) -> Result<u64, StateError> {
+    let lamports = instruction_data
+        .get(1..9)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)?;
+    if lamports == 0 {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    Ok(lamports)
+}
+
+// ========================================
+// 彩票 - 花功德买票，结束slot的哈希决定中奖票号，奖池全部归中奖者
+// ========================================
+// 布局：[轮次信息 LOTTERY_HEADER_SIZE][功德账户 32字节 + 累计票数 u32] × 投注条目数
+// 条目按投注顺序追加，累计票数递增；第 i 个条目拥有 [上一个条目的累计票数, 本条目的累计票数) 之间的票号
+
+/// 容纳指定投注条目数的彩票轮次大小
+pub fn lottery_size(entries: usize) -> usize {
+    LOTTERY_HEADER_SIZE + entries * LOTTERY_ENTRY_SIZE
+}
+
+/// 彩票轮次的固定部分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LotteryRound {
+    /// 创建这一轮的管理员
+    pub admin: Pubkey,
+    /// 轮次编号，同一管理员可以创建多轮
+    pub round_id: u64,
+    /// 开始投注的slot（含）
+    pub start_slot: u64,
+    /// 结束投注的slot（不含），这个slot的哈希决定中奖票号
+    pub end_slot: u64,
+    /// 每张票花费的功德
+    pub ticket_price: u32,
+    /// 已售出的总票数
+    pub total_tickets: u32,
+    /// 是否已开奖，作废的轮次也算已开奖
+    pub settled: bool,
+    /// 结束slot的哈希过期后作废，不再开奖，票款退还给投注者
+    pub voided: bool,
+    /// 中奖票号，开奖前和没有人投注时为0
+    pub winning_ticket: u32,
+    /// 投注条目数
+    pub entry_count: u32,
+}
+
+impl LotteryRound {
+    /// 从账户数据解码固定部分，后面的投注条目不读取
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        if data.len() < LOTTERY_HEADER_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        let admin = Pubkey::try_from(&data[0..32]).map_err(|_| StateError::AccountDataTooSmall)?;
+        let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let u32_at = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        Ok(Self {
+            admin,
+            round_id: u64_at(32),
+            start_slot: u64_at(40),
+            end_slot: u64_at(48),
+            ticket_price: u32_at(56),
+            total_tickets: u32_at(60),
+            settled: data[64] != 0,
+            voided: data[64] == LOTTERY_STATUS_VOIDED,
+            winning_ticket: u32_at(65),
+            entry_count: u32_at(69),
+        })
+    }
+
+    /// 编码写入账户数据的固定部分，不动后面的投注条目
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), StateError> {
+        if data.len() < LOTTERY_HEADER_SIZE {
+            return Err(StateError::AccountDataTooSmall);
+        }
+        data[0..32].copy_from_slice(self.admin.as_ref());
+        data[32..40].copy_from_slice(&self.round_id.to_le_bytes());
+        data[40..48].copy_from_slice(&self.start_slot.to_le_bytes());
+        data[48..56].copy_from_slice(&self.end_slot.to_le_bytes());
+        data[56..60].copy_from_slice(&self.ticket_price.to_le_bytes());
+        data[60..64].copy_from_slice(&self.total_tickets.to_le_bytes());
+        data[64] = match (self.settled, self.voided) {
+            (_, true) => LOTTERY_STATUS_VOIDED,
+            (true, false) => LOTTERY_STATUS_SETTLED,
+            (false, false) => 0,
+        };
+        data[65..69].copy_from_slice(&self.winning_ticket.to_le_bytes());
+        data[69..73].copy_from_slice(&self.entry_count.to_le_bytes());
+        Ok(())
+    }
+
+    /// 现在能否投注，投注时间为 [start_slot, end_slot)
+    ///
+    /// # 参数
+    /// * `slot` - 当前slot
+    pub fn is_open(&self, slot: u64) -> bool {
+        (self.start_slot..self.end_slot).contains(&slot)
+    }
+
+    /// 奖池：全部票款
+    pub fn jackpot(&self) -> u64 {
+        u64::from(self.ticket_price) * u64::from(self.total_tickets)
+    }
+
+    /// 购买 tickets 张票需要花费的功德
+    pub fn cost(&self, tickets: u16) -> u64 {
+        u64::from(self.ticket_price) * u64::from(tickets)
+    }
+
+    /// 记录一次投注，返回新条目的累计票数，调用方负责把条目写到第 entry_count - 1 个位置
+    ///
+    /// # 错误
+    /// * `GongDeError::InvalidAmount` - 如果总票数超过 u32
+    pub fn record_entry(&mut self, tickets: u16) -> Result<u32, StateError> {
+        self.total_tickets = self
+            .total_tickets
+            .checked_add(u32::from(tickets))
+            .ok_or(GongDeError::InvalidAmount)?;
+        self.entry_count += 1;
+        Ok(self.total_tickets)
+    }
+}
+
+/// 写入第 index 个投注条目
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户还没有为这个条目扩容
+pub fn write_lottery_entry(data: &mut [u8], index: usize, gongde: &Pubkey, ticket_end: u32) -> Result<(), StateError> {
+    let offset = lottery_size(index);
+    let entry = data
+        .get_mut(offset..offset + LOTTERY_ENTRY_SIZE)
+        .ok_or(StateError::AccountDataTooSmall)?;
+    entry[..32].copy_from_slice(gongde.as_ref());
+    entry[32..].copy_from_slice(&ticket_end.to_le_bytes());
+    Ok(())
+}
+
+/// 读取第 index 个投注条目
+///
+/// # 返回
+/// * `Option<(Pubkey, u32)>` - (功德账户, 累计票数)，超出数据范围返回None
+pub fn read_lottery_entry(data: &[u8], index: usize) -> Option<(Pubkey, u32)> {
+    let offset = lottery_size(index);
+    let entry = data.get(offset..offset + LOTTERY_ENTRY_SIZE)?;
+    let gongde = Pubkey::try_from(&entry[..32]).ok()?;
+    Some((gongde, u32::from_le_bytes(entry[32..].try_into().ok()?)))
+}
+
+/// 作废的轮次中一个功德账户还没有退还的票款，退还后条目的功德账户清零，不会重复退款
+///
+/// # 参数
+/// * `data` - 彩票轮次的账户数据
+/// * `gongde` - 投注的功德账户
+///
+/// # 返回
+/// * `Result<u64, StateError>` - 这个功德账户所有条目的票款合计，没有投注或已经退还时为0
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度与条目数不符
+pub fn lottery_refund_due(data: &[u8], gongde: &Pubkey) -> Result<u64, StateError> {
+    let round = LotteryRound::unpack(data)?;
+    let mut due = 0;
+    let mut ticket_start = 0;
+    for index in 0..round.entry_count as usize {
+        let (owner, ticket_end) = read_lottery_entry(data, index).ok_or(StateError::AccountDataTooSmall)?;
+        if &owner == gongde {
+            due += u64::from(round.ticket_price) * u64::from(ticket_end - ticket_start);
+        }
+        ticket_start = ticket_end;
+    }
+    Ok(due)
+}
+
+/// 作废的轮次中还没有退款的功德账户，按第一次投注的顺序排列，不重复
+///
+/// # 参数
+/// * `data` - 彩票轮次的账户数据
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度与条目数不符
+pub fn lottery_pending_refunds(data: &[u8]) -> Result<Vec<Pubkey>, StateError> {
+    let round = LotteryRound::unpack(data)?;
+    let mut pending: Vec<Pubkey> = Vec::new();
+    for index in 0..round.entry_count as usize {
+        let (owner, _) = read_lottery_entry(data, index).ok_or(StateError::AccountDataTooSmall)?;
+        if owner != Pubkey::default() && !pending.contains(&owner) {
+            pending.push(owner);
+        }
+    }
+    Ok(pending)
+}
+
+/// 把一个功德账户的所有投注条目标记为已退款：功德账户清零，累计票数保留，其他条目的票号区间不变
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度与条目数不符
+pub fn mark_lottery_refunded(data: &mut [u8], gongde: &Pubkey) -> Result<(), StateError> {
+    let round = LotteryRound::unpack(data)?;
+    for index in 0..round.entry_count as usize {
+        let (owner, ticket_end) = read_lottery_entry(data, index).ok_or(StateError::AccountDataTooSmall)?;
+        if &owner == gongde {
+            write_lottery_entry(data, index, &Pubkey::default(), ticket_end)?;
+        }
+    }
+    Ok(())
+}
+
+/// 查找持有指定票号的功德账户，累计票数递增，二分查找第一个累计票数大于票号的条目
+///
+/// # 参数
+/// * `data` - 彩票轮次的账户数据
+/// * `ticket` - 票号，从0开始
+///
+/// # 返回
+/// * `Result<Option<Pubkey>, StateError>` - 功德账户；票号超出总票数时返回None
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度与条目数不符
+pub fn lottery_ticket_owner(data: &[u8], ticket: u32) -> Result<Option<Pubkey>, StateError> {
+    let round = LotteryRound::unpack(data)?;
+    let entries = round.entry_count as usize;
+    if data.len() < lottery_size(entries) {
+        return Err(StateError::AccountDataTooSmall);
+    }
+    let (mut low, mut high) = (0, entries);
+    while low < high {
+        let mid = (low + high) / 2;
+        let (_, ticket_end) = read_lottery_entry(data, mid).ok_or(StateError::AccountDataTooSmall)?;
+        if ticket_end > ticket {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok((low < entries).then(|| read_lottery_entry(data, low).map(|(gongde, _)| gongde)).flatten())
+}
+
+/// 从 SlotHashes 系统变量的数据中查找指定slot的哈希
+/// 数据布局：[记录数 u64][slot u64 + 哈希 32字节] × 记录数，按slot从新到旧排列，最多保留最近512个slot；
+/// 指定的slot没有出块时使用它之后第一个出块的slot，最旧的记录都比它新时已无法确定，返回None
+///
+/// # 参数
+/// * `data` - SlotHashes 系统变量的账户数据
+/// * `slot` - 要查找的slot
+///
+/// # 返回
+/// * `Option<[u8; 32]>` - 哈希，还没有产生或已经滚出记录范围时返回None
+pub fn slot_hash_at(data: &[u8], slot: u64) -> Option<[u8; 32]> {
+    let count = u64::from_le_bytes(data.get(..8)?.try_into().ok()?) as usize;
+    let entries = data.get(8..8 + count.checked_mul(SLOT_HASH_ENTRY_SIZE)?)?;
+    let mut found = None;
+    let mut covered = false;
+    for entry in entries.chunks_exact(SLOT_HASH_ENTRY_SIZE) {
+        let entry_slot = u64::from_le_bytes(entry[..8].try_into().ok()?);
+        if entry_slot < slot {
+            covered = true;
+            break;
+        }
+        found = Some(entry[8..].try_into().ok()?);
+        if entry_slot == slot {
+            covered = true;
+            break;
+        }
+    }
+    found.filter(|_| covered)
+}
+
+/// 由结束slot的哈希计算中奖票号：哈希前8字节按小端序取模
+///
+/// # 参数
+/// * `slot_hash` - 结束slot的哈希
+/// * `total_tickets` - 总票数，必须大于0
+pub fn winning_ticket(slot_hash: &[u8; 32], total_tickets: u32) -> u32 {
+    let seed = u64::from_le_bytes(slot_hash[..8].try_into().unwrap());
+    (seed % u64::from(total_tickets.max(1))) as u32
+}
+
+// ========================================
+// 共享账本 - 所有用户的功德存放在同一个PDA中，按用户公钥排序
+// ========================================
+// 布局：[条目数量 u32][用户公钥 32字节 + 功德值 u32] × 条目数量
+// 每个用户省掉一个独立账户的128字节账户开销，代价是所有增加功德的交易都要写同一个账户，无法并行执行
+
+/// 容纳指定条目数量的共享账本大小
+/// 
+/// # 参数
+/// * `entries` - 条目数量
+pub fn shared_ledger_size(entries: usize) -> usize {
+    SHARED_LEDGER_HEADER_SIZE + entries * SHARED_LEDGER_ENTRY_SIZE
+}
+
+/// 读取共享账本的条目数量
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据长度装不下记录的条目数量
+pub fn shared_ledger_len(data: &[u8]) -> Result<usize, StateError> {
+    let count = data
+        .get(..SHARED_LEDGER_HEADER_SIZE)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(StateError::AccountDataTooSmall)? as usize;
+    if data.len() < shared_ledger_size(count) {
+        return Err(StateError::AccountDataTooSmall);
+    }
+    Ok(count)
+}
+
+/// 第 index 个条目的起始位置
+fn shared_ledger_entry_offset(index: usize) -> usize {
+    SHARED_LEDGER_HEADER_SIZE + index * SHARED_LEDGER_ENTRY_SIZE
+}
+
+/// 在共享账本中二分查找用户
+/// 
+/// # 返回
+/// * `Result<Result<usize, usize>, StateError>` - 找到时为 Ok(条目下标)，否则为 Err(应插入的下标)
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账本数据不完整
+pub fn shared_ledger_search(data: &[u8], user: &Pubkey) -> Result<Result<usize, usize>, StateError> {
+    let count = shared_ledger_len(data)?;
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let offset = shared_ledger_entry_offset(middle);
+        match data[offset..offset + 32].cmp(user.as_ref()) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => return Ok(Ok(middle)),
+        }
+    }
+    Ok(Err(low))
+}
+
+/// 查询用户在共享账本中的功德
+/// 
+/// # 返回
+/// * `Result<Option<u32>, StateError>` - 用户不在账本中时返回None
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账本数据不完整
+pub fn shared_ledger_get(data: &[u8], user: &Pubkey) -> Result<Option<u32>, StateError> {
+    Ok(shared_ledger_search(data, user)?.ok().map(|index| {
+        let offset = shared_ledger_entry_offset(index) + 32;
+        let mut value = [0u8; 4];
+        value.copy_from_slice(&data[offset..offset + 4]);
+        u32::from_le_bytes(value)
+    }))
+}
+
+/// 给共享账本中的用户增加功德，用户不在账本中时按顺序插入新条目
+/// 
+/// # 参数
+/// * `data` - 账本数据，插入新用户时必须已经为新条目留出空间
+/// * `user` - 用户公钥
+/// * `amount` - 增加的功德，达到 u32::MAX 后不再增加
+/// 
+/// # 返回
+/// * `Result<u32, StateError>` - 增加后的功德
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账本数据不完整或没有为新条目留出空间
+pub fn shared_ledger_increment(data: &mut [u8], user: &Pubkey, amount: u32) -> Result<u32, StateError> {
+    let index = match shared_ledger_search(data, user)? {
+        Ok(index) => index,
+        Err(index) => {
+            let count = shared_ledger_len(data)?;
+            if data.len() < shared_ledger_size(count + 1) {
+                return Err(StateError::AccountDataTooSmall);
+            }
+            // 后面的条目整体后移一格，新条目功德从0开始
+            let offset = shared_ledger_entry_offset(index);
+            data.copy_within(offset..shared_ledger_size(count), offset + SHARED_LEDGER_ENTRY_SIZE);
+            data[offset..offset + 32].copy_from_slice(user.as_ref());
+            data[offset + 32..offset + SHARED_LEDGER_ENTRY_SIZE].fill(0);
+            data[..SHARED_LEDGER_HEADER_SIZE].copy_from_slice(&(count as u32 + 1).to_le_bytes());
+            index
+        }
+    };
+    let offset = shared_ledger_entry_offset(index) + 32;
+    let mut value = [0u8; 4];
+    value.copy_from_slice(&data[offset..offset + 4]);
+    let new_value = u32::from_le_bytes(value).saturating_add(amount);
+    data[offset..offset + 4].copy_from_slice(&new_value.to_le_bytes());
+    Ok(new_value)
+}
+
+// ========================================
+// 指令类型枚举
+// ========================================
+
+/// 协议版本号，指令集出现不兼容变化（删除或修改已有指令）时加1
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// 当前支持的最小指令码
+pub const MIN_INSTRUCTION_TAG: u8 = GongDeInstruction::Increment as u8;
+
+/// 当前支持的最大指令码
+pub const MAX_INSTRUCTION_TAG: u8 = GongDeInstruction::SetEmoji as u8;
+
+/// ProtocolVersion 指令返回数据的长度
+pub const PROTOCOL_VERSION_DATA_SIZE: usize = 3;
+
+/// 合约支持的指令类型
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GongDeInstruction {
+    /// 增加功德值指令
+    Increment = 0,
+    /// 关闭账户指令
+    Close = 1,
+    /// 初始化账户指令，参数：1字节 is_public（0=仅本人，1=公开）
+    Initialize = 2,
+    /// 关闭账户指令，仅当功德值为0时允许
+    CloseIfEmpty = 3,
+    /// 查询协议版本指令，不需要账户，通过 return data 返回 [版本号, 最小指令码, 最大指令码]
+    ProtocolVersion = 4,
+    /// 转赠功德指令，参数：4字节 amount（u32，小端序），受每日额度限制
+    TransferMerit = 5,
+    /// 一次增加多点功德，参数：8字节 amount（u64，小端序），范围见 MIN_INCREMENT/MAX_INCREMENT
+    IncrementBy = 6,
+    /// 把旧布局的账户迁移到当前布局，补足的租金由付款人支付
+    Migrate = 7,
+    /// 把功德值回滚到检查点记录的值，参数：8字节 value（u64）+ 8字节 checkpoint（i64 Unix时间戳），
+    /// 只能由主人签名且只能调低，成功后发出 RestoreEvent 审计事件
+    RestoreTo = 8,
+    /// 创建配捐活动，参数：8字节活动编号（u64）+ 8字节开始时间 + 8字节结束时间（i64 Unix时间戳）
+    /// + 4字节倍数（u32）+ 4字节奖池（u32），奖池从发起人的功德中扣除
+    CreateCampaign = 9,
+    /// 在共享账本中给签名用户增加功德，参数：8字节 amount（u64，小端序），范围同 IncrementBy；
+    /// 共享账本第一次使用时创建，新用户的条目空间由本人支付租金
+    SharedIncrement = 10,
+    /// 指定受益人，参数：32字节受益人公钥 + 2字节等待天数（u16，小端序）；
+    /// 受益人为全0公钥时清除设置，否则等待天数不能少于 MIN_BENEFICIARY_TIMEOUT_DAYS
+    SetBeneficiary = 11,
+    /// 受益人在账户主人超过等待天数没有操作后接管账户，可以选择把功德合并到自己的功德账户
+    ClaimAsBeneficiary = 12,
+    /// 给别人的功德账户背书，被背书人获得 endorsement_weight(背书人功德) 点功德，每对用户只能背书一次
+    Endorse = 13,
+    /// 结算挂机功德：按上次结算以来经过的时间每 ACCRUE_SECONDS_PER_MERIT 秒积累1点，单次最多 MAX_ACCRUED_MERIT 点；
+    /// 第一次调用时创建挂机状态并开始计时
+    Accrue = 14,
+    /// 把 SOL 质押到自己的功德账户，参数：8字节 lamports（u64，小端序）；质押数量变化前先结算之前积累的功德
+    Stake = 15,
+    /// 结算质押积累的功德：质押数量 × 经过的slot ÷ STAKE_LAMPORT_SLOTS_PER_MERIT，余数留到下次
+    ClaimAccrued = 16,
+    /// 取回质押的 SOL，参数：8字节 lamports（u64，小端序）；先结算之前积累的功德，账户始终保留租金
+    Unstake = 17,
+    /// 管理员创建一轮彩票，参数：8字节轮次编号（u64）+ 8字节开始slot + 8字节结束slot（u64）+ 4字节票价（u32）
+    CreateLottery = 18,
+    /// 花功德买彩票，参数：2字节票数（u16，小端序）；只能在 [开始slot, 结束slot) 内投注
+    EnterLottery = 19,
+    /// 结束slot之后任何人都可以开奖，由结束slot的哈希决定中奖票号，奖池全部加给中奖者；没有人投注时只标记为已开奖
+    SettleLottery = 20,
+    /// 归档账户：保留功德，之后除了 Unarchive 和关闭之外修改这个账户的指令都返回 AccountArchived
+    Archive = 21,
+    /// 取消归档，恢复正常使用
+    Unarchive = 22,
+    /// 创作者设置全局配置中的最低客户端版本，参数：1字节版本号（0表示不限制）；全局配置第一次设置时创建
+    SetMinimumClientVersion = 23,
+    /// 交换两个功德账户的功德值，两个账户的主人都必须签名；累计功德不变
+    SwapCounts = 24,
+    /// 只读汇总多个功德账户的功德，通过 return data 返回合计和每个账户的功德，不修改任何账户
+    Report = 25,
+    /// 一次给多个功德账户背书，已经背过书的跳过，通过 return data 返回每个目标是否新背书
+    EndorseMany = 26,
+    /// 创作者设置全局配置中的工作量证明难度，参数：1字节前导0比特数（0表示关闭，最大 MAX_POW_DIFFICULTY）；
+    /// 全局配置不存在时创建，只有1字节的旧配置先扩容
+    SetPowDifficulty = 27,
+    /// 带工作量证明的功德+1，参数：8字节 nonce（u64，小端序），账户同 Increment；
+    /// sha256(功德账户地址 || 上次增加功德的slot || nonce) 的前导0比特数不能少于全局配置中的难度
+    IncrementWithProof = 28,
+    /// 账户主人设置头像emoji，参数：4字节Unicode码点（u32，小端序），0表示清除；
+    /// 排行榜和查询结果中显示在用户旁边，不可见字符和私用区字符返回 InvalidEmoji
+    SetEmoji = 29,
+}
+
+impl GongDeInstruction {
+    /// 全部指令，按指令码升序排列
+    pub const ALL: [Self; 30] = [
+        Self::Increment,
+        Self::Close,
+        Self::Initialize,
+        Self::CloseIfEmpty,
+        Self::ProtocolVersion,
+        Self::TransferMerit,
+        Self::IncrementBy,
+        Self::Migrate,
+        Self::RestoreTo,
+        Self::CreateCampaign,
+        Self::SharedIncrement,
+        Self::SetBeneficiary,
+        Self::ClaimAsBeneficiary,
+        Self::Endorse,
+        Self::Accrue,
+        Self::Stake,
+        Self::ClaimAccrued,
+        Self::Unstake,
+        Self::CreateLottery,
+        Self::EnterLottery,
+        Self::SettleLottery,
+        Self::Archive,
+        Self::Unarchive,
+        Self::SetMinimumClientVersion,
+        Self::SwapCounts,
+        Self::Report,
+        Self::EndorseMany,
+        Self::SetPowDifficulty,
+        Self::IncrementWithProof,
+        Self::SetEmoji,
+    ];
+
+    /// 从指令码解析指令类型，不检查参数
+    ///
+    /// # 参数
+    /// * `tag` - 指令码
+    ///
+    /// # 返回
+    /// * `Option<Self>` - 指令码不认识时返回None
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        // ALL 按指令码升序排列，下标就是指令码
+        Self::ALL.get(usize::from(tag)).copied()
+    }
+
+    /// 指令码之后参数的字节数，与 InstructionSpec 的参数表一致
+    pub const fn payload_len(self) -> usize {
+        match self {
+            Self::Initialize | Self::SetMinimumClientVersion | Self::SetPowDifficulty => 1,
+            Self::EnterLottery => 2,
+            Self::TransferMerit | Self::SetEmoji => 4,
+            Self::IncrementBy | Self::SharedIncrement | Self::Stake | Self::Unstake | Self::IncrementWithProof => 8,
+            Self::RestoreTo => 16,
+            Self::CreateLottery => 28,
+            Self::CreateCampaign => 32,
+            Self::SetBeneficiary => 34,
+            Self::Increment
+            | Self::Close
+            | Self::CloseIfEmpty
+            | Self::ProtocolVersion
+            | Self::Migrate
+            | Self::ClaimAsBeneficiary
+            | Self::Endorse
+            | Self::Accrue
+            | Self::ClaimAccrued
+            | Self::SettleLottery
+            | Self::Archive
+            | Self::Unarchive
+            | Self::SwapCounts
+            | Self::Report
+            | Self::EndorseMany => 0,
+        }
+    }
+
+    /// 从字节解析指令类型，并检查指令码之后的参数是否完整
+    /// 参数之后多出的字节不在这里检查，合约分发前会再按参数表检查总长度
+    /// 
+    /// # 参数
+    /// * `instruction_data` - 指令数据字节数组
+    /// 
+    /// # 返回
+    /// * `Result<Self, StateError>` - 解析的指令类型
+    /// 
+    /// # 错误
+    /// * `StateError::EmptyInstructionData` - 如果指令数据为空
+    /// * `StateError::UnknownInstruction` - 如果指令码不认识
+    /// * `StateError::TruncatedInstructionData` - 如果参数的字节数不够
+    pub fn from_instruction_data(instruction_data: &[u8]) -> Result<Self, StateError> {
+        let (&tag, payload) = instruction_data.split_first().ok_or(StateError::EmptyInstructionData)?;
+        let instruction = Self::from_tag(tag).ok_or(StateError::UnknownInstruction(tag))?;
+        if payload.len() < instruction.payload_len() {
+            return Err(StateError::TruncatedInstructionData {
+                instruction,
+                expected: 1 + instruction.payload_len(),
+                actual: instruction_data.len(),
+            });
+        }
+        Ok(instruction)
+    }
+
+    /// 指令名称，用于命令行参数、日志和JSON输出
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Increment => "increment",
+            Self::Close => "close",
+            Self::Initialize => "initialize",
+            Self::CloseIfEmpty => "close-if-empty",
+            Self::ProtocolVersion => "protocol-version",
+            Self::TransferMerit => "transfer-merit",
+            Self::IncrementBy => "increment-by",
+            Self::Migrate => "migrate",
+            Self::RestoreTo => "restore-to",
+            Self::CreateCampaign => "create-campaign",
+            Self::SharedIncrement => "shared-increment",
+            Self::SetBeneficiary => "set-beneficiary",
+            Self::ClaimAsBeneficiary => "claim-as-beneficiary",
+            Self::Endorse => "endorse",
+            Self::Accrue => "accrue",
+            Self::Stake => "stake",
+            Self::ClaimAccrued => "claim-accrued",
+            Self::Unstake => "unstake",
+            Self::CreateLottery => "create-lottery",
+            Self::EnterLottery => "enter-lottery",
+            Self::SettleLottery => "settle-lottery",
+            Self::Archive => "archive",
+            Self::Unarchive => "unarchive",
+            Self::SetMinimumClientVersion => "set-minimum-client-version",
+            Self::SwapCounts => "swap-counts",
+            Self::Report => "report",
+            Self::EndorseMany => "endorse-many",
+            Self::SetPowDifficulty => "set-pow-difficulty",
+            Self::IncrementWithProof => "increment-with-proof",
+            Self::SetEmoji => "set-emoji",
+        }
+    }
+}
+
+impl fmt::Display for GongDeInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for GongDeInstruction {
+    type Err = ParseNameError;
+
+    /// 按名称解析指令，不区分大小写，`-`、`_` 可以省略（"IncrementBy"、"increment_by" 都可以）
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let wanted = normalize_name(input);
+        Self::ALL
+            .into_iter()
+            .find(|instruction| normalize_name(instruction.name()) == wanted)
+            .ok_or_else(|| ParseNameError {
+                kind: "指令",
+                input: input.to_string(),
+                valid: Self::ALL.iter().map(|instruction| instruction.name()).collect(),
+            })
+    }
+}
+
+/// 名称比较前统一格式：转小写并去掉 `-`、`_` 和首尾空白
+fn normalize_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// ProtocolVersion 指令返回的版本信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolInfo {
+    /// 协议版本号
+    pub version: u8,
+    /// 支持的最小指令码
+    pub min_instruction: u8,
+    /// 支持的最大指令码
+    pub max_instruction: u8,
+}
+
+impl ProtocolInfo {
+    /// 当前合约的版本信息
+    pub const CURRENT: Self = Self {
+        version: PROTOCOL_VERSION,
+        min_instruction: MIN_INSTRUCTION_TAG,
+        max_instruction: MAX_INSTRUCTION_TAG,
+    };
+
+    /// 编码为 return data：[版本号, 最小指令码, 最大指令码]
+    pub fn to_bytes(&self) -> [u8; PROTOCOL_VERSION_DATA_SIZE] {
+        [self.version, self.min_instruction, self.max_instruction]
+    }
+
+    /// 从 return data 解析版本信息
+    ///
+    /// # 错误
+    /// * `StateError::InvalidInstructionData` - 如果数据长度不对
+    pub fn from_bytes(data: &[u8]) -> Result<Self, StateError> {
+        match data {
+            [version, min_instruction, max_instruction] => Ok(Self {
+                version: *version,
+                min_instruction: *min_instruction,
+                max_instruction: *max_instruction,
+            }),
+            _ => Err(StateError::InvalidInstructionData),
+        }
+    }
+
+    /// 合约是否支持指定的指令
+    pub fn supports(&self, instruction: GongDeInstruction) -> bool {
+        (self.min_instruction..=self.max_instruction).contains(&(instruction as u8))
+    }
+}
+
+// ========================================
+// 汇总 - Report 指令只读汇总多个功德账户，调用方（包括CPI）从 return data 读取结果
+// ========================================
+// return data 布局：[合计功德 u64][每个账户的功德 u32] × 账户数，账户顺序与指令中的账户顺序一致
+
+/// Report 指令一次最多汇总的功德账户数，return data 最多 8 + 4 × 16 = 72 字节
+pub const MAX_REPORT_ACCOUNTS: usize = 16;
+
+/// Report 指令返回的汇总结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeritReport {
+    /// 全部账户的功德合计
+    pub total: u64,
+    /// 每个账户的功德，顺序与指令中的账户顺序一致
+    pub values: Vec<u32>,
+}
+
+impl MeritReport {
+    /// 按账户顺序汇总功德
+    pub fn new(values: Vec<u32>) -> Self {
+        let total = values.iter().copied().map(u64::from).sum();
+        Self { total, values }
+    }
+
+    /// 编码为 return data
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(8 + 4 * self.values.len());
+        data.extend_from_slice(&self.total.to_le_bytes());
+        for value in &self.values {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    /// 从 return data 解析汇总结果
+    ///
+    /// # 错误
+    /// * `StateError::InvalidInstructionData` - 如果数据长度不对，或合计与各账户之和不一致
+    pub fn from_bytes(data: &[u8]) -> Result<Self, StateError> {
+        let total = u64::from_le_bytes(read_le_bytes(data, 0)?);
+        let values = data.get(8..).ok_or(StateError::InvalidInstructionData)?;
+        if values.len() % 4 != 0 || values.len() / 4 > MAX_REPORT_ACCOUNTS {
+            return Err(StateError::InvalidInstructionData);
+        }
+        let report = Self::new(
+            values
+                .chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect(),
+        );
+        if report.total != total {
+            return Err(StateError::InvalidInstructionData);
+        }
+        Ok(report)
+    }
+}
+
+// ========================================
+// 批量背书 - EndorseMany 指令一次给多个功德账户背书，适合活动组织者给到场的人背书
+// ========================================
+// return data 布局：[结果位图 u32]，第 i 位为 1 表示第 i 个目标这次新背书，为 0 表示之前已经背过书而跳过
+
+/// EndorseMany 指令一次最多背书的目标数
+/// 每个目标占两个账户（功德账户和背书记录），一笔1232字节的交易大约能放14个，留出计算预算等指令的空间
+pub const MAX_ENDORSE_MANY_TARGETS: usize = 12;
+
+/// EndorseMany 指令返回的每个目标的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EndorseManyResult {
+    /// 结果位图，第 i 位对应第 i 个目标
+    pub endorsed: u32,
+}
+
+impl EndorseManyResult {
+    /// return data 长度
+    pub const SIZE: usize = 4;
+
+    /// 记录第 i 个目标这次新背书
+    pub fn mark_endorsed(&mut self, index: usize) {
+        self.endorsed |= 1 << index;
+    }
+
+    /// 第 i 个目标这次是否新背书，false 表示之前已经背过书而跳过
+    pub fn is_endorsed(&self, index: usize) -> bool {
+        index < MAX_ENDORSE_MANY_TARGETS && self.endorsed & (1 << index) != 0
+    }
+
+    /// 这次新背书的目标数
+    pub fn endorsed_count(&self) -> u32 {
+        self.endorsed.count_ones()
+    }
+
+    /// 编码为 return data
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        self.endorsed.to_le_bytes()
+    }
+
+    /// 从 return data 解析结果
+    ///
+    /// # 错误
+    /// * `StateError::InvalidInstructionData` - 如果数据长度不对，或超出最大目标数的位被置上
+    pub fn from_bytes(data: &[u8]) -> Result<Self, StateError> {
+        if data.len() != Self::SIZE {
+            return Err(StateError::InvalidInstructionData);
+        }
+        let endorsed = u32::from_le_bytes(read_le_bytes(data, 0)?);
+        if endorsed >> MAX_ENDORSE_MANY_TARGETS != 0 {
+            return Err(StateError::InvalidInstructionData);
+        }
+        Ok(Self { endorsed })
+    }
+}
+
+// ========================================
+// 客户端版本 - 指令数据最前面可选的版本字段，配合全局配置要求旧客户端升级
+// ========================================
+// 带版本的编码：[VERSIONED_INSTRUCTION_MARKER][客户端版本][指令码][参数...]，账户列表最后附上全局配置PDA；
+// 旧客户端的编码直接以指令码开头，不附带全局配置，视为版本0；带着全局功德PDA的指令从其中的配置副本检查（见 GLOBAL_CONFIG_COPY_OFFSET）
+
+/// 拆出指令数据最前面的客户端版本
+///
+/// # 参数
+/// * `instruction_data` - 收到的完整指令数据
+///
+/// # 返回
+/// * `(Option<u8>, &[u8])` - (客户端版本，旧编码为None；从指令码开始的数据)
+pub fn split_client_version(instruction_data: &[u8]) -> (Option<u8>, &[u8]) {
+    match instruction_data {
+        [VERSIONED_INSTRUCTION_MARKER, client_version, rest @ ..] => (Some(*client_version), rest),
+        _ => (None, instruction_data),
+    }
+}
+
+/// 在指令数据前面加上客户端版本
+///
+/// # 参数
+/// * `instruction_data` - 从指令码开始的数据
+/// * `client_version` - 客户端版本，SDK 使用 CLIENT_VERSION
+pub fn stamp_client_version(instruction_data: &[u8], client_version: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(instruction_data.len() + 2);
+    data.extend_from_slice(&[VERSIONED_INSTRUCTION_MARKER, client_version]);
+    data.extend_from_slice(instruction_data);
+    data
+}
+
+/// 全局配置，账户不存在时等同于默认值（不做任何限制）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GlobalConfig {
+    /// 最低客户端版本，带版本的指令低于这个版本时返回 ClientVersionTooOld；0表示不限制
+    pub minimum_client_version: u8,
+    /// 工作量证明难度（前导0比特数），大于0时增加功德必须使用 IncrementWithProof；0表示不需要
+    pub pow_difficulty: u8,
+}
+
+impl GlobalConfig {
+    /// 从账户数据解码，只有1字节的旧配置难度为0
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn unpack(data: &[u8]) -> Result<Self, StateError> {
+        match data {
+            [minimum_client_version, rest @ ..] => Ok(Self {
+                minimum_client_version: *minimum_client_version,
+                pow_difficulty: rest.first().copied().unwrap_or(0),
+            }),
+            [] => Err(StateError::AccountDataTooSmall),
+        }
+    }
+
+    /// 编码写入账户数据，难度为0时可以写入只有1字节的旧配置
+    ///
+    /// # 错误
+    /// * `StateError::AccountDataTooSmall` - 如果数据长度不足
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), StateError> {
+        let (first, rest) = data.split_first_mut().ok_or(StateError::AccountDataTooSmall)?;
+        *first = self.minimum_client_version;
+        match rest.first_mut() {
+            Some(field) => *field = self.pow_difficulty,
+            None if self.pow_difficulty == 0 => {}
+            None => return Err(StateError::AccountDataTooSmall),
+        }
+        Ok(())
+    }
+
+    /// 增加功德是否需要工作量证明
+    pub fn pow_enabled(&self) -> bool {
+        self.pow_difficulty > 0
+    }
+
+    /// 检查客户端版本是否满足最低要求
+    ///
+    /// # 错误
+    /// * `GongDeError::ClientVersionTooOld` - 如果客户端版本低于最低版本
+    pub fn check_client_version(&self, client_version: u8) -> Result<(), StateError> {
+        if client_version < self.minimum_client_version {
+            return Err(GongDeError::ClientVersionTooOld.into());
+        }
+        Ok(())
+    }
+}
+
+/// 读取全局功德PDA中的全局配置副本
+/// 还没有设置过全局配置时全局功德PDA只有4字节的功德值，等同于默认配置
+///
+/// # 参数
+/// * `data` - 全局功德PDA的账户数据
+///
+/// # 返回
+/// * `Result<GlobalConfig, StateError>` - 全局配置副本
+///
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果数据不足4字节（不是全局功德PDA）
+pub fn read_global_config_copy(data: &[u8]) -> Result<GlobalConfig, StateError> {
+    match data.get(GLOBAL_CONFIG_COPY_OFFSET..) {
+        Some([]) => Ok(GlobalConfig::default()),
+        Some(copy) => GlobalConfig::unpack(copy),
+        None => Err(StateError::AccountDataTooSmall),
+    }
+}
+
+/// 解析SetMinimumClientVersion指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 从指令码开始的数据
+///
+/// # 返回
+/// * `Result<u8, StateError>` - 最低客户端版本
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+pub fn parse_set_minimum_client_version_args(instruction_data: &[u8]) -> Result<u8, StateError> {
+    instruction_data.get(1).copied().ok_or(StateError::InvalidInstructionData)
+}
+
+/// 解析SetPowDifficulty指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 从指令码开始的数据
+///
+/// # 返回
+/// * `Result<u8, StateError>` - 工作量证明难度
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数或难度超过 MAX_POW_DIFFICULTY
+pub fn parse_set_pow_difficulty_args(instruction_data: &[u8]) -> Result<u8, StateError> {
+    instruction_data
+        .get(1)
+        .copied()
+        .filter(|difficulty| *difficulty <= MAX_POW_DIFFICULTY)
+        .ok_or(StateError::InvalidInstructionData)
+}
+
+// ========================================
+// 工作量证明 - 全局配置设置了难度后，增加功德前要先算出满足难度的 nonce，提高脚本刷功德的成本
+// ========================================
+
+/// 读取上次带工作量证明增加功德的slot，从来没有时为0
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Option<u64>` - slot；还没有迁移的旧账户没有这个字段，返回None
+pub fn read_last_increment_slot(data: &[u8]) -> Option<u64> {
+    data.get(GONGDE_LAST_INCREMENT_SLOT_OFFSET..GONGDE_EMOJI_OFFSET)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+}
+
+/// 记录这次带工作量证明增加功德的slot，下一次的工作量证明必须重新计算
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户没有这个字段（需要先迁移）
+pub fn write_last_increment_slot(data: &mut [u8], slot: u64) -> Result<(), StateError> {
+    data.get_mut(GONGDE_LAST_INCREMENT_SLOT_OFFSET..GONGDE_EMOJI_OFFSET)
+        .ok_or(StateError::AccountDataTooSmall)?
+        .copy_from_slice(&slot.to_le_bytes());
+    Ok(())
+}
+
+/// 解析IncrementWithProof指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 从指令码开始的数据
+///
+/// # 返回
+/// * `Result<u64, StateError>` - 客户端算出的 nonce
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+pub fn parse_increment_with_proof_args(instruction_data: &[u8]) -> Result<u64, StateError> {
+    instruction_data
+        .get(1..9)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)
+}
+
+/// 哈希开头连续为0的比特数
+pub fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// 工作量证明的哈希是否满足难度：sha256(用户公钥 || 上次增加功德的slot || nonce) 至少有 difficulty 个前导0比特
+/// 哈希本身由调用方计算，链上使用 sol_sha256 系统调用
+pub fn meets_pow_difficulty(hash: &[u8], difficulty: u8) -> bool {
+    leading_zero_bits(hash) >= u32::from(difficulty)
+}
+
+/// 解析Initialize指令的参数
+/// 
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+/// 
+/// # 返回
+/// * `Result<bool, StateError>` - 是否为公开功德箱
+/// 
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数或参数不是0/1
+pub fn parse_initialize_args(instruction_data: &[u8]) -> Result<bool, StateError> {
+    match instruction_data.get(1) {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(StateError::InvalidInstructionData),
+    }
+}
+
+/// 解析TransferMerit指令的参数
+/// 
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+/// 
+/// # 返回
+/// * `Result<u32, StateError>` - 转出的功德数量
+/// 
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数或数量为0
+pub fn parse_transfer_args(instruction_data: &[u8]) -> Result<u32, StateError> {
+    let amount = instruction_data
+        .get(1..5)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)?;
+    if amount == 0 {
+        return Err(StateError::InvalidInstructionData);
+    }
+    Ok(amount)
+}
+
+/// 解析IncrementBy指令的参数
+/// 
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+/// 
+/// # 返回
+/// * `Result<u64, StateError>` - 要增加的功德数量（未校验范围）
+/// 
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+pub fn parse_increment_by_args(instruction_data: &[u8]) -> Result<u64, StateError> {
+    instruction_data
+        .get(1..9)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)
+}
+
+/// 校验IncrementBy的数量是否在允许范围内
+/// 
+/// # 参数
+/// * `amount` - 要增加的功德数量
+/// 
+/// # 返回
+/// * `Result<u32, StateError>` - 校验通过的数量
+/// 
+/// # 错误
+/// * `GongDeError::InvalidAmount` - 如果数量为0或超出 [MIN_INCREMENT, MAX_INCREMENT]
+pub fn validate_increment_amount(amount: u64) -> Result<u32, StateError> {
+    if amount == 0 || !(MIN_INCREMENT..=MAX_INCREMENT).contains(&amount) {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    u32::try_from(amount).map_err(|_| GongDeError::InvalidAmount.into())
+}
+
+/// 解析RestoreTo指令的参数
+/// 
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+/// 
+/// # 返回
+/// * `Result<(u64, i64), StateError>` - 要恢复的功德值（未校验范围）和检查点时间戳
+/// 
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+pub fn parse_restore_args(instruction_data: &[u8]) -> Result<(u64, i64), StateError> {
+    let value = instruction_data
+        .get(1..9)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)?;
+    let checkpoint = instruction_data
+        .get(9..17)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)?;
+    Ok((value, checkpoint))
+}
+
+/// 校验要恢复的功德值，回滚只能调低，不能借此绕过手续费增加功德
+/// 
+/// # 参数
+/// * `value` - 要恢复的功德值
+/// * `current` - 账户当前的功德值
+/// 
+/// # 返回
+/// * `Result<u32, StateError>` - 校验通过的功德值
+/// 
+/// # 错误
+/// * `GongDeError::InvalidAmount` - 如果恢复值大于当前功德值
+pub fn validate_restore_value(value: u64, current: u32) -> Result<u32, StateError> {
+    if value > u64::from(current) {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    u32::try_from(value).map_err(|_| GongDeError::InvalidAmount.into())
+}
+
+/// RestoreTo 指令 return data 的长度：回滚后的功德值（u32 小端序）
+pub const RESTORE_RETURN_DATA_SIZE: usize = 4;
+
+/// 解析 RestoreTo 指令的 return data，得到合约写入账户的功德值
+/// 恢复值等于当前功德时功德不变，返回的就是原来的值
+///
+/// # 参数
+/// * `data` - 交易的 return data
+///
+/// # 返回
+/// * `Result<u32, StateError>` - 回滚后的功德值
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果数据长度不对
+pub fn parse_restore_return_data(data: &[u8]) -> Result<u32, StateError> {
+    if data.len() != RESTORE_RETURN_DATA_SIZE {
+        return Err(StateError::InvalidInstructionData);
+    }
+    Ok(u32::from_le_bytes(read_le_bytes(data, 0)?))
+}
+
+/// CreateCampaign指令的参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreateCampaignArgs {
+    /// 活动编号，同一发起人可以创建多个活动
+    pub campaign_id: u64,
+    /// 开始时间（Unix时间戳，含）
+    pub start: i64,
+    /// 结束时间（Unix时间戳，不含）
+    pub end: i64,
+    /// 每点功德的配捐倍数
+    pub multiplier: u32,
+    /// 奖池功德
+    pub budget: u32,
+}
+
+/// 解析并校验CreateCampaign指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+///
+/// # 返回
+/// * `Result<CreateCampaignArgs, StateError>` - 活动参数
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数或结束时间不晚于开始时间
+/// * `GongDeError::InvalidAmount` - 如果倍数不在 [1, MAX_CAMPAIGN_MULTIPLIER] 内或奖池为0
+pub fn parse_create_campaign_args(instruction_data: &[u8]) -> Result<CreateCampaignArgs, StateError> {
+    let args = CreateCampaignArgs {
+        campaign_id: u64::from_le_bytes(read_le_bytes(instruction_data, 1)?),
+        start: i64::from_le_bytes(read_le_bytes(instruction_data, 9)?),
+        end: i64::from_le_bytes(read_le_bytes(instruction_data, 17)?),
+        multiplier: u32::from_le_bytes(read_le_bytes(instruction_data, 25)?),
+        budget: u32::from_le_bytes(read_le_bytes(instruction_data, 29)?),
+    };
+    if args.end <= args.start {
+        return Err(StateError::InvalidInstructionData);
+    }
+    if !(1..=MAX_CAMPAIGN_MULTIPLIER).contains(&args.multiplier) || args.budget == 0 {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    Ok(args)
+}
+
+/// CreateLottery指令的参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreateLotteryArgs {
+    /// 轮次编号，同一管理员可以创建多轮
+    pub round_id: u64,
+    /// 开始投注的slot（含）
+    pub start_slot: u64,
+    /// 结束投注的slot（不含）
+    pub end_slot: u64,
+    /// 每张票花费的功德
+    pub ticket_price: u32,
+}
+
+/// 解析并校验CreateLottery指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数或结束slot不晚于开始slot
+/// * `GongDeError::InvalidAmount` - 如果票价为0
+pub fn parse_create_lottery_args(instruction_data: &[u8]) -> Result<CreateLotteryArgs, StateError> {
+    let args = CreateLotteryArgs {
+        round_id: u64::from_le_bytes(read_le_bytes(instruction_data, 1)?),
+        start_slot: u64::from_le_bytes(read_le_bytes(instruction_data, 9)?),
+        end_slot: u64::from_le_bytes(read_le_bytes(instruction_data, 17)?),
+        ticket_price: u32::from_le_bytes(read_le_bytes(instruction_data, 25)?),
+    };
+    if args.end_slot <= args.start_slot {
+        return Err(StateError::InvalidInstructionData);
+    }
+    if args.ticket_price == 0 {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    Ok(args)
+}
+
+/// 解析EnterLottery指令的参数
+///
+/// # 返回
+/// * `Result<u16, StateError>` - 购买的票数
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+/// * `GongDeError::InvalidAmount` - 如果票数为0
+pub fn parse_enter_lottery_args(instruction_data: &[u8]) -> Result<u16, StateError> {
+    let tickets = u16::from_le_bytes(read_le_bytes(instruction_data, 1)?);
+    if tickets == 0 {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    Ok(tickets)
+}
+
+/// 解析SetBeneficiary指令的参数并校验
+/// 
+/// # 参数
+/// * `instruction_data` - 完整的指令数据（第1字节是指令类型）
+/// 
+/// # 返回
+/// * `Result<Option<(Pubkey, u16)>, StateError>` - (受益人, 等待天数)，受益人为全0公钥时返回None表示清除
+/// 
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+/// * `GongDeError::InvalidAmount` - 如果等待天数少于 MIN_BENEFICIARY_TIMEOUT_DAYS
+pub fn parse_set_beneficiary_args(instruction_data: &[u8]) -> Result<Option<(Pubkey, u16)>, StateError> {
+    let beneficiary = instruction_data
+        .get(1..33)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(Pubkey::new_from_array)
+        .ok_or(StateError::InvalidInstructionData)?;
+    let timeout_days = instruction_data
+        .get(33..35)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u16::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)?;
+    if beneficiary == Pubkey::default() {
+        return Ok(None);
+    }
+    if timeout_days < MIN_BENEFICIARY_TIMEOUT_DAYS {
+        return Err(GongDeError::InvalidAmount.into());
+    }
+    Ok(Some((beneficiary, timeout_days)))
+}
+
+/// 从指令数据的 offset 处读取 N 个字节
+fn read_le_bytes<const N: usize>(instruction_data: &[u8], offset: usize) -> Result<[u8; N], StateError> {
+    instruction_data
+        .get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(StateError::InvalidInstructionData)
+}
+
+// ========================================
+// 头像emoji - 账户主人可以给自己选一个字符，排行榜和查询结果中显示在用户旁边
+// ========================================
+
+/// 读取账户的头像emoji
+/// 
+/// # 参数
+/// * `data` - 账户数据字节数组
+/// 
+/// # 返回
+/// * `Option<char>` - 没有设置、旧账户没有这个字段或者码点无效时返回None
+pub fn read_emoji(data: &[u8]) -> Option<char> {
+    data.get(GONGDE_EMOJI_OFFSET..GONGDE_EMOJI_OFFSET + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .filter(|codepoint| *codepoint != 0)
+        .and_then(char::from_u32)
+}
+
+/// 写入头像emoji的码点，0表示清除
+/// 
+/// # 错误
+/// * `StateError::AccountDataTooSmall` - 如果账户没有这个字段（需要先迁移）
+pub fn write_emoji(data: &mut [u8], codepoint: u32) -> Result<(), StateError> {
+    data.get_mut(GONGDE_EMOJI_OFFSET..GONGDE_EMOJI_OFFSET + 4)
+        .ok_or(StateError::AccountDataTooSmall)?
+        .copy_from_slice(&codepoint.to_le_bytes());
+    Ok(())
+}
+
+/// 检查码点能不能作为头像：必须是合法的Unicode标量值，并且不是控制字符、空白或私用区字符
+/// 
+/// # 参数
+/// * `codepoint` - Unicode码点，0表示清除
+/// 
+/// # 返回
+/// * `bool` - 可以作为头像或者是0时返回true
+pub fn is_valid_emoji(codepoint: u32) -> bool {
+    if codepoint == 0 {
+        return true;
+    }
+    let Some(c) = char::from_u32(codepoint) else {
+        return false;
+    };
+    let private_use = matches!(codepoint, 0xE000..=0xF8FF | 0xF0000..=0xFFFFD | 0x100000..=0x10FFFD);
+    !c.is_control() && !c.is_whitespace() && !private_use
+}
+
+/// 解析SetEmoji指令的参数
+///
+/// # 参数
+/// * `instruction_data` - 从指令码开始的数据
+///
+/// # 返回
+/// * `Result<u32, StateError>` - 头像的Unicode码点，0表示清除
+///
+/// # 错误
+/// * `StateError::InvalidInstructionData` - 如果缺少参数
+/// * `GongDeError::InvalidEmoji` - 如果码点不能作为头像，见 `is_valid_emoji`
+pub fn parse_set_emoji_args(instruction_data: &[u8]) -> Result<u32, StateError> {
+    let codepoint = instruction_data
+        .get(1..5)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(StateError::InvalidInstructionData)?;
+    if !is_valid_emoji(codepoint) {
+        return Err(GongDeError::InvalidEmoji.into());
+    }
+    Ok(codepoint)
+}
+
+// ========================================
+// 审计事件
+// ========================================
+
+/// RestoreEvent 日志的第一段，用来区分事件类型
+pub const RESTORE_EVENT_TAG: &[u8] = b"RestoreTo";
+
+/// RestoreEvent 编码后的长度：地址32字节 + 原值4字节 + 恢复值4字节 + 检查点8字节
+pub const RESTORE_EVENT_SIZE: usize = 48;
+
+/// 回滚功德值的审计事件，通过 sol_log_data 写入交易日志（"Program data: ..."）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestoreEvent {
+    /// 被回滚的功德账户
+    pub gongde: Pubkey,
+    /// 回滚前的功德值
+    pub previous: u32,
+    /// 回滚后的功德值
+    pub restored: u32,
+    /// 恢复值对应的检查点时间戳
+    pub checkpoint: i64,
+}
+
+impl RestoreEvent {
+    /// 编码为事件数据
+    pub fn pack(&self) -> [u8; RESTORE_EVENT_SIZE] {
+        let mut data = [0u8; RESTORE_EVENT_SIZE];
+        data[0..32].copy_from_slice(self.gongde.as_ref());
+        data[32..36].copy_from_slice(&self.previous.to_le_bytes());
+        data[36..40].copy_from_slice(&self.restored.to_le_bytes());
+        data[40..48].copy_from_slice(&self.checkpoint.to_le_bytes());
+        data
+    }
+
+    /// 从 sol_log_data 的各段数据解码，第一段必须是 RESTORE_EVENT_TAG
+    /// 
+    /// # 参数
+    /// * `fields` - 一条 "Program data:" 日志解码后的各段数据
+    /// 
+    /// # 返回
+    /// * `Option<Self>` - 不是 RestoreEvent 或长度不对时返回None
+    pub fn from_log_data<T: AsRef<[u8]>>(fields: &[T]) -> Option<Self> {
+        let [tag, data] = fields else {
+            return None;
+        };
+        let data: &[u8; RESTORE_EVENT_SIZE] = data.as_ref().try_into().ok()?;
+        if tag.as_ref() != RESTORE_EVENT_TAG {
+            return None;
+        }
+        let mut gongde = [0u8; 32];
+        gongde.copy_from_slice(&data[0..32]);
+        let mut previous = [0u8; 4];
+        previous.copy_from_slice(&data[32..36]);
+        let mut restored = [0u8; 4];
+        restored.copy_from_slice(&data[36..40]);
+        let mut checkpoint = [0u8; 8];
+        checkpoint.copy_from_slice(&data[40..48]);
+        Some(Self {
+            gongde: Pubkey::new_from_array(gongde),
+            previous: u32::from_le_bytes(previous),
+            restored: u32::from_le_bytes(restored),
+            checkpoint: i64::from_le_bytes(checkpoint),
+        })
+    }
+}
+
+/// MeritChangeEvent 日志的第一段，用来区分事件类型
+pub const MERIT_CHANGE_EVENT_TAG: &[u8] = b"MeritChange";
+
+/// MeritChangeEvent 编码后的长度：地址32字节 + 修改前4字节 + 修改后4字节
+pub const MERIT_CHANGE_EVENT_SIZE: usize = 40;
+
+/// 功德值变化事件：合约每次改写功德账户或全局PDA的功德值都会通过 sol_log_data 写入交易日志
+/// 交易元数据里没有账户数据，链下要知道一笔交易改了哪些账户、改前改后的值，只能靠这个事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeritChangeEvent {
+    /// 被修改的功德账户或全局PDA
+    pub account: Pubkey,
+    /// 修改前的功德值
+    pub before: u32,
+    /// 修改后的功德值
+    pub after: u32,
+}
+
+impl MeritChangeEvent {
+    /// 编码为事件数据
+    pub fn pack(&self) -> [u8; MERIT_CHANGE_EVENT_SIZE] {
+        let mut data = [0u8; MERIT_CHANGE_EVENT_SIZE];
+        data[0..32].copy_from_slice(self.account.as_ref());
+        data[32..36].copy_from_slice(&self.before.to_le_bytes());
+        data[36..40].copy_from_slice(&self.after.to_le_bytes());
+        data
+    }
+
+    /// 从 sol_log_data 的各段数据解码，第一段必须是 MERIT_CHANGE_EVENT_TAG
+    /// 
+    /// # 参数
+    /// * `fields` - 一条 "Program data:" 日志解码后的各段数据
+    /// 
+    /// # 返回
+    /// * `Option<Self>` - 不是 MeritChangeEvent 或长度不对时返回None
+    pub fn from_log_data<T: AsRef<[u8]>>(fields: &[T]) -> Option<Self> {
+        let [tag, data] = fields else {
+            return None;
+        };
+        let data: &[u8; MERIT_CHANGE_EVENT_SIZE] = data.as_ref().try_into().ok()?;
+        if tag.as_ref() != MERIT_CHANGE_EVENT_TAG {
+            return None;
+        }
+        let mut account = [0u8; 32];
+        account.copy_from_slice(&data[0..32]);
+        let mut before = [0u8; 4];
+        before.copy_from_slice(&data[32..36]);
+        let mut after = [0u8; 4];
+        after.copy_from_slice(&data[36..40]);
+        Some(Self {
+            account: Pubkey::new_from_array(account),
+            before: u32::from_le_bytes(before),
+            after: u32::from_le_bytes(after),
+        })
+    }
+
+    /// 功德变化量，减少时为负数
+    pub fn delta(&self) -> i64 {
+        i64::from(self.after) - i64::from(self.before)
+    }
+}
+
+// ========================================
+// 功德等级
+// ========================================
+
+/// 功德等级，门槛见 `threshold`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MeritLevel {
+    /// 🥉 初心: 0
+    Beginner,
+    /// 🥈 善念: 1-10
+    Kindness,
+    /// 🥇 善行: 11-100
+    GoodDeed,
+    /// 🏆 德高: 101-1000
+    Virtue,
+    /// 💎 圣贤: 1001-10000
+    Sage,
+    /// 🌟 功德圆满: 10000以上
+    Perfect,
+}
+
+impl MeritLevel {
+    /// 全部等级，按门槛升序排列
+    pub const ALL: [Self; 6] = [Self::Beginner, Self::Kindness, Self::GoodDeed, Self::Virtue, Self::Sage, Self::Perfect];
+
+    /// 根据功德值确定等级
+    pub fn from_value(value: u32) -> Self {
+        match value {
+            0 => Self::Beginner,
+            1..=10 => Self::Kindness,
+            11..=100 => Self::GoodDeed,
+            101..=1000 => Self::Virtue,
+            1001..=10000 => Self::Sage,
+            _ => Self::Perfect,
+        }
+    }
+
+    /// 达到该等级所需的最低功德
+    pub fn threshold(self) -> u32 {
+        match self {
+            Self::Beginner => 0,
+            Self::Kindness => 1,
+            Self::GoodDeed => 11,
+            Self::Virtue => 101,
+            Self::Sage => 1001,
+            Self::Perfect => 10001,
+        }
+    }
+
+    /// 等级名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Beginner => "🥉 初心",
+            Self::Kindness => "🥈 善念",
+            Self::GoodDeed => "🥇 善行",
+            Self::Virtue => "🏆 德高",
+            Self::Sage => "💎 圣贤",
+            Self::Perfect => "🌟 功德圆满",
+        }
+    }
+
+    /// 等级的ASCII标识，用于命令行参数和JSON输出
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Beginner => "beginner",
+            Self::Kindness => "kindness",
+            Self::GoodDeed => "good-deed",
+            Self::Virtue => "virtue",
+            Self::Sage => "sage",
+            Self::Perfect => "perfect",
+        }
+    }
+
+    /// 下一个等级，已是最高等级时返回None
+    pub fn next(self) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| *level > self)
+    }
+
+    /// 属于该等级的功德范围，从门槛到下一等级门槛减1，最高等级到 u32::MAX
+    pub fn range(self) -> std::ops::RangeInclusive<u32> {
+        self.threshold()..=self.next().map_or(u32::MAX, |next| next.threshold() - 1)
+    }
+}
+
+impl fmt::Display for MeritLevel {
+    /// 显示带emoji的中文名称，ASCII标识用 `id()`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MeritLevel {
+    type Err = ParseNameError;
+
+    /// 解析ASCII标识（不区分大小写）或中文名称（emoji可以省略）
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let wanted = normalize_name(input);
+        let trimmed = input.trim();
+        Self::ALL
+            .into_iter()
+            .find(|level| {
+                let name = level.name();
+                let chinese = name.split_once(' ').map_or(name, |(_, chinese)| chinese);
+                normalize_name(level.id()) == wanted || trimmed == name || trimmed == chinese
+            })
+            .ok_or_else(|| ParseNameError {
+                kind: "功德等级",
+                input: input.to_string(),
+                valid: Self::ALL.iter().map(|level| level.id()).collect(),
+            })
+    }
+}
+
+/// 距离下一等级还需的功德，已是最高等级时返回None
+pub fn merit_to_next_level(value: u32) -> Option<u32> {
+    MeritLevel::from_value(value).next().map(|next| next.threshold() - value)
+}
+
+/// 功德的粗略显示：只显示所在等级的下限（例如 "101+"），不显示准确值
+/// 区间边界就是等级门槛，同一等级的功德显示相同，初心等级只有0一个值，显示为 "0"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MeritBand {
+    /// 功德所在的等级
+    pub level: MeritLevel,
+}
+
+impl MeritBand {
+    /// 根据功德值确定区间
+    pub fn from_value(value: u32) -> Self {
+        Self { level: MeritLevel::from_value(value) }
+    }
+
+    /// 区间包含的功德范围，与等级范围一致
+    pub fn range(self) -> std::ops::RangeInclusive<u32> {
+        self.level.range()
+    }
+}
+
+impl fmt::Display for MeritBand {
+    /// 显示区间下限，例如 "0"、"1+"、"101+"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            MeritLevel::Beginner => f.write_str("0"),
+            level => write!(f, "{}+", level.threshold()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_write_gongde_value() {
+        let mut data = vec![0u8; 8]; // 8字节数据，够用
+        let test_value = 12345u32;
+        
+        // 测试写入
+        assert!(write_gongde_value(&mut data, test_value).is_ok());
+        
+        // 测试读取
+        let read_value = read_gongde_value(&data).unwrap();
+        assert_eq!(read_value, test_value);
+    }
+    
+    #[test]
+    fn test_insufficient_data_size() {
+        let data = vec![0u8; 2]; // 只有2字节，不够
+        
+        // 测试读取失败
+        assert_eq!(read_gongde_value(&data), Err(StateError::AccountDataTooSmall));
+        
+        // 测试数据大小验证失败
+        assert_eq!(validate_account_data_size(2), Err(StateError::AccountDataTooSmall));
+    }
+    
+    /// 指令码加上全0的完整参数
+    fn full_instruction_data(instruction: GongDeInstruction) -> Vec<u8> {
+        let mut data = vec![instruction as u8];
+        data.resize(1 + instruction.payload_len(), 0);
+        data
+    }
+
+    #[test]
+    fn test_gongde_account_data() {
+        let data = gongde_account_data(42, false);
+        assert_eq!(data.len(), GONGDE_ACCOUNT_SIZE);
+        assert_eq!(read_gongde_value(&data), Ok(42));
+        assert_eq!(read_lifetime_total(&data), Ok(42));
+        assert!(is_usable_account(&data));
+        assert!(!is_public_account(&data));
+        assert!(!needs_migration(data.len()));
+        assert!(is_public_account(&gongde_account_data(0, true)));
+    }
+
+    #[test]
+    fn test_instruction_parsing() {
+        // 测试有效指令
+        assert_eq!(GongDeInstruction::from_instruction_data(&[0]), Ok(GongDeInstruction::Increment));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[1]), Ok(GongDeInstruction::Close));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[2, 1]), Ok(GongDeInstruction::Initialize));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[3]), Ok(GongDeInstruction::CloseIfEmpty));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[4]), Ok(GongDeInstruction::ProtocolVersion));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[5, 1, 0, 0, 0]), Ok(GongDeInstruction::TransferMerit));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[7]), Ok(GongDeInstruction::Migrate));
+        for (tag, instruction) in GongDeInstruction::ALL.into_iter().enumerate() {
+            assert_eq!(GongDeInstruction::from_tag(tag as u8), Some(instruction));
+            assert_eq!(GongDeInstruction::from_instruction_data(&full_instruction_data(instruction)), Ok(instruction));
+        }
+        // 参数之后多出的字节留给合约按参数表检查
+        assert_eq!(GongDeInstruction::from_instruction_data(&[0, 9]), Ok(GongDeInstruction::Increment));
+        
+        // 测试无效指令
+        assert_eq!(GongDeInstruction::from_instruction_data(&[30]), Err(StateError::UnknownInstruction(30)));
+        assert_eq!(GongDeInstruction::from_instruction_data(&[255, 1]), Err(StateError::UnknownInstruction(255)));
+        assert_eq!(GongDeInstruction::from_tag(MAX_INSTRUCTION_TAG + 1), None);
+        assert_eq!(GongDeInstruction::from_instruction_data(&[]), Err(StateError::EmptyInstructionData));
+    }
+
+    #[test]
+    fn test_instruction_parsing_truncated_payload() {
+        // 每种带参数的指令逐字节截短，包括只有指令码的情况
+        for instruction in GongDeInstruction::ALL {
+            let data = full_instruction_data(instruction);
+            for len in 1..data.len() {
+                assert_eq!(
+                    GongDeInstruction::from_instruction_data(&data[..len]),
+                    Err(StateError::TruncatedInstructionData { instruction, expected: data.len(), actual: len }),
+                    "{:?} 截短到 {} 字节",
+                    instruction,
+                    len
+                );
+            }
+        }
+        assert_eq!(
+            GongDeInstruction::from_instruction_data(&[GongDeInstruction::IncrementBy as u8]),
+            Err(StateError::TruncatedInstructionData { instruction: GongDeInstruction::IncrementBy, expected: 9, actual: 1 })
+        );
+    }
+
+    #[test]
+    fn test_protocol_info_roundtrip() {
+        let bytes = ProtocolInfo::CURRENT.to_bytes();
+        assert_eq!(bytes, [PROTOCOL_VERSION, 0, 29]);
+        assert_eq!(ProtocolInfo::from_bytes(&bytes), Ok(ProtocolInfo::CURRENT));
+        assert_eq!(ProtocolInfo::from_bytes(&bytes[..2]), Err(StateError::InvalidInstructionData));
+
+        assert!(ProtocolInfo::CURRENT.supports(GongDeInstruction::CloseIfEmpty));
+        let old = ProtocolInfo { version: 1, min_instruction: 0, max_instruction: 1 };
+        assert!(!old.supports(GongDeInstruction::Initialize));
+    }
+
+    #[test]
+    fn test_merit_report_roundtrip() {
+        let report = MeritReport::new(vec![3, u32::MAX, 0]);
+        assert_eq!(report.total, 3 + u64::from(u32::MAX));
+        let bytes = report.to_bytes();
+        assert_eq!(bytes.len(), 8 + 4 * 3);
+        assert_eq!(MeritReport::from_bytes(&bytes), Ok(report));
+
+        // 长度不对或合计与各账户之和不一致
+        assert_eq!(MeritReport::from_bytes(&bytes[..bytes.len() - 1]), Err(StateError::InvalidInstructionData));
+        assert_eq!(MeritReport::from_bytes(&bytes[..7]), Err(StateError::InvalidInstructionData));
+        let mut tampered = bytes.clone();
+        tampered[0] ^= 1;
+        assert_eq!(MeritReport::from_bytes(&tampered), Err(StateError::InvalidInstructionData));
+        let too_many = MeritReport::new(vec![1; MAX_REPORT_ACCOUNTS + 1]).to_bytes();
+        assert_eq!(MeritReport::from_bytes(&too_many), Err(StateError::InvalidInstructionData));
+        assert_eq!(MeritReport::from_bytes(&MeritReport::new(vec![]).to_bytes()), Ok(MeritReport { total: 0, values: vec![] }));
+    }
+
+    #[test]
+    fn test_endorse_many_result_roundtrip() {
+        let mut result = EndorseManyResult::default();
+        result.mark_endorsed(0);
+        result.mark_endorsed(2);
+        assert!(result.is_endorsed(0));
+        assert!(!result.is_endorsed(1));
+        assert!(result.is_endorsed(2));
+        assert!(!result.is_endorsed(MAX_ENDORSE_MANY_TARGETS));
+        assert_eq!(result.endorsed_count(), 2);
+        assert_eq!(EndorseManyResult::from_bytes(&result.to_bytes()), Ok(result));
+
+        // 长度不对或超出最大目标数的位被置上
+        assert_eq!(EndorseManyResult::from_bytes(&result.to_bytes()[..3]), Err(StateError::InvalidInstructionData));
+        let overflow = (1u32 << MAX_ENDORSE_MANY_TARGETS).to_le_bytes();
+        assert_eq!(EndorseManyResult::from_bytes(&overflow), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_client_version_envelope() {
+        let increment_by = [GongDeInstruction::IncrementBy as u8, 5, 0, 0, 0, 0, 0, 0, 0];
+        let stamped = stamp_client_version(&increment_by, CLIENT_VERSION);
+        assert_eq!(stamped[..2], [VERSIONED_INSTRUCTION_MARKER, CLIENT_VERSION]);
+        assert_eq!(split_client_version(&stamped), (Some(CLIENT_VERSION), &increment_by[..]));
+
+        // 旧编码原样返回；只有标记没有版本时也按旧编码处理，随后因为指令码未知而失败
+        assert_eq!(split_client_version(&increment_by), (None, &increment_by[..]));
+        assert_eq!(split_client_version(&[VERSIONED_INSTRUCTION_MARKER]), (None, &[VERSIONED_INSTRUCTION_MARKER][..]));
+        assert_eq!(split_client_version(&[VERSIONED_INSTRUCTION_MARKER, 3]), (Some(3), &[][..]));
+        assert!(GongDeInstruction::from_instruction_data(&[VERSIONED_INSTRUCTION_MARKER]).is_err());
+    }
+
+    #[test]
+    fn test_global_config_version_gate() {
+        let mut data = [0u8; CONFIG_SIZE];
+        GlobalConfig { minimum_client_version: 3, ..GlobalConfig::default() }.pack(&mut data).unwrap();
+        let config = GlobalConfig::unpack(&data).unwrap();
+        assert_eq!(config.minimum_client_version, 3);
+        assert_eq!(config.check_client_version(2), Err(GongDeError::ClientVersionTooOld.into()));
+        assert_eq!(config.check_client_version(3), Ok(()));
+        assert_eq!(config.check_client_version(4), Ok(()));
+        assert_eq!(GlobalConfig::default().check_client_version(0), Ok(()));
+        assert_eq!(GlobalConfig::unpack(&[]), Err(StateError::AccountDataTooSmall));
+
+        let data = [GongDeInstruction::SetMinimumClientVersion as u8, 2];
+        assert_eq!(parse_set_minimum_client_version_args(&data), Ok(2));
+        assert_eq!(parse_set_minimum_client_version_args(&data[..1]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_global_config_pow_difficulty() {
+        // 加入工作量证明之前的1字节配置读出难度0，难度为0时仍然可以写回
+        let legacy = [4u8];
+        let config = GlobalConfig::unpack(&legacy).unwrap();
+        assert_eq!(config, GlobalConfig { minimum_client_version: 4, pow_difficulty: 0 });
+        assert!(!config.pow_enabled());
+        assert_eq!(config.pack(&mut [0u8; 1]), Ok(()));
+        let enabled = GlobalConfig { pow_difficulty: 8, ..config };
+        assert_eq!(enabled.pack(&mut [0u8; 1]), Err(StateError::AccountDataTooSmall));
+
+        let mut data = [0u8; CONFIG_SIZE];
+        enabled.pack(&mut data).unwrap();
+        assert_eq!(data, [4, 8]);
+        assert_eq!(GlobalConfig::unpack(&data), Ok(enabled));
+        assert!(enabled.pow_enabled());
+
+        let data = [GongDeInstruction::SetPowDifficulty as u8, MAX_POW_DIFFICULTY];
+        assert_eq!(parse_set_pow_difficulty_args(&data), Ok(MAX_POW_DIFFICULTY));
+        let data = [GongDeInstruction::SetPowDifficulty as u8, MAX_POW_DIFFICULTY + 1];
+        assert_eq!(parse_set_pow_difficulty_args(&data), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_global_config_copy() {
+        // 没有设置过全局配置的全局功德PDA只有功德值
+        assert_eq!(read_global_config_copy(&7u32.to_le_bytes()), Ok(GlobalConfig::default()));
+        assert_eq!(read_global_config_copy(&[0u8; 3]), Err(StateError::AccountDataTooSmall));
+
+        let config = GlobalConfig { minimum_client_version: 3, pow_difficulty: 8 };
+        let mut data = vec![0u8; GLOBAL_PDA_WITH_CONFIG_SIZE];
+        write_gongde_value(&mut data, 7).unwrap();
+        config.pack(&mut data[GLOBAL_CONFIG_COPY_OFFSET..]).unwrap();
+        assert_eq!(read_global_config_copy(&data), Ok(config));
+        assert_eq!(read_gongde_value(&data), Ok(7));
+    }
+
+    #[test]
+    fn test_pow_difficulty_counts_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xFF; 32]), 0);
+        assert_eq!(leading_zero_bits(&[0x00, 0x10, 0xFF]), 11);
+        assert_eq!(leading_zero_bits(&[0; 32]), 256);
+        assert!(meets_pow_difficulty(&[0x00, 0x10, 0xFF], 11));
+        assert!(!meets_pow_difficulty(&[0x00, 0x10, 0xFF], 12));
+        // 难度0对任何哈希都成立
+        assert!(meets_pow_difficulty(&[0xFF; 32], 0));
+
+        let mut data = gongde_account_data(1, false);
+        assert_eq!(read_last_increment_slot(&data), Some(0));
+        write_last_increment_slot(&mut data, 77).unwrap();
+        assert_eq!(read_last_increment_slot(&data), Some(77));
+        assert_eq!(read_last_increment_slot(&data[..GONGDE_LAST_INCREMENT_SLOT_OFFSET]), None);
+        assert_eq!(
+            write_last_increment_slot(&mut data[..GONGDE_LAST_INCREMENT_SLOT_OFFSET], 1),
+            Err(StateError::AccountDataTooSmall)
+        );
+
+        let mut data = vec![GongDeInstruction::IncrementWithProof as u8];
+        data.extend_from_slice(&0x0102_0304_0506_0708u64.to_le_bytes());
+        assert_eq!(parse_increment_with_proof_args(&data), Ok(0x0102_0304_0506_0708));
+        assert_eq!(parse_increment_with_proof_args(&data[..8]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_emoji() {
+        let mut data = gongde_account_data(1, false);
+        assert_eq!(read_emoji(&data), None);
+        write_emoji(&mut data, '🙏' as u32).unwrap();
+        assert_eq!(read_emoji(&data), Some('🙏'));
+        write_emoji(&mut data, 0).unwrap();
+        assert_eq!(read_emoji(&data), None);
+        assert_eq!(read_emoji(&data[..GONGDE_EMOJI_OFFSET]), None);
+        assert_eq!(write_emoji(&mut data[..GONGDE_EMOJI_OFFSET], 0x41), Err(StateError::AccountDataTooSmall));
+
+        assert!(is_valid_emoji(0));
+        assert!(is_valid_emoji('🪷' as u32));
+        assert!(is_valid_emoji('佛' as u32));
+        // 代理对、超出范围、控制字符、空白和私用区都不能作为头像
+        for codepoint in [0xD800, 0x11_0000, u32::MAX, 0x07, '\n' as u32, ' ' as u32, 0x3000, 0xE000, 0x10_FFFD] {
+            assert!(!is_valid_emoji(codepoint), "{:#x}", codepoint);
+        }
+
+        let mut data = vec![GongDeInstruction::SetEmoji as u8];
+        data.extend_from_slice(&('🙏' as u32).to_le_bytes());
+        assert_eq!(parse_set_emoji_args(&data), Ok('🙏' as u32));
+        assert_eq!(parse_set_emoji_args(&data[..4]), Err(StateError::InvalidInstructionData));
+        data[1..5].copy_from_slice(&0xD800u32.to_le_bytes());
+        assert_eq!(parse_set_emoji_args(&data), Err(StateError::Custom(GongDeError::InvalidEmoji)));
+    }
+
+    #[test]
+    fn test_day_index() {
+        assert_eq!(day_index(0), 0);
+        assert_eq!(day_index(SECONDS_PER_DAY - 1), 0);
+        assert_eq!(day_index(SECONDS_PER_DAY), 1);
+        assert_eq!(day_index(-1), -1);
+    }
+
+    #[test]
+    fn test_day_boundaries() {
+        // 2024-03-10 23:59:59 UTC 和下一秒
+        let last_second = 1_710_115_199;
+        assert_eq!(day_index(last_second) + 1, day_index(last_second + 1));
+        assert_eq!(seconds_until_next_day(last_second), 1);
+        assert_eq!(seconds_until_next_day(last_second + 1), SECONDS_PER_DAY);
+
+        // 闰秒前后：Unix时间不计闰秒，2016-12-31 23:59:60 和 2017-01-01 00:00:00 是同一个时间戳
+        let before_leap = 1_483_228_799;
+        assert_eq!(seconds_until_next_day(before_leap), 1);
+        assert_eq!(day_index(before_leap + 1), 17_167);
+
+        // 1970年之前的时间戳也按UTC零点换日
+        assert_eq!(seconds_until_next_day(-1), 1);
+        assert_eq!(seconds_until_next_day(-SECONDS_PER_DAY), SECONDS_PER_DAY);
+        assert_eq!(day_index(-SECONDS_PER_DAY - 1), -2);
+
+        // 极端值不溢出
+        assert_eq!(day_index(i64::MIN), i64::MIN.div_euclid(SECONDS_PER_DAY));
+        assert!((1..=SECONDS_PER_DAY).contains(&seconds_until_next_day(i64::MIN)));
+        assert!((1..=SECONDS_PER_DAY).contains(&seconds_until_next_day(i64::MAX)));
+    }
+
+    #[test]
+    fn test_transfer_remaining_today() {
+        let state = TransferState { day: 100, transferred_today: 6 };
+        assert_eq!(state.remaining_today(100, 10), 4);
+        // 换日后额度恢复
+        assert_eq!(state.remaining_today(101, 10), 10);
+        // 额度下调后不会出现负数
+        assert_eq!(state.remaining_today(100, 5), 0);
+
+        // 与 record_transfer 的判断一致：剩余额度刚好可以转出，多1就失败
+        let mut attempt = state;
+        assert!(attempt.record_transfer(100, 5, 10).is_err());
+        let mut state = state;
+        state.record_transfer(100, state.remaining_today(100, 10), 10).unwrap();
+        assert_eq!(state.remaining_today(100, 10), 0);
+    }
+
+    #[test]
+    fn test_transfer_state_rollover() {
+        let mut state = TransferState::default();
+        state.record_transfer(100, 6, 10).unwrap();
+        state.record_transfer(100, 4, 10).unwrap();
+        assert_eq!(
+            state.record_transfer(100, 1, 10),
+            Err(GongDeError::DailyCapReached.into())
+        );
+        // 失败不改变状态
+        assert_eq!(state, TransferState { day: 100, transferred_today: 10 });
+
+        // 换日后额度清零
+        state.record_transfer(101, 10, 10).unwrap();
+        assert_eq!(state, TransferState { day: 101, transferred_today: 10 });
+
+        let mut data = [0u8; TRANSFER_STATE_SIZE];
+        state.pack(&mut data).unwrap();
+        assert_eq!(TransferState::unpack(&data), Ok(state));
+    }
+
+    #[test]
+    fn test_transfer_args() {
+        assert_eq!(parse_transfer_args(&[5, 3, 0, 0, 0]), Ok(3));
+        assert_eq!(parse_transfer_args(&[5, 0, 0, 0, 0]), Err(StateError::InvalidInstructionData));
+        assert_eq!(parse_transfer_args(&[5, 1]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_increment_amount_range() {
+        let invalid = Err(GongDeError::InvalidAmount.into());
+        assert_eq!(validate_increment_amount(0), invalid);
+        assert_eq!(validate_increment_amount(MIN_INCREMENT), Ok(1));
+        assert_eq!(validate_increment_amount(MAX_INCREMENT), Ok(MAX_INCREMENT as u32));
+        assert_eq!(validate_increment_amount(MAX_INCREMENT + 1), invalid);
+        assert_eq!(validate_increment_amount(u64::MAX), invalid);
+
+        let mut data = vec![GongDeInstruction::IncrementBy as u8];
+        data.extend_from_slice(&42u64.to_le_bytes());
+        assert_eq!(parse_increment_by_args(&data), Ok(42));
+        assert_eq!(parse_increment_by_args(&data[..5]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_restore_args() {
+        let mut data = vec![GongDeInstruction::RestoreTo as u8];
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        assert_eq!(parse_restore_args(&data), Ok((7, 1_700_000_000)));
+        assert_eq!(parse_restore_args(&data[..16]), Err(StateError::InvalidInstructionData));
+
+        assert_eq!(validate_restore_value(0, 5), Ok(0));
+        assert_eq!(validate_restore_value(5, 5), Ok(5));
+        assert_eq!(validate_restore_value(6, 5), Err(GongDeError::InvalidAmount.into()));
+        assert_eq!(validate_restore_value(u64::MAX, u32::MAX), Err(GongDeError::InvalidAmount.into()));
+    }
+
+    fn campaign_fixture() -> Campaign {
+        Campaign { organizer: Pubkey::new_unique(), start: 1_000, end: 2_000, multiplier: 1, remaining: 5 }
+    }
+
+    #[test]
+    fn test_campaign_window_boundaries() {
+        let campaign = campaign_fixture();
+        assert!(!campaign.is_active(999));
+        assert!(campaign.is_active(1_000));
+        assert!(campaign.is_active(1_999));
+        // 结束时间不含
+        assert!(!campaign.is_active(2_000));
+
+        let mut expired = campaign;
+        assert_eq!(expired.take_bonus(2_000, 1, u32::MAX), 0);
+        assert_eq!(expired, campaign);
+    }
+
+    #[test]
+    fn test_campaign_take_bonus_exhausts_pool() {
+        let mut campaign = Campaign { multiplier: 2, ..campaign_fixture() };
+        assert_eq!(campaign.take_bonus(1_000, 2, u32::MAX), 4);
+        // 奖池只剩1点，只配捐剩余部分
+        assert_eq!(campaign.take_bonus(1_000, 2, u32::MAX), 1);
+        assert_eq!(campaign.remaining, 0);
+        assert_eq!(campaign.take_bonus(1_000, 2, u32::MAX), 0);
+
+        // 功德接近上限时只扣除用得上的部分
+        let mut campaign = campaign_fixture();
+        assert_eq!(campaign.take_bonus(1_000, 3, 2), 2);
+        assert_eq!(campaign.remaining, 3);
+
+        let mut data = [0u8; CAMPAIGN_SIZE];
+        campaign.pack(&mut data).unwrap();
+        assert_eq!(Campaign::unpack(&data), Ok(campaign));
+        assert_eq!(Campaign::unpack(&data[..CAMPAIGN_SIZE - 1]), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_endorsement_weight_boundaries() {
+        let cases = [
+            (0, 1),
+            (1, 1),
+            (9, 1),
+            (10, 2),
+            (99, 2),
+            (100, 3),
+            (999, 3),
+            (1_000, 4),
+            (999_999_999, 9),
+            (1_000_000_000, 10),
+            (u32::MAX, 10),
+        ];
+        for (value, weight) in cases {
+            assert_eq!(endorsement_weight(value), weight, "功德 {}", value);
+        }
+    }
+
+    #[test]
+    fn test_accrue_merit() {
+        let start = 1_700_000_000;
+        let hour = ACCRUE_SECONDS_PER_MERIT;
+        assert_eq!(accrue_merit(start, start), (0, start));
+        // 不足一小时的零头保留到下次
+        assert_eq!(accrue_merit(start, start + hour - 1), (0, start));
+        assert_eq!(accrue_merit(start, start + 3 * hour + 59), (3, start + 3 * hour));
+        // 达到上限时零头作废，从现在重新计时
+        assert_eq!(accrue_merit(start, start + 24 * hour + 59), (MAX_ACCRUED_MERIT, start + 24 * hour + 59));
+        assert_eq!(accrue_merit(start, start + 1_000 * hour), (MAX_ACCRUED_MERIT, start + 1_000 * hour));
+        // 时钟回拨
+        assert_eq!(accrue_merit(start, start - hour), (0, start));
+        assert_eq!(accrue_merit(i64::MIN, i64::MAX), (MAX_ACCRUED_MERIT, i64::MAX));
+
+        let mut data = [0u8; ACCRUAL_STATE_SIZE];
+        write_last_accrued(&mut data, start).unwrap();
+        assert_eq!(read_last_accrued(&data), Ok(start));
+        assert_eq!(read_last_accrued(&data[..4]), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_stake_merit() {
+        let rate = STAKE_LAMPORT_SLOTS_PER_MERIT;
+        let sol = 1_000_000_000;
+        assert_eq!(stake_merit(0, 1_000_000, 0), (0, 0));
+        assert_eq!(stake_merit(sol, 216_000, 0), (1, 0));
+        // 不足1点的零头留到下次，两次加起来正好1点
+        assert_eq!(stake_merit(sol, 108_000, 0), (0, rate / 2));
+        assert_eq!(stake_merit(sol, 108_000, rate / 2), (1, 0));
+        // 没有质押时零头保持不变
+        assert_eq!(stake_merit(0, 1_000, rate - 1), (0, rate - 1));
+        assert_eq!(stake_merit(sol * 3, 216_000 + 1, 7), (3, sol * 3 + 7));
+        // 极端数值不会溢出
+        let (merit, remainder) = stake_merit(u64::MAX, u64::MAX, rate - 1);
+        assert_eq!(merit, u64::MAX);
+        assert!(remainder < rate);
+    }
+
+    #[test]
+    fn test_stake_state_settle() {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        assert_eq!(StakeState::unpack(&data), Ok(StakeState::default()));
+        assert_eq!(StakeState::unpack(&data[..GONGDE_STAKED_OFFSET]), Err(StateError::AccountDataTooSmall));
+
+        let mut state = StakeState { staked: 2_000_000_000, last_claim_slot: 100, remainder: 0 };
+        assert_eq!(state.settle(100 + 54_000), 0);
+        assert_eq!(state.last_claim_slot, 100 + 54_000);
+        assert_eq!(state.settle(100 + 108_000), 1);
+        assert_eq!(state.remainder, 0);
+        // slot不会倒退
+        assert_eq!(state.settle(50), 0);
+        assert_eq!(state.last_claim_slot, 100 + 108_000);
+
+        state.pack(&mut data).unwrap();
+        assert_eq!(StakeState::unpack(&data), Ok(state));
+        // 只覆盖质押字段
+        assert!(data[..GONGDE_STAKED_OFFSET].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_stake_args() {
+        let mut data = vec![GongDeInstruction::Stake as u8];
+        data.extend_from_slice(&5_000u64.to_le_bytes());
+        assert_eq!(parse_stake_args(&data), Ok(5_000));
+        assert_eq!(parse_stake_args(&data[..5]), Err(StateError::InvalidInstructionData));
+        let zero = [GongDeInstruction::Unstake as u8, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(parse_stake_args(&zero), Err(GongDeError::InvalidAmount.into()));
+    }
+
+    #[test]
+    fn test_lottery_ticket_owner() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut round = LotteryRound {
+            admin: Pubkey::new_unique(),
+            round_id: 1,
+            start_slot: 100,
+            end_slot: 200,
+            ticket_price: 3,
+            total_tickets: 0,
+            settled: false,
+            voided: false,
+            winning_ticket: 0,
+            entry_count: 0,
+        };
+        let mut data = vec![0u8; lottery_size(3)];
+        // alice 2张，bob 3张，alice 再买1张：票号 [0,2) [2,5) [5,6)
+        for (index, (gongde, tickets)) in [(alice, 2), (bob, 3), (alice, 1)].into_iter().enumerate() {
+            let ticket_end = round.record_entry(tickets).unwrap();
+            write_lottery_entry(&mut data, index, &gongde, ticket_end).unwrap();
+        }
+        round.pack(&mut data).unwrap();
+        assert_eq!(LotteryRound::unpack(&data), Ok(round));
+        assert_eq!(round.jackpot(), 18);
+
+        let owners = (0..7).map(|ticket| lottery_ticket_owner(&data, ticket).unwrap()).collect::<Vec<_>>();
+        assert_eq!(owners, vec![Some(alice), Some(alice), Some(bob), Some(bob), Some(bob), Some(alice), None]);
+        assert_eq!(lottery_ticket_owner(&data[..lottery_size(2)], 0), Err(StateError::AccountDataTooSmall));
+
+        assert!(!round.is_open(99));
+        assert!(round.is_open(100));
+        assert!(!round.is_open(200));
+        round.total_tickets = u32::MAX;
+        assert_eq!(round.record_entry(1), Err(GongDeError::InvalidAmount.into()));
+    }
+
+    #[test]
+    fn test_lottery_refund() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut round = LotteryRound {
+            admin: Pubkey::new_unique(),
+            round_id: 1,
+            start_slot: 100,
+            end_slot: 200,
+            ticket_price: 3,
+            total_tickets: 0,
+            settled: false,
+            voided: false,
+            winning_ticket: 0,
+            entry_count: 0,
+        };
+        let mut data = vec![0u8; lottery_size(3)];
+        for (index, (gongde, tickets)) in [(alice, 2), (bob, 3), (alice, 1)].into_iter().enumerate() {
+            let ticket_end = round.record_entry(tickets).unwrap();
+            write_lottery_entry(&mut data, index, &gongde, ticket_end).unwrap();
+        }
+        round.settled = true;
+        round.voided = true;
+        round.pack(&mut data).unwrap();
+        assert_eq!(data[64], LOTTERY_STATUS_VOIDED);
+        assert_eq!(LotteryRound::unpack(&data), Ok(round));
+
+        assert_eq!(lottery_pending_refunds(&data), Ok(vec![alice, bob]));
+        assert_eq!(lottery_refund_due(&data, &alice), Ok(9));
+        assert_eq!(lottery_refund_due(&data, &bob), Ok(9));
+        assert_eq!(lottery_refund_due(&data, &Pubkey::new_unique()), Ok(0));
+
+        // 退款后不会重复退款，其他条目的票号区间不变
+        mark_lottery_refunded(&mut data, &alice).unwrap();
+        assert_eq!(lottery_refund_due(&data, &alice), Ok(0));
+        assert_eq!(lottery_refund_due(&data, &bob), Ok(9));
+        assert_eq!(lottery_pending_refunds(&data), Ok(vec![bob]));
+        assert_eq!(read_lottery_entry(&data, 1), Some((bob, 5)));
+        assert_eq!(lottery_refund_due(&data[..lottery_size(2)], &bob), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_slot_hash_at() {
+        // 按slot从新到旧排列，slot 12 没有出块
+        let entries: [(u64, u8); 3] = [(13, 0xCC), (11, 0xBB), (10, 0xAA)];
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for (slot, byte) in entries {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[byte; 32]);
+        }
+        assert_eq!(slot_hash_at(&data, 11), Some([0xBB; 32]));
+        assert_eq!(slot_hash_at(&data, 10), Some([0xAA; 32]));
+        // 没有出块的slot使用之后第一个出块的slot
+        assert_eq!(slot_hash_at(&data, 12), Some([0xCC; 32]));
+        // 还没有产生
+        assert_eq!(slot_hash_at(&data, 14), None);
+        // 已经滚出记录范围
+        assert_eq!(slot_hash_at(&data, 9), None);
+        assert_eq!(slot_hash_at(&data[..20], 11), None);
+
+        let mut hash = [0u8; 32];
+        hash[..8].copy_from_slice(&17u64.to_le_bytes());
+        assert_eq!(winning_ticket(&hash, 5), 2);
+        assert_eq!(winning_ticket(&hash, 1), 0);
+    }
+
+    #[test]
+    fn test_lottery_args() {
+        let mut data = vec![GongDeInstruction::CreateLottery as u8];
+        for value in [4u64, 100, 200] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&5u32.to_le_bytes());
+        assert_eq!(
+            parse_create_lottery_args(&data),
+            Ok(CreateLotteryArgs { round_id: 4, start_slot: 100, end_slot: 200, ticket_price: 5 })
+        );
+        assert_eq!(parse_create_lottery_args(&data[..20]), Err(StateError::InvalidInstructionData));
+        let mut zero_price = data.clone();
+        zero_price[25..29].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(parse_create_lottery_args(&zero_price), Err(GongDeError::InvalidAmount.into()));
+        let mut backwards = data;
+        backwards[17..25].copy_from_slice(&100u64.to_le_bytes());
+        assert_eq!(parse_create_lottery_args(&backwards), Err(StateError::InvalidInstructionData));
+
+        assert_eq!(parse_enter_lottery_args(&[GongDeInstruction::EnterLottery as u8, 3, 1]), Ok(259));
+        assert_eq!(parse_enter_lottery_args(&[GongDeInstruction::EnterLottery as u8, 0, 0]), Err(GongDeError::InvalidAmount.into()));
+        assert_eq!(parse_enter_lottery_args(&[GongDeInstruction::EnterLottery as u8, 3]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_endorsement_roundtrip() {
+        let endorsement = Endorsement::new(150, 1_700_000_000);
+        assert_eq!(endorsement, Endorsement { weight: 3, endorser_value: 150, endorsed_at: 1_700_000_000 });
+
+        let mut data = [0u8; ENDORSEMENT_SIZE];
+        endorsement.pack(&mut data).unwrap();
+        assert_eq!(Endorsement::unpack(&data), Ok(endorsement));
+        assert_eq!(Endorsement::unpack(&data[..8]), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_create_campaign_args() {
+        let encode = |start: i64, end: i64, multiplier: u32, budget: u32| {
+            let mut data = vec![GongDeInstruction::CreateCampaign as u8];
+            data.extend_from_slice(&7u64.to_le_bytes());
+            data.extend_from_slice(&start.to_le_bytes());
+            data.extend_from_slice(&end.to_le_bytes());
+            data.extend_from_slice(&multiplier.to_le_bytes());
+            data.extend_from_slice(&budget.to_le_bytes());
+            data
+        };
+        assert_eq!(
+            parse_create_campaign_args(&encode(10, 20, 1, 100)),
+            Ok(CreateCampaignArgs { campaign_id: 7, start: 10, end: 20, multiplier: 1, budget: 100 })
+        );
+        assert_eq!(parse_create_campaign_args(&encode(20, 20, 1, 100)), Err(StateError::InvalidInstructionData));
+        assert_eq!(parse_create_campaign_args(&encode(10, 20, 0, 100)), Err(GongDeError::InvalidAmount.into()));
+        assert_eq!(
+            parse_create_campaign_args(&encode(10, 20, MAX_CAMPAIGN_MULTIPLIER + 1, 100)),
+            Err(GongDeError::InvalidAmount.into())
+        );
+        assert_eq!(parse_create_campaign_args(&encode(10, 20, 1, 0)), Err(GongDeError::InvalidAmount.into()));
+        assert_eq!(parse_create_campaign_args(&encode(10, 20, 1, 100)[..32]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_shared_ledger_keeps_entries_sorted() {
+        let mut users = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        users.sort();
+        let [a, b, c] = users;
+
+        let mut data = vec![0u8; shared_ledger_size(0)];
+        assert_eq!(shared_ledger_get(&data, &a), Ok(None));
+        // 没有为新条目留出空间
+        assert_eq!(shared_ledger_increment(&mut data, &a, 1), Err(StateError::AccountDataTooSmall));
+
+        // 乱序插入，账本内按公钥排序
+        for (user, amount) in [(c, 3), (a, 1), (b, 2)] {
+            data.resize(shared_ledger_size(shared_ledger_len(&data).unwrap() + 1), 0);
+            assert_eq!(shared_ledger_increment(&mut data, &user, amount), Ok(amount));
+        }
+        assert_eq!(shared_ledger_len(&data), Ok(3));
+        assert_eq!(shared_ledger_search(&data, &b), Ok(Ok(1)));
+
+        // 已有用户不需要新空间
+        assert_eq!(shared_ledger_increment(&mut data, &b, 5), Ok(7));
+        assert_eq!(data.len(), shared_ledger_size(3));
+        assert_eq!(shared_ledger_get(&data, &a), Ok(Some(1)));
+        assert_eq!(shared_ledger_get(&data, &b), Ok(Some(7)));
+        assert_eq!(shared_ledger_get(&data, &c), Ok(Some(3)));
+
+        // 记录的条目数量超出数据长度
+        assert_eq!(shared_ledger_len(&data[..shared_ledger_size(2)]), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_parse_restore_return_data() {
+        assert_eq!(parse_restore_return_data(&0u32.to_le_bytes()), Ok(0));
+        assert_eq!(parse_restore_return_data(&7u32.to_le_bytes()), Ok(7));
+        assert_eq!(parse_restore_return_data(&[0; 3]), Err(StateError::InvalidInstructionData));
+        assert_eq!(parse_restore_return_data(&[]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_restore_event_roundtrip() {
+        let event = RestoreEvent { gongde: Pubkey::new_unique(), previous: 9, restored: 3, checkpoint: -1 };
+        let data = event.pack();
+        assert_eq!(RestoreEvent::from_log_data(&[RESTORE_EVENT_TAG, &data[..]]), Some(event));
+        assert_eq!(RestoreEvent::from_log_data(&[b"Other".as_slice(), &data[..]]), None);
+        assert_eq!(RestoreEvent::from_log_data(&[RESTORE_EVENT_TAG, &data[..40]]), None);
+        assert_eq!(RestoreEvent::from_log_data(&[RESTORE_EVENT_TAG]), None);
+    }
+
+    #[test]
+    fn test_merit_change_event_roundtrip() {
+        let event = MeritChangeEvent { account: Pubkey::new_unique(), before: 9, after: 3 };
+        let data = event.pack();
+        assert_eq!(MeritChangeEvent::from_log_data(&[MERIT_CHANGE_EVENT_TAG, &data[..]]), Some(event));
+        assert_eq!(event.delta(), -6);
+        assert_eq!(MeritChangeEvent { before: 0, after: u32::MAX, ..event }.delta(), i64::from(u32::MAX));
+        // 其他事件的数据不会被误认
+        assert_eq!(MeritChangeEvent::from_log_data(&[RESTORE_EVENT_TAG, &data[..]]), None);
+        assert_eq!(MeritChangeEvent::from_log_data(&[MERIT_CHANGE_EVENT_TAG, &data[..32]]), None);
+    }
+
+    #[test]
+    fn test_initialize_args() {
+        assert_eq!(parse_initialize_args(&[2, 0]), Ok(false));
+        assert_eq!(parse_initialize_args(&[2, 1]), Ok(true));
+        assert_eq!(parse_initialize_args(&[2]), Err(StateError::InvalidInstructionData));
+        assert_eq!(parse_initialize_args(&[2, 2]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_public_flags() {
+        // 旧的4字节账户视为公开
+        assert!(is_public_account(&[0u8; GONGDE_VALUE_SIZE]));
+        // 未初始化的新账户视为公开
+        assert!(is_public_account(&[0u8; GONGDE_ACCOUNT_SIZE]));
+
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_initialized_flags(&mut data, false).unwrap();
+        assert!(!is_public_account(&data));
+
+        // 不能重复初始化
+        assert_eq!(write_initialized_flags(&mut data, true), Err(StateError::AccountAlreadyInitialized));
+
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_initialized_flags(&mut data, true).unwrap();
+        assert!(is_public_account(&data));
+
+        // 旧账户没有标志位空间
+        assert_eq!(write_initialized_flags(&mut [0u8; GONGDE_VALUE_SIZE], true), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_usable_account() {
+        // 旧的4字节账户始终可用
+        assert!(is_usable_account(&[0u8; GONGDE_VALUE_SIZE]));
+        // 未初始化或关闭后被清零的新账户不可用
+        assert!(!is_usable_account(&[0u8; GONGDE_ACCOUNT_SIZE]));
+
+        // 未初始化的5字节账户（布局v2）也不可用
+        assert!(!is_usable_account(&[0u8; GONGDE_LIFETIME_OFFSET]));
+
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_initialized_flags(&mut data, false).unwrap();
+        assert!(is_usable_account(&data));
+    }
+
+    #[test]
+    fn test_archived_flag() {
+        // 旧的4字节账户没有标志位
+        assert!(!is_archived_account(&[0u8; GONGDE_VALUE_SIZE]));
+        assert_eq!(write_archived_flag(&mut [0u8; GONGDE_VALUE_SIZE], true), Err(StateError::AccountDataTooSmall));
+
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_initialized_flags(&mut data, true).unwrap();
+        write_archived_flag(&mut data, true).unwrap();
+        assert!(is_archived_account(&data));
+        // 归档不影响其他标志位
+        assert!(is_usable_account(&data));
+        assert!(is_public_account(&data));
+        assert!(GongDeAccount::unpack(&data).unwrap().is_archived());
+
+        write_archived_flag(&mut data, false).unwrap();
+        assert!(!is_archived_account(&data));
+        assert_eq!(read_gongde_flags(&data), FLAG_INITIALIZED | FLAG_PUBLIC);
+    }
+
+    #[test]
+    fn test_lifetime_total() {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_gongde_value(&mut data, 3).unwrap();
+        write_lifetime_total(&mut data, 154).unwrap();
+        add_lifetime_total(&mut data, 3).unwrap();
+        assert_eq!(read_lifetime_total(&data), Ok(157));
+        // 功德值被调低不影响累计功德
+        write_gongde_value(&mut data, 0).unwrap();
+        assert_eq!(read_lifetime_total(&data), Ok(157));
+
+        write_lifetime_total(&mut data, u64::MAX - 1).unwrap();
+        add_lifetime_total(&mut data, 100).unwrap();
+        assert_eq!(read_lifetime_total(&data), Ok(u64::MAX));
+
+        // 旧布局没有累计功德字段，按功德值计算，增加时跳过
+        let mut legacy = vec![0u8; GONGDE_LIFETIME_OFFSET];
+        write_gongde_value(&mut legacy, 42).unwrap();
+        assert_eq!(add_lifetime_total(&mut legacy, 1), Ok(()));
+        assert_eq!(read_lifetime_total(&legacy), Ok(42));
+        assert_eq!(write_lifetime_total(&mut legacy, 1), Err(StateError::AccountDataTooSmall));
+        assert_eq!(read_lifetime_total(&[0u8; 3]), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_predict_increment() {
+        assert_eq!(increment_merit(7, 3), Some(10));
+        assert_eq!(increment_merit(u32::MAX - 1, 100), Some(u32::MAX));
+        assert_eq!(increment_merit(u32::MAX, 1), None);
+
+        assert_eq!(predict_increment(7, 100, 3), (10, 103));
+        // 全局功德到顶后保持不变，个人功德照常增加
+        assert_eq!(predict_increment(7, u32::MAX, 3), (10, u32::MAX));
+        // 个人功德已圆满时全局功德也不变
+        assert_eq!(predict_increment(u32::MAX, 100, 3), (u32::MAX, 100));
+    }
+
+    #[test]
+    fn test_record_milestones() {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        assert!(read_milestones(&data).is_empty());
+
+        // 一次跨过两个里程碑
+        assert_eq!(record_milestones(&mut data, 5, 105, 1_000), vec![10, 100]);
+        assert_eq!(
+            read_milestones(&data),
+            vec![Milestone { milestone: 10, reached_at: 1_000 }, Milestone { milestone: 100, reached_at: 1_000 }]
+        );
+
+        // 没有跨过新的里程碑；回滚后再次跨过也不覆盖第一次的时间
+        assert!(record_milestones(&mut data, 105, 200, 2_000).is_empty());
+        assert!(record_milestones(&mut data, 0, 999, 3_000).is_empty());
+        assert_eq!(record_milestones(&mut data, 999, u32::MAX, 4_000), vec![1_000, 10_000]);
+        assert_eq!(read_milestones(&data).len(), MAX_MILESTONES);
+        assert_eq!(read_milestones(&data)[0].reached_at, 1_000);
+
+        // 恰好达到里程碑也算跨过，增加前已经达到的不算
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        assert_eq!(record_milestones(&mut data, 9, 10, 1), vec![10]);
+        assert!(record_milestones(&mut [0u8; GONGDE_ACCOUNT_SIZE], 10, 11, 1).is_empty());
+
+        // 旧布局没有里程碑字段，直接跳过
+        let mut legacy = vec![0u8; GONGDE_MILESTONES_OFFSET];
+        assert!(record_milestones(&mut legacy, 0, 100, 1).is_empty());
+        assert!(read_milestones(&legacy).is_empty());
+    }
+
+    #[test]
+    fn test_beneficiary_fields() {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        assert_eq!(read_last_active(&data), Some(0));
+        assert_eq!(read_beneficiary(&data), None);
+        assert_eq!(read_authority(&data), None);
+
+        let (beneficiary, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        touch_last_active(&mut data, 1_700_000_000);
+        write_beneficiary(&mut data, Some((beneficiary, 90))).unwrap();
+        write_authority(&mut data, &authority).unwrap();
+        assert_eq!(read_last_active(&data), Some(1_700_000_000));
+        assert_eq!(read_beneficiary(&data), Some((beneficiary, 90)));
+        assert_eq!(read_authority(&data), Some(authority));
+        // 新字段都在里程碑之后，不影响已有字段
+        assert!(read_milestones(&data).is_empty());
+
+        write_beneficiary(&mut data, None).unwrap();
+        assert_eq!(read_beneficiary(&data), None);
+        assert_eq!(&data[GONGDE_BENEFICIARY_OFFSET..GONGDE_AUTHORITY_OFFSET], &[0u8; 34]);
+
+        // 旧布局没有这些字段：读取返回None，记录操作时间直接跳过，写入报错
+        let mut legacy = vec![0u8; GONGDE_LAST_ACTIVE_OFFSET];
+        touch_last_active(&mut legacy, 1);
+        assert_eq!(read_last_active(&legacy), None);
+        assert_eq!(write_beneficiary(&mut legacy, Some((beneficiary, 90))), Err(StateError::AccountDataTooSmall));
+        assert_eq!(write_authority(&mut legacy, &authority), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_can_claim_as_beneficiary() {
+        let last_active = 1_700_000_000;
+        let deadline = beneficiary_claimable_at(last_active, 30);
+        assert_eq!(deadline, last_active + 30 * SECONDS_PER_DAY);
+        assert!(!can_claim_as_beneficiary(last_active, 30, last_active));
+        assert!(!can_claim_as_beneficiary(last_active, 30, deadline));
+        assert!(can_claim_as_beneficiary(last_active, 30, deadline + 1));
+        assert_eq!(beneficiary_claimable_at(i64::MAX - 1, u16::MAX), i64::MAX);
+    }
+
+    #[test]
+    fn test_parse_set_beneficiary_args() {
+        let encode = |beneficiary: Pubkey, timeout_days: u16| {
+            let mut data = vec![GongDeInstruction::SetBeneficiary as u8];
+            data.extend_from_slice(beneficiary.as_ref());
+            data.extend_from_slice(&timeout_days.to_le_bytes());
+            data
+        };
+        let beneficiary = Pubkey::new_unique();
+        assert_eq!(
+            parse_set_beneficiary_args(&encode(beneficiary, MIN_BENEFICIARY_TIMEOUT_DAYS)),
+            Ok(Some((beneficiary, MIN_BENEFICIARY_TIMEOUT_DAYS)))
+        );
+        assert_eq!(
+            parse_set_beneficiary_args(&encode(beneficiary, MIN_BENEFICIARY_TIMEOUT_DAYS - 1)),
+            Err(GongDeError::InvalidAmount.into())
+        );
+        // 全0公钥表示清除，不检查等待天数
+        assert_eq!(parse_set_beneficiary_args(&encode(Pubkey::default(), 0)), Ok(None));
+        assert_eq!(parse_set_beneficiary_args(&encode(beneficiary, 90)[..34]), Err(StateError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_gongde_account_size() {
+        assert_eq!(gongde_account_size(1), Some(GONGDE_VALUE_SIZE));
+        assert_eq!(gongde_account_size(2), Some(GONGDE_LIFETIME_OFFSET));
+        assert_eq!(gongde_account_size(3), Some(GONGDE_MILESTONES_OFFSET));
+        assert_eq!(gongde_account_size(4), Some(GONGDE_LAST_ACTIVE_OFFSET));
+        assert_eq!(gongde_account_size(5), Some(GONGDE_STAKED_OFFSET));
+        assert_eq!(gongde_account_size(6), Some(GONGDE_LAST_INCREMENT_SLOT_OFFSET));
+        assert_eq!(gongde_account_size(7), Some(GONGDE_EMOJI_OFFSET));
+        assert_eq!(gongde_account_size(GONGDE_LAYOUT_VERSION), Some(GONGDE_ACCOUNT_SIZE));
+        assert_eq!(gongde_account_size(0), None);
+        assert_eq!(gongde_account_size(GONGDE_LAYOUT_VERSION + 1), None);
+    }
+
+    #[test]
+    fn test_needs_migration() {
+        assert!(needs_migration(GONGDE_VALUE_SIZE));
+        assert!(needs_migration(GONGDE_LIFETIME_OFFSET));
+        assert!(needs_migration(GONGDE_MILESTONES_OFFSET));
+        assert!(needs_migration(GONGDE_LAST_ACTIVE_OFFSET));
+        assert!(needs_migration(GONGDE_STAKED_OFFSET));
+        assert!(!needs_migration(GONGDE_ACCOUNT_SIZE));
+        assert!(!needs_migration(0));
+    }
+
+    #[test]
+    fn test_layout_version() {
+        assert_eq!(layout_version(GONGDE_VALUE_SIZE), Some(1));
+        assert_eq!(layout_version(GONGDE_LIFETIME_OFFSET), Some(2));
+        assert_eq!(layout_version(GONGDE_MILESTONES_OFFSET - 1), Some(2));
+        assert_eq!(layout_version(GONGDE_LAST_ACTIVE_OFFSET - 1), Some(3));
+        assert_eq!(layout_version(GONGDE_STAKED_OFFSET - 1), Some(4));
+        assert_eq!(layout_version(GONGDE_LAST_INCREMENT_SLOT_OFFSET - 1), Some(5));
+        assert_eq!(layout_version(GONGDE_EMOJI_OFFSET - 1), Some(6));
+        assert_eq!(layout_version(GONGDE_ACCOUNT_SIZE - 1), Some(7));
+        assert_eq!(layout_version(GONGDE_ACCOUNT_SIZE), Some(GONGDE_LAYOUT_VERSION));
+        assert_eq!(layout_version(GONGDE_ACCOUNT_SIZE + 8), Some(GONGDE_LAYOUT_VERSION));
+        assert_eq!(layout_version(GONGDE_VALUE_SIZE - 1), None);
+        for version in 1..=GONGDE_LAYOUT_VERSION {
+            assert_eq!(layout_version(gongde_account_size(version).unwrap()), Some(version));
+            assert!(is_gongde_layout_size(gongde_account_size(version).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_other_accounts_are_not_gongde_layout_sizes() {
+        assert!(!is_gongde_layout_size(GONGDE_ACCOUNT_SIZE + 8));
+        assert!(!is_gongde_layout_size(TRANSFER_STATE_SIZE));
+        assert!(!is_gongde_layout_size(CAMPAIGN_SIZE));
+        assert!(!is_gongde_layout_size(ENDORSEMENT_SIZE));
+        assert!(!is_gongde_layout_size(ACCRUAL_STATE_SIZE));
+        assert!(!is_gongde_layout_size(CONFIG_SIZE));
+        // 共享账本创建后同一条指令就会加入第一个条目，不会停留在只有头部的4字节
+        for entries in 1..64 {
+            assert!(!is_gongde_layout_size(shared_ledger_size(entries)));
+            assert!(!is_gongde_layout_size(lottery_size(entries)));
+        }
+        assert!(!is_gongde_layout_size(lottery_size(0)));
+    }
+
+    #[test]
+    fn test_unpack_gongde_account() {
+        let mut data = vec![0u8; GONGDE_ACCOUNT_SIZE];
+        write_gongde_value(&mut data, 7).unwrap();
+        write_initialized_flags(&mut data, false).unwrap();
+
+        let account = GongDeAccount::unpack(&data).unwrap();
+        assert_eq!(account.value, 7);
+        assert!(account.is_initialized());
+        assert!(!account.is_public());
+
+        // 旧的4字节账户
+        let legacy = GongDeAccount::unpack(&7u32.to_le_bytes()).unwrap();
+        assert_eq!(legacy, GongDeAccount { value: 7, flags: 0, lifetime_total: 7 });
+        assert!(legacy.is_public());
+
+        assert_eq!(GongDeAccount::unpack(&[0u8; 3]), Err(StateError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn test_unpack_ignores_trailing_bytes() {
+        // 以后增加字段或预留空间后账户会变大，只读取已定义的前缀
+        let mut data = vec![0xAB; GONGDE_ACCOUNT_SIZE + 32];
+        write_gongde_value(&mut data, 99).unwrap();
+        data[GONGDE_FLAGS_OFFSET] = FLAG_INITIALIZED | FLAG_PUBLIC;
+        write_lifetime_total(&mut data, 157).unwrap();
+
+        assert_eq!(read_gongde_value(&data), Ok(99));
+        assert_eq!(
+            GongDeAccount::unpack(&data),
+            Ok(GongDeAccount { value: 99, flags: FLAG_INITIALIZED | FLAG_PUBLIC, lifetime_total: 157 })
+        );
+        assert!(is_usable_account(&data));
+        assert!(!needs_migration(data.len()));
+
+        let state = TransferState { day: 20_000, transferred_today: 3 };
+        let mut state_data = vec![0xCD; TRANSFER_STATE_SIZE + 8];
+        state.pack(&mut state_data).unwrap();
+        assert_eq!(TransferState::unpack(&state_data), Ok(state));
+        // 写入也只覆盖前缀，不动后面的字节
+        assert_eq!(&state_data[TRANSFER_STATE_SIZE..], &[0xCD; 8]);
+    }
+
+    #[test]
+    fn test_merit_level_boundaries() {
+        let cases = [
+            (0, MeritLevel::Beginner),
+            (1, MeritLevel::Kindness),
+            (2, MeritLevel::Kindness),
+            (9, MeritLevel::Kindness),
+            (10, MeritLevel::Kindness),
+            (11, MeritLevel::GoodDeed),
+            (12, MeritLevel::GoodDeed),
+            (99, MeritLevel::GoodDeed),
+            (100, MeritLevel::GoodDeed),
+            (101, MeritLevel::Virtue),
+            (102, MeritLevel::Virtue),
+            (999, MeritLevel::Virtue),
+            (1000, MeritLevel::Virtue),
+            (1001, MeritLevel::Sage),
+            (1002, MeritLevel::Sage),
+            (9999, MeritLevel::Sage),
+            (10000, MeritLevel::Sage),
+            (10001, MeritLevel::Perfect),
+            (10002, MeritLevel::Perfect),
+            (u32::MAX - 1, MeritLevel::Perfect),
+            (u32::MAX, MeritLevel::Perfect),
+        ];
+        for (value, level) in cases {
+            assert_eq!(MeritLevel::from_value(value), level, "功德 {}", value);
+        }
+
+        // 门槛本身属于该等级，门槛减1属于上一等级
+        for pair in MeritLevel::ALL.windows(2) {
+            let (lower, upper) = (pair[0], pair[1]);
+            assert_eq!(MeritLevel::from_value(upper.threshold()), upper);
+            assert_eq!(MeritLevel::from_value(upper.threshold() - 1), lower);
+            assert_eq!(lower.next(), Some(upper));
+        }
+        assert_eq!(MeritLevel::Perfect.next(), None);
+    }
+
+    #[test]
+    fn test_merit_to_next_level() {
+        assert_eq!(merit_to_next_level(0), Some(1));
+        assert_eq!(merit_to_next_level(1), Some(10));
+        assert_eq!(merit_to_next_level(10), Some(1));
+        assert_eq!(merit_to_next_level(11), Some(90));
+        assert_eq!(merit_to_next_level(100), Some(1));
+        assert_eq!(merit_to_next_level(101), Some(900));
+        assert_eq!(merit_to_next_level(1000), Some(1));
+        assert_eq!(merit_to_next_level(1001), Some(9000));
+        assert_eq!(merit_to_next_level(10000), Some(1));
+        assert_eq!(merit_to_next_level(10001), None);
+        assert_eq!(merit_to_next_level(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_merit_band_aligns_with_levels() {
+        let labels: Vec<String> = MeritLevel::ALL.into_iter().map(|level| MeritBand { level }.to_string()).collect();
+        assert_eq!(labels, ["0", "1+", "11+", "101+", "1001+", "10001+"]);
+
+        // 区间首尾相接覆盖全部 u32，边界就是等级门槛
+        assert_eq!(MeritLevel::Beginner.range(), 0..=0);
+        assert_eq!(MeritLevel::Perfect.range(), 10001..=u32::MAX);
+        for pair in MeritLevel::ALL.windows(2) {
+            let (lower, upper) = (pair[0], pair[1]);
+            assert_eq!(*lower.range().end() + 1, *upper.range().start());
+            assert_eq!(*upper.range().start(), upper.threshold());
+        }
+        for level in MeritLevel::ALL {
+            let range = level.range();
+            for value in [*range.start(), *range.end()] {
+                assert_eq!(MeritBand::from_value(value), MeritBand { level }, "功德 {}", value);
+                assert_eq!(MeritLevel::from_value(value), level, "功德 {}", value);
+            }
+        }
+
+        // 同一等级内的功德显示相同，跨过门槛才变化
+        assert_eq!(MeritBand::from_value(100).to_string(), "11+");
+        assert_eq!(MeritBand::from_value(101).to_string(), "101+");
+        assert_eq!(MeritBand::from_value(999).to_string(), MeritBand::from_value(101).to_string());
+        assert_eq!(MeritBand::from_value(u32::MAX).to_string(), "10001+");
+        assert!(MeritBand::from_value(100) < MeritBand::from_value(101));
+    }
+
+    #[test]
+    fn test_instruction_name_round_trip() {
+        for (tag, instruction) in GongDeInstruction::ALL.into_iter().enumerate() {
+            assert_eq!(instruction as usize, tag);
+            assert_eq!(instruction.to_string().parse(), Ok(instruction));
+            assert_eq!(instruction.name().to_uppercase().parse(), Ok(instruction));
+            assert_eq!(format!("{:?}", instruction).parse(), Ok(instruction));
+        }
+        assert_eq!(GongDeInstruction::ALL.last().map(|instruction| *instruction as u8), Some(MAX_INSTRUCTION_TAG));
+        assert_eq!(" increment_by ".parse(), Ok(GongDeInstruction::IncrementBy));
+    }
+
+    #[test]
+    fn test_instruction_name_rejects_unknown() {
+        let err = "decrement".parse::<GongDeInstruction>().unwrap_err();
+        assert_eq!(err.input, "decrement");
+        assert_eq!(err.valid.len(), GongDeInstruction::ALL.len());
+        let message = err.to_string();
+        assert!(message.contains("decrement"));
+        assert!(message.contains("increment, close, initialize"));
+        assert!("".parse::<GongDeInstruction>().is_err());
+        assert!("increment by".parse::<GongDeInstruction>().is_err());
+    }
+
+    #[test]
+    fn test_merit_level_name_round_trip() {
+        for level in MeritLevel::ALL {
+            assert!(level.id().is_ascii());
+            assert_eq!(level.id().parse(), Ok(level));
+            assert_eq!(level.id().to_uppercase().parse(), Ok(level));
+            assert_eq!(level.to_string().parse(), Ok(level));
+            let chinese = level.name().split_once(' ').unwrap().1;
+            assert_eq!(chinese.parse(), Ok(level));
+        }
+        assert_eq!("GoodDeed".parse(), Ok(MeritLevel::GoodDeed));
+    }
+
+    #[test]
+    fn test_merit_level_name_rejects_unknown() {
+        let err = "buddha".parse::<MeritLevel>().unwrap_err();
+        assert_eq!(err.valid, vec!["beginner", "kindness", "good-deed", "virtue", "sage", "perfect"]);
+        assert!(err.to_string().contains("buddha"));
+        assert!("圆满".parse::<MeritLevel>().is_err());
+        assert!("🌟".parse::<MeritLevel>().is_err());
+    }
+}
@@ -1,91 +1,23 @@
 // ========================================
 // 工具模块 - 共享的序列化反序列化和字节处理工具
+// 纯数据逻辑在 state 模块中，这里全部重新导出，原有的 `utils::` 路径保持不变；
+// 本模块只补充依赖 solana-program 的账户地址生成
 // ========================================
 
 use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    hash::hashv,
     program_error::ProgramError,
     pubkey::Pubkey,
 };
 
-// ========================================
-// 常量定义 - 消除硬编码
-// ========================================
-
-/// 功德值存储所需的字节数（u32类型需要4字节）
-pub const GONGDE_VALUE_SIZE: usize = 4;
-
-/// 功德账户种子字符串
-pub const GONGDE_ACCOUNT_SEED: &str = "GongDeIncrease";
-
-/// 全局功德账户种子字符串
-pub const GLOBAL_GONGDE_ACCOUNT_SEED: &str = "GlobalGongDeIncrease";
+pub use crate::state::*;
 
 /// 创作者地址 - 接收手续费的硬编码地址
 /// 这里使用一个示例地址，实际部署时请替换为你的真实地址
 pub const CREATOR_ADDRESS: &str = "BvpjTs88TmXJrFfghPJmo1kEJXdtqXX8SdvW6jv8ng9R";
 
-// ========================================
-// 序列化反序列化工具函数
-// ========================================
-
-/// 从字节数组中读取功德值（u32，小端序）
-/// 
-/// # 参数
-/// * `data` - 字节数据数组
-/// 
-/// # 返回
-/// * `Result<u32, ProgramError>` - 解析的u32值
-/// 
-/// # 错误
-/// * `ProgramError::AccountDataTooSmall` - 如果数据长度不足4字节
-pub fn read_gongde_value(data: &[u8]) -> Result<u32, ProgramError> {
-    if data.len() < GONGDE_VALUE_SIZE {
-        return Err(ProgramError::AccountDataTooSmall);
-    }
-    
-    Ok(u32::from_le_bytes([
-        data[0], data[1], data[2], data[3]
-    ]))
-}
-
-/// 将功德值写入字节数组（u32，小端序）
-/// 
-/// # 参数
-/// * `data` - 目标字节数据数组（可变引用）
-/// * `value` - 要写入的u32值
-/// 
-/// # 返回
-/// * `Result<(), ProgramError>` - 成功返回Ok(())
-/// 
-/// # 错误
-/// * `ProgramError::AccountDataTooSmall` - 如果数据长度不足4字节
-pub fn write_gongde_value(data: &mut [u8], value: u32) -> Result<(), ProgramError> {
-    if data.len() < GONGDE_VALUE_SIZE {
-        return Err(ProgramError::AccountDataTooSmall);
-    }
-    
-    let bytes = value.to_le_bytes();
-    data[0..GONGDE_VALUE_SIZE].copy_from_slice(&bytes);
-    Ok(())
-}
-
-/// 验证账户数据长度是否足够存储功德值
-/// 
-/// # 参数
-/// * `data_len` - 账户数据长度
-/// 
-/// # 返回
-/// * `Result<(), ProgramError>` - 验证通过返回Ok(())
-/// 
-/// # 错误
-/// * `ProgramError::AccountDataTooSmall` - 如果数据长度不足
-pub fn validate_account_data_size(data_len: usize) -> Result<(), ProgramError> {
-    if data_len < GONGDE_VALUE_SIZE {
-        return Err(ProgramError::AccountDataTooSmall);
-    }
-    Ok(())
-}
-
 // ========================================
 // 账户地址生成工具函数
 // ========================================
@@ -104,10 +36,27 @@ pub fn validate_account_data_size(data_len: usize) -> Result<(), ProgramError> {
 pub fn derive_gongde_account_address(
     user_pubkey: &Pubkey, 
     program_id: &Pubkey
+) -> Result<Pubkey, ProgramError> {
+    derive_gongde_account_address_with_prefix(user_pubkey, program_id, SEED_PREFIX)
+}
+
+/// 按指定的种子前缀生成用户的功德账户地址，用于查看其他部署（用别的 GONGDE_SEED_PREFIX 编译）的账户
+///
+/// # 参数
+/// * `user_pubkey` - 用户公钥
+/// * `program_id` - 程序ID
+/// * `prefix` - 种子前缀
+///
+/// # 错误
+/// * `ProgramError::InvalidSeeds` - 前缀超过32个字节
+pub fn derive_gongde_account_address_with_prefix(
+    user_pubkey: &Pubkey,
+    program_id: &Pubkey,
+    prefix: &str,
 ) -> Result<Pubkey, ProgramError> {
     Pubkey::create_with_seed(
         user_pubkey,           // 基础地址（用户公钥）
-        GONGDE_ACCOUNT_SEED,   // 种子字符串
+        prefix,                // 种子字符串
         program_id,            // 合约程序ID
     ).map_err(|_| ProgramError::InvalidSeeds)
 }
@@ -133,6 +82,110 @@ pub fn derive_global_gongde_pda_address(
     Ok((pda, bump))
 }
 
+/// 按指定的种子前缀生成全局功德PDA账户地址
+///
+/// PDA的种子是直接拼接后计算哈希的，[GLOBAL_SEED_HEAD, 前缀] 两个种子与拼接成的一个种子地址相同，
+/// 所以默认前缀得到的就是 `derive_global_gongde_pda_address` 的地址
+///
+/// # 参数
+/// * `program_id` - 程序ID
+/// * `prefix` - 种子前缀
+///
+/// # 错误
+/// * `ProgramError::InvalidSeeds` - 前缀超过32个字节，或者找不到有效的bump
+pub fn derive_global_gongde_pda_address_with_prefix(
+    program_id: &Pubkey,
+    prefix: &str,
+) -> Result<(Pubkey, u8), ProgramError> {
+    Pubkey::try_find_program_address(&[GLOBAL_SEED_HEAD.as_bytes(), prefix.as_bytes()], program_id)
+        .ok_or(ProgramError::InvalidSeeds)
+}
+
+/// 生成转出者的转赠状态PDA地址
+/// 
+/// # 参数
+/// * `sender` - 转出者公钥
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_transfer_state_address(sender: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TRANSFER_STATE_SEED, sender.as_ref()], program_id)
+}
+
+/// 生成配捐活动PDA地址
+/// 
+/// # 参数
+/// * `organizer` - 发起人公钥
+/// * `campaign_id` - 活动编号
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_campaign_address(organizer: &Pubkey, campaign_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CAMPAIGN_SEED, organizer.as_ref(), &campaign_id.to_le_bytes()], program_id)
+}
+
+/// 生成背书记录PDA地址
+/// 
+/// # 参数
+/// * `endorser` - 背书人公钥
+/// * `endorsee_gongde` - 被背书的功德账户地址
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_endorsement_address(endorser: &Pubkey, endorsee_gongde: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ENDORSEMENT_SEED, endorser.as_ref(), endorsee_gongde.as_ref()], program_id)
+}
+
+/// 生成功德账户的挂机功德状态PDA地址
+/// 
+/// # 参数
+/// * `gongde_pubkey` - 功德账户地址
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_accrual_state_address(gongde_pubkey: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ACCRUAL_STATE_SEED, gongde_pubkey.as_ref()], program_id)
+}
+
+/// 生成彩票轮次PDA地址
+/// 
+/// # 参数
+/// * `admin` - 创建这一轮的管理员公钥
+/// * `round_id` - 轮次编号
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_lottery_address(admin: &Pubkey, round_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOTTERY_SEED, admin.as_ref(), &round_id.to_le_bytes()], program_id)
+}
+
+/// 生成共享账本PDA地址
+/// 
+/// # 参数
+/// * `program_id` - 程序ID
+/// 
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_shared_ledger_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SHARED_LEDGER_SEED], program_id)
+}
+
+/// 生成全局配置PDA地址
+///
+/// # 参数
+/// * `program_id` - 程序ID
+///
+/// # 返回
+/// * `(Pubkey, u8)` - (PDA地址, bump种子)
+pub fn derive_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
 /// 获取创作者地址
 /// 
 /// # 返回
@@ -145,75 +198,285 @@ pub fn get_creator_address() -> Result<Pubkey, ProgramError> {
 }
 
 // ========================================
-// 指令类型枚举
+// 用户相关地址清单 - 安全审计时预先授权或监控合约可能为某个用户创建的全部账户
 // ========================================
 
-/// 合约支持的指令类型
-#[repr(u8)]
+/// 地址清单中的一个账户，名称与指令账户要求（AccountSpec）中的名称一致
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum GongDeInstruction {
-    /// 增加功德值指令
-    Increment = 0,
-    /// 关闭账户指令
-    Close = 1,
-}
-
-impl GongDeInstruction {
-    /// 从字节解析指令类型
-    /// 
-    /// # 参数
-    /// * `instruction_data` - 指令数据字节数组
-    /// 
-    /// # 返回
-    /// * `Result<Self, ProgramError>` - 解析的指令类型
-    /// 
-    /// # 错误
-    /// * `ProgramError::InvalidInstructionData` - 如果指令数据无效
-    pub fn from_instruction_data(instruction_data: &[u8]) -> Result<Self, ProgramError> {
-        match instruction_data.first().copied().unwrap_or(255) {
-            0 => Ok(Self::Increment),
-            1 => Ok(Self::Close),
-            _ => Err(ProgramError::InvalidInstructionData),
+pub struct NamedPda {
+    /// 账户用途，例如 "transfer_state"
+    pub name: &'static str,
+    pub address: Pubkey,
+    /// PDA的bump种子；功德账户是 create_account_with_seed 的种子地址，没有bump，为None
+    pub bump: Option<u8>,
+}
+
+/// `enumerate_user_pdas` 的选项：按编号或对象区分的账户数量没有上限，只列出调用方指定的那些
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnumerateOptions {
+    /// 用户发起的配捐活动编号
+    pub campaign_ids: Vec<u64>,
+    /// 用户作为管理员创建的彩票轮次编号
+    pub lottery_rounds: Vec<u64>,
+    /// 用户可能背书的功德账户地址
+    pub endorsees: Vec<Pubkey>,
+    /// 是否包含所有用户共用、由第一个使用者付款创建的账户（全局功德PDA、共享账本）
+    pub include_shared: bool,
+}
+
+/// 列出合约可能为某个用户创建的全部账户地址，顺序固定
+/// 全局配置只能由创作者创建，不在清单中
+///
+/// # 参数
+/// * `user` - 用户公钥
+/// * `program_id` - 程序ID
+/// * `options` - 需要列出的活动编号、彩票轮次、背书对象，以及是否包含共用账户
+///
+/// # 返回
+/// * `Vec<NamedPda>` - 功德账户、挂机功德状态、转赠状态，然后依次是配捐活动、彩票轮次、背书记录和共用账户
+pub fn enumerate_user_pdas(user: &Pubkey, program_id: &Pubkey, options: &EnumerateOptions) -> Vec<NamedPda> {
+    let named = |name, (address, bump): (Pubkey, u8)| NamedPda { name, address, bump: Some(bump) };
+    let mut pdas = Vec::new();
+    // 种子字符串是常量且不超过长度限制，派生不会失败
+    if let Ok(gongde_pubkey) = derive_gongde_account_address(user, program_id) {
+        pdas.push(NamedPda { name: "gongde", address: gongde_pubkey, bump: None });
+        pdas.push(named("accrual_state", derive_accrual_state_address(&gongde_pubkey, program_id)));
+    }
+    pdas.push(named("transfer_state", derive_transfer_state_address(user, program_id)));
+    for campaign_id in &options.campaign_ids {
+        pdas.push(named("campaign", derive_campaign_address(user, *campaign_id, program_id)));
+    }
+    for round_id in &options.lottery_rounds {
+        pdas.push(named("lottery", derive_lottery_address(user, *round_id, program_id)));
+    }
+    for endorsee_gongde in &options.endorsees {
+        pdas.push(named("endorsement", derive_endorsement_address(user, endorsee_gongde, program_id)));
+    }
+    if options.include_shared {
+        if let Ok(global) = derive_global_gongde_pda_address(program_id) {
+            pdas.push(named("global_pda", global));
         }
+        pdas.push(named("shared_ledger", derive_shared_ledger_address(program_id)));
+    }
+    pdas
+}
+
+// ========================================
+// lamports 转移工具 - 直接修改余额的指令（关闭账户等）统一通过这里，不再各自解引用 RefCell
+// ========================================
+
+/// 在两个账户之间直接转移 lamports，`from` 必须归本程序所有
+///
+/// # 参数
+/// * `from` - 转出账户
+/// * `to` - 转入账户
+/// * `amount` - 转移数量，为0时不做任何修改
+///
+/// # 错误
+/// * `ProgramError::InvalidArgument` - 转出和转入是同一个账户
+/// * `ProgramError::InsufficientFunds` - 转出账户余额不足
+/// * `ProgramError::ArithmeticOverflow` - 转入账户余额溢出
+pub fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    // 同一个账户传两次时借用会冲突，加减也会互相覆盖
+    if from.key == to.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount == 0 {
+        return Ok(());
     }
+    // 先算出两边的新余额，都没有问题再写入，失败时余额保持不变
+    let from_balance = from.lamports().checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+    let to_balance = to.lamports().checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
+    Ok(())
+}
+
+/// 把账户的全部 lamports 转给另一个账户，用于关闭账户
+///
+/// # 参数
+/// * `from` - 被清空的账户
+/// * `to` - 接收余额的账户
+///
+/// # 错误
+/// * 同 `move_lamports`
+pub fn drain_lamports(from: &AccountInfo, to: &AccountInfo) -> ProgramResult {
+    move_lamports(from, to, from.lamports())
+}
+
+// ========================================
+// 工作量证明 - 合约校验和客户端挖 nonce 用同一个哈希，难度判断见 state 模块的 meets_pow_difficulty
+// ========================================
+
+/// 计算工作量证明的哈希：sha256(功德账户地址 || 上次证明的slot || nonce)，整数都按小端序编码
+///
+/// # 参数
+/// * `gongde_pubkey` - 增加功德的账户地址，为一个账户算出的 nonce 不能用在别的账户上
+/// * `last_increment_slot` - 功德账户记录的上次证明的slot，每次成功后都会变化
+/// * `nonce` - 客户端尝试的 nonce
+///
+/// # 返回
+/// * `[u8; 32]` - 哈希值，前导0比特数达到难度时证明有效
+pub fn pow_hash(gongde_pubkey: &Pubkey, last_increment_slot: u64, nonce: u64) -> [u8; 32] {
+    hashv(&[gongde_pubkey.as_ref(), &last_increment_slot.to_le_bytes(), &nonce.to_le_bytes()]).to_bytes()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// 测试用账户：地址、余额和数据都由调用方持有
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestAccount {
+        fn new(lamports: u64) -> Self {
+            Self { key: Pubkey::new_unique(), owner: Pubkey::new_unique(), lamports, data: Vec::new() }
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+        }
+    }
+
     #[test]
-    fn test_read_write_gongde_value() {
-        let mut data = vec![0u8; 8]; // 8字节数据，够用
-        let test_value = 12345u32;
-        
-        // 测试写入
-        assert!(write_gongde_value(&mut data, test_value).is_ok());
-        
-        // 测试读取
-        let read_value = read_gongde_value(&data).unwrap();
-        assert_eq!(read_value, test_value);
-    }
-    
+    fn test_seed_prefix_namespaces_addresses() {
+        let program_id = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+
+        // 当前编译使用的前缀与不带前缀的函数一致
+        assert_eq!(
+            derive_gongde_account_address_with_prefix(&user, &program_id, SEED_PREFIX),
+            derive_gongde_account_address(&user, &program_id)
+        );
+        assert_eq!(
+            derive_global_gongde_pda_address_with_prefix(&program_id, SEED_PREFIX),
+            derive_global_gongde_pda_address(&program_id)
+        );
+        assert_eq!(GLOBAL_GONGDE_ACCOUNT_SEED, format!("{}{}", GLOBAL_SEED_HEAD, SEED_PREFIX));
+
+        // 不同的前缀得到不同的地址
+        let devnet = derive_gongde_account_address_with_prefix(&user, &program_id, "devnet").unwrap();
+        let mainnet = derive_gongde_account_address_with_prefix(&user, &program_id, "mainnet").unwrap();
+        assert_ne!(devnet, mainnet);
+        let (devnet, _) = derive_global_gongde_pda_address_with_prefix(&program_id, "devnet").unwrap();
+        let (mainnet, _) = derive_global_gongde_pda_address_with_prefix(&program_id, "mainnet").unwrap();
+        assert_ne!(devnet, mainnet);
+
+        // 超过种子长度上限
+        let long = "x".repeat(33);
+        assert_eq!(derive_gongde_account_address_with_prefix(&user, &program_id, &long), Err(ProgramError::InvalidSeeds));
+        assert_eq!(derive_global_gongde_pda_address_with_prefix(&program_id, &long), Err(ProgramError::InvalidSeeds));
+    }
+
+    #[test]
+    fn test_enumerate_user_pdas() {
+        let program_id = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let gongde_pubkey = derive_gongde_account_address(&user, &program_id).unwrap();
+
+        let pdas = enumerate_user_pdas(&user, &program_id, &EnumerateOptions::default());
+        let names: Vec<_> = pdas.iter().map(|pda| pda.name).collect();
+        assert_eq!(names, ["gongde", "accrual_state", "transfer_state"]);
+        assert_eq!(pdas[0], NamedPda { name: "gongde", address: gongde_pubkey, bump: None });
+        let (address, bump) = derive_accrual_state_address(&gongde_pubkey, &program_id);
+        assert_eq!(pdas[1], NamedPda { name: "accrual_state", address, bump: Some(bump) });
+
+        let endorsee = Pubkey::new_unique();
+        let options = EnumerateOptions {
+            campaign_ids: vec![1, 2],
+            lottery_rounds: vec![7],
+            endorsees: vec![endorsee],
+            include_shared: true,
+        };
+        let pdas = enumerate_user_pdas(&user, &program_id, &options);
+        let names: Vec<_> = pdas.iter().map(|pda| pda.name).collect();
+        assert_eq!(
+            names,
+            [
+                "gongde",
+                "accrual_state",
+                "transfer_state",
+                "campaign",
+                "campaign",
+                "lottery",
+                "endorsement",
+                "global_pda",
+                "shared_ledger"
+            ]
+        );
+        assert_eq!(pdas[4].address, derive_campaign_address(&user, 2, &program_id).0);
+        assert_eq!(pdas[6].address, derive_endorsement_address(&user, &endorsee, &program_id).0);
+        // 每个地址只出现一次，同一个用户的清单不会重复
+        let mut addresses: Vec<_> = pdas.iter().map(|pda| pda.address).collect();
+        addresses.sort();
+        addresses.dedup();
+        assert_eq!(addresses.len(), pdas.len());
+        // 只有功德账户是种子地址，没有bump
+        assert!(pdas.iter().all(|pda| pda.bump.is_none() == (pda.name == "gongde")));
+    }
+
     #[test]
-    fn test_insufficient_data_size() {
-        let data = vec![0u8; 2]; // 只有2字节，不够
-        
-        // 测试读取失败
-        assert_eq!(read_gongde_value(&data), Err(ProgramError::AccountDataTooSmall));
-        
-        // 测试数据大小验证失败
-        assert_eq!(validate_account_data_size(2), Err(ProgramError::AccountDataTooSmall));
-    }
-    
+    fn test_move_lamports() {
+        let mut from = TestAccount::new(1_000);
+        let mut to = TestAccount::new(10);
+        {
+            let (from, to) = (from.info(), to.info());
+            move_lamports(&from, &to, 300).unwrap();
+            assert_eq!((from.lamports(), to.lamports()), (700, 310));
+            drain_lamports(&from, &to).unwrap();
+            assert_eq!((from.lamports(), to.lamports()), (0, 1_010));
+        }
+        assert_eq!((from.lamports, to.lamports), (0, 1_010));
+    }
+
     #[test]
-    fn test_instruction_parsing() {
-        // 测试有效指令
-        assert_eq!(GongDeInstruction::from_instruction_data(&[0]), Ok(GongDeInstruction::Increment));
-        assert_eq!(GongDeInstruction::from_instruction_data(&[1]), Ok(GongDeInstruction::Close));
-        
-        // 测试无效指令
-        assert_eq!(GongDeInstruction::from_instruction_data(&[2]), Err(ProgramError::InvalidInstructionData));
-        assert_eq!(GongDeInstruction::from_instruction_data(&[]), Err(ProgramError::InvalidInstructionData));
+    fn test_move_lamports_zero_amount() {
+        let mut from = TestAccount::new(0);
+        let mut to = TestAccount::new(u64::MAX);
+        let (from, to) = (from.info(), to.info());
+        move_lamports(&from, &to, 0).unwrap();
+        // 空账户清空也不会出错
+        drain_lamports(&from, &to).unwrap();
+        assert_eq!((from.lamports(), to.lamports()), (0, u64::MAX));
+    }
+
+    #[test]
+    fn test_move_lamports_rejects_overflow_and_shortfall() {
+        let mut from = TestAccount::new(100);
+        let mut to = TestAccount::new(u64::MAX - 50);
+        let (from, to) = (from.info(), to.info());
+        assert_eq!(move_lamports(&from, &to, 101), Err(ProgramError::InsufficientFunds));
+        assert_eq!(drain_lamports(&from, &to), Err(ProgramError::ArithmeticOverflow));
+        // 失败时两边余额都不变
+        assert_eq!((from.lamports(), to.lamports()), (100, u64::MAX - 50));
+    }
+
+    #[test]
+    fn test_move_lamports_rejects_same_account() {
+        let mut account = TestAccount::new(100);
+        let info = account.info();
+        let alias = info.clone();
+        assert_eq!(move_lamports(&info, &alias, 1), Err(ProgramError::InvalidArgument));
+        assert_eq!(drain_lamports(&info, &info), Err(ProgramError::InvalidArgument));
+        assert_eq!(info.lamports(), 100);
+    }
+
+    #[test]
+    fn test_pow_hash_pinned_nonce() {
+        // 固定输入下难度8的第一个有效 nonce 是156，合约和客户端的哈希编码改变时这里会失败
+        let gongde_pubkey = Pubkey::new_from_array([1; 32]);
+        assert!(meets_pow_difficulty(&pow_hash(&gongde_pubkey, 42, 156), 8));
+        assert!((0..156).all(|nonce| !meets_pow_difficulty(&pow_hash(&gongde_pubkey, 42, nonce), 8)));
+
+        // 上次证明的slot或功德账户变化后，同一个 nonce 失效
+        assert!(!meets_pow_difficulty(&pow_hash(&gongde_pubkey, 43, 156), 8));
+        assert!(!meets_pow_difficulty(&pow_hash(&Pubkey::new_from_array([2; 32]), 42, 156), 8));
+        // 难度0时任何 nonce 都有效
+        assert!(meets_pow_difficulty(&pow_hash(&gongde_pubkey, 42, 0), 0));
     }
 }
@@ -0,0 +1,84 @@
+// ========================================
+// 账户数量检查测试 - 账户不够时合约报出 NotEnoughAccountKeys，并在日志中列出指令期望的账户
+// ========================================
+
+mod common;
+
+use gong_de_increase::{instruction::InstructionSpec, utils::GongDeInstruction};
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    signature::Signer,
+};
+
+use common::instruction_error;
+
+/// 指令期望的账户名，与合约日志中列出的一行相同
+fn expected_names(instruction: GongDeInstruction) -> String {
+    let names: Vec<_> = InstructionSpec::of(instruction).accounts.iter().map(|account| account.name).collect();
+    format!("Program log: {}", names.join(", "))
+}
+
+#[test]
+fn test_every_instruction_lists_expected_accounts() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+
+    for instruction in GongDeInstruction::ALL {
+        let spec = InstructionSpec::of(instruction);
+        if spec.accounts.is_empty() {
+            continue;
+        }
+        // 参数全部为0，长度正确，账户一个都不传
+        let mut data = vec![0; spec.data_len()];
+        data[0] = instruction as u8;
+        let err = ctx
+            .send(&[Instruction::new_with_bytes(ctx.program_id, &data, vec![])], &user, &[])
+            .unwrap_err();
+        assert_eq!(err.err, instruction_error(InstructionError::NotEnoughAccountKeys), "{}", instruction);
+        assert!(
+            common::logged(
+                &err.meta.logs,
+                "{} 指令需要 {} 个账户，实际只传了 {} 个，依次为: {}",
+                &[instruction as u64, spec.accounts.len() as u64, 0]
+            ),
+            "{}: {:?}",
+            instruction,
+            err.meta.logs
+        );
+        assert!(err.meta.logs.contains(&expected_names(instruction)), "{}: {:?}", instruction, err.meta.logs);
+    }
+}
+
+#[test]
+fn test_missing_trailing_account_is_reported() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.increment(&user).expect("增加功德失败");
+
+    // 漏传系统程序，功德不变
+    // 旧编码没有附带全局配置，去掉的最后一个账户就是系统程序
+    let mut instruction = common::legacy_encoding(&ctx.increment_instruction(&gongde_pubkey, &user.pubkey()));
+    instruction.accounts.pop();
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::NotEnoughAccountKeys));
+    assert!(
+        common::logged(
+            &err.meta.logs,
+            "{} 指令需要 {} 个账户，实际只传了 {} 个，依次为: {}",
+            &[GongDeInstruction::Increment as u64, 4, 3]
+        ),
+        "{:?}",
+        err.meta.logs
+    );
+    assert!(err.meta.logs.contains(&expected_names(GongDeInstruction::Increment)), "{:?}", err.meta.logs);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+
+    // 关闭只传功德账户，不传账户主人
+    let mut instruction = common::legacy_encoding(&ctx.close_instruction(&user.pubkey()));
+    instruction.accounts.truncate(1);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::NotEnoughAccountKeys));
+    assert!(err.meta.logs.contains(&expected_names(GongDeInstruction::Close)), "{:?}", err.meta.logs);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+}
@@ -0,0 +1,118 @@
+// ========================================
+// Accrue 指令测试 - 按经过的时间积累挂机功德
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{read_last_accrued, ACCRUAL_STATE_SIZE, ACCRUE_SECONDS_PER_MERIT, MAX_ACCRUED_MERIT};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    signature::Signer,
+    transaction::TransactionError,
+};
+
+/// 测试中使用的起始时间
+const START: i64 = 1_700_000_000;
+
+/// 积累1点功德的时长
+const HOUR: i64 = ACCRUE_SECONDS_PER_MERIT;
+
+/// 创建功德账户并在 START 时刻开始计时
+fn setup_accruing(ctx: &mut common::TestContext) -> solana_sdk::signature::Keypair {
+    ctx.set_unix_timestamp(START);
+    let user = ctx.new_user();
+    ctx.create_gongde_account(&user);
+    ctx.accrue(&user).expect("开始计时失败");
+    user
+}
+
+#[test]
+fn test_first_accrue_starts_the_clock() {
+    let mut ctx = common::setup();
+    let user = setup_accruing(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+    let state = ctx.svm.get_account(&ctx.accrual_state_address(&gongde_pubkey)).unwrap();
+    assert_eq!(state.owner, ctx.program_id);
+    assert_eq!(read_last_accrued(&state.data), Ok(START));
+}
+
+#[test]
+fn test_prefunded_state_cannot_block_accrual() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(START);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 有人抢先往挂机功德状态PDA转账，第一次结算仍然可以创建状态账户并开始计时
+    let state_pubkey = ctx.accrual_state_address(&gongde_pubkey);
+    ctx.prefund(&state_pubkey);
+    ctx.accrue(&user).expect("状态地址被抢先转账后开始计时失败");
+
+    let state = ctx.svm.get_account(&state_pubkey).unwrap();
+    assert_eq!(state.owner, ctx.program_id);
+    assert_eq!(state.data.len(), ACCRUAL_STATE_SIZE);
+    assert_eq!(read_last_accrued(&state.data), Ok(START));
+
+    ctx.set_unix_timestamp(START + HOUR);
+    ctx.accrue(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+}
+
+#[test]
+fn test_accrue_matches_rate_and_keeps_remainder() {
+    let mut ctx = common::setup();
+    let user = setup_accruing(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 3小时半：积累3点，半小时的零头留到下次
+    ctx.set_unix_timestamp(START + 3 * HOUR + HOUR / 2);
+    ctx.accrue(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+
+    // 再过半小时，零头凑满1点
+    ctx.set_unix_timestamp(START + 4 * HOUR);
+    ctx.accrue(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(4));
+
+    // 不足一个周期时不积累
+    ctx.set_unix_timestamp(START + 4 * HOUR + 1);
+    ctx.accrue(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(4));
+}
+
+#[test]
+fn test_accrue_is_capped_after_long_gap() {
+    let mut ctx = common::setup();
+    let user = setup_accruing(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 一个月没有结算，也只积累上限
+    let later = START + 30 * 24 * HOUR;
+    ctx.set_unix_timestamp(later);
+    ctx.accrue(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(MAX_ACCRUED_MERIT));
+
+    // 超出的部分作废，从这次结算重新计时
+    let state = ctx.svm.get_account(&ctx.accrual_state_address(&gongde_pubkey)).unwrap();
+    assert_eq!(read_last_accrued(&state.data), Ok(later));
+    ctx.set_unix_timestamp(later + HOUR);
+    ctx.accrue(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(MAX_ACCRUED_MERIT + 1));
+}
+
+#[test]
+fn test_accrue_requires_authority() {
+    let mut ctx = common::setup();
+    let user = setup_accruing(&mut ctx);
+    let stranger = ctx.new_user();
+
+    // 别人不能替账户主人结算
+    let mut instruction = ctx.accrue_instruction(&user.pubkey());
+    instruction.accounts[1] = AccountMeta::new(stranger.pubkey(), true);
+    ctx.set_unix_timestamp(START + 5 * HOUR);
+    let err = ctx.send(&[instruction], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    assert_eq!(ctx.read_value(&ctx.gongde_address(&user.pubkey())), Some(0));
+}
@@ -0,0 +1,118 @@
+// ========================================
+// 归档测试 - Archive / Unarchive
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{is_archived_account, read_gongde_flags, FLAG_INITIALIZED, FLAG_PUBLIC},
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use common::custom_error;
+
+/// 创建一个有 value 点功德并已归档的账户
+fn archived_user(ctx: &mut common::TestContext, value: u64) -> Keypair {
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), value);
+    ctx.send(&[instruction], &user, &[]).expect("增加功德失败");
+    ctx.archive(&user).expect("归档失败");
+    user
+}
+
+#[test]
+fn test_archive_unarchive_close_sequence() {
+    let mut ctx = common::setup();
+    let user = archived_user(&mut ctx, 7);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 归档保留功德和其他标志位
+    let data = ctx.svm.get_account(&gongde_pubkey).unwrap().data;
+    assert!(is_archived_account(&data));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(7));
+
+    // 归档期间不能增加功德，重复归档也会失败
+    assert_eq!(ctx.increment(&user).unwrap_err().err, custom_error(GongDeError::AccountArchived));
+    assert_eq!(ctx.archive(&user).unwrap_err().err, custom_error(GongDeError::AccountArchived));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(7));
+
+    // 取消归档后恢复正常，重复取消归档失败
+    ctx.unarchive(&user).expect("取消归档失败");
+    let data = ctx.svm.get_account(&gongde_pubkey).unwrap().data;
+    assert_eq!(read_gongde_flags(&data), FLAG_INITIALIZED | FLAG_PUBLIC);
+    assert_eq!(ctx.unarchive(&user).unwrap_err().err, custom_error(GongDeError::AccountNotArchived));
+    ctx.increment(&user).expect("取消归档后增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(8));
+
+    // 再次归档后仍然可以关闭
+    ctx.archive(&user).expect("再次归档失败");
+    ctx.close(&user).expect("关闭已归档的账户失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), None);
+}
+
+#[test]
+fn test_archived_account_rejects_mutations() {
+    let mut ctx = common::setup();
+    let user = archived_user(&mut ctx, 20);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let other = ctx.new_user();
+    let other_gongde = ctx.create_gongde_account(&other);
+
+    let archived = custom_error(GongDeError::AccountArchived);
+    // 别人也不能给已归档的公开功德箱增加功德
+    assert_eq!(ctx.increment_for(&gongde_pubkey, &other).unwrap_err().err, archived);
+    assert_eq!(ctx.transfer_merit(&user, &other_gongde, 1).unwrap_err().err, archived);
+    assert_eq!(ctx.accrue(&user).unwrap_err().err, archived);
+    assert_eq!(ctx.stake(&user, 1_000_000).unwrap_err().err, archived);
+    assert_eq!(ctx.claim_accrued(&user).unwrap_err().err, archived);
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 1, 0);
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, archived);
+    let instruction = ctx.set_beneficiary_instruction(&user.pubkey(), Some((other.pubkey(), 30)));
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, archived);
+    // 已归档的账户也不能作为接收方或被背书
+    assert_eq!(ctx.transfer_merit(&other, &gongde_pubkey, 1).unwrap_err().err, archived);
+    assert_eq!(ctx.endorse(&other, &gongde_pubkey).unwrap_err().err, archived);
+
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(20));
+    assert_eq!(ctx.read_value(&other_gongde), Some(0));
+}
+
+#[test]
+fn test_archive_requires_authority() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    let stranger = ctx.new_user();
+
+    // 别人签名归档这个账户
+    let mut instruction = ctx.set_archived_instruction(&user.pubkey(), true);
+    instruction.accounts[1].pubkey = stranger.pubkey();
+    let result = ctx.send(&[instruction], &stranger, &[]);
+    assert_eq!(result.unwrap_err().err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+
+    // 缺少签名
+    let mut instruction = ctx.set_archived_instruction(&user.pubkey(), true);
+    instruction.accounts[1].is_signer = false;
+    let result = ctx.send(&[instruction], &stranger, &[]);
+    assert_eq!(
+        result.unwrap_err().err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    assert!(!is_archived_account(&ctx.svm.get_account(&gongde_pubkey).unwrap().data));
+}
+
+#[test]
+fn test_legacy_account_cannot_be_archived() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    ctx.create_legacy_account(&user.pubkey(), 5);
+
+    let result = ctx.archive(&user);
+    assert_eq!(result.unwrap_err().err, TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall));
+}
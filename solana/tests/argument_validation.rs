@@ -0,0 +1,110 @@
+// ========================================
+// 参数预检一致性测试 - 客户端的 validate_args/validate_spend 与合约处理函数拒绝同样的输入
+// 每个用例先在本地检查，再把同一条指令发到链上，两边报告的错误必须一致
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::StateError,
+    instruction::{self, ValidationError},
+    utils::{derive_lottery_address, CreateCampaignArgs, CreateLotteryArgs, MAX_INCREMENT, MIN_BENEFICIARY_TIMEOUT_DAYS},
+};
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    program_error::ProgramError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+/// 本地检查被拒绝的错误在链上对应的交易错误
+fn on_chain_error(error: StateError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::from(u64::from(ProgramError::from(error))))
+}
+
+/// 本地检查必须拒绝，发到链上也必须以同样的错误失败，且不改变功德
+fn assert_rejected_alike(ctx: &mut common::TestContext, user: &Keypair, instruction: Instruction, current: u32) {
+    let error = match instruction::validate_spend(&instruction, current) {
+        Err(ValidationError::InvalidArgs { error, .. }) => error,
+        other => panic!("本地检查应拒绝参数，实际 {:?}", other),
+    };
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let before = ctx.read_value(&gongde_pubkey);
+    let err = ctx.send(&[instruction], user, &[]).unwrap_err();
+    assert_eq!(err.err, on_chain_error(error), "{:?}", err.meta.logs);
+    assert_eq!(ctx.read_value(&gongde_pubkey), before);
+}
+
+#[test]
+fn test_argument_errors_match_program() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.increment_times(&user, 2);
+    let program_id = ctx.program_id;
+    let payer = user.pubkey();
+    let global_pubkey = ctx.global_address();
+
+    let cases = [
+        instruction::increment_by(&program_id, &gongde_pubkey, &payer, &global_pubkey, 0),
+        instruction::increment_by(&program_id, &gongde_pubkey, &payer, &global_pubkey, MAX_INCREMENT + 1),
+        instruction::shared_increment(&program_id, &payer, &global_pubkey, 0),
+        instruction::stake(&program_id, &gongde_pubkey, &payer, 0),
+        instruction::set_beneficiary(
+            &program_id,
+            &gongde_pubkey,
+            &payer,
+            Some((Keypair::new().pubkey(), MIN_BENEFICIARY_TIMEOUT_DAYS - 1)),
+        ),
+        instruction::enter_lottery(&program_id, &gongde_pubkey, &payer, &derive_lottery_address(&payer, 1, &program_id).0, 0),
+        instruction::create_lottery(
+            &program_id,
+            &payer,
+            &CreateLotteryArgs { round_id: 1, start_slot: 0, end_slot: 100, ticket_price: 0 },
+        ),
+        instruction::create_campaign(
+            &program_id,
+            &gongde_pubkey,
+            &payer,
+            &CreateCampaignArgs { campaign_id: 1, start: 60, end: 60, multiplier: 1, budget: 1 },
+        ),
+    ];
+    for instruction in cases {
+        assert_rejected_alike(&mut ctx, &user, instruction, 2);
+    }
+}
+
+#[test]
+fn test_balance_errors_match_program() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account(&alice);
+    let bob_gongde = ctx.create_gongde_account(&bob);
+    ctx.increment_times(&alice, 2);
+    let program_id = ctx.program_id;
+    let payer = alice.pubkey();
+    let current = ctx.read_value(&alice_gongde).unwrap();
+
+    let cases = [
+        instruction::transfer_merit(&program_id, &alice_gongde, &payer, &bob_gongde, 0),
+        instruction::transfer_merit(&program_id, &alice_gongde, &payer, &bob_gongde, current + 1),
+        instruction::restore_to(&program_id, &alice_gongde, &payer, u64::from(current) + 1, 0),
+        instruction::create_campaign(
+            &program_id,
+            &alice_gongde,
+            &payer,
+            &CreateCampaignArgs { campaign_id: 1, start: 0, end: 60, multiplier: 1, budget: current + 1 },
+        ),
+    ];
+    for instruction in cases {
+        assert_rejected_alike(&mut ctx, &alice, instruction, current);
+    }
+
+    // 本地检查通过的同一类指令链上也能成功
+    let transfer = instruction::transfer_merit(&program_id, &alice_gongde, &payer, &bob_gongde, current);
+    assert_eq!(instruction::validate_spend(&transfer, current), Ok(()));
+    ctx.send(&[transfer], &alice, &[]).expect("转赠功德失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(0));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(current));
+}
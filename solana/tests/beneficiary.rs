@@ -0,0 +1,176 @@
+// ========================================
+// 受益人测试 - 账户主人长期不操作后由受益人接管
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{read_authority, read_beneficiary, SECONDS_PER_DAY},
+};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use common::custom_error;
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+/// 测试使用的等待天数
+const TIMEOUT_DAYS: u16 = 30;
+
+/// 等待期的秒数
+const TIMEOUT: i64 = TIMEOUT_DAYS as i64 * SECONDS_PER_DAY;
+
+/// 创建账户主人和受益人，主人在 NOW 时刻指定受益人
+fn setup_with_beneficiary(ctx: &mut common::TestContext) -> (Keypair, Keypair) {
+    ctx.set_unix_timestamp(NOW);
+    let owner = ctx.new_user();
+    let beneficiary = ctx.new_user();
+    ctx.create_gongde_account(&owner);
+    ctx.increment_times(&owner, 3);
+
+    let instruction = ctx.set_beneficiary_instruction(&owner.pubkey(), Some((beneficiary.pubkey(), TIMEOUT_DAYS)));
+    ctx.send(&[instruction], &owner, &[]).expect("指定受益人失败");
+    (owner, beneficiary)
+}
+
+#[test]
+fn test_claim_too_early_fails() {
+    let mut ctx = common::setup();
+    let (owner, beneficiary) = setup_with_beneficiary(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&owner.pubkey());
+
+    // 正好满等待期还不行，必须超过
+    ctx.set_unix_timestamp(NOW + TIMEOUT);
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), None);
+    let err = ctx.send(&[instruction], &beneficiary, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::BeneficiaryClaimTooEarly));
+
+    // 别人冒充受益人也不行
+    ctx.set_unix_timestamp(NOW + TIMEOUT + 1);
+    let stranger = ctx.new_user();
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &stranger.pubkey(), None);
+    let err = ctx.send(&[instruction], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+}
+
+#[test]
+fn test_claim_after_timeout_takes_over_authority() {
+    let mut ctx = common::setup();
+    let (owner, beneficiary) = setup_with_beneficiary(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&owner.pubkey());
+
+    ctx.set_unix_timestamp(NOW + TIMEOUT + 1);
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), None);
+    ctx.send(&[instruction], &beneficiary, &[]).expect("超过等待期后接管应成功");
+
+    let data = ctx.svm.get_account(&gongde_pubkey).unwrap().data;
+    assert_eq!(read_authority(&data), Some(beneficiary.pubkey()));
+    assert_eq!(read_beneficiary(&data), None);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+
+    // 原来的用户不再是账户主人，接管人可以关闭账户并收回租金
+    let err = ctx.close(&owner).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    let mut instruction = ctx.close_instruction(&owner.pubkey());
+    instruction.accounts[1] = AccountMeta::new(beneficiary.pubkey(), true);
+    ctx.send(&[instruction], &beneficiary, &[]).expect("接管人关闭账户失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), None);
+}
+
+#[test]
+fn test_claim_can_merge_into_own_account() {
+    let mut ctx = common::setup();
+    let (owner, beneficiary) = setup_with_beneficiary(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&owner.pubkey());
+    let own_gongde = ctx.create_gongde_account(&beneficiary);
+    ctx.increment(&beneficiary).expect("增加功德失败");
+
+    ctx.set_unix_timestamp(NOW + TIMEOUT + 1);
+    let balance_before = ctx.svm.get_balance(&beneficiary.pubkey()).unwrap();
+    let rent = ctx.svm.get_balance(&gongde_pubkey).unwrap();
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), Some(&own_gongde));
+    ctx.send(&[instruction], &beneficiary, &[]).expect("合并接管失败");
+
+    assert_eq!(ctx.read_value(&own_gongde), Some(4));
+    assert_eq!(ctx.read_value(&gongde_pubkey), None);
+    // 被接管账户的租金退给受益人（扣除交易手续费）
+    assert!(ctx.svm.get_balance(&beneficiary.pubkey()).unwrap() > balance_before + rent - 10_000);
+}
+
+#[test]
+fn test_authority_activity_postpones_claim() {
+    let mut ctx = common::setup();
+    let (owner, beneficiary) = setup_with_beneficiary(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&owner.pubkey());
+
+    // 主人在到期前增加一次功德，等待期从那时重新计算
+    let active_at = NOW + TIMEOUT - 1;
+    ctx.set_unix_timestamp(active_at);
+    ctx.increment(&owner).expect("主人增加功德失败");
+
+    ctx.set_unix_timestamp(NOW + TIMEOUT + 1);
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), None);
+    let err = ctx.send(&[instruction], &beneficiary, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::BeneficiaryClaimTooEarly));
+
+    ctx.set_unix_timestamp(active_at + TIMEOUT + 1);
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), None);
+    ctx.send(&[instruction], &beneficiary, &[]).expect("新的等待期结束后接管应成功");
+}
+
+#[test]
+fn test_stranger_activity_does_not_postpone_claim() {
+    let mut ctx = common::setup();
+    let (owner, beneficiary) = setup_with_beneficiary(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&owner.pubkey());
+
+    // 别人往公开功德箱里增加功德不算账户主人的操作
+    let stranger = ctx.new_user();
+    ctx.set_unix_timestamp(NOW + TIMEOUT - 1);
+    ctx.increment_for(&gongde_pubkey, &stranger).expect("别人增加功德失败");
+
+    ctx.set_unix_timestamp(NOW + TIMEOUT + 1);
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), None);
+    ctx.send(&[instruction], &beneficiary, &[]).expect("别人的操作不应推迟接管");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(4));
+}
+
+#[test]
+fn test_clear_beneficiary() {
+    let mut ctx = common::setup();
+    let (owner, beneficiary) = setup_with_beneficiary(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&owner.pubkey());
+
+    let instruction = ctx.set_beneficiary_instruction(&owner.pubkey(), None);
+    ctx.send(&[instruction], &owner, &[]).expect("清除受益人失败");
+    assert_eq!(read_beneficiary(&ctx.svm.get_account(&gongde_pubkey).unwrap().data), None);
+
+    ctx.set_unix_timestamp(NOW + 10 * TIMEOUT);
+    let instruction = ctx.claim_as_beneficiary_instruction(&gongde_pubkey, &beneficiary.pubkey(), None);
+    let err = ctx.send(&[instruction], &beneficiary, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+}
+
+#[test]
+fn test_set_beneficiary_requires_authority_and_minimum_timeout() {
+    let mut ctx = common::setup();
+    let owner = ctx.new_user();
+    let stranger = ctx.new_user();
+    ctx.create_gongde_account(&owner);
+
+    // 等待天数太短
+    let instruction = ctx.set_beneficiary_instruction(&owner.pubkey(), Some((stranger.pubkey(), 1)));
+    let err = ctx.send(&[instruction], &owner, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidAmount));
+
+    // 别人不能给主人的账户指定受益人
+    let mut instruction = ctx.set_beneficiary_instruction(&owner.pubkey(), Some((stranger.pubkey(), TIMEOUT_DAYS)));
+    instruction.accounts[1] = AccountMeta::new_readonly(stranger.pubkey(), true);
+    let err = ctx.send(&[instruction], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+}
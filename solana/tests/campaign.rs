@@ -0,0 +1,152 @@
+// ========================================
+// 配捐活动测试 - 活动时间窗口、奖池扣减和奖池耗尽
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    instruction,
+    utils::CreateCampaignArgs,
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use common::TestContext;
+
+/// 测试活动的时间窗口 [START, END)
+const START: i64 = 1_000_000;
+const END: i64 = START + 3_600;
+
+fn campaign_args(multiplier: u32, budget: u32) -> CreateCampaignArgs {
+    CreateCampaignArgs { campaign_id: 1, start: START, end: END, multiplier, budget }
+}
+
+/// 创建发起人并设立奖池，返回活动地址
+fn setup_campaign(ctx: &mut TestContext, multiplier: u32, budget: u32) -> Pubkey {
+    let organizer = ctx.new_user();
+    ctx.create_gongde_account(&organizer);
+    ctx.increment_times(&organizer, budget);
+    let args = campaign_args(multiplier, budget);
+    ctx.create_campaign(&organizer, &args).expect("创建配捐活动失败");
+    ctx.campaign_address(&organizer.pubkey(), args.campaign_id)
+}
+
+/// 带上配捐活动账户增加功德
+fn increment_with_campaign(ctx: &mut TestContext, user: &Keypair, campaign: &Pubkey, amount: u64) {
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let instruction = instruction::with_campaign(ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), amount), campaign);
+    ctx.send(&[instruction], user, &[]).expect("带配捐活动的增加不应失败");
+}
+
+#[test]
+fn test_create_campaign_moves_budget_from_organizer() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    let organizer_gongde = ctx.create_gongde_account(&organizer);
+    ctx.increment_times(&organizer, 5);
+
+    // 奖池超过现有功德
+    let err = ctx.create_campaign(&organizer, &campaign_args(1, 6)).unwrap_err();
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(GongDeError::InsufficientMerit as u32))
+    );
+
+    ctx.create_campaign(&organizer, &campaign_args(1, 3)).expect("创建配捐活动失败");
+    assert_eq!(ctx.read_value(&organizer_gongde), Some(2));
+    // 划出奖池不影响累计功德
+    assert_eq!(ctx.read_lifetime_total(&organizer_gongde), Some(5));
+    let campaign = ctx.read_campaign(&ctx.campaign_address(&organizer.pubkey(), 1)).unwrap();
+    assert_eq!(campaign.organizer, organizer.pubkey());
+    assert_eq!(campaign.remaining, 3);
+
+    // 同一编号不能重复创建
+    let err = ctx.create_campaign(&organizer, &campaign_args(1, 1)).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+}
+
+#[test]
+fn test_prefunded_campaign_cannot_block_creation() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    let organizer_gongde = ctx.create_gongde_account(&organizer);
+    ctx.increment_times(&organizer, 3);
+    let campaign_pubkey = ctx.campaign_address(&organizer.pubkey(), 1);
+
+    // 有人抢先往活动地址转账，发起人仍然可以使用这个编号
+    ctx.prefund(&campaign_pubkey);
+    ctx.create_campaign(&organizer, &campaign_args(1, 3)).expect("活动地址被抢先转账后创建失败");
+
+    assert_eq!(ctx.read_value(&organizer_gongde), Some(0));
+    assert_eq!(ctx.read_campaign(&campaign_pubkey).unwrap().remaining, 3);
+    assert_eq!(ctx.svm.get_account(&campaign_pubkey).unwrap().owner, ctx.program_id);
+}
+
+#[test]
+fn test_campaign_window_boundaries() {
+    let mut ctx = common::setup();
+    let campaign = setup_campaign(&mut ctx, 1, 10);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 开始前一秒：只有普通增加
+    ctx.set_unix_timestamp(START - 1);
+    increment_with_campaign(&mut ctx, &user, &campaign, 1);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+
+    // 开始时刻和结束前一秒都在活动内
+    ctx.set_unix_timestamp(START);
+    increment_with_campaign(&mut ctx, &user, &campaign, 1);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+    ctx.set_unix_timestamp(END - 1);
+    increment_with_campaign(&mut ctx, &user, &campaign, 1);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(5));
+
+    // 结束时刻已过期，增加照常成功
+    ctx.set_unix_timestamp(END);
+    increment_with_campaign(&mut ctx, &user, &campaign, 1);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(6));
+
+    assert_eq!(ctx.read_campaign(&campaign).unwrap().remaining, 8);
+    // 配捐不计入累计功德和全局功德
+    assert_eq!(ctx.read_lifetime_total(&gongde_pubkey), Some(4));
+    assert_eq!(ctx.read_value(&ctx.global_address()), Some(14));
+}
+
+#[test]
+fn test_pool_exhausted_mid_instruction() {
+    let mut ctx = common::setup();
+    let campaign = setup_campaign(&mut ctx, 2, 3);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.set_unix_timestamp(START);
+
+    // 应配捐10点，奖池只有3点：配捐3点后奖池清零
+    increment_with_campaign(&mut ctx, &user, &campaign, 5);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(8));
+    assert_eq!(ctx.read_campaign(&campaign).unwrap().remaining, 0);
+
+    // 奖池耗尽后只做普通增加
+    increment_with_campaign(&mut ctx, &user, &campaign, 1);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(9));
+}
+
+#[test]
+fn test_non_campaign_account_rejected() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let other = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    let other_gongde = ctx.create_gongde_account(&other);
+
+    // 把别人的功德账户冒充活动账户
+    let instruction = instruction::with_campaign(ctx.increment_instruction(&gongde_pubkey, &user.pubkey()), &other_gongde);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+}
@@ -0,0 +1,170 @@
+// ========================================
+// 指令编码兼容性测试 - 多余字节、客户端版本字段和最低客户端版本
+// SDK 构建的指令带客户端版本并附上全局配置，旧客户端的编码由 common::legacy_encoding 转换得到
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    instruction,
+    utils::{stamp_client_version, GongDeInstruction, CLIENT_VERSION},
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+};
+
+use common::instruction_error;
+
+/// SDK 构建的给自己增加功德指令
+fn sdk_increment(ctx: &common::TestContext, user: &Keypair) -> Instruction {
+    instruction::increment(&ctx.program_id, &ctx.gongde_address(&user.pubkey()), &user.pubkey(), &ctx.global_address())
+}
+
+/// 旧客户端编码的给自己增加功德指令
+fn legacy_increment(ctx: &common::TestContext, user: &Keypair) -> Instruction {
+    common::legacy_encoding(&sdk_increment(ctx, user))
+}
+
+#[test]
+fn test_trailing_bytes_rejected() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 旧编码：参数之后多一个字节
+    let mut instruction = common::legacy_encoding(&ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 3));
+    instruction.data.push(0);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidInstructionData));
+    assert!(common::logged(&err.meta.logs, "指令数据解析失败，收到 {} 字节", &[10]), "{:?}", err.meta.logs);
+
+    // 带版本的编码同样不允许多余的字节，日志中是包含版本字段的完整长度
+    let mut instruction = sdk_increment(&ctx, &user);
+    instruction.data.extend_from_slice(&[0xAA, 0xBB]);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidInstructionData));
+    assert!(common::logged(&err.meta.logs, "指令数据解析失败，收到 {} 字节", &[5]), "{:?}", err.meta.logs);
+
+    // 参数缺少字节也在长度检查时失败
+    let mut instruction = common::legacy_encoding(&ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 3));
+    instruction.data.truncate(4);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidInstructionData));
+
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+}
+
+#[test]
+fn test_version_gate_rejects_older_client() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.set_minimum_client_version(CLIENT_VERSION + 1);
+
+    let err = ctx.send(&[sdk_increment(&ctx, &user)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::Custom(GongDeError::ClientVersionTooOld as u32)));
+    assert!(err.meta.logs.iter().any(|log| log.contains("请升级客户端")), "{:?}", err.meta.logs);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+
+    // 版本查询不受限制，旧客户端也能查到合约信息
+    let instruction = Instruction {
+        data: stamp_client_version(&[GongDeInstruction::ProtocolVersion as u8], CLIENT_VERSION),
+        accounts: vec![AccountMeta::new_readonly(ctx.config_address(), false)],
+        program_id: ctx.program_id,
+    };
+    ctx.send(&[instruction], &user, &[]).expect("版本查询不应受最低客户端版本限制");
+}
+
+#[test]
+fn test_version_gate_accepts_current_client() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 全局配置还没有设置过
+    ctx.send(&[sdk_increment(&ctx, &user)], &user, &[]).expect("未设置最低版本时增加功德失败");
+
+    // 版本等于或高于最低版本都可以
+    ctx.set_minimum_client_version(CLIENT_VERSION);
+    ctx.send(&[sdk_increment(&ctx, &user)], &user, &[]).expect("当前版本增加功德失败");
+    let mut instruction = sdk_increment(&ctx, &user);
+    instruction.data[1] = CLIENT_VERSION + 1;
+    ctx.send(&[instruction], &user, &[]).expect("更新的版本增加功德失败");
+
+    // 旧编码不带版本字段，视为版本0，低于最低版本
+    let err = ctx.send(&[legacy_increment(&ctx, &user)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::Custom(GongDeError::ClientVersionTooOld as u32)));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+}
+
+#[test]
+fn test_version_gate_rejects_legacy_encoding() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.send(&[legacy_increment(&ctx, &user)], &user, &[]).expect("未设置最低版本时旧编码增加功德失败");
+
+    // 旧编码不附带全局配置，合约从全局功德PDA中的配置副本读取最低版本
+    ctx.set_minimum_client_version(1);
+    let err = ctx.send(&[legacy_increment(&ctx, &user)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::Custom(GongDeError::ClientVersionTooOld as u32)));
+    assert!(common::logged(&err.meta.logs, "客户端版本 {} 低于合约要求的最低版本 {}，请升级客户端后重试", &[0, 1]), "{:?}", err.meta.logs);
+    let shared = common::legacy_encoding(&ctx.shared_increment_instruction(&user.pubkey(), 1));
+    let err = ctx.send(&[shared], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::Custom(GongDeError::ClientVersionTooOld as u32)));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+
+    // 取消限制后旧编码恢复可用
+    ctx.set_minimum_client_version(0);
+    ctx.send(&[legacy_increment(&ctx, &user)], &user, &[]).expect("取消最低版本后旧编码增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+}
+
+#[test]
+fn test_version_gate_exempts_legacy_transfer() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    ctx.increment_times(&sender, 3);
+    ctx.set_minimum_client_version(CLIENT_VERSION + 1);
+
+    // 带版本的转赠读到全局配置，低于最低版本被拒绝
+    let instruction = ctx.transfer_merit_instruction(&sender.pubkey(), &recipient_gongde, 1);
+    let err = ctx.send(&[instruction], &sender, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::Custom(GongDeError::ClientVersionTooOld as u32)));
+
+    // 旧编码的转赠不带全局功德PDA，没有可读的配置，不受最低版本限制
+    let instruction = common::legacy_encoding(&ctx.transfer_merit_instruction(&sender.pubkey(), &recipient_gongde, 1));
+    ctx.send(&[instruction], &sender, &[]).expect("旧编码的转赠不受最低客户端版本限制");
+    assert_eq!(ctx.read_value(&sender_gongde), Some(2));
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(1));
+}
+
+#[test]
+fn test_versioned_instruction_requires_config() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 去掉末尾的全局配置，最后一个账户变成了系统程序
+    let mut instruction = sdk_increment(&ctx, &user);
+    instruction.accounts.pop();
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidSeeds));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+}
+
+#[test]
+fn test_set_minimum_client_version_requires_creator() {
+    let mut ctx = common::setup();
+    let stranger = ctx.new_user();
+
+    let instruction = instruction::set_minimum_client_version(&ctx.program_id, &stranger.pubkey(), &ctx.global_address(), CLIENT_VERSION + 1);
+    let err = ctx.send(&[instruction], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::IllegalOwner));
+    assert_eq!(ctx.svm.get_account(&ctx.config_address()).map_or(0, |account| account.lamports), 0);
+}
@@ -0,0 +1,151 @@
+// ========================================
+// 确定性测试数据 - 固定种子生成用户和脚本化操作
+// 测试和 examples/fixtures.rs 共用，保证本地链和LiteSVM得到完全相同的状态
+// ========================================
+
+use gong_de_increase::utils::endorsement_weight;
+use solana_sdk::{
+    hash::hashv,
+    signature::Keypair,
+    signer::keypair::keypair_from_seed,
+};
+
+/// 生成夹具用户密钥对的种子前缀，修改后所有夹具地址都会变化
+pub const FIXTURE_SEED: &str = "GongDeFixture";
+
+/// 夹具用户数量
+pub const FIXTURE_USER_COUNT: usize = 5;
+
+/// 脚本执行完后全局功德的增量（Increment 和 IncrementBy 增加的功德之和，转赠、背书和回滚不计入）
+pub const EXPECTED_GLOBAL_TOTAL: u32 = 34;
+
+/// 脚本执行完后每个用户的功德值，None 表示账户已被关闭
+pub const EXPECTED_USER_COUNTS: [Option<u32>; FIXTURE_USER_COUNT] =
+    [Some(6), Some(2), Some(4), None, Some(20)];
+
+/// 脚本执行完后每个用户的账户是否已归档
+pub const EXPECTED_ARCHIVED: [bool; FIXTURE_USER_COUNT] = [false, false, true, false, false];
+
+/// 脚本中设置的头像emoji
+pub const FIXTURE_EMOJI: char = '🪷';
+
+/// 夹具脚本中的单步操作，操作对象用用户下标表示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureOp {
+    /// 增加一次功德
+    Increment,
+    /// 一次增加多点功德
+    IncrementBy(u64),
+    /// 转赠功德给另一个用户
+    Transfer { to: usize, amount: u32 },
+    /// 给另一个用户的功德账户背书
+    Endorse(usize),
+    /// 把功德值回滚到指定值
+    RestoreTo(u64),
+    /// 设置头像emoji
+    SetEmoji(char),
+    /// 归档账户
+    Archive,
+    /// 关闭账户并回收租金
+    Close,
+}
+
+/// 按顺序执行的夹具脚本：(用户下标, 操作)
+/// 用户3增加一次后关闭，用于覆盖"账户已关闭"的状态；
+/// 之后用户4转赠和背书，用户1回滚，用户0设置头像，用户2归档，覆盖排行榜和快照需要的各种状态
+pub const FIXTURE_SCRIPT: &[(usize, FixtureOp)] = &[
+    (0, FixtureOp::Increment),
+    (1, FixtureOp::Increment),
+    (2, FixtureOp::Increment),
+    (3, FixtureOp::Increment),
+    (4, FixtureOp::Increment),
+    (0, FixtureOp::Increment),
+    (2, FixtureOp::Increment),
+    (4, FixtureOp::Increment),
+    (3, FixtureOp::Close),
+    (0, FixtureOp::Increment),
+    (2, FixtureOp::Increment),
+    (4, FixtureOp::Increment),
+    (2, FixtureOp::Increment),
+    (4, FixtureOp::Increment),
+    (4, FixtureOp::Increment),
+    (4, FixtureOp::IncrementBy(20)),
+    (4, FixtureOp::Transfer { to: 1, amount: 5 }),
+    (4, FixtureOp::Endorse(0)),
+    (2, FixtureOp::Endorse(0)),
+    (1, FixtureOp::RestoreTo(2)),
+    (0, FixtureOp::SetEmoji(FIXTURE_EMOJI)),
+    (2, FixtureOp::Archive),
+];
+
+/// 根据下标生成确定性的用户密钥对
+///
+/// # 参数
+/// * `index` - 用户下标
+///
+/// # 返回
+/// * `Keypair` - 同一下标每次生成的密钥对都相同
+pub fn fixture_keypair(index: usize) -> Keypair {
+    let seed = hashv(&[FIXTURE_SEED.as_bytes(), &(index as u64).to_le_bytes()]);
+    keypair_from_seed(seed.as_ref()).expect("32字节种子必然可以生成密钥对")
+}
+
+/// 生成全部夹具用户
+pub fn fixture_users() -> Vec<Keypair> {
+    (0..FIXTURE_USER_COUNT).map(fixture_keypair).collect()
+}
+
+/// 重放脚本得到的预期状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedState {
+    /// 每个用户的功德值，None 表示账户已被关闭
+    pub counts: Vec<Option<u32>>,
+    /// 每个用户的账户是否已归档
+    pub archived: Vec<bool>,
+    /// 每个用户的头像emoji
+    pub emojis: Vec<Option<char>>,
+    /// 全局功德增量
+    pub global_total: u32,
+}
+
+/// 重放脚本计算预期状态，用来校验文档中写死的预期值没有过期
+/// 计算规则与合约一致：背书按背书人当时的功德计算权重，转赠、背书和回滚不改变全局功德
+pub fn replay_expected_state() -> ExpectedState {
+    let mut state = ExpectedState {
+        counts: vec![Some(0u32); FIXTURE_USER_COUNT],
+        archived: vec![false; FIXTURE_USER_COUNT],
+        emojis: vec![None; FIXTURE_USER_COUNT],
+        global_total: 0,
+    };
+    let value = |counts: &[Option<u32>], user: usize| counts[user].expect("脚本不能操作已关闭的账户");
+
+    for &(user, op) in FIXTURE_SCRIPT {
+        match op {
+            FixtureOp::Increment => {
+                state.counts[user] = Some(value(&state.counts, user) + 1);
+                state.global_total += 1;
+            }
+            FixtureOp::IncrementBy(amount) => {
+                state.counts[user] = Some(value(&state.counts, user) + amount as u32);
+                state.global_total += amount as u32;
+            }
+            FixtureOp::Transfer { to, amount } => {
+                state.counts[user] = Some(value(&state.counts, user) - amount);
+                state.counts[to] = Some(value(&state.counts, to) + amount);
+            }
+            FixtureOp::Endorse(to) => {
+                let weight = endorsement_weight(value(&state.counts, user));
+                state.counts[to] = Some(value(&state.counts, to) + weight);
+            }
+            FixtureOp::RestoreTo(restored) => {
+                assert!(restored as u32 <= value(&state.counts, user), "回滚只能调低功德");
+                state.counts[user] = Some(restored as u32);
+            }
+            FixtureOp::SetEmoji(emoji) => state.emojis[user] = Some(emoji),
+            FixtureOp::Archive => state.archived[user] = true,
+            FixtureOp::Close => state.counts[user] = None,
+        }
+    }
+
+    state
+}
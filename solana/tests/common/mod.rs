@@ -0,0 +1,682 @@
+// ========================================
+// 集成测试公共模块 - 基于LiteSVM的测试环境
+// 运行前需要先执行 cargo build-sbf 生成 target/deploy/gong_de_increase.so
+// 指令全部由 gong_de_increase::instruction 的 SDK 构建函数生成，需要旧客户端的编码时用 legacy_encoding 转换
+// ========================================
+
+// LiteSVM 的 TransactionResult 失败时带着完整的交易元数据，测试辅助函数原样返回它
+#![allow(dead_code, clippy::result_large_err)]
+
+pub mod fixtures;
+pub mod scenario;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use solana_system_interface::instruction as system_instruction;
+
+use gong_de_increase::error::GongDeError;
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{
+    derive_accrual_state_address,
+    derive_campaign_address,
+    derive_config_address,
+    derive_endorsement_address,
+    derive_global_gongde_pda_address,
+    derive_gongde_account_address,
+    derive_lottery_address,
+    derive_shared_ledger_address,
+    derive_transfer_state_address,
+    gongde_account_data,
+    read_gongde_value,
+    read_lifetime_total,
+    read_milestones,
+    shared_ledger_get,
+    Campaign,
+    CreateCampaignArgs,
+    CreateLotteryArgs,
+    GlobalConfig,
+    LotteryRound,
+    Milestone,
+    StakeState,
+    GONGDE_ACCOUNT_SEED,
+    CONFIG_SIZE,
+    GLOBAL_CONFIG_COPY_OFFSET,
+    GLOBAL_PDA_WITH_CONFIG_SIZE,
+    GONGDE_ACCOUNT_SIZE,
+};
+use fixtures::{fixture_users, FixtureOp, FIXTURE_SCRIPT};
+
+/// 编译后的合约文件路径
+pub const PROGRAM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy/gong_de_increase.so");
+
+/// 每个测试用户的初始资金
+pub const USER_AIRDROP: u64 = LAMPORTS_PER_SOL;
+
+/// 交易中第一条指令返回合约自定义错误时的交易错误
+pub fn custom_error(error: GongDeError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+}
+
+/// 交易中第一条指令返回的错误
+pub fn instruction_error(error: InstructionError) -> TransactionError {
+    TransactionError::InstructionError(0, error)
+}
+
+/// 去掉版本字段和末尾的全局配置，把 SDK 构建的指令还原成旧客户端的编码
+pub fn legacy_encoding(instruction: &Instruction) -> Instruction {
+    instruction::unwrap_client_version(instruction).expect("SDK 构建的指令最后一个账户应为全局配置").1
+}
+
+/// 解析交易日志中 sol_log_data 写入的 "Program data:" 记录
+///
+/// # 返回
+/// * `Vec<Vec<Vec<u8>>>` - 每条记录解码后的各段数据
+pub fn program_data(logs: &[String]) -> Vec<Vec<Vec<u8>>> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .map(|fields| {
+            fields
+                .split(' ')
+                .map(|field| BASE64_STANDARD.decode(field).expect("Program data 不是有效的base64"))
+                .collect()
+        })
+        .collect()
+}
+
+/// 在交易日志中查找合约用 log_msg! 输出的一条记录：模板原文后面依次是每个数值（sol_log_64 输出的最后一列）
+///
+/// # 返回
+/// * `bool` - 找到模板并且后面的数值全部对应时返回true
+pub fn logged(logs: &[String], template: &str, values: &[u64]) -> bool {
+    let template = format!("Program log: {}", template);
+    logs.iter().enumerate().filter(|(_, log)| **log == template).any(|(index, _)| {
+        let following = &logs[index + 1..];
+        following.len() >= values.len()
+            && values
+                .iter()
+                .zip(following)
+                .all(|(value, log)| log.starts_with("Program log: ") && log.ends_with(&format!(", {:#x}", value)))
+    })
+}
+
+/// 测试环境：虚拟机 + 已部署的合约ID
+pub struct TestContext {
+    pub svm: LiteSVM,
+    pub program_id: Pubkey,
+}
+
+/// 创建测试环境并部署合约
+pub fn setup() -> TestContext {
+    let mut svm = LiteSVM::new();
+    let program_id = Pubkey::new_unique();
+    svm.add_program_from_file(program_id, PROGRAM_PATH)
+        .expect("未找到合约文件，请先运行 cargo build-sbf");
+    TestContext { svm, program_id }
+}
+
+impl TestContext {
+    /// 把同一份合约再部署到一个新的程序ID，模拟新旧版本并存；切换版本时修改 program_id 即可
+    pub fn deploy_another_version(&mut self) -> Pubkey {
+        let program_id = Pubkey::new_unique();
+        self.svm.add_program_from_file(program_id, PROGRAM_PATH)
+            .expect("未找到合约文件，请先运行 cargo build-sbf");
+        program_id
+    }
+
+    /// 创建一个有初始资金的新用户
+    pub fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.fund(&user.pubkey());
+        user
+    }
+
+    /// 给指定地址空投测试资金
+    pub fn fund(&mut self, pubkey: &Pubkey) {
+        self.svm.airdrop(pubkey, USER_AIRDROP).expect("空投失败");
+    }
+
+    /// 模拟抢先转账：另一个用户往还不存在的账户地址转入免租金额，地址上出现一个系统程序所有的空账户
+    pub fn prefund(&mut self, address: &Pubkey) {
+        let attacker = self.new_user();
+        let prefund = self.svm.minimum_balance_for_rent_exemption(0);
+        let transfer = system_instruction::transfer(&attacker.pubkey(), address, prefund);
+        self.send(&[transfer], &attacker, &[]).expect("抢先转账失败");
+    }
+
+    /// 用户功德账户地址
+    pub fn gongde_address(&self, user: &Pubkey) -> Pubkey {
+        derive_gongde_account_address(user, &self.program_id).unwrap()
+    }
+
+    /// 全局功德PDA地址
+    pub fn global_address(&self) -> Pubkey {
+        derive_global_gongde_pda_address(&self.program_id).unwrap().0
+    }
+
+    /// 用户的转赠状态PDA地址
+    pub fn transfer_state_address(&self, sender: &Pubkey) -> Pubkey {
+        derive_transfer_state_address(sender, &self.program_id).0
+    }
+
+    /// 发起人的配捐活动PDA地址
+    pub fn campaign_address(&self, organizer: &Pubkey, campaign_id: u64) -> Pubkey {
+        derive_campaign_address(organizer, campaign_id, &self.program_id).0
+    }
+
+    /// 彩票轮次PDA地址
+    pub fn lottery_address(&self, admin: &Pubkey, round_id: u64) -> Pubkey {
+        derive_lottery_address(admin, round_id, &self.program_id).0
+    }
+
+    /// 共享账本PDA地址
+    pub fn shared_ledger_address(&self) -> Pubkey {
+        derive_shared_ledger_address(&self.program_id).0
+    }
+
+    /// 全局配置PDA地址
+    pub fn config_address(&self) -> Pubkey {
+        derive_config_address(&self.program_id).0
+    }
+
+    /// 直接写入全局配置中的最低客户端版本（SetMinimumClientVersion 只有创作者能签名，测试中无法发送）
+    pub fn set_minimum_client_version(&mut self, minimum_client_version: u8) {
+        self.set_global_config(GlobalConfig { minimum_client_version, ..GlobalConfig::default() });
+    }
+
+    /// 直接写入全局配置中的工作量证明难度（SetPowDifficulty 同样只有创作者能签名）
+    pub fn set_pow_difficulty(&mut self, pow_difficulty: u8) {
+        self.set_global_config(GlobalConfig { pow_difficulty, ..GlobalConfig::default() });
+    }
+
+    /// 直接写入整个全局配置，和合约一样在全局功德PDA中同步写入副本，全局功德值保持不变
+    pub fn set_global_config(&mut self, config: GlobalConfig) {
+        let mut data = vec![0; CONFIG_SIZE];
+        config.pack(&mut data).unwrap();
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(CONFIG_SIZE),
+            data,
+            owner: self.program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(self.config_address(), account).expect("写入全局配置失败");
+
+        let global = self.svm.get_account(&self.global_address());
+        let global_value = global.as_ref().map_or(0, |account| read_gongde_value(&account.data).unwrap());
+        let global_lamports = global.map_or(0, |account| account.lamports);
+        let mut data = vec![0; GLOBAL_PDA_WITH_CONFIG_SIZE];
+        data[..GLOBAL_CONFIG_COPY_OFFSET].copy_from_slice(&global_value.to_le_bytes());
+        config.pack(&mut data[GLOBAL_CONFIG_COPY_OFFSET..]).unwrap();
+        let account = Account {
+            lamports: global_lamports.max(self.svm.minimum_balance_for_rent_exemption(GLOBAL_PDA_WITH_CONFIG_SIZE)),
+            data,
+            owner: self.program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(self.global_address(), account).expect("写入全局配置副本失败");
+    }
+
+    /// 设置链上时钟的Unix时间戳，用于测试按天计算的逻辑
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.svm.get_sysvar();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// 设置链上时钟的slot，用于测试按slot计算的质押功德
+    pub fn set_slot(&mut self, slot: u64) {
+        let mut clock: Clock = self.svm.get_sysvar();
+        clock.slot = slot;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// 发送交易，每次发送前刷新blockhash，避免相同交易被判定为重复
+    pub fn send(&mut self, instructions: &[Instruction], payer: &Keypair, signers: &[&Keypair]) -> TransactionResult {
+        self.svm.expire_blockhash();
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(transaction)
+    }
+
+    /// 创建并初始化用户的公开功德账户
+    pub fn create_gongde_account(&mut self, user: &Keypair) -> Pubkey {
+        self.create_gongde_account_with(user, true)
+    }
+
+    /// 创建并初始化用户的功德账户，可指定是否公开
+    pub fn create_gongde_account_with(&mut self, user: &Keypair, is_public: bool) -> Pubkey {
+        let instructions = self.create_gongde_account_instructions(&user.pubkey(), is_public);
+        self.send(&instructions, user, &[]).expect("创建功德账户失败");
+        self.gongde_address(&user.pubkey())
+    }
+
+    /// 构建创建并初始化功德账户的两条指令，由用户自己付款
+    pub fn create_gongde_account_instructions(&self, user: &Pubkey, is_public: bool) -> [Instruction; 2] {
+        self.sponsored_create_instructions(user, user, is_public)
+    }
+
+    /// 构建由他人代付租金创建并初始化功德账户的两条指令，用户仍需签名
+    pub fn sponsored_create_instructions(&self, sponsor: &Pubkey, user: &Pubkey, is_public: bool) -> [Instruction; 2] {
+        let gongde_pubkey = self.gongde_address(user);
+        let rent = self.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+        let create_instruction = system_instruction::create_account_with_seed(
+            sponsor,
+            &gongde_pubkey,
+            user,
+            GONGDE_ACCOUNT_SEED,
+            rent,
+            GONGDE_ACCOUNT_SIZE as u64,
+            &self.program_id,
+        );
+        [create_instruction, self.initialize_instruction(user, is_public)]
+    }
+
+    /// 构建初始化指令
+    pub fn initialize_instruction(&self, user: &Pubkey, is_public: bool) -> Instruction {
+        instruction::initialize(&self.program_id, &self.gongde_address(user), user, is_public)
+    }
+
+    /// 构建增加功德指令，由 payer 支付手续费给 gongde_pubkey 增加功德
+    pub fn increment_instruction(&self, gongde_pubkey: &Pubkey, payer: &Pubkey) -> Instruction {
+        instruction::increment(&self.program_id, gongde_pubkey, payer, &self.global_address())
+    }
+
+    /// 构建附上账户主人的增加功德指令，功德账户不存在时合约先创建再增加
+    pub fn increment_creating_instruction(&self, user: &Pubkey) -> Instruction {
+        instruction::with_create_if_missing(self.increment_instruction(&self.gongde_address(user), user), user)
+    }
+
+    /// 构建一次增加多点功德的指令
+    pub fn increment_by_instruction(&self, gongde_pubkey: &Pubkey, payer: &Pubkey, amount: u64) -> Instruction {
+        instruction::increment_by(&self.program_id, gongde_pubkey, payer, &self.global_address(), amount)
+    }
+
+    /// 构建关闭账户指令
+    pub fn close_instruction(&self, user: &Pubkey) -> Instruction {
+        instruction::close(&self.program_id, &self.gongde_address(user), user)
+    }
+
+    /// 构建"空账户才关闭"指令
+    pub fn close_if_empty_instruction(&self, user: &Pubkey) -> Instruction {
+        instruction::close_if_empty(&self.program_id, &self.gongde_address(user), user)
+    }
+
+    /// 在用户的地址上直接写入旧的4字节账户，模拟布局升级前创建的账户
+    pub fn create_legacy_account(&mut self, user: &Pubkey, value: u32) -> Pubkey {
+        self.create_legacy_account_with_data(user, value.to_le_bytes().to_vec())
+    }
+
+    /// 在用户的地址上直接写入任意旧布局的账户数据，租金按数据大小刚好免租
+    pub fn create_legacy_account_with_data(&mut self, user: &Pubkey, data: Vec<u8>) -> Pubkey {
+        let gongde_pubkey = self.gongde_address(user);
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: self.program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(gongde_pubkey, account).expect("写入旧账户失败");
+        gongde_pubkey
+    }
+
+    /// 在用户的地址上直接写入最新布局、已初始化的私人功德账户，不用发交易就能预置功德；
+    /// 数据与 program_test::add_gongde_account 写入 ProgramTest 的一致
+    pub fn seed_gongde_account(&mut self, user: &Pubkey, value: u32) -> Pubkey {
+        self.create_legacy_account_with_data(user, gongde_account_data(value, false))
+    }
+
+    /// 构建迁移指令，owner 是派生出功德账户地址的用户（不需要签名），payer 支付补足的租金
+    pub fn migrate_instruction(&self, gongde_pubkey: &Pubkey, owner: &Pubkey, payer: &Pubkey) -> Instruction {
+        instruction::migrate(&self.program_id, gongde_pubkey, owner, payer)
+    }
+
+    /// 构建回滚指令，把用户的功德值恢复到 checkpoint 时记录的 value
+    pub fn restore_to_instruction(&self, user: &Pubkey, value: u64, checkpoint: i64) -> Instruction {
+        instruction::restore_to(&self.program_id, &self.gongde_address(user), user, value, checkpoint)
+    }
+
+    /// 构建创建配捐活动指令，奖池从 organizer 的功德中扣除
+    pub fn create_campaign_instruction(&self, organizer: &Pubkey, args: &CreateCampaignArgs) -> Instruction {
+        instruction::create_campaign(&self.program_id, &self.gongde_address(organizer), organizer, args)
+    }
+
+    /// organizer 创建配捐活动
+    pub fn create_campaign(&mut self, organizer: &Keypair, args: &CreateCampaignArgs) -> TransactionResult {
+        let instruction = self.create_campaign_instruction(&organizer.pubkey(), args);
+        self.send(&[instruction], organizer, &[])
+    }
+
+    /// 读取配捐活动，账户不存在时返回None
+    pub fn read_campaign(&self, pubkey: &Pubkey) -> Option<Campaign> {
+        self.svm
+            .get_account(pubkey)
+            .filter(|account| account.lamports > 0)
+            .and_then(|account| Campaign::unpack(&account.data).ok())
+    }
+
+    /// 构建共享账本增加功德指令，user 给自己在共享账本中的条目增加功德
+    pub fn shared_increment_instruction(&self, user: &Pubkey, amount: u64) -> Instruction {
+        instruction::shared_increment(&self.program_id, user, &self.global_address(), amount)
+    }
+
+    /// user 在共享账本中给自己增加功德
+    pub fn shared_increment(&mut self, user: &Keypair, amount: u64) -> TransactionResult {
+        let instruction = self.shared_increment_instruction(&user.pubkey(), amount);
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 读取用户在共享账本中的功德，账本不存在或用户不在账本中时返回None
+    pub fn read_shared_value(&self, user: &Pubkey) -> Option<u32> {
+        self.svm
+            .get_account(&self.shared_ledger_address())
+            .and_then(|account| shared_ledger_get(&account.data, user).ok().flatten())
+    }
+
+    /// 构建版本查询指令，不需要任何账户
+    pub fn protocol_version_instruction(&self) -> Instruction {
+        instruction::protocol_version(&self.program_id)
+    }
+
+    /// 构建转赠功德指令，sender 把自己的功德转给 recipient_gongde
+    pub fn transfer_merit_instruction(&self, sender: &Pubkey, recipient_gongde: &Pubkey, amount: u32) -> Instruction {
+        instruction::transfer_merit(&self.program_id, &self.gongde_address(sender), sender, recipient_gongde, amount)
+    }
+
+    /// sender 转赠功德给 recipient_gongde
+    pub fn transfer_merit(&mut self, sender: &Keypair, recipient_gongde: &Pubkey, amount: u32) -> TransactionResult {
+        let instruction = self.transfer_merit_instruction(&sender.pubkey(), recipient_gongde, amount);
+        self.send(&[instruction], sender, &[])
+    }
+
+    /// 构建指定受益人指令，beneficiary 为None时清除
+    pub fn set_beneficiary_instruction(&self, user: &Pubkey, beneficiary: Option<(Pubkey, u16)>) -> Instruction {
+        instruction::set_beneficiary(&self.program_id, &self.gongde_address(user), user, beneficiary)
+    }
+
+    /// 构建受益人接管 gongde_pubkey 的指令，传入 merge_into 时把功德合并到该账户
+    pub fn claim_as_beneficiary_instruction(
+        &self,
+        gongde_pubkey: &Pubkey,
+        beneficiary: &Pubkey,
+        merge_into: Option<&Pubkey>,
+    ) -> Instruction {
+        instruction::claim_as_beneficiary(&self.program_id, gongde_pubkey, beneficiary, merge_into)
+    }
+
+    /// endorser 给 endorsee_gongde 背书的背书记录地址
+    pub fn endorsement_address(&self, endorser: &Pubkey, endorsee_gongde: &Pubkey) -> Pubkey {
+        derive_endorsement_address(endorser, endorsee_gongde, &self.program_id).0
+    }
+
+    /// 构建背书指令，背书人的功德账户是自己的派生账户
+    pub fn endorse_instruction(&self, endorser: &Pubkey, endorsee_gongde: &Pubkey) -> Instruction {
+        instruction::endorse(&self.program_id, endorsee_gongde, endorser, &self.gongde_address(endorser))
+    }
+
+    /// endorser 给 endorsee_gongde 背书
+    pub fn endorse(&mut self, endorser: &Keypair, endorsee_gongde: &Pubkey) -> TransactionResult {
+        let instruction = self.endorse_instruction(&endorser.pubkey(), endorsee_gongde);
+        self.send(&[instruction], endorser, &[])
+    }
+
+    /// 构建批量背书指令，每个目标依次是功德账户和背书记录，目标数量为1到 MAX_ENDORSE_MANY_TARGETS 个
+    pub fn endorse_many_instruction(&self, endorser: &Pubkey, endorsee_gongdes: &[Pubkey]) -> Instruction {
+        instruction::endorse_many(&self.program_id, endorser, &self.gongde_address(endorser), endorsee_gongdes)
+    }
+
+    /// 功德账户的挂机功德状态地址
+    pub fn accrual_state_address(&self, gongde_pubkey: &Pubkey) -> Pubkey {
+        derive_accrual_state_address(gongde_pubkey, &self.program_id).0
+    }
+
+    /// 构建结算挂机功德指令
+    pub fn accrue_instruction(&self, user: &Pubkey) -> Instruction {
+        instruction::accrue(&self.program_id, &self.gongde_address(user), user)
+    }
+
+    /// 用户结算自己的挂机功德
+    pub fn accrue(&mut self, user: &Keypair) -> TransactionResult {
+        let instruction = self.accrue_instruction(&user.pubkey());
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 构建质押指令
+    pub fn stake_instruction(&self, user: &Pubkey, lamports: u64) -> Instruction {
+        instruction::stake(&self.program_id, &self.gongde_address(user), user, lamports)
+    }
+
+    /// 用户把 SOL 质押到自己的功德账户
+    pub fn stake(&mut self, user: &Keypair, lamports: u64) -> TransactionResult {
+        let instruction = self.stake_instruction(&user.pubkey(), lamports);
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 构建结算质押功德指令
+    pub fn claim_accrued_instruction(&self, user: &Pubkey) -> Instruction {
+        instruction::claim_accrued(&self.program_id, &self.gongde_address(user), user)
+    }
+
+    /// 用户结算自己质押积累的功德
+    pub fn claim_accrued(&mut self, user: &Keypair) -> TransactionResult {
+        let instruction = self.claim_accrued_instruction(&user.pubkey());
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 构建取回质押指令
+    pub fn unstake_instruction(&self, user: &Pubkey, lamports: u64) -> Instruction {
+        instruction::unstake(&self.program_id, &self.gongde_address(user), user, lamports)
+    }
+
+    /// 用户取回质押的 SOL
+    pub fn unstake(&mut self, user: &Keypair, lamports: u64) -> TransactionResult {
+        let instruction = self.unstake_instruction(&user.pubkey(), lamports);
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 读取功德账户中的质押状态，账户不存在或还没有迁移时返回None
+    pub fn read_stake(&self, pubkey: &Pubkey) -> Option<StakeState> {
+        self.svm
+            .get_account(pubkey)
+            .filter(|account| account.lamports > 0)
+            .and_then(|account| StakeState::unpack(&account.data).ok())
+    }
+
+    /// 构建创建彩票轮次指令
+    pub fn create_lottery_instruction(&self, admin: &Pubkey, args: &CreateLotteryArgs) -> Instruction {
+        instruction::create_lottery(&self.program_id, admin, args)
+    }
+
+    /// 管理员创建一轮彩票
+    pub fn create_lottery(&mut self, admin: &Keypair, args: &CreateLotteryArgs) -> TransactionResult {
+        let instruction = self.create_lottery_instruction(&admin.pubkey(), args);
+        self.send(&[instruction], admin, &[])
+    }
+
+    /// 构建买彩票指令
+    pub fn enter_lottery_instruction(&self, player: &Pubkey, lottery: &Pubkey, tickets: u16) -> Instruction {
+        instruction::enter_lottery(&self.program_id, &self.gongde_address(player), player, lottery, tickets)
+    }
+
+    /// 用户花自己的功德买彩票
+    pub fn enter_lottery(&mut self, player: &Keypair, lottery: &Pubkey, tickets: u16) -> TransactionResult {
+        let instruction = self.enter_lottery_instruction(&player.pubkey(), lottery, tickets);
+        self.send(&[instruction], player, &[])
+    }
+
+    /// 构建开奖指令，winner_gongde 为中奖者的功德账户，没有人投注时可以省略
+    pub fn settle_lottery_instruction(&self, lottery: &Pubkey, winner_gongde: Option<&Pubkey>) -> Instruction {
+        instruction::settle_lottery(&self.program_id, lottery, winner_gongde)
+    }
+
+    /// 任何人都可以开奖
+    pub fn settle_lottery(
+        &mut self,
+        settler: &Keypair,
+        lottery: &Pubkey,
+        winner_gongde: Option<&Pubkey>,
+    ) -> TransactionResult {
+        let instruction = self.settle_lottery_instruction(lottery, winner_gongde);
+        self.send(&[instruction], settler, &[])
+    }
+
+    /// 结束slot的哈希过期后开奖会作废本轮，给传入的投注功德账户退还票款；作废之后可以继续调用退款
+    pub fn refund_lottery(&mut self, settler: &Keypair, lottery: &Pubkey, entrant_gongdes: &[Pubkey]) -> TransactionResult {
+        let instruction = instruction::refund_lottery(&self.program_id, lottery, entrant_gongdes);
+        self.send(&[instruction], settler, &[])
+    }
+
+    /// 读取彩票轮次，账户不存在时返回None
+    pub fn read_lottery(&self, pubkey: &Pubkey) -> Option<LotteryRound> {
+        self.svm.get_account(pubkey).and_then(|account| LotteryRound::unpack(&account.data).ok())
+    }
+
+    /// 构建归档或取消归档指令
+    pub fn set_archived_instruction(&self, user: &Pubkey, archived: bool) -> Instruction {
+        let gongde_pubkey = self.gongde_address(user);
+        if archived {
+            instruction::archive(&self.program_id, &gongde_pubkey, user)
+        } else {
+            instruction::unarchive(&self.program_id, &gongde_pubkey, user)
+        }
+    }
+
+    /// 构建交换功德指令，两个用户都要签名
+    pub fn swap_counts_instruction(&self, user_a: &Pubkey, user_b: &Pubkey) -> Instruction {
+        instruction::swap_counts(&self.program_id, &self.gongde_address(user_a), user_a, &self.gongde_address(user_b), user_b)
+    }
+
+    /// 构建功德汇总指令，所有功德账户都只读，账户数量为1到 MAX_REPORT_ACCOUNTS 个
+    pub fn report_instruction(&self, gongde_accounts: &[Pubkey]) -> Instruction {
+        instruction::report(&self.program_id, gongde_accounts)
+    }
+
+    /// 两个用户交换功德，user_a 支付交易费
+    pub fn swap_counts(&mut self, user_a: &Keypair, user_b: &Keypair) -> TransactionResult {
+        let instruction = self.swap_counts_instruction(&user_a.pubkey(), &user_b.pubkey());
+        self.send(&[instruction], user_a, &[user_b])
+    }
+
+    /// 用户归档自己的功德账户
+    pub fn archive(&mut self, user: &Keypair) -> TransactionResult {
+        let instruction = self.set_archived_instruction(&user.pubkey(), true);
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 用户取消归档自己的功德账户
+    pub fn unarchive(&mut self, user: &Keypair) -> TransactionResult {
+        let instruction = self.set_archived_instruction(&user.pubkey(), false);
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 用户给自己增加多次功德
+    pub fn increment_times(&mut self, user: &Keypair, times: u32) {
+        for _ in 0..times {
+            self.increment(user).expect("增加功德失败");
+        }
+    }
+
+    /// 用户给自己增加一次功德
+    pub fn increment(&mut self, user: &Keypair) -> TransactionResult {
+        let gongde_pubkey = self.gongde_address(&user.pubkey());
+        self.increment_for(&gongde_pubkey, user)
+    }
+
+    /// payer 给指定功德账户增加一次功德
+    pub fn increment_for(&mut self, gongde_pubkey: &Pubkey, payer: &Keypair) -> TransactionResult {
+        let instruction = self.increment_instruction(gongde_pubkey, &payer.pubkey());
+        self.send(&[instruction], payer, &[])
+    }
+
+    /// 用户关闭功德账户
+    pub fn close(&mut self, user: &Keypair) -> TransactionResult {
+        let instruction = self.close_instruction(&user.pubkey());
+        self.send(&[instruction], user, &[])
+    }
+
+    /// 读取账户中的功德值，账户不存在或已关闭时返回None
+    pub fn read_value(&self, pubkey: &Pubkey) -> Option<u32> {
+        self.svm
+            .get_account(pubkey)
+            .filter(|account| account.lamports > 0)
+            .and_then(|account| read_gongde_value(&account.data).ok())
+    }
+
+    /// 读取账户中的累计功德，账户不存在或已关闭时返回None
+    pub fn read_lifetime_total(&self, pubkey: &Pubkey) -> Option<u64> {
+        self.svm
+            .get_account(pubkey)
+            .filter(|account| account.lamports > 0)
+            .and_then(|account| read_lifetime_total(&account.data).ok())
+    }
+
+    /// 读取账户中已达成的里程碑，账户不存在或已关闭时返回空列表
+    pub fn read_milestones(&self, pubkey: &Pubkey) -> Vec<Milestone> {
+        self.svm
+            .get_account(pubkey)
+            .filter(|account| account.lamports > 0)
+            .map(|account| read_milestones(&account.data))
+            .unwrap_or_default()
+    }
+
+    /// 执行确定性夹具脚本：创建全部夹具用户的账户并按脚本操作
+    ///
+    /// # 返回
+    /// * `Vec<Keypair>` - 夹具用户，下标与脚本中的用户下标一致
+    pub fn apply_fixture(&mut self) -> Vec<Keypair> {
+        let users = fixture_users();
+        for user in &users {
+            self.fund(&user.pubkey());
+            self.create_gongde_account(user);
+        }
+
+        for &(index, op) in FIXTURE_SCRIPT {
+            let user = &users[index];
+            let gongde_pubkey = self.gongde_address(&user.pubkey());
+            let result = match op {
+                FixtureOp::Increment => self.increment(user),
+                FixtureOp::IncrementBy(amount) => {
+                    let instruction = self.increment_by_instruction(&gongde_pubkey, &user.pubkey(), amount);
+                    self.send(&[instruction], user, &[])
+                }
+                FixtureOp::Transfer { to, amount } => {
+                    let recipient_gongde = self.gongde_address(&users[to].pubkey());
+                    self.transfer_merit(user, &recipient_gongde, amount)
+                }
+                FixtureOp::Endorse(to) => {
+                    let endorsee_gongde = self.gongde_address(&users[to].pubkey());
+                    self.endorse(user, &endorsee_gongde)
+                }
+                FixtureOp::RestoreTo(value) => {
+                    let instruction = self.restore_to_instruction(&user.pubkey(), value, 0);
+                    self.send(&[instruction], user, &[])
+                }
+                FixtureOp::SetEmoji(emoji) => {
+                    let instruction = instruction::set_emoji(&self.program_id, &gongde_pubkey, &user.pubkey(), emoji as u32);
+                    self.send(&[instruction], user, &[])
+                }
+                FixtureOp::Archive => self.archive(user),
+                FixtureOp::Close => self.close(user),
+            };
+            result.unwrap_or_else(|e| panic!("夹具脚本执行失败 用户{} {:?}: {:?}", index, op, e.err));
+        }
+
+        users
+    }
+}
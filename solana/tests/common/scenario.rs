@@ -0,0 +1,317 @@
+// ========================================
+// 场景矩阵 - 用抽象步骤描述测试场景，在每个合约实现上执行并比较结果
+// 新场景只需要在 tests/scenarios.rs 中添加一次，所有实现都会覆盖到
+// ========================================
+
+use std::fmt;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{GONGDE_ACCOUNT_SIZE, GONGDE_VALUE_SIZE},
+};
+use litesvm::types::TransactionResult;
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use solana_system_interface::instruction as system_instruction;
+
+use super::{setup, TestContext};
+
+/// 场景中的参与者
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Actor {
+    /// 功德账户的主人
+    Owner,
+    /// 其他用户
+    Visitor,
+}
+
+/// 场景中的单步操作，都作用在主人的功德账户上
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// 主人初始化功德账户，账户不存在时在同一交易中创建
+    Initialize { public: bool },
+    /// 指定用户连续增加功德，遇到第一个失败即停止
+    Increment { by: Actor, times: u32 },
+    /// 指定用户一次增加多点功德
+    IncrementBy { by: Actor, amount: u64 },
+    /// 指定用户签名关闭主人的账户
+    Close { by: Actor },
+    /// 指定用户签名"空账户才关闭"
+    CloseIfEmpty { by: Actor },
+    /// 同一交易内关闭账户、转回租金再增加功德
+    ReviveAndIncrement,
+}
+
+/// 与具体实现无关的错误类别，不同实现的错误码可以不同，但类别必须一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// 缺少签名或不是账户主人
+    Unauthorized,
+    /// 重复初始化
+    AlreadyInitialized,
+    /// 账户未初始化或已关闭
+    Uninitialized,
+    /// 账户功德不为0
+    NotEmpty,
+    /// 功德数量为0或超出范围
+    InvalidAmount,
+    /// 未归类的错误，保留原始信息便于排查
+    Other(String),
+}
+
+impl ErrorCategory {
+    /// 把交易错误归类
+    pub fn from_transaction_error(err: &TransactionError) -> Self {
+        match err {
+            TransactionError::InstructionError(_, InstructionError::IllegalOwner)
+            | TransactionError::InstructionError(_, InstructionError::MissingRequiredSignature) => Self::Unauthorized,
+            TransactionError::InstructionError(_, InstructionError::AccountAlreadyInitialized) => Self::AlreadyInitialized,
+            TransactionError::InstructionError(_, InstructionError::UninitializedAccount) => Self::Uninitialized,
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) if *code == GongDeError::NotEmpty as u32 => {
+                Self::NotEmpty
+            }
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) if *code == GongDeError::InvalidAmount as u32 => {
+                Self::InvalidAmount
+            }
+            other => Self::Other(format!("{:?}", other)),
+        }
+    }
+}
+
+/// 单步执行结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    Err(ErrorCategory),
+}
+
+impl Outcome {
+    /// 把LiteSVM的交易结果归类
+    pub fn from_result(result: &TransactionResult) -> Self {
+        match result {
+            Ok(_) => Self::Ok,
+            Err(failed) => Self::Err(ErrorCategory::from_transaction_error(&failed.err)),
+        }
+    }
+}
+
+/// 每步执行后观察到的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    /// 执行结果
+    pub outcome: Outcome,
+    /// 主人的功德值，账户不存在或已关闭时为None
+    pub value: Option<u32>,
+    /// 全局功德值
+    pub global: Option<u32>,
+    /// 全局账户收到的手续费（扣除租金押金）
+    pub global_fees: u64,
+}
+
+impl fmt::Display for Observation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} 功德={:?} 全局={:?} 手续费={}",
+            self.outcome, self.value, self.global, self.global_fees
+        )
+    }
+}
+
+/// 场景中的一步：操作和预期结果
+#[derive(Debug, Clone)]
+pub struct ExpectedStep {
+    pub step: Step,
+    pub outcome: Outcome,
+    /// 执行后主人的功德值
+    pub value: Option<u32>,
+}
+
+/// 预期成功的步骤
+pub fn ok(step: Step, value: Option<u32>) -> ExpectedStep {
+    ExpectedStep { step, outcome: Outcome::Ok, value }
+}
+
+/// 预期失败的步骤
+pub fn fails(step: Step, category: ErrorCategory, value: Option<u32>) -> ExpectedStep {
+    ExpectedStep { step, outcome: Outcome::Err(category), value }
+}
+
+/// 一个完整的测试场景
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub name: &'static str,
+    pub steps: Vec<ExpectedStep>,
+}
+
+/// 合约实现：按抽象步骤执行并报告状态
+pub trait Backend {
+    /// 实现名称，出现在差异报告中
+    fn name(&self) -> &'static str;
+    /// 执行一步操作
+    fn run(&mut self, step: Step) -> Outcome;
+    /// 观察当前状态
+    fn observe(&self, outcome: Outcome) -> Observation;
+}
+
+/// 原生合约实现
+pub struct NativeBackend {
+    ctx: TestContext,
+    owner: Keypair,
+    visitor: Keypair,
+}
+
+impl NativeBackend {
+    fn new() -> Self {
+        let mut ctx = setup();
+        let owner = ctx.new_user();
+        let visitor = ctx.new_user();
+        Self { ctx, owner, visitor }
+    }
+
+    fn actor(&self, actor: Actor) -> &Keypair {
+        match actor {
+            Actor::Owner => &self.owner,
+            Actor::Visitor => &self.visitor,
+        }
+    }
+}
+
+impl Backend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn run(&mut self, step: Step) -> Outcome {
+        let owner_pubkey = self.owner.pubkey();
+        let gongde_pubkey = self.ctx.gongde_address(&owner_pubkey);
+        let owner = self.owner.insecure_clone();
+        let result = match step {
+            Step::Initialize { public } => {
+                let exists = self.ctx.svm.get_account(&gongde_pubkey).is_some_and(|account| account.lamports > 0);
+                if exists {
+                    let instruction = self.ctx.initialize_instruction(&owner_pubkey, public);
+                    self.ctx.send(&[instruction], &owner, &[])
+                } else {
+                    let instructions = self.ctx.create_gongde_account_instructions(&owner_pubkey, public);
+                    self.ctx.send(&instructions, &owner, &[])
+                }
+            }
+            Step::Increment { by, times } => {
+                let payer = self.actor(by).insecure_clone();
+                for _ in 0..times {
+                    let result = self.ctx.increment_for(&gongde_pubkey, &payer);
+                    if result.is_err() {
+                        return Outcome::from_result(&result);
+                    }
+                }
+                return Outcome::Ok;
+            }
+            Step::IncrementBy { by, amount } => {
+                let payer = self.actor(by).insecure_clone();
+                let instruction = self.ctx.increment_by_instruction(&gongde_pubkey, &payer.pubkey(), amount);
+                self.ctx.send(&[instruction], &payer, &[])
+            }
+            Step::Close { by } => {
+                let signer = self.actor(by).insecure_clone();
+                let mut instruction = self.ctx.close_instruction(&signer.pubkey());
+                instruction.accounts[0].pubkey = gongde_pubkey;
+                self.ctx.send(&[instruction], &signer, &[])
+            }
+            Step::CloseIfEmpty { by } => {
+                let signer = self.actor(by).insecure_clone();
+                let mut instruction = self.ctx.close_if_empty_instruction(&signer.pubkey());
+                instruction.accounts[0].pubkey = gongde_pubkey;
+                self.ctx.send(&[instruction], &signer, &[])
+            }
+            Step::ReviveAndIncrement => {
+                let rent = self.ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+                let instructions = [
+                    self.ctx.close_instruction(&owner_pubkey),
+                    system_instruction::transfer(&owner_pubkey, &gongde_pubkey, rent),
+                    self.ctx.increment_instruction(&gongde_pubkey, &owner_pubkey),
+                ];
+                self.ctx.send(&instructions, &owner, &[])
+            }
+        };
+        Outcome::from_result(&result)
+    }
+
+    fn observe(&self, outcome: Outcome) -> Observation {
+        let global_pubkey = self.ctx.global_address();
+        let global_rent = self.ctx.svm.minimum_balance_for_rent_exemption(GONGDE_VALUE_SIZE);
+        let global_fees = self
+            .ctx
+            .svm
+            .get_account(&global_pubkey)
+            .map(|account| account.lamports.saturating_sub(global_rent))
+            .unwrap_or(0);
+        Observation {
+            outcome,
+            value: self.ctx.read_value(&self.ctx.gongde_address(&self.owner.pubkey())),
+            global: self.ctx.read_value(&global_pubkey),
+            global_fees,
+        }
+    }
+}
+
+/// 所有参与比较的合约实现，新实现加到这里即可覆盖全部场景
+pub fn backends() -> Vec<Box<dyn Backend>> {
+    vec![Box::new(NativeBackend::new())]
+}
+
+/// 在全部实现上执行场景
+///
+/// # 返回
+/// * `Vec<String>` - 差异描述，为空表示所有实现都符合预期且结果一致
+pub fn run_scenario(scenario: &Scenario) -> Vec<String> {
+    let mut diffs = Vec::new();
+    let mut reference: Option<(&'static str, Vec<Observation>)> = None;
+
+    for mut backend in backends() {
+        let mut observations = Vec::with_capacity(scenario.steps.len());
+        for (index, expected) in scenario.steps.iter().enumerate() {
+            let outcome = backend.run(expected.step);
+            let observation = backend.observe(outcome);
+            if observation.outcome != expected.outcome || observation.value != expected.value {
+                diffs.push(format!(
+                    "[{}] {} 第{}步 {:?}\n    预期: {:?} 功德={:?}\n    实际: {}",
+                    scenario.name,
+                    backend.name(),
+                    index + 1,
+                    expected.step,
+                    expected.outcome,
+                    expected.value,
+                    observation
+                ));
+            }
+            observations.push(observation);
+        }
+
+        // 不同实现之间还要比较全局状态和手续费流向
+        match &reference {
+            None => reference = Some((backend.name(), observations)),
+            Some((reference_name, reference_observations)) => {
+                for (index, (left, right)) in reference_observations.iter().zip(&observations).enumerate() {
+                    if left != right {
+                        diffs.push(format!(
+                            "[{}] 第{}步 {:?} 实现结果不一致\n    {}: {}\n    {}: {}",
+                            scenario.name,
+                            index + 1,
+                            scenario.steps[index].step,
+                            reference_name,
+                            left,
+                            backend.name(),
+                            right
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    diffs
+}
@@ -0,0 +1,87 @@
+// ========================================
+// 头像测试 - SetEmoji 设置、清除头像，只接受可以显示的字符
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    instruction,
+    utils::{read_emoji, read_last_active},
+};
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use common::custom_error;
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+fn set_emoji(ctx: &common::TestContext, gongde_pubkey: &Pubkey, authority: &Keypair, codepoint: u32) -> Instruction {
+    instruction::set_emoji(&ctx.program_id, gongde_pubkey, &authority.pubkey(), codepoint)
+}
+
+fn emoji(ctx: &common::TestContext, gongde_pubkey: &Pubkey) -> Option<char> {
+    read_emoji(&ctx.svm.get_account(gongde_pubkey).unwrap().data)
+}
+
+#[test]
+fn test_set_and_clear_emoji() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    assert_eq!(emoji(&ctx, &gongde_pubkey), None);
+
+    ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &user, '🙏' as u32)], &user, &[]).expect("设置头像失败");
+    assert_eq!(emoji(&ctx, &gongde_pubkey), Some('🙏'));
+    // 设置头像也算一次操作
+    assert_eq!(read_last_active(&ctx.svm.get_account(&gongde_pubkey).unwrap().data), Some(NOW));
+
+    // 可以换成别的字符，功德不受影响
+    ctx.increment(&user).expect("增加功德失败");
+    ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &user, '佛' as u32)], &user, &[]).expect("更换头像失败");
+    assert_eq!(emoji(&ctx, &gongde_pubkey), Some('佛'));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+
+    // 码点为0时清除
+    ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &user, 0)], &user, &[]).expect("清除头像失败");
+    assert_eq!(emoji(&ctx, &gongde_pubkey), None);
+}
+
+#[test]
+fn test_invalid_codepoint_is_rejected() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &user, '🪷' as u32)], &user, &[]).expect("设置头像失败");
+
+    // 代理对、超出Unicode范围、控制字符、空白和私用区字符都不能作为头像，原来的头像保持不变
+    for codepoint in [0xD800, 0x11_0000, 0x1B, ' ' as u32, 0xE000] {
+        let err = ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &user, codepoint)], &user, &[]).unwrap_err();
+        assert_eq!(err.err, custom_error(GongDeError::InvalidEmoji), "{:#x}", codepoint);
+    }
+    assert_eq!(emoji(&ctx, &gongde_pubkey), Some('🪷'));
+}
+
+#[test]
+fn test_only_owner_can_set_emoji() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let stranger = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    let err = ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &stranger, '😈' as u32)], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    assert_eq!(emoji(&ctx, &gongde_pubkey), None);
+
+    // 归档期间也不能修改
+    ctx.archive(&user).expect("归档失败");
+    let err = ctx.send(&[set_emoji(&ctx, &gongde_pubkey, &user, '🙏' as u32)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::AccountArchived));
+    assert_eq!(emoji(&ctx, &gongde_pubkey), None);
+}
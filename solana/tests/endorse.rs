@@ -0,0 +1,128 @@
+// ========================================
+// Endorse 指令测试 - 背书权重按背书人的功德计算
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{CreateLotteryArgs, Endorsement, ENDORSEMENT_SIZE};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    signature::Signer,
+    transaction::TransactionError,
+};
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn test_endorse_grants_weighted_merit_and_records_it() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let endorser = ctx.new_user();
+    let endorsee = ctx.new_user();
+    ctx.create_gongde_account(&endorser);
+    let endorsee_gongde = ctx.create_gongde_account(&endorsee);
+    // 功德12，权重 1 + floor(log10(12)) = 2
+    ctx.increment_times(&endorser, 12);
+
+    ctx.endorse(&endorser, &endorsee_gongde).expect("背书应成功");
+
+    assert_eq!(ctx.read_value(&endorsee_gongde), Some(2));
+    // 背书人自己的功德不变
+    assert_eq!(ctx.read_value(&ctx.gongde_address(&endorser.pubkey())), Some(12));
+    let record = ctx.svm.get_account(&ctx.endorsement_address(&endorser.pubkey(), &endorsee_gongde)).unwrap();
+    assert_eq!(record.owner, ctx.program_id);
+    assert_eq!(record.data.len(), ENDORSEMENT_SIZE);
+    assert_eq!(
+        Endorsement::unpack(&record.data),
+        Ok(Endorsement { weight: 2, endorser_value: 12, endorsed_at: NOW })
+    );
+}
+
+#[test]
+fn test_endorse_with_no_merit_has_weight_one() {
+    let mut ctx = common::setup();
+    let endorser = ctx.new_user();
+    let endorsee = ctx.new_user();
+    ctx.create_gongde_account(&endorser);
+    let endorsee_gongde = ctx.create_gongde_account(&endorsee);
+
+    ctx.endorse(&endorser, &endorsee_gongde).expect("背书应成功");
+    assert_eq!(ctx.read_value(&endorsee_gongde), Some(1));
+}
+
+#[test]
+fn test_endorse_only_once_per_pair() {
+    let mut ctx = common::setup();
+    let endorser = ctx.new_user();
+    let endorsee = ctx.new_user();
+    ctx.create_gongde_account(&endorser);
+    let endorsee_gongde = ctx.create_gongde_account(&endorsee);
+
+    ctx.endorse(&endorser, &endorsee_gongde).expect("第一次背书应成功");
+    let err = ctx.endorse(&endorser, &endorsee_gongde).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+    assert_eq!(ctx.read_value(&endorsee_gongde), Some(1));
+}
+
+#[test]
+fn test_prefunded_record_is_not_treated_as_endorsed() {
+    let mut ctx = common::setup();
+    let endorser = ctx.new_user();
+    let endorsee = ctx.new_user();
+    ctx.create_gongde_account(&endorser);
+    let endorsee_gongde = ctx.create_gongde_account(&endorsee);
+
+    // 有人抢先往背书记录地址转账，只有余额不算背过书，背书照常创建记录并加功德
+    let endorsement_pubkey = ctx.endorsement_address(&endorser.pubkey(), &endorsee_gongde);
+    ctx.prefund(&endorsement_pubkey);
+    ctx.endorse(&endorser, &endorsee_gongde).expect("记录地址被抢先转账后背书失败");
+
+    assert_eq!(ctx.read_value(&endorsee_gongde), Some(1));
+    let record = ctx.svm.get_account(&endorsement_pubkey).unwrap();
+    assert_eq!(record.owner, ctx.program_id);
+    assert_eq!(record.data.len(), ENDORSEMENT_SIZE);
+}
+
+#[test]
+fn test_endorse_rejects_borrowed_merit_and_self_endorsement() {
+    let mut ctx = common::setup();
+    let endorser = ctx.new_user();
+    let rich = ctx.new_user();
+    let endorsee = ctx.new_user();
+    ctx.create_gongde_account(&endorser);
+    let rich_gongde = ctx.create_gongde_account(&rich);
+    let endorsee_gongde = ctx.create_gongde_account(&endorsee);
+    ctx.increment_times(&rich, 10);
+
+    // 不能拿别人的功德账户抬高权重
+    let mut instruction = ctx.endorse_instruction(&endorser.pubkey(), &endorsee_gongde);
+    instruction.accounts[2] = AccountMeta::new_readonly(rich_gongde, false);
+    let err = ctx.send(&[instruction], &endorser, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+
+    // 不能给自己背书
+    let own_gongde = ctx.gongde_address(&endorser.pubkey());
+    let err = ctx.endorse(&endorser, &own_gongde).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    assert_eq!(ctx.read_value(&endorsee_gongde), Some(0));
+}
+
+#[test]
+fn test_endorse_rejects_non_gongde_account() {
+    let mut ctx = common::setup();
+    let endorser = ctx.new_user();
+    ctx.create_gongde_account(&endorser);
+
+    // 彩票轮次归本合约所有，背书不能把它的头部改写成功德值
+    let admin = ctx.new_user();
+    let args = CreateLotteryArgs { round_id: 1, start_slot: 0, end_slot: 100, ticket_price: 1 };
+    ctx.create_lottery(&admin, &args).unwrap();
+    let lottery = ctx.lottery_address(&admin.pubkey(), args.round_id);
+    let before = ctx.svm.get_account(&lottery).unwrap().data;
+
+    let err = ctx.endorse(&endorser, &lottery).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    assert_eq!(ctx.svm.get_account(&lottery).unwrap().data, before);
+    assert!(ctx.svm.get_account(&ctx.endorsement_address(&endorser.pubkey(), &lottery)).is_none());
+}
@@ -0,0 +1,128 @@
+// ========================================
+// EndorseMany 指令测试 - 一次给多个功德账户背书，已经背过书的跳过
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    instruction,
+    utils::{CreateLotteryArgs, EndorseManyResult, MAX_ENDORSE_MANY_TARGETS},
+};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+    signature::Signer,
+};
+
+use common::instruction_error;
+
+#[test]
+fn test_endorse_many_skips_already_endorsed() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    ctx.create_gongde_account(&organizer);
+    // 功德12，权重 1 + floor(log10(12)) = 2
+    ctx.increment_times(&organizer, 12);
+    let attendees: Vec<_> = (0..3).map(|_| ctx.seed_gongde_account(&Pubkey::new_unique(), 5)).collect();
+
+    // 第二个人之前单独背过书
+    ctx.endorse(&organizer, &attendees[1]).expect("背书应成功");
+    assert_eq!(ctx.read_value(&attendees[1]), Some(7));
+
+    let instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &attendees);
+    let meta = ctx.send(&[instruction], &organizer, &[]).expect("批量背书应成功");
+
+    let result = EndorseManyResult::from_bytes(&meta.return_data.data).expect("return data 格式错误");
+    assert_eq!(result, EndorseManyResult { endorsed: 0b101 });
+    assert!(common::logged(&meta.logs, "新背书 {} 个功德账户，共 {} 个", &[2, 3]), "{:?}", meta.logs);
+    assert!(
+        meta.logs.iter().any(|log| log.contains(&attendees[1].to_string())),
+        "跳过的账户应出现在日志中: {:?}",
+        meta.logs
+    );
+
+    // 跳过的账户没有重复加功德
+    assert_eq!(ctx.read_value(&attendees[0]), Some(7));
+    assert_eq!(ctx.read_value(&attendees[1]), Some(7));
+    assert_eq!(ctx.read_value(&attendees[2]), Some(7));
+    for attendee in &attendees {
+        let record = ctx.svm.get_account(&ctx.endorsement_address(&organizer.pubkey(), attendee));
+        assert!(record.is_some_and(|record| record.owner == ctx.program_id));
+    }
+
+    // 全部背过书后再来一次，整批跳过但不失败
+    let instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &attendees);
+    let meta = ctx.send(&[instruction], &organizer, &[]).expect("全部跳过也应成功");
+    assert_eq!(EndorseManyResult::from_bytes(&meta.return_data.data), Ok(EndorseManyResult { endorsed: 0 }));
+    assert_eq!(ctx.read_value(&attendees[0]), Some(7));
+}
+
+#[test]
+fn test_endorse_many_bounds_target_count() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    ctx.create_gongde_account(&organizer);
+    let attendees: Vec<_> = (0..=MAX_ENDORSE_MANY_TARGETS)
+        .map(|_| ctx.seed_gongde_account(&Pubkey::new_unique(), 0))
+        .collect();
+
+    // 超过上限整批拒绝，没有任何账户被修改；SDK 不会构建超过上限的指令，手动追加一个目标
+    let mut instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &attendees[..MAX_ENDORSE_MANY_TARGETS]);
+    let extra = attendees[MAX_ENDORSE_MANY_TARGETS];
+    instruction::append_account(&mut instruction, AccountMeta::new(extra, false));
+    instruction::append_account(&mut instruction, AccountMeta::new(ctx.endorsement_address(&organizer.pubkey(), &extra), false));
+    let err = ctx.send(&[instruction], &organizer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidArgument));
+    assert_eq!(ctx.read_value(&attendees[0]), Some(0));
+
+    // 正好上限可以背书
+    let instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &attendees[..MAX_ENDORSE_MANY_TARGETS]);
+    let meta = ctx.send(&[instruction], &organizer, &[]).expect("批量背书应成功");
+    let result = EndorseManyResult::from_bytes(&meta.return_data.data).expect("return data 格式错误");
+    assert_eq!(result.endorsed_count(), MAX_ENDORSE_MANY_TARGETS as u32);
+
+    // 没有目标，或者最后一个目标缺少背书记录；目标排在背书人、背书人的功德账户和系统程序之后，全局配置之前
+    let mut instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &attendees[MAX_ENDORSE_MANY_TARGETS..]);
+    instruction.accounts.drain(3..5);
+    let err = ctx.send(&[instruction], &organizer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::NotEnoughAccountKeys));
+    let mut instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &attendees[MAX_ENDORSE_MANY_TARGETS..]);
+    instruction.accounts.remove(4);
+    let err = ctx.send(&[instruction], &organizer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::NotEnoughAccountKeys));
+}
+
+#[test]
+fn test_endorse_many_fails_whole_batch_on_invalid_target() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    let organizer_gongde = ctx.create_gongde_account(&organizer);
+    let attendee = ctx.seed_gongde_account(&Pubkey::new_unique(), 0);
+
+    // 除了已经背过书之外的错误整批失败，前面的目标也回滚
+    let instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &[attendee, organizer_gongde]);
+    let err = ctx.send(&[instruction], &organizer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidArgument));
+    assert_eq!(ctx.read_value(&attendee), Some(0));
+}
+
+#[test]
+fn test_endorse_many_rejects_batch_with_non_gongde_account() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    ctx.create_gongde_account(&organizer);
+    let attendee = ctx.seed_gongde_account(&Pubkey::new_unique(), 0);
+    let admin = ctx.new_user();
+    let args = CreateLotteryArgs { round_id: 1, start_slot: 0, end_slot: 100, ticket_price: 1 };
+    ctx.create_lottery(&admin, &args).unwrap();
+    let lottery = ctx.lottery_address(&admin.pubkey(), args.round_id);
+    let before = ctx.svm.get_account(&lottery).unwrap().data;
+
+    // 彩票轮次混在功德账户后面，整批拒绝，前面的功德账户也没有被背书
+    let instruction = ctx.endorse_many_instruction(&organizer.pubkey(), &[attendee, lottery]);
+    let err = ctx.send(&[instruction], &organizer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidAccountData));
+    assert_eq!(ctx.read_value(&attendee), Some(0));
+    assert!(ctx.svm.get_account(&ctx.endorsement_address(&organizer.pubkey(), &attendee)).is_none());
+    assert_eq!(ctx.svm.get_account(&lottery).unwrap().data, before);
+}
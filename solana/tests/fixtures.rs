@@ -0,0 +1,47 @@
+// ========================================
+// 夹具测试 - 校验确定性脚本执行后的状态与文档中的预期一致
+// ========================================
+
+mod common;
+
+use common::fixtures::{
+    fixture_keypair,
+    replay_expected_state,
+    EXPECTED_ARCHIVED,
+    EXPECTED_GLOBAL_TOTAL,
+    EXPECTED_USER_COUNTS,
+};
+use gong_de_increase::utils::{is_archived_account, read_emoji};
+use solana_sdk::signature::Signer;
+
+#[test]
+fn test_fixture_keypairs_are_deterministic() {
+    assert_eq!(fixture_keypair(0).pubkey(), fixture_keypair(0).pubkey());
+    assert_ne!(fixture_keypair(0).pubkey(), fixture_keypair(1).pubkey());
+}
+
+#[test]
+fn test_documented_totals_match_script() {
+    let expected = replay_expected_state();
+    assert_eq!(expected.counts, EXPECTED_USER_COUNTS.to_vec());
+    assert_eq!(expected.archived, EXPECTED_ARCHIVED.to_vec());
+    assert_eq!(expected.global_total, EXPECTED_GLOBAL_TOTAL);
+}
+
+#[test]
+fn test_fixture_state_is_reproducible() {
+    let mut ctx = common::setup();
+    let users = ctx.apply_fixture();
+    let expected = replay_expected_state();
+
+    for (index, user) in users.iter().enumerate() {
+        let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+        assert_eq!(ctx.read_value(&gongde_pubkey), expected.counts[index], "用户 {}", user.pubkey());
+        if expected.counts[index].is_some() {
+            let data = ctx.svm.get_account(&gongde_pubkey).unwrap().data;
+            assert_eq!(is_archived_account(&data), expected.archived[index], "用户 {}", user.pubkey());
+            assert_eq!(read_emoji(&data), expected.emojis[index], "用户 {}", user.pubkey());
+        }
+    }
+    assert_eq!(ctx.read_value(&ctx.global_address()), Some(EXPECTED_GLOBAL_TOTAL));
+}
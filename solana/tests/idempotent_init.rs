@@ -0,0 +1,68 @@
+// ========================================
+// 幂等开通测试 - 与 client.rs 相同的开通流程，重复运行不会因为 "already in use" 失败
+// 先查询账户：不存在时创建并初始化，已创建但没有初始化时只补发初始化，已初始化时什么都不做
+// ========================================
+
+mod common;
+
+use common::TestContext;
+use gong_de_increase::utils::is_usable_account;
+use solana_sdk::signature::{Keypair, Signer};
+
+/// 按账户现状开通功德账户，与客户端的计划一致
+///
+/// # 返回
+/// * `usize` - 发送的交易数，已开通时为0
+fn ensure_gongde_account(ctx: &mut TestContext, user: &Keypair, is_public: bool) -> usize {
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let [create, initialize] = ctx.create_gongde_account_instructions(&user.pubkey(), is_public);
+    let instructions = match ctx.svm.get_account(&gongde_pubkey) {
+        Some(account) if account.lamports > 0 && is_usable_account(&account.data) => return 0,
+        Some(account) if account.lamports > 0 => vec![initialize],
+        _ => vec![create, initialize],
+    };
+    ctx.send(&instructions, user, &[]).expect("开通功德账户失败");
+    1
+}
+
+#[test]
+fn test_init_twice_is_noop() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    assert_eq!(ensure_gongde_account(&mut ctx, &user, false), 1);
+    ctx.increment_times(&user, 2);
+    let account = ctx.svm.get_account(&gongde_pubkey).unwrap();
+    let balance = ctx.svm.get_balance(&user.pubkey());
+
+    // 第二次运行不发送任何交易，账户和余额都不变
+    assert_eq!(ensure_gongde_account(&mut ctx, &user, false), 0);
+    assert_eq!(ctx.svm.get_account(&gongde_pubkey).unwrap(), account);
+    assert_eq!(ctx.svm.get_balance(&user.pubkey()), balance);
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+
+    // 不先查询、直接再创建一次就是原来的问题：地址已被占用，整笔交易失败
+    let instructions = ctx.create_gongde_account_instructions(&user.pubkey(), false);
+    assert!(ctx.send(&instructions, &user, &[]).is_err());
+}
+
+#[test]
+fn test_init_completes_partial_state() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 只创建了账户，初始化没有完成（例如初始化放在单独的交易里且没有发出去）
+    let [create, _initialize] = ctx.create_gongde_account_instructions(&user.pubkey(), true);
+    ctx.send(&[create], &user, &[]).expect("创建账户失败");
+    assert!(!is_usable_account(&ctx.svm.get_account(&gongde_pubkey).unwrap().data));
+
+    // 重新运行只补发初始化，之后再运行就什么都不做
+    assert_eq!(ensure_gongde_account(&mut ctx, &user, true), 1);
+    assert!(is_usable_account(&ctx.svm.get_account(&gongde_pubkey).unwrap().data));
+    assert_eq!(ensure_gongde_account(&mut ctx, &user, true), 0);
+
+    ctx.increment_for(&gongde_pubkey, &user).expect("增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+}
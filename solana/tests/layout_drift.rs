@@ -0,0 +1,179 @@
+// ========================================
+// 布局漂移测试 - 链上合约写入的数据必须能被客户端的解码函数原样读回
+// 合约和客户端共用 state 模块，但合约按偏移量逐个字段写入、签名时自己拼种子，
+// 任何一边单独改动布局或种子都会让已经上链的账户读错或找不到，这里用真实的合约执行结果把两边钉在一起
+// ========================================
+
+mod common;
+
+use gong_de_increase::instruction;
+use gong_de_increase::utils::{
+    day_index,
+    lottery_size,
+    endorsement_weight,
+    layout_version,
+    read_authority,
+    read_beneficiary,
+    read_emoji,
+    read_gongde_value,
+    read_last_accrued,
+    read_last_active,
+    read_last_increment_slot,
+    read_lottery_entry,
+    shared_ledger_get,
+    CreateCampaignArgs,
+    CreateLotteryArgs,
+    Endorsement,
+    GongDeAccount,
+    LotteryRound,
+    Milestone,
+    StakeState,
+    TransferState,
+    ACCRUAL_STATE_SEED,
+    ACCRUAL_STATE_SIZE,
+    CAMPAIGN_SEED,
+    CAMPAIGN_SIZE,
+    CONFIG_SEED,
+    DEFAULT_SEED_PREFIX,
+    ENDORSEMENT_SEED,
+    ENDORSEMENT_SIZE,
+    FLAG_INITIALIZED,
+    GLOBAL_GONGDE_ACCOUNT_SEED,
+    GONGDE_ACCOUNT_SEED,
+    GONGDE_ACCOUNT_SIZE,
+    GONGDE_LAYOUT_VERSION,
+    GONGDE_VALUE_SIZE,
+    LOTTERY_SEED,
+    SHARED_LEDGER_SEED,
+    TRANSFER_STATE_SEED,
+    TRANSFER_STATE_SIZE,
+};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signer};
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+/// 测试中使用的当前slot
+const SLOT: u64 = 5_000;
+
+/// 读取合约创建的账户，检查归本合约所有且大小与常量一致
+fn program_account_data(ctx: &common::TestContext, pubkey: &Pubkey, size: usize) -> Vec<u8> {
+    let account = ctx.svm.get_account(pubkey).unwrap_or_else(|| panic!("客户端派生的地址 {} 上没有账户", pubkey));
+    assert_eq!(account.owner, ctx.program_id, "{}", pubkey);
+    assert_eq!(account.data.len(), size, "{}", pubkey);
+    account.data
+}
+
+#[test]
+fn test_client_decoders_read_program_written_accounts() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    ctx.set_slot(SLOT);
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account_with(&alice, false);
+    let bob_gongde = ctx.create_gongde_account(&bob);
+
+    // 让合约写入功德账户的每一个字段
+    let instruction = ctx.increment_by_instruction(&alice_gongde, &alice.pubkey(), 11);
+    ctx.send(&[instruction], &alice, &[]).expect("增加功德失败");
+    // 难度为0时任何 nonce 都有效，只为了让合约写入上次证明的slot
+    let instruction = instruction::increment_with_proof(&ctx.program_id, &alice_gongde, &alice.pubkey(), &ctx.global_address(), 7);
+    ctx.send(&[instruction], &alice, &[]).expect("附带证明增加功德失败");
+    let instruction = ctx.set_beneficiary_instruction(&alice.pubkey(), Some((bob.pubkey(), 30)));
+    ctx.send(&[instruction], &alice, &[]).expect("指定受益人失败");
+    ctx.stake(&alice, LAMPORTS_PER_SOL / 4).expect("质押失败");
+    let instruction = instruction::set_emoji(&ctx.program_id, &alice_gongde, &alice.pubkey(), '🙏' as u32);
+    ctx.send(&[instruction], &alice, &[]).expect("设置头像失败");
+
+    let data = program_account_data(&ctx, &alice_gongde, GONGDE_ACCOUNT_SIZE);
+    assert_eq!(layout_version(data.len()), Some(GONGDE_LAYOUT_VERSION));
+    assert_eq!(
+        GongDeAccount::unpack(&data),
+        Ok(GongDeAccount { value: 12, flags: FLAG_INITIALIZED, lifetime_total: 12 })
+    );
+    assert_eq!(ctx.read_milestones(&alice_gongde), vec![Milestone { milestone: 10, reached_at: NOW }]);
+    assert_eq!(read_last_active(&data), Some(NOW));
+    assert_eq!(read_beneficiary(&data), Some((bob.pubkey(), 30)));
+    assert_eq!(read_authority(&data), None);
+    assert_eq!(
+        StakeState::unpack(&data),
+        Ok(StakeState { staked: LAMPORTS_PER_SOL / 4, last_claim_slot: SLOT, remainder: 0 })
+    );
+    assert_eq!(read_last_increment_slot(&data), Some(SLOT));
+    assert_eq!(read_emoji(&data), Some('🙏'));
+
+    // 合约用自己拼的种子签名创建PDA，客户端按派生函数找到的地址必须就是这些账户
+    ctx.transfer_merit(&alice, &bob_gongde, 1).expect("转赠失败");
+    let data = program_account_data(&ctx, &ctx.transfer_state_address(&alice.pubkey()), TRANSFER_STATE_SIZE);
+    assert_eq!(TransferState::unpack(&data), Ok(TransferState { day: day_index(NOW), transferred_today: 1 }));
+
+    ctx.accrue(&alice).expect("开始挂机失败");
+    let data = program_account_data(&ctx, &ctx.accrual_state_address(&alice_gongde), ACCRUAL_STATE_SIZE);
+    assert_eq!(read_last_accrued(&data), Ok(NOW));
+
+    let alice_value = ctx.read_value(&alice_gongde).unwrap();
+    ctx.endorse(&alice, &bob_gongde).expect("背书失败");
+    let data = program_account_data(&ctx, &ctx.endorsement_address(&alice.pubkey(), &bob_gongde), ENDORSEMENT_SIZE);
+    assert_eq!(
+        Endorsement::unpack(&data),
+        Ok(Endorsement { weight: endorsement_weight(alice_value), endorser_value: alice_value, endorsed_at: NOW })
+    );
+
+    let args = CreateCampaignArgs { campaign_id: 7, start: NOW, end: NOW + 3_600, multiplier: 2, budget: 5 };
+    ctx.create_campaign(&alice, &args).expect("创建配捐活动失败");
+    let campaign_pubkey = ctx.campaign_address(&alice.pubkey(), args.campaign_id);
+    program_account_data(&ctx, &campaign_pubkey, CAMPAIGN_SIZE);
+    let campaign = ctx.read_campaign(&campaign_pubkey).unwrap();
+    assert_eq!(
+        (campaign.organizer, campaign.start, campaign.end, campaign.multiplier, campaign.remaining),
+        (alice.pubkey(), args.start, args.end, args.multiplier, args.budget)
+    );
+
+    ctx.shared_increment(&alice, 3).expect("共享账本增加功德失败");
+    let ledger = ctx.svm.get_account(&ctx.shared_ledger_address()).expect("客户端派生的共享账本地址上没有账户");
+    assert_eq!(ledger.owner, ctx.program_id);
+    assert_eq!(shared_ledger_get(&ledger.data, &alice.pubkey()), Ok(Some(3)));
+
+    let args = CreateLotteryArgs { round_id: 3, start_slot: SLOT, end_slot: SLOT + 100, ticket_price: 1 };
+    ctx.create_lottery(&bob, &args).expect("创建彩票失败");
+    let lottery_pubkey = ctx.lottery_address(&bob.pubkey(), args.round_id);
+    ctx.enter_lottery(&alice, &lottery_pubkey, 1).expect("买彩票失败");
+    let data = program_account_data(&ctx, &lottery_pubkey, lottery_size(1));
+    assert_eq!(
+        LotteryRound::unpack(&data),
+        Ok(LotteryRound {
+            admin: bob.pubkey(),
+            round_id: args.round_id,
+            start_slot: args.start_slot,
+            end_slot: args.end_slot,
+            ticket_price: args.ticket_price,
+            total_tickets: 1,
+            settled: false,
+            voided: false,
+            winning_ticket: 0,
+            entry_count: 1,
+        })
+    );
+    assert_eq!(read_lottery_entry(&data, 0), Some((alice_gongde, 1)));
+
+    // 全局功德只累计 IncrementBy、IncrementWithProof 和 SharedIncrement
+    let data = program_account_data(&ctx, &ctx.global_address(), GONGDE_VALUE_SIZE);
+    assert_eq!(read_gongde_value(&data), Ok(15));
+}
+
+#[test]
+fn test_seeds_are_stable() {
+    // 种子决定已上链账户的地址，改动后旧账户就再也找不到了；确实要改时必须同时提供迁移方案
+    // 未指定 GONGDE_SEED_PREFIX 时的默认种子
+    assert_eq!(DEFAULT_SEED_PREFIX, "GongDeIncrease");
+    assert_eq!(GONGDE_ACCOUNT_SEED, "GongDeIncrease");
+    assert_eq!(GLOBAL_GONGDE_ACCOUNT_SEED, "GlobalGongDeIncrease");
+    assert_eq!(TRANSFER_STATE_SEED, b"xferstate");
+    assert_eq!(CAMPAIGN_SEED, b"campaign");
+    assert_eq!(SHARED_LEDGER_SEED, b"ledger");
+    assert_eq!(ENDORSEMENT_SEED, b"endorse");
+    assert_eq!(ACCRUAL_STATE_SEED, b"accrue");
+    assert_eq!(LOTTERY_SEED, b"lottery");
+    assert_eq!(CONFIG_SEED, b"config");
+}
@@ -0,0 +1,124 @@
+// ========================================
+// Increment 顺便创建功德账户测试 - 新用户第一笔交易只需要一条 increment
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{GongDeAccount, GONGDE_ACCOUNT_SIZE};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::Signer,
+    transaction::TransactionError,
+};
+use solana_system_interface::instruction as system_instruction;
+
+#[test]
+fn test_first_increment_creates_account() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    assert!(ctx.svm.get_account(&gongde_pubkey).is_none());
+    let balance_before = ctx.svm.get_balance(&user.pubkey()).unwrap();
+
+    let instruction = ctx.increment_creating_instruction(&user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("第一次增加功德失败");
+
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+    let account = ctx.svm.get_account(&gongde_pubkey).unwrap();
+    assert_eq!(account.owner, ctx.program_id);
+    assert_eq!(account.data.len(), GONGDE_ACCOUNT_SIZE);
+    assert_eq!(account.lamports, rent);
+    let gongde = GongDeAccount::unpack(&account.data).unwrap();
+    assert_eq!(gongde.value, 1);
+    assert_eq!(gongde.lifetime_total, 1);
+    assert!(gongde.is_initialized());
+    assert!(gongde.is_public());
+    // 租金由付款人支付
+    assert!(balance_before - ctx.svm.get_balance(&user.pubkey()).unwrap() > rent);
+}
+
+#[test]
+fn test_subsequent_increment_with_owner_does_not_recreate() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let instruction = ctx.increment_creating_instruction(&user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("第一次增加功德失败");
+    let created = ctx.svm.get_account(&gongde_pubkey).unwrap();
+
+    // 账户已存在时主人的签名不会被使用，照常增加
+    let instruction = ctx.increment_creating_instruction(&user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("再次增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+    assert_eq!(ctx.svm.get_account(&gongde_pubkey).unwrap().lamports, created.lamports);
+
+    // 不带账户主人的普通 increment 也可以继续使用
+    ctx.increment(&user).expect("普通增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+
+    // 用户手动创建的私有功德箱不会因为附上主人而变成公开
+    let other = ctx.new_user();
+    let private_gongde = ctx.create_gongde_account_with(&other, false);
+    let instruction = ctx.increment_creating_instruction(&other.pubkey());
+    ctx.send(&[instruction], &other, &[]).expect("私有功德箱增加功德失败");
+    let gongde = GongDeAccount::unpack(&ctx.svm.get_account(&private_gongde).unwrap().data).unwrap();
+    assert_eq!(gongde.value, 1);
+    assert!(!gongde.is_public());
+}
+
+#[test]
+fn test_missing_account_without_owner_still_fails() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    let err = ctx.increment_for(&gongde_pubkey, &user).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall));
+    assert!(ctx.svm.get_account(&gongde_pubkey).is_none());
+}
+
+#[test]
+fn test_prefunded_address_cannot_block_creation() {
+    let mut ctx = common::setup();
+    let attacker = ctx.new_user();
+    let victim = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&victim.pubkey());
+
+    // 攻击者抢先往受害者的功德账户地址转账，地址上出现一个系统程序所有的空账户
+    let prefund = ctx.svm.minimum_balance_for_rent_exemption(0);
+    let transfer = system_instruction::transfer(&attacker.pubkey(), &gongde_pubkey, prefund);
+    ctx.send(&[transfer], &attacker, &[]).expect("抢先转账失败");
+
+    // 攻击者不能以自己的名义创建受害者地址上的账户
+    let mut instruction = ctx.increment_creating_instruction(&attacker.pubkey());
+    instruction.accounts[0].pubkey = gongde_pubkey;
+    let err = ctx.send(&[instruction], &attacker, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::InvalidSeeds));
+
+    // 受害者仍然可以创建，只需补足租金差额，抢先转入的 lamports 留在账户里
+    let instruction = ctx.increment_creating_instruction(&victim.pubkey());
+    ctx.send(&[instruction], &victim, &[]).expect("地址被抢先转账后创建失败");
+    let account = ctx.svm.get_account(&gongde_pubkey).unwrap();
+    assert_eq!(account.owner, ctx.program_id);
+    assert_eq!(account.data.len(), GONGDE_ACCOUNT_SIZE);
+    assert_eq!(account.lamports, ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE));
+    let gongde = GongDeAccount::unpack(&account.data).unwrap();
+    assert_eq!(gongde.value, 1);
+    assert!(gongde.is_public());
+}
+
+#[test]
+fn test_prefunded_global_pda_cannot_block_increment() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 有人抢先往全局功德PDA转账，第一次增加功德时仍然可以创建全局功德PDA
+    let global_pubkey = ctx.global_address();
+    ctx.prefund(&global_pubkey);
+    ctx.increment(&user).expect("全局功德PDA被抢先转账后增加功德失败");
+
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    assert_eq!(ctx.read_value(&global_pubkey), Some(1));
+    assert_eq!(ctx.svm.get_account(&global_pubkey).unwrap().owner, ctx.program_id);
+}
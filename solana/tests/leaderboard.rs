@@ -0,0 +1,85 @@
+// ========================================
+// 排行榜和快照导出测试 - 用夹具脚本生成的状态检查排名、emoji、归档过滤和快照内容
+// ========================================
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::fixtures::{replay_expected_state, FIXTURE_EMOJI};
+use common::TestContext;
+use gong_de_increase::client::{
+    snapshot::{diff_snapshots, Snapshot},
+    utils::rank_accounts,
+};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+/// 执行夹具脚本，取出全部夹具用户的功德账户和全局功德PDA，相当于扫描程序账户的结果
+fn fixture_accounts(ctx: &mut TestContext) -> (Vec<Keypair>, Vec<(Pubkey, Account)>) {
+    let users = ctx.apply_fixture();
+    let accounts = users
+        .iter()
+        .map(|user| ctx.gongde_address(&user.pubkey()))
+        .chain([ctx.global_address()])
+        .filter_map(|pubkey| ctx.svm.get_account(&pubkey).map(|account| (pubkey, account)))
+        .collect();
+    (users, accounts)
+}
+
+#[test]
+fn test_leaderboard_ranks_fixture_accounts() {
+    let mut ctx = common::setup();
+    let (users, accounts) = fixture_accounts(&mut ctx);
+    let expected = replay_expected_state();
+    let gongde = |index: usize| ctx.gongde_address(&users[index].pubkey());
+    let ranking = |indices: &[usize]| -> Vec<(Pubkey, u32)> {
+        indices.iter().map(|&index| (gongde(index), expected.counts[index].unwrap())).collect()
+    };
+    let ranked = |include_archived| -> Vec<(Pubkey, u32)> {
+        rank_accounts(accounts.clone(), &ctx.global_address(), include_archived)
+            .iter()
+            .map(|entry| (entry.pubkey, entry.value))
+            .collect()
+    };
+
+    // 已关闭的用户3和全局功德PDA不参与排名，已归档的用户2默认不显示
+    assert_eq!(ranked(false), ranking(&[4, 0, 1]));
+    assert_eq!(ranked(true), ranking(&[4, 0, 2, 1]));
+
+    let entries = rank_accounts(accounts, &ctx.global_address(), true);
+    let entry = |index: usize| entries.iter().find(|entry| entry.pubkey == gongde(index)).unwrap();
+    assert_eq!(entry(0).emoji, Some(FIXTURE_EMOJI));
+    assert_eq!(entry(4).emoji, None);
+    assert!(entry(2).archived);
+    // 转出和背书不改变累计功德：用户4增加了5次和20点，用户0的背书所得不计入
+    assert_eq!((entry(4).lifetime_total, entry(0).lifetime_total), (25, 3));
+}
+
+#[test]
+fn test_snapshot_exports_fixture_accounts() {
+    let mut ctx = common::setup();
+    let (users, accounts) = fixture_accounts(&mut ctx);
+    let expected = replay_expected_state();
+
+    // 和 leaderboard --snapshot 一样，快照包含已归档的账户
+    let entries = rank_accounts(accounts, &ctx.global_address(), true);
+    let snapshot = Snapshot::from_leaderboard(ctx.program_id, 1_700_000_000, &entries);
+    let expected_accounts: BTreeMap<Pubkey, u32> = users
+        .iter()
+        .zip(&expected.counts)
+        .filter_map(|(user, count)| count.map(|count| (ctx.gongde_address(&user.pubkey()), count)))
+        .collect();
+    assert_eq!(snapshot.accounts, expected_accounts);
+
+    // 导出的JSON读回来与原快照一致
+    assert_eq!(Snapshot::from_json(&snapshot.to_json()), Ok(snapshot.clone()));
+
+    // 与空快照比较，每个账户都是新建的，功德总变化等于全部账户的功德之和
+    let diff = diff_snapshots(&BTreeMap::new(), &snapshot.accounts);
+    assert_eq!(diff.created().count(), expected_accounts.len());
+    assert_eq!(diff.total_change(), expected_accounts.values().map(|value| i64::from(*value)).sum::<i64>());
+}
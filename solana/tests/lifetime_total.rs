@@ -0,0 +1,56 @@
+// ========================================
+// 累计功德测试 - 只增不减，回滚和转出都不影响
+// ========================================
+
+mod common;
+
+use solana_sdk::signature::Signer;
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn test_lifetime_total_survives_restore() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    assert_eq!(ctx.read_lifetime_total(&gongde_pubkey), Some(0));
+
+    ctx.increment_times(&user, 3);
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 5);
+    ctx.send(&[instruction], &user, &[]).expect("增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(8));
+    assert_eq!(ctx.read_lifetime_total(&gongde_pubkey), Some(8));
+
+    // 回滚清零当前功德，累计功德保留
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 0, NOW);
+    ctx.send(&[instruction], &user, &[]).expect("回滚失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+    assert_eq!(ctx.read_lifetime_total(&gongde_pubkey), Some(8));
+
+    ctx.increment(&user).expect("回滚后增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    assert_eq!(ctx.read_lifetime_total(&gongde_pubkey), Some(9));
+}
+
+#[test]
+fn test_transfer_does_not_change_lifetime_total() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    ctx.increment_times(&sender, 4);
+
+    // 转赠只是转移功德，不是新增：转出者的累计功德不减少，接收者的累计功德也不增加
+    ctx.transfer_merit(&sender, &recipient_gongde, 3).expect("转赠失败");
+    assert_eq!(ctx.read_value(&sender_gongde), Some(1));
+    assert_eq!(ctx.read_lifetime_total(&sender_gongde), Some(4));
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(3));
+    assert_eq!(ctx.read_lifetime_total(&recipient_gongde), Some(0));
+
+    // 别人帮忙增加的功德计入账户的累计功德
+    ctx.increment_for(&recipient_gongde, &sender).expect("代为增加功德失败");
+    assert_eq!(ctx.read_lifetime_total(&recipient_gongde), Some(1));
+}
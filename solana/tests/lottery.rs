@@ -0,0 +1,299 @@
+// ========================================
+// 彩票测试 - CreateLottery / EnterLottery / SettleLottery
+// 开奖依赖 SlotHashes 系统变量，测试中直接写入结束slot的哈希，让中奖票号完全确定；哈希过期后本轮作废并退还票款
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{lottery_pending_refunds, lottery_size, lottery_ticket_owner, CreateLotteryArgs, SLOT_HASHES_MAX_ENTRIES},
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    slot_hashes::SlotHashes,
+    transaction::TransactionError,
+};
+
+use common::custom_error;
+
+/// 测试中开始投注的slot
+const START_SLOT: u64 = 100;
+
+/// 测试中结束投注的slot
+const END_SLOT: u64 = 200;
+
+/// 测试使用的票价
+const PRICE: u32 = 2;
+
+/// 前8个字节按小端序是 ticket 的哈希，对总票数取余后就是 ticket 本身
+fn hash_for_ticket(ticket: u64) -> Hash {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&ticket.to_le_bytes());
+    Hash::new_from_array(bytes)
+}
+
+/// 把结束slot的哈希写入 SlotHashes
+fn set_end_slot_hash(ctx: &mut common::TestContext, hash: Hash) {
+    ctx.svm.set_sysvar(&SlotHashes::new(&[(END_SLOT, hash)]));
+}
+
+/// 创建一个有 merit 点功德的用户
+fn player_with_merit(ctx: &mut common::TestContext, merit: u64) -> Keypair {
+    let player = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&player);
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &player.pubkey(), merit);
+    ctx.send(&[instruction], &player, &[]).expect("增加功德失败");
+    player
+}
+
+/// 在 START_SLOT 创建一轮 [START_SLOT, END_SLOT) 的彩票
+fn setup_round(ctx: &mut common::TestContext) -> (Keypair, Pubkey) {
+    ctx.set_slot(START_SLOT);
+    let admin = ctx.new_user();
+    let args = CreateLotteryArgs { round_id: 1, start_slot: START_SLOT, end_slot: END_SLOT, ticket_price: PRICE };
+    ctx.create_lottery(&admin, &args).expect("创建彩票失败");
+    let lottery = ctx.lottery_address(&admin.pubkey(), args.round_id);
+    (admin, lottery)
+}
+
+#[test]
+fn test_winner_receives_jackpot() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+    let alice = player_with_merit(&mut ctx, 10);
+    let bob = player_with_merit(&mut ctx, 10);
+    let alice_gongde = ctx.gongde_address(&alice.pubkey());
+    let bob_gongde = ctx.gongde_address(&bob.pubkey());
+
+    // alice 持有票号 [0, 3)，bob 持有票号 [3, 5)
+    ctx.enter_lottery(&alice, &lottery, 3).expect("alice 买彩票失败");
+    ctx.enter_lottery(&bob, &lottery, 2).expect("bob 买彩票失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(10 - 3 * PRICE));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(10 - 2 * PRICE));
+
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert_eq!((round.total_tickets, round.entry_count, round.jackpot()), (5, 2, u64::from(5 * PRICE)));
+    let account = ctx.svm.get_account(&lottery).unwrap();
+    assert_eq!(account.data.len(), lottery_size(2));
+    assert_eq!(account.lamports, ctx.svm.minimum_balance_for_rent_exemption(lottery_size(2)));
+
+    // 哈希指向票号 4，属于 bob
+    ctx.set_slot(END_SLOT + 1);
+    set_end_slot_hash(&mut ctx, hash_for_ticket(4));
+    ctx.settle_lottery(&admin, &lottery, Some(&bob_gongde)).expect("开奖失败");
+
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert!(round.settled);
+    assert_eq!(round.winning_ticket, 4);
+    assert_eq!(ctx.read_value(&bob_gongde), Some(10 - 2 * PRICE + 5 * PRICE));
+    assert_eq!(ctx.read_value(&alice_gongde), Some(10 - 3 * PRICE));
+    // 奖池只加到当前功德，累计功德不变
+    assert_eq!(ctx.read_lifetime_total(&bob_gongde), Some(10));
+}
+
+#[test]
+fn test_settle_rejects_wrong_winner() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+    let alice = player_with_merit(&mut ctx, 10);
+    let bob = player_with_merit(&mut ctx, 10);
+    ctx.enter_lottery(&alice, &lottery, 3).expect("alice 买彩票失败");
+    ctx.enter_lottery(&bob, &lottery, 2).expect("bob 买彩票失败");
+
+    // 哈希指向票号 1，属于 alice，传入 bob 的账户或不传都不能开奖
+    ctx.set_slot(END_SLOT + 1);
+    set_end_slot_hash(&mut ctx, hash_for_ticket(1));
+    let bob_gongde = ctx.gongde_address(&bob.pubkey());
+    let result = ctx.settle_lottery(&admin, &lottery, Some(&bob_gongde));
+    assert_eq!(result.unwrap_err().err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    let result = ctx.settle_lottery(&admin, &lottery, None);
+    assert_eq!(result.unwrap_err().err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    assert!(!ctx.read_lottery(&lottery).unwrap().settled);
+
+    let data = ctx.svm.get_account(&lottery).unwrap().data;
+    assert_eq!(lottery_ticket_owner(&data, 1), Ok(Some(ctx.gongde_address(&alice.pubkey()))));
+    let alice_gongde = ctx.gongde_address(&alice.pubkey());
+    ctx.settle_lottery(&admin, &lottery, Some(&alice_gongde)).expect("开奖失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(10 - 3 * PRICE + 5 * PRICE));
+}
+
+#[test]
+fn test_settle_before_end_slot_fails() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+    let player = player_with_merit(&mut ctx, 10);
+    ctx.enter_lottery(&player, &lottery, 1).expect("买彩票失败");
+    let player_gongde = ctx.gongde_address(&player.pubkey());
+
+    // 结束slot本身的哈希还没有产生
+    for slot in [START_SLOT + 1, END_SLOT] {
+        ctx.set_slot(slot);
+        let result = ctx.settle_lottery(&admin, &lottery, Some(&player_gongde));
+        assert_eq!(result.unwrap_err().err, custom_error(GongDeError::LotteryNotEnded));
+    }
+    assert!(!ctx.read_lottery(&lottery).unwrap().settled);
+}
+
+#[test]
+fn test_settle_twice_fails() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+    let player = player_with_merit(&mut ctx, 10);
+    ctx.enter_lottery(&player, &lottery, 1).expect("买彩票失败");
+    let player_gongde = ctx.gongde_address(&player.pubkey());
+
+    ctx.set_slot(END_SLOT + 1);
+    set_end_slot_hash(&mut ctx, hash_for_ticket(0));
+    ctx.settle_lottery(&admin, &lottery, Some(&player_gongde)).expect("开奖失败");
+    assert_eq!(ctx.read_value(&player_gongde), Some(10));
+
+    ctx.set_slot(END_SLOT + 2);
+    let result = ctx.settle_lottery(&admin, &lottery, Some(&player_gongde));
+    assert_eq!(result.unwrap_err().err, custom_error(GongDeError::LotteryAlreadySettled));
+    assert_eq!(ctx.read_value(&player_gongde), Some(10));
+}
+
+#[test]
+fn test_settle_without_entries_is_noop() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+
+    // 没有人投注时不需要 SlotHashes 中有结束slot的哈希，也不需要中奖账户
+    ctx.set_slot(END_SLOT + 1);
+    ctx.settle_lottery(&admin, &lottery, None).expect("开奖失败");
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert!(round.settled);
+    assert_eq!((round.total_tickets, round.winning_ticket, round.jackpot()), (0, 0, 0));
+}
+
+#[test]
+fn test_settle_requires_end_slot_hash() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+    let player = player_with_merit(&mut ctx, 10);
+    ctx.enter_lottery(&player, &lottery, 1).expect("买彩票失败");
+    let player_gongde = ctx.gongde_address(&player.pubkey());
+
+    // 还在 SlotHashes 的保留范围内但查不到结束slot的哈希时不能作废，只能稍后重试
+    ctx.set_slot(END_SLOT + SLOT_HASHES_MAX_ENTRIES);
+    ctx.svm.set_sysvar(&SlotHashes::new(&[(END_SLOT + 299, hash_for_ticket(0)), (END_SLOT + 88, Hash::default())]));
+    let result = ctx.settle_lottery(&admin, &lottery, Some(&player_gongde));
+    assert_eq!(result.unwrap_err().err, custom_error(GongDeError::SlotHashUnavailable));
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert!(!round.settled && !round.voided);
+    assert_eq!(ctx.read_value(&player_gongde), Some(10 - PRICE));
+}
+
+#[test]
+fn test_expired_slot_hash_voids_and_refunds() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+    let alice = player_with_merit(&mut ctx, 10);
+    let bob = player_with_merit(&mut ctx, 10);
+    let alice_gongde = ctx.gongde_address(&alice.pubkey());
+    let bob_gongde = ctx.gongde_address(&bob.pubkey());
+    ctx.enter_lottery(&alice, &lottery, 2).expect("alice 买彩票失败");
+    ctx.enter_lottery(&bob, &lottery, 3).expect("bob 买彩票失败");
+    ctx.enter_lottery(&alice, &lottery, 1).expect("alice 再买彩票失败");
+
+    // 结束之后超过 SlotHashes 的保留范围才开奖，最旧的记录都比结束slot新
+    ctx.set_slot(END_SLOT + SLOT_HASHES_MAX_ENTRIES + 1);
+    ctx.svm.set_sysvar(&SlotHashes::new(&[(END_SLOT + SLOT_HASHES_MAX_ENTRIES, hash_for_ticket(0))]));
+    ctx.refund_lottery(&admin, &lottery, &[alice_gongde, alice_gongde]).expect("作废失败");
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert!(round.settled && round.voided);
+    // alice 两次投注的票款都退还，重复传入不会多退；bob 还没有退款
+    assert_eq!(ctx.read_value(&alice_gongde), Some(10));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(10 - 3 * PRICE));
+    let data = ctx.svm.get_account(&lottery).unwrap().data;
+    assert_eq!(lottery_pending_refunds(&data), Ok(vec![bob_gongde]));
+
+    // 作废之后任何人都可以继续给剩下的投注者退款，已经退款的账户不会再退
+    ctx.refund_lottery(&bob, &lottery, &[bob_gongde, alice_gongde]).expect("退款失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(10));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(10));
+    let data = ctx.svm.get_account(&lottery).unwrap().data;
+    assert_eq!(lottery_pending_refunds(&data), Ok(vec![]));
+
+    // 作废的轮次不会再开奖
+    set_end_slot_hash(&mut ctx, hash_for_ticket(0));
+    ctx.settle_lottery(&admin, &lottery, Some(&alice_gongde)).expect("作废的轮次只会退款");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(10));
+    assert!(ctx.read_lottery(&lottery).unwrap().voided);
+}
+
+#[test]
+fn test_enter_outside_window_fails() {
+    let mut ctx = common::setup();
+    ctx.set_slot(START_SLOT - 50);
+    let admin = ctx.new_user();
+    let args = CreateLotteryArgs { round_id: 9, start_slot: START_SLOT, end_slot: END_SLOT, ticket_price: PRICE };
+    ctx.create_lottery(&admin, &args).expect("创建彩票失败");
+    let lottery = ctx.lottery_address(&admin.pubkey(), args.round_id);
+    let player = player_with_merit(&mut ctx, 10);
+
+    for slot in [START_SLOT - 1, END_SLOT] {
+        ctx.set_slot(slot);
+        let result = ctx.enter_lottery(&player, &lottery, 1);
+        assert_eq!(result.unwrap_err().err, custom_error(GongDeError::LotteryClosed));
+    }
+    assert_eq!(ctx.read_value(&ctx.gongde_address(&player.pubkey())), Some(10));
+    assert_eq!(ctx.read_lottery(&lottery).unwrap().total_tickets, 0);
+
+    // 已经结束的轮次不能再创建
+    let args = CreateLotteryArgs { round_id: 10, ..args };
+    let result = ctx.create_lottery(&admin, &args);
+    assert_eq!(result.unwrap_err().err, custom_error(GongDeError::LotteryClosed));
+}
+
+#[test]
+fn test_enter_requires_enough_merit() {
+    let mut ctx = common::setup();
+    let (_admin, lottery) = setup_round(&mut ctx);
+    let player = player_with_merit(&mut ctx, 5);
+
+    let result = ctx.enter_lottery(&player, &lottery, 3);
+    assert_eq!(result.unwrap_err().err, custom_error(GongDeError::InsufficientMerit));
+    let result = ctx.enter_lottery(&player, &lottery, 0);
+    assert_eq!(result.unwrap_err().err, custom_error(GongDeError::InvalidAmount));
+    ctx.enter_lottery(&player, &lottery, 2).expect("买彩票失败");
+    assert_eq!(ctx.read_value(&ctx.gongde_address(&player.pubkey())), Some(1));
+}
+
+#[test]
+fn test_create_lottery_rejects_reused_round_id() {
+    let mut ctx = common::setup();
+    let (admin, lottery) = setup_round(&mut ctx);
+
+    let args = CreateLotteryArgs { round_id: 1, start_slot: START_SLOT, end_slot: END_SLOT + 10, ticket_price: 1 };
+    let result = ctx.create_lottery(&admin, &args);
+    assert_eq!(
+        result.unwrap_err().err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+    // 已有的轮次归本程序所有，内容没有被覆盖
+    assert_eq!(ctx.svm.get_account(&lottery).unwrap().owner, ctx.program_id);
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert_eq!((round.end_slot, round.ticket_price), (END_SLOT, PRICE));
+}
+
+#[test]
+fn test_prefunded_lottery_cannot_block_creation() {
+    let mut ctx = common::setup();
+    ctx.set_slot(START_SLOT);
+    let admin = ctx.new_user();
+    let lottery = ctx.lottery_address(&admin.pubkey(), 1);
+
+    // 有人抢先往轮次地址转账，管理员仍然可以使用这个轮次编号
+    ctx.prefund(&lottery);
+    let args = CreateLotteryArgs { round_id: 1, start_slot: START_SLOT, end_slot: END_SLOT, ticket_price: PRICE };
+    ctx.create_lottery(&admin, &args).expect("轮次地址被抢先转账后创建失败");
+
+    assert_eq!(ctx.svm.get_account(&lottery).unwrap().owner, ctx.program_id);
+    let round = ctx.read_lottery(&lottery).unwrap();
+    assert_eq!((round.admin, round.end_slot, round.total_tickets), (admin.pubkey(), END_SLOT, 0));
+}
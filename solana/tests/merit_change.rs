@@ -0,0 +1,49 @@
+// ========================================
+// 功德值变化事件测试 - 每次改写功德值都在交易日志中留下 MeritChangeEvent
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::MeritChangeEvent;
+use solana_sdk::signature::Signer;
+
+/// 解析交易日志中的功德值变化事件
+fn merit_changes(logs: &[String]) -> Vec<MeritChangeEvent> {
+    common::program_data(logs)
+        .iter()
+        .filter_map(|fields| MeritChangeEvent::from_log_data(fields.as_slice()))
+        .collect()
+}
+
+#[test]
+fn test_increment_and_transfer_emit_merit_changes() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    let global = ctx.global_address();
+    ctx.increment_times(&sender, 4);
+
+    // 个人功德和全局功德各一条
+    let instruction = ctx.increment_instruction(&sender_gongde, &sender.pubkey());
+    let meta = ctx.send(&[instruction], &sender, &[]).expect("增加功德失败");
+    assert_eq!(
+        merit_changes(&meta.logs),
+        vec![
+            MeritChangeEvent { account: sender_gongde, before: 4, after: 5 },
+            MeritChangeEvent { account: global, before: 4, after: 5 },
+        ]
+    );
+
+    // 转赠：转出者和接收者各一条，全局功德不变
+    let instruction = ctx.transfer_merit_instruction(&sender.pubkey(), &recipient_gongde, 3);
+    let meta = ctx.send(&[instruction], &sender, &[]).expect("转赠失败");
+    assert_eq!(
+        merit_changes(&meta.logs),
+        vec![
+            MeritChangeEvent { account: sender_gongde, before: 5, after: 2 },
+            MeritChangeEvent { account: recipient_gongde, before: 0, after: 3 },
+        ]
+    );
+}
@@ -0,0 +1,235 @@
+// ========================================
+// Migrate 指令测试 - 旧布局的账户升级到当前布局
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{
+    CreateCampaignArgs,
+    CreateLotteryArgs,
+    GongDeAccount,
+    FLAG_INITIALIZED,
+    FLAG_PUBLIC,
+    GONGDE_ACCOUNT_SIZE,
+    GONGDE_LIFETIME_OFFSET,
+    GONGDE_MILESTONES_OFFSET,
+    GONGDE_VALUE_SIZE,
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+#[test]
+fn test_migrate_two_legacy_accounts_in_one_transaction() {
+    let mut ctx = common::setup();
+    let payer = ctx.new_user();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let alice_gongde = ctx.create_legacy_account(&alice.pubkey(), 7);
+    let bob_gongde = ctx.create_legacy_account(&bob.pubkey(), 42);
+    let payer_before = ctx.svm.get_balance(&payer.pubkey()).unwrap();
+
+    // 迁移不改变账户行为，由第三方打包迁移并支付租金差额
+    let instructions = [
+        ctx.migrate_instruction(&alice_gongde, &alice.pubkey(), &payer.pubkey()),
+        ctx.migrate_instruction(&bob_gongde, &bob.pubkey(), &payer.pubkey()),
+    ];
+    ctx.send(&instructions, &payer, &[]).expect("迁移失败");
+
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+    let deficit = rent - ctx.svm.minimum_balance_for_rent_exemption(GONGDE_VALUE_SIZE);
+    for (gongde_pubkey, value) in [(alice_gongde, 7), (bob_gongde, 42)] {
+        let account = ctx.svm.get_account(&gongde_pubkey).unwrap();
+        assert_eq!(account.data.len(), GONGDE_ACCOUNT_SIZE);
+        assert_eq!(account.lamports, rent);
+        let gongde = GongDeAccount::unpack(&account.data).unwrap();
+        assert_eq!(gongde.value, value);
+        // 旧账户没有记录累计功德，从当前功德值开始
+        assert_eq!(gongde.lifetime_total, u64::from(value));
+        assert!(gongde.is_initialized());
+        assert!(gongde.is_public());
+    }
+    let payer_after = ctx.svm.get_balance(&payer.pubkey()).unwrap();
+    assert_eq!(payer_before - payer_after, 2 * deficit + 5000);
+
+    // 迁移后的账户仍然是公开功德箱，任何人都可以继续增加功德
+    ctx.increment_for(&alice_gongde, &bob).expect("迁移后增加功德失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(8));
+}
+
+#[test]
+fn test_migrate_flagged_account_keeps_flags() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    // 布局v2：功德值 + 标志位，私有功德箱
+    let mut data = 42u32.to_le_bytes().to_vec();
+    data.push(FLAG_INITIALIZED);
+    assert_eq!(data.len(), GONGDE_LIFETIME_OFFSET);
+    let gongde_pubkey = ctx.create_legacy_account_with_data(&user.pubkey(), data);
+
+    let instruction = ctx.migrate_instruction(&gongde_pubkey, &user.pubkey(), &user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("迁移失败");
+
+    let account = ctx.svm.get_account(&gongde_pubkey).unwrap();
+    assert_eq!(account.data.len(), GONGDE_ACCOUNT_SIZE);
+    let gongde = GongDeAccount::unpack(&account.data).unwrap();
+    assert_eq!((gongde.value, gongde.lifetime_total), (42, 42));
+    assert!(gongde.is_initialized());
+    assert!(!gongde.is_public());
+
+    // 迁移后继续增加功德，累计功德同步增加
+    ctx.increment(&user).expect("迁移后增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(43));
+    assert_eq!(ctx.read_lifetime_total(&gongde_pubkey), Some(43));
+}
+
+#[test]
+fn test_migrate_lifetime_account_keeps_lifetime_total() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    // 布局v3：累计功德已经比当前功德高（转出过），迁移时不能被覆盖
+    let mut data = 5u32.to_le_bytes().to_vec();
+    data.push(FLAG_INITIALIZED | FLAG_PUBLIC);
+    data.extend_from_slice(&42u64.to_le_bytes());
+    assert_eq!(data.len(), GONGDE_MILESTONES_OFFSET);
+    let gongde_pubkey = ctx.create_legacy_account_with_data(&user.pubkey(), data);
+
+    let instruction = ctx.migrate_instruction(&gongde_pubkey, &user.pubkey(), &user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("迁移失败");
+
+    let account = ctx.svm.get_account(&gongde_pubkey).unwrap();
+    assert_eq!(account.data.len(), GONGDE_ACCOUNT_SIZE);
+    let gongde = GongDeAccount::unpack(&account.data).unwrap();
+    assert_eq!((gongde.value, gongde.lifetime_total), (5, 42));
+    assert!(gongde.is_public());
+
+    // 迁移后跨过里程碑开始记录
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 5);
+    ctx.send(&[instruction], &user, &[]).expect("迁移后增加功德失败");
+    assert_eq!(ctx.read_milestones(&gongde_pubkey).len(), 1);
+}
+
+#[test]
+fn test_migrate_current_account_is_noop() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account_with(&user, false);
+    let before = ctx.svm.get_account(&gongde_pubkey).unwrap();
+
+    let instruction = ctx.migrate_instruction(&gongde_pubkey, &user.pubkey(), &user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("重复迁移应直接跳过");
+
+    // 私有功德箱不会被迁移成公开的
+    let after = ctx.svm.get_account(&gongde_pubkey).unwrap();
+    assert_eq!(after.data, before.data);
+    assert_eq!(after.lamports, before.lamports);
+}
+
+#[test]
+fn test_migrate_requires_payer_signature() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let other = ctx.new_user();
+    let gongde_pubkey = ctx.create_legacy_account(&user.pubkey(), 1);
+
+    let mut instruction = ctx.migrate_instruction(&gongde_pubkey, &user.pubkey(), &user.pubkey());
+    instruction.accounts[1].is_signer = false;
+    let err = ctx.send(&[instruction], &other, &[]).unwrap_err();
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    assert_eq!(ctx.svm.get_account(&gongde_pubkey).unwrap().data.len(), GONGDE_VALUE_SIZE);
+}
+
+#[test]
+fn test_initialize_legacy_sized_account_requires_migration() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    // 5字节的旧布局账户，还没有初始化
+    let gongde_pubkey = ctx.create_legacy_account_with_data(&user.pubkey(), vec![3, 0, 0, 0, 0]);
+
+    let instruction = ctx.initialize_instruction(&user.pubkey(), false);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall));
+    assert!(err.meta.logs.iter().any(|log| log.contains("Migrate")));
+    assert_eq!(ctx.svm.get_account(&gongde_pubkey).unwrap().data, vec![3, 0, 0, 0, 0]);
+
+    // 迁移后保留原来的标志位（未初始化），这时就可以初始化了
+    let migrate = ctx.migrate_instruction(&gongde_pubkey, &user.pubkey(), &user.pubkey());
+    ctx.send(&[migrate], &user, &[]).expect("迁移失败");
+    let instruction = ctx.initialize_instruction(&user.pubkey(), false);
+    ctx.send(&[instruction], &user, &[]).expect("迁移后初始化失败");
+    let gongde = GongDeAccount::unpack(&ctx.svm.get_account(&gongde_pubkey).unwrap().data).unwrap();
+    assert_eq!(gongde.value, 3);
+    assert!(gongde.is_initialized());
+    assert!(!gongde.is_public());
+}
+
+/// 发送迁移指令，返回失败的错误；确认账户数据和余额都没有被改动
+fn assert_migrate_rejected(
+    ctx: &mut common::TestContext,
+    target: &Pubkey,
+    owner: &Pubkey,
+    payer: &Keypair,
+    expected: InstructionError,
+) {
+    let before = ctx.svm.get_account(target).unwrap();
+    let instruction = ctx.migrate_instruction(target, owner, &payer.pubkey());
+    let err = ctx.send(&[instruction], payer, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, expected));
+    let after = ctx.svm.get_account(target).unwrap();
+    assert_eq!((after.data, after.lamports), (before.data, before.lamports));
+}
+
+#[test]
+fn test_migrate_rejects_wrong_owner() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let mallory = ctx.new_user();
+    let gongde_pubkey = ctx.create_legacy_account(&user.pubkey(), 1);
+
+    // 传入的用户派生不出这个地址
+    assert_migrate_rejected(&mut ctx, &gongde_pubkey, &mallory.pubkey(), &mallory, InstructionError::InvalidSeeds);
+}
+
+#[test]
+fn test_migrate_rejects_global_pda() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    ctx.create_gongde_account(&user);
+    ctx.increment(&user).expect("增加功德失败");
+    let global_pubkey = ctx.global_address();
+    assert_eq!(ctx.svm.get_account(&global_pubkey).unwrap().data.len(), GONGDE_VALUE_SIZE);
+
+    // 全局功德PDA和旧布局一样是4字节，但不是任何用户派生的地址
+    assert_migrate_rejected(&mut ctx, &global_pubkey, &user.pubkey(), &user, InstructionError::InvalidSeeds);
+}
+
+#[test]
+fn test_migrate_rejects_campaign_pda() {
+    let mut ctx = common::setup();
+    let organizer = ctx.new_user();
+    ctx.create_gongde_account(&organizer);
+    ctx.increment_times(&organizer, 3);
+    let args = CreateCampaignArgs { campaign_id: 1, start: 1_000_000, end: 1_003_600, multiplier: 2, budget: 3 };
+    ctx.create_campaign(&organizer, &args).expect("创建配捐活动失败");
+    let campaign = ctx.campaign_address(&organizer.pubkey(), args.campaign_id);
+
+    assert_migrate_rejected(&mut ctx, &campaign, &organizer.pubkey(), &organizer, InstructionError::InvalidAccountData);
+}
+
+#[test]
+fn test_migrate_rejects_lottery_pda() {
+    let mut ctx = common::setup();
+    ctx.set_slot(100);
+    let admin = ctx.new_user();
+    let args = CreateLotteryArgs { round_id: 1, start_slot: 100, end_slot: 200, ticket_price: 2 };
+    ctx.create_lottery(&admin, &args).expect("创建彩票失败");
+    let lottery = ctx.lottery_address(&admin.pubkey(), args.round_id);
+
+    assert_migrate_rejected(&mut ctx, &lottery, &admin.pubkey(), &admin, InstructionError::InvalidAccountData);
+}
@@ -0,0 +1,45 @@
+// ========================================
+// 功德里程碑测试 - 第一次跨过里程碑时记录达成时间
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::Milestone;
+use solana_sdk::signature::Signer;
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn test_milestones_recorded_once() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    assert!(ctx.read_milestones(&gongde_pubkey).is_empty());
+
+    // 从5增加到105，一次跨过10和100两个里程碑
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 5);
+    ctx.send(&[instruction], &user, &[]).expect("增加功德失败");
+    assert!(ctx.read_milestones(&gongde_pubkey).is_empty());
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 100);
+    ctx.send(&[instruction], &user, &[]).expect("增加功德失败");
+    let reached = vec![
+        Milestone { milestone: 10, reached_at: NOW },
+        Milestone { milestone: 100, reached_at: NOW },
+    ];
+    assert_eq!(ctx.read_milestones(&gongde_pubkey), reached);
+
+    // 之后再增加不会改写达成时间
+    ctx.set_unix_timestamp(NOW + 3_600);
+    ctx.increment(&user).expect("增加功德失败");
+    assert_eq!(ctx.read_milestones(&gongde_pubkey), reached);
+
+    // 回滚到里程碑以下后再次跨过，仍然保留第一次达成的时间
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 0, NOW);
+    ctx.send(&[instruction], &user, &[]).expect("回滚失败");
+    let instruction = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 100);
+    ctx.send(&[instruction], &user, &[]).expect("增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(100));
+    assert_eq!(ctx.read_milestones(&gongde_pubkey), reached);
+}
@@ -0,0 +1,81 @@
+// ========================================
+// 批量开通测试 - 管理员代付租金，一笔交易为多个用户创建功德账户
+// ========================================
+
+mod common;
+
+use common::TestContext;
+use gong_de_increase::utils::GONGDE_ACCOUNT_SIZE;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+};
+
+/// 每笔交易开通的用户数，与客户端按交易大小分组的结果一致
+const USERS_PER_TRANSACTION: usize = 4;
+
+/// 还没有功德账户的用户，重复运行时已开通的用户被排除
+fn pending_users<'a>(ctx: &TestContext, users: &'a [Keypair]) -> Vec<&'a Keypair> {
+    users
+        .iter()
+        .filter(|user| ctx.read_value(&ctx.gongde_address(&user.pubkey())).is_none())
+        .collect()
+}
+
+/// 按批发送开通交易，管理员是付款人，批内每个用户共同签名
+fn onboard(ctx: &mut TestContext, admin: &Keypair, users: &[&Keypair]) {
+    for batch in users.chunks(USERS_PER_TRANSACTION) {
+        let instructions: Vec<Instruction> = batch
+            .iter()
+            .flat_map(|user| ctx.sponsored_create_instructions(&admin.pubkey(), &user.pubkey(), true))
+            .collect();
+        ctx.send(&instructions, admin, batch).expect("批量开通失败");
+    }
+}
+
+#[test]
+fn test_onboard_dozen_users_skips_existing() {
+    let mut ctx = common::setup();
+    let admin = ctx.new_user();
+    // 用户没有任何余额，租金和交易费全部由管理员支付
+    let mut users: Vec<Keypair> = (0..11).map(|_| Keypair::new()).collect();
+    let veteran = ctx.new_user();
+    let veteran_gongde = ctx.create_gongde_account(&veteran);
+    ctx.increment_times(&veteran, 3);
+    users.insert(5, veteran);
+
+    let pending = pending_users(&ctx, &users);
+    assert_eq!(pending.len(), 11);
+    let admin_before = ctx.svm.get_balance(&admin.pubkey()).unwrap();
+    onboard(&mut ctx, &admin, &pending);
+
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+    let admin_after = ctx.svm.get_balance(&admin.pubkey()).unwrap();
+    assert!(admin_before - admin_after >= rent * 11);
+    for user in &users {
+        let expected = if ctx.gongde_address(&user.pubkey()) == veteran_gongde { 3 } else { 0 };
+        assert_eq!(ctx.read_value(&ctx.gongde_address(&user.pubkey())), Some(expected));
+    }
+    assert_eq!(ctx.svm.get_balance(&users[0].pubkey()).unwrap_or(0), 0);
+
+    // 重新运行时没有需要开通的用户
+    assert!(pending_users(&ctx, &users).is_empty());
+}
+
+#[test]
+fn test_onboard_existing_user_fails_whole_batch() {
+    let mut ctx = common::setup();
+    let admin = ctx.new_user();
+    let veteran = ctx.new_user();
+    ctx.create_gongde_account(&veteran);
+    let newcomer = Keypair::new();
+
+    // 不跳过已开通的用户时，同一笔交易里的其他用户也开通不了
+    let instructions: Vec<Instruction> = [&veteran, &newcomer]
+        .iter()
+        .flat_map(|user| ctx.sponsored_create_instructions(&admin.pubkey(), &user.pubkey(), true))
+        .collect();
+    assert!(ctx.send(&instructions, &admin, &[&veteran, &newcomer]).is_err());
+    let newcomer_gongde = ctx.gongde_address(&newcomer.pubkey());
+    assert_eq!(ctx.read_value(&newcomer_gongde), None);
+}
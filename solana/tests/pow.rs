@@ -0,0 +1,186 @@
+// ========================================
+// 工作量证明测试 - 全局配置开启难度后，只能用附带有效 nonce 的 IncrementWithProof 增加功德
+// 难度为0（默认）时 Increment 和以前完全一样
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    instruction,
+    utils::{meets_pow_difficulty, pow_hash, read_last_increment_slot},
+};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use common::custom_error;
+
+/// 测试使用的难度，平均256次就能找到有效的 nonce
+const DIFFICULTY: u8 = 8;
+
+/// 功德账户第一个满足（或不满足）难度的 nonce
+fn first_nonce(gongde_pubkey: &Pubkey, last_slot: u64, valid: bool) -> u64 {
+    (0..).find(|nonce| meets_pow_difficulty(&pow_hash(gongde_pubkey, last_slot, *nonce), DIFFICULTY) == valid).unwrap()
+}
+
+fn sdk_increment(ctx: &common::TestContext, user: &Keypair) -> Instruction {
+    instruction::increment(&ctx.program_id, &ctx.gongde_address(&user.pubkey()), &user.pubkey(), &ctx.global_address())
+}
+
+fn increment_with_proof(ctx: &common::TestContext, user: &Keypair, nonce: u64) -> Instruction {
+    instruction::increment_with_proof(
+        &ctx.program_id,
+        &ctx.gongde_address(&user.pubkey()),
+        &user.pubkey(),
+        &ctx.global_address(),
+        nonce,
+    )
+}
+
+fn last_increment_slot(ctx: &common::TestContext, gongde_pubkey: &Pubkey) -> Option<u64> {
+    read_last_increment_slot(&ctx.svm.get_account(gongde_pubkey).unwrap().data)
+}
+
+#[test]
+fn test_difficulty_zero_keeps_increment_unchanged() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 全局配置不存在和难度为0都不要求证明
+    ctx.send(&[sdk_increment(&ctx, &user)], &user, &[]).expect("未设置全局配置时增加功德失败");
+    ctx.set_pow_difficulty(0);
+    ctx.send(&[sdk_increment(&ctx, &user)], &user, &[]).expect("难度为0时增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+    assert_eq!(last_increment_slot(&ctx, &gongde_pubkey), Some(0));
+
+    // 难度为0时任何 nonce 都有效
+    ctx.set_slot(50);
+    ctx.send(&[increment_with_proof(&ctx, &user, 12345)], &user, &[]).expect("难度为0时附带证明增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+    assert_eq!(last_increment_slot(&ctx, &gongde_pubkey), Some(50));
+}
+
+#[test]
+fn test_enabled_difficulty_requires_proof() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.set_pow_difficulty(DIFFICULTY);
+
+    let err = ctx.send(&[sdk_increment(&ctx, &user)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::ProofOfWorkRequired));
+    assert!(
+        common::logged(&err.meta.logs, "已开启工作量证明（难度 {} 比特），请使用 IncrementWithProof 指令", &[DIFFICULTY as u64]),
+        "{:?}",
+        err.meta.logs
+    );
+    let increment_by = instruction::increment_by(
+        &ctx.program_id,
+        &gongde_pubkey,
+        &user.pubkey(),
+        &ctx.global_address(),
+        3,
+    );
+    let err = ctx.send(&[increment_by], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::ProofOfWorkRequired));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+
+    // 旧客户端的编码不附带全局配置，合约从全局功德PDA中的配置副本读到难度，同样要求工作量证明
+    let err = ctx.increment(&user).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::ProofOfWorkRequired));
+    let increment_by = ctx.increment_by_instruction(&gongde_pubkey, &user.pubkey(), 3);
+    let err = ctx.send(&[increment_by], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::ProofOfWorkRequired));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+}
+
+#[test]
+fn test_enabled_difficulty_pauses_shared_increment() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    ctx.shared_increment(&alice, 2).expect("未开启工作量证明时共享账本增加失败");
+    ctx.set_pow_difficulty(DIFFICULTY);
+
+    // 共享账本没有附带证明的版本，已有条目和新用户都不能绕过难度增加功德
+    let err = ctx.shared_increment(&alice, 1).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::ProofOfWorkRequired));
+    let err = ctx.shared_increment(&bob, 1).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::ProofOfWorkRequired));
+    assert_eq!(ctx.read_shared_value(&alice.pubkey()), Some(2));
+    assert_eq!(ctx.read_shared_value(&bob.pubkey()), None);
+    assert_eq!(ctx.read_value(&ctx.global_address()), Some(2));
+
+    // 关闭后恢复
+    ctx.set_pow_difficulty(0);
+    ctx.shared_increment(&alice, 1).expect("关闭工作量证明后共享账本增加失败");
+    assert_eq!(ctx.read_shared_value(&alice.pubkey()), Some(3));
+}
+
+#[test]
+fn test_valid_proof_increments_and_invalid_nonce_is_rejected() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.set_pow_difficulty(DIFFICULTY);
+
+    // 不达标的 nonce 被拒绝，功德不变
+    let invalid = first_nonce(&gongde_pubkey, 0, false);
+    let err = ctx.send(&[increment_with_proof(&ctx, &user, invalid)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidProofOfWork));
+
+    // 去掉版本字段和全局配置的旧编码同样按配置副本中的难度检查
+    let legacy = common::legacy_encoding(&increment_with_proof(&ctx, &user, invalid));
+    let err = ctx.send(&[legacy], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidProofOfWork));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+
+    // 有效的 nonce 增加功德并记录当前slot
+    ctx.set_slot(100);
+    let nonce = first_nonce(&gongde_pubkey, 0, true);
+    ctx.send(&[increment_with_proof(&ctx, &user, nonce)], &user, &[]).expect("有效的工作量证明被拒绝");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    assert_eq!(last_increment_slot(&ctx, &gongde_pubkey), Some(100));
+
+    // 下一次的证明按新记录的slot计算
+    ctx.set_slot(101);
+    let invalid = first_nonce(&gongde_pubkey, 100, false);
+    let err = ctx.send(&[increment_with_proof(&ctx, &user, invalid)], &user, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidProofOfWork));
+    let nonce = first_nonce(&gongde_pubkey, 100, true);
+    ctx.send(&[increment_with_proof(&ctx, &user, nonce)], &user, &[]).expect("第二次工作量证明被拒绝");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+    assert_eq!(last_increment_slot(&ctx, &gongde_pubkey), Some(101));
+}
+
+#[test]
+fn test_nonce_is_bound_to_gongde_account() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account(&alice);
+    let bob_gongde = ctx.create_gongde_account(&bob);
+    ctx.set_pow_difficulty(DIFFICULTY);
+
+    // 两个新账户上次证明的slot都是0，为 alice 的账户算出的 nonce 不能用在 bob 的公开功德箱上
+    let nonce = (0..)
+        .find(|nonce| {
+            meets_pow_difficulty(&pow_hash(&alice_gongde, 0, *nonce), DIFFICULTY)
+                && !meets_pow_difficulty(&pow_hash(&bob_gongde, 0, *nonce), DIFFICULTY)
+        })
+        .unwrap();
+    let for_bob = instruction::increment_with_proof(&ctx.program_id, &bob_gongde, &alice.pubkey(), &ctx.global_address(), nonce);
+    let err = ctx.send(&[for_bob], &alice, &[]).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidProofOfWork));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(0));
+
+    // 按 bob 的账户算出的 nonce 由谁付款都有效
+    let nonce = first_nonce(&bob_gongde, 0, true);
+    let for_bob = instruction::increment_with_proof(&ctx.program_id, &bob_gongde, &alice.pubkey(), &ctx.global_address(), nonce);
+    ctx.send(&[for_bob], &alice, &[]).expect("按功德账户算出的 nonce 被拒绝");
+    assert_eq!(ctx.read_value(&bob_gongde), Some(1));
+}
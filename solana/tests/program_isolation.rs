@@ -0,0 +1,74 @@
+// ========================================
+// 跨版本隔离测试 - 同一份合约部署在两个程序ID下
+// ========================================
+
+mod common;
+
+use solana_sdk::{instruction::InstructionError, signature::Signer, transaction::TransactionError};
+
+fn incorrect_program_id() -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+}
+
+#[test]
+fn test_each_version_only_mutates_its_own_accounts() {
+    let mut ctx = common::setup();
+    let old_version = ctx.program_id;
+    let new_version = ctx.deploy_another_version();
+    let user = ctx.new_user();
+
+    // 同一个用户在两个版本下各有一个账户，地址不同，互不影响
+    let old_gongde = ctx.create_gongde_account(&user);
+    ctx.increment_times(&user, 3);
+    ctx.program_id = new_version;
+    let new_gongde = ctx.create_gongde_account(&user);
+    ctx.increment(&user).expect("新版本增加功德失败");
+    assert_ne!(old_gongde, new_gongde);
+    assert_eq!(ctx.read_value(&old_gongde), Some(3));
+    assert_eq!(ctx.read_value(&new_gongde), Some(1));
+
+    // 新版本不能给旧版本的账户增加功德
+    let instruction = ctx.increment_instruction(&old_gongde, &user.pubkey());
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, incorrect_program_id());
+    let instruction = ctx.increment_by_instruction(&old_gongde, &user.pubkey(), 5);
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, incorrect_program_id());
+
+    // 新版本不能从旧版本的账户转出功德，也不能转入旧版本的账户
+    let mut instruction = ctx.transfer_merit_instruction(&user.pubkey(), &new_gongde, 1);
+    instruction.accounts[0].pubkey = old_gongde;
+    let recipient = ctx.new_user();
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    instruction.accounts[2].pubkey = recipient_gongde;
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, incorrect_program_id());
+    let instruction = ctx.transfer_merit_instruction(&user.pubkey(), &old_gongde, 1);
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, incorrect_program_id());
+
+    // 新版本也不能关闭旧版本的账户
+    let mut instruction = ctx.close_instruction(&user.pubkey());
+    instruction.accounts[0].pubkey = old_gongde;
+    assert!(ctx.send(&[instruction], &user, &[]).is_err());
+
+    ctx.program_id = old_version;
+    assert_eq!(ctx.read_value(&old_gongde), Some(3));
+    assert_eq!(ctx.read_value(&new_gongde), Some(1));
+    assert_eq!(ctx.svm.get_account(&old_gongde).unwrap().owner, old_version);
+}
+
+#[test]
+fn test_close_if_empty_rejects_other_version() {
+    let mut ctx = common::setup();
+    let old_version = ctx.program_id;
+    let user = ctx.new_user();
+    let old_gongde = ctx.create_gongde_account(&user);
+
+    ctx.program_id = ctx.deploy_another_version();
+    let mut instruction = ctx.close_if_empty_instruction(&user.pubkey());
+    instruction.accounts[0].pubkey = old_gongde;
+    assert_eq!(ctx.send(&[instruction], &user, &[]).unwrap_err().err, incorrect_program_id());
+
+    // 空账户仍然可以由创建它的版本关闭
+    ctx.program_id = old_version;
+    let instruction = ctx.close_if_empty_instruction(&user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("旧版本关闭自己的空账户失败");
+    assert_eq!(ctx.read_value(&old_gongde), None);
+}
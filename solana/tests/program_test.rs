@@ -0,0 +1,161 @@
+// ========================================
+// ProgramTest 集成测试 - 按第三方项目的用法加载本合约
+// 只依赖 gong_de_increase 的 `program-test` feature：注册合约、预置功德账户、发送指令
+// 运行：cargo test --features program-test --test program_test
+// ========================================
+
+use gong_de_increase::{
+    instruction,
+    program_test::{add_gongde_account, add_to_program_test},
+    utils::{
+        derive_config_address, derive_gongde_account_address, derive_global_gongde_pda_address, is_public_account,
+        read_gongde_value, MeritReport, GONGDE_ACCOUNT_SEED, GONGDE_ACCOUNT_SIZE,
+    },
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use solana_system_interface::{instruction as system_instruction, program as system_program};
+
+#[tokio::test]
+async fn test_third_party_program_test_setup() {
+    // 第三方项目的测试环境里通常还有自己的程序，这里只加载功德合约
+    let program_id = Pubkey::new_unique();
+    let mut pt = ProgramTest::default();
+    add_to_program_test(&mut pt, program_id);
+
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let alice_gongde = add_gongde_account(&mut pt, &program_id, &alice.pubkey(), 7);
+    let bob_gongde = add_gongde_account(&mut pt, &program_id, &bob.pubkey(), 35);
+
+    let (banks_client, payer, recent_blockhash) = pt.start().await;
+
+    // 预置的账户可以直接汇总，不用先发交易增加功德
+    let report = instruction::report(&program_id, &[alice_gongde, bob_gongde]);
+    let transaction = Transaction::new_signed_with_payer(&[report], Some(&payer.pubkey()), &[&payer], recent_blockhash);
+    let simulation = banks_client.simulate_transaction(transaction).await.expect("模拟交易失败");
+    assert!(matches!(simulation.result, Some(Ok(()))), "{:?}", simulation.result);
+    let return_data = simulation.simulation_details.and_then(|details| details.return_data).expect("没有 return data");
+    assert_eq!(MeritReport::from_bytes(&return_data.data), Ok(MeritReport { total: 42, values: vec![7, 35] }));
+
+    // 预置的账户和正常创建的一样可以继续增加功德
+    let (global_pubkey, _bump) = derive_global_gongde_pda_address(&program_id).unwrap();
+    let increment = instruction::increment(&program_id, &alice_gongde, &alice.pubkey(), &global_pubkey);
+    let transfer = system_instruction::transfer(&payer.pubkey(), &alice.pubkey(), 1_000_000_000);
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer, increment],
+        Some(&payer.pubkey()),
+        &[&payer, &alice],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.expect("增加功德失败");
+
+    let account = banks_client.get_account(alice_gongde).await.unwrap().expect("功德账户不存在");
+    assert_eq!(account.owner, program_id);
+    assert_eq!(read_gongde_value(&account.data), Ok(8));
+}
+
+// ========================================
+// 通过CPI使用功德合约 - 另一个程序用自己的PDA作为功德账户主人
+// 🔗 功德账户地址由主人派生（create_with_seed），主人是PDA时三步都要用 invoke_signed 带上PDA的种子：
+// 1. 系统程序 create_account_with_seed：PDA是派生的基础地址，必须签名；付款人在外层交易中签名
+// 2. 功德合约 Initialize：PDA作为账户主人签名
+// 3. 功德合约 Increment：PDA既是主人也支付手续费，所以PDA要有余额且不带数据（由系统程序所有）
+// 带版本的指令在账户列表最后附上全局配置，CPI时同样要把它传进来
+// ========================================
+
+/// 包装程序中保存功德的PDA的种子
+const VAULT_SEED: &[u8] = b"vault";
+
+/// 包装程序：创建、初始化功德账户并增加一次功德
+///
+/// 账户顺序：付款人（签名）、PDA、功德账户、全局功德PDA、全局配置、系统程序、功德合约
+fn wrapper_process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let gongde_account = next_account_info(accounts_iter)?;
+    let global_account = next_account_info(accounts_iter)?;
+    let _config_account = next_account_info(accounts_iter)?;
+    let _system_program = next_account_info(accounts_iter)?;
+    let gongde_program = next_account_info(accounts_iter)?;
+
+    let (expected_vault, bump) = Pubkey::find_program_address(&[VAULT_SEED], program_id);
+    if vault.key != &expected_vault {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let vault_seeds: &[&[u8]] = &[VAULT_SEED, &[bump]];
+
+    // 功德账户以PDA为基础地址派生，付款人出租金
+    let rent = Rent::get()?.minimum_balance(GONGDE_ACCOUNT_SIZE);
+    let create = system_instruction::create_account_with_seed(
+        payer.key,
+        gongde_account.key,
+        vault.key,
+        GONGDE_ACCOUNT_SEED,
+        rent,
+        GONGDE_ACCOUNT_SIZE as u64,
+        gongde_program.key,
+    );
+    invoke_signed(&create, accounts, &[vault_seeds])?;
+
+    let initialize = instruction::initialize(gongde_program.key, gongde_account.key, vault.key, false);
+    invoke_signed(&initialize, accounts, &[vault_seeds])?;
+
+    let increment = instruction::increment(gongde_program.key, gongde_account.key, vault.key, global_account.key);
+    invoke_signed(&increment, accounts, &[vault_seeds])?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrapper_program_initializes_via_cpi() {
+    let program_id = Pubkey::new_unique();
+    let wrapper_id = Pubkey::new_unique();
+    let mut pt = ProgramTest::default();
+    add_to_program_test(&mut pt, program_id);
+    pt.add_program("gongde_wrapper", wrapper_id, processor!(wrapper_process_instruction));
+
+    let (banks_client, payer, recent_blockhash) = pt.start().await;
+
+    let (vault, _bump) = Pubkey::find_program_address(&[VAULT_SEED], &wrapper_id);
+    let gongde_pubkey = derive_gongde_account_address(&vault, &program_id).unwrap();
+    let (global_pubkey, _bump) = derive_global_gongde_pda_address(&program_id).unwrap();
+    let (config_pubkey, _bump) = derive_config_address(&program_id);
+
+    // PDA支付增加功德的手续费，先转入余额
+    let fund = system_instruction::transfer(&payer.pubkey(), &vault, 1_000_000_000);
+    let wrapper = Instruction::new_with_bytes(
+        wrapper_id,
+        &[],
+        vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(gongde_pubkey, false),
+            AccountMeta::new(global_pubkey, false),
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(program_id, false),
+        ],
+    );
+    let transaction =
+        Transaction::new_signed_with_payer(&[fund, wrapper], Some(&payer.pubkey()), &[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.expect("通过CPI创建并增加功德失败");
+
+    let account = banks_client.get_account(gongde_pubkey).await.unwrap().expect("功德账户不存在");
+    assert_eq!(account.owner, program_id);
+    assert!(!is_public_account(&account.data));
+    assert_eq!(read_gongde_value(&account.data), Ok(1));
+}
@@ -0,0 +1,57 @@
+// ========================================
+// ProtocolVersion 指令测试 - 客户端据此判断合约是否支持要发送的指令
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{ProtocolInfo, MAX_INSTRUCTION_TAG, PROTOCOL_VERSION};
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    signature::Signer,
+    transaction::TransactionError,
+};
+
+#[test]
+fn test_protocol_version_returns_version_bytes() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+
+    let instruction = ctx.protocol_version_instruction();
+    let meta = ctx.send(&[instruction], &user, &[]).expect("版本查询不应失败");
+
+    assert_eq!(meta.return_data.program_id, ctx.program_id);
+    assert_eq!(meta.return_data.data, vec![PROTOCOL_VERSION, 0, MAX_INSTRUCTION_TAG]);
+    assert_eq!(ProtocolInfo::from_bytes(&meta.return_data.data), Ok(ProtocolInfo::CURRENT));
+}
+
+#[test]
+fn test_unknown_instruction_fails_cleanly() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+
+    // 模拟新版本客户端发送了本合约不认识的指令码
+    let mut instruction = ctx.increment_instruction(&gongde_pubkey, &user.pubkey());
+    instruction.data = vec![MAX_INSTRUCTION_TAG + 1];
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+    assert!(err.meta.logs.iter().any(|log| log.contains("ProtocolVersion")));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+}
+
+#[test]
+fn test_unknown_instruction_without_accounts_fails_cleanly() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+
+    let instruction = Instruction::new_with_bytes(ctx.program_id, &[u8::MAX], vec![]);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
@@ -0,0 +1,79 @@
+// ========================================
+// Report 指令测试 - 只读汇总多个功德账户，通过 return data 返回合计和每个账户的功德
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    instruction,
+    utils::{MeritReport, MAX_REPORT_ACCOUNTS},
+};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    pubkey::Pubkey,
+    signature::Signer,
+};
+
+use common::instruction_error;
+
+#[test]
+fn test_report_returns_total_and_values() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let carol = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account(&alice);
+    ctx.increment_times(&alice, 3);
+    // 预置的账户和交易创建的账户汇总起来没有区别
+    let bob_gongde = ctx.seed_gongde_account(&bob.pubkey(), 5);
+    let carol_gongde = ctx.seed_gongde_account(&carol.pubkey(), 0);
+
+    let viewer = ctx.new_user();
+    let instruction = ctx.report_instruction(&[alice_gongde, bob_gongde, carol_gongde]);
+    let meta = ctx.send(&[instruction], &viewer, &[]).expect("汇总功德失败");
+
+    assert_eq!(meta.return_data.program_id, ctx.program_id);
+    let report = MeritReport::from_bytes(&meta.return_data.data).expect("return data 格式错误");
+    assert_eq!(report, MeritReport { total: 8, values: vec![3, 5, 0] });
+    assert!(common::logged(&meta.logs, "{} 个功德账户合计功德: {}", &[3, 8]), "{:?}", meta.logs);
+
+    // 只读取，不修改任何账户
+    assert_eq!(ctx.read_value(&alice_gongde), Some(3));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(5));
+    assert_eq!(ctx.read_value(&carol_gongde), Some(0));
+}
+
+#[test]
+fn test_report_rejects_bad_account_lists() {
+    let mut ctx = common::setup();
+    let viewer = ctx.new_user();
+    let gongde_accounts: Vec<_> = (0..=MAX_REPORT_ACCOUNTS)
+        .map(|value| ctx.seed_gongde_account(&Pubkey::new_unique(), value as u32))
+        .collect();
+
+    // 超过上限，SDK 不会构建超过上限的指令，手动追加一个账户
+    let mut instruction = ctx.report_instruction(&gongde_accounts[..MAX_REPORT_ACCOUNTS]);
+    instruction::append_account(&mut instruction, AccountMeta::new_readonly(gongde_accounts[MAX_REPORT_ACCOUNTS], false));
+    let err = ctx.send(&[instruction], &viewer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidArgument));
+
+    // 正好上限可以汇总
+    let instruction = ctx.report_instruction(&gongde_accounts[..MAX_REPORT_ACCOUNTS]);
+    ctx.send(&[instruction], &viewer, &[]).expect("汇总功德失败");
+
+    // 没有账户，只剩末尾的全局配置
+    let mut instruction = ctx.report_instruction(&gongde_accounts[..1]);
+    instruction.accounts.remove(0);
+    let err = ctx.send(&[instruction], &viewer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::NotEnoughAccountKeys));
+
+    // 同一个账户传两次
+    let instruction = ctx.report_instruction(&[gongde_accounts[0], gongde_accounts[1], gongde_accounts[0]]);
+    let err = ctx.send(&[instruction], &viewer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidArgument));
+
+    // 不是功德账户
+    let instruction = ctx.report_instruction(&[gongde_accounts[0], viewer.pubkey()]);
+    let err = ctx.send(&[instruction], &viewer, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::IncorrectProgramId));
+}
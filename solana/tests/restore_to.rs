@@ -0,0 +1,114 @@
+// ========================================
+// RestoreTo 指令测试 - 回滚功德值并发出审计事件
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{parse_restore_return_data, RestoreEvent},
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::Signer,
+    transaction::TransactionError,
+};
+
+/// 测试中使用的当前时间
+const NOW: i64 = 1_700_000_000;
+
+#[test]
+fn test_restore_emits_event_with_checkpoint() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.increment_times(&user, 5);
+
+    let checkpoint = NOW - 3600;
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 2, checkpoint);
+    let meta = ctx.send(&[instruction], &user, &[]).expect("回滚失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+
+    let events: Vec<RestoreEvent> = common::program_data(&meta.logs)
+        .iter()
+        .filter_map(|fields| RestoreEvent::from_log_data(fields.as_slice()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![RestoreEvent { gongde: gongde_pubkey, previous: 5, restored: 2, checkpoint }]
+    );
+
+    // 回滚后仍可以继续增加功德
+    ctx.increment(&user).expect("回滚后增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+}
+
+#[test]
+fn test_restore_returns_value_after_reset() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.increment_times(&user, 4);
+
+    // 回滚到0，return data 是写入后的功德值
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 0, NOW);
+    let meta = ctx.send(&[instruction], &user, &[]).expect("回滚失败");
+    assert_eq!(meta.return_data.program_id, ctx.program_id);
+    assert_eq!(parse_restore_return_data(&meta.return_data.data), Ok(0));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+
+    // 恢复值等于当前功德时功德不变，返回的仍是当前值
+    ctx.increment_times(&user, 2);
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 2, NOW);
+    let meta = ctx.send(&[instruction], &user, &[]).expect("回滚失败");
+    assert_eq!(parse_restore_return_data(&meta.return_data.data), Ok(2));
+}
+
+#[test]
+fn test_restore_cannot_raise_value() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    ctx.increment_times(&user, 2);
+
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 3, NOW);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(
+        err.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(GongDeError::InvalidAmount as u32))
+    );
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+}
+
+#[test]
+fn test_restore_rejects_future_checkpoint() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let user = ctx.new_user();
+    ctx.create_gongde_account(&user);
+    ctx.increment(&user).unwrap();
+
+    let instruction = ctx.restore_to_instruction(&user.pubkey(), 0, NOW + 1);
+    let err = ctx.send(&[instruction], &user, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}
+
+#[test]
+fn test_restore_requires_owner() {
+    let mut ctx = common::setup();
+    ctx.set_unix_timestamp(NOW);
+    let owner = ctx.new_user();
+    let visitor = ctx.new_user();
+    let gongde_pubkey = ctx.create_gongde_account(&owner);
+    ctx.increment(&owner).unwrap();
+
+    // 访客签名，但指向主人的功德账户
+    let mut instruction = ctx.restore_to_instruction(&visitor.pubkey(), 0, NOW);
+    instruction.accounts[0].pubkey = gongde_pubkey;
+    let err = ctx.send(&[instruction], &visitor, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+}
@@ -0,0 +1,131 @@
+// ========================================
+// 场景矩阵测试 - 正常流程和攻击场景都写在这里，每个场景在所有合约实现上执行
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{MAX_INCREMENT, MIN_INCREMENT};
+use common::scenario::{
+    fails, ok, run_scenario, Actor::{Owner, Visitor}, ErrorCategory, Scenario, Step,
+};
+
+/// 全部场景，新增场景加到这里即可
+fn scenarios() -> Vec<Scenario> {
+    vec![
+        // 公开/私有功德箱
+        Scenario {
+            name: "公开功德箱任何人都可以增加",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::Increment { by: Visitor, times: 1 }, Some(1)),
+                ok(Step::Increment { by: Owner, times: 1 }, Some(2)),
+            ],
+        },
+        Scenario {
+            name: "私有功德箱只有主人可以增加",
+            steps: vec![
+                ok(Step::Initialize { public: false }, Some(0)),
+                fails(Step::Increment { by: Visitor, times: 1 }, ErrorCategory::Unauthorized, Some(0)),
+                ok(Step::Increment { by: Owner, times: 1 }, Some(1)),
+            ],
+        },
+        Scenario {
+            name: "不能重复初始化",
+            steps: vec![
+                ok(Step::Initialize { public: false }, Some(0)),
+                fails(Step::Initialize { public: true }, ErrorCategory::AlreadyInitialized, Some(0)),
+                fails(Step::Increment { by: Visitor, times: 1 }, ErrorCategory::Unauthorized, Some(0)),
+            ],
+        },
+        // 一次增加多点功德
+        Scenario {
+            name: "IncrementBy边界值",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::IncrementBy { by: Owner, amount: MIN_INCREMENT }, Some(1)),
+                ok(Step::IncrementBy { by: Visitor, amount: MAX_INCREMENT }, Some(1 + MAX_INCREMENT as u32)),
+                fails(Step::IncrementBy { by: Owner, amount: MAX_INCREMENT + 1 }, ErrorCategory::InvalidAmount, Some(1 + MAX_INCREMENT as u32)),
+                fails(Step::IncrementBy { by: Owner, amount: u64::MAX }, ErrorCategory::InvalidAmount, Some(1 + MAX_INCREMENT as u32)),
+            ],
+        },
+        Scenario {
+            name: "IncrementBy不接受0",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                fails(Step::IncrementBy { by: Owner, amount: 0 }, ErrorCategory::InvalidAmount, Some(0)),
+            ],
+        },
+        Scenario {
+            name: "私有功德箱IncrementBy只有主人可以",
+            steps: vec![
+                ok(Step::Initialize { public: false }, Some(0)),
+                fails(Step::IncrementBy { by: Visitor, amount: 5 }, ErrorCategory::Unauthorized, Some(0)),
+                ok(Step::IncrementBy { by: Owner, amount: 5 }, Some(5)),
+            ],
+        },
+        // 关闭账户
+        Scenario {
+            name: "增加后关闭",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::Increment { by: Owner, times: 3 }, Some(3)),
+                ok(Step::Close { by: Owner }, None),
+            ],
+        },
+        Scenario {
+            name: "关闭后在同一地址重新创建，功德从0开始",
+            steps: vec![
+                ok(Step::Initialize { public: false }, Some(0)),
+                ok(Step::Increment { by: Owner, times: 2 }, Some(2)),
+                ok(Step::Close { by: Owner }, None),
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::Increment { by: Visitor, times: 1 }, Some(1)),
+            ],
+        },
+        Scenario {
+            name: "他人不能关闭主人的功德账户",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::Increment { by: Owner, times: 2 }, Some(2)),
+                fails(Step::Close { by: Visitor }, ErrorCategory::Unauthorized, Some(2)),
+                ok(Step::Close { by: Owner }, None),
+            ],
+        },
+        Scenario {
+            name: "空账户可以用CloseIfEmpty关闭",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::CloseIfEmpty { by: Owner }, None),
+            ],
+        },
+        Scenario {
+            name: "有功德的账户不能用CloseIfEmpty关闭",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::Increment { by: Owner, times: 1 }, Some(1)),
+                fails(Step::CloseIfEmpty { by: Owner }, ErrorCategory::NotEmpty, Some(1)),
+            ],
+        },
+        Scenario {
+            name: "CloseIfEmpty必须由主人签名",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                fails(Step::CloseIfEmpty { by: Visitor }, ErrorCategory::Unauthorized, Some(0)),
+            ],
+        },
+        Scenario {
+            name: "同一交易内关闭后复活再增加被拒绝",
+            steps: vec![
+                ok(Step::Initialize { public: true }, Some(0)),
+                ok(Step::Increment { by: Owner, times: 1 }, Some(1)),
+                fails(Step::ReviveAndIncrement, ErrorCategory::Uninitialized, Some(1)),
+            ],
+        },
+    ]
+}
+
+#[test]
+fn test_scenario_matrix() {
+    let diffs: Vec<String> = scenarios().iter().flat_map(run_scenario).collect();
+    assert!(diffs.is_empty(), "场景矩阵存在差异:\n{}", diffs.join("\n"));
+}
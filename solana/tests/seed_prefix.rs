@@ -0,0 +1,73 @@
+// ========================================
+// 种子前缀测试 - 合约只认编译时配置的前缀派生出的功德账户和全局功德PDA
+// 用别的前缀派生的地址属于另一个部署，初始化和增加功德都会被拒绝
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::{
+    derive_global_gongde_pda_address_with_prefix,
+    derive_gongde_account_address_with_prefix,
+    GONGDE_ACCOUNT_SIZE,
+    SEED_PREFIX,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    signature::Signer,
+    transaction::TransactionError,
+};
+use solana_system_interface::instruction as system_instruction;
+
+use common::instruction_error;
+
+/// 另一个部署使用的前缀
+const OTHER_PREFIX: &str = "devnet-community";
+
+#[test]
+fn test_configured_prefix_is_used_by_program() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+
+    // 按配置的前缀派生的地址可以正常创建和增加功德
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    assert_eq!(gongde_pubkey, derive_gongde_account_address_with_prefix(&user.pubkey(), &ctx.program_id, SEED_PREFIX).unwrap());
+    ctx.increment(&user).expect("增加功德失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    let (global_pubkey, _bump) = derive_global_gongde_pda_address_with_prefix(&ctx.program_id, SEED_PREFIX).unwrap();
+    assert_eq!(global_pubkey, ctx.global_address());
+    assert_eq!(ctx.read_value(&global_pubkey), Some(1));
+}
+
+#[test]
+fn test_other_prefix_is_rejected() {
+    let mut ctx = common::setup();
+    let user = ctx.new_user();
+
+    // 用别的前缀创建的账户地址不同，合约拒绝初始化
+    let other_gongde = derive_gongde_account_address_with_prefix(&user.pubkey(), &ctx.program_id, OTHER_PREFIX).unwrap();
+    assert_ne!(other_gongde, ctx.gongde_address(&user.pubkey()));
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+    let create = system_instruction::create_account_with_seed(
+        &user.pubkey(),
+        &other_gongde,
+        &user.pubkey(),
+        OTHER_PREFIX,
+        rent,
+        GONGDE_ACCOUNT_SIZE as u64,
+        &ctx.program_id,
+    );
+    let mut initialize = ctx.initialize_instruction(&user.pubkey(), true);
+    initialize.accounts[0] = AccountMeta::new(other_gongde, false);
+    let err = ctx.send(&[create, initialize], &user, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(1, InstructionError::InvalidSeeds));
+
+    // 别的前缀派生的全局功德PDA也不被接受
+    let gongde_pubkey = ctx.create_gongde_account(&user);
+    let (other_global, _bump) = derive_global_gongde_pda_address_with_prefix(&ctx.program_id, OTHER_PREFIX).unwrap();
+    assert_ne!(other_global, ctx.global_address());
+    let mut increment = ctx.increment_instruction(&gongde_pubkey, &user.pubkey());
+    increment.accounts[2] = AccountMeta::new(other_global, false);
+    let err = ctx.send(&[increment], &user, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidAccountData));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+}
@@ -0,0 +1,86 @@
+// ========================================
+// 共享账本测试 - 多个用户的功德存放在同一个PDA中
+// ========================================
+
+mod common;
+
+use gong_de_increase::utils::shared_ledger_size;
+use solana_sdk::{instruction::InstructionError, signature::Signer, transaction::TransactionError};
+
+#[test]
+fn test_two_users_in_one_shared_ledger() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let ledger = ctx.shared_ledger_address();
+    assert!(ctx.svm.get_account(&ledger).is_none());
+
+    ctx.shared_increment(&alice, 3).expect("第一次使用时应创建共享账本");
+    ctx.shared_increment(&bob, 1).expect("新用户应加入共享账本");
+    ctx.shared_increment(&alice, 2).expect("已有用户应继续增加");
+
+    assert_eq!(ctx.read_shared_value(&alice.pubkey()), Some(5));
+    assert_eq!(ctx.read_shared_value(&bob.pubkey()), Some(1));
+
+    // 两个用户共用一个账户，每个用户只占一个条目，租金刚好免租
+    let account = ctx.svm.get_account(&ledger).unwrap();
+    assert_eq!(account.owner, ctx.program_id);
+    assert_eq!(account.data.len(), shared_ledger_size(2));
+    assert_eq!(account.lamports, ctx.svm.minimum_balance_for_rent_exemption(shared_ledger_size(2)));
+
+    // 不需要独立的功德账户，手续费和全局功德与普通增加相同
+    assert!(ctx.svm.get_account(&ctx.gongde_address(&alice.pubkey())).is_none());
+    assert_eq!(ctx.read_value(&ctx.global_address()), Some(6));
+}
+
+#[test]
+fn test_shared_increment_requires_user_signature() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let mallory = ctx.new_user();
+    ctx.shared_increment(&alice, 1).unwrap();
+
+    // 别人不能替 alice 增加功德
+    let mut instruction = ctx.shared_increment_instruction(&alice.pubkey(), 1);
+    instruction.accounts[1].is_signer = false;
+    let err = ctx.send(&[instruction], &mallory, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature));
+    assert_eq!(ctx.read_shared_value(&alice.pubkey()), Some(1));
+}
+
+#[test]
+fn test_prefunded_ledger_cannot_block_creation() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let ledger = ctx.shared_ledger_address();
+
+    // 有人抢先往共享账本地址转账，第一次使用时仍然可以创建账本
+    ctx.prefund(&ledger);
+    ctx.shared_increment(&alice, 2).expect("账本地址被抢先转账后创建失败");
+
+    assert_eq!(ctx.read_shared_value(&alice.pubkey()), Some(2));
+    let account = ctx.svm.get_account(&ledger).unwrap();
+    assert_eq!(account.owner, ctx.program_id);
+    assert_eq!(account.data.len(), shared_ledger_size(1));
+}
+
+#[test]
+fn test_ledger_is_not_a_gongde_account() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let mallory = ctx.new_user();
+    let ledger = ctx.shared_ledger_address();
+    ctx.shared_increment(&alice, 1).unwrap();
+    let before = ctx.svm.get_account(&ledger).unwrap().data;
+
+    // 账本归本合约所有，但不能当作功德账户增加功德或迁移
+    let invalid = TransactionError::InstructionError(0, InstructionError::InvalidAccountData);
+    let err = ctx.increment_for(&ledger, &mallory).unwrap_err();
+    assert_eq!(err.err, invalid);
+    let instruction = ctx.migrate_instruction(&ledger, &mallory.pubkey(), &mallory.pubkey());
+    let err = ctx.send(&[instruction], &mallory, &[]).unwrap_err();
+    assert_eq!(err.err, invalid);
+
+    assert_eq!(ctx.svm.get_account(&ledger).unwrap().data, before);
+    assert_eq!(ctx.read_shared_value(&alice.pubkey()), Some(1));
+}
@@ -0,0 +1,229 @@
+// ========================================
+// 质押功德测试 - Stake / ClaimAccrued / Unstake
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{
+        FLAG_INITIALIZED,
+        FLAG_PUBLIC,
+        GONGDE_ACCOUNT_SIZE,
+        GONGDE_FLAGS_OFFSET,
+        GONGDE_STAKED_OFFSET,
+        STAKE_LAMPORT_SLOTS_PER_MERIT,
+    },
+};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use common::custom_error;
+
+/// 测试中开始质押的slot
+const START_SLOT: u64 = 1_000;
+
+/// 测试使用的质押数量
+const STAKE: u64 = LAMPORTS_PER_SOL / 2;
+
+/// 质押 STAKE 积累1点功德需要的slot数
+const SLOTS_PER_MERIT: u64 = STAKE_LAMPORT_SLOTS_PER_MERIT / STAKE;
+
+/// 创建功德账户并在 START_SLOT 质押 STAKE
+fn setup_staking(ctx: &mut common::TestContext) -> Keypair {
+    ctx.set_slot(START_SLOT);
+    let user = ctx.new_user();
+    ctx.create_gongde_account(&user);
+    ctx.stake(&user, STAKE).expect("质押失败");
+    user
+}
+
+#[test]
+fn test_stake_moves_sol_into_gongde_account() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    let stake = ctx.read_stake(&gongde_pubkey).unwrap();
+    assert_eq!(stake.staked, STAKE);
+    assert_eq!(stake.last_claim_slot, START_SLOT);
+    assert_eq!(stake.remainder, 0);
+    // 质押和租金分开记账
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+    assert_eq!(ctx.svm.get_balance(&gongde_pubkey), Some(rent + STAKE));
+}
+
+#[test]
+fn test_claim_accrued_matches_rate_and_keeps_remainder() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 2.5个周期：积累2点，半个周期的零头留在账户中
+    ctx.set_slot(START_SLOT + 2 * SLOTS_PER_MERIT + SLOTS_PER_MERIT / 2);
+    ctx.claim_accrued(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+    assert_eq!(ctx.read_stake(&gongde_pubkey).unwrap().remainder, STAKE_LAMPORT_SLOTS_PER_MERIT / 2);
+
+    // 再过半个周期，零头凑满1点
+    ctx.set_slot(START_SLOT + 3 * SLOTS_PER_MERIT);
+    ctx.claim_accrued(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(3));
+    assert_eq!(ctx.read_stake(&gongde_pubkey).unwrap().remainder, 0);
+}
+
+#[test]
+fn test_frequent_claims_do_not_lose_dust() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 每次只过十分之一个周期，单次都不足1点，零头累加后不会丢失
+    for step in 1..=10 {
+        ctx.set_slot(START_SLOT + step * SLOTS_PER_MERIT / 10);
+        ctx.claim_accrued(&user).expect("结算失败");
+    }
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    assert_eq!(ctx.read_stake(&gongde_pubkey).unwrap().remainder, 0);
+}
+
+#[test]
+fn test_stake_more_settles_previous_amount_first() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 追加质押前按原来的数量结算，追加的部分不会按之前的时长计算
+    ctx.set_slot(START_SLOT + SLOTS_PER_MERIT);
+    ctx.stake(&user, STAKE / 2).expect("追加质押失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+
+    // 1.5倍的质押过2个周期积累3点
+    ctx.set_slot(START_SLOT + 3 * SLOTS_PER_MERIT);
+    ctx.claim_accrued(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(4));
+}
+
+#[test]
+fn test_claim_after_unstake() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let balance = ctx.svm.get_balance(&user.pubkey()).unwrap();
+
+    // 取回时先结算之前积累的功德
+    ctx.set_slot(START_SLOT + SLOTS_PER_MERIT + SLOTS_PER_MERIT / 2);
+    ctx.unstake(&user, STAKE).expect("取回失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    let stake = ctx.read_stake(&gongde_pubkey).unwrap();
+    assert_eq!(stake.staked, 0);
+    assert_eq!(stake.remainder, STAKE_LAMPORT_SLOTS_PER_MERIT / 2);
+    // 交易手续费由用户支付，取回的数量原样到账
+    assert!(ctx.svm.get_balance(&user.pubkey()).unwrap() > balance + STAKE - 10_000);
+
+    // 全部取回后不再积累，零头保留
+    ctx.set_slot(START_SLOT + 10 * SLOTS_PER_MERIT);
+    ctx.claim_accrued(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(1));
+    assert_eq!(ctx.read_stake(&gongde_pubkey).unwrap().remainder, STAKE_LAMPORT_SLOTS_PER_MERIT / 2);
+
+    // 重新质押后零头继续累加
+    ctx.stake(&user, STAKE).expect("重新质押失败");
+    ctx.set_slot(START_SLOT + 10 * SLOTS_PER_MERIT + SLOTS_PER_MERIT / 2);
+    ctx.claim_accrued(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(2));
+}
+
+#[test]
+fn test_unstake_keeps_account_rent_exempt() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 不能取回超过质押的数量，租金不算在质押里
+    let err = ctx.unstake(&user, STAKE + 1).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InsufficientStake));
+
+    ctx.unstake(&user, STAKE / 4).expect("部分取回失败");
+    ctx.unstake(&user, STAKE - STAKE / 4).expect("取回剩余质押失败");
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(GONGDE_ACCOUNT_SIZE);
+    assert_eq!(ctx.svm.get_balance(&gongde_pubkey), Some(rent));
+    assert_eq!(ctx.read_stake(&gongde_pubkey).unwrap().staked, 0);
+
+    let err = ctx.unstake(&user, 1).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InsufficientStake));
+}
+
+#[test]
+fn test_zero_amount_is_rejected() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+
+    let err = ctx.stake(&user, 0).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidAmount));
+    let err = ctx.unstake(&user, 0).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InvalidAmount));
+}
+
+#[test]
+fn test_stake_requires_authority() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let stranger = ctx.new_user();
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+
+    // 别人不能取走质押
+    let mut instruction = ctx.unstake_instruction(&user.pubkey(), STAKE);
+    instruction.accounts[1] = AccountMeta::new(stranger.pubkey(), true);
+    let err = ctx.send(&[instruction], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+
+    // 也不能替账户主人结算
+    let mut instruction = ctx.claim_accrued_instruction(&user.pubkey());
+    instruction.accounts[1] = AccountMeta::new_readonly(stranger.pubkey(), true);
+    ctx.set_slot(START_SLOT + SLOTS_PER_MERIT);
+    let err = ctx.send(&[instruction], &stranger, &[]).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(0));
+    assert_eq!(ctx.read_stake(&gongde_pubkey).unwrap().staked, STAKE);
+}
+
+#[test]
+fn test_close_returns_staked_sol() {
+    let mut ctx = common::setup();
+    let user = setup_staking(&mut ctx);
+    let gongde_pubkey = ctx.gongde_address(&user.pubkey());
+    let balance = ctx.svm.get_balance(&user.pubkey()).unwrap();
+    let account_balance = ctx.svm.get_balance(&gongde_pubkey).unwrap();
+
+    ctx.close(&user).expect("关闭失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), None);
+    // 质押的 SOL 随租金一起退还
+    assert!(ctx.svm.get_balance(&user.pubkey()).unwrap() > balance + account_balance - 10_000);
+}
+
+#[test]
+fn test_legacy_account_must_migrate_before_staking() {
+    let mut ctx = common::setup();
+    ctx.set_slot(START_SLOT);
+    let user = ctx.new_user();
+    // 布局v5：没有质押字段
+    let mut data = vec![0u8; GONGDE_STAKED_OFFSET];
+    data[..4].copy_from_slice(&3u32.to_le_bytes());
+    data[GONGDE_FLAGS_OFFSET] = FLAG_INITIALIZED | FLAG_PUBLIC;
+    let gongde_pubkey = ctx.create_legacy_account_with_data(&user.pubkey(), data);
+
+    let err = ctx.stake(&user, STAKE).unwrap_err();
+    assert_eq!(err.err, TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall));
+
+    let instruction = ctx.migrate_instruction(&gongde_pubkey, &user.pubkey(), &user.pubkey());
+    ctx.send(&[instruction], &user, &[]).expect("迁移失败");
+    ctx.stake(&user, STAKE).expect("迁移后质押失败");
+    ctx.set_slot(START_SLOT + SLOTS_PER_MERIT);
+    ctx.claim_accrued(&user).expect("结算失败");
+    assert_eq!(ctx.read_value(&gongde_pubkey), Some(4));
+}
@@ -0,0 +1,74 @@
+// ========================================
+// SwapCounts 指令测试 - 双方签名后交换功德
+// ========================================
+
+mod common;
+
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    signature::Signer,
+};
+
+use common::instruction_error;
+
+#[test]
+fn test_swap_exchanges_values() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account(&alice);
+    let bob_gongde = ctx.create_gongde_account_with(&bob, false);
+    ctx.increment_times(&alice, 5);
+    ctx.increment_times(&bob, 2);
+
+    ctx.swap_counts(&alice, &bob).expect("交换功德失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(2));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(5));
+    // 累计功德是自己挣的，交换后不变
+    assert_eq!(ctx.read_lifetime_total(&alice_gongde), Some(5));
+    assert_eq!(ctx.read_lifetime_total(&bob_gongde), Some(2));
+
+    // 再换一次就换回来了
+    ctx.swap_counts(&bob, &alice).expect("交换功德失败");
+    assert_eq!(ctx.read_value(&alice_gongde), Some(5));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(2));
+}
+
+#[test]
+fn test_swap_requires_both_signatures() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account(&alice);
+    let bob_gongde = ctx.create_gongde_account(&bob);
+    ctx.increment_times(&alice, 1);
+    ctx.increment_times(&bob, 9);
+
+    // 只有 alice 签名，bob 没有同意
+    let mut instruction = ctx.swap_counts_instruction(&alice.pubkey(), &bob.pubkey());
+    instruction.accounts[3] = AccountMeta::new_readonly(bob.pubkey(), false);
+    let err = ctx.send(&[instruction], &alice, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::MissingRequiredSignature));
+
+    // alice 自己签两个位置也不行，第二个账户必须由它的主人签名
+    let mut instruction = ctx.swap_counts_instruction(&alice.pubkey(), &bob.pubkey());
+    instruction.accounts[3] = AccountMeta::new_readonly(alice.pubkey(), true);
+    let err = ctx.send(&[instruction], &alice, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::IllegalOwner));
+
+    assert_eq!(ctx.read_value(&alice_gongde), Some(1));
+    assert_eq!(ctx.read_value(&bob_gongde), Some(9));
+}
+
+#[test]
+fn test_swap_rejects_same_account() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let alice_gongde = ctx.create_gongde_account(&alice);
+    ctx.increment_times(&alice, 3);
+
+    let instruction = ctx.swap_counts_instruction(&alice.pubkey(), &alice.pubkey());
+    let err = ctx.send(&[instruction], &alice, &[]).unwrap_err();
+    assert_eq!(err.err, instruction_error(InstructionError::InvalidArgument));
+    assert_eq!(ctx.read_value(&alice_gongde), Some(3));
+}
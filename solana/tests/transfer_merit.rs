@@ -0,0 +1,142 @@
+// ========================================
+// TransferMerit 指令测试 - 转赠功德和每日转出额度
+// ========================================
+
+mod common;
+
+use gong_de_increase::{
+    error::GongDeError,
+    utils::{CreateLotteryArgs, DAILY_TRANSFER_CAP, SECONDS_PER_DAY, TRANSFER_STATE_SIZE},
+};
+use solana_sdk::{instruction::InstructionError, signature::Signer, transaction::TransactionError};
+
+use common::custom_error;
+
+/// 测试使用的日期：第100天
+const DAY: i64 = 100;
+
+#[test]
+fn test_transfer_moves_merit_and_creates_state() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account_with(&recipient, false);
+    ctx.increment_times(&sender, 3);
+
+    let state_pubkey = ctx.transfer_state_address(&sender.pubkey());
+    assert!(ctx.svm.get_account(&state_pubkey).is_none());
+
+    // 私有功德箱也可以接收转赠
+    ctx.transfer_merit(&sender, &recipient_gongde, 2).expect("转赠应成功");
+
+    assert_eq!(ctx.read_value(&sender_gongde), Some(1));
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(2));
+    assert_eq!(ctx.svm.get_account(&state_pubkey).unwrap().owner, ctx.program_id);
+}
+
+#[test]
+fn test_prefunded_state_cannot_block_transfer() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    ctx.increment_times(&sender, 1);
+
+    // 有人抢先往转赠状态PDA转账，第一次转赠仍然可以创建状态账户
+    let state_pubkey = ctx.transfer_state_address(&sender.pubkey());
+    ctx.prefund(&state_pubkey);
+    ctx.transfer_merit(&sender, &recipient_gongde, 1).expect("状态地址被抢先转账后转赠失败");
+
+    assert_eq!(ctx.read_value(&sender_gongde), Some(0));
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(1));
+    let state = ctx.svm.get_account(&state_pubkey).unwrap();
+    assert_eq!(state.owner, ctx.program_id);
+    assert_eq!(state.data.len(), TRANSFER_STATE_SIZE);
+    assert_eq!(state.lamports, ctx.svm.minimum_balance_for_rent_exemption(TRANSFER_STATE_SIZE));
+}
+
+#[test]
+fn test_daily_quota_resets_on_rollover() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    ctx.increment_times(&sender, DAILY_TRANSFER_CAP + 2);
+
+    // 当天最后一分钟用完额度
+    ctx.set_unix_timestamp((DAY + 1) * SECONDS_PER_DAY - 60);
+    ctx.transfer_merit(&sender, &recipient_gongde, DAILY_TRANSFER_CAP - 1).unwrap();
+    ctx.transfer_merit(&sender, &recipient_gongde, 1).unwrap();
+    let err = ctx.transfer_merit(&sender, &recipient_gongde, 1).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::DailyCapReached));
+
+    // 过了零点额度清零
+    ctx.set_unix_timestamp((DAY + 1) * SECONDS_PER_DAY);
+    ctx.transfer_merit(&sender, &recipient_gongde, 2).expect("换日后应可以继续转赠");
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(DAILY_TRANSFER_CAP + 2));
+}
+
+#[test]
+fn test_receiving_is_not_limited() {
+    let mut ctx = common::setup();
+    let first = ctx.new_user();
+    let second = ctx.new_user();
+    let recipient = ctx.new_user();
+    ctx.create_gongde_account(&first);
+    ctx.create_gongde_account(&second);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    ctx.increment_times(&first, DAILY_TRANSFER_CAP);
+    ctx.increment_times(&second, 1);
+    ctx.set_unix_timestamp(DAY * SECONDS_PER_DAY);
+
+    ctx.transfer_merit(&first, &recipient_gongde, DAILY_TRANSFER_CAP).unwrap();
+    ctx.transfer_merit(&second, &recipient_gongde, 1).expect("接收者当天收到的功德不受额度限制");
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(DAILY_TRANSFER_CAP + 1));
+}
+
+#[test]
+fn test_transfer_more_than_balance_rejected() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let recipient = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    let recipient_gongde = ctx.create_gongde_account(&recipient);
+    ctx.increment_times(&sender, 1);
+
+    let err = ctx.transfer_merit(&sender, &recipient_gongde, 2).unwrap_err();
+    assert_eq!(err.err, custom_error(GongDeError::InsufficientMerit));
+    assert_eq!(ctx.read_value(&sender_gongde), Some(1));
+    assert_eq!(ctx.read_value(&recipient_gongde), Some(0));
+}
+
+#[test]
+fn test_transfer_into_non_gongde_accounts_rejected() {
+    let mut ctx = common::setup();
+    let sender = ctx.new_user();
+    let sender_gongde = ctx.create_gongde_account(&sender);
+    ctx.increment_times(&sender, 3);
+
+    // 彩票轮次、共享账本和全局功德PDA都归本合约所有，但不是功德账户
+    let admin = ctx.new_user();
+    let args = CreateLotteryArgs { round_id: 1, start_slot: 0, end_slot: 100, ticket_price: 1 };
+    ctx.create_lottery(&admin, &args).unwrap();
+    let lottery = ctx.lottery_address(&admin.pubkey(), args.round_id);
+    ctx.shared_increment(&admin, 1).unwrap();
+    let ledger = ctx.shared_ledger_address();
+    let global = ctx.global_address();
+
+    for (target, expected) in [
+        (lottery, InstructionError::InvalidAccountData),
+        (ledger, InstructionError::InvalidAccountData),
+        (global, InstructionError::InvalidArgument),
+    ] {
+        let before = ctx.svm.get_account(&target).unwrap().data;
+        let err = ctx.transfer_merit(&sender, &target, 1).unwrap_err();
+        assert_eq!(err.err, TransactionError::InstructionError(0, expected));
+        assert_eq!(ctx.svm.get_account(&target).unwrap().data, before);
+    }
+    assert_eq!(ctx.read_value(&sender_gongde), Some(3));
+}
@@ -0,0 +1,87 @@
+// ========================================
+// 用户地址清单测试 - 合约每条会创建账户的路径，创建的地址都必须出现在 enumerate_user_pdas 的清单中
+// 新增创建账户的指令时在这里补一步，清单漏掉的地址会让这个测试失败
+// ========================================
+
+mod common;
+
+use common::TestContext;
+use gong_de_increase::utils::{enumerate_user_pdas, CreateCampaignArgs, CreateLotteryArgs, EnumerateOptions};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+/// 发送一条指令，返回这笔交易新创建、归本合约所有的账户
+fn created_by(ctx: &mut TestContext, instruction: Instruction, payer: &Keypair) -> Vec<Pubkey> {
+    let candidates: Vec<(Pubkey, bool)> = instruction
+        .accounts
+        .iter()
+        .map(|meta| (meta.pubkey, ctx.svm.get_account(&meta.pubkey).is_some_and(|account| account.lamports > 0)))
+        .collect();
+    ctx.send(&[instruction], payer, &[]).expect("发送交易失败");
+    candidates
+        .into_iter()
+        .filter(|(pubkey, existed)| {
+            !existed && ctx.svm.get_account(pubkey).is_some_and(|account| account.owner == ctx.program_id)
+        })
+        .map(|(pubkey, _)| pubkey)
+        .collect()
+}
+
+#[test]
+fn test_every_created_account_is_enumerated() {
+    let mut ctx = common::setup();
+    let alice = ctx.new_user();
+    let bob = ctx.new_user();
+    let bob_gongde = ctx.create_gongde_account(&bob);
+    let mut created = Vec::new();
+
+    // 第一次增加功德时顺便创建功德账户；bob 只创建了账户还没有增加过功德，全局功德PDA也在这一步创建
+    let instruction = ctx.increment_creating_instruction(&alice.pubkey());
+    created.extend(created_by(&mut ctx, instruction, &alice));
+    ctx.increment_times(&alice, 4);
+
+    let instruction = ctx.transfer_merit_instruction(&alice.pubkey(), &bob_gongde, 1);
+    created.extend(created_by(&mut ctx, instruction, &alice));
+
+    let args = CreateCampaignArgs { campaign_id: 3, start: 1_000_000, end: 1_003_600, multiplier: 1, budget: 1 };
+    let instruction = ctx.create_campaign_instruction(&alice.pubkey(), &args);
+    created.extend(created_by(&mut ctx, instruction, &alice));
+
+    let instruction = ctx.endorse_instruction(&alice.pubkey(), &bob_gongde);
+    created.extend(created_by(&mut ctx, instruction, &alice));
+
+    let instruction = ctx.accrue_instruction(&alice.pubkey());
+    created.extend(created_by(&mut ctx, instruction, &alice));
+
+    let args = CreateLotteryArgs { round_id: 5, start_slot: 100, end_slot: 200, ticket_price: 2 };
+    let instruction = ctx.create_lottery_instruction(&alice.pubkey(), &args);
+    created.extend(created_by(&mut ctx, instruction, &alice));
+
+    let instruction = ctx.shared_increment_instruction(&alice.pubkey(), 1);
+    created.extend(created_by(&mut ctx, instruction, &alice));
+
+    let options = EnumerateOptions {
+        campaign_ids: vec![3],
+        lottery_rounds: vec![5],
+        endorsees: vec![bob_gongde],
+        include_shared: true,
+    };
+    let enumerated = enumerate_user_pdas(&alice.pubkey(), &ctx.program_id, &options);
+    for pubkey in &created {
+        assert!(
+            enumerated.iter().any(|pda| pda.address == *pubkey),
+            "创建的账户 {} 不在地址清单中: {:?}",
+            pubkey,
+            enumerated
+        );
+    }
+    // 上面走过了每一条创建路径，清单中的地址也都真的被创建了
+    assert_eq!(created.len(), enumerated.len(), "{:?}", enumerated);
+    for pda in &enumerated {
+        let account = ctx.svm.get_account(&pda.address).unwrap_or_else(|| panic!("{} 没有被创建", pda.name));
+        assert_eq!(account.owner, ctx.program_id, "{}", pda.name);
+    }
+}